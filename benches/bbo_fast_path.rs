@@ -0,0 +1,104 @@
+//! Top-of-book fast path benchmarks
+//!
+//! Replays an L1 quote and trade stream through the per-tick book stage of
+//! the backtest engine, once reconstructing the full book and once tracking
+//! only the best bid and ask, and reports ticks per second for each. Both
+//! produce the book state handed to the strategy on every tick.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rust_decimal::Decimal;
+use strategy_lab::data::*;
+use strategy_lab::market::{BboTracker, OrderBook};
+
+/// Ticks replayed per iteration
+const TICKS: usize = 100_000;
+
+/// Bid, ask and trade events around a touch that walks one tick at a time
+fn generate_l1_stream(count: usize) -> Vec<TickData> {
+    let base = 1_700_000_000_000_000_000i64;
+    let tick_size = Decimal::new(25, 2);
+    let mut ticks = Vec::with_capacity(count);
+
+    for i in 0..count {
+        let event = (i / 3) as i64;
+        // The touch drifts up for 200 events, then back down
+        let cycle = event % 400;
+        let bid = Decimal::from(18_000) + tick_size * Decimal::from(if cycle < 200 { cycle } else { 400 - cycle });
+        let (mdt, price) = match i % 3 {
+            0 => (MarketDataType::BidQuote, bid),
+            1 => (MarketDataType::AskQuote, bid + tick_size),
+            _ => (MarketDataType::Trade, if event % 2 == 0 { bid } else { bid + tick_size }),
+        };
+        ticks.push(TickData::new(
+            DataLevel::L1,
+            mdt,
+            base + i as i64 * 10_000,
+            price,
+            1 + (i % 40) as i32,
+            "0624".to_string(),
+        ));
+    }
+
+    ticks
+}
+
+/// Ticks per second through the book stage, full depth against top of book
+fn benchmark_book_stage_throughput(c: &mut Criterion) {
+    let ticks = generate_l1_stream(TICKS);
+    let mut group = c.benchmark_group("l1_book_stage_throughput");
+    group.throughput(Throughput::Elements(ticks.len() as u64));
+
+    group.bench_with_input(BenchmarkId::new("full_book", ticks.len()), &ticks, |b, ticks| {
+        b.iter(|| {
+            // The engine validates its books
+            let mut book = OrderBook::new("0624".to_string(), true);
+            for tick in ticks {
+                book.process_tick(black_box(tick));
+                black_box(book.get_state().clone());
+            }
+        });
+    });
+
+    group.bench_with_input(BenchmarkId::new("top_of_book", ticks.len()), &ticks, |b, ticks| {
+        b.iter(|| {
+            let mut tracker = BboTracker::new();
+            for tick in ticks {
+                black_box(tracker.process_tick(black_box(tick)).to_state(&tick.contract_month));
+            }
+        });
+    });
+
+    group.finish();
+}
+
+/// Ticks per second when the strategy only needs the quote, not a book state
+fn benchmark_quote_only_throughput(c: &mut Criterion) {
+    let ticks = generate_l1_stream(TICKS);
+    let mut group = c.benchmark_group("l1_quote_throughput");
+    group.throughput(Throughput::Elements(ticks.len() as u64));
+
+    group.bench_with_input(BenchmarkId::new("full_book", ticks.len()), &ticks, |b, ticks| {
+        b.iter(|| {
+            let mut book = OrderBook::new("0624".to_string(), true);
+            for tick in ticks {
+                book.process_tick(black_box(tick));
+                black_box(book.get_state().mid_price());
+            }
+        });
+    });
+
+    group.bench_with_input(BenchmarkId::new("top_of_book", ticks.len()), &ticks, |b, ticks| {
+        b.iter(|| {
+            let mut tracker = BboTracker::new();
+            for tick in ticks {
+                black_box(tracker.process_tick(black_box(tick)).mid_price());
+            }
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_book_stage_throughput, benchmark_quote_only_throughput);
+
+criterion_main!(benches);
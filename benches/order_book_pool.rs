@@ -0,0 +1,159 @@
+//! Order book level pool benchmarks
+//!
+//! Replays depth churn (levels added and removed around a moving touch) into
+//! order books with and without the price level pool, comparing throughput
+//! and the p99 latency of single updates.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use hdrhistogram::Histogram;
+use rust_decimal::Decimal;
+use std::time::{Duration, Instant};
+use strategy_lab::data::*;
+use strategy_lab::market::{OrderBook, DEFAULT_LEVEL_POOL_CAPACITY};
+
+/// Depth updates replayed per iteration
+const UPDATES: usize = 100_000;
+
+/// Levels kept on each side of the touch
+const BOOK_DEPTH: i64 = 10;
+
+/// Add/remove pairs that shift a 10-level book up and down one tick at a time
+fn generate_depth_churn(count: usize) -> Vec<TickData> {
+    let base = 1_700_000_000_000_000_000i64;
+    let tick_size = Decimal::new(25, 2);
+    let mut ticks = Vec::with_capacity(count);
+
+    for i in 0..count {
+        // The touch drifts up for 500 updates, then back down
+        let cycle = (i / 2) as i64 % 1000;
+        let touch = if cycle < 500 { cycle } else { 1000 - cycle };
+        let (mdt, entering, leaving) = if i % 4 < 2 {
+            (MarketDataType::BidQuote, touch, touch - BOOK_DEPTH)
+        } else {
+            (MarketDataType::AskQuote, touch + 1, touch + 1 + BOOK_DEPTH)
+        };
+        let (operation, offset) = if i % 2 == 0 {
+            (OrderBookOperation::Add, entering)
+        } else {
+            (OrderBookOperation::Remove, leaving)
+        };
+
+        let price = Decimal::from(18_000) + tick_size * Decimal::from(offset);
+        let tick = TickData::new(
+            DataLevel::L2,
+            mdt,
+            base + i as i64 * 10_000, // 100K updates per second
+            price,
+            1 + (i % 20) as i32,
+            "0624".to_string(),
+        );
+        ticks.push(tick.with_l2_data(operation, 1));
+    }
+
+    ticks
+}
+
+fn book(pool_capacity: usize) -> OrderBook {
+    OrderBook::new("0624".to_string(), false).with_level_pool(pool_capacity)
+}
+
+/// Throughput of depth updates with and without the level pool
+fn benchmark_depth_update_throughput(c: &mut Criterion) {
+    let ticks = generate_depth_churn(UPDATES);
+    let mut group = c.benchmark_group("depth_update_throughput");
+    group.throughput(Throughput::Elements(ticks.len() as u64));
+
+    for (name, capacity) in [("unpooled", 0), ("pooled", DEFAULT_LEVEL_POOL_CAPACITY)] {
+        group.bench_with_input(BenchmarkId::new(name, capacity), &ticks, |b, ticks| {
+            b.iter(|| {
+                let mut book = book(capacity);
+                for tick in ticks {
+                    book.process_tick(black_box(tick));
+                }
+                black_box(book.level_pool_stats())
+            });
+        });
+    }
+
+    group.finish();
+}
+
+/// p99 of single depth updates; criterion times the whole replay, and the
+/// per-update percentiles are printed for each variant
+fn benchmark_depth_update_p99(c: &mut Criterion) {
+    let ticks = generate_depth_churn(UPDATES);
+    let mut group = c.benchmark_group("depth_update_p99");
+
+    for (name, capacity) in [("unpooled", 0), ("pooled", DEFAULT_LEVEL_POOL_CAPACITY)] {
+        let mut latencies = Histogram::<u64>::new_with_bounds(1, 10_000_000, 3).unwrap();
+        group.bench_function(name, |b| {
+            b.iter_custom(|iterations| {
+                let mut total = Duration::ZERO;
+                for _ in 0..iterations {
+                    let mut book = book(capacity);
+                    for tick in &ticks {
+                        let start = Instant::now();
+                        book.process_tick(black_box(tick));
+                        let elapsed = start.elapsed();
+                        total += elapsed;
+                        let _ = latencies.record(elapsed.as_nanos().max(1) as u64);
+                    }
+                }
+                total
+            });
+        });
+
+        println!(
+            "{}: p50 {}ns, p99 {}ns, p99.9 {}ns over {} updates",
+            name,
+            latencies.value_at_quantile(0.50),
+            latencies.value_at_quantile(0.99),
+            latencies.value_at_quantile(0.999),
+            latencies.len()
+        );
+    }
+
+    group.finish();
+}
+
+/// Share of level allocations the pool avoids on the churn replay
+fn benchmark_allocator_pressure(c: &mut Criterion) {
+    let ticks = generate_depth_churn(UPDATES);
+    let mut group = c.benchmark_group("level_allocations");
+
+    for (name, capacity) in [("unpooled", 0), ("pooled", DEFAULT_LEVEL_POOL_CAPACITY)] {
+        let mut replay = book(capacity);
+        for tick in &ticks {
+            replay.process_tick(tick);
+        }
+        let stats = replay.level_pool_stats();
+        println!(
+            "{}: {} level allocations, {} reused ({:.1}%)",
+            name,
+            stats.allocated,
+            stats.reused,
+            stats.reuse_ratio() * 100.0
+        );
+
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut book = book(capacity);
+                for tick in ticks.iter().take(10_000) {
+                    book.process_tick(black_box(tick));
+                }
+                black_box(book.level_pool_stats().allocated)
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    benchmark_depth_update_throughput,
+    benchmark_depth_update_p99,
+    benchmark_allocator_pressure
+);
+
+criterion_main!(benches);
@@ -0,0 +1,23 @@
+-- Named strategy parameter presets
+--
+-- Strategy ids are the API's string ids, so there is no foreign key to
+-- strategies. Names are unique per strategy within a workspace.
+
+CREATE TABLE IF NOT EXISTS parameter_presets (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    workspace_id VARCHAR(255) NOT NULL DEFAULT 'default',
+    strategy_id VARCHAR(255) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    parameters JSONB NOT NULL DEFAULT '{}',
+    favorite BOOLEAN NOT NULL DEFAULT false,
+    source_optimization_id VARCHAR(255),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(workspace_id, strategy_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_parameter_presets_strategy
+    ON parameter_presets(workspace_id, strategy_id);
+
+CREATE TRIGGER update_parameter_presets_updated_at BEFORE UPDATE ON parameter_presets
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
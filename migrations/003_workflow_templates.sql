@@ -0,0 +1,20 @@
+-- User-defined workflow templates
+--
+-- The full definition (steps, dependencies, inputs, help content) is kept
+-- as JSON; id and name are copied out for listing. Built-in workflows are
+-- defined in code and never stored here.
+
+CREATE TABLE IF NOT EXISTS workflow_templates (
+    workspace_id VARCHAR(255) NOT NULL DEFAULT 'default',
+    id VARCHAR(255) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    definition JSONB NOT NULL,
+    created_by VARCHAR(255),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (workspace_id, id)
+);
+
+CREATE TRIGGER update_workflow_templates_updated_at BEFORE UPDATE ON workflow_templates
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,23 @@
+-- Saved analysis views
+--
+-- A view is a researcher's dashboard: selected metrics, chart settings and
+-- the results being compared, kept as JSON. Views are private to their
+-- owner unless shared, in which case anyone in the workspace can open them.
+
+CREATE TABLE IF NOT EXISTS analysis_views (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    workspace_id VARCHAR(255) NOT NULL DEFAULT 'default',
+    owner VARCHAR(255) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    shared BOOLEAN NOT NULL DEFAULT false,
+    version INTEGER NOT NULL DEFAULT 1,
+    definition JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_analysis_views_owner
+    ON analysis_views(workspace_id, owner);
+
+CREATE TRIGGER update_analysis_views_updated_at BEFORE UPDATE ON analysis_views
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,43 @@
+-- Help articles
+--
+-- Contextual help shown by the guided workflows, editable without a
+-- release. The full article (examples, interactive elements) is kept as
+-- JSON; title, body and tags are copied out and indexed for full-text
+-- search, and the workflow step types an article covers are kept as an
+-- array so step lookups can use an index too.
+
+CREATE TABLE IF NOT EXISTS help_articles (
+    id VARCHAR(255) PRIMARY KEY,
+    title VARCHAR(255) NOT NULL,
+    body TEXT NOT NULL,
+    tags TEXT[] NOT NULL DEFAULT '{}',
+    step_types TEXT[] NOT NULL DEFAULT '{}',
+    difficulty_level VARCHAR(32),
+    content JSONB NOT NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    search_vector TSVECTOR NOT NULL DEFAULT ''::tsvector,
+    updated_by VARCHAR(255),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_help_articles_search ON help_articles USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS idx_help_articles_step_types ON help_articles USING GIN (step_types);
+
+-- Titles rank above tags, tags above the body
+CREATE OR REPLACE FUNCTION update_help_articles_search_vector()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.search_vector :=
+        setweight(to_tsvector('english', coalesce(NEW.title, '')), 'A') ||
+        setweight(to_tsvector('english', array_to_string(NEW.tags, ' ')), 'B') ||
+        setweight(to_tsvector('english', coalesce(NEW.body, '')), 'C');
+    RETURN NEW;
+END;
+$$ language 'plpgsql';
+
+CREATE TRIGGER update_help_articles_search_vector BEFORE INSERT OR UPDATE ON help_articles
+    FOR EACH ROW EXECUTE FUNCTION update_help_articles_search_vector();
+
+CREATE TRIGGER update_help_articles_updated_at BEFORE UPDATE ON help_articles
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
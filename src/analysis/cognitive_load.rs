@@ -1,6 +1,6 @@
 //! Cognitive load management for strategy analysis results
 
-use crate::backtesting::BacktestResult;
+use crate::backtesting::{BacktestResult, MetricError, MetricInput, MetricRegistry};
 use crate::optimization::OptimizationResult;
 use crate::statistics::StatisticalTest;
 use serde::{Deserialize, Serialize};
@@ -62,6 +62,7 @@ impl ConfidenceLevel {
 /// Multi-criteria ranking system for optimization results
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RankingCriteria {
+    /// Weight of each metric's score, by `MetricRegistry` name
     pub weights: HashMap<String, f64>,
     pub thresholds: HashMap<String, f64>,
     pub penalties: HashMap<String, f64>,
@@ -90,6 +91,16 @@ impl Default for RankingCriteria {
     }
 }
 
+impl RankingCriteria {
+    /// Check that every weighted metric is registered
+    pub fn validate(&self, metrics: &MetricRegistry) -> Result<(), MetricError> {
+        match self.weights.keys().find(|name| !metrics.contains(name)) {
+            Some(name) => Err(MetricError::UnknownMetric(name.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
 /// Comprehensive result ranking with confidence indicators
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResultRanking {
@@ -218,6 +229,7 @@ impl RecommendationLevel {
 pub struct CognitiveLoadManager {
     ranking_criteria: RankingCriteria,
     explanation_templates: HashMap<String, ContextualExplanation>,
+    metrics: MetricRegistry,
 }
 
 impl CognitiveLoadManager {
@@ -225,6 +237,7 @@ impl CognitiveLoadManager {
         Self {
             ranking_criteria: RankingCriteria::default(),
             explanation_templates: Self::create_explanation_templates(),
+            metrics: MetricRegistry::new(),
         }
     }
     
@@ -232,9 +245,16 @@ impl CognitiveLoadManager {
         Self {
             ranking_criteria: criteria,
             explanation_templates: Self::create_explanation_templates(),
+            metrics: MetricRegistry::new(),
         }
     }
     
+    /// Score ranking criteria with the metrics of `metrics`, including custom ones
+    pub fn with_metric_registry(mut self, metrics: MetricRegistry) -> Self {
+        self.metrics = metrics;
+        self
+    }
+    
     /// Rank multiple optimization results with cognitive load management
     pub fn rank_results(&self, results: &[OptimizationResult]) -> Vec<(usize, ResultRanking)> {
         let mut ranked_results = Vec::new();
@@ -260,18 +280,16 @@ impl CognitiveLoadManager {
         let mut warnings = Vec::new();
         let mut recommendations = Vec::new();
         
-        // Calculate individual metric scores (0-100 scale)
-        let sharpe_score = self.score_sharpe_ratio(result.best_result.performance.sharpe_ratio);
-        let drawdown_score = self.score_max_drawdown(result.best_result.performance.max_drawdown);
-        let win_rate_score = self.score_win_rate(result.best_result.performance.win_rate);
-        let profit_factor_score = self.score_profit_factor(result.best_result.performance.profit_factor);
-        let trade_freq_score = self.score_trade_frequency(result.best_result.total_trades);
-        
-        individual_scores.insert("sharpe_ratio".to_string(), sharpe_score);
-        individual_scores.insert("max_drawdown".to_string(), drawdown_score);
-        individual_scores.insert("win_rate".to_string(), win_rate_score);
-        individual_scores.insert("profit_factor".to_string(), profit_factor_score);
-        individual_scores.insert("trade_frequency".to_string(), trade_freq_score);
+        // Score each weighted metric on its 0-100 scale
+        let input = MetricInput::new(&result.backtest_result).with_equity_curve(&result.equity_curve);
+        for metric in self.ranking_criteria.weights.keys() {
+            match self.metrics.score(metric, &input) {
+                Ok(score) => {
+                    individual_scores.insert(metric.clone(), score);
+                }
+                Err(e) => warnings.push(format!("{} left out of the ranking", e)),
+            }
+        }
         
         // Calculate composite score using weights
         let mut composite_score = 0.0;
@@ -313,60 +331,6 @@ impl CognitiveLoadManager {
         }
     }
     
-    fn score_sharpe_ratio(&self, sharpe: f64) -> f64 {
-        match sharpe {
-            s if s >= 2.0 => 100.0,
-            s if s >= 1.5 => 85.0 + (s - 1.5) * 30.0,
-            s if s >= 1.0 => 70.0 + (s - 1.0) * 30.0,
-            s if s >= 0.5 => 50.0 + (s - 0.5) * 40.0,
-            s if s >= 0.0 => s * 100.0,
-            _ => 0.0,
-        }
-    }
-    
-    fn score_max_drawdown(&self, drawdown: f64) -> f64 {
-        let dd_pct = drawdown.abs();
-        match dd_pct {
-            d if d <= 5.0 => 100.0,
-            d if d <= 10.0 => 90.0 - (d - 5.0) * 4.0,
-            d if d <= 15.0 => 70.0 - (d - 10.0) * 6.0,
-            d if d <= 25.0 => 40.0 - (d - 15.0) * 2.0,
-            _ => 0.0,
-        }
-    }
-    
-    fn score_win_rate(&self, win_rate: f64) -> f64 {
-        let wr_pct = win_rate * 100.0;
-        match wr_pct {
-            w if w >= 70.0 => 100.0,
-            w if w >= 60.0 => 85.0 + (w - 60.0) * 1.5,
-            w if w >= 50.0 => 70.0 + (w - 50.0) * 1.5,
-            w if w >= 40.0 => 50.0 + (w - 40.0) * 2.0,
-            _ => win_rate * 100.0,
-        }
-    }
-    
-    fn score_profit_factor(&self, pf: f64) -> f64 {
-        match pf {
-            p if p >= 2.0 => 100.0,
-            p if p >= 1.5 => 80.0 + (p - 1.5) * 40.0,
-            p if p >= 1.2 => 60.0 + (p - 1.2) * 66.7,
-            p if p >= 1.0 => 30.0 + (p - 1.0) * 150.0,
-            _ => 0.0,
-        }
-    }
-    
-    fn score_trade_frequency(&self, trades: u32) -> f64 {
-        match trades {
-            t if t >= 200 => 100.0,
-            t if t >= 100 => 80.0 + (t - 100) as f64 * 0.2,
-            t if t >= 50 => 60.0 + (t - 50) as f64 * 0.4,
-            t if t >= 20 => 30.0 + (t - 20) as f64 * 1.0,
-            t if t >= 10 => t as f64 * 2.0,
-            _ => 0.0,
-        }
-    }
-    
     fn calculate_penalties(&self, result: &OptimizationResult) -> HashMap<String, f64> {
         let mut penalties = HashMap::new();
         
@@ -658,10 +622,10 @@ mod tests {
     
     #[test]
     fn test_sharpe_ratio_scoring() {
-        let manager = CognitiveLoadManager::new();
-        assert_eq!(manager.score_sharpe_ratio(2.0), 100.0);
-        assert!(manager.score_sharpe_ratio(1.5) > 80.0);
-        assert!(manager.score_sharpe_ratio(0.5) < 60.0);
-        assert_eq!(manager.score_sharpe_ratio(-0.5), 0.0);
+        let sharpe = MetricRegistry::new().get("sharpe_ratio").unwrap();
+        assert_eq!(sharpe.score(2.0), 100.0);
+        assert!(sharpe.score(1.5) > 80.0);
+        assert!(sharpe.score(0.5) < 60.0);
+        assert_eq!(sharpe.score(-0.5), 0.0);
     }
 }
\ No newline at end of file
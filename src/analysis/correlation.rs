@@ -0,0 +1,302 @@
+//! Correlation and diversification between strategies
+//!
+//! Aligns the periodic returns of several backtests on the periods they
+//! share, then computes correlation and covariance matrices, rolling
+//! correlation of every pair, and how much an equal-weight portfolio of the
+//! strategies reduces volatility compared to holding them one at a time.
+
+use chrono::{Datelike, Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Errors raised by correlation analysis
+#[derive(Debug, thiserror::Error)]
+pub enum CorrelationError {
+    #[error("At least two return series are needed, got {0}")]
+    TooFewSeries(usize),
+    #[error("Only {found} shared periods, at least {required} needed")]
+    InsufficientOverlap { found: usize, required: usize },
+}
+
+/// Period returns are compounded into before comparing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReturnPeriod {
+    #[default]
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl ReturnPeriod {
+    /// First day of the period `date` falls in
+    fn start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            ReturnPeriod::Daily => date,
+            ReturnPeriod::Weekly => date
+                .checked_sub_days(Days::new(date.weekday().num_days_from_monday() as u64))
+                .unwrap_or(date),
+            ReturnPeriod::Monthly => date.with_day(1).unwrap_or(date),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrelationConfig {
+    #[serde(default)]
+    pub period: ReturnPeriod,
+    /// Periods per rolling correlation window
+    #[serde(default = "default_rolling_window")]
+    pub rolling_window: usize,
+    /// Fewest shared periods the analysis runs on
+    #[serde(default = "default_min_overlap")]
+    pub min_overlap: usize,
+}
+
+fn default_rolling_window() -> usize {
+    20
+}
+
+fn default_min_overlap() -> usize {
+    10
+}
+
+impl Default for CorrelationConfig {
+    fn default() -> Self {
+        Self {
+            period: ReturnPeriod::default(),
+            rolling_window: default_rolling_window(),
+            min_overlap: default_min_overlap(),
+        }
+    }
+}
+
+/// Daily returns of one backtest, as fractions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReturnSeries {
+    pub id: String,
+    pub returns: BTreeMap<NaiveDate, f64>,
+}
+
+impl ReturnSeries {
+    pub fn new(id: impl Into<String>, returns: BTreeMap<NaiveDate, f64>) -> Self {
+        Self { id: id.into(), returns }
+    }
+
+    /// Returns compounded per period, keyed by the period's first day
+    fn resample(&self, period: ReturnPeriod) -> BTreeMap<NaiveDate, f64> {
+        let mut growth: BTreeMap<NaiveDate, f64> = BTreeMap::new();
+        for (date, ret) in &self.returns {
+            *growth.entry(period.start(*date)).or_insert(1.0) *= 1.0 + ret;
+        }
+        growth.into_iter().map(|(date, g)| (date, g - 1.0)).collect()
+    }
+}
+
+/// Correlation of one pair over a trailing window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollingPoint {
+    /// Last period of the window
+    pub date: NaiveDate,
+    pub correlation: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollingCorrelation {
+    pub first: String,
+    pub second: String,
+    pub points: Vec<RollingPoint>,
+}
+
+/// Volatility reduction from holding the strategies together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiversificationEstimate {
+    /// Mean of the individual strategies' volatilities, per period
+    pub average_volatility: f64,
+    /// Volatility of an equal-weight portfolio, per period
+    pub portfolio_volatility: f64,
+    /// Average over portfolio volatility; 1 means no benefit
+    pub diversification_ratio: f64,
+    /// Share of volatility removed by combining, from 0 to 1
+    pub volatility_reduction: f64,
+    /// Mean pairwise correlation
+    pub average_correlation: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrelationReport {
+    pub config: CorrelationConfig,
+    /// Series ids, in matrix row and column order
+    pub ids: Vec<String>,
+    /// Shared periods the analysis ran on
+    pub periods: usize,
+    pub first_period: NaiveDate,
+    pub last_period: NaiveDate,
+    pub correlation: Vec<Vec<f64>>,
+    pub covariance: Vec<Vec<f64>>,
+    pub rolling: Vec<RollingCorrelation>,
+    pub diversification: DiversificationEstimate,
+}
+
+impl CorrelationReport {
+    /// Align `series` on their shared periods and analyze them
+    pub fn compute(series: &[ReturnSeries], config: &CorrelationConfig) -> Result<Self, CorrelationError> {
+        if series.len() < 2 {
+            return Err(CorrelationError::TooFewSeries(series.len()));
+        }
+
+        let resampled: Vec<_> = series.iter().map(|s| s.resample(config.period)).collect();
+        let mut shared: BTreeSet<NaiveDate> = resampled[0].keys().copied().collect();
+        for returns in &resampled[1..] {
+            shared.retain(|date| returns.contains_key(date));
+        }
+        let required = config.min_overlap.max(2);
+        if shared.len() < required {
+            return Err(CorrelationError::InsufficientOverlap { found: shared.len(), required });
+        }
+
+        let dates: Vec<NaiveDate> = shared.into_iter().collect();
+        let aligned: Vec<Vec<f64>> = resampled.iter()
+            .map(|returns| dates.iter().map(|date| returns[date]).collect())
+            .collect();
+
+        let n = aligned.len();
+        let mut covariance = vec![vec![0.0; n]; n];
+        let mut correlation = vec![vec![0.0; n]; n];
+        for i in 0..n {
+            for j in i..n {
+                let cov = covariance_of(&aligned[i], &aligned[j]);
+                covariance[i][j] = cov;
+                covariance[j][i] = cov;
+                let corr = if i == j { 1.0 } else { correlation_of(&aligned[i], &aligned[j]) };
+                correlation[i][j] = corr;
+                correlation[j][i] = corr;
+            }
+        }
+
+        let mut rolling = Vec::new();
+        let window = config.rolling_window.max(2);
+        for i in 0..n {
+            for j in (i + 1)..n {
+                let points = (window..=dates.len())
+                    .map(|end| RollingPoint {
+                        date: dates[end - 1],
+                        correlation: correlation_of(&aligned[i][end - window..end], &aligned[j][end - window..end]),
+                    })
+                    .collect();
+                rolling.push(RollingCorrelation {
+                    first: series[i].id.clone(),
+                    second: series[j].id.clone(),
+                    points,
+                });
+            }
+        }
+
+        let diversification = diversification(&covariance, &correlation);
+
+        Ok(Self {
+            config: config.clone(),
+            ids: series.iter().map(|s| s.id.clone()).collect(),
+            periods: dates.len(),
+            first_period: dates[0],
+            last_period: dates[dates.len() - 1],
+            correlation,
+            covariance,
+            rolling,
+            diversification,
+        })
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Sample covariance
+fn covariance_of(a: &[f64], b: &[f64]) -> f64 {
+    if a.len() < 2 {
+        return 0.0;
+    }
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    a.iter().zip(b)
+        .map(|(x, y)| (x - mean_a) * (y - mean_b))
+        .sum::<f64>() / (a.len() - 1) as f64
+}
+
+/// Pearson correlation; zero when either side has no variance
+fn correlation_of(a: &[f64], b: &[f64]) -> f64 {
+    let denominator = (covariance_of(a, a) * covariance_of(b, b)).sqrt();
+    if denominator > 0.0 {
+        (covariance_of(a, b) / denominator).clamp(-1.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+fn diversification(covariance: &[Vec<f64>], correlation: &[Vec<f64>]) -> DiversificationEstimate {
+    let n = covariance.len();
+    let weight = 1.0 / n as f64;
+    let average_volatility = (0..n).map(|i| covariance[i][i].sqrt()).sum::<f64>() * weight;
+    let portfolio_variance: f64 = covariance.iter()
+        .flat_map(|row| row.iter())
+        .map(|cov| cov * weight * weight)
+        .sum();
+    let portfolio_volatility = portfolio_variance.max(0.0).sqrt();
+
+    let pairs = n * (n - 1) / 2;
+    let average_correlation = (0..n)
+        .flat_map(|i| ((i + 1)..n).map(move |j| (i, j)))
+        .map(|(i, j)| correlation[i][j])
+        .sum::<f64>() / pairs as f64;
+
+    let (diversification_ratio, volatility_reduction) = if portfolio_volatility > 0.0 {
+        (average_volatility / portfolio_volatility, 1.0 - portfolio_volatility / average_volatility)
+    } else {
+        (1.0, 0.0)
+    };
+
+    DiversificationEstimate {
+        average_volatility,
+        portfolio_volatility,
+        diversification_ratio,
+        volatility_reduction,
+        average_correlation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(id: &str, returns: impl Fn(usize) -> f64) -> ReturnSeries {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        ReturnSeries::new(id, (0..40)
+            .map(|i| (start + Days::new(i as u64), returns(i)))
+            .collect())
+    }
+
+    #[test]
+    fn test_offsetting_strategies_diversify() {
+        let wave = |i: usize| if i % 2 == 0 { 0.01 } else { -0.005 };
+        let a = series("a", wave);
+        let b = series("b", move |i| -wave(i));
+        let c = series("c", move |i| wave(i) * 2.0);
+
+        let config = CorrelationConfig { rolling_window: 10, ..Default::default() };
+        let report = CorrelationReport::compute(&[a, b, c], &config).unwrap();
+
+        assert_eq!(report.periods, 40);
+        assert!((report.correlation[0][1] + 1.0).abs() < 1e-9);
+        assert!((report.correlation[0][2] - 1.0).abs() < 1e-9);
+        assert_eq!(report.rolling.len(), 3);
+        assert_eq!(report.rolling[0].points.len(), 31);
+        assert!(report.diversification.volatility_reduction > 0.0);
+
+        let weekly = CorrelationConfig { period: ReturnPeriod::Weekly, ..Default::default() };
+        let short = series("d", wave);
+        assert!(matches!(
+            CorrelationReport::compute(&[short.clone(), short], &weekly),
+            Err(CorrelationError::InsufficientOverlap { .. })
+        ));
+    }
+}
@@ -0,0 +1,416 @@
+//! What-if cost model analysis
+//!
+//! Re-prices a finished backtest's fill ledger under different slippage and
+//! commission assumptions, without rerunning the strategy. Each fill's price
+//! is stripped of the slippage it was charged and re-slipped under the
+//! scenario; fills are then matched first-in first-out into round trips to
+//! recompute the headline metrics.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::backtesting::metrics::TradeRecord;
+use crate::strategy::OrderSide;
+
+/// Contract terms the ledger is valued with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostModelConfig {
+    #[serde(default = "default_tick_size")]
+    pub tick_size: Decimal,
+    /// Dollars per point per contract
+    #[serde(default = "default_point_value")]
+    pub point_value: Decimal,
+    #[serde(default = "default_initial_capital")]
+    pub initial_capital: Decimal,
+}
+
+fn default_tick_size() -> Decimal {
+    Decimal::new(25, 2)
+}
+
+fn default_point_value() -> Decimal {
+    Decimal::from(2)
+}
+
+fn default_initial_capital() -> Decimal {
+    Decimal::from(10_000)
+}
+
+impl Default for CostModelConfig {
+    fn default() -> Self {
+        Self {
+            tick_size: default_tick_size(),
+            point_value: default_point_value(),
+            initial_capital: default_initial_capital(),
+        }
+    }
+}
+
+/// Alternative cost assumptions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostScenario {
+    pub name: String,
+    /// Ticks of slippage added to every fill
+    #[serde(default)]
+    pub extra_slippage_ticks: Decimal,
+    /// Scale applied to the slippage each fill was charged
+    #[serde(default = "default_multiplier")]
+    pub slippage_multiplier: Decimal,
+    /// Scale applied to the commission each fill was charged
+    #[serde(default = "default_multiplier")]
+    pub commission_multiplier: Decimal,
+    /// Commission added per contract filled
+    #[serde(default)]
+    pub extra_commission_per_contract: Decimal,
+}
+
+fn default_multiplier() -> Decimal {
+    Decimal::ONE
+}
+
+impl CostScenario {
+    /// The costs the backtest was run with
+    pub fn baseline() -> Self {
+        Self {
+            name: "baseline".to_string(),
+            extra_slippage_ticks: Decimal::ZERO,
+            slippage_multiplier: Decimal::ONE,
+            commission_multiplier: Decimal::ONE,
+            extra_commission_per_contract: Decimal::ZERO,
+        }
+    }
+
+    pub fn extra_slippage(ticks: u32) -> Self {
+        Self {
+            name: format!("+{} tick slippage", ticks),
+            extra_slippage_ticks: Decimal::from(ticks),
+            ..Self::baseline()
+        }
+    }
+
+    pub fn commission_multiple(multiplier: Decimal) -> Self {
+        Self {
+            name: format!("{}x commissions", multiplier),
+            commission_multiplier: multiplier,
+            ..Self::baseline()
+        }
+    }
+
+    /// +1 tick slippage, doubled commissions, and both together
+    pub fn standard_set() -> Vec<Self> {
+        vec![
+            Self::extra_slippage(1),
+            Self::commission_multiple(Decimal::from(2)),
+            Self {
+                name: "+1 tick slippage, 2x commissions".to_string(),
+                extra_slippage_ticks: Decimal::ONE,
+                commission_multiplier: Decimal::from(2),
+                ..Self::baseline()
+            },
+        ]
+    }
+
+    /// The ledger with this scenario's costs applied
+    pub fn reprice(&self, trades: &[TradeRecord], config: &CostModelConfig) -> Vec<TradeRecord> {
+        trades.iter()
+            .map(|trade| {
+                let slippage = trade.slippage * self.slippage_multiplier
+                    + self.extra_slippage_ticks * config.tick_size;
+                let price = match trade.side {
+                    OrderSide::Buy => trade.price - trade.slippage + slippage,
+                    OrderSide::Sell => trade.price + trade.slippage - slippage,
+                };
+                TradeRecord {
+                    price,
+                    slippage,
+                    commission: trade.commission * self.commission_multiplier
+                        + self.extra_commission_per_contract * Decimal::from(trade.quantity),
+                    ..trade.clone()
+                }
+            })
+            .collect()
+    }
+}
+
+/// Headline metrics of a fill ledger
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LedgerMetrics {
+    /// Closed round trips, partial closes counted separately
+    pub round_trips: u32,
+    pub net_pnl: f64,
+    pub total_commission: f64,
+    /// Dollar cost of slippage across all fills
+    pub total_slippage: f64,
+    pub win_rate: f64,
+    pub profit_factor: f64,
+    pub avg_trade: f64,
+    pub total_return: f64,
+    /// Deepest peak-to-trough fall of closed-trade equity, as a negative percentage
+    pub max_drawdown: f64,
+    /// Annualized from daily closed-trade returns
+    pub sharpe_ratio: f64,
+}
+
+/// An open lot awaiting its closing fill
+struct Lot {
+    price: Decimal,
+    quantity: i32,
+    commission_per_contract: Decimal,
+}
+
+/// A closed round trip of one or more contracts
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RoundTrip {
+    pub closed_at: DateTime<Utc>,
+    pub quantity: i32,
+    /// Net of commission on both fills
+    pub pnl: Decimal,
+}
+
+/// Net P&L of each closed round trip and when it closed, matching fills
+/// first-in first-out
+pub fn round_trips(trades: &[TradeRecord], config: &CostModelConfig) -> Vec<(DateTime<Utc>, Decimal)> {
+    closed_round_trips(trades, config).into_iter()
+        .map(|trip| (trip.closed_at, trip.pnl))
+        .collect()
+}
+
+/// Closed round trips with their size, matching fills first-in first-out
+pub fn closed_round_trips(trades: &[TradeRecord], config: &CostModelConfig) -> Vec<RoundTrip> {
+    let mut lots: VecDeque<Lot> = VecDeque::new();
+    // Sign of the open position: 1 long, -1 short
+    let mut open_sign = 0i32;
+    let mut closed: Vec<RoundTrip> = Vec::new();
+
+    for trade in trades {
+        if trade.quantity <= 0 {
+            continue;
+        }
+        let sign = match trade.side {
+            OrderSide::Buy => 1,
+            OrderSide::Sell => -1,
+        };
+        let commission_per_contract = trade.commission / Decimal::from(trade.quantity);
+        let mut remaining = trade.quantity;
+
+        while remaining > 0 && open_sign == -sign {
+            let Some(lot) = lots.front_mut() else {
+                break;
+            };
+            let take = remaining.min(lot.quantity);
+            let quantity = Decimal::from(take);
+            let pnl = (trade.price - lot.price) * quantity * Decimal::from(open_sign) * config.point_value
+                - (lot.commission_per_contract + commission_per_contract) * quantity;
+            closed.push(RoundTrip { closed_at: trade.timestamp, quantity: take, pnl });
+
+            lot.quantity -= take;
+            remaining -= take;
+            if lot.quantity == 0 {
+                lots.pop_front();
+            }
+            if lots.is_empty() {
+                open_sign = 0;
+            }
+        }
+
+        if remaining > 0 {
+            open_sign = sign;
+            lots.push_back(Lot { price: trade.price, quantity: remaining, commission_per_contract });
+        }
+    }
+
+    closed
+}
+
+impl LedgerMetrics {
+    pub fn from_ledger(trades: &[TradeRecord], config: &CostModelConfig) -> Self {
+        let mut total_commission = Decimal::ZERO;
+        let mut total_slippage = Decimal::ZERO;
+        for trade in trades.iter().filter(|trade| trade.quantity > 0) {
+            total_commission += trade.commission;
+            total_slippage += trade.slippage * Decimal::from(trade.quantity) * config.point_value;
+        }
+
+        Self {
+            total_commission: total_commission.to_f64().unwrap_or(0.0),
+            total_slippage: total_slippage.to_f64().unwrap_or(0.0),
+            ..Self::from_round_trips(&round_trips(trades, config), config)
+        }
+    }
+
+    /// Metrics of already matched round trips, in closing order
+    ///
+    /// Commission and slippage totals are left at zero since the fills they
+    /// were charged on are not known.
+    pub fn from_round_trips(closed: &[(DateTime<Utc>, Decimal)], config: &CostModelConfig) -> Self {
+        let to_f64 = |value: Decimal| value.to_f64().unwrap_or(0.0);
+        let pnls: Vec<f64> = closed.iter().map(|(_, pnl)| to_f64(*pnl)).collect();
+        let net_pnl: f64 = pnls.iter().sum();
+        let gross_profit: f64 = pnls.iter().filter(|p| **p > 0.0).sum();
+        let gross_loss: f64 = pnls.iter().filter(|p| **p < 0.0).map(|p| -p).sum();
+        let capital = to_f64(config.initial_capital).max(f64::EPSILON);
+
+        let mut equity = capital;
+        let mut peak = capital;
+        let mut max_drawdown = 0.0f64;
+        for pnl in &pnls {
+            equity += pnl;
+            peak = peak.max(equity);
+            max_drawdown = max_drawdown.min((equity - peak) / peak * 100.0);
+        }
+
+        Self {
+            round_trips: pnls.len() as u32,
+            net_pnl,
+            total_commission: 0.0,
+            total_slippage: 0.0,
+            win_rate: if pnls.is_empty() {
+                0.0
+            } else {
+                pnls.iter().filter(|p| **p > 0.0).count() as f64 / pnls.len() as f64
+            },
+            profit_factor: if gross_loss > 0.0 { gross_profit / gross_loss } else { 0.0 },
+            avg_trade: if pnls.is_empty() { 0.0 } else { net_pnl / pnls.len() as f64 },
+            total_return: net_pnl / capital * 100.0,
+            max_drawdown,
+            sharpe_ratio: daily_sharpe(closed, capital),
+        }
+    }
+}
+
+fn daily_sharpe(closed: &[(DateTime<Utc>, Decimal)], capital: f64) -> f64 {
+    let mut daily: BTreeMap<NaiveDate, f64> = BTreeMap::new();
+    for (at, pnl) in closed {
+        *daily.entry(at.date_naive()).or_insert(0.0) += pnl.to_f64().unwrap_or(0.0);
+    }
+
+    let mut equity = capital;
+    let returns: Vec<f64> = daily.values()
+        .map(|pnl| {
+            let ret = pnl / equity;
+            equity += pnl;
+            ret
+        })
+        .collect();
+    if returns.len() < 2 {
+        return 0.0;
+    }
+
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+    if variance > 0.0 {
+        mean / variance.sqrt() * 252f64.sqrt()
+    } else {
+        0.0
+    }
+}
+
+/// Change of each metric from the baseline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricDeltas {
+    pub net_pnl: f64,
+    pub win_rate: f64,
+    pub profit_factor: f64,
+    pub total_return: f64,
+    pub max_drawdown: f64,
+    pub sharpe_ratio: f64,
+}
+
+impl MetricDeltas {
+    pub(crate) fn between(baseline: &LedgerMetrics, scenario: &LedgerMetrics) -> Self {
+        Self {
+            net_pnl: scenario.net_pnl - baseline.net_pnl,
+            win_rate: scenario.win_rate - baseline.win_rate,
+            profit_factor: scenario.profit_factor - baseline.profit_factor,
+            total_return: scenario.total_return - baseline.total_return,
+            max_drawdown: scenario.max_drawdown - baseline.max_drawdown,
+            sharpe_ratio: scenario.sharpe_ratio - baseline.sharpe_ratio,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioOutcome {
+    pub scenario: CostScenario,
+    pub metrics: LedgerMetrics,
+    pub deltas: MetricDeltas,
+    /// Whether the strategy still makes money under these costs
+    pub profitable: bool,
+}
+
+/// A ledger re-priced under several cost scenarios
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostSensitivityReport {
+    pub config: CostModelConfig,
+    pub fills: usize,
+    pub baseline: LedgerMetrics,
+    pub scenarios: Vec<ScenarioOutcome>,
+}
+
+impl CostSensitivityReport {
+    pub fn compute(trades: &[TradeRecord], scenarios: &[CostScenario], config: &CostModelConfig) -> Self {
+        let baseline = LedgerMetrics::from_ledger(trades, config);
+        let scenarios = scenarios.iter()
+            .map(|scenario| {
+                let metrics = LedgerMetrics::from_ledger(&scenario.reprice(trades, config), config);
+                ScenarioOutcome {
+                    scenario: scenario.clone(),
+                    deltas: MetricDeltas::between(&baseline, &metrics),
+                    profitable: metrics.net_pnl > 0.0,
+                    metrics,
+                }
+            })
+            .collect();
+
+        Self {
+            config: config.clone(),
+            fills: trades.len(),
+            baseline,
+            scenarios,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn fill(minute: u32, side: OrderSide, price: i64) -> TradeRecord {
+        TradeRecord {
+            timestamp: Utc.with_ymd_and_hms(2024, 3, 12, 14, minute, 0).unwrap(),
+            side,
+            quantity: 1,
+            price: Decimal::new(price, 2),
+            commission: Decimal::new(62, 2),
+            slippage: Decimal::new(25, 2),
+            tag: None,
+        }
+    }
+
+    #[test]
+    fn test_extra_slippage_and_commission_reduce_pnl() {
+        // Long 18000 -> 18005, then short 18010 -> 18012
+        let ledger = vec![
+            fill(0, OrderSide::Buy, 1_800_000),
+            fill(5, OrderSide::Sell, 1_800_500),
+            fill(10, OrderSide::Sell, 1_801_000),
+            fill(15, OrderSide::Buy, 1_801_200),
+        ];
+        let config = CostModelConfig::default();
+        let report = CostSensitivityReport::compute(&ledger, &CostScenario::standard_set(), &config);
+
+        assert_eq!(report.baseline.round_trips, 2);
+        // (5 - 2) points * $2 less 4 fills of $0.62
+        assert!((report.baseline.net_pnl - (6.0 - 2.48)).abs() < 1e-9);
+
+        // One more tick on each of four fills at $0.50 a tick
+        assert!((report.scenarios[0].deltas.net_pnl + 2.0).abs() < 1e-9);
+        assert!((report.scenarios[1].deltas.net_pnl + 2.48).abs() < 1e-9);
+        assert!((report.scenarios[2].deltas.net_pnl + 4.48).abs() < 1e-9);
+        assert!(!report.scenarios[2].profitable);
+    }
+}
@@ -1,5 +1,42 @@
 //! Analysis and cognitive load management module
 
 pub mod cognitive_load;
+pub mod strategy_family;
+pub mod promotion;
+pub mod correlation;
+pub mod cost_model;
+pub mod outliers;
+pub mod prop_firm;
+pub mod reconciliation;
+pub mod risk_normalization;
+pub mod risk_of_ruin;
+pub mod streaks;
 
-pub use cognitive_load::*;
\ No newline at end of file
+pub use cognitive_load::*;
+pub use correlation::{
+    CorrelationConfig, CorrelationError, CorrelationReport, DiversificationEstimate, ReturnPeriod, ReturnSeries,
+    RollingCorrelation,
+};
+pub use cost_model::{
+    CostModelConfig, CostScenario, CostSensitivityReport, LedgerMetrics, MetricDeltas, RoundTrip, ScenarioOutcome,
+    closed_round_trips,
+};
+pub use outliers::{OutlierOutcome, OutlierReport, OutlierRule};
+pub use prop_firm::{
+    EvaluationFailure, EvaluationOutcome, EvaluationRules, MonteCarloConfig, PassProbability, ProgramResult,
+    PropFirmReport, TradingDay,
+};
+pub use reconciliation::{
+    Divergence, FidelityDrift, FillComparison, ReconciliationConfig, ReconciliationReport, SessionSummary,
+    reconcile_session,
+};
+pub use risk_normalization::{
+    RiskMetrics, RiskNormalizationConfig, RiskNormalizationError, RiskNormalizedComparison, ScaledMetrics,
+    StrategyComparison,
+};
+pub use risk_of_ruin::{
+    KellySuggestion, PerContractStats, RiskOfRuinReport, RuinConfig, RuinEstimate, SizingRule,
+};
+pub use streaks::{StopRuleOutcome, StreakBucket, StreakConfig, StreakReport, TradeGroup};
+pub use promotion::{GateCheck, PromotionDecision, PromotionPolicy};
+pub use strategy_family::{FamilyRun, MetricDistribution, RobustnessVerdict, StrategyFamilyReport};
\ No newline at end of file
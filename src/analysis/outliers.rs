@@ -0,0 +1,204 @@
+//! Outlier trade sensitivity
+//!
+//! Recomputes a backtest's headline metrics without its most extreme round
+//! trips, either a fixed number of the best and worst or those beyond a
+//! multiple of the interquartile range, and reports how far each metric
+//! moves. A strategy whose profit disappears once one or two trades are
+//! dropped owes its result to luck rather than to an edge.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::cost_model::{closed_round_trips, CostModelConfig, LedgerMetrics, MetricDeltas, RoundTrip};
+use crate::backtesting::metrics::TradeRecord;
+
+/// Which round trips count as outliers
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum OutlierRule {
+    /// The `top` most profitable and `bottom` least profitable round trips
+    Extremes {
+        #[serde(default)]
+        top: usize,
+        #[serde(default)]
+        bottom: usize,
+    },
+    /// Round trips more than `k` interquartile ranges below the first or
+    /// above the third quartile of P&L
+    Iqr {
+        #[serde(default = "default_iqr_multiple")]
+        k: f64,
+    },
+}
+
+fn default_iqr_multiple() -> f64 {
+    1.5
+}
+
+impl OutlierRule {
+    /// Without the single best trade, the best and worst three, and Tukey's
+    /// 1.5 IQR fences
+    pub fn standard_set() -> Vec<Self> {
+        vec![
+            Self::Extremes { top: 1, bottom: 0 },
+            Self::Extremes { top: 3, bottom: 3 },
+            Self::Iqr { k: default_iqr_multiple() },
+        ]
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Extremes { top, bottom: 0 } => format!("without the best {}", top),
+            Self::Extremes { top: 0, bottom } => format!("without the worst {}", bottom),
+            Self::Extremes { top, bottom } => format!("without the best {} and worst {}", top, bottom),
+            Self::Iqr { k } => format!("without trades beyond {}x IQR", k),
+        }
+    }
+
+    /// Whether each P&L is an outlier under this rule
+    fn flags(&self, pnls: &[f64]) -> Vec<bool> {
+        let mut flags = vec![false; pnls.len()];
+        match *self {
+            Self::Extremes { top, bottom } => {
+                let mut order: Vec<usize> = (0..pnls.len()).collect();
+                order.sort_by(|a, b| pnls[*a].total_cmp(&pnls[*b]));
+                let bottom = bottom.min(order.len());
+                let top = top.min(order.len() - bottom);
+                for &index in order[..bottom].iter().chain(&order[order.len() - top..]) {
+                    flags[index] = true;
+                }
+            }
+            Self::Iqr { k } => {
+                if pnls.len() < 4 {
+                    return flags;
+                }
+                let mut sorted = pnls.to_vec();
+                sorted.sort_by(f64::total_cmp);
+                let (q1, q3) = (quantile(&sorted, 0.25), quantile(&sorted, 0.75));
+                let fence = k.max(0.0) * (q3 - q1);
+                for (flag, pnl) in flags.iter_mut().zip(pnls) {
+                    *flag = *pnl < q1 - fence || *pnl > q3 + fence;
+                }
+            }
+        }
+        flags
+    }
+}
+
+/// Linearly interpolated quantile of sorted values
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let (lower, upper) = (position.floor() as usize, position.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
+
+/// Metrics without the round trips one rule excludes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutlierOutcome {
+    pub rule: OutlierRule,
+    pub excluded: Vec<RoundTrip>,
+    /// Net P&L of the excluded round trips
+    pub excluded_pnl: f64,
+    pub metrics: LedgerMetrics,
+    /// Change of each metric from all trades
+    pub deltas: MetricDeltas,
+    /// Profitable with every trade but not without the outliers
+    pub depends_on_outliers: bool,
+}
+
+/// A ledger's metrics with and without its outlier trades
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutlierReport {
+    pub config: CostModelConfig,
+    pub all_trades: LedgerMetrics,
+    pub outcomes: Vec<OutlierOutcome>,
+}
+
+impl OutlierReport {
+    pub fn from_ledger(trades: &[TradeRecord], rules: &[OutlierRule], config: &CostModelConfig) -> Self {
+        let all_trades = LedgerMetrics::from_ledger(trades, config);
+        let closed = closed_round_trips(trades, config);
+        let pnls: Vec<f64> = closed.iter().map(|trip| trip.pnl.to_f64().unwrap_or(0.0)).collect();
+
+        let outcomes = rules.iter()
+            .map(|rule| {
+                let mut excluded: Vec<RoundTrip> = Vec::new();
+                let mut kept: Vec<(DateTime<Utc>, Decimal)> = Vec::new();
+                for (trip, outlier) in closed.iter().zip(rule.flags(&pnls)) {
+                    if outlier {
+                        excluded.push(*trip);
+                    } else {
+                        kept.push((trip.closed_at, trip.pnl));
+                    }
+                }
+
+                // Fills are shared between kept and excluded trips, so cost
+                // totals stay those of the whole ledger
+                let metrics = LedgerMetrics {
+                    total_commission: all_trades.total_commission,
+                    total_slippage: all_trades.total_slippage,
+                    ..LedgerMetrics::from_round_trips(&kept, config)
+                };
+                OutlierOutcome {
+                    rule: *rule,
+                    excluded_pnl: excluded.iter().map(|trip| trip.pnl.to_f64().unwrap_or(0.0)).sum(),
+                    excluded,
+                    deltas: MetricDeltas::between(&all_trades, &metrics),
+                    depends_on_outliers: all_trades.net_pnl > 0.0 && metrics.net_pnl <= 0.0,
+                    metrics,
+                }
+            })
+            .collect();
+
+        Self {
+            config: config.clone(),
+            all_trades,
+            outcomes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::OrderSide;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_one_lucky_trade_carries_the_result() {
+        // Nine round trips losing a point each and one making 50 points
+        let mut ledger = Vec::new();
+        for (i, exit) in [-1i64; 9].into_iter().chain([50]).enumerate() {
+            let at = Utc.with_ymd_and_hms(2024, 3, 12, 14, 0, 0).unwrap() + chrono::Duration::minutes(i as i64 * 2);
+            let fill = |side, price: i64, at| TradeRecord {
+                timestamp: at,
+                side,
+                quantity: 1,
+                price: Decimal::from(price),
+                commission: Decimal::ZERO,
+                slippage: Decimal::ZERO,
+                tag: None,
+            };
+            ledger.push(fill(OrderSide::Buy, 18_000, at));
+            ledger.push(fill(OrderSide::Sell, 18_000 + exit, at + chrono::Duration::minutes(1)));
+        }
+
+        let config = CostModelConfig::default();
+        let report = OutlierReport::from_ledger(&ledger, &OutlierRule::standard_set(), &config);
+        // 50 - 9 points at $2 a point
+        assert!((report.all_trades.net_pnl - 82.0).abs() < 1e-9);
+
+        let without_best = &report.outcomes[0];
+        assert_eq!(without_best.excluded.len(), 1);
+        assert!((without_best.excluded_pnl - 100.0).abs() < 1e-9);
+        assert!((without_best.deltas.net_pnl + 100.0).abs() < 1e-9);
+        assert!(without_best.depends_on_outliers);
+
+        // The identical losers have no spread, so only the winner is outside the fences
+        let iqr = &report.outcomes[2];
+        assert_eq!(iqr.excluded.len(), 1);
+        assert_eq!(iqr.metrics.round_trips, 9);
+    }
+}
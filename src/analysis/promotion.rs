@@ -0,0 +1,201 @@
+//! Promotion gates for validating strategies
+//!
+//! A strategy is only validated, and so on its way to going live, once its
+//! backtests clear a configurable set of gates: enough trades to judge it,
+//! a minimum Sharpe on out-of-sample runs, and a family of runs that is statistically robust. Each gate reports
+//! what it saw so a failed promotion says exactly what is missing.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::strategy_family::{FamilyRun, RobustnessVerdict, StrategyFamilyReport};
+
+/// Gates a strategy must pass to be marked validated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromotionPolicy {
+    /// Trades summed over all runs
+    #[serde(default = "default_min_trades")]
+    pub min_trades: u32,
+    /// Out-of-sample runs required before the Sharpe gate can pass
+    #[serde(default = "default_min_oos_runs")]
+    pub min_oos_runs: usize,
+    /// Minimum mean Sharpe of out-of-sample runs
+    #[serde(default = "default_min_oos_sharpe")]
+    pub min_oos_sharpe: f64,
+    /// Require the family of runs to be judged robust, i.e. a mean Sharpe
+    /// significantly above zero at 95% confidence
+    #[serde(default = "default_require_robust")]
+    pub require_robust_family: bool,
+    /// Deepest drawdown allowed in any run, in percent; unchecked when absent
+    #[serde(default)]
+    pub max_drawdown_pct: Option<f64>,
+}
+
+fn default_min_trades() -> u32 {
+    100
+}
+
+fn default_min_oos_runs() -> usize {
+    1
+}
+
+fn default_min_oos_sharpe() -> f64 {
+    1.0
+}
+
+fn default_require_robust() -> bool {
+    true
+}
+
+impl Default for PromotionPolicy {
+    fn default() -> Self {
+        Self {
+            min_trades: default_min_trades(),
+            min_oos_runs: default_min_oos_runs(),
+            min_oos_sharpe: default_min_oos_sharpe(),
+            require_robust_family: default_require_robust(),
+            max_drawdown_pct: None,
+        }
+    }
+}
+
+/// Outcome of one gate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GateCheck {
+    pub gate: String,
+    pub passed: bool,
+    /// Observed value against the requirement
+    pub detail: String,
+}
+
+impl GateCheck {
+    fn new(gate: &str, passed: bool, detail: String) -> Self {
+        Self {
+            gate: gate.to_string(),
+            passed,
+            detail,
+        }
+    }
+}
+
+/// Whether a strategy may be validated, gate by gate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromotionDecision {
+    pub strategy_id: String,
+    pub passed: bool,
+    pub checks: Vec<GateCheck>,
+    pub evaluated_at: DateTime<Utc>,
+}
+
+impl PromotionDecision {
+    pub fn failed_gates(&self) -> Vec<&str> {
+        self.checks.iter()
+            .filter(|check| !check.passed)
+            .map(|check| check.gate.as_str())
+            .collect()
+    }
+}
+
+impl PromotionPolicy {
+    /// Check every gate against a strategy's runs
+    ///
+    /// `runs` holds all backtests of the strategy; `out_of_sample` the
+    /// subset run on data held out from development and optimization.
+    pub fn evaluate(
+        &self,
+        strategy_id: &str,
+        runs: &[FamilyRun],
+        out_of_sample: &[FamilyRun],
+    ) -> PromotionDecision {
+        let mut checks = Vec::new();
+
+        let trades: u32 = runs.iter().map(|r| r.total_trades).sum();
+        checks.push(GateCheck::new(
+            "min_trades",
+            trades >= self.min_trades,
+            format!("{} trades across {} runs, {} required", trades, runs.len(), self.min_trades),
+        ));
+
+        let oos_sharpe = if out_of_sample.is_empty() {
+            None
+        } else {
+            Some(out_of_sample.iter().map(|r| r.sharpe_ratio).sum::<f64>() / out_of_sample.len() as f64)
+        };
+        let enough_oos = out_of_sample.len() >= self.min_oos_runs.max(1);
+        checks.push(GateCheck::new(
+            "min_oos_sharpe",
+            enough_oos && oos_sharpe.map_or(false, |sharpe| sharpe >= self.min_oos_sharpe),
+            match oos_sharpe {
+                Some(sharpe) => format!(
+                    "mean Sharpe {:.2} over {} out-of-sample runs, {:.2} over at least {} required",
+                    sharpe, out_of_sample.len(), self.min_oos_sharpe, self.min_oos_runs
+                ),
+                None => "no out-of-sample runs".to_string(),
+            },
+        ));
+
+        if self.require_robust_family {
+            let family = StrategyFamilyReport::from_runs(strategy_id, runs);
+            let detail = match &family.sharpe_confidence_interval {
+                Some(ci) => format!(
+                    "{:?}: 95% interval of mean Sharpe [{:.2}, {:.2}], {:.0}% of runs positive",
+                    family.verdict, ci.lower_bound, ci.upper_bound, family.positive_sharpe_fraction * 100.0
+                ),
+                None => format!("{:?}: {} runs", family.verdict, family.runs),
+            };
+            checks.push(GateCheck::new(
+                "robust_family",
+                family.verdict == RobustnessVerdict::Robust,
+                detail,
+            ));
+        }
+
+        if let Some(limit) = self.max_drawdown_pct {
+            // Drawdowns are stored as negative percentages
+            let worst = runs.iter().map(|r| r.max_drawdown.abs()).fold(0.0, f64::max);
+            checks.push(GateCheck::new(
+                "max_drawdown",
+                worst <= limit,
+                format!("worst drawdown {:.1}%, {:.1}% allowed", worst, limit),
+            ));
+        }
+
+        PromotionDecision {
+            strategy_id: strategy_id.to_string(),
+            passed: checks.iter().all(|check| check.passed),
+            checks,
+            evaluated_at: Utc::now(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(sharpe: f64, trades: u32, period: usize) -> FamilyRun {
+        FamilyRun {
+            run_id: format!("run-{}", period),
+            start_date: format!("2024-0{}-01", period),
+            end_date: format!("2024-0{}-28", period),
+            total_return: 8.0,
+            sharpe_ratio: sharpe,
+            max_drawdown: -6.0,
+            total_trades: trades,
+        }
+    }
+
+    #[test]
+    fn test_gates_report_what_is_missing() {
+        let runs: Vec<_> = (1..=6).map(|i| run(1.2 + i as f64 * 0.1, 40, i)).collect();
+        let policy = PromotionPolicy { max_drawdown_pct: Some(5.0), ..Default::default() };
+
+        let decision = policy.evaluate("s1", &runs, &[]);
+        assert!(!decision.passed);
+        assert_eq!(decision.failed_gates(), vec!["min_oos_sharpe", "max_drawdown"]);
+
+        let policy = PromotionPolicy::default();
+        let decision = policy.evaluate("s1", &runs, &runs[4..]);
+        assert!(decision.passed);
+    }
+}
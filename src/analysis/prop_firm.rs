@@ -0,0 +1,307 @@
+//! Prop-firm evaluation simulation
+//!
+//! Funded-account programs pass a trader who reaches a profit target within
+//! a set of rules: a trailing drawdown from the balance high-water mark, a
+//! daily loss limit and a minimum number of trading days. The closed round
+//! trips of a backtest are replayed against those rules day by day, and the
+//! trading days are bootstrapped to estimate how likely the strategy is to
+//! pass rather than whether the one historical path happened to.
+
+use chrono::NaiveDate;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::cost_model::{round_trips, CostModelConfig};
+use crate::backtesting::metrics::TradeRecord;
+
+/// Constraints of one evaluation program, in account dollars
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvaluationRules {
+    pub name: String,
+    pub account_size: f64,
+    /// Profit over the starting balance needed to pass
+    pub profit_target: f64,
+    /// Distance the balance may fall below its high-water mark
+    pub trailing_drawdown: f64,
+    /// Stop trailing once the threshold reaches the starting balance
+    #[serde(default)]
+    pub drawdown_locks_at_balance: bool,
+    /// Largest loss allowed within one trading day
+    #[serde(default)]
+    pub daily_loss_limit: Option<f64>,
+    /// Trading days required before the evaluation can pass
+    #[serde(default)]
+    pub min_trading_days: u32,
+    /// Trading days allowed before the evaluation expires
+    #[serde(default)]
+    pub max_trading_days: Option<u32>,
+}
+
+impl EvaluationRules {
+    /// A typical 50K evaluation: $3,000 target, $2,000 trailing drawdown
+    /// locking at the starting balance, $1,000 daily loss limit, 5 days
+    pub fn standard_50k() -> Self {
+        Self {
+            name: "50K evaluation".to_string(),
+            account_size: 50_000.0,
+            profit_target: 3_000.0,
+            trailing_drawdown: 2_000.0,
+            drawdown_locks_at_balance: true,
+            daily_loss_limit: Some(1_000.0),
+            min_trading_days: 5,
+            max_trading_days: None,
+        }
+    }
+
+    /// A typical 150K evaluation: $9,000 target, $4,500 trailing drawdown
+    pub fn standard_150k() -> Self {
+        Self {
+            name: "150K evaluation".to_string(),
+            account_size: 150_000.0,
+            profit_target: 9_000.0,
+            trailing_drawdown: 4_500.0,
+            drawdown_locks_at_balance: true,
+            daily_loss_limit: Some(3_300.0),
+            min_trading_days: 5,
+            max_trading_days: None,
+        }
+    }
+}
+
+/// Why an evaluation ended without passing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvaluationFailure {
+    TrailingDrawdown,
+    DailyLossLimit,
+    /// `max_trading_days` ran out first
+    Expired,
+    /// The trades ran out before the target was reached
+    TargetNotReached,
+}
+
+/// How one run through an evaluation ended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvaluationOutcome {
+    pub passed: bool,
+    pub failure: Option<EvaluationFailure>,
+    pub days_traded: u32,
+    /// Profit or loss over the starting balance when the run ended
+    pub final_pnl: f64,
+    /// Highest balance reached
+    pub high_water_mark: f64,
+}
+
+/// Closed round-trip P&L of one trading day, in the order they closed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradingDay {
+    pub date: NaiveDate,
+    pub trades: Vec<f64>,
+}
+
+impl TradingDay {
+    /// Group a fill ledger's round trips by the date they closed
+    pub fn from_ledger(trades: &[TradeRecord], config: &CostModelConfig) -> Vec<Self> {
+        let mut days: BTreeMap<NaiveDate, Vec<f64>> = BTreeMap::new();
+        for (at, pnl) in round_trips(trades, config) {
+            days.entry(at.date_naive()).or_default().push(pnl.to_f64().unwrap_or(0.0));
+        }
+        days.into_iter().map(|(date, trades)| TradingDay { date, trades }).collect()
+    }
+}
+
+/// Replay `days` in order against `rules`
+pub fn evaluate<'a>(rules: &EvaluationRules, days: impl IntoIterator<Item = &'a TradingDay>) -> EvaluationOutcome {
+    let mut balance = rules.account_size;
+    let mut high_water = balance;
+    let mut days_traded = 0;
+    let outcome = |passed, failure, days_traded, balance: f64, high_water| EvaluationOutcome {
+        passed,
+        failure,
+        days_traded,
+        final_pnl: balance - rules.account_size,
+        high_water_mark: high_water,
+    };
+
+    for day in days {
+        if rules.max_trading_days.is_some_and(|max| days_traded >= max) {
+            return outcome(false, Some(EvaluationFailure::Expired), days_traded, balance, high_water);
+        }
+        days_traded += 1;
+
+        let day_start = balance;
+        for pnl in &day.trades {
+            balance += pnl;
+            high_water = high_water.max(balance);
+
+            let mut threshold = high_water - rules.trailing_drawdown;
+            if rules.drawdown_locks_at_balance {
+                threshold = threshold.min(rules.account_size);
+            }
+            if balance <= threshold {
+                return outcome(false, Some(EvaluationFailure::TrailingDrawdown), days_traded, balance, high_water);
+            }
+            if rules.daily_loss_limit.is_some_and(|limit| day_start - balance >= limit) {
+                return outcome(false, Some(EvaluationFailure::DailyLossLimit), days_traded, balance, high_water);
+            }
+        }
+
+        if balance - rules.account_size >= rules.profit_target && days_traded >= rules.min_trading_days {
+            return outcome(true, None, days_traded, balance, high_water);
+        }
+    }
+
+    outcome(false, Some(EvaluationFailure::TargetNotReached), days_traded, balance, high_water)
+}
+
+/// Bootstrap settings for the pass probability
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonteCarloConfig {
+    #[serde(default = "default_simulations")]
+    pub simulations: usize,
+    /// Days drawn per path when the program has no `max_trading_days`;
+    /// the length of the history when absent
+    #[serde(default)]
+    pub horizon_days: Option<u32>,
+    /// Fixed seed for reproducible estimates
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+fn default_simulations() -> usize {
+    10_000
+}
+
+impl Default for MonteCarloConfig {
+    fn default() -> Self {
+        Self {
+            simulations: default_simulations(),
+            horizon_days: None,
+            seed: None,
+        }
+    }
+}
+
+/// Pass and failure rates across bootstrapped paths
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PassProbability {
+    pub simulations: usize,
+    pub pass_rate: f64,
+    pub trailing_drawdown_rate: f64,
+    pub daily_loss_limit_rate: f64,
+    /// Paths that neither passed nor breached a rule in time
+    pub incomplete_rate: f64,
+    /// Median trading days of the passing paths
+    pub median_days_to_pass: Option<u32>,
+}
+
+/// Resample whole trading days with replacement and evaluate each path
+pub fn pass_probability(rules: &EvaluationRules, days: &[TradingDay], config: &MonteCarloConfig) -> PassProbability {
+    if days.is_empty() || config.simulations == 0 {
+        return PassProbability::default();
+    }
+
+    let horizon = rules.max_trading_days
+        .or(config.horizon_days)
+        .unwrap_or(days.len() as u32) as usize;
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    let mut passed_days = Vec::new();
+    let mut trailing = 0;
+    let mut daily = 0;
+    for _ in 0..config.simulations {
+        let path = (0..horizon).map(|_| &days[rng.gen_range(0..days.len())]);
+        let outcome = evaluate(rules, path);
+        match outcome.failure {
+            None => passed_days.push(outcome.days_traded),
+            Some(EvaluationFailure::TrailingDrawdown) => trailing += 1,
+            Some(EvaluationFailure::DailyLossLimit) => daily += 1,
+            Some(_) => {}
+        }
+    }
+
+    let total = config.simulations as f64;
+    let passed = passed_days.len();
+    passed_days.sort_unstable();
+    PassProbability {
+        simulations: config.simulations,
+        pass_rate: passed as f64 / total,
+        trailing_drawdown_rate: trailing as f64 / total,
+        daily_loss_limit_rate: daily as f64 / total,
+        incomplete_rate: (config.simulations - passed - trailing - daily) as f64 / total,
+        median_days_to_pass: passed_days.get(passed / 2).copied(),
+    }
+}
+
+/// One program's verdict on the historical trades and its pass probability
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgramResult {
+    pub rules: EvaluationRules,
+    pub historical: EvaluationOutcome,
+    pub monte_carlo: PassProbability,
+}
+
+/// Evaluation results of a backtest across one or more programs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PropFirmReport {
+    pub trading_days: usize,
+    pub programs: Vec<ProgramResult>,
+}
+
+impl PropFirmReport {
+    pub fn compute(days: &[TradingDay], programs: &[EvaluationRules], config: &MonteCarloConfig) -> Self {
+        Self {
+            trading_days: days.len(),
+            programs: programs.iter()
+                .map(|rules| ProgramResult {
+                    rules: rules.clone(),
+                    historical: evaluate(rules, days),
+                    monte_carlo: pass_probability(rules, days, config),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32, trades: &[f64]) -> TradingDay {
+        TradingDay {
+            date: NaiveDate::from_ymd_opt(2024, 3, d).unwrap(),
+            trades: trades.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_evaluation_rules() {
+        let rules = EvaluationRules::standard_50k();
+
+        // Target reached on day 2 but the minimum days hold the pass to day 5
+        let winning: Vec<_> = (1..=6).map(|d| day(d, &[800.0])).collect();
+        let outcome = evaluate(&rules, &winning);
+        assert!(outcome.passed);
+        assert_eq!(outcome.days_traded, 5);
+
+        let blown = [day(1, &[500.0, -600.0]), day(2, &[-1_100.0])];
+        assert_eq!(evaluate(&rules, &blown).failure, Some(EvaluationFailure::DailyLossLimit));
+
+        // Trails from the $51,200 high to $49,200
+        let trailed = [day(1, &[900.0]), day(2, &[300.0]), day(3, &[-900.0]), day(4, &[-900.0]), day(5, &[-300.0])];
+        let outcome = evaluate(&rules, &trailed);
+        assert_eq!(outcome.failure, Some(EvaluationFailure::TrailingDrawdown));
+        assert_eq!(outcome.high_water_mark, 51_200.0);
+
+        let config = MonteCarloConfig { simulations: 200, horizon_days: Some(20), seed: Some(7) };
+        assert_eq!(pass_probability(&rules, &winning, &config).pass_rate, 1.0);
+        let report = PropFirmReport::compute(&blown, &[rules], &config);
+        assert_eq!(report.programs[0].monte_carlo.pass_rate, 0.0);
+    }
+}
@@ -0,0 +1,325 @@
+//! End-of-day reconciliation of paper and live sessions
+//!
+//! A session traded live or on paper is replayed through the backtest
+//! engine on the tick stream recorded during it, and the two fill ledgers
+//! are compared. Fills are paired by side within a time window; the price
+//! and timing differences of each pair, the fills only one side made, and
+//! the gap in session P&L and slippage measure how far the simulation has
+//! drifted from what the market actually did.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::cost_model::{round_trips, CostModelConfig};
+use crate::backtesting::metrics::TradeRecord;
+use crate::backtesting::{BacktestConfig, BacktestEngine};
+use crate::strategy::{OrderSide, Strategy};
+use crate::subscription::RecordedSession;
+use crate::timestamp::Timestamp;
+
+/// How fills are paired and what counts as divergent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationConfig {
+    /// Largest gap between a live fill and its replayed counterpart
+    #[serde(default = "default_match_window_ms")]
+    pub match_window_ms: i64,
+    /// Price difference of a paired fill, in ticks, beyond which it diverges
+    #[serde(default = "default_price_tolerance_ticks")]
+    pub price_tolerance_ticks: Decimal,
+    #[serde(default)]
+    pub cost: CostModelConfig,
+}
+
+fn default_match_window_ms() -> i64 {
+    2_000
+}
+
+fn default_price_tolerance_ticks() -> Decimal {
+    Decimal::ONE
+}
+
+impl Default for ReconciliationConfig {
+    fn default() -> Self {
+        Self {
+            match_window_ms: default_match_window_ms(),
+            price_tolerance_ticks: default_price_tolerance_ticks(),
+            cost: CostModelConfig::default(),
+        }
+    }
+}
+
+/// Totals of one side's fill ledger
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub fills: usize,
+    pub contracts: i64,
+    pub round_trips: usize,
+    /// Closed round-trip P&L net of commission
+    pub net_pnl: Decimal,
+    pub commission: Decimal,
+    /// Mean slippage per fill, in price units
+    pub avg_slippage: Decimal,
+}
+
+impl SessionSummary {
+    pub fn from_ledger(trades: &[TradeRecord], config: &CostModelConfig) -> Self {
+        let closed = round_trips(trades, config);
+        let slippage: Decimal = trades.iter().map(|t| t.slippage).sum();
+        Self {
+            fills: trades.len(),
+            contracts: trades.iter().map(|t| t.quantity as i64).sum(),
+            round_trips: closed.len(),
+            net_pnl: closed.iter().map(|(_, pnl)| *pnl).sum(),
+            commission: trades.iter().map(|t| t.commission).sum(),
+            avg_slippage: if trades.is_empty() {
+                Decimal::ZERO
+            } else {
+                slippage / Decimal::from(trades.len())
+            },
+        }
+    }
+}
+
+/// Why a fill did not reconcile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Divergence {
+    /// Paired, but the prices differ by more than the tolerance
+    PriceDrift,
+    /// Paired, but for a different number of contracts
+    QuantityMismatch,
+    /// Filled live with nothing in the replay
+    MissingFromReplay,
+    /// Filled in the replay but never live
+    MissingFromLive,
+}
+
+/// A live fill and the replayed fill it was paired with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FillComparison {
+    pub side: OrderSide,
+    pub live: Option<TradeRecord>,
+    pub replay: Option<TradeRecord>,
+    /// Live fill time minus replay fill time
+    #[serde(default)]
+    pub delay_ms: Option<i64>,
+    /// How much worse the live price was than the replayed one, in ticks
+    #[serde(default)]
+    pub price_diff_ticks: Option<Decimal>,
+    #[serde(default)]
+    pub divergence: Option<Divergence>,
+}
+
+/// Differences between the live session and its replay
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FidelityDrift {
+    /// Live net P&L minus replay net P&L
+    pub pnl_diff: Decimal,
+    /// Live average slippage minus replay average slippage
+    pub slippage_diff: Decimal,
+    /// Share of all fills, live and replayed, that were paired
+    pub match_rate: f64,
+    /// Mean adverse price difference of paired fills, in ticks
+    pub mean_price_diff_ticks: Decimal,
+    /// Mean delay of paired live fills behind the replay
+    pub mean_delay_ms: f64,
+}
+
+/// Comparison of a session's live fills against a same-day replay
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationReport {
+    #[serde(default)]
+    pub trade_date: Option<NaiveDate>,
+    pub generated_at: DateTime<Utc>,
+    pub live: SessionSummary,
+    pub replay: SessionSummary,
+    pub drift: FidelityDrift,
+    pub fills: Vec<FillComparison>,
+    pub divergent_fills: usize,
+}
+
+impl ReconciliationReport {
+    pub fn compute(live: &[TradeRecord], replay: &[TradeRecord], config: &ReconciliationConfig) -> Self {
+        let fills = pair_fills(live, replay, config);
+
+        let live_summary = SessionSummary::from_ledger(live, &config.cost);
+        let replay_summary = SessionSummary::from_ledger(replay, &config.cost);
+        let paired: Vec<&FillComparison> = fills.iter()
+            .filter(|f| f.live.is_some() && f.replay.is_some())
+            .collect();
+
+        let total = live.len() + replay.len();
+        let drift = FidelityDrift {
+            pnl_diff: live_summary.net_pnl - replay_summary.net_pnl,
+            slippage_diff: live_summary.avg_slippage - replay_summary.avg_slippage,
+            match_rate: if total == 0 { 1.0 } else { (2 * paired.len()) as f64 / total as f64 },
+            mean_price_diff_ticks: if paired.is_empty() {
+                Decimal::ZERO
+            } else {
+                paired.iter().filter_map(|f| f.price_diff_ticks).sum::<Decimal>() / Decimal::from(paired.len())
+            },
+            mean_delay_ms: if paired.is_empty() {
+                0.0
+            } else {
+                paired.iter().filter_map(|f| f.delay_ms).sum::<i64>() as f64 / paired.len() as f64
+            },
+        };
+
+        Self {
+            trade_date: live.first().or(replay.first()).map(|t| t.timestamp.date_naive()),
+            generated_at: Utc::now(),
+            live: live_summary,
+            replay: replay_summary,
+            drift,
+            divergent_fills: fills.iter().filter(|f| f.divergence.is_some()).count(),
+            fills,
+        }
+    }
+
+    /// Whether every fill paired up within tolerance
+    pub fn is_reconciled(&self) -> bool {
+        self.divergent_fills == 0
+    }
+
+    pub fn divergent(&self) -> impl Iterator<Item = &FillComparison> {
+        self.fills.iter().filter(|f| f.divergence.is_some())
+    }
+}
+
+/// Pair each live fill with the earliest unpaired replayed fill on the same
+/// side inside the window, then list what is left of the replay
+fn pair_fills(live: &[TradeRecord], replay: &[TradeRecord], config: &ReconciliationConfig) -> Vec<FillComparison> {
+    let mut live: Vec<&TradeRecord> = live.iter().collect();
+    let mut replay: Vec<&TradeRecord> = replay.iter().collect();
+    live.sort_by_key(|t| t.timestamp);
+    replay.sort_by_key(|t| t.timestamp);
+
+    let mut used = vec![false; replay.len()];
+    let mut fills = Vec::with_capacity(live.len().max(replay.len()));
+    for live_fill in live {
+        let candidate = replay.iter().enumerate().position(|(i, r)| {
+            !used[i]
+                && r.side == live_fill.side
+                && (live_fill.timestamp - r.timestamp).num_milliseconds().abs() <= config.match_window_ms
+        });
+        let Some(i) = candidate else {
+            fills.push(FillComparison {
+                side: live_fill.side,
+                live: Some(live_fill.clone()),
+                replay: None,
+                delay_ms: None,
+                price_diff_ticks: None,
+                divergence: Some(Divergence::MissingFromReplay),
+            });
+            continue;
+        };
+        used[i] = true;
+        let replay_fill = replay[i];
+
+        let mut price_diff = live_fill.price - replay_fill.price;
+        if live_fill.side == OrderSide::Sell {
+            price_diff = -price_diff;
+        }
+        let price_diff_ticks = if config.cost.tick_size.is_zero() {
+            price_diff
+        } else {
+            price_diff / config.cost.tick_size
+        };
+        let divergence = if live_fill.quantity != replay_fill.quantity {
+            Some(Divergence::QuantityMismatch)
+        } else if price_diff_ticks.abs() > config.price_tolerance_ticks {
+            Some(Divergence::PriceDrift)
+        } else {
+            None
+        };
+
+        fills.push(FillComparison {
+            side: live_fill.side,
+            live: Some(live_fill.clone()),
+            replay: Some(replay_fill.clone()),
+            delay_ms: Some((live_fill.timestamp - replay_fill.timestamp).num_milliseconds()),
+            price_diff_ticks: Some(price_diff_ticks),
+            divergence,
+        });
+    }
+
+    for (replay_fill, _) in replay.into_iter().zip(used).filter(|(_, used)| !used) {
+        fills.push(FillComparison {
+            side: replay_fill.side,
+            live: None,
+            replay: Some(replay_fill.clone()),
+            delay_ms: None,
+            price_diff_ticks: None,
+            divergence: Some(Divergence::MissingFromLive),
+        });
+    }
+    fills
+}
+
+/// Replay a recorded session through the engine and reconcile its fills
+/// against the ones made live
+///
+/// The engine's date range is narrowed to the session's first and last tick.
+pub async fn reconcile_session<S: Strategy>(
+    strategy: &mut S,
+    mut backtest: BacktestConfig,
+    session: &RecordedSession,
+    live: &[TradeRecord],
+    config: &ReconciliationConfig,
+) -> Result<ReconciliationReport, Box<dyn std::error::Error>> {
+    backtest.start_date = Timestamp::from_nanos(session.first_timestamp).to_utc();
+    backtest.end_date = Timestamp::from_nanos(session.last_timestamp).to_utc();
+
+    let mut engine = BacktestEngine::new(backtest);
+    engine.run_backtest(strategy, &session.path).await?;
+
+    let mut report = ReconciliationReport::compute(live, engine.trades(), config);
+    report.trade_date = Some(session.trade_date);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn trade(second: u32, side: OrderSide, price: i64) -> TradeRecord {
+        TradeRecord {
+            timestamp: Utc.with_ymd_and_hms(2024, 3, 1, 14, 30, second).unwrap(),
+            side,
+            quantity: 1,
+            price: Decimal::new(price, 2),
+            commission: Decimal::ONE,
+            slippage: Decimal::ZERO,
+            tag: None,
+        }
+    }
+
+    #[test]
+    fn test_reconciliation_flags_divergent_fills() {
+        let live = vec![
+            trade(0, OrderSide::Buy, 1_805_050),
+            trade(10, OrderSide::Sell, 1_806_000),
+            trade(40, OrderSide::Buy, 1_805_000),
+        ];
+        let replay = vec![
+            trade(1, OrderSide::Buy, 1_805_000),
+            trade(10, OrderSide::Sell, 1_806_000),
+            trade(50, OrderSide::Sell, 1_804_000),
+        ];
+        let report = ReconciliationReport::compute(&live, &replay, &ReconciliationConfig::default());
+
+        // Two ticks worse on entry, same exit
+        assert_eq!(report.fills[0].price_diff_ticks, Some(Decimal::from(2)));
+        assert_eq!(report.fills[0].divergence, Some(Divergence::PriceDrift));
+        assert_eq!(report.fills[0].delay_ms, Some(-1_000));
+        assert_eq!(report.fills[1].divergence, None);
+
+        let kinds: Vec<_> = report.divergent().filter_map(|f| f.divergence).collect();
+        assert_eq!(kinds, vec![Divergence::PriceDrift, Divergence::MissingFromReplay, Divergence::MissingFromLive]);
+        assert_eq!(report.drift.match_rate, 4.0 / 6.0);
+        // 0.50 points on one contract at $2 a point
+        assert_eq!(report.drift.pnl_diff, Decimal::from(-1));
+    }
+}
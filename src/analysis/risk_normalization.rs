@@ -0,0 +1,311 @@
+//! Risk-normalized comparison of backtests
+//!
+//! Raw P&L favors whichever strategy traded the most size. Scaling each
+//! strategy's returns to the same volatility, or the same maximum drawdown,
+//! compares them as if they had been sized to take the same risk. Changing
+//! position size on fixed capital scales each day's return, so returns are
+//! summed rather than compounded here and drawdown scales exactly with size.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+use super::correlation::ReturnSeries;
+
+/// Errors raised comparing backtests
+#[derive(Debug, thiserror::Error)]
+pub enum RiskNormalizationError {
+    #[error("At least two return series are needed, got {0}")]
+    TooFewSeries(usize),
+    #[error("Only {found} shared days, at least {required} needed")]
+    InsufficientOverlap { found: usize, required: usize },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskNormalizationConfig {
+    /// Annualized volatility every strategy is scaled to, as a fraction
+    #[serde(default = "default_target_volatility")]
+    pub target_volatility: f64,
+    /// Maximum drawdown every strategy is scaled to, as a positive fraction
+    #[serde(default = "default_target_max_drawdown")]
+    pub target_max_drawdown: f64,
+    #[serde(default = "default_periods_per_year")]
+    pub periods_per_year: f64,
+    /// Fewest shared days the comparison runs on
+    #[serde(default = "default_min_periods")]
+    pub min_periods: usize,
+}
+
+fn default_target_volatility() -> f64 {
+    0.10
+}
+
+fn default_target_max_drawdown() -> f64 {
+    0.10
+}
+
+fn default_periods_per_year() -> f64 {
+    252.0
+}
+
+fn default_min_periods() -> usize {
+    10
+}
+
+impl Default for RiskNormalizationConfig {
+    fn default() -> Self {
+        Self {
+            target_volatility: default_target_volatility(),
+            target_max_drawdown: default_target_max_drawdown(),
+            periods_per_year: default_periods_per_year(),
+            min_periods: default_min_periods(),
+        }
+    }
+}
+
+/// Return and risk of one return series, as fractions of capital
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RiskMetrics {
+    pub total_return: f64,
+    pub annualized_return: f64,
+    pub annualized_volatility: f64,
+    /// Deepest fall from a peak of cumulative return; zero or negative
+    pub max_drawdown: f64,
+    pub sharpe_ratio: f64,
+    /// Annualized return over the depth of the maximum drawdown
+    pub return_over_drawdown: f64,
+}
+
+impl RiskMetrics {
+    fn from_returns(returns: &[f64], periods_per_year: f64) -> Self {
+        let n = returns.len().max(1) as f64;
+        let total_return: f64 = returns.iter().sum();
+        let mean = total_return / n;
+        let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0).max(1.0);
+
+        let mut cumulative = 0.0f64;
+        let mut peak = 0.0f64;
+        let mut max_drawdown = 0.0f64;
+        for r in returns {
+            cumulative += r;
+            peak = peak.max(cumulative);
+            max_drawdown = max_drawdown.min(cumulative - peak);
+        }
+
+        let annualized_return = mean * periods_per_year;
+        let annualized_volatility = variance.sqrt() * periods_per_year.sqrt();
+        Self {
+            total_return,
+            annualized_return,
+            annualized_volatility,
+            max_drawdown,
+            sharpe_ratio: ratio(annualized_return, annualized_volatility),
+            return_over_drawdown: ratio(annualized_return, -max_drawdown),
+        }
+    }
+
+    /// The metrics had every position been `scale` times the size
+    ///
+    /// Risk-adjusted ratios do not change with size.
+    fn scaled(&self, scale: f64) -> Self {
+        Self {
+            total_return: self.total_return * scale,
+            annualized_return: self.annualized_return * scale,
+            annualized_volatility: self.annualized_volatility * scale,
+            max_drawdown: self.max_drawdown * scale,
+            ..*self
+        }
+    }
+}
+
+fn ratio(numerator: f64, denominator: f64) -> f64 {
+    if denominator > 0.0 {
+        numerator / denominator
+    } else {
+        0.0
+    }
+}
+
+/// Metrics after scaling to a risk target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScaledMetrics {
+    /// Multiple of the strategy's actual size that meets the target
+    pub scale: f64,
+    pub metrics: RiskMetrics,
+    /// Rank by total return among the strategies that could be scaled, 1 first
+    pub rank: usize,
+}
+
+/// One backtest, raw and risk-normalized
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyComparison {
+    pub id: String,
+    pub raw: RiskMetrics,
+    /// Rank by raw total return, 1 first
+    pub raw_rank: usize,
+    /// Scaled to the target volatility; `None` when returns never vary
+    pub equal_volatility: Option<ScaledMetrics>,
+    /// Scaled to the target drawdown; `None` when the strategy never drew down
+    pub equal_drawdown: Option<ScaledMetrics>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskNormalizedComparison {
+    pub config: RiskNormalizationConfig,
+    /// Shared days the comparison ran on
+    pub periods: usize,
+    pub first_period: NaiveDate,
+    pub last_period: NaiveDate,
+    /// In the order requested
+    pub strategies: Vec<StrategyComparison>,
+    /// Strategies whose standing changes once risk is equalized
+    pub notes: Vec<String>,
+}
+
+impl RiskNormalizedComparison {
+    /// Compare `series` over the days they share
+    pub fn compute(series: &[ReturnSeries], config: &RiskNormalizationConfig) -> Result<Self, RiskNormalizationError> {
+        if series.len() < 2 {
+            return Err(RiskNormalizationError::TooFewSeries(series.len()));
+        }
+
+        let mut shared: BTreeSet<NaiveDate> = series[0].returns.keys().copied().collect();
+        for s in &series[1..] {
+            shared.retain(|date| s.returns.contains_key(date));
+        }
+        let required = config.min_periods.max(2);
+        if shared.len() < required {
+            return Err(RiskNormalizationError::InsufficientOverlap { found: shared.len(), required });
+        }
+
+        let raw: Vec<RiskMetrics> = series.iter()
+            .map(|s| {
+                let returns: Vec<f64> = shared.iter().map(|date| s.returns[date]).collect();
+                RiskMetrics::from_returns(&returns, config.periods_per_year)
+            })
+            .collect();
+        let by_volatility: Vec<Option<f64>> = raw.iter()
+            .map(|m| (m.annualized_volatility > f64::EPSILON).then(|| config.target_volatility / m.annualized_volatility))
+            .collect();
+        let by_drawdown: Vec<Option<f64>> = raw.iter()
+            .map(|m| (m.max_drawdown < -f64::EPSILON).then(|| config.target_max_drawdown / -m.max_drawdown))
+            .collect();
+
+        let raw_ranks = ranks(&raw.iter().map(|m| Some(m.total_return)).collect::<Vec<_>>());
+        let scaled = |scales: &[Option<f64>]| -> Vec<Option<ScaledMetrics>> {
+            let metrics: Vec<Option<RiskMetrics>> = scales.iter().zip(&raw)
+                .map(|(scale, m)| scale.map(|scale| m.scaled(scale)))
+                .collect();
+            let ranks = ranks(&metrics.iter().map(|m| m.map(|m| m.total_return)).collect::<Vec<_>>());
+            metrics.into_iter().zip(scales).zip(ranks)
+                .map(|((metrics, scale), rank)| Some(ScaledMetrics { scale: (*scale)?, metrics: metrics?, rank: rank? }))
+                .collect()
+        };
+        let equal_volatility = scaled(&by_volatility);
+        let equal_drawdown = scaled(&by_drawdown);
+
+        let strategies: Vec<StrategyComparison> = series.iter().enumerate()
+            .map(|(i, s)| StrategyComparison {
+                id: s.id.clone(),
+                raw: raw[i],
+                raw_rank: raw_ranks[i].unwrap_or(i + 1),
+                equal_volatility: equal_volatility[i].clone(),
+                equal_drawdown: equal_drawdown[i].clone(),
+            })
+            .collect();
+
+        Ok(Self {
+            config: config.clone(),
+            periods: shared.len(),
+            first_period: *shared.first().unwrap(),
+            last_period: *shared.last().unwrap(),
+            notes: notes(&strategies),
+            strategies,
+        })
+    }
+}
+
+/// 1-based ranks by descending value; missing values are unranked
+fn ranks(values: &[Option<f64>]) -> Vec<Option<usize>> {
+    let mut order: Vec<usize> = (0..values.len()).filter(|&i| values[i].is_some()).collect();
+    order.sort_by(|&a, &b| values[b].partial_cmp(&values[a]).unwrap_or(std::cmp::Ordering::Equal));
+    let mut ranks = vec![None; values.len()];
+    for (rank, i) in order.into_iter().enumerate() {
+        ranks[i] = Some(rank + 1);
+    }
+    ranks
+}
+
+fn notes(strategies: &[StrategyComparison]) -> Vec<String> {
+    let mut notes = Vec::new();
+    for s in strategies {
+        for (basis, normalized) in [("equal volatility", &s.equal_volatility), ("equal drawdown", &s.equal_drawdown)] {
+            if let Some(normalized) = normalized.as_ref().filter(|n| n.rank != s.raw_rank) {
+                notes.push(format!(
+                    "{} ranks {} on raw return but {} at {} ({:.2}x its size)",
+                    s.id, ordinal(s.raw_rank), ordinal(normalized.rank), basis, normalized.scale
+                ));
+            }
+        }
+    }
+    notes
+}
+
+fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (1, 11) | (2, 12) | (3, 13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Days;
+
+    fn series(id: &str, returns: impl Fn(usize) -> f64) -> ReturnSeries {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        ReturnSeries::new(id, (0..40)
+            .map(|i| (start + Days::new(i as u64), returns(i)))
+            .collect())
+    }
+
+    #[test]
+    fn test_larger_size_does_not_win_once_risk_is_equal() {
+        // `big` trades five times the size of `small` with a worse edge
+        let small = series("small", |i| if i % 2 == 0 { 0.004 } else { -0.002 });
+        let big = series("big", |i| if i % 2 == 0 { 0.015 } else { -0.011 });
+        let flat = series("flat", |_| 0.0005);
+
+        let config = RiskNormalizationConfig::default();
+        let report = RiskNormalizedComparison::compute(&[small, big, flat], &config).unwrap();
+        let [small, big, flat] = &report.strategies[..] else { panic!("three strategies") };
+
+        assert_eq!(report.periods, 40);
+        assert_eq!((big.raw_rank, small.raw_rank), (1, 2));
+        let small_vol = small.equal_volatility.as_ref().unwrap();
+        let big_vol = big.equal_volatility.as_ref().unwrap();
+        assert!((small_vol.metrics.annualized_volatility - config.target_volatility).abs() < 1e-9);
+        assert!((big_vol.metrics.annualized_volatility - config.target_volatility).abs() < 1e-9);
+        assert!(small_vol.metrics.total_return > big_vol.metrics.total_return);
+        assert_eq!((small_vol.rank, big_vol.rank), (1, 2));
+
+        let small_dd = small.equal_drawdown.as_ref().unwrap();
+        assert!((small_dd.metrics.max_drawdown + config.target_max_drawdown).abs() < 1e-9);
+        assert_eq!(small_dd.metrics.sharpe_ratio, small.raw.sharpe_ratio);
+
+        // Constant returns have no risk to scale
+        assert!(flat.equal_volatility.is_none() && flat.equal_drawdown.is_none());
+        assert!(report.notes.iter().any(|note| note.starts_with("big ranks 1st on raw return but 2nd")));
+
+        let short = series("short", |_| 0.0);
+        assert!(matches!(
+            RiskNormalizedComparison::compute(&[short], &config),
+            Err(RiskNormalizationError::TooFewSeries(1))
+        ));
+    }
+}
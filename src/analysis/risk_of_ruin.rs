@@ -0,0 +1,331 @@
+//! Risk of ruin and Kelly position sizing
+//!
+//! A backtest's closed round trips, scaled to one contract, form the trade
+//! distribution. Paths of future trades are bootstrapped from it under each
+//! sizing rule, and the share of paths whose equity falls to the ruin level
+//! before the horizon is the risk of ruin. The Kelly fraction of the same
+//! distribution, and fractions of it, are offered as sizing suggestions
+//! with their own risk of ruin.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+
+use super::cost_model::{closed_round_trips, CostModelConfig};
+use crate::backtesting::metrics::TradeRecord;
+
+/// How many contracts each simulated trade takes
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum SizingRule {
+    /// The same size on every trade
+    FixedContracts { contracts: u32 },
+    /// Risk `fraction` of current equity per trade, measured against the
+    /// average one-contract loss
+    FixedFraction { fraction: f64 },
+}
+
+impl SizingRule {
+    fn contracts(&self, equity: f64, avg_loss: f64) -> u32 {
+        match *self {
+            SizingRule::FixedContracts { contracts } => contracts,
+            SizingRule::FixedFraction { fraction } if avg_loss > 0.0 => {
+                (fraction * equity / avg_loss).floor().max(0.0) as u32
+            }
+            SizingRule::FixedFraction { .. } => 1,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            SizingRule::FixedContracts { contracts } => format!("{} contract(s)", contracts),
+            SizingRule::FixedFraction { fraction } => format!("{:.1}% of equity", fraction * 100.0),
+        }
+    }
+}
+
+/// Capital, ruin level and simulation settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuinConfig {
+    #[serde(default = "default_capital")]
+    pub capital: f64,
+    /// Fall below starting capital, as a fraction of it, that counts as ruin
+    #[serde(default = "default_ruin_drawdown")]
+    pub ruin_drawdown: f64,
+    /// Trades per simulated path
+    #[serde(default = "default_horizon_trades")]
+    pub horizon_trades: usize,
+    #[serde(default = "default_simulations")]
+    pub simulations: usize,
+    /// Fixed seed for reproducible estimates
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Rules to estimate; one contract per trade when empty
+    #[serde(default)]
+    pub sizing: Vec<SizingRule>,
+    /// Multiples of the Kelly fraction to suggest
+    #[serde(default = "default_kelly_multipliers")]
+    pub kelly_multipliers: Vec<f64>,
+}
+
+fn default_capital() -> f64 {
+    10_000.0
+}
+
+fn default_ruin_drawdown() -> f64 {
+    0.5
+}
+
+fn default_horizon_trades() -> usize {
+    500
+}
+
+fn default_simulations() -> usize {
+    5_000
+}
+
+fn default_kelly_multipliers() -> Vec<f64> {
+    vec![1.0, 0.5, 0.25]
+}
+
+impl Default for RuinConfig {
+    fn default() -> Self {
+        Self {
+            capital: default_capital(),
+            ruin_drawdown: default_ruin_drawdown(),
+            horizon_trades: default_horizon_trades(),
+            simulations: default_simulations(),
+            seed: None,
+            sizing: Vec::new(),
+            kelly_multipliers: default_kelly_multipliers(),
+        }
+    }
+}
+
+/// One-contract P&L of the backtest's round trips
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PerContractStats {
+    pub trades: usize,
+    pub win_rate: f64,
+    pub avg_win: f64,
+    /// Average losing trade, as a positive amount
+    pub avg_loss: f64,
+    pub worst_loss: f64,
+    pub expectancy: f64,
+}
+
+impl PerContractStats {
+    pub fn from_pnls(pnls: &[f64]) -> Self {
+        if pnls.is_empty() {
+            return Self::default();
+        }
+        let wins: Vec<f64> = pnls.iter().copied().filter(|p| *p > 0.0).collect();
+        let losses: Vec<f64> = pnls.iter().copied().filter(|p| *p < 0.0).map(|p| -p).collect();
+        let mean = |values: &[f64]| if values.is_empty() { 0.0 } else { values.iter().sum::<f64>() / values.len() as f64 };
+        Self {
+            trades: pnls.len(),
+            win_rate: wins.len() as f64 / pnls.len() as f64,
+            avg_win: mean(&wins),
+            avg_loss: mean(&losses),
+            worst_loss: losses.iter().copied().fold(0.0, f64::max),
+            expectancy: mean(pnls),
+        }
+    }
+
+    /// Kelly fraction `p - (1 - p) / b`, with `b` the average win over the
+    /// average loss; capped at 1 when there are no losses
+    pub fn kelly_fraction(&self) -> f64 {
+        if self.trades == 0 || self.avg_win <= 0.0 {
+            return 0.0;
+        }
+        if self.avg_loss <= 0.0 {
+            return 1.0;
+        }
+        let payoff = self.avg_win / self.avg_loss;
+        (self.win_rate - (1.0 - self.win_rate) / payoff).min(1.0)
+    }
+}
+
+/// Outcome of one sizing rule across the simulated paths
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuinEstimate {
+    pub sizing: SizingRule,
+    /// Contracts the rule trades at the starting capital
+    pub initial_contracts: u32,
+    pub risk_of_ruin: f64,
+    pub median_final_equity: f64,
+    /// Median of each path's deepest fall from its high, as a fraction
+    pub median_max_drawdown: f64,
+}
+
+/// A fraction of the Kelly bet and what it implies
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KellySuggestion {
+    pub multiplier: f64,
+    /// Share of equity risked per trade
+    pub fraction: f64,
+    pub estimate: RuinEstimate,
+}
+
+/// Risk of ruin under each sizing rule and Kelly sizing suggestions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskOfRuinReport {
+    pub distribution: PerContractStats,
+    pub capital: f64,
+    /// Equity at which a path counts as ruined
+    pub ruin_level: f64,
+    pub simulations: usize,
+    pub horizon_trades: usize,
+    /// Full Kelly fraction; zero or below means the trades show no edge
+    pub kelly_fraction: f64,
+    pub sizing: Vec<RuinEstimate>,
+    pub kelly: Vec<KellySuggestion>,
+}
+
+impl RiskOfRuinReport {
+    /// Estimate from a fill ledger, scaling each round trip to one contract
+    pub fn from_ledger(trades: &[TradeRecord], cost: &CostModelConfig, config: &RuinConfig) -> Self {
+        let pnls: Vec<f64> = closed_round_trips(trades, cost).into_iter()
+            .filter(|trip| trip.quantity > 0)
+            .map(|trip| trip.pnl.to_f64().unwrap_or(0.0) / trip.quantity as f64)
+            .collect();
+        Self::compute(&pnls, config)
+    }
+
+    /// Estimate from one-contract trade P&Ls
+    pub fn compute(pnls: &[f64], config: &RuinConfig) -> Self {
+        let distribution = PerContractStats::from_pnls(pnls);
+        let kelly_fraction = distribution.kelly_fraction();
+        let seed = config.seed.unwrap_or_else(rand::random);
+        let estimate = |sizing| simulate(pnls, sizing, &distribution, config, seed);
+
+        let rules = if config.sizing.is_empty() {
+            vec![SizingRule::FixedContracts { contracts: 1 }]
+        } else {
+            config.sizing.clone()
+        };
+        let kelly = if kelly_fraction > 0.0 {
+            config.kelly_multipliers.iter()
+                .map(|&multiplier| {
+                    let fraction = kelly_fraction * multiplier;
+                    KellySuggestion {
+                        multiplier,
+                        fraction,
+                        estimate: estimate(SizingRule::FixedFraction { fraction }),
+                    }
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            capital: config.capital,
+            ruin_level: ruin_level(config),
+            simulations: config.simulations,
+            horizon_trades: config.horizon_trades,
+            kelly_fraction,
+            sizing: rules.into_iter().map(estimate).collect(),
+            kelly,
+            distribution,
+        }
+    }
+
+    /// The rule with the highest risk of ruin
+    pub fn riskiest(&self) -> Option<&RuinEstimate> {
+        self.sizing.iter().max_by(|a, b| a.risk_of_ruin.total_cmp(&b.risk_of_ruin))
+    }
+}
+
+fn ruin_level(config: &RuinConfig) -> f64 {
+    config.capital * (1.0 - config.ruin_drawdown.clamp(0.0, 1.0))
+}
+
+/// Bootstrap `config.simulations` paths of trades sized by `sizing`; a path
+/// is ruined at the ruin level or once the rule can no longer size a trade
+fn simulate(pnls: &[f64], sizing: SizingRule, distribution: &PerContractStats, config: &RuinConfig, seed: u64) -> RuinEstimate {
+    let initial_contracts = sizing.contracts(config.capital, distribution.avg_loss);
+    if pnls.is_empty() || config.simulations == 0 {
+        return RuinEstimate {
+            sizing,
+            initial_contracts,
+            risk_of_ruin: 0.0,
+            median_final_equity: config.capital,
+            median_max_drawdown: 0.0,
+        };
+    }
+
+    let ruin_level = ruin_level(config);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut ruined = 0;
+    let mut finals = Vec::with_capacity(config.simulations);
+    let mut drawdowns = Vec::with_capacity(config.simulations);
+    for _ in 0..config.simulations {
+        let mut equity = config.capital;
+        let mut high = equity;
+        let mut max_drawdown: f64 = 0.0;
+        for _ in 0..config.horizon_trades {
+            let contracts = sizing.contracts(equity, distribution.avg_loss);
+            if contracts > 0 {
+                equity += contracts as f64 * pnls[rng.gen_range(0..pnls.len())];
+                high = high.max(equity);
+                max_drawdown = max_drawdown.max(1.0 - equity / high);
+            }
+            if contracts == 0 || equity <= ruin_level {
+                ruined += 1;
+                break;
+            }
+        }
+        finals.push(equity);
+        drawdowns.push(max_drawdown);
+    }
+
+    let median = |values: &mut Vec<f64>| {
+        values.sort_by(|a, b| a.total_cmp(b));
+        values[values.len() / 2]
+    };
+    RuinEstimate {
+        sizing,
+        initial_contracts,
+        risk_of_ruin: ruined as f64 / config.simulations as f64,
+        median_final_equity: median(&mut finals),
+        median_max_drawdown: median(&mut drawdowns),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kelly_and_ruin() {
+        // 60% winners of $100 against $100 losers: Kelly risks 20%
+        let pnls: Vec<f64> = (0..10).map(|i| if i < 6 { 100.0 } else { -100.0 }).collect();
+        let distribution = PerContractStats::from_pnls(&pnls);
+        assert!((distribution.kelly_fraction() - 0.2).abs() < 1e-9);
+
+        let config = RuinConfig {
+            capital: 2_000.0,
+            simulations: 2_000,
+            seed: Some(11),
+            sizing: vec![
+                SizingRule::FixedContracts { contracts: 1 },
+                SizingRule::FixedContracts { contracts: 8 },
+            ],
+            ..Default::default()
+        };
+        let report = RiskOfRuinReport::compute(&pnls, &config);
+        assert_eq!(report.ruin_level, 1_000.0);
+        assert!(report.sizing[0].risk_of_ruin < report.sizing[1].risk_of_ruin);
+        assert_eq!(report.riskiest().unwrap().sizing, config.sizing[1]);
+
+        // Full Kelly starts at 4 contracts; quarter Kelly ruins less often
+        assert_eq!(report.kelly[0].estimate.initial_contracts, 4);
+        assert!(report.kelly[2].estimate.risk_of_ruin <= report.kelly[0].estimate.risk_of_ruin);
+
+        let losing = RiskOfRuinReport::compute(&[-50.0, 20.0], &config);
+        assert!(losing.kelly_fraction <= 0.0);
+        assert!(losing.kelly.is_empty());
+    }
+}
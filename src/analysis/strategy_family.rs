@@ -0,0 +1,211 @@
+//! Strategy family reports
+//!
+//! Aggregates every stored backtest of one strategy, across parameter sets
+//! and time ranges, into metric distributions. A single good run proves
+//! little; a family whose Sharpe stays positive across most runs and periods
+//! is much more likely to be a real edge.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::statistics::{ConfidenceInterval, StatisticalAnalyzer};
+
+/// Fewest runs needed before a robustness verdict is given
+const MIN_RUNS_FOR_VERDICT: usize = 5;
+
+/// Fraction of runs that must have a positive Sharpe to call a family robust
+const ROBUST_POSITIVE_FRACTION: f64 = 0.6;
+
+/// Metrics of one backtest run in a family
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FamilyRun {
+    pub run_id: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub total_return: f64,
+    pub sharpe_ratio: f64,
+    pub max_drawdown: f64,
+    pub total_trades: u32,
+}
+
+/// Summary of a metric across runs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetricDistribution {
+    pub count: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub p05: f64,
+    pub p25: f64,
+    pub median: f64,
+    pub p75: f64,
+    pub p95: f64,
+    pub max: f64,
+}
+
+impl MetricDistribution {
+    pub fn from_values(values: &[f64]) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        let n = sorted.len() as f64;
+        let mean = sorted.iter().sum::<f64>() / n;
+        let variance = if sorted.len() > 1 {
+            sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)
+        } else {
+            0.0
+        };
+
+        Self {
+            count: sorted.len(),
+            mean,
+            std_dev: variance.sqrt(),
+            min: sorted[0],
+            p05: percentile(&sorted, 0.05),
+            p25: percentile(&sorted, 0.25),
+            median: percentile(&sorted, 0.50),
+            p75: percentile(&sorted, 0.75),
+            p95: percentile(&sorted, 0.95),
+            max: sorted[sorted.len() - 1],
+        }
+    }
+}
+
+/// Linear-interpolated percentile of sorted values
+fn percentile(sorted: &[f64], q: f64) -> f64 {
+    let rank = q * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Whether the strategy idea holds up across runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RobustnessVerdict {
+    /// Sharpe is positive in most runs and its mean is significantly above zero
+    Robust,
+    /// Results depend on the particular parameters or period
+    Fragile,
+    /// Too few runs to judge
+    Inconclusive,
+}
+
+/// Aggregate statistics over every backtest of a strategy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyFamilyReport {
+    pub strategy_id: String,
+    pub runs: usize,
+    /// Distinct backtest periods covered by the runs
+    pub distinct_periods: usize,
+    pub sharpe_ratio: MetricDistribution,
+    pub max_drawdown: MetricDistribution,
+    pub total_return: MetricDistribution,
+    pub trade_count: MetricDistribution,
+    pub profitable_fraction: f64,
+    pub positive_sharpe_fraction: f64,
+    /// 95% confidence interval of the mean Sharpe, when there are at least two runs
+    pub sharpe_confidence_interval: Option<ConfidenceInterval>,
+    pub verdict: RobustnessVerdict,
+}
+
+impl StrategyFamilyReport {
+    pub fn from_runs(strategy_id: impl Into<String>, runs: &[FamilyRun]) -> Self {
+        let collect = |f: fn(&FamilyRun) -> f64| runs.iter().map(f).collect::<Vec<f64>>();
+        let sharpes = collect(|r| r.sharpe_ratio);
+
+        let fraction = |predicate: fn(&FamilyRun) -> bool| {
+            if runs.is_empty() {
+                0.0
+            } else {
+                runs.iter().filter(|r| predicate(r)).count() as f64 / runs.len() as f64
+            }
+        };
+        let profitable_fraction = fraction(|r| r.total_return > 0.0);
+        let positive_sharpe_fraction = fraction(|r| r.sharpe_ratio > 0.0);
+
+        let sharpe_confidence_interval = (sharpes.len() >= 2)
+            .then(|| StatisticalAnalyzer::confidence_interval(&sharpes, 0.95));
+
+        let verdict = match &sharpe_confidence_interval {
+            _ if runs.len() < MIN_RUNS_FOR_VERDICT => RobustnessVerdict::Inconclusive,
+            Some(ci) if ci.lower_bound > 0.0 && positive_sharpe_fraction >= ROBUST_POSITIVE_FRACTION => {
+                RobustnessVerdict::Robust
+            }
+            _ => RobustnessVerdict::Fragile,
+        };
+
+        let distinct_periods = runs.iter()
+            .map(|r| (r.start_date.as_str(), r.end_date.as_str()))
+            .collect::<HashSet<_>>()
+            .len();
+
+        Self {
+            strategy_id: strategy_id.into(),
+            runs: runs.len(),
+            distinct_periods,
+            sharpe_ratio: MetricDistribution::from_values(&sharpes),
+            max_drawdown: MetricDistribution::from_values(&collect(|r| r.max_drawdown)),
+            total_return: MetricDistribution::from_values(&collect(|r| r.total_return)),
+            trade_count: MetricDistribution::from_values(&collect(|r| r.total_trades as f64)),
+            profitable_fraction,
+            positive_sharpe_fraction,
+            sharpe_confidence_interval,
+            verdict,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(sharpe: f64, total_return: f64, period: usize) -> FamilyRun {
+        FamilyRun {
+            run_id: format!("run-{}-{}", period, sharpe),
+            start_date: format!("2024-0{}-01", period),
+            end_date: format!("2024-0{}-28", period),
+            total_return,
+            sharpe_ratio: sharpe,
+            max_drawdown: -5.0,
+            total_trades: 100,
+        }
+    }
+
+    #[test]
+    fn test_distribution_percentiles() {
+        let values: Vec<f64> = (0..=100).map(|v| v as f64).collect();
+        let distribution = MetricDistribution::from_values(&values);
+
+        assert_eq!(distribution.count, 101);
+        assert_eq!(distribution.median, 50.0);
+        assert_eq!(distribution.p05, 5.0);
+        assert_eq!(distribution.max, 100.0);
+    }
+
+    #[test]
+    fn test_robust_family() {
+        let runs: Vec<_> = (1..=6).map(|i| run(1.0 + i as f64 * 0.1, 10.0, i)).collect();
+        let report = StrategyFamilyReport::from_runs("s1", &runs);
+
+        assert_eq!(report.distinct_periods, 6);
+        assert_eq!(report.positive_sharpe_fraction, 1.0);
+        assert_eq!(report.verdict, RobustnessVerdict::Robust);
+    }
+
+    #[test]
+    fn test_fragile_and_inconclusive_families() {
+        let mixed: Vec<_> = [2.0, -1.5, 0.3, -0.8, 1.1, -1.2].iter()
+            .enumerate()
+            .map(|(i, s)| run(*s, *s, i + 1))
+            .collect();
+        assert_eq!(StrategyFamilyReport::from_runs("s2", &mixed).verdict, RobustnessVerdict::Fragile);
+
+        let few = vec![run(2.0, 5.0, 1), run(2.1, 5.0, 2)];
+        assert_eq!(StrategyFamilyReport::from_runs("s3", &few).verdict, RobustnessVerdict::Inconclusive);
+    }
+}
@@ -0,0 +1,345 @@
+//! Behavior conditioned on recent results
+//!
+//! Looks at whether a strategy's next trade depends on how the last ones
+//! went: expectancy after a run of consecutive losses or wins, and trades
+//! taken while in drawdown against those taken at an equity high. It then
+//! replays the trade sequence under "stop for the session after N
+//! consecutive losses" rules to show whether such a meta-rule would have
+//! helped or only cut profitable trades.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::cost_model::{round_trips, CostModelConfig};
+use crate::backtesting::metrics::TradeRecord;
+use crate::timestamp::{SessionCalendar, Timestamp};
+
+/// Streak lengths and sample sizes the analysis works with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreakConfig {
+    /// Longest streak given its own bucket; longer streaks count toward it.
+    /// Stop rules are evaluated for every length up to this one
+    #[serde(default = "default_max_streak")]
+    pub max_streak: usize,
+    /// Trades a bucket or rule needs before it feeds a recommendation
+    #[serde(default = "default_min_samples")]
+    pub min_samples: usize,
+}
+
+fn default_max_streak() -> usize {
+    5
+}
+
+fn default_min_samples() -> usize {
+    20
+}
+
+impl Default for StreakConfig {
+    fn default() -> Self {
+        Self {
+            max_streak: default_max_streak(),
+            min_samples: default_min_samples(),
+        }
+    }
+}
+
+/// Outcomes of a group of trades
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TradeGroup {
+    pub trades: usize,
+    pub net_pnl: f64,
+    pub expectancy: f64,
+    pub win_rate: f64,
+}
+
+impl TradeGroup {
+    fn from_pnls(pnls: impl IntoIterator<Item = f64>) -> Self {
+        let (mut trades, mut wins, mut net_pnl) = (0usize, 0usize, 0.0);
+        for pnl in pnls {
+            trades += 1;
+            net_pnl += pnl;
+            if pnl > 0.0 {
+                wins += 1;
+            }
+        }
+        if trades == 0 {
+            return Self::default();
+        }
+        Self {
+            trades,
+            net_pnl,
+            expectancy: net_pnl / trades as f64,
+            win_rate: wins as f64 / trades as f64,
+        }
+    }
+}
+
+/// Trades that directly followed a streak of `length` results of one kind;
+/// the last bucket also holds longer streaks
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StreakBucket {
+    pub length: usize,
+    pub next_trade: TradeGroup,
+}
+
+/// The trade sequence replayed under a stop-after-losses rule
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StopRuleOutcome {
+    /// Consecutive losses after which trading stops for the session
+    pub after_losses: usize,
+    pub trades_taken: usize,
+    pub trades_skipped: usize,
+    /// Net P&L of the trades the rule would have skipped
+    pub skipped_pnl: f64,
+    pub net_pnl: f64,
+    pub max_drawdown: f64,
+    /// Higher net P&L and no deeper drawdown than trading every signal
+    pub improves: bool,
+}
+
+/// How a strategy performs depending on its recent results
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreakReport {
+    pub config: StreakConfig,
+    pub all_trades: TradeGroup,
+    pub max_drawdown: f64,
+    pub longest_win_streak: usize,
+    pub longest_loss_streak: usize,
+    pub after_losses: Vec<StreakBucket>,
+    pub after_wins: Vec<StreakBucket>,
+    /// Trades opened with equity at its high so far
+    pub at_equity_high: TradeGroup,
+    /// Trades opened with equity below its high so far
+    pub in_drawdown: TradeGroup,
+    pub stop_rules: Vec<StopRuleOutcome>,
+}
+
+impl StreakReport {
+    /// Analyze the round trips of a fill ledger
+    pub fn from_ledger(
+        trades: &[TradeRecord],
+        cost: &CostModelConfig,
+        calendar: &SessionCalendar,
+        config: &StreakConfig,
+    ) -> Self {
+        Self::from_round_trips(&round_trips(trades, cost), calendar, config)
+    }
+
+    /// Analyze closed trades given as close time and net P&L, in order of close
+    pub fn from_round_trips(
+        trips: &[(DateTime<Utc>, Decimal)],
+        calendar: &SessionCalendar,
+        config: &StreakConfig,
+    ) -> Self {
+        let max_streak = config.max_streak.max(1);
+        let pnls: Vec<f64> = trips.iter().map(|(_, pnl)| pnl.to_f64().unwrap_or(0.0)).collect();
+        let sessions: Vec<NaiveDate> = trips.iter()
+            .map(|(closed_at, _)| {
+                let closed_at = Timestamp::from(*closed_at);
+                calendar.trading_date(closed_at).unwrap_or_else(|| closed_at.exchange_date())
+            })
+            .collect();
+
+        let mut after_losses = vec![Vec::new(); max_streak];
+        let mut after_wins = vec![Vec::new(); max_streak];
+        let (mut at_equity_high, mut in_drawdown) = (Vec::new(), Vec::new());
+        let (mut losses, mut wins) = (0usize, 0usize);
+        let (mut longest_loss_streak, mut longest_win_streak) = (0usize, 0usize);
+        let (mut equity, mut peak) = (0.0f64, 0.0f64);
+
+        for &pnl in &pnls {
+            if losses > 0 {
+                after_losses[losses.min(max_streak) - 1].push(pnl);
+            }
+            if wins > 0 {
+                after_wins[wins.min(max_streak) - 1].push(pnl);
+            }
+            if equity >= peak {
+                at_equity_high.push(pnl);
+            } else {
+                in_drawdown.push(pnl);
+            }
+
+            equity += pnl;
+            peak = peak.max(equity);
+            if pnl > 0.0 {
+                wins += 1;
+                losses = 0;
+            } else if pnl < 0.0 {
+                losses += 1;
+                wins = 0;
+            }
+            longest_loss_streak = longest_loss_streak.max(losses);
+            longest_win_streak = longest_win_streak.max(wins);
+        }
+
+        let buckets = |groups: Vec<Vec<f64>>| -> Vec<StreakBucket> {
+            groups.into_iter()
+                .enumerate()
+                .map(|(i, pnls)| StreakBucket { length: i + 1, next_trade: TradeGroup::from_pnls(pnls) })
+                .collect()
+        };
+
+        let all_trades = TradeGroup::from_pnls(pnls.iter().copied());
+        let max_drawdown = max_drawdown(pnls.iter().copied());
+        let stop_rules = (1..=max_streak)
+            .map(|after| stop_rule(&pnls, &sessions, after, all_trades.net_pnl, max_drawdown))
+            .collect();
+
+        Self {
+            config: config.clone(),
+            all_trades,
+            max_drawdown,
+            longest_win_streak,
+            longest_loss_streak,
+            after_losses: buckets(after_losses),
+            after_wins: buckets(after_wins),
+            at_equity_high: TradeGroup::from_pnls(at_equity_high),
+            in_drawdown: TradeGroup::from_pnls(in_drawdown),
+            stop_rules,
+        }
+    }
+
+    /// The stop rule with the highest net P&L among those that improve on
+    /// trading every signal and skip enough trades to be judged
+    pub fn best_stop_rule(&self) -> Option<&StopRuleOutcome> {
+        self.stop_rules.iter()
+            .filter(|rule| rule.improves && rule.trades_skipped >= self.config.min_samples)
+            .max_by(|a, b| a.net_pnl.total_cmp(&b.net_pnl))
+    }
+
+    /// Suggestions for the report where recent results predict the next trade
+    pub fn recommendations(&self) -> Vec<String> {
+        let mut recommendations = Vec::new();
+        let min_samples = self.config.min_samples;
+        if self.all_trades.trades < min_samples {
+            return recommendations;
+        }
+
+        if let Some(bucket) = self.after_losses.iter().rev().find(|b| b.next_trade.trades >= min_samples) {
+            if bucket.next_trade.expectancy < 0.0 && self.all_trades.expectancy > 0.0 {
+                recommendations.push(format!(
+                    "Trades after {} or more consecutive losses average {:.2} against {:.2} overall ({} trades)",
+                    bucket.length, bucket.next_trade.expectancy, self.all_trades.expectancy, bucket.next_trade.trades
+                ));
+            }
+        }
+
+        if self.in_drawdown.trades >= min_samples
+            && self.at_equity_high.trades >= min_samples
+            && self.in_drawdown.expectancy < 0.0
+            && self.at_equity_high.expectancy > 0.0
+        {
+            recommendations.push(format!(
+                "Trades taken in drawdown average {:.2} against {:.2} at equity highs. Consider reducing size while in drawdown",
+                self.in_drawdown.expectancy, self.at_equity_high.expectancy
+            ));
+        }
+
+        match self.best_stop_rule() {
+            Some(rule) => recommendations.push(format!(
+                "Stopping for the session after {} would have skipped {} trades, raising net P&L by {:.2} and cutting max drawdown from {:.2} to {:.2}",
+                match rule.after_losses {
+                    1 => "a loss".to_string(),
+                    n => format!("{} consecutive losses", n),
+                },
+                rule.trades_skipped, rule.net_pnl - self.all_trades.net_pnl,
+                self.max_drawdown, rule.max_drawdown
+            )),
+            None if self.longest_loss_streak >= 3 => recommendations.push(
+                "Loss streaks don't predict worse trades; a stop-after-losses rule would not have helped".to_string()
+            ),
+            None => {}
+        }
+
+        recommendations
+    }
+}
+
+/// Replay `pnls`, skipping the rest of a session once `after` losses in a
+/// row have been taken in it
+fn stop_rule(pnls: &[f64], sessions: &[NaiveDate], after: usize, net_pnl: f64, max_dd: f64) -> StopRuleOutcome {
+    let mut taken = Vec::new();
+    let (mut skipped, mut skipped_pnl) = (0usize, 0.0);
+    let mut session = None;
+    let mut losses = 0usize;
+
+    for (&pnl, &date) in pnls.iter().zip(sessions) {
+        if session != Some(date) {
+            session = Some(date);
+            losses = 0;
+        }
+        if losses >= after {
+            skipped += 1;
+            skipped_pnl += pnl;
+            continue;
+        }
+        taken.push(pnl);
+        if pnl < 0.0 {
+            losses += 1;
+        } else if pnl > 0.0 {
+            losses = 0;
+        }
+    }
+
+    let outcome = TradeGroup::from_pnls(taken.iter().copied());
+    let drawdown = max_drawdown(taken);
+    StopRuleOutcome {
+        after_losses: after,
+        trades_taken: outcome.trades,
+        trades_skipped: skipped,
+        skipped_pnl,
+        net_pnl: outcome.net_pnl,
+        max_drawdown: drawdown,
+        improves: outcome.net_pnl > net_pnl && drawdown <= max_dd,
+    }
+}
+
+/// Deepest fall of cumulative P&L from its high, starting from zero
+fn max_drawdown(pnls: impl IntoIterator<Item = f64>) -> f64 {
+    let (mut equity, mut peak, mut deepest) = (0.0f64, 0.0f64, 0.0f64);
+    for pnl in pnls {
+        equity += pnl;
+        peak = peak.max(equity);
+        deepest = deepest.max(peak - equity);
+    }
+    deepest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_tilting_strategy_is_helped_by_stopping_after_losses() {
+        // Each session opens with a winner, then two losers are followed by
+        // a run of larger losers
+        let mut trips = Vec::new();
+        for day in 0..10u32 {
+            let open = Utc.with_ymd_and_hms(2024, 3, 4, 15, 0, 0).unwrap() + chrono::Duration::days(day as i64 % 5)
+                + chrono::Duration::weeks(day as i64 / 5);
+            for (i, pnl) in [30i64, -5, -5, -10, -10].into_iter().enumerate() {
+                trips.push((open + chrono::Duration::minutes(i as i64 * 10), Decimal::from(pnl)));
+            }
+        }
+
+        let config = StreakConfig { max_streak: 3, min_samples: 10 };
+        let report = StreakReport::from_round_trips(&trips, &SessionCalendar::default(), &config);
+        assert_eq!(report.all_trades.trades, 50);
+        assert_eq!(report.longest_loss_streak, 4);
+        // After two losses the next trade always loses 10
+        assert!((report.after_losses[1].next_trade.expectancy + 10.0).abs() < 1e-9);
+        assert_eq!(report.at_equity_high.trades + report.in_drawdown.trades, 50);
+
+        let stop_after_two = &report.stop_rules[1];
+        assert_eq!(stop_after_two.trades_skipped, 20);
+        assert!((stop_after_two.skipped_pnl + 200.0).abs() < 1e-9);
+        assert!(stop_after_two.improves);
+        // Stopping at the first loss skips even more of the losers
+        assert_eq!(report.best_stop_rule().map(|rule| rule.after_losses), Some(1));
+        assert!(report.recommendations().iter().any(|r| r.contains("for the session after a loss")));
+    }
+}
@@ -0,0 +1,148 @@
+//! Restriction of ad-hoc SQL to single read-only queries
+//!
+//! Queries run on a read-only connection with external access disabled, so
+//! this check is the first line rather than the only one: it turns away
+//! anything that is not a single `SELECT` or `WITH` statement, and the
+//! statements and table functions that reach outside the mirrored tables,
+//! with an error that says why.
+
+/// Statements that change the database or its configuration
+const FORBIDDEN_KEYWORDS: &[&str] = &[
+    "alter", "attach", "call", "checkpoint", "copy", "create", "delete", "detach", "drop", "export",
+    "import", "insert", "install", "load", "pragma", "reset", "set", "truncate", "update", "use", "vacuum",
+];
+
+/// Table functions that read files or the environment
+const FORBIDDEN_FUNCTIONS: &[&str] = &[
+    "getenv", "glob", "parquet_metadata", "parquet_schema", "read_blob", "read_csv", "read_csv_auto",
+    "read_json", "read_json_auto", "read_ndjson", "read_parquet", "read_text", "sniff_csv",
+];
+
+/// Reasons a query is turned away
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum QueryRejection {
+    #[error("Query is empty")]
+    Empty,
+    #[error("Only a single statement may be run")]
+    MultipleStatements,
+    #[error("Only SELECT and WITH queries may be run")]
+    NotAQuery,
+    #[error("'{0}' is not allowed in analytics queries")]
+    Forbidden(String),
+    #[error("Unterminated quote")]
+    UnterminatedQuote,
+}
+
+/// Check `sql` and return it without its trailing semicolon
+pub fn check_query(sql: &str) -> Result<&str, QueryRejection> {
+    let sql = sql.trim().trim_end_matches(';').trim_end();
+    let words = words(sql)?;
+    let Some(first) = words.first() else {
+        return Err(QueryRejection::Empty);
+    };
+    if first != "select" && first != "with" {
+        return Err(QueryRejection::NotAQuery);
+    }
+    for word in &words {
+        if word == ";" {
+            return Err(QueryRejection::MultipleStatements);
+        }
+        if FORBIDDEN_KEYWORDS.contains(&word.as_str()) || FORBIDDEN_FUNCTIONS.contains(&word.as_str()) {
+            return Err(QueryRejection::Forbidden(word.clone()));
+        }
+    }
+    Ok(sql)
+}
+
+/// Lowercased identifiers and keywords of `sql`, plus any `;`, skipping
+/// string literals and comments; quoted identifiers are kept as written
+fn words(sql: &str) -> Result<Vec<String>, QueryRejection> {
+    let mut words = Vec::new();
+    let mut chars = sql.chars().peekable();
+    let mut current = String::new();
+    let flush = |current: &mut String, words: &mut Vec<String>| {
+        if !current.is_empty() {
+            words.push(std::mem::take(current).to_lowercase());
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                flush(&mut current, &mut words);
+                skip_quoted(&mut chars, '\'')?;
+            }
+            '"' => {
+                flush(&mut current, &mut words);
+                skip_quoted(&mut chars, '"')?;
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                flush(&mut current, &mut words);
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                flush(&mut current, &mut words);
+                chars.next();
+                let mut previous = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if previous == '*' => break,
+                        Some(c) => previous = c,
+                        None => return Err(QueryRejection::UnterminatedQuote),
+                    }
+                }
+            }
+            ';' => {
+                flush(&mut current, &mut words);
+                words.push(";".to_string());
+            }
+            c if c.is_alphanumeric() || c == '_' => current.push(c),
+            _ => flush(&mut current, &mut words),
+        }
+    }
+    flush(&mut current, &mut words);
+    Ok(words)
+}
+
+/// Advance past a quoted section, where a doubled quote is an escaped one
+fn skip_quoted(chars: &mut std::iter::Peekable<std::str::Chars<'_>>, quote: char) -> Result<(), QueryRejection> {
+    while let Some(c) = chars.next() {
+        if c == quote {
+            if chars.peek() == Some(&quote) {
+                chars.next();
+            } else {
+                return Ok(());
+            }
+        }
+    }
+    Err(QueryRejection::UnterminatedQuote)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_query() {
+        assert_eq!(
+            check_query("SELECT side, count(*) FROM trades GROUP BY side;\n"),
+            Ok("SELECT side, count(*) FROM trades GROUP BY side")
+        );
+        assert!(check_query("with t as (select 1) select * from t").is_ok());
+        // Keywords inside literals and comments are data, not statements
+        assert!(check_query("SELECT 'drop table trades; --' AS note -- delete\n FROM trades").is_ok());
+
+        assert_eq!(check_query("  ; "), Err(QueryRejection::Empty));
+        assert_eq!(check_query("DELETE FROM trades"), Err(QueryRejection::NotAQuery));
+        assert_eq!(check_query("SELECT 1; DROP TABLE trades"), Err(QueryRejection::MultipleStatements));
+        assert_eq!(
+            check_query("SELECT * FROM read_csv('/etc/passwd')"),
+            Err(QueryRejection::Forbidden("read_csv".to_string()))
+        );
+        assert_eq!(check_query("SELECT 'open"), Err(QueryRejection::UnterminatedQuote));
+    }
+}
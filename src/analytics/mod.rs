@@ -0,0 +1,14 @@
+//! Embedded analytics database
+//!
+//! Mirrors trade ledgers and optimization evaluations into DuckDB files so
+//! power users can run ad-hoc SQL aggregations over them without standing
+//! up separate analytics infrastructure. Queries are restricted to single
+//! read-only statements. Enabled by the `analytics` feature.
+
+pub mod guard;
+pub mod store;
+
+pub use guard::{check_query, QueryRejection};
+pub use store::{
+    AnalyticsError, AnalyticsStore, LedgerMirror, QueryResult, SyncReport, DEFAULT_ROW_LIMIT, MAX_ROW_LIMIT,
+};
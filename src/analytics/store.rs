@@ -0,0 +1,306 @@
+//! DuckDB files mirroring trade ledgers and optimization evaluations
+//!
+//! Each workspace gets its own database file under the store's directory,
+//! so a query can only ever see the workspace it was run for. A sync
+//! rebuilds the mirrored tables from scratch inside one transaction; the
+//! in-memory ledgers and the evaluation log stay the source of truth.
+//!
+//! | table         | columns                                                                   |
+//! |---------------|---------------------------------------------------------------------------|
+//! | `trades`      | result_id, strategy_id, ts, side, quantity, price, commission, slippage   |
+//! | `evaluations` | optimization_id, objective_value, total_pnl, total_trades, win_rate,      |
+//! |               | sharpe_ratio, max_drawdown, profit_factor, parameters (JSON), evaluated_at |
+
+use chrono::{DateTime, Utc};
+use duckdb::types::{TimeUnit, ValueRef};
+use duckdb::{params, AccessMode, Config, Connection};
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::guard::{check_query, QueryRejection};
+use crate::backtesting::metrics::TradeRecord;
+use crate::optimization::{EvalStoreError, EvaluationRecord};
+use crate::strategy::OrderSide;
+
+/// Rows returned when a query does not ask for a limit
+pub const DEFAULT_ROW_LIMIT: usize = 1_000;
+
+/// Most rows a single query may return
+pub const MAX_ROW_LIMIT: usize = 100_000;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS trades (
+        result_id VARCHAR NOT NULL,
+        strategy_id VARCHAR NOT NULL,
+        ts TIMESTAMPTZ NOT NULL,
+        side VARCHAR NOT NULL,
+        quantity INTEGER NOT NULL,
+        price DOUBLE NOT NULL,
+        commission DOUBLE NOT NULL,
+        slippage DOUBLE NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS evaluations (
+        optimization_id VARCHAR NOT NULL,
+        objective_value DOUBLE NOT NULL,
+        total_pnl DOUBLE NOT NULL,
+        total_trades INTEGER NOT NULL,
+        win_rate DOUBLE NOT NULL,
+        sharpe_ratio DOUBLE NOT NULL,
+        max_drawdown DOUBLE NOT NULL,
+        profit_factor DOUBLE NOT NULL,
+        parameters JSON NOT NULL,
+        evaluated_at TIMESTAMPTZ NOT NULL
+    );
+";
+
+/// Errors raised by the analytics store
+#[derive(Debug, thiserror::Error)]
+pub enum AnalyticsError {
+    #[error("Invalid workspace id: {0}")]
+    InvalidWorkspace(String),
+    #[error("Workspace {0} has not been synced")]
+    NotSynced(String),
+    #[error("Query rejected: {0}")]
+    Rejected(#[from] QueryRejection),
+    #[error("Evaluation store error: {0}")]
+    Evaluations(#[from] EvalStoreError),
+    #[error("DuckDB error: {0}")]
+    Database(#[from] duckdb::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// A backtest's fills, tagged with the result and strategy they belong to
+#[derive(Debug, Clone)]
+pub struct LedgerMirror<'a> {
+    pub result_id: &'a str,
+    pub strategy_id: &'a str,
+    pub trades: &'a [TradeRecord],
+}
+
+/// Rows copied by a sync
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncReport {
+    pub workspace_id: String,
+    pub ledgers: usize,
+    pub trades: usize,
+    pub optimizations: usize,
+    pub evaluations: usize,
+    pub synced_at: DateTime<Utc>,
+}
+
+/// Column names and JSON-encoded rows of a query
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    /// Whether rows beyond the limit were dropped
+    pub truncated: bool,
+}
+
+/// Per-workspace DuckDB mirrors rooted at one directory
+#[derive(Debug, Clone)]
+pub struct AnalyticsStore {
+    dir: PathBuf,
+    /// DuckDB allows one writer per file; syncs and queries take turns
+    lock: Arc<Mutex<()>>,
+}
+
+impl AnalyticsStore {
+    /// Open (creating if needed) a store rooted at `dir`
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, AnalyticsError> {
+        std::fs::create_dir_all(dir.as_ref())?;
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+            lock: Arc::new(Mutex::new(())),
+        })
+    }
+
+    fn path(&self, workspace_id: &str) -> Result<PathBuf, AnalyticsError> {
+        let valid = !workspace_id.is_empty()
+            && workspace_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(AnalyticsError::InvalidWorkspace(workspace_id.to_string()));
+        }
+        Ok(self.dir.join(format!("{}.duckdb", workspace_id)))
+    }
+
+    /// Replace the workspace's mirrored tables with `ledgers` and the
+    /// evaluations of each `(optimization id, records)` pair
+    pub fn sync(
+        &self,
+        workspace_id: &str,
+        ledgers: &[LedgerMirror<'_>],
+        evaluations: &[(String, Vec<EvaluationRecord>)],
+    ) -> Result<SyncReport, AnalyticsError> {
+        let path = self.path(workspace_id)?;
+        let _guard = self.lock.lock().unwrap();
+        let mut conn = Connection::open(&path)?;
+        conn.execute_batch(SCHEMA)?;
+
+        let tx = conn.transaction()?;
+        tx.execute_batch("DELETE FROM trades; DELETE FROM evaluations;")?;
+        let mut report = SyncReport {
+            workspace_id: workspace_id.to_string(),
+            synced_at: Utc::now(),
+            ..Default::default()
+        };
+        {
+            let mut appender = tx.appender("trades")?;
+            for ledger in ledgers {
+                for trade in ledger.trades {
+                    let side = match trade.side {
+                        OrderSide::Buy => "buy",
+                        OrderSide::Sell => "sell",
+                    };
+                    appender.append_row(params![
+                        ledger.result_id,
+                        ledger.strategy_id,
+                        trade.timestamp,
+                        side,
+                        trade.quantity,
+                        trade.price.to_f64().unwrap_or(0.0),
+                        trade.commission.to_f64().unwrap_or(0.0),
+                        trade.slippage.to_f64().unwrap_or(0.0),
+                    ])?;
+                }
+                report.ledgers += 1;
+                report.trades += ledger.trades.len();
+            }
+            appender.flush()?;
+        }
+        {
+            let mut appender = tx.appender("evaluations")?;
+            for (optimization_id, records) in evaluations {
+                for record in records {
+                    appender.append_row(params![
+                        optimization_id,
+                        record.objective_value,
+                        record.metrics.total_pnl.to_f64().unwrap_or(0.0),
+                        record.metrics.total_trades,
+                        record.metrics.win_rate,
+                        record.metrics.sharpe_ratio,
+                        record.metrics.max_drawdown.to_f64().unwrap_or(0.0),
+                        record.metrics.profit_factor,
+                        serde_json::to_string(&record.parameters.parameters)?,
+                        record.evaluated_at,
+                    ])?;
+                }
+                report.optimizations += 1;
+                report.evaluations += records.len();
+            }
+            appender.flush()?;
+        }
+        tx.commit()?;
+        Ok(report)
+    }
+
+    /// Run a restricted read-only query against the workspace's mirror,
+    /// returning at most `limit` rows
+    pub fn query(&self, workspace_id: &str, sql: &str, limit: usize) -> Result<QueryResult, AnalyticsError> {
+        let sql = check_query(sql)?;
+        let path = self.path(workspace_id)?;
+        if !path.exists() {
+            return Err(AnalyticsError::NotSynced(workspace_id.to_string()));
+        }
+        let limit = limit.clamp(1, MAX_ROW_LIMIT);
+
+        let _guard = self.lock.lock().unwrap();
+        let config = Config::default()
+            .access_mode(AccessMode::ReadOnly)?
+            .enable_external_access(false)?
+            .enable_autoload_extension(false)?;
+        let conn = Connection::open_with_flags(&path, config)?;
+        // One row past the limit tells whether anything was cut off
+        let mut statement = conn.prepare(&format!("SELECT * FROM ({}) LIMIT {}", sql, limit + 1))?;
+        let mut rows = statement.query([])?;
+        let columns = rows.as_ref().map(|s| s.column_names()).unwrap_or_default();
+
+        let mut result = QueryResult { columns, rows: Vec::new(), truncated: false };
+        while let Some(row) = rows.next()? {
+            if result.rows.len() == limit {
+                result.truncated = true;
+                break;
+            }
+            let values = (0..result.columns.len())
+                .map(|i| row.get_ref(i).map(json_value))
+                .collect::<Result<Vec<_>, _>>()?;
+            result.rows.push(values);
+        }
+        Ok(result)
+    }
+}
+
+/// JSON form of a DuckDB value; timestamps become RFC 3339 strings and
+/// types without a natural JSON form are rendered as text
+fn json_value(value: ValueRef<'_>) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Boolean(b) => Value::from(b),
+        ValueRef::TinyInt(v) => Value::from(v),
+        ValueRef::SmallInt(v) => Value::from(v),
+        ValueRef::Int(v) => Value::from(v),
+        ValueRef::BigInt(v) => Value::from(v),
+        ValueRef::HugeInt(v) => i64::try_from(v).map(Value::from).unwrap_or_else(|_| Value::from(v.to_string())),
+        ValueRef::UTinyInt(v) => Value::from(v),
+        ValueRef::USmallInt(v) => Value::from(v),
+        ValueRef::UInt(v) => Value::from(v),
+        ValueRef::UBigInt(v) => Value::from(v),
+        ValueRef::Float(v) => Value::from(v),
+        ValueRef::Double(v) => Value::from(v),
+        ValueRef::Decimal(v) => v.to_f64().map(Value::from).unwrap_or(Value::Null),
+        ValueRef::Timestamp(unit, v) => DateTime::from_timestamp_micros(unit.to_micros(v))
+            .map(|t| Value::from(t.to_rfc3339()))
+            .unwrap_or(Value::Null),
+        ValueRef::Text(bytes) => Value::from(String::from_utf8_lossy(bytes).into_owned()),
+        ValueRef::Date32(days) => chrono::NaiveDate::from_num_days_from_ce_opt(days + 719_163)
+            .map(|d| Value::from(d.to_string()))
+            .unwrap_or(Value::Null),
+        ValueRef::Time64(TimeUnit::Microsecond, micros) => Value::from(micros),
+        other => Value::from(format!("{:?}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_sync_and_query() {
+        let dir = std::env::temp_dir().join(format!("analytics-{}", uuid::Uuid::new_v4()));
+        let store = AnalyticsStore::open(&dir).unwrap();
+        let trade = |side, price: i64| TradeRecord {
+            timestamp: Utc::now(),
+            side,
+            quantity: 2,
+            price: Decimal::from(price),
+            commission: Decimal::new(125, 2),
+            slippage: Decimal::ZERO,
+            tag: None,
+        };
+        let trades = vec![trade(OrderSide::Buy, 18_000), trade(OrderSide::Sell, 18_010)];
+        let ledgers = [LedgerMirror { result_id: "r1", strategy_id: "s1", trades: &trades }];
+
+        let report = store.sync("team-a", &ledgers, &[]).unwrap();
+        assert_eq!((report.ledgers, report.trades), (1, 2));
+
+        let result = store
+            .query("team-a", "SELECT side, sum(price * quantity) AS notional FROM trades GROUP BY side ORDER BY side;", 10)
+            .unwrap();
+        assert_eq!(result.columns, vec!["side", "notional"]);
+        assert_eq!(result.rows[0], vec![serde_json::json!("buy"), serde_json::json!(36_000.0)]);
+
+        let limited = store.query("team-a", "SELECT * FROM trades", 1).unwrap();
+        assert!(limited.truncated);
+        assert!(store.query("team-a", "SELECT * FROM trades; DELETE FROM trades", 10).is_err());
+        assert!(matches!(store.query("team-b", "SELECT 1", 10), Err(AnalyticsError::NotSynced(_))));
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,55 @@
+//! Access log and request spans
+//!
+//! Each request runs inside an `http_request` span tagged with its trace id,
+//! taken from the caller's `traceparent` header or started here. Handlers
+//! read the `TraceContext` from the request extensions to hand it on to the
+//! jobs they queue, and the response echoes the `traceparent` so a client can
+//! quote it when reporting a slow run. One line per request is logged under
+//! the `access` target once the response is ready.
+
+use axum::{
+    http::{HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use std::time::Instant;
+use tracing::{field, info, info_span, Instrument};
+
+use crate::telemetry::{self, TraceContext, TRACEPARENT_HEADER};
+
+pub async fn trace_requests<B>(mut request: Request<B>, next: Next<B>) -> Response {
+    let parent = request.headers().get(TRACEPARENT_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(TraceContext::parse);
+    let context = parent.as_ref().map_or_else(TraceContext::new_root, TraceContext::child);
+
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let span = info_span!(
+        "http_request",
+        method = %method,
+        path = %path,
+        trace_id = %context.trace_id,
+        status = field::Empty,
+        latency_ms = field::Empty,
+    );
+    if let Some(parent) = &parent {
+        telemetry::attach(&span, parent);
+    }
+    // With span export the request span itself is the parent jobs continue from
+    let context = span.in_scope(TraceContext::current).unwrap_or(context);
+    request.extensions_mut().insert(context.clone());
+
+    let started = Instant::now();
+    let mut response = next.run(request).instrument(span.clone()).await;
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+    let status = response.status().as_u16();
+    span.record("status", status);
+    span.record("latency_ms", latency_ms);
+    span.in_scope(|| info!(target: "access", "{} {} {} {:.1}ms", method, path, status, latency_ms));
+
+    if let Ok(value) = HeaderValue::from_str(&context.to_traceparent()) {
+        response.headers_mut().insert(TRACEPARENT_HEADER, value);
+    }
+    response
+}
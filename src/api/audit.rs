@@ -0,0 +1,65 @@
+//! Audit trail of sensitive API actions
+//!
+//! Records who did what and why for actions that bypass or change policy,
+//! such as validating a strategy over failed promotion gates.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+
+/// A recorded action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub id: String,
+    pub at: DateTime<Utc>,
+    pub workspace_id: String,
+    /// Calling user, when the request identified one
+    pub actor: Option<String>,
+    /// What was done, e.g. `strategy.transition`
+    pub action: String,
+    /// Id of the record acted on
+    pub subject: String,
+    pub details: serde_json::Value,
+}
+
+/// Append-only log of audit events
+#[derive(Debug, Clone, Default)]
+pub struct AuditTrail {
+    events: Arc<RwLock<Vec<AuditEvent>>>,
+}
+
+impl AuditTrail {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(
+        &self,
+        workspace_id: &str,
+        actor: Option<&str>,
+        action: &str,
+        subject: &str,
+        details: serde_json::Value,
+    ) -> AuditEvent {
+        let event = AuditEvent {
+            id: Uuid::new_v4().to_string(),
+            at: Utc::now(),
+            workspace_id: workspace_id.to_string(),
+            actor: actor.map(str::to_string),
+            action: action.to_string(),
+            subject: subject.to_string(),
+            details,
+        };
+        self.events.write().unwrap().push(event.clone());
+        event
+    }
+
+    /// Events of a workspace, newest first
+    pub fn list(&self, workspace_id: &str) -> Vec<AuditEvent> {
+        self.events.read().unwrap().iter().rev()
+            .filter(|event| event.workspace_id == workspace_id)
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,111 @@
+//! Caller identity from API keys
+//!
+//! Workspace membership is checked against the `x-user-id` header, so that
+//! header has to come from the server rather than the client. Callers
+//! present an API key in `x-api-key`; the key's user replaces any
+//! `x-user-id` the client sent. Requests without a key are anonymous, which
+//! only the default workspace admits.
+
+use axum::{
+    extract::State,
+    http::{HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+
+use super::handlers::USER_HEADER;
+use super::ApiState;
+
+/// Header carrying the caller's API key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// API keys and the users they identify
+///
+/// Only digests of the keys are held, so lookups compare digests and never
+/// the keys themselves.
+#[derive(Debug, Clone, Default)]
+pub struct ApiKeys {
+    users: Arc<HashMap<[u8; 32], String>>,
+}
+
+impl ApiKeys {
+    /// Keys from `(user, key)` pairs; users that cannot be sent as a header
+    /// value are skipped
+    pub fn new(keys: impl IntoIterator<Item = (String, String)>) -> Self {
+        let users = keys.into_iter()
+            .filter(|(user, key)| {
+                let valid = !user.is_empty() && !key.is_empty() && HeaderValue::from_str(user).is_ok();
+                if !valid {
+                    warn!("Skipping API key of invalid user {:?}", user);
+                }
+                valid
+            })
+            .map(|(user, key)| (Sha256::digest(key.as_bytes()).into(), user))
+            .collect();
+        Self { users: Arc::new(users) }
+    }
+
+    /// Keys from `STRATEGY_LAB_API_KEYS`, a comma separated `user=key` list
+    pub fn from_env() -> Self {
+        let keys = std::env::var("STRATEGY_LAB_API_KEYS").unwrap_or_default();
+        let keys = Self::new(keys.split(',')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(user, key)| (user.trim().to_string(), key.trim().to_string())));
+        if keys.users.is_empty() {
+            warn!("STRATEGY_LAB_API_KEYS not set, every request is anonymous and limited to the default workspace");
+        }
+        keys
+    }
+
+    /// User identified by `key`
+    pub fn user(&self, key: &str) -> Option<&str> {
+        let digest: [u8; 32] = Sha256::digest(key.as_bytes()).into();
+        self.users.get(&digest).map(String::as_str)
+    }
+}
+
+/// Replace the client's `x-user-id` with the user of its API key
+///
+/// An unknown key gives 401 rather than an anonymous request, so a typo
+/// does not silently land the caller in the default workspace.
+pub async fn identify<B>(
+    State(state): State<ApiState>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let headers = request.headers_mut();
+    headers.remove(USER_HEADER);
+    if let Some(key) = headers.get(API_KEY_HEADER) {
+        let user = key.to_str().ok()
+            .and_then(|key| state.api_keys.user(key))
+            .and_then(|user| HeaderValue::from_str(user).ok());
+        match user {
+            Some(user) => {
+                headers.insert(USER_HEADER, user);
+            }
+            None => return (StatusCode::UNAUTHORIZED, "Invalid API key").into_response(),
+        }
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_identify_their_user_only() {
+        let keys = ApiKeys::new([
+            ("alice".to_string(), "k-alice".to_string()),
+            ("bad\nuser".to_string(), "k-bad".to_string()),
+        ]);
+
+        assert_eq!(keys.user("k-alice"), Some("alice"));
+        assert_eq!(keys.user("k-alic"), None);
+        assert_eq!(keys.user("k-bad"), None);
+    }
+}
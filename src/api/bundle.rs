@@ -0,0 +1,199 @@
+//! Strategy export bundles
+//!
+//! A bundle is a single JSON document holding a strategy's definition, its
+//! parameter presets, metadata of the datasets its backtests ran on and its
+//! recent results. Importing it into another Strategy Lab instance recreates
+//! the strategy under a fresh id; dataset files are not carried, so the
+//! import reports which of them the target catalog is missing.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::{BacktestResult, StrategyInfo};
+use crate::database::PresetDraft;
+use crate::subscription::DatasetEntry;
+
+/// Bundle format written by this version
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// Results included in an export, most recent first
+pub const MAX_BUNDLED_RESULTS: usize = 50;
+
+/// Errors raised when reading a bundle
+#[derive(Debug, thiserror::Error)]
+pub enum BundleError {
+    #[error("Bundle format {found} is newer than supported format {supported}")]
+    UnsupportedVersion { found: u32, supported: u32 },
+}
+
+/// A strategy with everything needed to recreate it elsewhere
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyBundle {
+    pub format_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub strategy: StrategyInfo,
+    #[serde(default)]
+    pub presets: Vec<PresetDraft>,
+    /// Catalog entries of datasets the bundled results were run on
+    #[serde(default)]
+    pub datasets: Vec<DatasetEntry>,
+    /// License notices of the bundled datasets, which travel with the bundle
+    #[serde(default)]
+    pub data_notices: Vec<String>,
+    #[serde(default)]
+    pub results: Vec<BacktestResult>,
+}
+
+impl StrategyBundle {
+    pub fn new(strategy: StrategyInfo) -> Self {
+        Self {
+            format_version: BUNDLE_FORMAT_VERSION,
+            exported_at: Utc::now(),
+            strategy,
+            presets: Vec::new(),
+            datasets: Vec::new(),
+            data_notices: Vec::new(),
+            results: Vec::new(),
+        }
+    }
+
+    pub fn with_presets(mut self, presets: Vec<PresetDraft>) -> Self {
+        self.presets = presets;
+        self
+    }
+
+    pub fn with_datasets(mut self, datasets: Vec<DatasetEntry>) -> Self {
+        let mut notices: Vec<String> = datasets.iter()
+            .filter_map(|dataset| dataset.license.as_ref().map(|license| license.notice()))
+            .collect();
+        notices.sort();
+        notices.dedup();
+        self.data_notices = notices;
+        self.datasets = datasets;
+        self
+    }
+
+    /// Keep the most recent `MAX_BUNDLED_RESULTS` of `results`, given oldest first
+    pub fn with_results(mut self, results: Vec<BacktestResult>) -> Self {
+        self.results = results.into_iter().rev().take(MAX_BUNDLED_RESULTS).collect();
+        self
+    }
+
+    /// Reject bundles written by a newer format
+    pub fn validate(&self) -> Result<(), BundleError> {
+        if self.format_version > BUNDLE_FORMAT_VERSION {
+            return Err(BundleError::UnsupportedVersion {
+                found: self.format_version,
+                supported: BUNDLE_FORMAT_VERSION,
+            });
+        }
+        Ok(())
+    }
+
+    /// The strategy and its results re-keyed for `workspace_id`
+    ///
+    /// The strategy and every result get fresh ids so an import never
+    /// collides with what the target instance already holds. Results are
+    /// returned oldest first, the order the results list is kept in.
+    pub fn rebase(&self, workspace_id: &str) -> (StrategyInfo, Vec<BacktestResult>) {
+        let strategy = StrategyInfo {
+            id: Uuid::new_v4().to_string(),
+            workspace_id: workspace_id.to_string(),
+            ..self.strategy.clone()
+        };
+
+        let results = self.results.iter().rev()
+            .map(|result| BacktestResult {
+                id: Uuid::new_v4().to_string(),
+                strategy_id: strategy.id.clone(),
+                workspace_id: workspace_id.to_string(),
+                ..result.clone()
+            })
+            .collect();
+
+        (strategy, results)
+    }
+
+    /// Ids of bundled datasets whose fingerprint differs from, or is absent
+    /// in, the target catalog
+    pub fn missing_datasets(&self, local: &HashMap<String, DatasetEntry>) -> Vec<String> {
+        self.datasets.iter()
+            .filter(|dataset| {
+                local.get(&dataset.id)
+                    .map_or(true, |entry| entry.fingerprint != dataset.fingerprint)
+            })
+            .map(|dataset| dataset.id.clone())
+            .collect()
+    }
+}
+
+/// Outcome of importing a bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportReport {
+    /// Id the strategy was created under
+    pub strategy_id: String,
+    pub presets_imported: usize,
+    pub results_imported: usize,
+    /// Bundled datasets the target catalog lacks; results built on them
+    /// cannot be reproduced until the data is ingested
+    pub missing_datasets: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::LifecycleState;
+
+    fn result(id: &str) -> BacktestResult {
+        BacktestResult {
+            id: id.to_string(),
+            strategy_id: "source".to_string(),
+            start_date: "2024-01-02".to_string(),
+            end_date: "2024-03-29".to_string(),
+            total_return: 12.0,
+            sharpe_ratio: 1.4,
+            max_drawdown: -5.0,
+            total_trades: 320,
+            workspace_id: "default".to_string(),
+            out_of_sample: false,
+            daily_returns: Default::default(),
+            code: None,
+            demo: false,
+            config: None,
+        }
+    }
+
+    #[test]
+    fn test_rebase_assigns_fresh_ids() {
+        let strategy = StrategyInfo {
+            id: "source".to_string(),
+            name: "Mean reversion".to_string(),
+            description: String::new(),
+            parameters: serde_json::json!({ "lookback": 20 }),
+            status: LifecycleState::Draft,
+            workspace_id: "default".to_string(),
+            lifecycle: Vec::new(),
+            source: Default::default(),
+            demo: false,
+        };
+        let bundle = StrategyBundle::new(strategy)
+            .with_results(vec![result("first"), result("second")]);
+        assert_eq!(bundle.results[0].id, "second");
+
+        let json = serde_json::to_string(&bundle).unwrap();
+        let bundle: StrategyBundle = serde_json::from_str(&json).unwrap();
+        bundle.validate().unwrap();
+
+        let (strategy, results) = bundle.rebase("research");
+        assert_ne!(strategy.id, "source");
+        assert_eq!(strategy.workspace_id, "research");
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.strategy_id == strategy.id && r.workspace_id == "research"));
+        assert!(results.iter().all(|r| r.id != "first" && r.id != "second"));
+
+        let future = StrategyBundle { format_version: BUNDLE_FORMAT_VERSION + 1, ..bundle };
+        assert!(future.validate().is_err());
+    }
+}
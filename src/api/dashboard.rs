@@ -0,0 +1,258 @@
+//! Job tracking behind the consolidated dashboard endpoint
+//!
+//! Handlers that start background work register it on the `JobBoard` and
+//! report progress and completion there, so `GET /api/dashboard` can return
+//! running jobs, recent completions, resources and optimization leaders in
+//! one payload instead of the frontend polling each endpoint.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, RwLock};
+
+use crate::monitoring::ResourceUsage;
+use crate::optimization::EvaluationRecord;
+use crate::workspace::{ResourceQuota, ResourceUsage as WorkspaceUsage};
+
+/// Finished jobs kept for the dashboard
+const RECENT_COMPLETIONS: usize = 20;
+
+/// Hours finished jobs are kept for reports such as the daily digest
+pub const HISTORY_HOURS: i64 = 48;
+
+/// Finished jobs kept for reports, however busy the period
+const HISTORY_LIMIT: usize = 10_000;
+
+/// Kind of background job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    Backtest,
+    Optimization,
+    CacheWarmup,
+    ReportGeneration,
+}
+
+/// How a job ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobOutcome {
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// A job still running or scheduled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveJob {
+    pub id: String,
+    pub kind: JobKind,
+    pub workspace_id: String,
+    /// Strategy or dataset the job works on
+    pub subject: String,
+    pub started_at: DateTime<Utc>,
+    /// Share done from 0 to 1, when the job reports it
+    pub progress: Option<f64>,
+    /// Latest progress note, e.g. the date a backtest has reached
+    pub detail: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A job that ended
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletedJob {
+    pub id: String,
+    pub kind: JobKind,
+    pub workspace_id: String,
+    pub subject: String,
+    pub outcome: JobOutcome,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_secs: f64,
+}
+
+/// Where a job is, whether still running or finished
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum JobStatus {
+    Active(ActiveJob),
+    Finished(CompletedJob),
+}
+
+#[derive(Debug, Default)]
+struct BoardState {
+    active: HashMap<String, ActiveJob>,
+    recent: VecDeque<CompletedJob>,
+    /// Newest first, pruned by age and count
+    history: VecDeque<CompletedJob>,
+}
+
+/// Shared record of running and recently finished jobs
+#[derive(Debug, Clone, Default)]
+pub struct JobBoard {
+    state: Arc<RwLock<BoardState>>,
+}
+
+impl JobBoard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start(&self, id: &str, kind: JobKind, workspace_id: &str, subject: impl Into<String>) {
+        let now = Utc::now();
+        self.state.write().unwrap().active.insert(id.to_string(), ActiveJob {
+            id: id.to_string(),
+            kind,
+            workspace_id: workspace_id.to_string(),
+            subject: subject.into(),
+            started_at: now,
+            progress: None,
+            detail: None,
+            updated_at: now,
+        });
+    }
+
+    /// Record progress of a running job; unknown ids are ignored
+    pub fn progress(&self, id: &str, progress: f64, detail: Option<String>) {
+        if let Some(job) = self.state.write().unwrap().active.get_mut(id) {
+            job.progress = Some(progress.clamp(0.0, 1.0));
+            job.detail = detail;
+            job.updated_at = Utc::now();
+        }
+    }
+
+    /// Move a job to the recent completions, returning whether it was active
+    pub fn finish(&self, id: &str, outcome: JobOutcome) -> bool {
+        let mut state = self.state.write().unwrap();
+        let Some(job) = state.active.remove(id) else {
+            return false;
+        };
+
+        let finished_at = Utc::now();
+        let completed = CompletedJob {
+            id: job.id,
+            kind: job.kind,
+            workspace_id: job.workspace_id,
+            subject: job.subject,
+            outcome,
+            started_at: job.started_at,
+            finished_at,
+            duration_secs: (finished_at - job.started_at).num_milliseconds() as f64 / 1000.0,
+        };
+        state.recent.push_front(completed.clone());
+        state.recent.truncate(RECENT_COMPLETIONS);
+
+        state.history.push_front(completed);
+        state.history.truncate(HISTORY_LIMIT);
+        let cutoff = finished_at - Duration::hours(HISTORY_HOURS);
+        while state.history.back().is_some_and(|job| job.finished_at < cutoff) {
+            state.history.pop_back();
+        }
+        true
+    }
+
+    /// Status of a running job or one that finished in the last two days
+    pub fn status(&self, id: &str) -> Option<JobStatus> {
+        let state = self.state.read().unwrap();
+        if let Some(job) = state.active.get(id) {
+            return Some(JobStatus::Active(job.clone()));
+        }
+        state.history.iter()
+            .find(|job| job.id == id)
+            .map(|job| JobStatus::Finished(job.clone()))
+    }
+
+    /// Active jobs of a workspace, oldest first
+    pub fn active(&self, workspace_id: &str) -> Vec<ActiveJob> {
+        let mut jobs: Vec<ActiveJob> = self.state.read().unwrap().active.values()
+            .filter(|job| job.workspace_id == workspace_id)
+            .cloned()
+            .collect();
+        jobs.sort_by_key(|job| job.started_at);
+        jobs
+    }
+
+    /// Active jobs of every workspace, oldest first
+    pub fn all_active(&self) -> Vec<ActiveJob> {
+        let mut jobs: Vec<ActiveJob> = self.state.read().unwrap().active.values().cloned().collect();
+        jobs.sort_by_key(|job| job.started_at);
+        jobs
+    }
+
+    /// Finished jobs of a workspace, newest first
+    pub fn recent(&self, workspace_id: &str) -> Vec<CompletedJob> {
+        self.state.read().unwrap().recent.iter()
+            .filter(|job| job.workspace_id == workspace_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Jobs of every workspace that finished in `[from, to)`, newest first
+    ///
+    /// Only the last two days are kept.
+    pub fn finished_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<CompletedJob> {
+        self.state.read().unwrap().history.iter()
+            .filter(|job| job.finished_at >= from && job.finished_at < to)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Host-wide job capacity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueDepth {
+    /// Background jobs running on the host, across workspaces
+    pub running: usize,
+    pub max_inflight: usize,
+    /// Jobs that can still be admitted
+    pub available: usize,
+}
+
+/// Best evaluation so far of an optimization
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptimizationLeader {
+    pub optimization_id: String,
+    pub strategy_id: String,
+    pub evaluations: usize,
+    pub leader: Option<EvaluationRecord>,
+}
+
+/// Everything the task dashboard shows, for one workspace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DashboardSnapshot {
+    pub generated_at: DateTime<Utc>,
+    pub workspace_id: String,
+    pub active_jobs: Vec<ActiveJob>,
+    pub queue: QueueDepth,
+    pub recent_completions: Vec<CompletedJob>,
+    pub resources: ResourceUsage,
+    pub workspace_usage: WorkspaceUsage,
+    pub workspace_quota: ResourceQuota,
+    pub optimization_leaders: Vec<OptimizationLeader>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finished_jobs_move_to_recent() {
+        let board = JobBoard::new();
+        board.start("a", JobKind::Backtest, "default", "mean_reversion");
+        board.start("b", JobKind::CacheWarmup, "research", "mnq_20240612");
+        board.progress("a", 1.4, Some("2024-06-12".to_string()));
+
+        assert_eq!(board.active("default")[0].progress, Some(1.0));
+        assert!(board.finish("a", JobOutcome::Completed));
+        assert!(!board.finish("a", JobOutcome::Completed));
+
+        assert!(board.active("default").is_empty());
+        assert_eq!(board.recent("default")[0].outcome, JobOutcome::Completed);
+        assert!(board.recent("research").is_empty());
+        assert_eq!(board.active("research").len(), 1);
+
+        let now = Utc::now();
+        assert_eq!(board.finished_between(now - Duration::hours(1), now + Duration::hours(1)).len(), 1);
+        assert!(board.finished_between(now - Duration::days(2), now - Duration::hours(1)).is_empty());
+    }
+}
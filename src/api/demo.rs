@@ -0,0 +1,225 @@
+//! Demo mode
+//!
+//! With `STRATEGY_LAB_DEMO` set, the server generates a week of synthetic
+//! sessions at startup and seeds the default workspace with example
+//! strategies and backtests over them, so the API, workflows and reports can
+//! be explored before licensed data is provided. Everything seeded has its
+//! `demo` flag set and the generated datasets are cataloged as demo data.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::{ApiState, BacktestResult, StrategyInfo};
+use crate::backtesting::engine::read_ticks;
+use crate::backtesting::{BacktestConfig, BacktestEngine};
+use crate::data::TickData;
+use crate::strategy::examples::from_template;
+use crate::strategy::templates::StrategyTemplate;
+use crate::strategy::{LifecycleState, StrategySource};
+use crate::subscription::{generate_demo_datasets, DatasetEntry, DemoDataConfig};
+use crate::timestamp::{SessionCalendar, Timestamp};
+use crate::workspace::DEFAULT_WORKSPACE;
+
+/// Demo output directory unless overridden
+const DEFAULT_DEMO_DIR: &str = "data/demo";
+
+/// Ticks decoded per batch reading demo datasets
+const DEMO_BATCH_SIZE: usize = 10_000;
+
+/// Demo data settings when `STRATEGY_LAB_DEMO` is enabled
+pub fn demo_config_from_env() -> Option<DemoDataConfig> {
+    let enabled = std::env::var("STRATEGY_LAB_DEMO").ok()?;
+    if !matches!(enabled.to_ascii_lowercase().as_str(), "1" | "true" | "yes") {
+        return None;
+    }
+    let mut config = DemoDataConfig::new(
+        std::env::var("STRATEGY_LAB_DEMO_DIR").unwrap_or_else(|_| DEFAULT_DEMO_DIR.to_string())
+    );
+    if let Ok(seed) = std::env::var("STRATEGY_LAB_DEMO_SEED") {
+        match seed.parse() {
+            Ok(seed) => config.seed = seed,
+            Err(_) => warn!("Ignoring invalid STRATEGY_LAB_DEMO_SEED={}", seed),
+        }
+    }
+    Some(config)
+}
+
+/// Example strategies seeded in demo mode
+#[derive(Debug, Clone, Copy)]
+enum DemoStrategy {
+    VwapReversion,
+    BidAskBounce,
+    OpeningRangeBreakout,
+}
+
+impl DemoStrategy {
+    const ALL: [Self; 3] = [Self::VwapReversion, Self::BidAskBounce, Self::OpeningRangeBreakout];
+
+    fn template(self) -> StrategyTemplate {
+        match self {
+            Self::VwapReversion => StrategyTemplate::vwap_reversion(),
+            Self::BidAskBounce => StrategyTemplate::bid_ask_bounce(),
+            Self::OpeningRangeBreakout => StrategyTemplate::opening_range_breakout(),
+        }
+    }
+
+    fn run(self, engine: &mut BacktestEngine, path: &Path, ticks: &[TickData]) -> Result<crate::backtesting::BacktestResult, String> {
+        let mut strategy = from_template(&self.template().name, &serde_json::Value::Null)
+            .ok_or_else(|| format!("No example implements {}", self.template().name))?;
+        engine.run_loaded(&mut strategy, path, ticks).map_err(|e| e.to_string())
+    }
+}
+
+/// Generate the demo datasets and seed demo strategies and results
+///
+/// Failures are logged rather than returned; the server runs without demo
+/// data if it cannot be generated.
+pub async fn seed_demo(state: ApiState, config: DemoDataConfig) {
+    warn!(
+        "Demo mode is on: seeding synthetic data under {}. Demo datasets, strategies and results are flagged `demo` and are not market data",
+        config.output_dir.display(),
+    );
+
+    let catalog = state.catalog.clone();
+    let lineage = state.lineage.clone();
+    let calendar = SessionCalendar::default();
+    let generated = {
+        let config = config.clone();
+        let calendar = calendar.clone();
+        tokio::task::spawn_blocking(move || generate_demo_datasets(&config, &calendar, &catalog, Some(&lineage))).await
+    };
+    let datasets = match generated.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e| e.to_string())) {
+        Ok(datasets) => datasets,
+        Err(e) => {
+            warn!("Could not generate demo data: {}", e);
+            return;
+        }
+    };
+
+    // Every session is backtested in one run, so results span the week
+    let mut ticks = Vec::new();
+    for dataset in &datasets {
+        let loaded = read_ticks(Path::new(&dataset.path), Some(&state.dataset_cache), DEMO_BATCH_SIZE).await
+            .map_err(|e| e.to_string());
+        match loaded {
+            Ok(loaded) => ticks.extend(loaded.iter().cloned()),
+            Err(e) => {
+                warn!("Could not read demo dataset {}: {}", dataset.id, e);
+                return;
+            }
+        }
+    }
+    let ticks = Arc::new(ticks);
+    let Some((start, end)) = demo_range(&datasets, &calendar) else {
+        return;
+    };
+
+    for strategy in DemoStrategy::ALL {
+        let template = strategy.template();
+        let strategy_id = Uuid::new_v4().to_string();
+        let source = StrategySource::Template { name: template.name.clone() };
+        state.strategies.write().await.push(StrategyInfo {
+            id: strategy_id.clone(),
+            name: format!("{} (demo)", template.name),
+            description: template.description.clone(),
+            parameters: template.parameters.iter()
+                .map(|(key, param)| (key.clone(), param.default_value.clone()))
+                .collect::<serde_json::Map<_, _>>()
+                .into(),
+            status: LifecycleState::Draft,
+            workspace_id: DEFAULT_WORKSPACE.to_string(),
+            lifecycle: Vec::new(),
+            source: source.clone(),
+            demo: true,
+        });
+
+        let engine_config = BacktestConfig {
+            start_date: start,
+            end_date: end,
+            session_calendar: calendar.clone(),
+            ..Default::default()
+        };
+        let run_config = engine_config.clone();
+        let run_ticks = Arc::clone(&ticks);
+        let data_path = config.output_dir.clone();
+        let latency = state.latency.clone();
+        let run = tokio::task::spawn_blocking(move || {
+            let mut engine = BacktestEngine::new(engine_config).with_latency_registry(latency);
+            let result = strategy.run(&mut engine, &data_path, &run_ticks)?;
+            Ok::<_, String>((result, engine.trades().to_vec(), engine.equity_curve().to_vec()))
+        }).await;
+        let (result, trades, curve) = match run.map_err(|e| e.to_string()).and_then(|r| r) {
+            Ok(run) => run,
+            Err(e) => {
+                warn!("Demo backtest of {} failed: {}", template.name, e);
+                continue;
+            }
+        };
+
+        let result_id = Uuid::new_v4().to_string();
+        let capital = result.initial_capital;
+        let percent = |value: Decimal| if capital.is_zero() { 0.0 } else { (value / capital * Decimal::from(100)).to_f64().unwrap_or(0.0) };
+        if let Err(e) = state.equity_curves.save(&result_id, &curve) {
+            warn!("Could not store the equity curve of demo backtest {}: {}", result_id, e);
+        }
+        let code = state.code_archive.capture(&source).ok();
+        if let Err(e) = state.ledger_store.save(&result_id, &trades) {
+            warn!("Could not store the trade ledger of demo backtest {}: {}", result_id, e);
+        }
+        state.trade_ledgers.write().await.insert(result_id.clone(), Arc::new(trades));
+        state.backtest_results.write().await.push(BacktestResult {
+            id: result_id,
+            strategy_id,
+            start_date: datasets[0].trade_date.map(|d| d.to_string()).unwrap_or_default(),
+            end_date: datasets[datasets.len() - 1].trade_date.map(|d| d.to_string()).unwrap_or_default(),
+            total_return: percent(result.total_pnl),
+            sharpe_ratio: result.sharpe_ratio,
+            max_drawdown: percent(result.max_drawdown),
+            total_trades: result.total_trades,
+            workspace_id: DEFAULT_WORKSPACE.to_string(),
+            out_of_sample: false,
+            daily_returns: daily_returns(&curve, capital, &calendar),
+            code,
+            demo: true,
+            config: Some(run_config),
+        });
+    }
+
+    info!("Demo mode seeded {} strategies over {} synthetic sessions", DemoStrategy::ALL.len(), datasets.len());
+}
+
+/// Open of the first demo session to close of the last
+fn demo_range(datasets: &[DatasetEntry], calendar: &SessionCalendar) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let (open, _) = calendar.session_bounds(datasets.first()?.trade_date?).ok()?;
+    let (_, close) = calendar.session_bounds(datasets.last()?.trade_date?).ok()?;
+    Some((open.to_utc(), close.to_utc()))
+}
+
+/// Daily returns as fractions from an equity curve, by exchange trade date
+pub(crate) fn daily_returns(
+    curve: &[(DateTime<Utc>, Decimal)],
+    initial_capital: Decimal,
+    calendar: &SessionCalendar,
+) -> BTreeMap<NaiveDate, f64> {
+    let mut closes = BTreeMap::new();
+    for (time, equity) in curve {
+        if let Some(date) = calendar.trading_date(Timestamp::from(*time)) {
+            closes.insert(date, *equity);
+        }
+    }
+
+    let mut previous = initial_capital;
+    closes.into_iter()
+        .map(|(date, close)| {
+            let change = if previous.is_zero() { 0.0 } else { ((close - previous) / previous).to_f64().unwrap_or(0.0) };
+            previous = close;
+            (date, change)
+        })
+        .collect()
+}
@@ -0,0 +1,267 @@
+//! Scheduled daily digest
+//!
+//! When notification channels are configured, the server compiles a
+//! `DailyDigest` of the last day once a day at a set local time, records
+//! the run on the job board as a report generation job and sends the digest
+//! through every channel. `GET /api/reports/digest` previews the same digest
+//! for one workspace.
+
+use chrono::{DateTime, Duration, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use std::collections::HashMap;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::dashboard::{JobKind, JobOutcome};
+use super::ApiState;
+use crate::monitoring::ResourceMonitor;
+use crate::reporting::{DailyDigest, DigestBacktest, DigestDataset, DigestOptimization, Incident, NotificationChannel};
+use crate::timestamp::EXCHANGE_TZ;
+use crate::workspace::DEFAULT_WORKSPACE;
+
+/// Results of each kind listed unless configured otherwise
+pub const DEFAULT_TOP_RESULTS: usize = 5;
+
+/// When and where the daily digest is sent
+#[derive(Debug, Clone)]
+pub struct DigestConfig {
+    /// Wall-clock time the digest is sent, in `zone`
+    pub send_at: NaiveTime,
+    pub zone: Tz,
+    pub channels: Vec<NotificationChannel>,
+    /// Best backtests and optimizations listed
+    pub top_results: usize,
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self {
+            send_at: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+            zone: EXCHANGE_TZ,
+            channels: Vec::new(),
+            top_results: DEFAULT_TOP_RESULTS,
+        }
+    }
+}
+
+impl DigestConfig {
+    /// Configuration from `STRATEGY_LAB_DIGEST_CHANNELS`, a comma-separated
+    /// list such as `log,dir:reports,email:ops@example.com`, with the defaults
+    /// overridden by `STRATEGY_LAB_DIGEST_TIME` (`HH:MM`),
+    /// `STRATEGY_LAB_DIGEST_TZ` and `STRATEGY_LAB_DIGEST_TOP`
+    ///
+    /// `None` when no channel is configured, which disables the digest.
+    pub fn from_env() -> Option<Self> {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            match std::env::var(name) {
+                Ok(value) => value.parse().unwrap_or_else(|_| {
+                    warn!("Ignoring invalid {}={}", name, value);
+                    default
+                }),
+                Err(_) => default,
+            }
+        }
+
+        let channels: Vec<NotificationChannel> = std::env::var("STRATEGY_LAB_DIGEST_CHANNELS").ok()?
+            .split(',')
+            .filter(|spec| !spec.trim().is_empty())
+            .filter_map(|spec| spec.parse().map_err(|e| warn!("{}", e)).ok())
+            .collect();
+        if channels.is_empty() {
+            return None;
+        }
+
+        let defaults = Self::default();
+        Some(Self {
+            send_at: var("STRATEGY_LAB_DIGEST_TIME", defaults.send_at),
+            zone: var("STRATEGY_LAB_DIGEST_TZ", defaults.zone),
+            channels,
+            top_results: var("STRATEGY_LAB_DIGEST_TOP", defaults.top_results),
+        })
+    }
+
+    /// First send time strictly after `after`
+    ///
+    /// A send time skipped by a DST change moves to the first valid time
+    /// after it.
+    pub fn next_send_after(&self, after: DateTime<Utc>) -> DateTime<Utc> {
+        let mut date = after.with_timezone(&self.zone).date_naive();
+        loop {
+            let local = date.and_time(self.send_at);
+            let send = (0..=2)
+                .find_map(|hours| self.zone.from_local_datetime(&(local + Duration::hours(hours))).earliest())
+                .map(|send| send.with_timezone(&Utc));
+            if let Some(send) = send.filter(|send| *send > after) {
+                return send;
+            }
+            date = date.succ_opt().expect("date in range");
+        }
+    }
+}
+
+/// Serde name of a job kind or outcome, e.g. `cache_warmup`
+fn label<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => "unknown".to_string(),
+    }
+}
+
+/// Compile the digest of `[from, to)` for one workspace, or all of them
+///
+/// Datasets are shared between workspaces and always listed.
+pub async fn compile_digest(
+    state: &ApiState,
+    workspace: Option<&str>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    top_results: usize,
+) -> DailyDigest {
+    let mut digest = DailyDigest::new(from, to);
+    digest.workspace_id = workspace.map(String::from);
+    let in_scope = |workspace_id: &str| workspace.map_or(true, |workspace| workspace == workspace_id);
+
+    let finished: Vec<_> = state.job_board.finished_between(from, to).into_iter()
+        .filter(|job| in_scope(&job.workspace_id))
+        .collect();
+    for job in &finished {
+        digest.record_job(&label(&job.kind), &label(&job.outcome), job.duration_secs);
+        if job.outcome == JobOutcome::Failed {
+            digest.incidents.push(Incident::warning(
+                "job",
+                format!("{} {} of {} failed", label(&job.kind), job.id, job.subject),
+                Some(job.finished_at),
+            ));
+        }
+    }
+
+    let finished_backtests: Vec<&str> = finished.iter()
+        .filter(|job| job.kind == JobKind::Backtest && job.outcome == JobOutcome::Completed)
+        .map(|job| job.id.as_str())
+        .collect();
+    digest.best_backtests = state.backtest_results.read().await.iter()
+        .filter(|result| finished_backtests.contains(&result.id.as_str()))
+        .map(|result| DigestBacktest {
+            id: result.id.clone(),
+            strategy_id: result.strategy_id.clone(),
+            workspace_id: result.workspace_id.clone(),
+            total_return: result.total_return,
+            sharpe_ratio: result.sharpe_ratio,
+            max_drawdown: result.max_drawdown,
+            total_trades: result.total_trades,
+        })
+        .collect();
+
+    // Optimizations that ran in the period, whether or not they have finished
+    let optimizations: HashMap<String, String> = state.job_board.all_active().into_iter()
+        .filter(|job| job.kind == JobKind::Optimization && in_scope(&job.workspace_id))
+        .map(|job| (job.id, job.subject))
+        .chain(finished.iter()
+            .filter(|job| job.kind == JobKind::Optimization)
+            .map(|job| (job.id.clone(), job.subject.clone())))
+        .collect();
+    let store = state.evaluation_store.clone();
+    let evaluated = tokio::task::spawn_blocking(move || {
+        optimizations.into_iter()
+            .filter_map(|(optimization_id, strategy_id)| {
+                let records = store.load(&optimization_id).ok().flatten()?;
+                let in_period: Vec<_> = records.into_iter()
+                    .filter(|record| record.evaluated_at >= from && record.evaluated_at < to)
+                    .collect();
+                let best = in_period.iter().max_by(|a, b| a.objective_value.total_cmp(&b.objective_value))?;
+                Some(DigestOptimization {
+                    optimization_id,
+                    strategy_id,
+                    evaluations: in_period.len(),
+                    best_objective: best.objective_value,
+                    best_parameters: serde_json::to_value(&best.parameters).unwrap_or_default(),
+                })
+            })
+            .collect::<Vec<_>>()
+    }).await;
+    match evaluated {
+        Ok(optimizations) => digest.optimizations = optimizations,
+        Err(e) => warn!("Failed to read optimization evaluations for the digest: {}", e),
+    }
+
+    for entry in state.catalog.list().into_iter().filter(|entry| entry.ingested_at >= from && entry.ingested_at < to) {
+        let summary = &entry.validation;
+        if summary.ticks_read > 0 && summary.ticks_accepted == 0 {
+            digest.incidents.push(Incident::critical(
+                "ingestion",
+                format!("{} had no valid ticks out of {}", entry.id, summary.ticks_read),
+                Some(entry.ingested_at),
+            ));
+        } else if summary.ticks_dropped > 0 {
+            digest.incidents.push(Incident::warning(
+                "ingestion",
+                format!("{} dropped {} of {} ticks", entry.id, summary.ticks_dropped, summary.ticks_read),
+                Some(entry.ingested_at),
+            ));
+        }
+        digest.datasets.push(DigestDataset {
+            id: entry.id.clone(),
+            trade_date: entry.trade_date,
+            contracts: entry.contracts.clone(),
+            ticks: entry.ticks,
+            ticks_dropped: summary.ticks_dropped,
+            ticks_flagged: summary.ticks_flagged,
+        });
+    }
+
+    digest.resources = Some(ResourceMonitor::new().get_current_usage());
+    digest.finalize(top_results);
+    digest
+}
+
+/// Send the digest of the previous day at each configured time, forever
+pub async fn run_daily_digest(state: ApiState, config: DigestConfig) {
+    loop {
+        let now = Utc::now();
+        let send_at = config.next_send_after(now);
+        tokio::time::sleep((send_at - now).to_std().unwrap_or_default()).await;
+
+        let job_id = Uuid::new_v4().to_string();
+        state.job_board.start(&job_id, JobKind::ReportGeneration, DEFAULT_WORKSPACE, "daily digest");
+        let digest = compile_digest(&state, None, send_at - Duration::days(1), send_at, config.top_results).await;
+        let notification = digest.to_notification();
+
+        let channels = config.channels.clone();
+        let delivered = tokio::task::spawn_blocking(move || {
+            channels.iter()
+                .filter(|channel| match channel.deliver(&notification) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        warn!("Failed to deliver the daily digest to {:?}: {}", channel, e);
+                        false
+                    }
+                })
+                .count()
+        }).await.unwrap_or(0);
+
+        let outcome = if delivered > 0 { JobOutcome::Completed } else { JobOutcome::Failed };
+        state.job_board.finish(&job_id, outcome);
+        info!("Sent the daily digest to {} of {} channels", delivered, config.channels.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_send_is_the_next_local_send_time() {
+        let config = DigestConfig::default();
+        // 07:00 in Chicago is 12:00 UTC in June
+        let after = Utc.with_ymd_and_hms(2024, 6, 12, 11, 0, 0).unwrap();
+        assert_eq!(config.next_send_after(after), Utc.with_ymd_and_hms(2024, 6, 12, 12, 0, 0).unwrap());
+        let after = Utc.with_ymd_and_hms(2024, 6, 12, 12, 0, 0).unwrap();
+        assert_eq!(config.next_send_after(after), Utc.with_ymd_and_hms(2024, 6, 13, 12, 0, 0).unwrap());
+
+        // 02:30 does not exist on the spring-forward day and moves an hour on
+        let config = DigestConfig { send_at: NaiveTime::from_hms_opt(2, 30, 0).unwrap(), ..Default::default() };
+        let after = Utc.with_ymd_and_hms(2024, 3, 10, 0, 0, 0).unwrap();
+        assert_eq!(config.next_send_after(after), Utc.with_ymd_and_hms(2024, 3, 10, 8, 30, 0).unwrap());
+    }
+}
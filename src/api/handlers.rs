@@ -1,21 +1,217 @@
 //! API request handlers
 
 use axum::{
-    extract::State,
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{sse::{Event, KeepAlive, Sse}, IntoResponse, Response},
     Json,
 };
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::StreamExt;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, info_span, warn, Instrument};
 use uuid::Uuid;
 
 use super::{ApiState, StrategyInfo, BacktestResult, SystemMetrics};
-use crate::monitoring::ResourceMonitor;
+use super::bundle::{ImportReport, StrategyBundle};
+use super::manifest::{ManifestAction, ManifestFormat, ManifestOutcome, ManifestSyncReport, StrategyManifest};
+use super::audit::AuditEvent;
+use super::trash::{TrashEntry, TrashedItem};
+use super::dashboard::{
+    DashboardSnapshot, JobKind, JobOutcome, JobStatus, OptimizationLeader, QueueDepth, HISTORY_HOURS,
+};
+use super::idempotency::{Claim, IdempotencyError, IDEMPOTENCY_KEY_HEADER};
+use super::demo::daily_returns;
+use super::digest::{compile_digest, DEFAULT_TOP_RESULTS};
+use super::websocket::WsMessage;
+use crate::analysis::{
+    CorrelationConfig, CorrelationError, CorrelationReport, CostModelConfig, CostScenario, CostSensitivityReport,
+    EvaluationRules, FamilyRun, MonteCarloConfig, OutlierReport, OutlierRule, PromotionDecision, PropFirmReport,
+    ReconciliationConfig,
+    ReconciliationReport, ReturnSeries, RiskNormalizationConfig, RiskNormalizationError, RiskNormalizedComparison,
+    RiskOfRuinReport, RuinConfig, StrategyFamilyReport, StreakConfig, StreakReport, TradingDay,
+    reconcile_session as replay_session,
+};
+#[cfg(feature = "analytics")]
+use crate::analytics::{AnalyticsError, LedgerMirror, QueryResult, SyncReport, DEFAULT_ROW_LIMIT};
+use crate::backtesting::metrics::TradeRecord;
+use crate::backtesting::{
+    BacktestConfig, BacktestEngine, BacktestResult as EngineResult, EquityView, WalConfig, WarmSession,
+    WarmSessionError, WarmSessionInfo,
+};
+use crate::data::{DataIngestionEngine, IngestionConfig, TickData};
+use crate::database::{
+    AnalysisView, AnalysisViewDraft, AnalysisViewError, AnalysisViewStore, HelpArticleError, HelpArticleStore,
+    HelpSearchHit, ParameterPreset, PresetDraft, PresetError, PresetStore, StoredHelpArticle, WorkflowTemplate,
+    WorkflowTemplateError, WorkflowTemplateStore, MAX_SEARCH_RESULTS,
+};
+use crate::jobs::FleetStatus;
+use crate::lineage::integrity::canonical_json;
+use crate::lineage::{ArtifactKind, ArtifactRole, LineageNode, LineageTrace, RunManifest, VerificationReport};
+use crate::market::{
+    build_footprint, reconstruct_book, BookDiffConfig, BookDiffReport, BookSnapshot, ConsistencyConfig,
+    ConsistencyReport, FootprintBar, FootprintConfig, FootprintWindow, LiquidityConfig, LiquidityProfile,
+    SessionSpread, SpreadConfig, SpreadFilter, TapeBuilder, TapeTrade,
+};
+use crate::monitoring::{LatencyReport, ResourceMonitor};
+use crate::optimization::genetic::SelectionStrategy;
+use crate::optimization::{
+    ConstraintHandling, ConvergenceCurve, EvalStoreError, EvaluationRecord, GeneticConfig, GeneticOptimizer,
+    ObjectiveFunction, OptimizationControl, ParameterSet, SteeringCommand, SteeringStatus,
+};
+use crate::performance::warm_cache::{CachedDatasetInfo, WarmupReport, WarmupRequest};
+use crate::performance::{MemorySnapshot, ThreadingConfig, ThreadingReport, WorkloadClass};
+use crate::reporting::{
+    trade_windows, DailyDigest, ReplayConfig, TradeFilter, TradePage, TradeReplay, DEFAULT_PAGE_SIZE,
+};
+use crate::subscription::{
+    derive_dataset, CatalogError, DataLicense, DatasetEntry, RecordedSession, SampleMethod, SamplingError,
+};
+use crate::timestamp::{detect_sessions, SessionCalendar, SessionDetectionConfig, SessionProposal, Timestamp};
+use crate::telemetry::{JobLogChunk, JobLogCursor, JobLogError};
+use crate::strategy::examples::{from_template, TemplateBuilder};
+use crate::strategy::{CodeSnapshot, EvidenceKind, LifecycleState, LifecycleTransition, Strategy, StrategySource};
+use crate::workflow::{
+    check_user_workflow, HelpArticle, HelpArticleIssue, TemplateIssue, WorkflowStepType, WorkflowTemplateDraft,
+};
+use crate::workspace::{ResourceQuota, Workspace, WorkspaceError, WorkspaceRegistry, DEFAULT_WORKSPACE};
+
+/// Header selecting the workspace a request acts in
+pub const WORKSPACE_HEADER: &str = "x-workspace-id";
+
+/// Header identifying the calling user
+pub const USER_HEADER: &str = "x-user-id";
+
+/// Equity curve bars returned when a chart does not say how many it wants
+const DEFAULT_EQUITY_POINTS: usize = 2_000;
+
+pub(crate) fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Resolve and authorize the workspace a request is scoped to
+///
+/// Requests without a workspace header act in the default workspace.
+fn workspace_scope(state: &ApiState, headers: &HeaderMap) -> Result<String, StatusCode> {
+    let workspace = header(headers, WORKSPACE_HEADER).unwrap_or(DEFAULT_WORKSPACE);
+    state.workspaces.authorize(workspace, header(headers, USER_HEADER))
+        .map_err(workspace_status)?;
+    Ok(workspace.to_string())
+}
+
+/// A cataloged dataset the calling workspace may read; datasets of other
+/// workspaces give 404 like unknown ones
+fn scoped_dataset(state: &ApiState, headers: &HeaderMap, id: &str) -> Result<DatasetEntry, StatusCode> {
+    let workspace = workspace_scope(state, headers)?;
+    state.catalog.get(id)
+        .filter(|entry| entry.visible_to(&workspace))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// A cataloged dataset the calling workspace owns, for changes to its entry
+fn owned_dataset(state: &ApiState, headers: &HeaderMap, id: &str) -> Result<DatasetEntry, StatusCode> {
+    let workspace = workspace_scope(state, headers)?;
+    state.catalog.get(id)
+        .filter(|entry| entry.workspace_id == workspace)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+fn workspace_status(e: WorkspaceError) -> StatusCode {
+    match e {
+        WorkspaceError::UnknownWorkspace(_) => StatusCode::NOT_FOUND,
+        WorkspaceError::NotAMember { .. } => StatusCode::FORBIDDEN,
+        _ => StatusCode::TOO_MANY_REQUESTS,
+    }
+}
+
+fn idempotency_status(e: IdempotencyError) -> StatusCode {
+    match e {
+        IdempotencyError::InvalidKey => StatusCode::BAD_REQUEST,
+        IdempotencyError::InProgress(_) => StatusCode::CONFLICT,
+        IdempotencyError::KeyReused(_) => StatusCode::UNPROCESSABLE_ENTITY,
+    }
+}
+
+/// Claim the request's idempotency key, when it sent one
+fn claim_idempotency_key(
+    state: &ApiState,
+    headers: &HeaderMap,
+    workspace: &str,
+    kind: JobKind,
+    request: &impl Serialize,
+) -> Result<Option<Claim>, StatusCode> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+    let key = value.to_str().map_err(|_| StatusCode::BAD_REQUEST)?;
+    state.idempotency.claim(workspace, key, kind, request)
+        .map(Some)
+        .map_err(idempotency_status)
+}
+
+fn preset_status(e: PresetError) -> StatusCode {
+    match e {
+        PresetError::NotFound { .. } => StatusCode::NOT_FOUND,
+        PresetError::InvalidParameters => StatusCode::UNPROCESSABLE_ENTITY,
+        PresetError::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        PresetError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn warm_session_status(e: WarmSessionError) -> StatusCode {
+    match e {
+        WarmSessionError::NotFound(_) => StatusCode::NOT_FOUND,
+        WarmSessionError::TooManySessions(_) => StatusCode::TOO_MANY_REQUESTS,
+        WarmSessionError::Load { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        WarmSessionError::Run(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Refuse new batch work while memory use is into the interactive reservation
+fn admit_batch(state: &ApiState) -> Result<(), StatusCode> {
+    state.thread_pools.config().reservation
+        .admit(WorkloadClass::Batch, MemorySnapshot::current())
+        .map_err(|e| {
+            warn!("Refusing batch job: {}", e);
+            StatusCode::TOO_MANY_REQUESTS
+        })
+}
+
+fn preset_store(state: &ApiState) -> Result<&PresetStore, PresetError> {
+    state.presets.as_ref().ok_or(PresetError::Unavailable)
+}
+
+/// Parameters for a run: the named preset's values, if any, with `overrides` on top
+pub async fn resolve_parameters(
+    state: &ApiState,
+    workspace: &str,
+    strategy_id: &str,
+    preset: Option<&str>,
+    overrides: &serde_json::Value,
+) -> Result<serde_json::Value, PresetError> {
+    match preset {
+        Some(name) => {
+            let preset = preset_store(state)?.get(workspace, strategy_id, name).await?;
+            Ok(preset.merged_with(overrides))
+        }
+        None => Ok(overrides.clone()),
+    }
+}
 
 #[derive(Debug, Deserialize)]
 pub struct CreateStrategyRequest {
     pub name: String,
     pub description: String,
     pub parameters: serde_json::Value,
+    #[serde(default)]
+    pub source: StrategySource,
 }
 
 #[derive(Debug, Serialize)]
@@ -27,16 +223,25 @@ pub struct CreateStrategyResponse {
 /// List all strategies
 pub async fn list_strategies(
     State(state): State<ApiState>,
+    headers: HeaderMap,
 ) -> Result<Json<Vec<StrategyInfo>>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
     let strategies = state.strategies.read().await;
-    Ok(Json(strategies.clone()))
+    Ok(Json(strategies.iter()
+        .filter(|s| s.workspace_id == workspace)
+        .cloned()
+        .collect()))
 }
 
 /// Create a new strategy
 pub async fn create_strategy(
     State(state): State<ApiState>,
+    headers: HeaderMap,
     Json(req): Json<CreateStrategyRequest>,
 ) -> Result<Json<CreateStrategyResponse>, StatusCode> {
+    let workspace_id = workspace_scope(&state, &headers)?;
+    // Reject sources that could not be snapshotted when a backtest runs
+    state.code_archive.capture(&req.source).map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
     let id = Uuid::new_v4().to_string();
     
     let strategy = StrategyInfo {
@@ -44,7 +249,11 @@ pub async fn create_strategy(
         name: req.name,
         description: req.description,
         parameters: req.parameters,
-        status: "draft".to_string(),
+        status: LifecycleState::Draft,
+        workspace_id,
+        lifecycle: Vec::new(),
+        source: req.source,
+        demo: false,
     };
     
     let mut strategies = state.strategies.write().await;
@@ -56,102 +265,3252 @@ pub async fn create_strategy(
     }))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RunBacktestRequest {
     pub strategy_id: String,
     pub start_date: String,
     pub end_date: String,
     pub initial_capital: f64,
+    /// Lineage ids of the datasets this backtest reads
+    #[serde(default)]
+    pub inputs: Vec<String>,
+    /// Saved preset to take parameters from
+    #[serde(default)]
+    pub preset: Option<String>,
+    /// Parameter values, applied on top of the preset's
+    #[serde(default)]
+    pub parameters: serde_json::Value,
+    /// Whether the date range is held out from development, for promotion gates
+    #[serde(default)]
+    pub out_of_sample: bool,
+    /// Warm session whose resident data the backtest runs against
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Cataloged dataset to run over; required unless `session_id` names a warm session
+    #[serde(default)]
+    pub dataset_id: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct RunBacktestResponse {
     pub backtest_id: String,
     pub message: String,
+    /// Status of the backtest an earlier submission with the same
+    /// idempotency key started
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<JobStatus>,
 }
 
 /// Run a backtest
+///
+/// A request repeating the `Idempotency-Key` of an earlier one returns the
+/// backtest that request started instead of starting another.
 pub async fn run_backtest(
     State(state): State<ApiState>,
-    Json(req): Json<RunBacktestRequest>,
+    headers: HeaderMap,
+    Json(mut req): Json<RunBacktestRequest>,
 ) -> Result<Json<RunBacktestResponse>, StatusCode> {
-    let backtest_id = Uuid::new_v4().to_string();
-    
-    // In a real implementation, this would spawn a background task
-    // For now, create a mock result
-    let result = BacktestResult {
-        id: backtest_id.clone(),
-        strategy_id: req.strategy_id,
-        start_date: req.start_date,
-        end_date: req.end_date,
-        total_return: 24.5,
-        sharpe_ratio: 1.82,
-        max_drawdown: -8.3,
-        total_trades: 500,
+    let workspace = workspace_scope(&state, &headers)?;
+    let reservation = match claim_idempotency_key(&state, &headers, &workspace, JobKind::Backtest, &req)? {
+        Some(Claim::Existing(backtest_id)) => return Ok(Json(RunBacktestResponse {
+            status: state.job_board.status(&backtest_id),
+            backtest_id,
+            message: "Backtest already submitted".to_string(),
+        })),
+        Some(Claim::New(reservation)) => Some(reservation),
+        None => None,
     };
-    
-    let mut results = state.backtest_results.write().await;
-    results.push(result);
+    req.parameters = resolve_parameters(&state, &workspace, &req.strategy_id, req.preset.as_deref(), &req.parameters)
+        .await
+        .map_err(preset_status)?;
+    let backtest_id = submit_backtest(&state, &workspace, req).await
+        .map_err(submit_status)?;
+    if let Some(reservation) = reservation {
+        reservation.complete(&backtest_id);
+    }
     
     Ok(Json(RunBacktestResponse {
         backtest_id,
         message: "Backtest started".to_string(),
+        status: None,
     }))
 }
 
+/// A workspace job slot, released when the job finishes or is aborted
+struct JobSlot {
+    workspaces: WorkspaceRegistry,
+    workspace_id: String,
+    started: std::time::Instant,
+}
+
+impl Drop for JobSlot {
+    fn drop(&mut self) {
+        let _ = self.workspaces.finish_job(&self.workspace_id, self.started.elapsed().as_secs_f64());
+    }
+}
+
+/// Why a backtest could not be submitted
+#[derive(Debug, thiserror::Error)]
+pub enum SubmitError {
+    #[error(transparent)]
+    Workspace(#[from] WorkspaceError),
+    #[error("Unknown strategy: {0}")]
+    UnknownStrategy(String),
+    #[error("Strategy {0} has no implementation the server can run")]
+    NotRunnable(String),
+    #[error("Unknown dataset: {0}")]
+    UnknownDataset(String),
+    #[error("A backtest needs a dataset_id or a session_id")]
+    NoData,
+    #[error(transparent)]
+    WarmSession(#[from] WarmSessionError),
+    #[error("Invalid date: {0}")]
+    InvalidDate(String),
+}
+
+fn submit_status(e: SubmitError) -> StatusCode {
+    match e {
+        SubmitError::Workspace(e) => workspace_status(e),
+        SubmitError::WarmSession(e) => warm_session_status(e),
+        SubmitError::UnknownStrategy(_) | SubmitError::UnknownDataset(_) => StatusCode::NOT_FOUND,
+        SubmitError::NotRunnable(_) | SubmitError::NoData | SubmitError::InvalidDate(_) => {
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+    }
+}
+
+/// Start or end of a run from an RFC 3339 time or a `YYYY-MM-DD` date; an
+/// end date includes the whole day
+fn run_bound(value: &str, end: bool) -> Result<DateTime<Utc>, SubmitError> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+        .and_then(|date| match end {
+            true => date.and_hms_nano_opt(23, 59, 59, 999_999_999),
+            false => date.and_hms_opt(0, 0, 0),
+        })
+        .map(|time| time.and_utc())
+        .ok_or_else(|| SubmitError::InvalidDate(value.to_string()))
+}
+
+/// Data a submitted backtest runs over
+enum RunData {
+    Dataset(DatasetEntry),
+    Session(WarmSession),
+}
+
+/// Run `strategy` over `data` and return the result, trades and equity curve
+///
+/// Datasets are read through the shared cache so the engine can seek the
+/// book from its checkpoints; the run itself is on a blocking thread.
+async fn run_engine(
+    state: &ApiState,
+    job_id: &str,
+    mut strategy: Box<dyn Strategy>,
+    data: RunData,
+    config: BacktestConfig,
+) -> Result<(EngineResult, Vec<TradeRecord>, Vec<(DateTime<Utc>, Decimal)>), String> {
+    // Stage latencies feed `/api/metrics/latency`; the write-ahead log keeps
+    // fills of a run the process dies during
+    let engine = BacktestEngine::new(config)
+        .with_latency_registry(state.latency.clone())
+        .with_write_ahead_log(WalConfig::new(&state.wal_dir, job_id));
+    let (mut engine, path, ticks) = match data {
+        RunData::Dataset(entry) => {
+            let mut engine = engine.with_dataset_cache(state.dataset_cache.clone());
+            let path = PathBuf::from(&entry.path);
+            let ticks = engine.load_data(&path).await.map_err(|e| e.to_string())?;
+            (engine, path, ticks)
+        }
+        RunData::Session(session) => {
+            (engine, session.data_path().to_path_buf(), session.resident_ticks())
+        }
+    };
+    tokio::task::spawn_blocking(move || {
+        let result = engine.run_loaded(&mut strategy, &path, &ticks).map_err(|e| e.to_string())?;
+        Ok((result, engine.trades().to_vec(), engine.equity_curve().to_vec()))
+    }).await.map_err(|e| e.to_string())?
+}
+
+/// A workspace's strategy, built with its stored parameters and `overrides` on top
+async fn runnable_strategy(
+    state: &ApiState,
+    workspace: &str,
+    strategy_id: &str,
+    overrides: &serde_json::Value,
+) -> Result<(StrategyInfo, Box<dyn Strategy>), SubmitError> {
+    let strategy_info = state.strategies.read().await.iter()
+        .find(|s| s.id == strategy_id && s.workspace_id == workspace)
+        .cloned()
+        .ok_or_else(|| SubmitError::UnknownStrategy(strategy_id.to_string()))?;
+    let mut parameters = strategy_info.parameters.clone();
+    match (parameters.as_object_mut(), overrides.as_object()) {
+        (Some(stored), Some(overrides)) => stored.extend(overrides.clone()),
+        _ if !overrides.is_null() => parameters = overrides.clone(),
+        _ => {}
+    }
+    let strategy = match &strategy_info.source {
+        StrategySource::Template { name } => from_template(name, &parameters),
+        _ => None,
+    }.ok_or_else(|| SubmitError::NotRunnable(strategy_info.name.clone()))?;
+    Ok((strategy_info, strategy))
+}
+
+/// Start a backtest job in the background and return its id
+///
+/// Shared by the REST endpoint and the WebSocket command channel. The
+/// strategy, data and dates are resolved before anything starts, so a bad
+/// request takes no job slot. The job takes one of the workspace's
+/// concurrent job slots until it finishes.
+pub async fn submit_backtest(
+    state: &ApiState,
+    workspace: &str,
+    req: RunBacktestRequest,
+) -> Result<String, SubmitError> {
+    let (strategy_info, strategy) = runnable_strategy(state, workspace, &req.strategy_id, &req.parameters).await?;
+    let mut engine_config = BacktestConfig {
+        start_date: run_bound(&req.start_date, false)?,
+        end_date: run_bound(&req.end_date, true)?,
+        ..Default::default()
+    };
+    if let Some(capital) = Decimal::from_f64_retain(req.initial_capital).filter(|c| c.is_sign_positive()) {
+        engine_config.initial_capital = capital;
+    }
+    let data = match (&req.session_id, &req.dataset_id) {
+        (Some(session_id), _) => RunData::Session(state.warm_sessions.checkout(workspace, session_id)?),
+        (None, Some(dataset_id)) => state.catalog.get(dataset_id)
+            .filter(|entry| entry.visible_to(workspace))
+            .map(RunData::Dataset)
+            .ok_or_else(|| SubmitError::UnknownDataset(dataset_id.clone()))?,
+        (None, None) => return Err(SubmitError::NoData),
+    };
+    
+    state.workspaces.start_job(workspace)?;
+    let backtest_id = Uuid::new_v4().to_string();
+    
+    let config = serde_json::json!({
+        "strategy_id": req.strategy_id,
+        "preset": req.preset,
+        "parameters": req.parameters,
+        "session_id": req.session_id,
+        "dataset_id": req.dataset_id,
+    });
+    state.lineage.record_in(workspace, ArtifactKind::Backtest, backtest_id.clone(), &req.inputs, config.clone());
+    
+    // Input files are hashed by the job before it runs; results once they exist
+    let mut manifest = RunManifest::new(backtest_id.clone());
+    let mut files: Vec<(ArtifactRole, PathBuf)> = req.inputs.iter()
+        .filter_map(|input| state.lineage.get(input).filter(|node| node.kind == ArtifactKind::RawFile))
+        .map(|node| (ArtifactRole::InputData, PathBuf::from(node.name)))
+        .collect();
+    files.push((ArtifactRole::InputData, match &data {
+        RunData::Dataset(entry) => PathBuf::from(&entry.path),
+        RunData::Session(session) => session.data_path().to_path_buf(),
+    }));
+    if let Err(e) = manifest.add_json(ArtifactRole::Config, "config", &config) {
+        warn!("Could not hash configuration of backtest {}: {}", backtest_id, e);
+    }
+    
+    // The strategy's code as it is when the run starts
+    let source = strategy_info.source;
+    let code = match state.code_archive.capture(&source) {
+        Ok(code) => Some(code),
+        Err(e) => {
+            warn!("Could not snapshot the code of backtest {}: {}", backtest_id, e);
+            None
+        }
+    };
+    let archived = code.as_ref()
+        .and_then(|code| code.sha256.as_deref())
+        .and_then(|sha| state.code_archive.get(sha));
+    if let Some(text) = archived {
+        manifest.add_bytes(ArtifactRole::Config, "strategy_code", text.as_bytes());
+    }
+    if let StrategySource::Plugin { path } = &source {
+        files.push((ArtifactRole::Config, path.clone()));
+    }
+    
+    state.job_board.start(&backtest_id, JobKind::Backtest, workspace, req.strategy_id.clone());
+    let task_state = state.clone();
+    let task_id = backtest_id.clone();
+    let slot = JobSlot {
+        workspaces: state.workspaces.clone(),
+        workspace_id: workspace.to_string(),
+        started: std::time::Instant::now(),
+    };
+    // A child of the request span, so the run is traced under the request's id
+    let span = info_span!("backtest_job", job_id = %backtest_id, strategy = %req.strategy_id);
+    spawn_job(state, &backtest_id, async move {
+        let calendar = engine_config.session_calendar.clone();
+        let run_config = engine_config.clone();
+        let mut manifest = match hash_files(manifest, files).await {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                warn!("Hashing the inputs of backtest {} failed: {}", task_id, e);
+                task_state.jobs.write().await.remove(&task_id);
+                task_state.job_board.finish(&task_id, JobOutcome::Failed);
+                return;
+            }
+        };
+        let (run, trades, curve) = match run_engine(&task_state, &task_id, strategy, data, engine_config).await {
+            Ok(run) => run,
+            Err(e) => {
+                warn!("Backtest {} failed: {}", task_id, e);
+                task_state.jobs.write().await.remove(&task_id);
+                task_state.job_board.finish(&task_id, JobOutcome::Failed);
+                return;
+            }
+        };
+        let capital = run.initial_capital;
+        let percent = |value: Decimal| if capital.is_zero() { 0.0 } else { (value / capital * Decimal::from(100)).to_f64().unwrap_or(0.0) };
+        let result = BacktestResult {
+            id: task_id.clone(),
+            strategy_id: req.strategy_id,
+            start_date: req.start_date,
+            end_date: req.end_date.clone(),
+            total_return: percent(run.total_pnl),
+            sharpe_ratio: run.sharpe_ratio,
+            max_drawdown: percent(run.max_drawdown),
+            total_trades: run.total_trades,
+            workspace_id: slot.workspace_id.clone(),
+            out_of_sample: req.out_of_sample,
+            daily_returns: daily_returns(&curve, capital, &calendar),
+            code,
+            demo: false,
+            config: Some(run_config),
+        };
+        
+        if let Err(e) = manifest.add_json(ArtifactRole::Output, "ledger", &trades) {
+            warn!("Could not hash trade ledger of backtest {}: {}", task_id, e);
+        }
+        match manifest.add_json(ArtifactRole::Output, "result", &result) {
+            Ok(()) => {
+                if let Err(e) = task_state.integrity.record(manifest) {
+                    warn!("Could not write the manifest of backtest {}: {}", task_id, e);
+                }
+            }
+            Err(e) => warn!("Could not hash result of backtest {}: {}", task_id, e),
+        }
+        let curves = task_state.equity_curves.clone();
+        let curve_id = task_id.clone();
+        let saved = tokio::task::spawn_blocking(move || curves.save(&curve_id, &curve)).await;
+        if let Err(e) = saved.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e| e.to_string())) {
+            warn!("Could not store the equity curve of backtest {}: {}", task_id, e);
+        }
+        let store = task_state.ledger_store.clone();
+        let trades = Arc::new(trades);
+        let stored = Arc::clone(&trades);
+        let ledger_id = task_id.clone();
+        let saved = tokio::task::spawn_blocking(move || store.save(&ledger_id, &stored)).await;
+        if let Err(e) = saved.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e| e.to_string())) {
+            warn!("Could not store the trade ledger of backtest {}: {}", task_id, e);
+        }
+        task_state.trade_ledgers.write().await.insert(task_id.clone(), trades);
+        task_state.backtest_results.write().await.push(result);
+        task_state.jobs.write().await.remove(&task_id);
+        task_state.job_board.progress(&task_id, 1.0, Some(req.end_date.clone()));
+        task_state.job_board.finish(&task_id, JobOutcome::Completed);
+        drop(slot);
+        
+        let _ = task_state.events.send(WsMessage::BacktestProgress {
+            progress: 1.0,
+            current_date: req.end_date,
+            trades_executed: run.total_trades,
+        });
+    }.instrument(span)).await;
+    Ok(backtest_id)
+}
+
+/// Hash `files` into a run's manifest on the blocking pool
+///
+/// Files that cannot be read are left out of the manifest with a warning.
+async fn hash_files(
+    mut manifest: RunManifest,
+    files: Vec<(ArtifactRole, PathBuf)>,
+) -> Result<RunManifest, tokio::task::JoinError> {
+    tokio::task::spawn_blocking(move || {
+        for (role, path) in &files {
+            if let Err(e) = manifest.add_file(*role, path) {
+                warn!("Could not hash {} of run {}: {}", path.display(), manifest.run_id, e);
+            }
+        }
+        manifest
+    }).await
+}
+
+/// Spawn a cancellable job under `job_id`
+///
+/// The job table stays locked until the handle is in it, so a job that
+/// finishes at once still finds its own entry to remove.
+pub(crate) async fn spawn_job<F>(state: &ApiState, job_id: &str, job: F)
+where
+    F: std::future::Future<Output = ()> + Send + 'static,
+{
+    let mut jobs = state.jobs.write().await;
+    let task = tokio::spawn(job);
+    jobs.insert(job_id.to_string(), task.abort_handle());
+}
+
+/// Cancel a running job of `workspace`, returning whether it was found
+///
+/// Jobs of other workspaces are treated as unknown.
+pub async fn cancel_job(state: &ApiState, workspace: &str, job_id: &str) -> bool {
+    let owned = matches!(state.job_board.status(job_id), Some(JobStatus::Active(job)) if job.workspace_id == workspace);
+    if !owned {
+        return false;
+    }
+    match state.jobs.write().await.remove(job_id) {
+        Some(handle) => {
+            handle.abort();
+            state.optimization_controls.write().await.remove(job_id);
+            state.job_board.finish(job_id, JobOutcome::Cancelled);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Lines of a job log returned unless `tail` says otherwise
+const DEFAULT_LOG_TAIL: usize = 200;
+
+/// How often a followed job log is checked for new lines
+const JOB_LOG_POLL: Duration = Duration::from_millis(500);
+
+fn job_log_status(e: JobLogError) -> StatusCode {
+    match e {
+        JobLogError::NotFound(_) => StatusCode::NOT_FOUND,
+        JobLogError::InvalidJobId(_) => StatusCode::BAD_REQUEST,
+        JobLogError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JobLogQuery {
+    /// Lines from the end of the log
+    pub tail: Option<usize>,
+    /// Only lines logged after this cursor, as returned in `next`
+    pub after: Option<JobLogCursor>,
+    /// Stream lines as server-sent events until the job ends
+    #[serde(default)]
+    pub follow: bool,
+}
+
+/// Read the log of a backtest, optimization, ingestion or warm-up job
+///
+/// Jobs the board knows of are only visible to their workspace; logs of
+/// jobs run by workers of another server are readable by id.
+pub async fn get_job_logs(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(query): Query<JobLogQuery>,
+) -> Result<Response, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let owner = match state.job_board.status(&id) {
+        Some(JobStatus::Active(job)) => Some(job.workspace_id),
+        Some(JobStatus::Finished(job)) => Some(job.workspace_id),
+        None => None,
+    };
+    if owner.as_deref().is_some_and(|owner| owner != workspace) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let chunk = match query.after {
+        Some(cursor) => state.job_logs.read_after(&id, cursor),
+        None => state.job_logs.tail(&id, query.tail.unwrap_or(DEFAULT_LOG_TAIL)),
+    };
+    let chunk = match chunk {
+        // A job on the board may not have logged anything yet
+        Err(JobLogError::NotFound(_)) if owner.is_some() => JobLogChunk {
+            job_id: id.clone(),
+            lines: Vec::new(),
+            next: query.after.unwrap_or_default(),
+            truncated: false,
+        },
+        chunk => chunk.map_err(job_log_status)?,
+    };
+    if !query.follow {
+        return Ok(Json(chunk).into_response());
+    }
+
+    let backlog = futures::stream::iter((!chunk.lines.is_empty()).then(|| log_event(&chunk)));
+    let followed = futures::stream::unfold(Some(chunk.next), move |cursor| {
+        let state = state.clone();
+        let id = id.clone();
+        async move {
+            let mut cursor = cursor?;
+            loop {
+                // Checked before reading, so lines logged as the job ends are sent
+                let running = matches!(state.job_board.status(&id), Some(JobStatus::Active(_)));
+                match state.job_logs.read_after(&id, cursor) {
+                    Ok(chunk) if !chunk.lines.is_empty() => return Some((log_event(&chunk), Some(chunk.next))),
+                    Ok(chunk) => cursor = chunk.next,
+                    Err(JobLogError::NotFound(_)) => {}
+                    Err(e) => warn!("Could not follow the log of job {}: {}", id, e),
+                }
+                if !running {
+                    return Some((Ok(Event::default().event("end").data(&id)), None));
+                }
+                tokio::time::sleep(JOB_LOG_POLL).await;
+            }
+        }
+    });
+    Ok(Sse::new(backlog.chain(followed)).keep_alive(KeepAlive::default()).into_response())
+}
+
+/// Lines of a job log as one event, resumable from its id
+fn log_event(chunk: &JobLogChunk) -> Result<Event, Infallible> {
+    Ok(Event::default().id(chunk.next.to_string()).data(chunk.lines.join("\n").replace('\r', "")))
+}
+
 /// Get backtest results
 pub async fn get_backtest_results(
     State(state): State<ApiState>,
+    headers: HeaderMap,
 ) -> Result<Json<Vec<BacktestResult>>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
     let results = state.backtest_results.read().await;
-    Ok(Json(results.clone()))
+    Ok(Json(results.iter()
+        .filter(|r| r.workspace_id == workspace)
+        .cloned()
+        .collect()))
+}
+
+/// Aggregate every stored backtest of a strategy into a family report
+pub async fn get_strategy_family_report(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(strategy_id): Path<String>,
+) -> Result<Json<StrategyFamilyReport>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let runs = family_runs(&state, &workspace, &strategy_id, false).await;
+    
+    if runs.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    
+    Ok(Json(StrategyFamilyReport::from_runs(strategy_id, &runs)))
+}
+
+/// Stored backtests of a strategy as family runs, optionally only out-of-sample ones
+async fn family_runs(state: &ApiState, workspace: &str, strategy_id: &str, out_of_sample_only: bool) -> Vec<FamilyRun> {
+    state.backtest_results.read().await.iter()
+        .filter(|r| r.strategy_id == strategy_id && r.workspace_id == workspace)
+        .filter(|r| r.out_of_sample || !out_of_sample_only)
+        .map(|r| FamilyRun {
+            run_id: r.id.clone(),
+            start_date: r.start_date.clone(),
+            end_date: r.end_date.clone(),
+            total_return: r.total_return,
+            sharpe_ratio: r.sharpe_ratio,
+            max_drawdown: r.max_drawdown,
+            total_trades: r.total_trades,
+        })
+        .collect()
+}
+
+/// Check a strategy against the promotion gates without changing it
+pub async fn check_strategy_promotion(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(strategy_id): Path<String>,
+) -> Result<Json<PromotionDecision>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    if !state.strategies.read().await.iter().any(|s| s.id == strategy_id && s.workspace_id == workspace) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(Json(promotion_decision(&state, &workspace, &strategy_id).await))
+}
+
+async fn promotion_decision(state: &ApiState, workspace: &str, strategy_id: &str) -> PromotionDecision {
+    let runs = family_runs(state, workspace, strategy_id, false).await;
+    let out_of_sample = family_runs(state, workspace, strategy_id, true).await;
+    state.promotion_policy.evaluate(strategy_id, &runs, &out_of_sample)
 }
 
 #[derive(Debug, Deserialize)]
+pub struct SetStatusRequest {
+    pub status: LifecycleState,
+    /// Result the promotion rests on, when the target state needs one
+    #[serde(default)]
+    pub result_id: Option<String>,
+    /// Validate despite failed promotion gates; needs a user and a reason
+    #[serde(default, rename = "override")]
+    pub override_gates: bool,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SetStatusResponse {
+    pub strategy: StrategyInfo,
+    /// Gate results, when the strategy was validated
+    pub decision: Option<PromotionDecision>,
+    /// Audit record of the transition
+    pub audit_event: Option<AuditEvent>,
+}
+
+/// Move a strategy to another lifecycle state
+///
+/// Transitions the lifecycle does not allow are refused with 409. Promotions
+/// need their evidence: moving to backtested or live names a result of the
+/// strategy in `result_id`, out-of-sample for live, and is refused with 422
+/// without one. Validation is refused with 422 and the gate results unless
+/// every promotion gate passes or the caller overrides them. Every
+/// transition is written to the audit trail, overridden ones with the
+/// failed gates and the caller's reason.
+pub async fn set_strategy_status(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(strategy_id): Path<String>,
+    Json(req): Json<SetStatusRequest>,
+) -> Result<Json<SetStatusResponse>, (StatusCode, Json<Option<PromotionDecision>>)> {
+    let reject = |status: StatusCode| (status, Json(None));
+    let workspace = workspace_scope(&state, &headers).map_err(reject)?;
+    let current = state.strategies.read().await.iter()
+        .find(|s| s.id == strategy_id && s.workspace_id == workspace)
+        .map(|s| s.status)
+        .ok_or_else(|| reject(StatusCode::NOT_FOUND))?;
+    let needs = current.check_transition(req.status).map_err(|_| reject(StatusCode::CONFLICT))?;
+    
+    let user = header(&headers, USER_HEADER);
+    let mut decision = None;
+    let mut result_id = None;
+    match needs {
+        Some(EvidenceKind::BacktestResult) | Some(EvidenceKind::OutOfSampleResult) => {
+            let out_of_sample_only = needs == Some(EvidenceKind::OutOfSampleResult);
+            let id = req.result_id.as_deref().ok_or_else(|| reject(StatusCode::UNPROCESSABLE_ENTITY))?;
+            let runs = family_runs(&state, &workspace, &strategy_id, out_of_sample_only).await;
+            if !runs.iter().any(|run| run.run_id == id) {
+                return Err(reject(StatusCode::UNPROCESSABLE_ENTITY));
+            }
+            result_id = Some(id.to_string());
+        }
+        Some(EvidenceKind::PassingValidation) => {
+            let gates = promotion_decision(&state, &workspace, &strategy_id).await;
+            if !gates.passed {
+                if !req.override_gates {
+                    return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(Some(gates))));
+                }
+                let reason_given = req.reason.as_deref().map_or(false, |r| !r.trim().is_empty());
+                if user.is_none() || !reason_given {
+                    return Err((StatusCode::BAD_REQUEST, Json(Some(gates))));
+                }
+            }
+            decision = Some(gates);
+        }
+        None => {}
+    }
+    
+    let overridden = decision.as_ref().map_or(false, |d| !d.passed);
+    let transition = LifecycleTransition {
+        from: current,
+        to: req.status,
+        at: Utc::now(),
+        actor: user.map(str::to_string),
+        result_id,
+        overridden,
+        reason: req.reason,
+    };
+    
+    let mut strategies = state.strategies.write().await;
+    let strategy = strategies.iter_mut()
+        .find(|s| s.id == strategy_id && s.workspace_id == workspace)
+        .ok_or_else(|| reject(StatusCode::NOT_FOUND))?;
+    // Another request moved the strategy while the evidence was checked
+    if strategy.status != current {
+        return Err(reject(StatusCode::CONFLICT));
+    }
+    let audit_event = state.audit.record(&workspace, user, "strategy.transition", &strategy_id, serde_json::json!({
+        "from": transition.from,
+        "to": transition.to,
+        "result_id": transition.result_id,
+        "overridden": overridden,
+        "failed_gates": decision.as_ref().map(|d| d.failed_gates()).unwrap_or_default(),
+        "reason": transition.reason,
+    }));
+    strategy.status = req.status;
+    strategy.lifecycle.push(transition);
+    
+    Ok(Json(SetStatusResponse {
+        strategy: strategy.clone(),
+        decision,
+        audit_event: Some(audit_event),
+    }))
+}
+
+/// List the workspace's audit trail, newest first
+pub async fn list_audit_events(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<AuditEvent>>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    Ok(Json(state.audit.list(&workspace)))
+}
+
+/// Move a strategy and its results to the trash
+pub async fn delete_strategy(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(strategy_id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let strategy = {
+        let mut strategies = state.strategies.write().await;
+        let index = strategies.iter()
+            .position(|s| s.id == strategy_id && s.workspace_id == workspace)
+            .ok_or(StatusCode::NOT_FOUND)?;
+        strategies.remove(index)
+    };
+    
+    let mut results = Vec::new();
+    state.backtest_results.write().await.retain(|r| {
+        let owned = r.strategy_id == strategy_id && r.workspace_id == workspace;
+        if owned {
+            results.push(r.clone());
+        }
+        !owned
+    });
+    
+    state.trash.put(&workspace, header(&headers, USER_HEADER), TrashedItem::Strategy { strategy, results });
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Move a backtest result to the trash
+pub async fn delete_backtest_result(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let result = {
+        let mut results = state.backtest_results.write().await;
+        let index = results.iter()
+            .position(|r| r.id == id && r.workspace_id == workspace)
+            .ok_or(StatusCode::NOT_FOUND)?;
+        results.remove(index)
+    };
+    
+    state.trash.put(&workspace, header(&headers, USER_HEADER), TrashedItem::Result { result });
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// List the workspace's deleted strategies and results
+pub async fn list_trash(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<TrashEntry>>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    Ok(Json(state.trash.list(&workspace)))
+}
+
+/// Put a deleted strategy or result back
+///
+/// A result whose strategy is itself in the trash is refused with 409;
+/// restore the strategy instead, which brings its results back with it.
+pub async fn restore_trash_entry(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<TrashEntry>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let parent_trashed = state.trash.list(&workspace).iter().any(|entry| match &entry.item {
+        TrashedItem::Strategy { results, .. } => results.iter().any(|r| r.id == id),
+        TrashedItem::Result { result } => {
+            result.id == id && state.trash.contains(&workspace, &result.strategy_id)
+        }
+    });
+    if parent_trashed {
+        return Err(StatusCode::CONFLICT);
+    }
+    
+    let entry = state.trash.take(&workspace, &id).ok_or(StatusCode::NOT_FOUND)?;
+    match &entry.item {
+        TrashedItem::Strategy { strategy, results } => {
+            state.strategies.write().await.push(strategy.clone());
+            state.backtest_results.write().await.extend(results.iter().cloned());
+        }
+        TrashedItem::Result { result } => {
+            state.backtest_results.write().await.push(result.clone());
+        }
+    }
+    Ok(Json(entry))
+}
+
+/// Permanently delete a trashed strategy or result ahead of its retention
+pub async fn purge_trash_entry(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    state.trash.take(&workspace, &id)
+        .map(|_| StatusCode::NO_CONTENT)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Export a strategy with its presets, dataset metadata and recent results
+///
+/// Presets are left out when no database is configured.
+pub async fn export_strategy(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(strategy_id): Path<String>,
+) -> Result<Json<StrategyBundle>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let strategy = state.strategies.read().await.iter()
+        .find(|s| s.id == strategy_id && s.workspace_id == workspace)
+        .cloned()
+        .ok_or(StatusCode::NOT_FOUND)?;
+    
+    let presets = match state.presets.as_ref() {
+        Some(store) => store.list(&workspace, &strategy_id).await
+            .map_err(preset_status)?
+            .into_iter()
+            .map(|preset| PresetDraft {
+                name: preset.name,
+                parameters: preset.parameters,
+                favorite: preset.favorite,
+                source_optimization_id: preset.source_optimization_id,
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    
+    let results: Vec<BacktestResult> = state.backtest_results.read().await.iter()
+        .filter(|r| r.strategy_id == strategy_id && r.workspace_id == workspace)
+        .cloned()
+        .collect();
+    let bundle = StrategyBundle::new(strategy)
+        .with_presets(presets)
+        .with_results(results);
+    
+    // Datasets upstream of the bundled results in the lineage graph
+    let mut dataset_nodes = HashSet::new();
+    for result in &bundle.results {
+        for node in state.lineage.find_by_name(&result.id) {
+            if let Ok(trace) = state.lineage.trace(&node.id) {
+                dataset_nodes.extend(trace.upstream.into_iter()
+                    .filter(|n| n.kind == ArtifactKind::Dataset)
+                    .map(|n| n.id));
+            }
+        }
+    }
+    let datasets = state.catalog.list().into_iter()
+        .filter(|entry| entry.visible_to(&workspace))
+        .filter(|entry| entry.lineage_id.as_ref().map_or(false, |id| dataset_nodes.contains(id)))
+        .collect();
+    
+    Ok(Json(bundle.with_datasets(datasets)))
+}
+
+/// Recreate an exported strategy in the calling workspace
+///
+/// The strategy and its results get fresh ids. Dataset files are not part
+/// of a bundle; datasets this instance lacks are listed in the report.
+pub async fn import_strategy(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(bundle): Json<StrategyBundle>,
+) -> Result<Json<ImportReport>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    bundle.validate().map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+    if !bundle.presets.is_empty() {
+        preset_store(&state).map_err(preset_status)?;
+    }
+    
+    let (mut strategy, results) = bundle.rebase(&workspace);
+    let strategy_id = strategy.id.clone();
+    // Lifecycle evidence points at the source instance's results and gates;
+    // promotions have to be earned again here
+    strategy.status = LifecycleState::Draft;
+    strategy.lifecycle.clear();
+    
+    // Optimizations the presets were promoted from stay behind
+    let mut presets_imported = 0;
+    if let Some(store) = state.presets.as_ref() {
+        for preset in &bundle.presets {
+            let draft = PresetDraft { source_optimization_id: None, ..preset.clone() };
+            store.save(&workspace, &strategy_id, draft).await.map_err(preset_status)?;
+            presets_imported += 1;
+        }
+    }
+    
+    let local = state.catalog.list().into_iter()
+        .filter(|entry| entry.visible_to(&workspace))
+        .map(|entry| (entry.id.clone(), entry))
+        .collect();
+    let missing_datasets = bundle.missing_datasets(&local);
+    
+    let results_imported = results.len();
+    state.strategies.write().await.push(strategy);
+    state.backtest_results.write().await.extend(results);
+    
+    Ok(Json(ImportReport {
+        strategy_id,
+        presets_imported,
+        results_imported,
+        missing_datasets,
+    }))
+}
+
+/// Sync the workspace's strategy catalog with a JSON or YAML manifest
+///
+/// The manifest is read as YAML when the `Content-Type` says so. Every
+/// entry is checked before anything is written, so a manifest with one bad
+/// source changes nothing.
+pub async fn sync_strategy_manifest(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<ManifestSyncReport>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let format = ManifestFormat::from_content_type(header(&headers, "content-type"));
+    let manifest = StrategyManifest::parse(&body, format).map_err(|e| {
+        warn!("Rejected strategy manifest: {}", e);
+        StatusCode::UNPROCESSABLE_ENTITY
+    })?;
+    for entry in &manifest.strategies {
+        state.code_archive.capture(&entry.source).map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+    }
+    if manifest.strategies.iter().any(|entry| !entry.presets.is_empty()) {
+        preset_store(&state).map_err(preset_status)?;
+    }
+    
+    let applied = manifest.apply(&mut *state.strategies.write().await, &workspace);
+    
+    let mut report = ManifestSyncReport::default();
+    for (entry, (action, strategy_id)) in manifest.strategies.iter().zip(applied) {
+        let mut presets = 0;
+        if let (Some(store), true) = (state.presets.as_ref(), action != ManifestAction::Skipped) {
+            for preset in &entry.presets {
+                let draft = PresetDraft { source_optimization_id: None, ..preset.clone() };
+                store.save(&workspace, &strategy_id, draft).await.map_err(preset_status)?;
+                presets += 1;
+            }
+        }
+        report.push(ManifestOutcome { name: entry.name.trim().to_string(), strategy_id, action, presets });
+    }
+    
+    Ok(Json(report))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct RunOptimizationRequest {
     pub strategy_id: String,
     pub optimization_type: String,
     pub parameters: serde_json::Value,
+    /// Lineage ids of the backtests or datasets this optimization builds on
+    #[serde(default)]
+    pub inputs: Vec<String>,
+    /// Saved preset to seed the search with as an initial candidate
+    #[serde(default)]
+    pub preset: Option<String>,
+    /// Cataloged dataset each candidate is backtested over
+    #[serde(default)]
+    pub dataset_id: Option<String>,
+    /// Start of the backtests, as for `/api/backtest`; the engine's default window when unset
+    #[serde(default)]
+    pub start_date: Option<String>,
+    /// End of the backtests, as for `/api/backtest`
+    #[serde(default)]
+    pub end_date: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct RunOptimizationResponse {
     pub optimization_id: String,
     pub message: String,
+    /// Status of the optimization an earlier submission with the same
+    /// idempotency key started
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<JobStatus>,
 }
 
+/// Generations of an optimization started through the API
+const OPTIMIZATION_GENERATIONS: usize = 10;
+
+/// Candidates per generation of an optimization started through the API
+const OPTIMIZATION_POPULATION: usize = 20;
+
 /// Run optimization
+///
+/// A genetic search over the request's parameter ranges, each candidate
+/// backtested over the dataset. The search runs as a background job that
+/// finishes on the job board when it completes, fails or is cancelled, and
+/// takes one of the workspace's concurrent job slots until then.
+/// A request repeating the `Idempotency-Key` of an earlier one returns the
+/// optimization that request started instead of starting another.
 pub async fn run_optimization(
-    State(_state): State<ApiState>,
+    State(state): State<ApiState>,
+    headers: HeaderMap,
     Json(req): Json<RunOptimizationRequest>,
 ) -> Result<Json<RunOptimizationResponse>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let reservation = match claim_idempotency_key(&state, &headers, &workspace, JobKind::Optimization, &req)? {
+        Some(Claim::Existing(optimization_id)) => return Ok(Json(RunOptimizationResponse {
+            status: state.job_board.status(&optimization_id),
+            optimization_id,
+            message: "Optimization already submitted".to_string(),
+        })),
+        Some(Claim::New(reservation)) => Some(reservation),
+        None => None,
+    };
+    admit_batch(&state)?;
+    let seed = match &req.preset {
+        Some(name) => Some(preset_store(&state)
+            .map_err(preset_status)?
+            .get(&workspace, &req.strategy_id, name)
+            .await
+            .map_err(preset_status)?),
+        None => None,
+    };
+    let strategy_info = state.strategies.read().await.iter()
+        .find(|s| s.id == req.strategy_id && s.workspace_id == workspace)
+        .cloned()
+        .ok_or_else(|| submit_status(SubmitError::UnknownStrategy(req.strategy_id.clone())))?;
+    let template = match &strategy_info.source {
+        StrategySource::Template { name } => TemplateBuilder::named(name).ok_or(StatusCode::BAD_REQUEST)?,
+        _ => return Err(submit_status(SubmitError::NotRunnable(strategy_info.name))),
+    };
+    let dataset_id = req.dataset_id.as_ref().ok_or_else(|| submit_status(SubmitError::NoData))?;
+    let dataset = state.catalog.get(dataset_id)
+        .filter(|entry| entry.visible_to(&workspace))
+        .ok_or_else(|| submit_status(SubmitError::UnknownDataset(dataset_id.clone())))?;
+    let mut engine_config = BacktestConfig::default();
+    if let Some(start) = &req.start_date {
+        engine_config.start_date = run_bound(start, false).map_err(submit_status)?;
+    }
+    if let Some(end) = &req.end_date {
+        engine_config.end_date = run_bound(end, true).map_err(submit_status)?;
+    }
+    // Register a steering handle so the run can be controlled while in flight
+    let parameter_bounds = parse_parameter_bounds(&req.parameters);
+    let control = OptimizationControl::new(parameter_bounds.clone());
+    if let Some(preset) = seed {
+        control.inject_candidate(preset_candidate(&preset))
+            .map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+    }
+    
+    state.workspaces.start_job(&workspace).map_err(workspace_status)?;
+    let slot = JobSlot {
+        workspaces: state.workspaces.clone(),
+        workspace_id: workspace.clone(),
+        started: std::time::Instant::now(),
+    };
     let optimization_id = Uuid::new_v4().to_string();
     
-    // In a real implementation, this would spawn optimization
+    state.evaluation_store.record_owner(&optimization_id, &workspace).map_err(|e| {
+        warn!("Could not record the workspace of optimization {}: {}", optimization_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    state.lineage.record_in(
+        &workspace,
+        ArtifactKind::Optimization,
+        optimization_id.clone(),
+        &req.inputs,
+        serde_json::json!({
+            "strategy_id": req.strategy_id,
+            "optimization_type": req.optimization_type,
+            "preset": req.preset,
+            "dataset_id": req.dataset_id,
+        }),
+    );
+    state.optimization_controls.write().await.insert(optimization_id.clone(), control.clone());
+    state.job_board.start(&optimization_id, JobKind::Optimization, &workspace, req.strategy_id.clone());
+    if let Some(reservation) = reservation {
+        reservation.complete(&optimization_id);
+    }
+    
+    let optimizer = GeneticOptimizer::new(GeneticConfig {
+        population_size: OPTIMIZATION_POPULATION,
+        generations: OPTIMIZATION_GENERATIONS,
+        mutation_rate: 0.1,
+        crossover_rate: 0.8,
+        selection_strategy: SelectionStrategy::Tournament,
+        elite_size: 2,
+        tournament_size: 3,
+        objective: ObjectiveFunction::SharpeRatio,
+        parameter_bounds,
+        parameter_constraints: Vec::new(),
+        constraint_handling: ConstraintHandling::default(),
+        seed: None,
+        snapshot_populations: false,
+    })
+    .with_control(control)
+    .with_evaluation_store(state.evaluation_store.clone(), optimization_id.clone());
+    let task_state = state.clone();
+    let task_id = optimization_id.clone();
+    let span = info_span!("optimization_job", job_id = %optimization_id, strategy = %req.strategy_id);
+    spawn_job(&state, &optimization_id, async move {
+        let outcome = match run_optimizer(optimizer, template, strategy_info.parameters, dataset, engine_config).await {
+            Ok(results) => {
+                info!("Optimization {} finished with {} results", task_id, results);
+                JobOutcome::Completed
+            }
+            Err(e) => {
+                warn!("Optimization {} failed: {}", task_id, e);
+                JobOutcome::Failed
+            }
+        };
+        task_state.optimization_controls.write().await.remove(&task_id);
+        task_state.jobs.write().await.remove(&task_id);
+        task_state.job_board.finish(&task_id, outcome);
+        drop(slot);
+    }.instrument(span)).await;
     
     Ok(Json(RunOptimizationResponse {
         optimization_id,
         message: format!("Started {} optimization", req.optimization_type),
+        status: None,
     }))
 }
 
-/// Get system metrics
-pub async fn get_system_metrics(
+/// Run `optimizer` over `dataset`, building each candidate from `template`
+/// with the candidate's values over the strategy's stored `parameters`
+///
+/// The search evaluates its candidates on its own threads, so it runs on a
+/// blocking thread. Returns the number of results.
+async fn run_optimizer(
+    mut optimizer: GeneticOptimizer,
+    template: TemplateBuilder,
+    parameters: serde_json::Value,
+    dataset: DatasetEntry,
+    config: BacktestConfig,
+) -> Result<usize, String> {
+    let runtime = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || {
+        let factory = move |candidate: ParameterSet| {
+            let mut values = parameters.clone();
+            if let (Some(values), Ok(serde_json::Value::Object(candidate))) =
+                (values.as_object_mut(), serde_json::to_value(&candidate.parameters))
+            {
+                values.extend(candidate);
+            }
+            template.build(&values)
+        };
+        runtime.block_on(optimizer.optimize(factory, config, &dataset.path))
+            .map(|results| results.len())
+            .map_err(|e| e.to_string())
+    }).await.map_err(|e| e.to_string())?
+}
+
+/// Numeric values of a preset as an optimization candidate
+fn preset_candidate(preset: &ParameterPreset) -> ParameterSet {
+    let values = preset.parameters.as_object()
+        .map(|object| {
+            object.iter()
+                .filter_map(|(name, value)| Some((name.clone(), value.as_f64()?)))
+                .collect()
+        })
+        .unwrap_or_default();
+    ParameterSet::from_hashmap(values)
+}
+
+/// Extract `{"name": {"min": x, "max": y}}` ranges from a request payload
+fn parse_parameter_bounds(parameters: &serde_json::Value) -> HashMap<String, (f64, f64)> {
+    parameters.as_object()
+        .map(|object| {
+            object.iter()
+                .filter_map(|(name, range)| {
+                    let min = range.get("min")?.as_f64()?;
+                    let max = range.get("max")?.as_f64()?;
+                    Some((name.clone(), (min, max)))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Check that optimization `id` runs in the caller's workspace and return
+/// the workspace
+///
+/// Optimizations of other workspaces give 404, as unknown ones do.
+fn scoped_optimization(state: &ApiState, headers: &HeaderMap, id: &str) -> Result<String, StatusCode> {
+    let workspace = workspace_scope(state, headers)?;
+    let owner = state.evaluation_store.owner(id).map_err(|e| match e {
+        EvalStoreError::InvalidId(_) => StatusCode::NOT_FOUND,
+        e => {
+            warn!("Could not read the workspace of optimization {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    })?;
+    if owner != workspace {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(workspace)
+}
+
+/// Get the steering state of a running optimization
+pub async fn get_optimization_control(
     State(state): State<ApiState>,
-) -> Result<Json<SystemMetrics>, StatusCode> {
-    let monitor = ResourceMonitor::new();
-    let usage = monitor.get_current_usage();
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<SteeringStatus>, StatusCode> {
+    scoped_optimization(&state, &headers, &id)?;
+    let controls = state.optimization_controls.read().await;
+    let control = controls.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(control.status()))
+}
+
+/// Pause, resume, narrow a range or inject a candidate into a running optimization
+pub async fn steer_optimization(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(command): Json<SteeringCommand>,
+) -> Result<Json<SteeringStatus>, StatusCode> {
+    scoped_optimization(&state, &headers, &id)?;
+    let controls = state.optimization_controls.read().await;
+    let control = controls.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+    control.apply(command).map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+    Ok(Json(control.status()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PartialResultsQuery {
+    /// Number of leading parameter sets to return
+    #[serde(default = "default_top")]
+    pub top: usize,
+}
+
+fn default_top() -> usize {
+    10
+}
+
+#[derive(Debug, Serialize)]
+pub struct PartialResultsResponse {
+    pub optimization_id: String,
+    /// Whether the optimization still has a live control handle
+    pub running: bool,
+    /// Evaluations persisted so far
+    pub evaluations: usize,
+    /// Best parameter sets so far, highest objective first
+    pub results: Vec<EvaluationRecord>,
+}
+
+/// Current top-K parameter sets of an optimization, available while it runs
+pub async fn get_optimization_results(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(query): Query<PartialResultsQuery>,
+) -> Result<Json<PartialResultsResponse>, StatusCode> {
+    scoped_optimization(&state, &headers, &id)?;
+    let running = state.optimization_controls.read().await.contains_key(&id);
     
-    let metrics = SystemMetrics {
-        cpu_usage: usage.cpu_percent,
-        memory_gb: usage.memory_gb,
-        active_threads: usage.active_threads,
-        ticks_processed: 0, // Would get from actual processing
+    let store = state.evaluation_store.clone();
+    let lookup_id = id.clone();
+    let top = tokio::task::spawn_blocking(move || store.top_k(&lookup_id, query.top))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|e| match e {
+            EvalStoreError::InvalidId(_) => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+    
+    let (results, evaluations) = match top {
+        Some(top) => top,
+        // Started but nothing evaluated yet
+        None if running => (Vec::new(), 0),
+        None => return Err(StatusCode::NOT_FOUND),
     };
     
-    // Update cached metrics
-    let mut cached = state.system_metrics.write().await;
-    *cached = metrics.clone();
+    Ok(Json(PartialResultsResponse {
+        optimization_id: id,
+        running,
+        evaluations,
+        results,
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConvergenceResponse {
+    pub optimization_id: String,
+    /// Whether the optimization still has a live control handle
+    pub running: bool,
+    pub curve: ConvergenceCurve,
+}
+
+/// Per-generation or per-batch best and mean objective of an optimization,
+/// with whether the best value has stopped improving
+pub async fn get_optimization_convergence(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ConvergenceResponse>, StatusCode> {
+    scoped_optimization(&state, &headers, &id)?;
+    let running = state.optimization_controls.read().await.contains_key(&id);
     
-    Ok(Json(metrics))
-}
\ No newline at end of file
+    let store = state.evaluation_store.clone();
+    let lookup_id = id.clone();
+    let points = tokio::task::spawn_blocking(move || store.load_convergence(&lookup_id))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|e| match e {
+            EvalStoreError::InvalidId(_) => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+    
+    let points = match points {
+        Some(points) => points,
+        // Started but no generation or batch finished yet
+        None if running => Vec::new(),
+        None => return Err(StatusCode::NOT_FOUND),
+    };
+    
+    Ok(Json(ConvergenceResponse {
+        optimization_id: id,
+        running,
+        curve: ConvergenceCurve::from_points(points),
+    }))
+}
+
+/// Error response of the analytics endpoints, with the reason a query was
+/// rejected or failed
+#[cfg(feature = "analytics")]
+type AnalyticsRejection = (StatusCode, Json<AnalyticsErrorResponse>);
+
+#[cfg(feature = "analytics")]
+#[derive(Debug, Serialize)]
+pub struct AnalyticsErrorResponse {
+    pub error: String,
+}
+
+#[cfg(feature = "analytics")]
+fn analytics_rejection(e: AnalyticsError) -> AnalyticsRejection {
+    let status = match &e {
+        AnalyticsError::NotSynced(_) => StatusCode::NOT_FOUND,
+        AnalyticsError::Rejected(_) | AnalyticsError::Database(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        AnalyticsError::InvalidWorkspace(_) => StatusCode::BAD_REQUEST,
+        AnalyticsError::Evaluations(_) | AnalyticsError::Io(_) | AnalyticsError::Serialization(_) => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, Json(AnalyticsErrorResponse { error: e.to_string() }))
+}
+
+#[cfg(feature = "analytics")]
+fn analytics_status(status: StatusCode) -> AnalyticsRejection {
+    let error = status.canonical_reason().unwrap_or_default().to_string();
+    (status, Json(AnalyticsErrorResponse { error }))
+}
+
+/// Rebuild the workspace's analytics database from its trade ledgers and
+/// the persisted optimization evaluations
+#[cfg(feature = "analytics")]
+pub async fn sync_analytics(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+) -> Result<Json<SyncReport>, AnalyticsRejection> {
+    let workspace = workspace_scope(&state, &headers).map_err(analytics_status)?;
+    let results: Vec<(String, String)> = state.backtest_results.read().await.iter()
+        .filter(|r| r.workspace_id == workspace)
+        .map(|r| (r.id.clone(), r.strategy_id.clone()))
+        .collect();
+    let ledgers: Vec<(String, String, Arc<Vec<TradeRecord>>)> = {
+        let ledgers = state.trade_ledgers.read().await;
+        results.into_iter()
+            .filter_map(|(id, strategy)| ledgers.get(&id).cloned().map(|trades| (id, strategy, trades)))
+            .collect()
+    };
+    
+    let evaluation_store = state.evaluation_store.clone();
+    let analytics = state.analytics.clone();
+    let report = tokio::task::spawn_blocking(move || {
+        let mut evaluations = Vec::new();
+        for id in evaluation_store.optimization_ids_in(&workspace)? {
+            if let Ok(Some(records)) = evaluation_store.load(&id) {
+                evaluations.push((id, records));
+            }
+        }
+        let mirrors: Vec<LedgerMirror<'_>> = ledgers.iter()
+            .map(|(result_id, strategy_id, trades)| LedgerMirror { result_id, strategy_id, trades: trades.as_slice() })
+            .collect();
+        analytics.sync(&workspace, &mirrors, &evaluations)
+    })
+        .await
+        .map_err(|_| analytics_status(StatusCode::INTERNAL_SERVER_ERROR))?
+        .map_err(analytics_rejection)?;
+    Ok(Json(report))
+}
+
+#[cfg(feature = "analytics")]
+#[derive(Debug, Deserialize)]
+pub struct AnalyticsQueryRequest {
+    /// A single `SELECT` or `WITH` query over `trades` and `evaluations`
+    pub sql: String,
+    #[serde(default = "default_analytics_limit")]
+    pub limit: usize,
+}
+
+#[cfg(feature = "analytics")]
+fn default_analytics_limit() -> usize {
+    DEFAULT_ROW_LIMIT
+}
+
+/// Run a read-only SQL query against the workspace's last analytics sync
+#[cfg(feature = "analytics")]
+pub async fn query_analytics(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(req): Json<AnalyticsQueryRequest>,
+) -> Result<Json<QueryResult>, AnalyticsRejection> {
+    let workspace = workspace_scope(&state, &headers).map_err(analytics_status)?;
+    let analytics = state.analytics.clone();
+    tokio::task::spawn_blocking(move || analytics.query(&workspace, &req.sql, req.limit))
+        .await
+        .map_err(|_| analytics_status(StatusCode::INTERNAL_SERVER_ERROR))?
+        .map(Json)
+        .map_err(analytics_rejection)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PromoteResultRequest {
+    /// Name of the preset to create or replace
+    pub name: String,
+    /// Position in the current ranking, 0 being the best
+    #[serde(default)]
+    pub rank: usize,
+    #[serde(default)]
+    pub favorite: bool,
+    /// Strategy the preset belongs to; defaults to the optimization's
+    #[serde(default)]
+    pub strategy_id: Option<String>,
+}
+
+/// Save an optimization's top (or `rank`-th) parameter set as a named preset
+///
+/// Optimizations and strategies of other workspaces give 404.
+pub async fn promote_optimization_result(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<PromoteResultRequest>,
+) -> Result<Json<ParameterPreset>, StatusCode> {
+    let workspace = scoped_optimization(&state, &headers, &id)?;
+    let presets = preset_store(&state).map_err(preset_status)?;
+    
+    let strategy_id = req.strategy_id
+        .or_else(|| {
+            state.lineage.find_by_name(&id).into_iter()
+                .find_map(|node| node.metadata.get("strategy_id")?.as_str().map(str::to_string))
+        })
+        .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+    if !state.strategies.read().await.iter().any(|s| s.id == strategy_id && s.workspace_id == workspace) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    
+    let store = state.evaluation_store.clone();
+    let lookup_id = id.clone();
+    let rank = req.rank;
+    let (mut results, _) = tokio::task::spawn_blocking(move || store.top_k(&lookup_id, rank + 1))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|_| StatusCode::NOT_FOUND)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if results.len() <= rank {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let winner = results.swap_remove(rank);
+    
+    let draft = PresetDraft {
+        name: req.name,
+        parameters: serde_json::to_value(&winner.parameters.parameters)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+        favorite: req.favorite,
+        source_optimization_id: Some(id),
+    };
+    presets.save(&workspace, &strategy_id, draft).await
+        .map(Json)
+        .map_err(preset_status)
+}
+
+/// List a strategy's saved presets, favorites first
+pub async fn list_presets(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(strategy_id): Path<String>,
+) -> Result<Json<Vec<ParameterPreset>>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    preset_store(&state).map_err(preset_status)?
+        .list(&workspace, &strategy_id).await
+        .map(Json)
+        .map_err(preset_status)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SavePresetRequest {
+    pub name: String,
+    pub parameters: serde_json::Value,
+    #[serde(default)]
+    pub favorite: bool,
+}
+
+/// Save a named preset, replacing one of the same name
+pub async fn save_preset(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(strategy_id): Path<String>,
+    Json(req): Json<SavePresetRequest>,
+) -> Result<Json<ParameterPreset>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let draft = PresetDraft {
+        name: req.name,
+        parameters: req.parameters,
+        favorite: req.favorite,
+        source_optimization_id: None,
+    };
+    preset_store(&state).map_err(preset_status)?
+        .save(&workspace, &strategy_id, draft).await
+        .map(Json)
+        .map_err(preset_status)
+}
+
+pub async fn get_preset(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path((strategy_id, name)): Path<(String, String)>,
+) -> Result<Json<ParameterPreset>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    preset_store(&state).map_err(preset_status)?
+        .get(&workspace, &strategy_id, &name).await
+        .map(Json)
+        .map_err(preset_status)
+}
+
+pub async fn delete_preset(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path((strategy_id, name)): Path<(String, String)>,
+) -> Result<StatusCode, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    preset_store(&state).map_err(preset_status)?
+        .delete(&workspace, &strategy_id, &name).await
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(preset_status)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetFavoriteRequest {
+    pub favorite: bool,
+}
+
+/// Mark or unmark a preset as a favorite
+pub async fn set_preset_favorite(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path((strategy_id, name)): Path<(String, String)>,
+    Json(req): Json<SetFavoriteRequest>,
+) -> Result<Json<ParameterPreset>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    preset_store(&state).map_err(preset_status)?
+        .set_favorite(&workspace, &strategy_id, &name, req.favorite).await
+        .map(Json)
+        .map_err(preset_status)
+}
+
+/// Error response of the workflow template endpoints; lists the problems
+/// found when a template fails validation
+type TemplateRejection = (StatusCode, Json<Vec<TemplateIssue>>);
+
+fn template_rejection(e: WorkflowTemplateError) -> TemplateRejection {
+    let status = match &e {
+        WorkflowTemplateError::NotFound(_) => StatusCode::NOT_FOUND,
+        WorkflowTemplateError::AlreadyExists(_) | WorkflowTemplateError::Conflict(_) => StatusCode::CONFLICT,
+        WorkflowTemplateError::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        WorkflowTemplateError::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        WorkflowTemplateError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    let issues = match e {
+        WorkflowTemplateError::Invalid(issues) => issues,
+        _ => Vec::new(),
+    };
+    (status, Json(issues))
+}
+
+fn template_store(state: &ApiState) -> Result<&WorkflowTemplateStore, TemplateRejection> {
+    state.workflow_templates.as_ref()
+        .ok_or_else(|| template_rejection(WorkflowTemplateError::Unavailable))
+}
+
+/// Scope a template request to its workspace, rejecting it like any template error
+fn template_scope(state: &ApiState, headers: &HeaderMap) -> Result<String, TemplateRejection> {
+    workspace_scope(state, headers).map_err(|status| (status, Json(Vec::new())))
+}
+
+/// List the workspace's workflow templates
+pub async fn list_workflow_templates(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<WorkflowTemplate>>, TemplateRejection> {
+    let workspace = template_scope(&state, &headers)?;
+    template_store(&state)?
+        .list(&workspace).await
+        .map(Json)
+        .map_err(template_rejection)
+}
+
+/// Create a workflow template; fails if one with the same id exists
+pub async fn create_workflow_template(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(draft): Json<WorkflowTemplateDraft>,
+) -> Result<Json<WorkflowTemplate>, TemplateRejection> {
+    let workspace = template_scope(&state, &headers)?;
+    let author = header(&headers, USER_HEADER).unwrap_or("anonymous");
+    template_store(&state)?
+        .create(&workspace, author, draft).await
+        .map(Json)
+        .map_err(template_rejection)
+}
+
+/// Check a template without saving it
+pub async fn validate_workflow_template(
+    headers: HeaderMap,
+    Json(draft): Json<WorkflowTemplateDraft>,
+) -> Json<Vec<TemplateIssue>> {
+    let author = header(&headers, USER_HEADER).unwrap_or("anonymous");
+    Json(check_user_workflow(&draft.into_workflow(author, 1)).err().unwrap_or_default())
+}
+
+pub async fn get_workflow_template(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<WorkflowTemplate>, TemplateRejection> {
+    let workspace = template_scope(&state, &headers)?;
+    template_store(&state)?
+        .get(&workspace, &id).await
+        .map(Json)
+        .map_err(template_rejection)
+}
+
+/// Replace a workflow template, bumping its version
+pub async fn update_workflow_template(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(mut draft): Json<WorkflowTemplateDraft>,
+) -> Result<Json<WorkflowTemplate>, TemplateRejection> {
+    let workspace = template_scope(&state, &headers)?;
+    let author = header(&headers, USER_HEADER).unwrap_or("anonymous");
+    draft.id = id;
+    template_store(&state)?
+        .update(&workspace, author, draft).await
+        .map(Json)
+        .map_err(template_rejection)
+}
+
+pub async fn delete_workflow_template(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, TemplateRejection> {
+    let workspace = template_scope(&state, &headers)?;
+    template_store(&state)?
+        .delete(&workspace, &id).await
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(template_rejection)
+}
+
+/// Error response of the help article endpoints; lists the problems found
+/// when an article fails its checks
+type HelpRejection = (StatusCode, Json<Vec<HelpArticleIssue>>);
+
+fn help_rejection(e: HelpArticleError) -> HelpRejection {
+    let status = match &e {
+        HelpArticleError::NotFound(_) => StatusCode::NOT_FOUND,
+        HelpArticleError::AlreadyExists(_) | HelpArticleError::Conflict(_) => StatusCode::CONFLICT,
+        HelpArticleError::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        HelpArticleError::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        HelpArticleError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    let issues = match e {
+        HelpArticleError::Invalid(issues) => issues,
+        _ => Vec::new(),
+    };
+    (status, Json(issues))
+}
+
+/// The user changing help articles, which every workspace reads; anonymous
+/// callers get 401
+fn help_author(headers: &HeaderMap) -> Result<&str, HelpRejection> {
+    header(headers, USER_HEADER).ok_or((StatusCode::UNAUTHORIZED, Json(Vec::new())))
+}
+
+fn help_store(state: &ApiState) -> Result<&HelpArticleStore, HelpRejection> {
+    state.help_articles.as_ref()
+        .ok_or_else(|| help_rejection(HelpArticleError::Unavailable))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HelpQuery {
+    /// Search terms, in web search syntax
+    #[serde(default)]
+    pub q: String,
+    /// Only articles covering this step type, by name
+    pub step: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// List help articles, optionally only those for one step type
+pub async fn list_help_articles(
+    State(state): State<ApiState>,
+    Query(query): Query<HelpQuery>,
+) -> Result<Json<Vec<StoredHelpArticle>>, HelpRejection> {
+    let step = query.step.as_deref().map(WorkflowStepType::from_name);
+    help_store(&state)?
+        .list(step.as_ref()).await
+        .map(Json)
+        .map_err(help_rejection)
+}
+
+/// Full-text search of help articles, best match first
+pub async fn search_help_articles(
+    State(state): State<ApiState>,
+    Query(query): Query<HelpQuery>,
+) -> Result<Json<Vec<HelpSearchHit>>, HelpRejection> {
+    if query.q.trim().is_empty() {
+        return Ok(Json(Vec::new()));
+    }
+    let step = query.step.as_deref().map(WorkflowStepType::from_name);
+    help_store(&state)?
+        .search(&query.q, step.as_ref(), query.limit.unwrap_or(MAX_SEARCH_RESULTS)).await
+        .map(Json)
+        .map_err(help_rejection)
+}
+
+/// Publish a help article; fails if one with the same id exists
+pub async fn create_help_article(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(article): Json<HelpArticle>,
+) -> Result<Json<StoredHelpArticle>, HelpRejection> {
+    let author = help_author(&headers)?;
+    let stored = help_store(&state)?
+        .create(author, article).await
+        .map_err(help_rejection)?;
+    state.workflows.write().await.load_help_articles(std::slice::from_ref(&stored.content.0));
+    Ok(Json(stored))
+}
+
+pub async fn get_help_article(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+) -> Result<Json<StoredHelpArticle>, HelpRejection> {
+    help_store(&state)?
+        .get(&id).await
+        .map(Json)
+        .map_err(help_rejection)
+}
+
+/// Replace a help article, bumping its version
+pub async fn update_help_article(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(mut article): Json<HelpArticle>,
+) -> Result<Json<StoredHelpArticle>, HelpRejection> {
+    let author = help_author(&headers)?;
+    article.content.id = id;
+    let stored = help_store(&state)?
+        .update(author, article).await
+        .map_err(help_rejection)?;
+    state.workflows.write().await.load_help_articles(std::slice::from_ref(&stored.content.0));
+    Ok(Json(stored))
+}
+
+pub async fn delete_help_article(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, HelpRejection> {
+    let user = help_author(&headers)?;
+    info!("User {} is deleting help article {}", user, id);
+    help_store(&state)?
+        .delete(&id).await
+        .map_err(help_rejection)?;
+    state.workflows.write().await.remove_help_article(&id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn analysis_view_status(e: AnalysisViewError) -> StatusCode {
+    match e {
+        AnalysisViewError::NotFound(_) => StatusCode::NOT_FOUND,
+        AnalysisViewError::NotOwner(_) => StatusCode::FORBIDDEN,
+        AnalysisViewError::Conflict(_) => StatusCode::CONFLICT,
+        AnalysisViewError::MissingName => StatusCode::UNPROCESSABLE_ENTITY,
+        AnalysisViewError::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        AnalysisViewError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn analysis_view_store(state: &ApiState) -> Result<&AnalysisViewStore, StatusCode> {
+    state.analysis_views.as_ref()
+        .ok_or_else(|| analysis_view_status(AnalysisViewError::Unavailable))
+}
+
+/// List the caller's views and the views shared in the workspace
+pub async fn list_analysis_views(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<AnalysisView>>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let user = header(&headers, USER_HEADER).unwrap_or("anonymous");
+    analysis_view_store(&state)?
+        .list(&workspace, user).await
+        .map(Json)
+        .map_err(analysis_view_status)
+}
+
+/// Save a new view owned by the caller
+pub async fn create_analysis_view(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(draft): Json<AnalysisViewDraft>,
+) -> Result<Json<AnalysisView>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let owner = header(&headers, USER_HEADER).unwrap_or("anonymous");
+    analysis_view_store(&state)?
+        .create(&workspace, owner, draft).await
+        .map(Json)
+        .map_err(analysis_view_status)
+}
+
+/// Open a view by id; this is what shared view links resolve to
+pub async fn get_analysis_view(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AnalysisView>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let user = header(&headers, USER_HEADER).unwrap_or("anonymous");
+    analysis_view_store(&state)?
+        .get(&workspace, user, id).await
+        .map(Json)
+        .map_err(analysis_view_status)
+}
+
+/// Replace one of the caller's views, bumping its version
+pub async fn update_analysis_view(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(draft): Json<AnalysisViewDraft>,
+) -> Result<Json<AnalysisView>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let user = header(&headers, USER_HEADER).unwrap_or("anonymous");
+    analysis_view_store(&state)?
+        .update(&workspace, user, id, draft).await
+        .map(Json)
+        .map_err(analysis_view_status)
+}
+
+pub async fn delete_analysis_view(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let user = header(&headers, USER_HEADER).unwrap_or("anonymous");
+    analysis_view_store(&state)?
+        .delete(&workspace, user, id).await
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(analysis_view_status)
+}
+
+/// Get system metrics
+pub async fn get_system_metrics(
+    State(state): State<ApiState>,
+) -> Result<Json<SystemMetrics>, StatusCode> {
+    let monitor = ResourceMonitor::new();
+    let usage = monitor.get_current_usage();
+    
+    let metrics = SystemMetrics {
+        cpu_usage: usage.cpu_percent,
+        memory_gb: usage.memory_gb,
+        active_threads: usage.active_threads,
+        ticks_processed: 0, // Would get from actual processing
+    };
+    
+    // Update cached metrics
+    let mut cached = state.system_metrics.write().await;
+    *cached = metrics.clone();
+    
+    Ok(Json(metrics))
+}
+
+/// Everything the task dashboard polls for, in one payload
+///
+/// Covers the calling workspace's active jobs and recent completions, host
+/// job capacity, resource usage and the leader of each running optimization.
+pub async fn get_dashboard(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+) -> Result<Json<DashboardSnapshot>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let details = state.workspaces.get(&workspace).ok_or(StatusCode::NOT_FOUND)?;
+    let active_jobs = state.job_board.active(&workspace);
+    
+    let running = state.jobs.read().await.len();
+    let max_inflight = state.limits.config.max_inflight_jobs;
+    let queue = QueueDepth {
+        running,
+        max_inflight,
+        available: max_inflight.saturating_sub(running),
+    };
+    
+    let mut optimization_leaders = Vec::new();
+    for job in active_jobs.iter().filter(|job| job.kind == JobKind::Optimization) {
+        let store = state.evaluation_store.clone();
+        let lookup_id = job.id.clone();
+        let top = tokio::task::spawn_blocking(move || store.top_k(&lookup_id, 1))
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        // Runs that have not persisted an evaluation yet have no leader
+        let (leader, evaluations) = match top {
+            Ok(Some((mut top, total))) => (top.pop(), total),
+            _ => (None, 0),
+        };
+        optimization_leaders.push(OptimizationLeader {
+            optimization_id: job.id.clone(),
+            strategy_id: job.subject.clone(),
+            evaluations,
+            leader,
+        });
+    }
+    
+    Ok(Json(DashboardSnapshot {
+        generated_at: Utc::now(),
+        recent_completions: state.job_board.recent(&workspace),
+        workspace_id: workspace,
+        active_jobs,
+        queue,
+        resources: ResourceMonitor::new().get_current_usage(),
+        workspace_usage: details.usage,
+        workspace_quota: details.quota,
+        optimization_leaders,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigestQuery {
+    /// Length of the period ending now; a day unless given
+    pub hours: Option<i64>,
+}
+
+/// Preview the daily digest of the calling workspace
+pub async fn get_digest(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Query(query): Query<DigestQuery>,
+) -> Result<Json<DailyDigest>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let hours = query.hours.unwrap_or(24);
+    if !(1..=HISTORY_HOURS).contains(&hours) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    let to = Utc::now();
+    let digest = compile_digest(&state, Some(&workspace), to - chrono::Duration::hours(hours), to, DEFAULT_TOP_RESULTS).await;
+    Ok(Json(digest))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CorrelationRequest {
+    pub backtest_ids: Vec<String>,
+    #[serde(flatten)]
+    pub config: CorrelationConfig,
+}
+
+/// Correlate the returns of several backtests and estimate their diversification benefit
+///
+/// Unknown ids give 404; fewer than two series or too few shared periods give 422.
+pub async fn analyze_correlation(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(req): Json<CorrelationRequest>,
+) -> Result<Json<CorrelationReport>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let results = state.backtest_results.read().await;
+    let series = req.backtest_ids.iter()
+        .map(|id| {
+            results.iter()
+                .find(|r| &r.id == id && r.workspace_id == workspace)
+                .map(|r| ReturnSeries::new(id.clone(), r.daily_returns.clone()))
+                .ok_or(StatusCode::NOT_FOUND)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    drop(results);
+    
+    CorrelationReport::compute(&series, &req.config)
+        .map(Json)
+        .map_err(|e| match e {
+            CorrelationError::TooFewSeries(_) | CorrelationError::InsufficientOverlap { .. } => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+        })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RiskComparisonRequest {
+    pub backtest_ids: Vec<String>,
+    #[serde(flatten)]
+    pub config: RiskNormalizationConfig,
+}
+
+/// Compare backtests raw and scaled to equal volatility and equal drawdown
+///
+/// Unknown ids give 404; fewer than two series or too few shared days give 422.
+pub async fn compare_risk_normalized(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(req): Json<RiskComparisonRequest>,
+) -> Result<Json<RiskNormalizedComparison>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let results = state.backtest_results.read().await;
+    let series = req.backtest_ids.iter()
+        .map(|id| {
+            results.iter()
+                .find(|r| &r.id == id && r.workspace_id == workspace)
+                .map(|r| ReturnSeries::new(id.clone(), r.daily_returns.clone()))
+                .ok_or(StatusCode::NOT_FOUND)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    drop(results);
+    
+    RiskNormalizedComparison::compute(&series, &req.config)
+        .map(Json)
+        .map_err(|e| match e {
+            RiskNormalizationError::TooFewSeries(_) | RiskNormalizationError::InsufficientOverlap { .. } => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+        })
+}
+
+/// Fill ledger of a backtest in `workspace`, read from the ledger store
+/// when it is not in memory
+///
+/// Unknown results, results outside `workspace` and results without a
+/// recorded fill ledger give 404, so every ledger analysis answers them the
+/// same way.
+async fn ledger_for(state: &ApiState, workspace: &str, id: &str) -> Result<Arc<Vec<TradeRecord>>, StatusCode> {
+    if !state.backtest_results.read().await.iter().any(|r| r.id == id && r.workspace_id == workspace) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    if let Some(ledger) = state.trade_ledgers.read().await.get(id) {
+        return Ok(Arc::clone(ledger));
+    }
+    let store = state.ledger_store.clone();
+    let result_id = id.to_string();
+    let stored = tokio::task::spawn_blocking(move || store.load(&result_id))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|e| {
+            warn!("Failed to read trade ledger of {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let ledger = Arc::new(stored);
+    state.trade_ledgers.write().await.insert(id.to_string(), Arc::clone(&ledger));
+    Ok(ledger)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CostWhatIfRequest {
+    /// Scenarios to price; +1 tick slippage, doubled commissions and both when empty
+    #[serde(default)]
+    pub scenarios: Vec<CostScenario>,
+    #[serde(default)]
+    pub config: CostModelConfig,
+}
+
+/// Re-price a backtest's fills under alternative slippage and commission
+/// assumptions, returning each scenario's metrics and their change
+///
+/// An empty scenario list prices `CostScenario::standard_set`.
+pub async fn analyze_cost_what_if(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<CostWhatIfRequest>,
+) -> Result<Json<CostSensitivityReport>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let ledger = ledger_for(&state, &workspace, &id).await?;
+    
+    let scenarios = if req.scenarios.is_empty() {
+        CostScenario::standard_set()
+    } else {
+        req.scenarios
+    };
+    Ok(Json(CostSensitivityReport::compute(&ledger, &scenarios, &req.config)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OutlierRequest {
+    /// Exclusions to compare; without the best trade, the best and worst
+    /// three, and beyond 1.5x IQR when empty
+    #[serde(default)]
+    pub rules: Vec<OutlierRule>,
+    #[serde(default)]
+    pub config: CostModelConfig,
+}
+
+/// Recompute a backtest's metrics without its outlier trades, returning the
+/// metrics under each exclusion and their change
+///
+/// An empty rule list compares the exclusions of `OutlierRule::standard_set`.
+pub async fn analyze_outlier_trades(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<OutlierRequest>,
+) -> Result<Json<OutlierReport>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let ledger = ledger_for(&state, &workspace, &id).await?;
+    
+    let rules = if req.rules.is_empty() {
+        OutlierRule::standard_set()
+    } else {
+        req.rules
+    };
+    Ok(Json(OutlierReport::from_ledger(&ledger, &rules, &req.config)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StreakRequest {
+    #[serde(default)]
+    pub streaks: StreakConfig,
+    /// Sessions a stop-after-losses rule halts for
+    #[serde(default)]
+    pub calendar: SessionCalendar,
+    #[serde(default)]
+    pub config: CostModelConfig,
+}
+
+/// Analyze how a backtest's trades depend on its recent results: expectancy
+/// after loss and win streaks, in drawdown against at equity highs, and
+/// under stop-after-losses rules
+///
+/// Stop-after-losses rules halt trading for whole sessions of the
+/// request's calendar.
+pub async fn analyze_streaks(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<StreakRequest>,
+) -> Result<Json<StreakReport>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let ledger = ledger_for(&state, &workspace, &id).await?;
+
+    Ok(Json(StreakReport::from_ledger(&ledger, &req.config, &req.calendar, &req.streaks)))
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayFormat {
+    #[default]
+    Json,
+    Svg,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TradePageQuery {
+    /// `next_cursor` of the previous page
+    #[serde(default)]
+    pub cursor: usize,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// Dollars per point per contract, for P&L
+    #[serde(default)]
+    pub point_value: Option<Decimal>,
+}
+
+/// A page of a backtest's trades, filtered by opening time, side, P&L sign
+/// and order tag
+///
+/// Trades are numbered as for replays. Pages are read from the stored
+/// ledger, starting near the cursor. Unknown results and results without a
+/// stored fill ledger give 404.
+pub async fn list_backtest_trades(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(filter): Query<TradeFilter>,
+    Query(page): Query<TradePageQuery>,
+) -> Result<Json<TradePage>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    if !state.backtest_results.read().await.iter().any(|r| r.id == id && r.workspace_id == workspace) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    
+    let point_value = page.point_value.unwrap_or_else(|| CostModelConfig::default().point_value);
+    let limit = page.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let store = state.ledger_store.clone();
+    let trades = tokio::task::spawn_blocking(move || store.page(&id, &filter, page.cursor, limit, point_value))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    match trades {
+        Ok(Some(trades)) => Ok(Json(trades)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            warn!("Failed to read trade ledger: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TradeReplayRequest {
+    /// Catalog id of the dataset the backtest ran on
+    pub dataset: String,
+    /// Contract to show; the one trading when the position opened when absent
+    #[serde(default)]
+    pub contract: Option<String>,
+    #[serde(default)]
+    pub format: ReplayFormat,
+    #[serde(default)]
+    pub config: ReplayConfig,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TradeReplayExport {
+    pub replay: TradeReplay,
+    /// One image per frame, when SVG was asked for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub svg_frames: Option<Vec<String>>,
+}
+
+/// Frames of the market around one trade of a backtest, for the frontend
+/// to animate entry and exit
+///
+/// Trades are numbered from 0 in ledger order, each running from flat to
+/// flat. Trades and datasets that don't exist give 404. The frames are raw
+/// market data, so a dataset whose license keeps it internal gives 403 and
+/// one requiring attribution has its notice stamped on the replay.
+pub async fn export_trade_replay(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path((id, trade)): Path<(String, usize)>,
+    Json(req): Json<TradeReplayRequest>,
+) -> Result<Json<TradeReplayExport>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let ledger = ledger_for(&state, &workspace, &id).await?;
+    let window = trade_windows(&ledger).into_iter().nth(trade).ok_or(StatusCode::NOT_FOUND)?;
+    let entry = scoped_dataset(&state, &headers, &req.dataset)?;
+    let watermark = entry.check_raw_export(Utc::now().date_naive()).map_err(|e| {
+        state.audit.record(&workspace, header(&headers, USER_HEADER), "dataset.export_blocked", &entry.id, serde_json::json!({
+            "export": "trade_replay",
+            "result_id": id,
+            "reason": e.to_string(),
+        }));
+        StatusCode::FORBIDDEN
+    })?;
+
+    let mut engine = DataIngestionEngine::new(IngestionConfig::default());
+    let ticks = engine.ingest_file(&entry.path).await
+        .map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+    let mut replay = TradeReplay::build(&ticks, &window, req.contract.as_deref(), &req.config)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if let Some(watermark) = watermark {
+        replay = replay.with_watermark(watermark);
+    }
+    let svg_frames = match req.format {
+        ReplayFormat::Json => None,
+        ReplayFormat::Svg => Some(replay.to_svg_frames()),
+    };
+    Ok(Json(TradeReplayExport { replay, svg_frames }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PropFirmRequest {
+    /// Evaluation programs to check; the standard 50K and 150K when empty
+    #[serde(default)]
+    pub programs: Vec<EvaluationRules>,
+    #[serde(default)]
+    pub monte_carlo: MonteCarloConfig,
+    #[serde(default)]
+    pub config: CostModelConfig,
+}
+
+/// Check a backtest's trades against prop-firm evaluation rules, with the
+/// probability of passing each program estimated by bootstrapping its days
+///
+/// Without programs, the standard 50k and 150k evaluations are checked. The
+/// bootstrap runs on the blocking pool.
+pub async fn simulate_prop_firm_evaluation(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<PropFirmRequest>,
+) -> Result<Json<PropFirmReport>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let ledger = ledger_for(&state, &workspace, &id).await?;
+    let days = TradingDay::from_ledger(&ledger, &req.config);
+
+    let programs = if req.programs.is_empty() {
+        vec![EvaluationRules::standard_50k(), EvaluationRules::standard_150k()]
+    } else {
+        req.programs
+    };
+    let report = tokio::task::spawn_blocking(move || PropFirmReport::compute(&days, &programs, &req.monte_carlo))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(report))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RiskOfRuinRequest {
+    #[serde(default)]
+    pub ruin: RuinConfig,
+    #[serde(default)]
+    pub config: CostModelConfig,
+}
+
+/// Estimate a backtest's risk of ruin under the requested sizing rules,
+/// with full and fractional Kelly sizing suggestions
+///
+/// The ruin simulation runs on the blocking pool, so large path counts do
+/// not hold up other requests.
+pub async fn estimate_risk_of_ruin(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<RiskOfRuinRequest>,
+) -> Result<Json<RiskOfRuinReport>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let ledger = ledger_for(&state, &workspace, &id).await?;
+
+    let report = tokio::task::spawn_blocking(move || RiskOfRuinReport::from_ledger(&ledger, &req.config, &req.ruin))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(report))
+}
+
+/// The engine configuration a stored backtest ran with
+///
+/// Results from before configurations were stored are rebuilt from their
+/// dates, with the costs, slippage and margin every run then used.
+fn replay_config(result: &BacktestResult) -> Result<BacktestConfig, SubmitError> {
+    match &result.config {
+        Some(config) => Ok(config.clone()),
+        None => Ok(BacktestConfig {
+            start_date: run_bound(&result.start_date, false)?,
+            end_date: run_bound(&result.end_date, true)?,
+            ..Default::default()
+        }),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReconciliationRequest {
+    /// Fills the strategy made live or on paper during the session
+    pub live_trades: Vec<TradeRecord>,
+    /// Cataloged recording of the session's ticks
+    pub dataset_id: String,
+    #[serde(default)]
+    pub config: ReconciliationConfig,
+}
+
+/// Reconcile a live or paper session's fills against a backtest replaying
+/// the session's recorded ticks
+///
+/// The recording is replayed through the engine with the strategy,
+/// parameters and engine configuration backtest `id` ran with. Unknown
+/// results or datasets give 404; results of strategies the server cannot
+/// run and recordings without ticks give 422.
+pub async fn reconcile_session(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<ReconciliationRequest>,
+) -> Result<Json<ReconciliationReport>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let (strategy_id, engine_config) = state.backtest_results.read().await.iter()
+        .find(|r| r.id == id && r.workspace_id == workspace)
+        .map(|r| (r.strategy_id.clone(), replay_config(r)))
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let engine_config = engine_config.map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+    let entry = scoped_dataset(&state, &headers, &req.dataset_id)?;
+    let session = match (entry.trade_date, entry.first_timestamp, entry.last_timestamp) {
+        (Some(trade_date), Some(first_timestamp), Some(last_timestamp)) => RecordedSession {
+            contract: entry.contracts.first().cloned().unwrap_or_default(),
+            trade_date,
+            path: PathBuf::from(&entry.path),
+            rows: entry.ticks as u64,
+            first_timestamp,
+            last_timestamp,
+        },
+        _ => return Err(StatusCode::UNPROCESSABLE_ENTITY),
+    };
+    // Replayed with the parameters the backtest was submitted with
+    let parameters = state.lineage.get(&id)
+        .and_then(|node| node.metadata.get("parameters").cloned())
+        .unwrap_or_default();
+    let (_, mut strategy) = runnable_strategy(&state, &workspace, &strategy_id, &parameters).await
+        .map_err(submit_status)?;
+
+    let runtime = tokio::runtime::Handle::current();
+    let report = tokio::task::spawn_blocking(move || {
+        runtime.block_on(replay_session(&mut strategy, engine_config, &session, &req.live_trades, &req.config))
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    report.map(Json).map_err(|e| {
+        warn!("Could not replay dataset {} for backtest {}: {}", req.dataset_id, id, e);
+        StatusCode::UNPROCESSABLE_ENTITY
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EquityQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Most bars wanted; the finest resolution that fits is returned
+    pub max_points: Option<usize>,
+}
+
+/// Equity curve of a backtest at the resolution a chart's zoom needs
+///
+/// Unknown results and results stored without a curve give 404.
+pub async fn get_equity_curve(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(query): Query<EquityQuery>,
+) -> Result<Json<EquityView>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    if !state.backtest_results.read().await.iter().any(|r| r.id == id && r.workspace_id == workspace) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    
+    let store = state.equity_curves.clone();
+    let max_points = query.max_points.unwrap_or(DEFAULT_EQUITY_POINTS).max(1);
+    let view = tokio::task::spawn_blocking(move || {
+        store.load(&id)?
+            .map(|curve| curve.view(query.from, query.to, max_points))
+            .transpose()
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    match view {
+        Ok(Some(view)) => Ok(Json(view)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            warn!("Failed to read equity curve: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Re-hash a backtest's stored result, configuration and input files and
+/// compare them with the digests recorded when it ran
+///
+/// Unknown results and results recorded without digests give 404.
+pub async fn verify_backtest_result(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<VerificationReport>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let result = state.backtest_results.read().await.iter()
+        .find(|r| r.id == id && r.workspace_id == workspace)
+        .cloned()
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let manifest = state.integrity.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+    
+    let config = state.lineage.find_by_name(&id).into_iter()
+        .find(|node| node.kind == ArtifactKind::Backtest)
+        .map(|node| node.metadata);
+    // A ledger that was never stored shows up as a missing artifact
+    let ledger = match ledger_for(&state, &workspace, &id).await {
+        Ok(ledger) => Some(ledger),
+        Err(StatusCode::NOT_FOUND) => None,
+        Err(status) => return Err(status),
+    };
+    let report = manifest.verify(|digest| match digest.name.as_str() {
+        "result" => canonical_json(&result).ok(),
+        "config" => config.as_ref().and_then(|config| canonical_json(config).ok()),
+        "ledger" => ledger.as_deref().and_then(|ledger| canonical_json(ledger).ok()),
+        "strategy_code" => result.code.as_ref()
+            .and_then(|code| code.sha256.as_deref())
+            .and_then(|sha| state.code_archive.get(sha))
+            .map(String::into_bytes),
+        _ => None,
+    });
+    
+    if !report.is_intact() {
+        warn!("Backtest result {} failed integrity verification", id);
+    }
+    Ok(Json(report))
+}
+
+/// Code a backtest result was produced with
+#[derive(Debug, Serialize)]
+pub struct ResultCode {
+    pub snapshot: CodeSnapshot,
+    /// Source text, for strategies built from a template
+    pub code: Option<String>,
+}
+
+/// Retrieve the code a historical backtest result ran
+pub async fn get_result_code(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ResultCode>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let snapshot = state.backtest_results.read().await.iter()
+        .find(|r| r.id == id && r.workspace_id == workspace)
+        .ok_or(StatusCode::NOT_FOUND)?
+        .code.clone()
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let code = snapshot.sha256.as_deref().and_then(|sha| state.code_archive.get(sha));
+    Ok(Json(ResultCode { snapshot, code }))
+}
+
+/// Get per-stage tick-processing latency percentiles
+pub async fn get_latency_metrics(
+    State(state): State<ApiState>,
+) -> Result<Json<LatencyReport>, StatusCode> {
+    Ok(Json(state.latency.report()))
+}
+
+/// Workers taking jobs from the Redis queue, with their liveness and the
+/// jobs each holds
+pub async fn list_workers(
+    State(state): State<ApiState>,
+) -> Result<Json<FleetStatus>, StatusCode> {
+    let queue = state.job_queue.as_ref().ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    let status = queue.lock().await.fleet_status().await.map_err(|e| {
+        warn!("Failed to read worker fleet status: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(status))
+}
+
+/// Get the engine thread pool layout and throughput measured under each layout
+pub async fn get_threading(
+    State(state): State<ApiState>,
+) -> Result<Json<ThreadingReport>, StatusCode> {
+    Ok(Json(state.thread_pools.report()))
+}
+
+/// Rebuild the engine thread pools with a new layout
+///
+/// The pools are shared by every workspace, so anonymous callers get 401.
+/// Layouts naming cores or NUMA nodes the machine does not have give 400.
+pub async fn configure_threading(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(config): Json<ThreadingConfig>,
+) -> Result<Json<ThreadingReport>, StatusCode> {
+    let user = header(&headers, USER_HEADER).ok_or(StatusCode::UNAUTHORIZED)?;
+    info!("User {} is reconfiguring the thread pools", user);
+    state.thread_pools.reconfigure(config).map_err(|e| {
+        warn!("Rejected thread pool configuration: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+    Ok(Json(state.thread_pools.report()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OrderFlowRequest {
+    /// Cataloged dataset to read
+    pub dataset_id: String,
+    /// Window start, nanoseconds since epoch
+    pub start_ns: Option<i64>,
+    /// Window end, nanoseconds since epoch
+    pub end_ns: Option<i64>,
+    /// Maximum number of tape entries returned
+    pub limit: Option<usize>,
+    /// Footprint bar window (defaults to one minute)
+    pub window: Option<FootprintWindow>,
+    /// Footprint price bucket size (defaults to one tick)
+    pub price_increment: Option<Decimal>,
+}
+
+/// Load the ticks of an order flow request's time window
+///
+/// Only cataloged datasets the caller's workspace may read are read; any
+/// other dataset gives 404.
+async fn load_order_flow_ticks(
+    state: &ApiState,
+    headers: &HeaderMap,
+    req: &OrderFlowRequest,
+) -> Result<Vec<TickData>, StatusCode> {
+    let entry = scoped_dataset(state, headers, &req.dataset_id)?;
+    let mut engine = DataIngestionEngine::new(IngestionConfig::default());
+    let ticks = engine.ingest_file(&entry.path).await
+        .map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+    
+    let start = req.start_ns.unwrap_or(i64::MIN);
+    let end = req.end_ns.unwrap_or(i64::MAX);
+    Ok(ticks.into_iter()
+        .filter(|t| t.timestamp >= start && t.timestamp <= end)
+        .collect())
+}
+
+/// Aggressor-classified trade tape for a window of tick data
+pub async fn get_order_flow_tape(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(req): Json<OrderFlowRequest>,
+) -> Result<Json<Vec<TapeTrade>>, StatusCode> {
+    let ticks = load_order_flow_ticks(&state, &headers, &req).await?;
+    let mut tape = TapeBuilder::reconstruct(&ticks);
+    if let Some(limit) = req.limit {
+        tape.truncate(limit);
+    }
+    Ok(Json(tape))
+}
+
+/// Footprint (volume at price by aggressor side) bars for a window of tick data
+pub async fn get_footprint(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(req): Json<OrderFlowRequest>,
+) -> Result<Json<Vec<FootprintBar>>, StatusCode> {
+    let ticks = load_order_flow_ticks(&state, &headers, &req).await?;
+    let tape = TapeBuilder::reconstruct(&ticks);
+    let defaults = FootprintConfig::default();
+    let config = FootprintConfig {
+        window: req.window.unwrap_or(defaults.window),
+        price_increment: req.price_increment.unwrap_or(defaults.price_increment),
+    };
+    Ok(Json(build_footprint(&tape, &config)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LineageQuery {
+    /// Only return artifacts with this name (e.g. a data file path)
+    pub name: Option<String>,
+}
+
+/// List tracked artifacts visible to the workspace
+pub async fn list_lineage(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Query(query): Query<LineageQuery>,
+) -> Result<Json<Vec<LineageNode>>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let mut nodes = match query.name {
+        Some(name) => state.lineage.find_by_name(&name),
+        None => state.lineage.nodes(),
+    };
+    nodes.retain(|node| node.visible_to(&workspace));
+    Ok(Json(nodes))
+}
+
+/// Get an artifact with everything upstream and downstream of it
+///
+/// Artifacts of other workspaces are left out of the trace.
+pub async fn get_lineage(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<LineageTrace>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let mut trace = state.lineage.trace(&id)
+        .ok()
+        .filter(|trace| trace.node.visible_to(&workspace))
+        .ok_or(StatusCode::NOT_FOUND)?;
+    trace.upstream.retain(|node| node.visible_to(&workspace));
+    trace.downstream.retain(|node| node.visible_to(&workspace));
+    Ok(Json(trace))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InvalidateLineageRequest {
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InvalidateLineageResponse {
+    pub invalidated: Vec<String>,
+}
+
+/// Invalidate an artifact and every result derived from it
+///
+/// Only the workspace owning the artifact may invalidate it; others get 404.
+/// The response lists the invalidated artifacts the caller can see.
+pub async fn invalidate_lineage(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<InvalidateLineageRequest>,
+) -> Result<Json<InvalidateLineageResponse>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    if !state.lineage.get(&id).is_some_and(|node| node.workspace_id == workspace) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let mut invalidated = state.lineage.invalidate(&id, &req.reason)
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    invalidated.retain(|artifact| state.lineage.get(artifact).is_some_and(|node| node.visible_to(&workspace)));
+    Ok(Json(InvalidateLineageResponse { invalidated }))
+}
+
+/// List ingested datasets by trade date
+///
+/// Spread bounds in the query keep only datasets with at least one session
+/// in that spread regime.
+pub async fn list_datasets(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Query(filter): Query<SpreadFilter>,
+) -> Result<Json<Vec<DatasetEntry>>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let mut entries = state.catalog.list();
+    entries.retain(|entry| entry.visible_to(&workspace));
+    if !filter.is_empty() {
+        entries.retain(|entry| entry.spread.iter().any(|session| filter.matches(session)));
+    }
+    Ok(Json(entries))
+}
+
+pub async fn get_dataset(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<DatasetEntry>, StatusCode> {
+    scoped_dataset(&state, &headers, &id).map(Json)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LiquidityQuery {
+    /// Only samples starting at or after this time, nanoseconds since epoch
+    pub start_ns: Option<i64>,
+    /// Only samples starting at or before this time, nanoseconds since epoch
+    pub end_ns: Option<i64>,
+}
+
+/// Book liquidity time series of an ingested dataset
+///
+/// Datasets ingested before liquidity metrics existed have theirs computed
+/// on first request and stored for later ones.
+pub async fn get_dataset_liquidity(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(query): Query<LiquidityQuery>,
+) -> Result<Json<LiquidityProfile>, StatusCode> {
+    let mut entry = scoped_dataset(&state, &headers, &id)?;
+    let data_path = std::path::PathBuf::from(&entry.path);
+
+    let stored = LiquidityProfile::load(&data_path).map_err(|e| {
+        warn!("Failed to read liquidity profile of {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let mut profile = match stored {
+        Some(profile) => profile,
+        None => {
+            let mut engine = DataIngestionEngine::new(IngestionConfig::default());
+            let ticks = engine.ingest_file(&entry.path).await
+                .map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+            let profile = LiquidityProfile::compute(&ticks, &LiquidityConfig::default());
+            if let Err(e) = profile.save(&data_path) {
+                warn!("Failed to store liquidity profile of {}: {}", id, e);
+            }
+            entry.liquidity = Some(profile.summary.clone());
+            if let Err(e) = state.catalog.upsert(entry) {
+                warn!("Failed to update catalog entry of {}: {}", id, e);
+            }
+            profile
+        }
+    };
+
+    profile.samples = profile.samples_between(query.start_ns, query.end_ns);
+    Ok(Json(profile))
+}
+
+/// Per-session bid-ask spread statistics of an ingested dataset
+///
+/// Datasets ingested before spread statistics existed, or whose session
+/// calendar was changed since, have them computed on first request and
+/// stored in the catalog.
+pub async fn get_dataset_spread(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<SessionSpread>>, StatusCode> {
+    let mut entry = scoped_dataset(&state, &headers, &id)?;
+    if !entry.spread.is_empty() {
+        return Ok(Json(entry.spread));
+    }
+
+    let mut engine = DataIngestionEngine::new(IngestionConfig::default());
+    let ticks = engine.ingest_file(&entry.path).await
+        .map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+    let calendar = entry.session_calendar.clone().unwrap_or_default();
+    entry.spread = SessionSpread::compute(&ticks, &SpreadConfig::default(), &calendar);
+    let spread = entry.spread.clone();
+    if let Err(e) = state.catalog.upsert(entry) {
+        warn!("Failed to update catalog entry of {}: {}", id, e);
+    }
+    Ok(Json(spread))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConsistencyQuery {
+    /// Time the L2 feed has to reflect a trade or quote change
+    pub reaction_window_ms: Option<u64>,
+    /// Allowed ratio of traded volume to displayed queue size
+    pub queue_tolerance: Option<f64>,
+}
+
+/// Per-session consistency of an ingested dataset's L1 trades with its L2 depth
+pub async fn get_dataset_consistency(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(query): Query<ConsistencyQuery>,
+) -> Result<Json<Vec<ConsistencyReport>>, StatusCode> {
+    let entry = scoped_dataset(&state, &headers, &id)?;
+
+    let mut config = ConsistencyConfig::default();
+    if let Some(window) = query.reaction_window_ms {
+        config.reaction_window_ms = window;
+    }
+    if let Some(tolerance) = query.queue_tolerance {
+        config.queue_tolerance = tolerance;
+    }
+
+    let mut engine = DataIngestionEngine::new(IngestionConfig::default());
+    let ticks = engine.ingest_file(&entry.path).await
+        .map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+    Ok(Json(ConsistencyReport::check(&ticks, &config)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BookDiffRequest {
+    /// Instant the reference was taken, nanoseconds since epoch
+    pub at_ns: i64,
+    /// Contract to rebuild; the one last updated by `at_ns` when absent
+    #[serde(default)]
+    pub contract: Option<String>,
+    /// The book to compare with, e.g. a vendor depth snapshot
+    pub reference: BookSnapshot,
+    #[serde(default)]
+    pub config: BookDiffConfig,
+}
+
+/// Compare our reconstruction of an ingested dataset's book at an instant
+/// with a reference snapshot, level by level
+///
+/// A dataset with no ticks of the contract by then gives 404.
+pub async fn diff_dataset_book(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<BookDiffRequest>,
+) -> Result<Json<BookDiffReport>, StatusCode> {
+    let entry = scoped_dataset(&state, &headers, &id)?;
+
+    let mut engine = DataIngestionEngine::new(IngestionConfig::default());
+    let ticks = engine.ingest_file(&entry.path).await
+        .map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+    let contract = match req.contract {
+        Some(contract) => contract,
+        None => ticks.iter().rev()
+            .find(|tick| tick.timestamp <= req.at_ns)
+            .map(|tick| tick.contract_month.clone())
+            .ok_or(StatusCode::NOT_FOUND)?,
+    };
+    if !ticks.iter().any(|tick| tick.contract_month == contract && tick.timestamp <= req.at_ns) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let book = reconstruct_book(&ticks, &contract, req.at_ns);
+    Ok(Json(BookDiffReport::compare(&BookSnapshot::from_state(&book), &req.reference, &req.config)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SampleDatasetRequest {
+    /// Name of the new dataset, stored as `<label>.parquet`
+    pub label: String,
+    #[serde(flatten)]
+    pub method: SampleMethod,
+}
+
+/// Cut a labeled subset of an ingested dataset into a new derived dataset
+///
+/// The sample is written to a `derived` directory next to its source, never
+/// to a path the caller names, and belongs to the caller's workspace, whose
+/// storage quota it is charged to. A label already in the catalog gives 409,
+/// an invalid label or a sample selecting no ticks 400, and a sample over
+/// the quota 429.
+pub async fn sample_dataset(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<SampleDatasetRequest>,
+) -> Result<Json<DatasetEntry>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let source = scoped_dataset(&state, &headers, &id)?;
+    let output_dir = std::path::Path::new(&source.path)
+        .parent()
+        .map(|dir| dir.join("derived"))
+        .unwrap_or_else(|| "derived".into());
+
+    let mut engine = DataIngestionEngine::new(IngestionConfig::default());
+    let ticks = engine.ingest_file(&source.path).await
+        .map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+
+    let catalog = state.catalog.clone();
+    let lineage = state.lineage.clone();
+    let derived = tokio::task::spawn_blocking(move || {
+        derive_dataset(&source, &ticks, &req.label, req.method, &output_dir, &workspace, &catalog, Some(&lineage))
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    match derived {
+        Ok(entry) => Ok(Json(entry)),
+        Err(SamplingError::Exists(_)) => Err(StatusCode::CONFLICT),
+        Err(SamplingError::InvalidLabel(_) | SamplingError::Empty) => Err(StatusCode::BAD_REQUEST),
+        Err(SamplingError::Catalog(CatalogError::Quota(e))) => Err(workspace_status(e)),
+        Err(e) => {
+            warn!("Failed to sample dataset {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Propose session hours, halts and holidays for an ingested dataset from
+/// when its ticks arrive; nothing is stored until the calendar is confirmed
+///
+/// Data too sparse or too continuous to show a daily close gives 422.
+pub async fn detect_dataset_sessions(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(config): Query<SessionDetectionConfig>,
+) -> Result<Json<SessionProposal>, StatusCode> {
+    let entry = scoped_dataset(&state, &headers, &id)?;
+
+    let mut engine = DataIngestionEngine::new(IngestionConfig::default());
+    let ticks = engine.ingest_file(&entry.path).await
+        .map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+
+    let timestamps: Vec<Timestamp> = ticks.iter().map(|t| Timestamp::from_nanos(t.timestamp)).collect();
+    tokio::task::spawn_blocking(move || detect_sessions(timestamps, &config))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(Json)
+        .map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)
+}
+
+/// Store a session calendar, typically a confirmed or corrected proposal,
+/// on a dataset's catalog entry
+pub async fn confirm_dataset_sessions(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(calendar): Json<SessionCalendar>,
+) -> Result<Json<DatasetEntry>, StatusCode> {
+    let mut entry = owned_dataset(&state, &headers, &id)?;
+    entry.session_calendar = Some(calendar);
+    // Spread statistics were split by the previous calendar's sessions
+    entry.spread.clear();
+    state.catalog.upsert(entry.clone()).map_err(|e| {
+        warn!("Failed to store session calendar for {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(entry))
+}
+
+/// Record the terms a dataset was licensed under, or clear them with `null`
+///
+/// Samples cut from the dataset afterwards inherit the new terms; existing
+/// samples keep theirs.
+pub async fn set_dataset_license(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(license): Json<Option<DataLicense>>,
+) -> Result<Json<DatasetEntry>, StatusCode> {
+    let mut entry = owned_dataset(&state, &headers, &id)?;
+    entry.license = license;
+    state.catalog.upsert(entry.clone()).map_err(|e| {
+        warn!("Failed to store license of {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(entry))
+}
+
+/// List the preloaded datasets the workspace can read
+pub async fn list_cached_datasets(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<CachedDatasetInfo>>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    let visible: HashSet<String> = state.catalog.list().into_iter()
+        .filter(|entry| entry.visible_to(&workspace))
+        .map(|entry| entry.path)
+        .collect();
+    let mut cached = state.dataset_cache.list();
+    cached.retain(|info| visible.contains(&info.path));
+    Ok(Json(cached))
+}
+
+#[derive(Debug, Serialize)]
+pub struct WarmCacheResponse {
+    pub job_id: String,
+    /// When loading begins; immediately when absent
+    pub starts_at: Option<DateTime<Utc>>,
+    /// Status of the job an earlier submission with the same idempotency
+    /// key started
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<JobStatus>,
+}
+
+/// Schedule a job preloading datasets ahead of an optimization run
+///
+/// Datasets are named by catalog id; one the workspace cannot read gives
+/// 404. A request repeating the `Idempotency-Key` of an earlier one returns the
+/// job that request scheduled instead of scheduling another.
+pub async fn warm_cache(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(mut req): Json<WarmupRequest>,
+) -> Result<Json<WarmCacheResponse>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    if req.datasets.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    // Only cataloged datasets are loaded, never a path the caller names
+    req.datasets = req.datasets.iter()
+        .map(|id| scoped_dataset(&state, &headers, id).map(|entry| entry.path))
+        .collect::<Result<_, _>>()?;
+    let reservation = match claim_idempotency_key(&state, &headers, &workspace, JobKind::CacheWarmup, &req)? {
+        Some(Claim::Existing(job_id)) => return Ok(Json(WarmCacheResponse {
+            status: state.job_board.status(&job_id),
+            job_id,
+            starts_at: req.start_time(),
+        })),
+        Some(Claim::New(reservation)) => Some(reservation),
+        None => None,
+    };
+    admit_batch(&state)?;
+    
+    let job_id = Uuid::new_v4().to_string();
+    let starts_at = req.start_time();
+    
+    let subject = format!("{} datasets", req.datasets.len());
+    state.job_board.start(&job_id, JobKind::CacheWarmup, &workspace, subject);
+    let task_state = state.clone();
+    let task_id = job_id.clone();
+    let task_workspace = workspace.clone();
+    let span = info_span!("cache_warmup_job", job_id = %job_id);
+    spawn_job(&state, &job_id, async move {
+        let report = task_state.dataset_cache.warm(&req).await;
+        task_state.cache_warmups.write().await.insert(task_id.clone(), (task_workspace, report));
+        task_state.jobs.write().await.remove(&task_id);
+        task_state.job_board.finish(&task_id, JobOutcome::Completed);
+    }.instrument(span)).await;
+    if let Some(reservation) = reservation {
+        reservation.complete(&job_id);
+    }
+    Ok(Json(WarmCacheResponse { job_id, starts_at, status: None }))
+}
+
+/// Get the report of a finished warm-up job of the workspace
+pub async fn get_cache_warmup(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<WarmupReport>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    if let Some((owner, report)) = state.cache_warmups.read().await.get(&id) {
+        return match owner == &workspace {
+            true => Ok(Json(report.clone())),
+            false => Err(StatusCode::NOT_FOUND),
+        };
+    }
+    
+    // Still scheduled or loading
+    let running = matches!(state.job_board.status(&id), Some(JobStatus::Active(job)) if job.workspace_id == workspace);
+    if running && state.jobs.read().await.contains_key(&id) {
+        return Err(StatusCode::ACCEPTED);
+    }
+    Err(StatusCode::NOT_FOUND)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenWarmSessionRequest {
+    /// Cataloged dataset to load
+    pub dataset_id: String,
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    /// Engine configuration; the defaults when absent. Its date range is
+    /// replaced by the session's.
+    #[serde(default)]
+    pub config: Option<BacktestConfig>,
+}
+
+/// Load a date range into memory for fast repeated backtests
+///
+/// Responds once the data is resident. Backtests naming the session in
+/// `session_id` run against it without reading the data again.
+pub async fn open_warm_session(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(req): Json<OpenWarmSessionRequest>,
+) -> Result<Json<WarmSessionInfo>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    if req.start_date >= req.end_date {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    // Refuse before loading rather than after
+    if !state.warm_sessions.has_capacity() {
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    }
+    
+    let entry = scoped_dataset(&state, &headers, &req.dataset_id)?;
+    
+    let mut config = req.config.unwrap_or_default();
+    config.start_date = req.start_date;
+    config.end_date = req.end_date;
+    let session = WarmSession::open(&entry.path, config, Some(&state.dataset_cache)).await
+        .map_err(warm_session_status)?;
+    Ok(Json(state.warm_sessions.insert(&workspace, session).map_err(warm_session_status)?))
+}
+
+/// List the workspace's warm sessions
+pub async fn list_warm_sessions(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<WarmSessionInfo>>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    Ok(Json(state.warm_sessions.list(&workspace)))
+}
+
+pub async fn get_warm_session(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<WarmSessionInfo>, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    state.warm_sessions.get(&workspace, &id).map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Close a warm session, releasing its memory
+pub async fn close_warm_session(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let workspace = workspace_scope(&state, &headers)?;
+    if state.warm_sessions.close(&workspace, &id) {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateWorkspaceRequest {
+    pub name: String,
+    #[serde(default)]
+    pub quota: ResourceQuota,
+}
+
+/// Create a workspace owned by the calling user
+pub async fn create_workspace(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateWorkspaceRequest>,
+) -> Result<Json<Workspace>, StatusCode> {
+    let user = header(&headers, USER_HEADER).ok_or(StatusCode::UNAUTHORIZED)?;
+    Ok(Json(state.workspaces.create(&req.name, user, req.quota)))
+}
+
+/// List the calling user's workspaces
+pub async fn list_workspaces(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<Workspace>>, StatusCode> {
+    let user = header(&headers, USER_HEADER).ok_or(StatusCode::UNAUTHORIZED)?;
+    Ok(Json(state.workspaces.for_user(user)))
+}
+
+/// Get a workspace with its quota and current usage
+pub async fn get_workspace(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Workspace>, StatusCode> {
+    state.workspaces.authorize(&id, header(&headers, USER_HEADER))
+        .map_err(workspace_status)?;
+    state.workspaces.get(&id)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Only the owner may change membership or quotas
+fn require_owner(state: &ApiState, headers: &HeaderMap, id: &str) -> Result<(), StatusCode> {
+    let workspace = state.workspaces.get(id).ok_or(StatusCode::NOT_FOUND)?;
+    match header(headers, USER_HEADER) {
+        Some(user) if user == workspace.owner => Ok(()),
+        _ => Err(StatusCode::FORBIDDEN),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddMemberRequest {
+    pub user_id: String,
+}
+
+/// Add a user to a workspace
+pub async fn add_workspace_member(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<AddMemberRequest>,
+) -> Result<StatusCode, StatusCode> {
+    require_owner(&state, &headers, &id)?;
+    state.workspaces.add_member(&id, &req.user_id).map_err(workspace_status)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Replace a workspace's quota
+pub async fn set_workspace_quota(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(quota): Json<ResourceQuota>,
+) -> Result<Json<Workspace>, StatusCode> {
+    require_owner(&state, &headers, &id)?;
+    state.workspaces.set_quota(&id, quota).map_err(workspace_status)?;
+    state.workspaces.get(&id)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preset_candidates_keep_the_numeric_parameters() {
+        let preset = ParameterPreset {
+            id: Uuid::new_v4(),
+            workspace_id: DEFAULT_WORKSPACE.to_string(),
+            strategy_id: "vwap".to_string(),
+            name: "tight".to_string(),
+            parameters: serde_json::json!({ "fast": 5, "stop": 2.5, "label": "tight", "enabled": true }),
+            favorite: false,
+            source_optimization_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        let candidate = preset_candidate(&preset);
+        assert_eq!(candidate.get_float("fast"), Some(5.0));
+        assert_eq!(candidate.get_float("stop"), Some(2.5));
+        assert_eq!(candidate.get_float("label"), None);
+        assert_eq!(candidate.get_float("enabled"), None);
+    }
+}
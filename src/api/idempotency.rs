@@ -0,0 +1,188 @@
+//! Idempotent job submission
+//!
+//! Clients send an `Idempotency-Key` header with job submissions so a retry
+//! after a dropped connection finds the job the first attempt started
+//! instead of starting another multi-hour run. A key is reserved while its
+//! submission is handled and bound to the job id once the job starts; a
+//! submission that fails before starting a job releases the key so it can
+//! be retried. Keys are scoped to a workspace and forgotten after a day.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::dashboard::JobKind;
+
+/// Header carrying the client's idempotency key
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Hours a key keeps pointing at its job
+const KEY_TTL_HOURS: i64 = 24;
+
+const MAX_KEY_LEN: usize = 255;
+
+/// Errors raised claiming an idempotency key
+#[derive(Debug, thiserror::Error)]
+pub enum IdempotencyError {
+    #[error("Idempotency keys must be 1 to {MAX_KEY_LEN} visible ASCII characters")]
+    InvalidKey,
+    #[error("A submission with idempotency key {0} is still being handled")]
+    InProgress(String),
+    #[error("Idempotency key {0} was already used for a different request")]
+    KeyReused(String),
+}
+
+#[derive(Debug, Clone)]
+struct KeyRecord {
+    kind: JobKind,
+    /// SHA-256 of the request body the key was first used with
+    fingerprint: String,
+    /// Set once the submission has started its job
+    job_id: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+/// Outcome of claiming a key
+#[derive(Debug)]
+pub enum Claim {
+    /// First use of the key; the submission goes ahead
+    New(KeyReservation),
+    /// The key already started this job
+    Existing(String),
+}
+
+/// Idempotency keys seen by the API, by workspace and key
+#[derive(Debug, Clone, Default)]
+pub struct IdempotencyStore {
+    keys: Arc<RwLock<HashMap<(String, String), KeyRecord>>>,
+}
+
+impl IdempotencyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Claim `key` for a submission of `kind` with body `request`
+    ///
+    /// Reusing a key for a different kind of job or a different body is an
+    /// error, as is retrying while the first submission is still in flight.
+    pub fn claim(
+        &self,
+        workspace_id: &str,
+        key: &str,
+        kind: JobKind,
+        request: &impl Serialize,
+    ) -> Result<Claim, IdempotencyError> {
+        if key.is_empty() || key.len() > MAX_KEY_LEN || !key.chars().all(|c| c.is_ascii_graphic()) {
+            return Err(IdempotencyError::InvalidKey);
+        }
+        let fingerprint = fingerprint(request);
+        let now = Utc::now();
+
+        let mut keys = self.keys.write().unwrap();
+        let cutoff = now - Duration::hours(KEY_TTL_HOURS);
+        keys.retain(|_, record| record.created_at >= cutoff);
+
+        let scoped = (workspace_id.to_string(), key.to_string());
+        if let Some(record) = keys.get(&scoped) {
+            if record.kind != kind || record.fingerprint != fingerprint {
+                return Err(IdempotencyError::KeyReused(key.to_string()));
+            }
+            return match &record.job_id {
+                Some(job_id) => Ok(Claim::Existing(job_id.clone())),
+                None => Err(IdempotencyError::InProgress(key.to_string())),
+            };
+        }
+
+        keys.insert(scoped, KeyRecord { kind, fingerprint, job_id: None, created_at: now });
+        Ok(Claim::New(KeyReservation {
+            store: self.clone(),
+            workspace_id: workspace_id.to_string(),
+            key: key.to_string(),
+            completed: false,
+        }))
+    }
+
+    /// Whether `key` has already started a job in the workspace
+    pub fn has_job(&self, workspace_id: &str, key: &str) -> bool {
+        self.keys.read().unwrap()
+            .get(&(workspace_id.to_string(), key.to_string()))
+            .is_some_and(|record| record.job_id.is_some())
+    }
+}
+
+/// A claimed key, released unless completed with the job it started
+#[derive(Debug)]
+pub struct KeyReservation {
+    store: IdempotencyStore,
+    workspace_id: String,
+    key: String,
+    completed: bool,
+}
+
+impl KeyReservation {
+    /// Bind the key to the job the submission started
+    pub fn complete(mut self, job_id: &str) {
+        let scoped = (std::mem::take(&mut self.workspace_id), std::mem::take(&mut self.key));
+        if let Some(record) = self.store.keys.write().unwrap().get_mut(&scoped) {
+            record.job_id = Some(job_id.to_string());
+        }
+        self.completed = true;
+    }
+}
+
+impl Drop for KeyReservation {
+    fn drop(&mut self) {
+        if !self.completed {
+            let scoped = (std::mem::take(&mut self.workspace_id), std::mem::take(&mut self.key));
+            self.store.keys.write().unwrap().remove(&scoped);
+        }
+    }
+}
+
+fn fingerprint(request: &impl Serialize) -> String {
+    let body = serde_json::to_vec(request).unwrap_or_default();
+    format!("{:x}", Sha256::digest(&body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_finds_the_original_job() {
+        let store = IdempotencyStore::new();
+        let body = serde_json::json!({ "strategy_id": "s1" });
+
+        let Ok(Claim::New(reservation)) = store.claim("default", "retry-1", JobKind::Optimization, &body) else {
+            panic!("first use of a key should be new");
+        };
+        assert!(matches!(
+            store.claim("default", "retry-1", JobKind::Optimization, &body),
+            Err(IdempotencyError::InProgress(_))
+        ));
+        reservation.complete("job-1");
+        assert!(store.has_job("default", "retry-1"));
+        assert!(matches!(
+            store.claim("default", "retry-1", JobKind::Optimization, &body),
+            Ok(Claim::Existing(id)) if id == "job-1"
+        ));
+
+        let other = serde_json::json!({ "strategy_id": "s2" });
+        assert!(matches!(
+            store.claim("default", "retry-1", JobKind::Optimization, &other),
+            Err(IdempotencyError::KeyReused(_))
+        ));
+        // Keys belong to their workspace
+        assert!(matches!(store.claim("research", "retry-1", JobKind::Optimization, &body), Ok(Claim::New(_))));
+
+        // A failed submission frees its key for the retry
+        let Ok(Claim::New(failed)) = store.claim("default", "retry-2", JobKind::Backtest, &body) else {
+            panic!("first use of a key should be new");
+        };
+        drop(failed);
+        assert!(matches!(store.claim("default", "retry-2", JobKind::Backtest, &body), Ok(Claim::New(_))));
+    }
+}
@@ -0,0 +1,366 @@
+//! Request rate limits and size guards
+//!
+//! Protects the engine host from a runaway frontend or script. Every request
+//! draws from a token bucket keyed by the user its API key identified, or by
+//! the client address for anonymous requests; unlike a header the client
+//! sets, neither changes from one request to the next. Job
+//! submissions, over HTTP or the WebSocket, are refused
+//! once too many jobs are running on the host, and oversized payloads are
+//! rejected before they are buffered. Rejections carry a JSON body saying
+//! which limit was hit and when to retry.
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{header, HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use super::handlers::{USER_HEADER, WORKSPACE_HEADER};
+use super::idempotency::IDEMPOTENCY_KEY_HEADER;
+use super::ApiState;
+use crate::workspace::DEFAULT_WORKSPACE;
+
+/// Buckets idle this long are dropped once the table grows large
+const BUCKET_IDLE_TTL: Duration = Duration::from_secs(600);
+
+/// Buckets tracked at most; the least recently used is dropped beyond this
+const MAX_TRACKED_KEYS: usize = 10_000;
+
+/// Request limit configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimitsConfig {
+    /// Sustained requests per minute allowed for one key
+    #[serde(default = "default_requests_per_minute")]
+    pub requests_per_minute: u32,
+    /// Requests a key may make in a burst above the sustained rate
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+    /// Largest accepted request body
+    #[serde(default = "default_max_body_bytes")]
+    pub max_body_bytes: usize,
+    /// Jobs that may run on the host at once, across all workspaces
+    #[serde(default = "default_max_inflight_jobs")]
+    pub max_inflight_jobs: usize,
+}
+
+fn default_requests_per_minute() -> u32 {
+    120
+}
+
+fn default_burst() -> u32 {
+    30
+}
+
+fn default_max_body_bytes() -> usize {
+    10 * 1024 * 1024
+}
+
+fn default_max_inflight_jobs() -> usize {
+    32
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: default_requests_per_minute(),
+            burst: default_burst(),
+            max_body_bytes: default_max_body_bytes(),
+            max_inflight_jobs: default_max_inflight_jobs(),
+        }
+    }
+}
+
+impl LimitsConfig {
+    /// Defaults overridden by `STRATEGY_LAB_RATE_LIMIT_RPM`, `STRATEGY_LAB_RATE_LIMIT_BURST`,
+    /// `STRATEGY_LAB_MAX_BODY_BYTES` and `STRATEGY_LAB_MAX_INFLIGHT_JOBS`
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            match std::env::var(name) {
+                Ok(value) => value.parse().unwrap_or_else(|_| {
+                    warn!("Ignoring invalid {}={}", name, value);
+                    default
+                }),
+                Err(_) => default,
+            }
+        }
+
+        let defaults = Self::default();
+        Self {
+            requests_per_minute: var("STRATEGY_LAB_RATE_LIMIT_RPM", defaults.requests_per_minute),
+            burst: var("STRATEGY_LAB_RATE_LIMIT_BURST", defaults.burst),
+            max_body_bytes: var("STRATEGY_LAB_MAX_BODY_BYTES", defaults.max_body_bytes),
+            max_inflight_jobs: var("STRATEGY_LAB_MAX_INFLIGHT_JOBS", defaults.max_inflight_jobs),
+        }
+    }
+}
+
+/// Body of a 429 or 413 response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimitExceeded {
+    pub error: String,
+    /// Which limit was hit: `rate`, `inflight_jobs` or `body_size`
+    pub limit: String,
+    pub allowed: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+}
+
+impl IntoResponse for LimitExceeded {
+    fn into_response(self) -> Response {
+        let status = if self.limit == "body_size" {
+            StatusCode::PAYLOAD_TOO_LARGE
+        } else {
+            StatusCode::TOO_MANY_REQUESTS
+        };
+        let retry_after = self.retry_after_secs;
+        let mut response = (status, Json(self)).into_response();
+        if let Some(secs) = retry_after {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Outcome of drawing a token
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateDecision {
+    Allowed { remaining: u32 },
+    Limited { retry_after: Duration },
+}
+
+/// Per-key token buckets
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    capacity: f64,
+    /// Tokens added per second
+    refill_rate: f64,
+    buckets: Arc<Mutex<HashMap<String, Bucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_minute: u32, burst: u32) -> Self {
+        Self {
+            capacity: burst.max(1) as f64,
+            refill_rate: requests_per_minute.max(1) as f64 / 60.0,
+            buckets: Default::default(),
+        }
+    }
+
+    /// Take one token from `key`'s bucket
+    pub fn check(&self, key: &str) -> RateDecision {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> RateDecision {
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_KEYS && !buckets.contains_key(key) {
+            buckets.retain(|_, bucket| now.saturating_duration_since(bucket.updated) < BUCKET_IDLE_TTL);
+            if buckets.len() >= MAX_TRACKED_KEYS {
+                let oldest = buckets.iter()
+                    .min_by_key(|(_, bucket)| bucket.updated)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    buckets.remove(&oldest);
+                }
+            }
+        }
+
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: self.capacity,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_rate).min(self.capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            RateDecision::Allowed { remaining: bucket.tokens as u32 }
+        } else {
+            let wait = (1.0 - bucket.tokens) / self.refill_rate;
+            RateDecision::Limited { retry_after: Duration::from_secs_f64(wait) }
+        }
+    }
+}
+
+/// Limits enforced on API requests
+#[derive(Debug, Clone)]
+pub struct RequestLimits {
+    pub config: LimitsConfig,
+    limiter: RateLimiter,
+}
+
+impl RequestLimits {
+    pub fn new(config: LimitsConfig) -> Self {
+        let limiter = RateLimiter::new(config.requests_per_minute, config.burst);
+        Self { config, limiter }
+    }
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self::new(LimitsConfig::default())
+    }
+}
+
+/// Key requests are rate limited by: the authenticated user, else the
+/// client address
+///
+/// The user header is set by `auth::identify` from the API key, never taken
+/// from the client, which could otherwise get a fresh bucket per request.
+fn client_key(user: Option<&str>, peer: Option<SocketAddr>) -> String {
+    match (user, peer) {
+        (Some(user), _) => format!("user:{}", user),
+        (None, Some(peer)) => format!("ip:{}", peer.ip()),
+        (None, None) => "anonymous".to_string(),
+    }
+}
+
+/// Reject requests over the caller's rate or with an oversized declared body
+///
+/// Bodies sent without a length are capped while being read by the router's
+/// `DefaultBodyLimit`.
+pub async fn guard_requests<B>(
+    State(state): State<ApiState>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let limits = &state.limits;
+
+    let declared = request.headers().get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if let Some(length) = declared {
+        if length > limits.config.max_body_bytes as u64 {
+            return LimitExceeded {
+                error: format!("Request body of {} bytes exceeds the limit of {} bytes",
+                    length, limits.config.max_body_bytes),
+                limit: "body_size".to_string(),
+                allowed: limits.config.max_body_bytes as u64,
+                retry_after_secs: None,
+            }.into_response();
+        }
+    }
+
+    let peer = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|info| info.0);
+    let user = request.headers().get(USER_HEADER).and_then(|value| value.to_str().ok());
+    let key = client_key(user, peer);
+    match limits.limiter.check(&key) {
+        RateDecision::Allowed { remaining } => {
+            let mut response = next.run(request).await;
+            let headers = response.headers_mut();
+            headers.insert("x-ratelimit-limit", HeaderValue::from(limits.config.requests_per_minute));
+            headers.insert("x-ratelimit-remaining", HeaderValue::from(remaining));
+            response
+        }
+        RateDecision::Limited { retry_after } => {
+            let retry_after_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            warn!("Rate limited {} for {}s", key, retry_after_secs);
+            LimitExceeded {
+                error: format!("Rate limit of {} requests per minute exceeded", limits.config.requests_per_minute),
+                limit: "rate".to_string(),
+                allowed: limits.config.requests_per_minute as u64,
+                retry_after_secs: Some(retry_after_secs),
+            }.into_response()
+        }
+    }
+}
+
+/// Refuse new jobs while the host is running its maximum
+///
+/// Workspace quotas cap each team separately; this caps the host as a whole.
+/// Retries of a submission that already started its job are let through so
+/// they get that job back rather than a rejection.
+pub async fn guard_job_admission<B>(
+    State(state): State<ApiState>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let headers = request.headers();
+    if let Some(key) = headers.get(IDEMPOTENCY_KEY_HEADER).and_then(|value| value.to_str().ok()) {
+        let workspace = headers.get(WORKSPACE_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or(DEFAULT_WORKSPACE);
+        if state.idempotency.has_job(workspace, key) {
+            return next.run(request).await;
+        }
+    }
+    match admit_job(&state).await {
+        Ok(()) => next.run(request).await,
+        Err(exceeded) => exceeded.into_response(),
+    }
+}
+
+/// Fail if the host is already running its maximum number of jobs
+pub async fn admit_job(state: &ApiState) -> Result<(), LimitExceeded> {
+    let running = state.jobs.read().await.len();
+    let max = state.limits.config.max_inflight_jobs;
+    if running >= max {
+        return Err(LimitExceeded {
+            error: format!("{} jobs are already running, the host limit is {}", running, max),
+            limit: "inflight_jobs".to_string(),
+            allowed: max as u64,
+            retry_after_secs: Some(5),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket_refills() {
+        // One token per second, bursts of two
+        let limiter = RateLimiter::new(60, 2);
+        let start = Instant::now();
+
+        assert_eq!(limiter.check_at("a", start), RateDecision::Allowed { remaining: 1 });
+        assert_eq!(limiter.check_at("a", start), RateDecision::Allowed { remaining: 0 });
+        match limiter.check_at("a", start) {
+            RateDecision::Limited { retry_after } => assert_eq!(retry_after, Duration::from_secs(1)),
+            other => panic!("expected limit, got {:?}", other),
+        }
+
+        // Keys are independent
+        assert!(matches!(limiter.check_at("b", start), RateDecision::Allowed { .. }));
+
+        let later = start + Duration::from_millis(1500);
+        assert_eq!(limiter.check_at("a", later), RateDecision::Allowed { remaining: 0 });
+    }
+
+    #[test]
+    fn test_full_table_evicts_the_least_recent_bucket() {
+        let limiter = RateLimiter::new(60, 1);
+        let start = Instant::now();
+        limiter.check_at("drained", start);
+        for i in 0..MAX_TRACKED_KEYS {
+            limiter.check_at(&format!("ip:{i}"), start + Duration::from_millis(1));
+        }
+        assert_eq!(limiter.buckets.lock().unwrap().len(), MAX_TRACKED_KEYS);
+        assert!(!limiter.buckets.lock().unwrap().contains_key("drained"));
+
+        // Known keys keep their bucket however full the table is
+        let now = start + Duration::from_millis(2);
+        assert!(matches!(limiter.check_at("ip:0", now), RateDecision::Limited { .. }));
+        let peer = Some(SocketAddr::from(([10, 0, 0, 1], 4000)));
+        assert_eq!(client_key(None, None), "anonymous");
+        assert_eq!(client_key(None, peer), "ip:10.0.0.1");
+        assert_eq!(client_key(Some("alice"), peer), "user:alice");
+    }
+}
@@ -0,0 +1,288 @@
+//! Strategy catalog manifests
+//!
+//! A manifest lists strategy definitions (name, code source, default
+//! parameters and parameter presets) in a JSON or YAML document a team can
+//! keep under version control. Syncing it into a workspace creates the
+//! strategies it does not yet hold and brings the drafts it does up to date,
+//! matching by name, so applying the same manifest twice changes nothing.
+//! Strategies that have left draft keep their definition; changing it would
+//! bypass the evidence their promotion was granted on.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use super::StrategyInfo;
+use crate::database::PresetDraft;
+use crate::strategy::{LifecycleState, StrategySource};
+
+/// Manifest format read by this version
+pub const MANIFEST_FORMAT_VERSION: u32 = 1;
+
+/// Most strategies one manifest may define
+pub const MAX_MANIFEST_STRATEGIES: usize = 500;
+
+/// Errors raised when reading a manifest
+#[derive(Debug, thiserror::Error)]
+pub enum ManifestError {
+    #[error("Invalid JSON manifest: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid YAML manifest: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("Manifest format {found} is newer than supported format {supported}")]
+    UnsupportedVersion { found: u32, supported: u32 },
+    #[error("Manifest defines {0} strategies, at most {MAX_MANIFEST_STRATEGIES} allowed")]
+    TooManyStrategies(usize),
+    #[error("Strategy {0} has no name")]
+    MissingName(usize),
+    #[error("Strategy {0} is defined more than once")]
+    DuplicateStrategy(String),
+    #[error("Strategy {strategy} defines preset {preset} more than once")]
+    DuplicatePreset { strategy: String, preset: String },
+}
+
+/// Encoding of a manifest document
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ManifestFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+impl ManifestFormat {
+    /// Format named by a `Content-Type`; anything that is not YAML is read as JSON
+    pub fn from_content_type(content_type: Option<&str>) -> Self {
+        match content_type {
+            Some(content_type) if content_type.contains("yaml") || content_type.contains("yml") => Self::Yaml,
+            _ => Self::Json,
+        }
+    }
+}
+
+/// One strategy definition in a manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestStrategy {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Where the strategy's code lives; `type` is accepted as an alias
+    #[serde(default, alias = "type")]
+    pub source: StrategySource,
+    #[serde(default)]
+    pub parameters: serde_json::Value,
+    #[serde(default)]
+    pub presets: Vec<PresetDraft>,
+}
+
+/// A strategy catalog
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyManifest {
+    #[serde(default = "default_format_version")]
+    pub format_version: u32,
+    pub strategies: Vec<ManifestStrategy>,
+}
+
+fn default_format_version() -> u32 {
+    MANIFEST_FORMAT_VERSION
+}
+
+/// What syncing did with one manifest entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ManifestAction {
+    Created,
+    Updated,
+    /// Already matched the manifest
+    Unchanged,
+    /// Differs from the manifest but has left draft, so was left as it is
+    Skipped,
+}
+
+/// Outcome for one manifest entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestOutcome {
+    pub name: String,
+    pub strategy_id: String,
+    pub action: ManifestAction,
+    /// Presets saved for the strategy; skipped strategies keep theirs
+    pub presets: usize,
+}
+
+/// Outcome of syncing a manifest
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ManifestSyncReport {
+    /// In manifest order
+    pub strategies: Vec<ManifestOutcome>,
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub skipped: usize,
+    pub presets_saved: usize,
+}
+
+impl ManifestSyncReport {
+    pub fn push(&mut self, outcome: ManifestOutcome) {
+        match outcome.action {
+            ManifestAction::Created => self.created += 1,
+            ManifestAction::Updated => self.updated += 1,
+            ManifestAction::Unchanged => self.unchanged += 1,
+            ManifestAction::Skipped => self.skipped += 1,
+        }
+        self.presets_saved += outcome.presets;
+        self.strategies.push(outcome);
+    }
+}
+
+impl StrategyManifest {
+    /// Read and validate a manifest document
+    pub fn parse(text: &str, format: ManifestFormat) -> Result<Self, ManifestError> {
+        let manifest: Self = match format {
+            ManifestFormat::Json => serde_json::from_str(text)?,
+            ManifestFormat::Yaml => serde_yaml::from_str(text)?,
+        };
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// Reject newer formats, unnamed strategies and names defined twice
+    pub fn validate(&self) -> Result<(), ManifestError> {
+        if self.format_version > MANIFEST_FORMAT_VERSION {
+            return Err(ManifestError::UnsupportedVersion {
+                found: self.format_version,
+                supported: MANIFEST_FORMAT_VERSION,
+            });
+        }
+        if self.strategies.len() > MAX_MANIFEST_STRATEGIES {
+            return Err(ManifestError::TooManyStrategies(self.strategies.len()));
+        }
+
+        let mut names = HashSet::new();
+        for (i, strategy) in self.strategies.iter().enumerate() {
+            let name = strategy.name.trim();
+            if name.is_empty() {
+                return Err(ManifestError::MissingName(i));
+            }
+            if !names.insert(name) {
+                return Err(ManifestError::DuplicateStrategy(name.to_string()));
+            }
+            let mut presets = HashSet::new();
+            if let Some(preset) = strategy.presets.iter().find(|p| !presets.insert(p.name.as_str())) {
+                return Err(ManifestError::DuplicatePreset {
+                    strategy: name.to_string(),
+                    preset: preset.name.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Bring the strategies of `workspace_id` in line with the manifest
+    ///
+    /// Returns, in manifest order, the id each entry maps to and what was
+    /// done with it. Strategies the manifest does not mention are left
+    /// alone; presets are saved by the caller.
+    pub fn apply(&self, strategies: &mut Vec<StrategyInfo>, workspace_id: &str) -> Vec<(ManifestAction, String)> {
+        self.strategies.iter()
+            .map(|entry| {
+                let name = entry.name.trim();
+                let existing = strategies.iter_mut()
+                    .find(|s| s.workspace_id == workspace_id && s.name == name);
+
+                match existing {
+                    Some(strategy) if entry.matches(strategy) => (ManifestAction::Unchanged, strategy.id.clone()),
+                    Some(strategy) if strategy.status != LifecycleState::Draft => {
+                        (ManifestAction::Skipped, strategy.id.clone())
+                    }
+                    Some(strategy) => {
+                        strategy.description = entry.description.clone();
+                        strategy.parameters = entry.parameters.clone();
+                        strategy.source = entry.source.clone();
+                        (ManifestAction::Updated, strategy.id.clone())
+                    }
+                    None => {
+                        let id = Uuid::new_v4().to_string();
+                        strategies.push(StrategyInfo {
+                            id: id.clone(),
+                            name: name.to_string(),
+                            description: entry.description.clone(),
+                            parameters: entry.parameters.clone(),
+                            status: LifecycleState::Draft,
+                            workspace_id: workspace_id.to_string(),
+                            lifecycle: Vec::new(),
+                            source: entry.source.clone(),
+                            demo: false,
+                        });
+                        (ManifestAction::Created, id)
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+impl ManifestStrategy {
+    fn matches(&self, strategy: &StrategyInfo) -> bool {
+        strategy.description == self.description
+            && strategy.parameters == self.parameters
+            && strategy.source == self.source
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+strategies:
+  - name: Mean reversion
+    type:
+      kind: template
+      name: mean_reversion
+    parameters:
+      lookback: 20
+    presets:
+      - name: conservative
+        parameters:
+          lookback: 40
+  - name: Breakout
+    description: Opening range breakout
+"#;
+
+    #[test]
+    fn test_sync_is_idempotent_and_spares_promoted_strategies() {
+        let manifest = StrategyManifest::parse(MANIFEST, ManifestFormat::from_content_type(Some("application/yaml")))
+            .unwrap();
+        assert_eq!(manifest.format_version, MANIFEST_FORMAT_VERSION);
+        assert_eq!(manifest.strategies[0].source, StrategySource::Template { name: "mean_reversion".to_string() });
+
+        let mut strategies = Vec::new();
+        let first = manifest.apply(&mut strategies, "research");
+        assert!(first.iter().all(|(action, _)| *action == ManifestAction::Created));
+        assert_eq!(strategies.len(), 2);
+
+        let second = manifest.apply(&mut strategies, "research");
+        assert!(second.iter().all(|(action, _)| *action == ManifestAction::Unchanged));
+        assert_eq!(first[0].1, second[0].1);
+
+        // The same names in another workspace are different strategies
+        manifest.apply(&mut strategies, "default");
+        assert_eq!(strategies.len(), 4);
+
+        let mut changed = manifest.clone();
+        changed.strategies[0].parameters = serde_json::json!({ "lookback": 30 });
+        changed.strategies[1].description = "Range breakout".to_string();
+        strategies[1].status = LifecycleState::Paper;
+        let third = changed.apply(&mut strategies, "research");
+        assert_eq!(third[0].0, ManifestAction::Updated);
+        assert_eq!(strategies[0].parameters["lookback"], 30);
+        assert_eq!(third[1].0, ManifestAction::Skipped);
+        assert_eq!(strategies[1].description, "Opening range breakout");
+
+        let duplicate = r#"{ "strategies": [{ "name": "A" }, { "name": " A " }] }"#;
+        assert!(matches!(
+            StrategyManifest::parse(duplicate, ManifestFormat::Json),
+            Err(ManifestError::DuplicateStrategy(name)) if name == "A"
+        ));
+    }
+}
@@ -1,25 +1,132 @@
 //! API server and WebSocket support
 
 pub mod server;
+pub mod auth;
 pub mod websocket;
 pub mod handlers;
+pub mod limits;
+pub mod dashboard;
+pub mod digest;
+pub mod bundle;
+pub mod manifest;
+pub mod trash;
+pub mod audit;
+pub mod access_log;
+pub mod idempotency;
+pub mod demo;
 
 use axum::{
     Router,
-    routing::{get, post},
+    extract::DefaultBodyLimit,
+    middleware,
+    routing::{delete, get, post, put},
     http::StatusCode,
     Json,
 };
 use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, Mutex, RwLock};
+use tokio::task::AbortHandle;
+
+use crate::analysis::PromotionPolicy;
+#[cfg(feature = "analytics")]
+use crate::analytics::AnalyticsStore;
+use crate::backtesting::metrics::TradeRecord;
+use crate::backtesting::{BacktestConfig, EquityCurveStore, WarmSessions};
+use crate::database::{AnalysisViewStore, HelpArticleStore, PresetStore, WorkflowTemplateStore};
+use crate::jobs::JobQueue;
+use crate::lineage::{IntegrityStore, LineageTracker};
+use crate::monitoring::LatencyRegistry;
+use crate::optimization::{EvaluationStore, OptimizationControl};
+use crate::reporting::TradeLedgerStore;
+use crate::strategy::{CodeArchive, CodeSnapshot, LifecycleState, LifecycleTransition, StrategySource};
+use crate::performance::{DatasetCache, ThreadPools, WarmupReport};
+use crate::subscription::DatasetCatalog;
+use crate::telemetry::JobLogs;
+use crate::workflow::GuidedWorkflowEngine;
+use crate::workspace::{WorkspaceRegistry, DEFAULT_WORKSPACE};
+use dashboard::JobBoard;
+use idempotency::IdempotencyStore;
+use trash::Trash;
+use audit::AuditTrail;
+use auth::ApiKeys;
+use limits::RequestLimits;
+use websocket::{WsAuth, WsMessage};
 
 /// API state shared across handlers
 #[derive(Clone)]
 pub struct ApiState {
     pub strategies: Arc<RwLock<Vec<StrategyInfo>>>,
     pub backtest_results: Arc<RwLock<Vec<BacktestResult>>>,
+    /// Fill ledgers of finished backtests, keyed by result id
+    pub trade_ledgers: Arc<RwLock<HashMap<String, Arc<Vec<TradeRecord>>>>>,
+    /// The same ledgers on disk, indexed for paging
+    pub ledger_store: TradeLedgerStore,
+    /// Compressed per-tick equity curves of finished backtests
+    pub equity_curves: EquityCurveStore,
+    /// Write-ahead logs of running backtests, named by job id
+    pub wal_dir: PathBuf,
     pub system_metrics: Arc<RwLock<SystemMetrics>>,
+    pub optimization_controls: Arc<RwLock<HashMap<String, OptimizationControl>>>,
+    /// Evaluations persisted by running and finished optimizations
+    pub evaluation_store: EvaluationStore,
+    pub latency: LatencyRegistry,
+    /// Engine ingestion and evaluation pools, reconfigurable at runtime
+    pub thread_pools: ThreadPools,
+    /// Background jobs that can be cancelled, keyed by job id
+    pub jobs: Arc<RwLock<HashMap<String, AbortHandle>>>,
+    /// Redis queue shared with job workers; `None` when no Redis is configured
+    pub job_queue: Option<Arc<Mutex<JobQueue>>>,
+    /// Job updates fanned out to WebSocket subscribers
+    pub events: broadcast::Sender<WsMessage>,
+    /// How WebSocket clients are admitted before sending commands
+    pub ws_auth: WsAuth,
+    /// Users identified by the API keys callers present
+    pub api_keys: ApiKeys,
+    pub lineage: LineageTracker,
+    /// Digests recorded for each backtest result, checked on verification
+    pub integrity: IntegrityStore,
+    /// Template sources backtests ran, by digest
+    pub code_archive: CodeArchive,
+    /// Preloaded datasets shared with backtests started from the API
+    pub dataset_cache: DatasetCache,
+    /// Daily datasets ingested by the subscription watcher
+    pub catalog: DatasetCatalog,
+    /// Reports of finished cache warm-up jobs with the workspace that ran
+    /// them, keyed by job id
+    pub cache_warmups: Arc<RwLock<HashMap<String, (String, WarmupReport)>>>,
+    /// Date ranges held in memory for interactive backtests
+    pub warm_sessions: WarmSessions,
+    pub workspaces: WorkspaceRegistry,
+    /// Saved parameter presets; `None` when no database is configured
+    pub presets: Option<PresetStore>,
+    /// User-defined workflow templates; `None` when no database is configured
+    pub workflow_templates: Option<WorkflowTemplateStore>,
+    /// Saved analysis views and dashboards; `None` when no database is configured
+    pub analysis_views: Option<AnalysisViewStore>,
+    /// Editable workflow help articles; `None` when no database is configured
+    pub help_articles: Option<HelpArticleStore>,
+    /// Guided workflows, serving the stored help articles as contextual help
+    pub workflows: Arc<RwLock<GuidedWorkflowEngine>>,
+    /// Rate, job and payload limits applied to every request
+    pub limits: RequestLimits,
+    /// Logs captured from job spans, read back for debugging
+    pub job_logs: JobLogs,
+    /// Progress and recent completions of background jobs for the dashboard
+    pub job_board: JobBoard,
+    /// Idempotency keys of job submissions and the jobs they started
+    pub idempotency: IdempotencyStore,
+    /// Deleted strategies and results, restorable until purged
+    pub trash: Trash,
+    /// Gates a strategy must pass before it can be marked active
+    pub promotion_policy: PromotionPolicy,
+    pub audit: AuditTrail,
+    /// DuckDB mirrors of ledgers and evaluations for ad-hoc SQL
+    #[cfg(feature = "analytics")]
+    pub analytics: AnalyticsStore,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,7 +135,18 @@ pub struct StrategyInfo {
     pub name: String,
     pub description: String,
     pub parameters: serde_json::Value,
-    pub status: String,
+    pub status: LifecycleState,
+    #[serde(default = "default_workspace")]
+    pub workspace_id: String,
+    /// Lifecycle state changes, oldest first
+    #[serde(default)]
+    pub lifecycle: Vec<LifecycleTransition>,
+    /// Where the strategy's code lives
+    #[serde(default)]
+    pub source: StrategySource,
+    /// Seeded by demo mode
+    #[serde(default)]
+    pub demo: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +159,28 @@ pub struct BacktestResult {
     pub sharpe_ratio: f64,
     pub max_drawdown: f64,
     pub total_trades: u32,
+    #[serde(default = "default_workspace")]
+    pub workspace_id: String,
+    /// Run on data held out from development and optimization
+    #[serde(default)]
+    pub out_of_sample: bool,
+    /// Daily returns as fractions, keyed by exchange trade date
+    #[serde(default)]
+    pub daily_returns: BTreeMap<NaiveDate, f64>,
+    /// Code the run used; `None` for results from before snapshots were taken
+    #[serde(default)]
+    pub code: Option<CodeSnapshot>,
+    /// Seeded by demo mode from synthetic data
+    #[serde(default)]
+    pub demo: bool,
+    /// Engine configuration the run used; `None` for results from before it
+    /// was stored
+    #[serde(default)]
+    pub config: Option<BacktestConfig>,
+}
+
+fn default_workspace() -> String {
+    DEFAULT_WORKSPACE.to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -64,13 +204,161 @@ impl Default for SystemMetrics {
 
 /// Create the API router
 pub fn create_router(state: ApiState) -> Router {
-    Router::new()
+    // Routes that start background jobs are also subject to the host job cap
+    let job_routes = Router::new()
+        .route("/api/backtest", post(handlers::run_backtest))
+        .route("/api/optimize", post(handlers::run_optimization))
+        .route("/api/cache/warm", post(handlers::warm_cache))
+        .route_layer(middleware::from_fn_with_state(state.clone(), limits::guard_job_admission));
+    
+    let router = Router::new()
+        .merge(job_routes)
         .route("/api/strategies", get(handlers::list_strategies))
         .route("/api/strategies", post(handlers::create_strategy))
-        .route("/api/backtest", post(handlers::run_backtest))
+        .route("/api/strategies/manifest", post(handlers::sync_strategy_manifest))
+        .route("/api/strategies/:id", delete(handlers::delete_strategy))
+        .route("/api/strategies/:id/status", put(handlers::set_strategy_status))
+        .route("/api/strategies/:id/promotion", get(handlers::check_strategy_promotion))
+        .route("/api/strategies/:id/family-report", get(handlers::get_strategy_family_report))
+        .route("/api/strategies/:id/export", get(handlers::export_strategy))
+        .route("/api/strategies/import", post(handlers::import_strategy))
         .route("/api/backtest/results", get(handlers::get_backtest_results))
-        .route("/api/optimize", post(handlers::run_optimization))
+        .route("/api/backtest/results/:id", delete(handlers::delete_backtest_result))
+        .route("/api/backtest/results/:id/what-if", post(handlers::analyze_cost_what_if))
+        .route("/api/backtest/results/:id/outliers", post(handlers::analyze_outlier_trades))
+        .route("/api/backtest/results/:id/streaks", post(handlers::analyze_streaks))
+        .route("/api/backtest/results/:id/trades/:trade/replay", post(handlers::export_trade_replay))
+        .route("/api/backtest/:id/trades", get(handlers::list_backtest_trades))
+        .route("/api/backtest/results/:id/verify", get(handlers::verify_backtest_result))
+        .route("/api/backtest/results/:id/code", get(handlers::get_result_code))
+        .route("/api/backtest/results/:id/equity", get(handlers::get_equity_curve))
+        .route("/api/backtest/results/:id/prop-firm", post(handlers::simulate_prop_firm_evaluation))
+        .route("/api/backtest/results/:id/risk-of-ruin", post(handlers::estimate_risk_of_ruin))
+        .route("/api/backtest/results/:id/reconcile", post(handlers::reconcile_session))
+        .route("/api/trash", get(handlers::list_trash))
+        .route("/api/audit", get(handlers::list_audit_events))
+        .route("/api/trash/:id", delete(handlers::purge_trash_entry))
+        .route("/api/trash/:id/restore", post(handlers::restore_trash_entry))
+        .route("/api/optimize/:id/control", get(handlers::get_optimization_control))
+        .route("/api/optimize/:id/control", post(handlers::steer_optimization))
+        .route("/api/optimization/:id/results", get(handlers::get_optimization_results))
+        .route("/api/optimization/:id/convergence", get(handlers::get_optimization_convergence))
+        .route("/api/optimization/:id/promote", post(handlers::promote_optimization_result))
+        .route("/api/strategies/:id/presets", get(handlers::list_presets))
+        .route("/api/strategies/:id/presets", post(handlers::save_preset))
+        .route("/api/strategies/:id/presets/:name", get(handlers::get_preset))
+        .route("/api/strategies/:id/presets/:name", delete(handlers::delete_preset))
+        .route("/api/strategies/:id/presets/:name/favorite", put(handlers::set_preset_favorite))
+        .route("/api/workflow-templates", get(handlers::list_workflow_templates))
+        .route("/api/workflow-templates", post(handlers::create_workflow_template))
+        .route("/api/workflow-templates/validate", post(handlers::validate_workflow_template))
+        .route("/api/workflow-templates/:id", get(handlers::get_workflow_template))
+        .route("/api/workflow-templates/:id", put(handlers::update_workflow_template))
+        .route("/api/workflow-templates/:id", delete(handlers::delete_workflow_template))
+        .route("/api/views", get(handlers::list_analysis_views))
+        .route("/api/views", post(handlers::create_analysis_view))
+        .route("/api/views/:id", get(handlers::get_analysis_view))
+        .route("/api/views/:id", put(handlers::update_analysis_view))
+        .route("/api/views/:id", delete(handlers::delete_analysis_view))
+        .route("/api/help/articles", get(handlers::list_help_articles))
+        .route("/api/help/articles", post(handlers::create_help_article))
+        .route("/api/help/articles/:id", get(handlers::get_help_article))
+        .route("/api/help/articles/:id", put(handlers::update_help_article))
+        .route("/api/help/articles/:id", delete(handlers::delete_help_article))
+        .route("/api/help/search", get(handlers::search_help_articles))
+        .route("/api/jobs/:id/logs", get(handlers::get_job_logs))
+        .route("/api/dashboard", get(handlers::get_dashboard))
+        .route("/api/reports/digest", get(handlers::get_digest))
         .route("/api/metrics", get(handlers::get_system_metrics))
-        .route("/ws", get(websocket::websocket_handler))
+        .route("/api/metrics/latency", get(handlers::get_latency_metrics))
+        .route("/api/metrics/threading", get(handlers::get_threading))
+        .route("/api/metrics/threading", put(handlers::configure_threading))
+        .route("/api/workers", get(handlers::list_workers))
+        .route("/api/analysis/correlation", post(handlers::analyze_correlation))
+        .route("/api/analysis/risk-normalized", post(handlers::compare_risk_normalized))
+        .route("/api/orderflow/tape", post(handlers::get_order_flow_tape))
+        .route("/api/orderflow/footprint", post(handlers::get_footprint))
+        .route("/api/lineage", get(handlers::list_lineage))
+        .route("/api/lineage/:id", get(handlers::get_lineage))
+        .route("/api/lineage/:id/invalidate", post(handlers::invalidate_lineage))
+        .route("/api/workspaces", get(handlers::list_workspaces))
+        .route("/api/workspaces", post(handlers::create_workspace))
+        .route("/api/workspaces/:id", get(handlers::get_workspace))
+        .route("/api/workspaces/:id/members", post(handlers::add_workspace_member))
+        .route("/api/workspaces/:id/quota", put(handlers::set_workspace_quota))
+        .route("/api/datasets", get(handlers::list_datasets))
+        .route("/api/datasets/:id", get(handlers::get_dataset))
+        .route("/api/datasets/:id/liquidity", get(handlers::get_dataset_liquidity))
+        .route("/api/datasets/:id/spread", get(handlers::get_dataset_spread))
+        .route("/api/datasets/:id/consistency", get(handlers::get_dataset_consistency))
+        .route("/api/datasets/:id/book-diff", post(handlers::diff_dataset_book))
+        .route("/api/datasets/:id/sample", post(handlers::sample_dataset))
+        .route("/api/datasets/:id/sessions", put(handlers::confirm_dataset_sessions))
+        .route("/api/datasets/:id/license", put(handlers::set_dataset_license))
+        .route("/api/datasets/:id/sessions/detect", get(handlers::detect_dataset_sessions))
+        .route("/api/cache", get(handlers::list_cached_datasets))
+        .route("/api/cache/warm/:id", get(handlers::get_cache_warmup))
+        .route("/api/sessions/warm", get(handlers::list_warm_sessions))
+        .route("/api/sessions/warm", post(handlers::open_warm_session))
+        .route("/api/sessions/warm/:id", get(handlers::get_warm_session))
+        .route("/api/sessions/warm/:id", delete(handlers::close_warm_session))
+        .route("/ws", get(websocket::websocket_handler));
+    #[cfg(feature = "analytics")]
+    let router = router
+        .route("/api/analytics/sync", post(handlers::sync_analytics))
+        .route("/api/analytics/query", post(handlers::query_analytics));
+    
+    router
+        .layer(DefaultBodyLimit::max(state.limits.config.max_body_bytes))
+        .layer(middleware::from_fn_with_state(state.clone(), limits::guard_requests))
+        .layer(middleware::from_fn_with_state(state.clone(), auth::identify))
+        .layer(middleware::from_fn(access_log::trace_requests))
         .with_state(state)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+impl ApiState {
+    /// State with no records, its stores kept under `dir`
+    pub(crate) fn for_tests(dir: &std::path::Path) -> Self {
+        Self {
+            strategies: Default::default(),
+            backtest_results: Default::default(),
+            trade_ledgers: Default::default(),
+            ledger_store: TradeLedgerStore::open(dir.join("ledgers")).unwrap(),
+            equity_curves: EquityCurveStore::open(dir.join("equity")).unwrap(),
+            wal_dir: dir.join("wal"),
+            system_metrics: Default::default(),
+            optimization_controls: Default::default(),
+            evaluation_store: EvaluationStore::open(dir.join("evaluations")).unwrap(),
+            latency: Default::default(),
+            thread_pools: ThreadPools::new(Default::default()).unwrap(),
+            jobs: Default::default(),
+            job_queue: None,
+            events: broadcast::channel(16).0,
+            ws_auth: WsAuth::Closed,
+            api_keys: Default::default(),
+            lineage: LineageTracker::new(),
+            integrity: IntegrityStore::open(dir.join("manifests")).unwrap(),
+            code_archive: Default::default(),
+            dataset_cache: Default::default(),
+            catalog: DatasetCatalog::new(),
+            cache_warmups: Default::default(),
+            warm_sessions: Default::default(),
+            workspaces: Default::default(),
+            presets: None,
+            workflow_templates: None,
+            analysis_views: None,
+            help_articles: None,
+            workflows: Arc::new(RwLock::new(GuidedWorkflowEngine::new())),
+            limits: RequestLimits::new(Default::default()),
+            job_logs: JobLogs::new(crate::telemetry::JobLogConfig { dir: dir.join("logs"), ..Default::default() }),
+            job_board: Default::default(),
+            idempotency: Default::default(),
+            trash: Trash::new(Default::default()),
+            promotion_policy: Default::default(),
+            audit: Default::default(),
+            #[cfg(feature = "analytics")]
+            analytics: AnalyticsStore::open(dir.join("analytics")).unwrap(),
+        }
+    }
+}
@@ -6,19 +6,238 @@ use axum::{
 };
 use tower_http::cors::{CorsLayer, Any};
 use std::net::SocketAddr;
-use tracing::info;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::{broadcast, Mutex, RwLock};
+use tracing::{info, warn};
 
 use super::{ApiState, create_router};
+use super::auth::ApiKeys;
+use super::demo::{demo_config_from_env, seed_demo};
+use super::digest::{run_daily_digest, DigestConfig};
+use super::limits::{LimitsConfig, RequestLimits};
+use super::trash::{RetentionConfig, Trash};
+use super::websocket::WsAuth;
+#[cfg(feature = "analytics")]
+use crate::analytics::AnalyticsStore;
+use crate::backtesting::warm_session::{WarmSessions, DEFAULT_MAX_IDLE_MINUTES};
+use crate::backtesting::EquityCurveStore;
+use crate::database::{AnalysisViewStore, Database, HelpArticleStore, PresetStore, WorkflowTemplateStore};
+use crate::jobs::{GcConfig, JobGarbageCollector, JobQueue, ReaperConfig, WorkerReaper};
+use crate::lineage::{IntegrityStore, LineageTracker};
+use crate::monitoring::{MonitorConfig, PerformanceMonitor};
+use crate::optimization::EvaluationStore;
+use crate::performance::warm_cache::DatasetCache;
+use crate::performance::{ThreadPools, ThreadingConfig};
+use crate::reporting::TradeLedgerStore;
+use crate::subscription::{DatasetCatalog, SubscriptionConfig, SubscriptionWatcher};
+use crate::telemetry::{JobLogConfig, JobLogs};
+use crate::workflow::GuidedWorkflowEngine;
+use crate::workspace::WorkspaceRegistry;
+
+/// Directory optimization evaluations are persisted to unless overridden
+const DEFAULT_EVALUATIONS_DIR: &str = "data/evaluations";
+
+/// Directory compressed equity curves are stored in unless overridden
+const DEFAULT_EQUITY_DIR: &str = "data/equity";
+
+/// Directory trade ledgers are stored in unless overridden
+const DEFAULT_LEDGER_DIR: &str = "data/ledgers";
+
+/// Directory run manifests are written to unless overridden
+const DEFAULT_MANIFEST_DIR: &str = "data/manifests";
+
+/// Directory write-ahead logs of running backtests are kept in unless overridden
+const DEFAULT_WAL_DIR: &str = "data/wal";
+
+/// Job queue workers take from unless overridden
+const DEFAULT_JOB_QUEUE: &str = "jobs";
+
+/// Dataset catalog location unless overridden
+const DEFAULT_CATALOG_PATH: &str = "data/catalog.json";
+
+/// Directory of the per-workspace analytics databases unless overridden
+#[cfg(feature = "analytics")]
+const DEFAULT_ANALYTICS_DIR: &str = "data/analytics";
 
 /// Start the API server
 pub async fn start_server(port: u16) -> Result<(), Box<dyn std::error::Error>> {
+    // Parsed datasets kept in memory, bounded by `STRATEGY_LAB_CACHE_MAX_MB`
+    let dataset_cache = match std::env::var("STRATEGY_LAB_CACHE_MAX_MB").ok().and_then(|mb| mb.parse::<u64>().ok()) {
+        Some(mb) => DatasetCache::new().with_max_bytes(mb * 1024 * 1024),
+        None => DatasetCache::new(),
+    };
+    let evaluations_dir = std::env::var("STRATEGY_LAB_EVALUATIONS_DIR")
+        .unwrap_or_else(|_| DEFAULT_EVALUATIONS_DIR.to_string());
+    
+    // Presets, workflow templates, saved views and help articles need
+    // Postgres; the rest of the API works without it
+    let (presets, workflow_templates, analysis_views, help_articles, database) = match std::env::var("DATABASE_URL") {
+        Ok(url) => {
+            let database = Database::new(&url).await?;
+            database.migrate().await?;
+            (
+                Some(PresetStore::new(database.pool.clone())),
+                Some(WorkflowTemplateStore::new(database.pool.clone())),
+                Some(AnalysisViewStore::new(database.pool.clone())),
+                Some(HelpArticleStore::new(database.pool.clone())),
+                Some(database.pool),
+            )
+        }
+        Err(_) => {
+            warn!("DATABASE_URL not set, parameter presets, workflow templates, saved views and help articles are disabled");
+            (None, None, None, None, None)
+        }
+    };
+    
+    // Edited help articles replace the built-in contextual help
+    let mut workflows = GuidedWorkflowEngine::new();
+    if let Some(store) = &help_articles {
+        match store.list(None).await {
+            Ok(articles) => {
+                let articles: Vec<_> = articles.into_iter().map(|article| article.content.0).collect();
+                workflows.load_help_articles(&articles);
+            }
+            Err(e) => warn!("Failed to load help articles: {}", e),
+        }
+    }
+    
+    // Jobs and stored datasets are charged to their workspace's quotas
+    let workspaces = WorkspaceRegistry::new();
+    
+    let wal_dir = PathBuf::from(
+        std::env::var("STRATEGY_LAB_WAL_DIR").unwrap_or_else(|_| DEFAULT_WAL_DIR.to_string())
+    );
+    
+    // Worker status, reaping and garbage collection need the Redis job queue
+    let job_queue = match std::env::var("REDIS_URL") {
+        Ok(url) => {
+            let queue_name = std::env::var("STRATEGY_LAB_JOB_QUEUE")
+                .unwrap_or_else(|_| DEFAULT_JOB_QUEUE.to_string());
+            WorkerReaper::new(ReaperConfig::default())
+                .spawn(JobQueue::new(&url, &queue_name).await?.with_quotas(workspaces.clone()));
+            let collector = JobGarbageCollector::new(GcConfig::default());
+            let collector = match &database {
+                Some(pool) => collector.with_database(pool.clone()),
+                None => collector,
+            };
+            collector.spawn(JobQueue::new(&url, &queue_name).await?.with_quotas(workspaces.clone()));
+            
+            // Settle the jobs of runs a restart cut short before serving
+            let mut queue = JobQueue::new(&url, &queue_name).await?.with_quotas(workspaces.clone());
+            match queue.recover_interrupted(&wal_dir).await {
+                Ok(interrupted) if !interrupted.is_empty() => {
+                    info!("Recovered {} interrupted backtest jobs", interrupted.len());
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Failed to recover interrupted backtest jobs: {}", e),
+            }
+            Some(Arc::new(Mutex::new(queue)))
+        }
+        Err(_) => {
+            warn!("REDIS_URL not set, worker status, reaping and job garbage collection are disabled");
+            None
+        }
+    };
+    
+    let catalog = DatasetCatalog::open(
+        std::env::var("STRATEGY_LAB_CATALOG").unwrap_or_else(|_| DEFAULT_CATALOG_PATH.to_string())
+    )?
+    .with_quotas(workspaces.clone());
+    let lineage = LineageTracker::new();
+    
+    let thread_pools = ThreadPools::new(ThreadingConfig::default())?;
+    
+    // Ingest daily files delivered to the drop directory, if one is configured
+    if let Ok(drop_dir) = std::env::var("STRATEGY_LAB_DROP_DIR") {
+        let watcher = SubscriptionWatcher::new(SubscriptionConfig::new(drop_dir), catalog.clone())
+            .with_lineage(lineage.clone())
+            .with_thread_pools(thread_pools.clone());
+        tokio::spawn(watcher.run());
+    }
+    
+    // Deleted records stay restorable until the retention task purges them
+    let trash = Trash::new(RetentionConfig::from_env());
+    tokio::spawn(trash.clone().run_retention());
+    
+    // Warm sessions hold their data until closed or idle too long
+    let warm_sessions = WarmSessions::default();
+    tokio::spawn(warm_sessions.clone().run_idle_reaper(chrono::Duration::minutes(DEFAULT_MAX_IDLE_MINUTES)));
+    
     // Create shared state
     let state = ApiState {
         strategies: Default::default(),
         backtest_results: Default::default(),
+        trade_ledgers: Default::default(),
+        ledger_store: TradeLedgerStore::open(
+            std::env::var("STRATEGY_LAB_LEDGER_DIR").unwrap_or_else(|_| DEFAULT_LEDGER_DIR.to_string())
+        )?,
+        equity_curves: EquityCurveStore::open(
+            std::env::var("STRATEGY_LAB_EQUITY_DIR").unwrap_or_else(|_| DEFAULT_EQUITY_DIR.to_string())
+        )?,
+        wal_dir,
         system_metrics: Default::default(),
+        optimization_controls: Default::default(),
+        evaluation_store: EvaluationStore::open(evaluations_dir)?,
+        latency: Default::default(),
+        thread_pools,
+        jobs: Default::default(),
+        job_queue,
+        events: broadcast::channel(256).0,
+        ws_auth: WsAuth::from_env(),
+        api_keys: ApiKeys::from_env(),
+        lineage,
+        integrity: IntegrityStore::open(
+            std::env::var("STRATEGY_LAB_MANIFEST_DIR").unwrap_or_else(|_| DEFAULT_MANIFEST_DIR.to_string())
+        )?,
+        code_archive: Default::default(),
+        dataset_cache,
+        catalog,
+        cache_warmups: Default::default(),
+        warm_sessions,
+        workspaces,
+        presets,
+        workflow_templates,
+        analysis_views,
+        help_articles,
+        workflows: Arc::new(RwLock::new(workflows)),
+        job_logs: JobLogs::new(JobLogConfig::from_env()),
+        limits: RequestLimits::new(LimitsConfig::from_env()),
+        job_board: Default::default(),
+        idempotency: Default::default(),
+        trash,
+        promotion_policy: Default::default(),
+        audit: Default::default(),
+        #[cfg(feature = "analytics")]
+        analytics: AnalyticsStore::open(
+            std::env::var("STRATEGY_LAB_ANALYTICS_DIR").unwrap_or_else(|_| DEFAULT_ANALYTICS_DIR.to_string())
+        )?,
     };
     
+    // Seed synthetic data, strategies and results for exploring without licensed data
+    if let Some(config) = demo_config_from_env() {
+        tokio::spawn(seed_demo(state.clone(), config));
+    }
+    
+    // Send the daily digest once channels are configured
+    match DigestConfig::from_env() {
+        Some(config) => {
+            info!("Daily digest scheduled for {} {}", config.send_at, config.zone);
+            tokio::spawn(run_daily_digest(state.clone(), config));
+        }
+        None => info!("STRATEGY_LAB_DIGEST_CHANNELS not set, daily digest is disabled"),
+    }
+    
+    // Report the engine pools' layout and throughput with the resource
+    // metrics; the API serves its own WebSocket, so the monitor binds none
+    let mut monitor = PerformanceMonitor::new(MonitorConfig {
+        websocket_port: None,
+        save_to_file: false,
+        ..Default::default()
+    })
+    .with_thread_pools(state.thread_pools.clone());
+    monitor.start().await?;
+    
     // Configure CORS
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -34,7 +253,8 @@ pub async fn start_server(port: u16) -> Result<(), Box<dyn std::error::Error>> {
     info!("API server listening on {}", addr);
     
     axum::Server::bind(&addr)
-        .serve(app.into_make_service())
+        // Client addresses key the rate limit of requests without credentials
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await?;
     
     Ok(())
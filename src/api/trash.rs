@@ -0,0 +1,205 @@
+//! Trash for deleted strategies and backtest results
+//!
+//! Deleting a strategy or result moves it here instead of dropping it, so an
+//! accidental delete can be restored. Entries are purged for good once they
+//! are older than the retention period, or on request.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tracing::{info, warn};
+
+use super::{BacktestResult, StrategyInfo};
+
+/// A deleted record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TrashedItem {
+    /// A strategy together with the results deleted along with it
+    Strategy {
+        strategy: StrategyInfo,
+        results: Vec<BacktestResult>,
+    },
+    Result { result: BacktestResult },
+}
+
+/// A deleted record and when it was deleted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    /// Id of the deleted strategy or result
+    pub id: String,
+    pub workspace_id: String,
+    pub deleted_at: DateTime<Utc>,
+    pub deleted_by: Option<String>,
+    /// When the retention task purges the entry
+    pub purge_after: DateTime<Utc>,
+    pub item: TrashedItem,
+}
+
+/// How long deleted records are kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: i64,
+    /// How often expired entries are purged
+    #[serde(default = "default_purge_interval_secs")]
+    pub purge_interval_secs: u64,
+}
+
+fn default_trash_retention_days() -> i64 {
+    30
+}
+
+fn default_purge_interval_secs() -> u64 {
+    3600
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            trash_retention_days: default_trash_retention_days(),
+            purge_interval_secs: default_purge_interval_secs(),
+        }
+    }
+}
+
+impl RetentionConfig {
+    /// Defaults overridden by `STRATEGY_LAB_TRASH_RETENTION_DAYS` and
+    /// `STRATEGY_LAB_TRASH_PURGE_INTERVAL_SECS`
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            match std::env::var(name) {
+                Ok(value) => value.parse().unwrap_or_else(|_| {
+                    warn!("Ignoring invalid {}={}", name, value);
+                    default
+                }),
+                Err(_) => default,
+            }
+        }
+
+        let defaults = Self::default();
+        Self {
+            trash_retention_days: var("STRATEGY_LAB_TRASH_RETENTION_DAYS", defaults.trash_retention_days),
+            purge_interval_secs: var("STRATEGY_LAB_TRASH_PURGE_INTERVAL_SECS", defaults.purge_interval_secs),
+        }
+    }
+}
+
+/// Deleted records awaiting restore or purge, keyed by record id
+#[derive(Debug, Clone, Default)]
+pub struct Trash {
+    config: RetentionConfig,
+    entries: Arc<RwLock<HashMap<String, TrashEntry>>>,
+}
+
+impl Trash {
+    pub fn new(config: RetentionConfig) -> Self {
+        Self {
+            config,
+            entries: Default::default(),
+        }
+    }
+
+    /// Move a record into the trash
+    pub fn put(&self, workspace_id: &str, deleted_by: Option<&str>, item: TrashedItem) -> TrashEntry {
+        let id = match &item {
+            TrashedItem::Strategy { strategy, .. } => strategy.id.clone(),
+            TrashedItem::Result { result } => result.id.clone(),
+        };
+        let deleted_at = Utc::now();
+        let entry = TrashEntry {
+            id: id.clone(),
+            workspace_id: workspace_id.to_string(),
+            deleted_at,
+            deleted_by: deleted_by.map(str::to_string),
+            purge_after: deleted_at + Duration::days(self.config.trash_retention_days),
+            item,
+        };
+        self.entries.write().unwrap().insert(id, entry.clone());
+        entry
+    }
+
+    /// Trashed records of a workspace, most recently deleted first
+    pub fn list(&self, workspace_id: &str) -> Vec<TrashEntry> {
+        let mut entries: Vec<TrashEntry> = self.entries.read().unwrap().values()
+            .filter(|entry| entry.workspace_id == workspace_id)
+            .cloned()
+            .collect();
+        entries.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+        entries
+    }
+
+    pub fn contains(&self, workspace_id: &str, id: &str) -> bool {
+        self.entries.read().unwrap().get(id)
+            .map_or(false, |entry| entry.workspace_id == workspace_id)
+    }
+
+    /// Remove an entry from the trash, to restore or drop it
+    pub fn take(&self, workspace_id: &str, id: &str) -> Option<TrashEntry> {
+        let mut entries = self.entries.write().unwrap();
+        if entries.get(id)?.workspace_id != workspace_id {
+            return None;
+        }
+        entries.remove(id)
+    }
+
+    /// Permanently drop entries past their retention, returning how many were dropped
+    pub fn purge_expired(&self, now: DateTime<Utc>) -> usize {
+        let mut entries = self.entries.write().unwrap();
+        let before = entries.len();
+        entries.retain(|_, entry| entry.purge_after > now);
+        before - entries.len()
+    }
+
+    /// Purge expired entries every `purge_interval_secs`, forever
+    pub async fn run_retention(self) {
+        let period = std::time::Duration::from_secs(self.config.purge_interval_secs.max(1));
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            let purged = self.purge_expired(Utc::now());
+            if purged > 0 {
+                info!("Purged {} trash entries past {} days of retention", purged, self.config.trash_retention_days);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::LifecycleState;
+
+    fn strategy(id: &str) -> StrategyInfo {
+        StrategyInfo {
+            id: id.to_string(),
+            name: "Breakout".to_string(),
+            description: String::new(),
+            parameters: serde_json::json!({}),
+            status: LifecycleState::Draft,
+            workspace_id: "default".to_string(),
+            lifecycle: Vec::new(),
+            source: Default::default(),
+            demo: false,
+        }
+    }
+
+    #[test]
+    fn test_entries_expire_after_retention() {
+        let trash = Trash::new(RetentionConfig { trash_retention_days: 7, ..Default::default() });
+        let entry = trash.put("default", Some("ana"), TrashedItem::Strategy {
+            strategy: strategy("s1"),
+            results: Vec::new(),
+        });
+        trash.put("default", None, TrashedItem::Strategy { strategy: strategy("s2"), results: Vec::new() });
+
+        assert!(trash.take("research", "s1").is_none());
+        assert!(trash.take("default", "s2").is_some());
+        assert_eq!(trash.list("default").len(), 1);
+
+        assert_eq!(trash.purge_expired(entry.deleted_at + Duration::days(6)), 0);
+        assert_eq!(trash.purge_expired(entry.deleted_at + Duration::days(7)), 1);
+        assert!(!trash.contains("default", "s1"));
+    }
+}
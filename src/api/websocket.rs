@@ -1,20 +1,26 @@
-//! WebSocket implementation for real-time updates
+//! WebSocket implementation for real-time updates and client commands
 
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
         State,
     },
+    http::HeaderMap,
     response::Response,
 };
 use futures::{sink::SinkExt, stream::StreamExt};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tokio::sync::broadcast;
-use tracing::{debug, error};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error, warn};
 
 use super::ApiState;
+use super::handlers::{cancel_job, header, resolve_parameters, submit_backtest, RunBacktestRequest, USER_HEADER};
+use super::limits::admit_job;
 use crate::monitoring::ResourceMonitor;
+use crate::workspace::DEFAULT_WORKSPACE;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -38,29 +44,197 @@ pub enum WsMessage {
         best_fitness: f64,
         evaluations: u32,
     },
+    /// A client command was accepted
+    #[serde(rename = "ack")]
+    Ack {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        data: Option<serde_json::Value>,
+    },
     #[serde(rename = "error")]
     Error {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
         message: String,
     },
 }
 
+impl WsMessage {
+    /// Subscription topic of a push update; acks and errors are always delivered
+    pub fn topic(&self) -> Option<WsTopic> {
+        match self {
+            WsMessage::Metrics { .. } => Some(WsTopic::Metrics),
+            WsMessage::BacktestProgress { .. } => Some(WsTopic::BacktestProgress),
+            WsMessage::OptimizationUpdate { .. } => Some(WsTopic::OptimizationUpdate),
+            WsMessage::Ack { .. } | WsMessage::Error { .. } => None,
+        }
+    }
+
+    fn error(request_id: Option<String>, message: impl Into<String>) -> Self {
+        WsMessage::Error { request_id, message: message.into() }
+    }
+}
+
+/// How WebSocket clients are admitted to the command channel
+///
+/// Without a token the channel stays closed unless anonymous access is
+/// switched on explicitly; the socket still carries updates either way.
+#[derive(Debug, Clone, Default)]
+pub enum WsAuth {
+    /// Commands are refused
+    #[default]
+    Closed,
+    /// Clients must authenticate with this token
+    Token(String),
+    /// Every connection may send commands without authenticating
+    Anonymous,
+}
+
+impl WsAuth {
+    /// `STRATEGY_LAB_WS_TOKEN`, or anonymous access when
+    /// `STRATEGY_LAB_WS_ALLOW_ANONYMOUS` is set
+    pub fn from_env() -> Self {
+        if let Ok(token) = std::env::var("STRATEGY_LAB_WS_TOKEN") {
+            if !token.is_empty() {
+                return Self::Token(token);
+            }
+        }
+        let anonymous = std::env::var("STRATEGY_LAB_WS_ALLOW_ANONYMOUS")
+            .is_ok_and(|value| matches!(value.to_ascii_lowercase().as_str(), "1" | "true" | "yes"));
+        if anonymous {
+            warn!("STRATEGY_LAB_WS_ALLOW_ANONYMOUS is set, WebSocket commands are accepted without a token");
+            Self::Anonymous
+        } else {
+            Self::Closed
+        }
+    }
+
+    /// Whether `token` admits a client
+    ///
+    /// Tokens are compared by digest in constant time, so the comparison
+    /// leaks neither where they differ nor the expected length.
+    pub fn accepts(&self, token: &str) -> bool {
+        match self {
+            Self::Closed => false,
+            Self::Anonymous => true,
+            Self::Token(expected) => {
+                let (expected, presented) = (Sha256::digest(expected.as_bytes()), Sha256::digest(token.as_bytes()));
+                expected.iter().zip(presented.iter()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+            }
+        }
+    }
+}
+
+/// Update streams a client can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WsTopic {
+    Metrics,
+    BacktestProgress,
+    OptimizationUpdate,
+}
+
+impl WsTopic {
+    pub const ALL: [WsTopic; 3] = [
+        WsTopic::Metrics,
+        WsTopic::BacktestProgress,
+        WsTopic::OptimizationUpdate,
+    ];
+}
+
+/// Commands a client can send over the socket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum WsCommand {
+    /// Connections act as the user the upgrade request's API key identified
+    Authenticate {
+        token: String,
+        /// Workspace backtests started on the connection run in
+        #[serde(default)]
+        workspace_id: Option<String>,
+    },
+    StartBacktest(RunBacktestRequest),
+    CancelJob { job_id: String },
+    /// Replace the connection's subscriptions with `topics`
+    Subscribe { topics: Vec<WsTopic> },
+    Unsubscribe { topics: Vec<WsTopic> },
+}
+
+/// Command frame; `request_id` is echoed back in the ack or error
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WsRequest {
+    #[serde(default)]
+    pub request_id: Option<String>,
+    #[serde(flatten)]
+    pub command: WsCommand,
+}
+
+/// Per-connection command state
+struct Session {
+    authenticated: bool,
+    /// User identified when the connection was upgraded, if any
+    user_id: Option<String>,
+    workspace_id: String,
+    subscriptions: Arc<Mutex<HashSet<WsTopic>>>,
+}
+
 /// WebSocket handler
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
     State(state): State<ApiState>,
+    headers: HeaderMap,
 ) -> Response {
-    ws.on_upgrade(move |socket| handle_socket(socket, state))
+    let user_id = header(&headers, USER_HEADER).map(str::to_string);
+    ws.on_upgrade(move |socket| handle_socket(socket, state, user_id))
 }
 
 /// Handle individual WebSocket connection
-async fn handle_socket(socket: WebSocket, state: ApiState) {
+async fn handle_socket(socket: WebSocket, state: ApiState, user_id: Option<String>) {
     let (mut sender, mut receiver) = socket.split();
-    let (tx, mut rx) = broadcast::channel::<WsMessage>(100);
+    let (tx, mut rx) = mpsc::channel::<WsMessage>(100);
+    let mut events = state.events.subscribe();
+    
+    // Existing broadcast-only clients keep receiving every update
+    let subscriptions = Arc::new(Mutex::new(WsTopic::ALL.into_iter().collect::<HashSet<_>>()));
+    let mut session = Session {
+        authenticated: matches!(state.ws_auth, WsAuth::Anonymous),
+        user_id,
+        workspace_id: DEFAULT_WORKSPACE.to_string(),
+        subscriptions: subscriptions.clone(),
+    };
     
     // Spawn task to send messages to client
-    let mut send_task = tokio::spawn(async move {
-        while let Ok(msg) = rx.recv().await {
-            let json = serde_json::to_string(&msg).unwrap();
+    let send_task = tokio::spawn(async move {
+        loop {
+            let msg = tokio::select! {
+                msg = rx.recv() => match msg {
+                    Some(msg) => msg,
+                    None => break,
+                },
+                event = events.recv() => match event {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        debug!("WebSocket client lagged, skipped {} updates", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            };
+            
+            if let Some(topic) = msg.topic() {
+                if !subscriptions.lock().unwrap().contains(&topic) {
+                    continue;
+                }
+            }
+            
+            let json = match serde_json::to_string(&msg) {
+                Ok(json) => json,
+                Err(e) => {
+                    error!("Failed to serialize WebSocket message: {}", e);
+                    continue;
+                }
+            };
             if sender.send(Message::Text(json)).await.is_err() {
                 break;
             }
@@ -84,7 +258,7 @@ async fn handle_socket(socket: WebSocket, state: ApiState) {
                 ticks_per_sec: 0, // Would get from actual processing
             };
             
-            if tx_metrics.send(msg).is_err() {
+            if tx_metrics.send(msg).await.is_err() {
                 break;
             }
         }
@@ -95,7 +269,13 @@ async fn handle_socket(socket: WebSocket, state: ApiState) {
         match msg {
             Message::Text(text) => {
                 debug!("Received WebSocket message: {}", text);
-                // Handle client messages here
+                let reply = match serde_json::from_str::<WsRequest>(&text) {
+                    Ok(request) => handle_command(&state, &mut session, request).await,
+                    Err(e) => WsMessage::error(None, format!("Invalid command: {}", e)),
+                };
+                if tx.send(reply).await.is_err() {
+                    break;
+                }
             }
             Message::Close(_) => {
                 debug!("WebSocket connection closed");
@@ -110,7 +290,242 @@ async fn handle_socket(socket: WebSocket, state: ApiState) {
     monitor_task.abort();
 }
 
+/// Execute a client command and build the acknowledgment or error frame
+async fn handle_command(state: &ApiState, session: &mut Session, request: WsRequest) -> WsMessage {
+    let WsRequest { request_id, command } = request;
+    
+    if let WsCommand::Authenticate { token, workspace_id } = &command {
+        match &state.ws_auth {
+            WsAuth::Closed => return WsMessage::error(request_id, "WebSocket commands are disabled"),
+            auth if !auth.accepts(token) => return WsMessage::error(request_id, "Invalid token"),
+            _ => {}
+        }
+        
+        let workspace = workspace_id.as_deref().unwrap_or(DEFAULT_WORKSPACE);
+        if let Err(e) = state.workspaces.authorize(workspace, session.user_id.as_deref()) {
+            return WsMessage::error(request_id, e.to_string());
+        }
+        
+        session.authenticated = true;
+        session.workspace_id = workspace.to_string();
+        return WsMessage::Ack { request_id, data: None };
+    }
+    
+    if !session.authenticated {
+        return WsMessage::error(request_id, "Not authenticated");
+    }
+    
+    match command {
+        WsCommand::Authenticate { .. } => unreachable!("handled above"),
+        WsCommand::StartBacktest(mut req) => {
+            if let Err(exceeded) = admit_job(state).await {
+                return WsMessage::error(request_id, exceeded.error);
+            }
+            let workspace = &session.workspace_id;
+            match resolve_parameters(state, workspace, &req.strategy_id, req.preset.as_deref(), &req.parameters).await {
+                Ok(parameters) => req.parameters = parameters,
+                Err(e) => return WsMessage::error(request_id, e.to_string()),
+            }
+            match submit_backtest(state, workspace, req).await {
+                Ok(backtest_id) => WsMessage::Ack {
+                    request_id,
+                    data: Some(serde_json::json!({ "backtest_id": backtest_id })),
+                },
+                Err(e) => WsMessage::error(request_id, e.to_string()),
+            }
+        }
+        WsCommand::CancelJob { job_id } => {
+            if cancel_job(state, &session.workspace_id, &job_id).await {
+                WsMessage::Ack { request_id, data: None }
+            } else {
+                WsMessage::error(request_id, format!("Unknown job: {}", job_id))
+            }
+        }
+        WsCommand::Subscribe { topics } => {
+            let mut subscriptions = session.subscriptions.lock().unwrap();
+            subscriptions.clear();
+            subscriptions.extend(topics);
+            WsMessage::Ack { request_id, data: None }
+        }
+        WsCommand::Unsubscribe { topics } => {
+            let mut subscriptions = session.subscriptions.lock().unwrap();
+            for topic in &topics {
+                subscriptions.remove(topic);
+            }
+            WsMessage::Ack { request_id, data: None }
+        }
+    }
+}
+
 /// Broadcast message to all connected clients
 pub fn broadcast_message(tx: &broadcast::Sender<WsMessage>, message: WsMessage) {
     let _ = tx.send(message);
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::dashboard::{JobKind, JobOutcome, JobStatus};
+    use crate::api::handlers::spawn_job;
+
+    fn state(auth: WsAuth) -> ApiState {
+        let dir = std::env::temp_dir().join(format!("ws-{}", uuid::Uuid::new_v4()));
+        ApiState { ws_auth: auth, ..ApiState::for_tests(&dir) }
+    }
+
+    fn session(state: &ApiState) -> Session {
+        Session {
+            authenticated: matches!(state.ws_auth, WsAuth::Anonymous),
+            user_id: None,
+            workspace_id: DEFAULT_WORKSPACE.to_string(),
+            subscriptions: Default::default(),
+        }
+    }
+
+    fn request(command: WsCommand) -> WsRequest {
+        WsRequest { request_id: Some("1".to_string()), command }
+    }
+
+    fn authenticate(token: &str) -> WsRequest {
+        request(WsCommand::Authenticate { token: token.to_string(), workspace_id: None })
+    }
+
+    fn is_ack(reply: &WsMessage) -> bool {
+        matches!(reply, WsMessage::Ack { .. })
+    }
+
+    #[tokio::test]
+    async fn test_commands_need_a_token_or_explicit_anonymous_access() {
+        let subscribe = || request(WsCommand::Subscribe { topics: vec![WsTopic::Metrics] });
+
+        // No token configured: nothing authenticates
+        let closed = state(WsAuth::Closed);
+        let mut conn = session(&closed);
+        assert!(!is_ack(&handle_command(&closed, &mut conn, authenticate("")).await));
+        assert!(!is_ack(&handle_command(&closed, &mut conn, subscribe()).await));
+
+        let guarded = state(WsAuth::Token("s3cret".to_string()));
+        let mut conn = session(&guarded);
+        assert!(!is_ack(&handle_command(&guarded, &mut conn, subscribe()).await));
+        assert!(!is_ack(&handle_command(&guarded, &mut conn, authenticate("s3cre")).await));
+        assert!(!is_ack(&handle_command(&guarded, &mut conn, authenticate("s3cret!")).await));
+        assert!(is_ack(&handle_command(&guarded, &mut conn, authenticate("s3cret")).await));
+        assert!(is_ack(&handle_command(&guarded, &mut conn, subscribe()).await));
+
+        let open = state(WsAuth::Anonymous);
+        let mut conn = session(&open);
+        assert!(is_ack(&handle_command(&open, &mut conn, subscribe()).await));
+    }
+
+    #[tokio::test]
+    async fn test_workspaces_admit_the_user_identified_at_upgrade() {
+        let state = state(WsAuth::Token("s3cret".to_string()));
+        let team = state.workspaces.create("Team", "alice", Default::default());
+        let join = || request(WsCommand::Authenticate {
+            token: "s3cret".to_string(),
+            workspace_id: Some(team.id.clone()),
+        });
+
+        let mut anonymous = session(&state);
+        assert!(!is_ack(&handle_command(&state, &mut anonymous, join()).await));
+
+        let mut alice = Session { user_id: Some("alice".to_string()), ..session(&state) };
+        assert!(is_ack(&handle_command(&state, &mut alice, join()).await));
+        assert_eq!(alice.workspace_id, team.id);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cancel_aborts_the_job_and_finished_jobs_leave_no_handle() {
+        let state = state(WsAuth::Anonymous);
+        let mut conn = session(&state);
+
+        state.job_board.start("slow", JobKind::Backtest, DEFAULT_WORKSPACE, "test");
+        spawn_job(&state, "slow", std::future::pending()).await;
+        let cancel = |job_id: &str| request(WsCommand::CancelJob { job_id: job_id.to_string() });
+        assert!(is_ack(&handle_command(&state, &mut conn, cancel("slow")).await));
+        assert!(!state.jobs.read().await.contains_key("slow"));
+        assert!(matches!(
+            state.job_board.status("slow"),
+            Some(JobStatus::Finished(job)) if job.outcome == JobOutcome::Cancelled
+        ));
+        assert!(!is_ack(&handle_command(&state, &mut conn, cancel("slow")).await));
+
+        // Jobs of another workspace are not the session's to cancel
+        state.job_board.start("theirs", JobKind::Backtest, "research", "test");
+        spawn_job(&state, "theirs", std::future::pending()).await;
+        assert!(!is_ack(&handle_command(&state, &mut conn, cancel("theirs")).await));
+        assert!(cancel_job(&state, "research", "theirs").await);
+
+        // A job that removes itself at once still finds its handle registered
+        for i in 0..50 {
+            let id = format!("quick-{i}");
+            let jobs = state.jobs.clone();
+            let task_id = id.clone();
+            spawn_job(&state, &id, async move {
+                jobs.write().await.remove(&task_id);
+            }).await;
+        }
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while !state.jobs.read().await.is_empty() {
+                tokio::task::yield_now().await;
+            }
+        }).await.expect("every quick job removed its own handle");
+    }
+
+    #[tokio::test]
+    async fn test_socket_backtests_respect_the_host_job_cap() {
+        let mut state = state(WsAuth::Anonymous);
+        state.limits = crate::api::limits::RequestLimits::new(crate::api::limits::LimitsConfig {
+            max_inflight_jobs: 1,
+            ..Default::default()
+        });
+        let mut conn = session(&state);
+        spawn_job(&state, "running", std::future::pending()).await;
+
+        let req: RunBacktestRequest = serde_json::from_value(serde_json::json!({
+            "strategy_id": "s",
+            "start_date": "2024-01-02",
+            "end_date": "2024-01-03",
+            "initial_capital": 10000.0,
+        })).unwrap();
+        let reply = handle_command(&state, &mut conn, request(WsCommand::StartBacktest(req))).await;
+        assert!(matches!(reply, WsMessage::Error { message, .. } if message.contains("host limit")));
+    }
+
+    #[tokio::test]
+    async fn test_socket_backtests_resolve_strategy_and_data_before_starting() {
+        let state = state(WsAuth::Anonymous);
+        let mut conn = session(&state);
+        state.strategies.write().await.push(crate::api::StrategyInfo {
+            id: "vwap".to_string(),
+            name: "VWAP".to_string(),
+            description: String::new(),
+            parameters: serde_json::Value::Null,
+            status: crate::strategy::LifecycleState::Draft,
+            workspace_id: DEFAULT_WORKSPACE.to_string(),
+            lifecycle: Vec::new(),
+            source: crate::strategy::StrategySource::Template { name: "VWAP Reversion".to_string() },
+            demo: false,
+        });
+        let start = |fields: serde_json::Value| {
+            let mut req = serde_json::json!({
+                "strategy_id": "vwap",
+                "start_date": "2024-01-02",
+                "end_date": "2024-01-03",
+                "initial_capital": 10000.0,
+            });
+            req.as_object_mut().unwrap().extend(fields.as_object().unwrap().clone());
+            request(WsCommand::StartBacktest(serde_json::from_value(req).unwrap()))
+        };
+
+        for (fields, error) in [
+            (serde_json::json!({ "strategy_id": "other", "dataset_id": "es" }), "Unknown strategy"),
+            (serde_json::json!({}), "needs a dataset_id"),
+            (serde_json::json!({ "dataset_id": "es" }), "Unknown dataset"),
+            (serde_json::json!({ "dataset_id": "es", "end_date": "soon" }), "Invalid date"),
+        ] {
+            let reply = handle_command(&state, &mut conn, start(fields)).await;
+            assert!(matches!(&reply, WsMessage::Error { message, .. } if message.contains(error)), "{:?}", reply);
+        }
+        assert!(state.jobs.read().await.is_empty());
+    }
+}
@@ -0,0 +1,250 @@
+//! Account model with cash, margin requirements and buying power
+//!
+//! Futures positions tie up margin per contract. The account tracks cash and
+//! the net position so the executor can reject orders that would need more
+//! margin than the account has available.
+
+use crate::strategy::OrderSide;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Margin requirements per contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarginConfig {
+    /// Reject orders that exceed available buying power
+    pub enabled: bool,
+
+    /// Margin required to open a contract
+    pub initial_margin: Decimal,
+
+    /// Margin required to keep a contract open
+    pub maintenance_margin: Decimal,
+}
+
+impl Default for MarginConfig {
+    fn default() -> Self {
+        // Exchange margins for one MNQ contract
+        Self {
+            enabled: true,
+            initial_margin: Decimal::from(2_090),
+            maintenance_margin: Decimal::from(1_900),
+        }
+    }
+}
+
+/// Margin state of the account at a point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarginSnapshot {
+    pub timestamp: DateTime<Utc>,
+    pub equity: Decimal,
+    pub margin_used: Decimal,
+    pub buying_power: Decimal,
+    /// Margin used as a fraction of equity
+    pub utilization: f64,
+}
+
+/// Summary of margin usage over a backtest
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MarginReport {
+    pub max_utilization: f64,
+    pub rejected_orders: u32,
+    pub maintenance_breaches: u32,
+    pub snapshots: Vec<MarginSnapshot>,
+}
+
+/// Simulated trading account
+#[derive(Debug, Clone)]
+pub struct Account {
+    config: MarginConfig,
+    cash: Decimal,
+    net_position: i32,
+    rejected_orders: u32,
+    maintenance_breaches: u32,
+    below_maintenance: bool,
+    max_utilization: f64,
+    snapshots: Vec<MarginSnapshot>,
+}
+
+impl Account {
+    pub fn new(initial_cash: Decimal, config: MarginConfig) -> Self {
+        Self {
+            config,
+            cash: initial_cash,
+            net_position: 0,
+            rejected_orders: 0,
+            maintenance_breaches: 0,
+            below_maintenance: false,
+            max_utilization: 0.0,
+            snapshots: Vec::new(),
+        }
+    }
+
+    pub fn cash(&self) -> Decimal {
+        self.cash
+    }
+
+    /// Net contracts held (positive long, negative short)
+    pub fn net_position(&self) -> i32 {
+        self.net_position
+    }
+
+    /// Cash plus the market value of the open position
+    pub fn equity(&self, mark_price: Decimal) -> Decimal {
+        self.cash + mark_price * Decimal::from(self.net_position)
+    }
+
+    /// Initial margin held against the open position
+    pub fn margin_used(&self) -> Decimal {
+        self.config.initial_margin * Decimal::from(self.net_position.abs())
+    }
+
+    /// Equity not committed to margin
+    pub fn buying_power(&self, mark_price: Decimal) -> Decimal {
+        self.equity(mark_price) - self.margin_used()
+    }
+
+    /// Margin used as a fraction of equity
+    pub fn utilization(&self, mark_price: Decimal) -> f64 {
+        let equity = self.equity(mark_price);
+        if equity <= Decimal::ZERO {
+            return if self.net_position == 0 { 0.0 } else { 1.0 };
+        }
+        (self.margin_used() / equity).to_string().parse::<f64>().unwrap_or(0.0)
+    }
+
+    /// Check whether an order fits within available buying power
+    ///
+    /// Orders that reduce the position free margin and are always accepted.
+    pub fn can_fill(&self, side: OrderSide, quantity: i32, price: Decimal, commission: Decimal) -> bool {
+        if !self.config.enabled {
+            return true;
+        }
+
+        let resulting = self.net_position + Self::signed(side, quantity);
+        if resulting.abs() <= self.net_position.abs() {
+            return true;
+        }
+
+        let additional_contracts = resulting.abs() - self.net_position.abs();
+        let required = self.config.initial_margin * Decimal::from(additional_contracts) + commission;
+        required <= self.buying_power(price)
+    }
+
+    /// Record an order rejected for insufficient margin
+    pub fn reject(&mut self, timestamp: DateTime<Utc>, mark_price: Decimal) {
+        self.rejected_orders += 1;
+        self.snapshot(timestamp, mark_price);
+    }
+
+    /// Apply a fill to cash and position
+    pub fn apply_fill(
+        &mut self,
+        side: OrderSide,
+        quantity: i32,
+        price: Decimal,
+        commission: Decimal,
+        timestamp: DateTime<Utc>,
+    ) {
+        let trade_value = price * Decimal::from(quantity);
+        match side {
+            OrderSide::Buy => self.cash -= trade_value + commission,
+            OrderSide::Sell => self.cash += trade_value - commission,
+        }
+        self.net_position += Self::signed(side, quantity);
+
+        self.snapshot(timestamp, price);
+    }
+
+    /// Revalue the position and track peak utilization and maintenance breaches
+    pub fn mark_to_market(&mut self, mark_price: Decimal) {
+        if self.net_position == 0 {
+            self.below_maintenance = false;
+            return;
+        }
+
+        self.max_utilization = self.max_utilization.max(self.utilization(mark_price));
+
+        let maintenance = self.config.maintenance_margin * Decimal::from(self.net_position.abs());
+        let below = self.equity(mark_price) < maintenance;
+        if below && !self.below_maintenance {
+            self.maintenance_breaches += 1;
+        }
+        self.below_maintenance = below;
+    }
+
+    /// Margin usage over the life of the account
+    pub fn report(&self) -> MarginReport {
+        MarginReport {
+            max_utilization: self.max_utilization,
+            rejected_orders: self.rejected_orders,
+            maintenance_breaches: self.maintenance_breaches,
+            snapshots: self.snapshots.clone(),
+        }
+    }
+
+    fn snapshot(&mut self, timestamp: DateTime<Utc>, mark_price: Decimal) {
+        let utilization = self.utilization(mark_price);
+        self.max_utilization = self.max_utilization.max(utilization);
+
+        self.snapshots.push(MarginSnapshot {
+            timestamp,
+            equity: self.equity(mark_price),
+            margin_used: self.margin_used(),
+            buying_power: self.buying_power(mark_price),
+            utilization,
+        });
+    }
+
+    fn signed(side: OrderSide, quantity: i32) -> i32 {
+        match side {
+            OrderSide::Buy => quantity,
+            OrderSide::Sell => -quantity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(cash: i64) -> Account {
+        Account::new(Decimal::from(cash), MarginConfig::default())
+    }
+
+    #[test]
+    fn test_rejects_orders_beyond_buying_power() {
+        let account = account(5_000);
+        let price = Decimal::from(20_000);
+
+        assert!(account.can_fill(OrderSide::Buy, 2, price, Decimal::ZERO));
+        assert!(!account.can_fill(OrderSide::Buy, 3, price, Decimal::ZERO));
+    }
+
+    #[test]
+    fn test_reducing_orders_always_allowed() {
+        let mut account = account(2_500);
+        let price = Decimal::from(20_000);
+
+        account.apply_fill(OrderSide::Sell, 1, price, Decimal::ZERO, Utc::now());
+        assert_eq!(account.margin_used(), Decimal::from(2_090));
+        assert!(!account.can_fill(OrderSide::Sell, 1, price, Decimal::ZERO));
+        assert!(account.can_fill(OrderSide::Buy, 1, price, Decimal::ZERO));
+    }
+
+    #[test]
+    fn test_margin_report() {
+        let mut account = account(10_000);
+        let price = Decimal::from(20_000);
+
+        account.apply_fill(OrderSide::Buy, 2, price, Decimal::ZERO, Utc::now());
+        account.reject(Utc::now(), price);
+        account.mark_to_market(Decimal::from(15_000));
+
+        let report = account.report();
+        assert_eq!(report.rejected_orders, 1);
+        assert_eq!(report.maintenance_breaches, 1);
+        assert_eq!(report.snapshots.len(), 2);
+        assert!((report.snapshots[0].utilization - 0.418).abs() < 1e-9);
+    }
+}
@@ -1,19 +1,39 @@
 //! Core backtesting engine implementation
 
-use crate::data::{DataIngestionEngine, IngestionConfig, TickData};
-use crate::market::{OrderBook};
+use crate::data::{DataIngestionEngine, IngestionConfig, MarketDataType, TickData};
+use crate::market::{BboTracker, BookDepth, OrderBook, OrderBookState};
 use crate::market::order_book::OrderBookManager;
 use crate::strategy::{Strategy, StrategyContext, Order, OrderSide};
+use crate::strategy::position::{CloseOut, LotClose, PositionAccounting, PositionManager};
+use crate::strategy::lookahead::{LookaheadGuard, LookaheadMode, LookaheadViolation};
+use crate::strategy::state::{StateKey, StateResume, StrategyStateStore};
 use crate::strategy::traits::OrderFill;
 use crate::backtesting::{
     StrategyExecutor, TransactionCostModel, PerformanceMetrics, BacktestReport
 };
-use chrono::{DateTime, Utc};
+use crate::backtesting::account::{MarginConfig, MarginReport};
+use crate::backtesting::halts::{Admission, HaltConfig, HaltReport, HaltSimulator};
+use crate::backtesting::metrics::{TradeRecord, DEFAULT_EQUITY_BAR_MS};
+use crate::backtesting::queue_fill::QueueFillConfig;
+use crate::backtesting::quiet_periods::{QuietPeriodConfig, QuietPeriodReport};
+use crate::backtesting::wal::{BacktestWal, WalCheckpoint, WalConfig, WalHeader};
+use crate::backtesting::execution_quality::{
+    ExecutionQualityReport, ExecutionQualityTracker, Quote, DEFAULT_MARKOUT_HORIZONS_MS,
+};
+use crate::monitoring::{AnomalyMonitor, LatencyHistograms, LatencyRegistry, LatencyStage, MetricKind};
+use crate::lineage::{ArtifactKind, ArtifactRole, LineageTracker, RunManifest, VerificationReport};
+use crate::lineage::integrity::canonical_json;
+use crate::performance::warm_cache::{CachedDataset, DatasetCache, DEFAULT_CHECKPOINT_INTERVAL};
+use crate::timestamp::{SessionCalendar, Timestamp};
+use chrono::{DateTime, NaiveDate, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::path::Path;
+use std::sync::Arc;
 use std::time::Instant;
-use tracing::{info, debug, warn};
+use tokio::sync::mpsc;
+use tracing::{field, info, info_span, debug, warn, Instrument};
 
 /// Configuration for backtesting
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +53,26 @@ pub struct BacktestConfig {
     /// Slippage configuration
     pub slippage: SlippageConfig,
     
+    /// How strategy attempts to read future data are handled
+    #[serde(default)]
+    pub lookahead: LookaheadMode,
+    
+    /// Margin requirements and buying power checks
+    #[serde(default)]
+    pub margin: MarginConfig,
+    
+    /// Exchange trading hours used for session boundaries and statistics
+    #[serde(default)]
+    pub session_calendar: SessionCalendar,
+    
+    /// Horizons after each fill at which the mid is sampled for markouts
+    #[serde(default = "default_markout_horizons_ms")]
+    pub markout_horizons_ms: Vec<u64>,
+    
+    /// Saved strategy state to start from when a state store is attached
+    #[serde(default)]
+    pub state_resume: StateResume,
+    
     /// Latency simulation
     pub latency_ms: u32,
     
@@ -44,6 +84,50 @@ pub struct BacktestConfig {
     
     /// Tick processing batch size
     pub batch_size: usize,
+    
+    /// Record per-stage latency histograms of the tick-processing loop
+    ///
+    /// Only takes effect on engines given a `LatencyRegistry` to publish to.
+    #[serde(default = "default_true")]
+    pub latency_instrumentation: bool,
+    
+    /// Fill resting limit orders by estimated queue position rather than
+    /// as soon as the price touches them
+    #[serde(default)]
+    pub queue_fill: Option<QueueFillConfig>,
+    
+    /// Resolution of the recorded equity curve; zero keeps every tick's mark
+    #[serde(default = "default_equity_bar_ms")]
+    pub equity_bar_ms: u64,
+    
+    /// Simulate exchange halts, price limits and velocity logic pauses
+    #[serde(default)]
+    pub halts: Option<HaltConfig>,
+    
+    /// How overnight breaks, weekends and holidays enter the equity curve
+    /// and return statistics
+    #[serde(default)]
+    pub quiet_periods: QuietPeriodConfig,
+    
+    /// Net fills into one position, or hold long and short lots side by side
+    #[serde(default)]
+    pub position_accounting: PositionAccounting,
+    
+    /// Which lot a closing fill takes first under hedged accounting
+    #[serde(default)]
+    pub close_out: CloseOut,
+}
+
+fn default_markout_horizons_ms() -> Vec<u64> {
+    DEFAULT_MARKOUT_HORIZONS_MS.to_vec()
+}
+
+fn default_equity_bar_ms() -> u64 {
+    DEFAULT_EQUITY_BAR_MS
+}
+
+fn default_true() -> bool {
+    true
 }
 
 /// Transaction cost configuration
@@ -72,6 +156,16 @@ pub struct SlippageConfig {
     pub market_impact: f64,
 }
 
+impl Default for SlippageConfig {
+    fn default() -> Self {
+        Self {
+            fixed_slippage: Decimal::from_str_exact("0.25").unwrap(),
+            volume_slippage: 0.001,
+            market_impact: 0.0001,
+        }
+    }
+}
+
 impl Default for BacktestConfig {
     fn default() -> Self {
         Self {
@@ -83,15 +177,23 @@ impl Default for BacktestConfig {
                 exchange_fee: Decimal::from_str_exact("0.35").unwrap(),
                 regulatory_fee: Decimal::from_str_exact("0.03").unwrap(),
             },
-            slippage: SlippageConfig {
-                fixed_slippage: Decimal::from_str_exact("0.25").unwrap(),
-                volume_slippage: 0.001,
-                market_impact: 0.0001,
-            },
+            slippage: SlippageConfig::default(),
+            lookahead: LookaheadMode::default(),
+            margin: MarginConfig::default(),
+            session_calendar: SessionCalendar::default(),
+            markout_horizons_ms: default_markout_horizons_ms(),
+            state_resume: StateResume::default(),
             latency_ms: 1,
             detailed_logging: false,
             save_trades: true,
             batch_size: 10000,
+            latency_instrumentation: true,
+            queue_fill: None,
+            equity_bar_ms: default_equity_bar_ms(),
+            halts: None,
+            quiet_periods: QuietPeriodConfig::default(),
+            position_accounting: PositionAccounting::default(),
+            close_out: CloseOut::default(),
         }
     }
 }
@@ -101,27 +203,180 @@ pub struct BacktestEngine {
     config: BacktestConfig,
     executor: StrategyExecutor,
     order_book_manager: OrderBookManager,
+    /// Best quotes for runs that skip depth reconstruction
+    bbo: BboTracker,
+    book_depth: BookDepth,
+    halts: Option<HaltSimulator>,
     metrics: PerformanceMetrics,
+    positions: PositionManager,
     tick_count: usize,
     start_time: Instant,
+    latency: LatencyHistograms,
+    latency_registry: Option<LatencyRegistry>,
+    lineage: Option<LineageTracker>,
+    lookahead: LookaheadGuard,
+    dataset_cache: Option<DatasetCache>,
+    /// Dataset of the run being started when it was read through the
+    /// cache; its checkpoints let the book seek to the start of the range
+    cached: Option<CachedDataset>,
+    session: SessionStats,
+    execution: ExecutionQualityTracker,
+    anomalies: Option<AnomalyMonitor>,
+    state_store: Option<StrategyStateStore>,
+    wal_config: Option<WalConfig>,
+    wal: Option<ActiveWal>,
+    progress: Option<mpsc::UnboundedSender<RunProgress>>,
+}
+
+/// Where a run stands after a batch of ticks, with its metrics so far
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunProgress {
+    pub ticks_processed: usize,
+    pub total_ticks: usize,
+    /// Time of the last tick processed
+    pub timestamp: DateTime<Utc>,
+    pub equity: Decimal,
+    pub total_return: f64,
+    pub sharpe_ratio: f64,
+    pub current_drawdown: Decimal,
+    pub max_drawdown: Decimal,
+    pub fills: usize,
+}
+
+/// Write-ahead log of the run in progress
+struct ActiveWal {
+    log: BacktestWal,
+    /// Engine tick count when the run started
+    first_tick: usize,
+    next_checkpoint: usize,
+}
+
+/// Running statistics of the current exchange session
+#[derive(Debug, Clone, Default)]
+struct SessionStats {
+    trade_date: Option<NaiveDate>,
+    high: Option<Decimal>,
+    low: Option<Decimal>,
+    volume: i64,
+}
+
+impl SessionStats {
+    fn record_trade(&mut self, tick: &TickData) {
+        self.high = Some(self.high.map_or(tick.price, |high| high.max(tick.price)));
+        self.low = Some(self.low.map_or(tick.price, |low| low.min(tick.price)));
+        self.volume += tick.volume as i64;
+    }
 }
 
 impl BacktestEngine {
     /// Create a new backtesting engine
     pub fn new(config: BacktestConfig) -> Self {
         let transaction_model = TransactionCostModel::from_config(&config.transaction_costs);
-        let executor = StrategyExecutor::new(transaction_model, config.initial_capital);
+        let mut executor = StrategyExecutor::with_margin(
+            transaction_model,
+            config.initial_capital,
+            config.margin.clone(),
+        ).with_slippage(config.slippage.clone());
+        if let Some(queue_fill) = &config.queue_fill {
+            executor = executor.with_queue_model(queue_fill);
+        }
+        let lookahead = LookaheadGuard::new(config.lookahead);
+        let execution = ExecutionQualityTracker::new(config.markout_horizons_ms.clone());
+        let metrics = PerformanceMetrics::new()
+            .with_equity_bar_ms(config.equity_bar_ms)
+            .with_quiet_periods(config.quiet_periods.clone());
+        let halts = config.halts.clone().map(HaltSimulator::new);
+        let positions = PositionManager::new().with_accounting(config.position_accounting, config.close_out);
         
         Self {
             config,
             executor,
             order_book_manager: OrderBookManager::new(true),
-            metrics: PerformanceMetrics::new(),
+            bbo: BboTracker::new(),
+            book_depth: BookDepth::Full,
+            halts,
+            metrics,
+            positions,
             tick_count: 0,
             start_time: Instant::now(),
+            latency: LatencyHistograms::new(),
+            latency_registry: None,
+            lineage: None,
+            lookahead,
+            dataset_cache: None,
+            cached: None,
+            session: SessionStats::default(),
+            execution,
+            anomalies: None,
+            state_store: None,
+            wal_config: None,
+            wal: None,
+            progress: None,
         }
     }
     
+    /// Publish latency histograms to a shared registry after every batch
+    pub fn with_latency_registry(mut self, registry: LatencyRegistry) -> Self {
+        self.latency_registry = Some(registry);
+        self
+    }
+    
+    /// Record the data file and run in a lineage graph
+    pub fn with_lineage(mut self, lineage: LineageTracker) -> Self {
+        self.lineage = Some(lineage);
+        self
+    }
+    
+    /// Read tick data from a preloaded dataset cache, loading into it on a miss
+    pub fn with_dataset_cache(mut self, cache: DatasetCache) -> Self {
+        self.dataset_cache = Some(cache);
+        self
+    }
+    
+    /// Report each run's tick throughput to an anomaly monitor
+    pub fn with_anomaly_monitor(mut self, anomalies: AnomalyMonitor) -> Self {
+        self.anomalies = Some(anomalies);
+        self
+    }
+    
+    /// Restore strategy state before each run and save it afterwards
+    pub fn with_state_store(mut self, store: StrategyStateStore) -> Self {
+        self.state_store = Some(store);
+        self
+    }
+    
+    /// Log fills and checkpoints of each run so an interrupted run can be recovered
+    pub fn with_write_ahead_log(mut self, config: WalConfig) -> Self {
+        self.wal_config = Some(config);
+        self
+    }
+    
+    /// Send the run's progress and metrics so far after every batch
+    pub fn with_progress(mut self, sender: mpsc::UnboundedSender<RunProgress>) -> Self {
+        self.progress = Some(sender);
+        self
+    }
+    
+    /// Latency histograms recorded by this engine since the last publish
+    pub fn latency(&self) -> &LatencyHistograms {
+        &self.latency
+    }
+    
+    /// Whether stages are timed; without a registry nobody reads the samples
+    fn instrumented(&self) -> bool {
+        self.config.latency_instrumentation && self.latency_registry.is_some()
+    }
+    
+    /// Fills made so far, in the order they happened
+    pub fn trades(&self) -> &[TradeRecord] {
+        &self.metrics.trades
+    }
+    
+    /// Equity marked so far at `equity_bar_ms` resolution, for `EquityCurveStore::save`
+    pub fn equity_curve(&self) -> &[(DateTime<Utc>, Decimal)] {
+        self.metrics.get_equity_curve()
+    }
+    
     /// Run backtest on historical data
     pub async fn run_backtest<S, P>(
         &mut self,
@@ -135,14 +390,64 @@ impl BacktestEngine {
         info!("Starting backtest from {} to {}", 
             self.config.start_date, self.config.end_date);
         
+        // Load historical data
+        let path = data_path.as_ref();
+        let ticks = self.load_data(path)
+            .instrument(info_span!("load_data", data = %path.display()))
+            .await?;
+        
+        self.run_loaded(strategy, data_path.as_ref(), &ticks)
+    }
+    
+    /// Run over ticks already read from `data_path`
+    ///
+    /// Used by `SharedScan` so several engines can share one read of a
+    /// dataset; ticks outside the configured date range are skipped.
+    pub(crate) fn run_loaded<S: Strategy>(
+        &mut self,
+        strategy: &mut S,
+        data_path: &Path,
+        ticks: &[TickData],
+    ) -> Result<BacktestResult, Box<dyn std::error::Error>> {
+        let span = info_span!(
+            "backtest_run",
+            strategy = %strategy.get_parameters().name,
+            data = %data_path.display(),
+            ticks = field::Empty,
+            ticks_per_second = field::Empty,
+        );
+        let _entered = span.enter();
         self.start_time = Instant::now();
         
-        // Load historical data
-        let ticks = self.load_data(data_path).await?;
+        let raw_file = self.lineage.as_ref()
+            .map(|lineage| lineage.record_raw_file(data_path));
+        
+        // Checkpoints only describe the ticks they were built from
+        let cached = self.cached.take().filter(|dataset| dataset.ticks.as_ptr() == ticks.as_ptr());
+        let all_ticks = ticks;
+        let ticks = self.in_range(ticks);
+        span.record("ticks", ticks.len());
         info!("Loaded {} ticks for backtesting", ticks.len());
         
+        // Strategies that only read the touch, or data without depth, skip
+        // reconstructing the full book
+        self.book_depth = BookDepth::for_run(strategy.book_depth(), &ticks);
+        if self.book_depth == BookDepth::TopOfBook {
+            debug!("Tracking top of book only");
+        } else {
+            self.seek_book(all_ticks, cached.as_ref());
+        }
+        
         // Reset strategy
         strategy.reset();
+        self.lookahead.reset();
+        self.session = SessionStats::default();
+        self.execution.reset();
+        if let Some(halts) = &mut self.halts {
+            halts.reset();
+        }
+        let resumed_from = self.restore_state(strategy, data_path);
+        self.open_wal(strategy, data_path, ticks.len())?;
         
         // Process ticks in batches for performance
         let mut processed = 0;
@@ -150,6 +455,16 @@ impl BacktestEngine {
             self.process_batch(strategy, batch)?;
             processed += batch.len();
             
+            if let Some(last) = batch.last() {
+                self.checkpoint_wal(strategy, processed, last);
+                self.report_progress(processed, ticks.len(), last);
+            }
+            
+            if let Some(registry) = &self.latency_registry {
+                registry.publish(&self.latency);
+                self.latency.reset();
+            }
+            
             if processed % 100000 == 0 {
                 let rate = self.calculate_processing_rate();
                 debug!("Processed {} ticks, rate: {:.0} ticks/sec", processed, rate);
@@ -157,7 +472,36 @@ impl BacktestEngine {
         }
         
         // Generate final results
-        let result = self.generate_results(strategy);
+        let mut result = self.generate_results(strategy);
+        result.state_resumed_from = resumed_from;
+        self.persist_state(strategy, data_path);
+        
+        if let Some(wal) = self.wal.take() {
+            if let Err(e) = wal.log.finish() {
+                warn!("Failed to remove write-ahead log of completed run: {}", e);
+            }
+        }
+        
+        if let Some(lineage) = &self.lineage {
+            let inputs: Vec<String> = raw_file.into_iter().collect();
+            result.lineage_id = Some(lineage.record(
+                ArtifactKind::Backtest,
+                strategy.get_parameters().name.clone(),
+                &inputs,
+                serde_json::json!({
+                    "start_date": self.config.start_date,
+                    "end_date": self.config.end_date,
+                    "ticks_processed": result.ticks_processed,
+                }),
+            ));
+        }
+        
+        self.seal(&mut result, strategy, data_path, &ticks);
+        
+        if let Some(anomalies) = &self.anomalies {
+            anomalies.observe(MetricKind::Throughput, result.ticks_per_second);
+        }
+        span.record("ticks_per_second", result.ticks_per_second);
         
         let elapsed = self.start_time.elapsed();
         info!("Backtest completed in {:.2}s, processed {} ticks at {:.0} ticks/sec",
@@ -169,29 +513,206 @@ impl BacktestEngine {
         Ok(result)
     }
     
-    /// Load historical tick data
-    async fn load_data<P: AsRef<Path>>(
-        &self,
-        path: P,
-    ) -> Result<Vec<TickData>, Box<dyn std::error::Error>> {
-        let config = IngestionConfig {
-            batch_size: self.config.batch_size,
-            parallel: true,
-            ..Default::default()
+    /// Bring the books up to the start of the configured range
+    ///
+    /// Depth resting before the range is part of the market the run starts
+    /// in, so earlier ticks are replayed into the books, without the
+    /// strategy seeing them. A cached dataset starts the replay from its
+    /// latest checkpoint before the range rather than from the first tick.
+    fn seek_book(&mut self, ticks: &[TickData], cached: Option<&CachedDataset>) {
+        if !ticks.is_sorted_by_key(|t| t.timestamp) {
+            return;
+        }
+        let start_nanos = Timestamp::from(self.config.start_date).nanos();
+        let first = ticks.partition_point(|t| t.timestamp < start_nanos);
+        
+        let mut from = 0;
+        if let Some(checkpoint) = cached.and_then(|dataset| dataset.checkpoint_before(start_nanos - 1)) {
+            self.order_book_manager.restore(checkpoint.books.iter().cloned());
+            from = checkpoint.tick_index + 1;
+        }
+        for tick in &ticks[from.min(first)..first] {
+            self.order_book_manager.process_tick(tick);
+        }
+        if first > 0 {
+            debug!("Replayed {} ticks before the range into the book", first - from.min(first));
+        }
+    }
+    
+    /// Load the state selected by `state_resume`, returning the dataset it was saved for
+    ///
+    /// A missing or unloadable state leaves the strategy as `reset` left it.
+    fn restore_state<S: Strategy>(&self, strategy: &mut S, data_path: &Path) -> Option<String> {
+        let store = self.state_store.as_ref()?;
+        let parameters = strategy.get_parameters();
+        let key = StateKey::new(&parameters.name, &parameters.version, StateKey::dataset_id(data_path));
+        
+        let loaded = match &self.config.state_resume {
+            StateResume::Fresh => return None,
+            StateResume::Previous => store.load_previous(&key),
+            StateResume::From(dataset) => store.load(&StateKey { dataset: dataset.clone(), ..key }),
+        };
+        let envelope = match loaded {
+            Ok(Some(envelope)) => envelope,
+            Ok(None) => return None,
+            Err(e) => {
+                warn!("Failed to read saved state of {}: {}", parameters.name, e);
+                return None;
+            }
         };
         
-        let mut engine = DataIngestionEngine::new(config);
-        let ticks = engine.ingest_file(path).await?;
+        let dataset = envelope.key.dataset;
+        match strategy.load_state(envelope.state, envelope.state_version) {
+            Ok(()) => {
+                info!("Resumed strategy state saved after {}", dataset);
+                Some(dataset)
+            }
+            Err(e) => {
+                warn!("Discarded state saved after {}: {}", dataset, e);
+                strategy.reset();
+                None
+            }
+        }
+    }
+    
+    /// Save the strategy's state under this run's dataset
+    fn persist_state<S: Strategy>(&self, strategy: &S, data_path: &Path) {
+        let Some(store) = &self.state_store else {
+            return;
+        };
+        let Some(state) = strategy.save_state() else {
+            return;
+        };
         
-        // Filter by date range
-        let start_nanos = self.config.start_date.timestamp_nanos_opt().unwrap_or(0);
-        let end_nanos = self.config.end_date.timestamp_nanos_opt().unwrap_or(i64::MAX);
+        let parameters = strategy.get_parameters();
+        let key = StateKey::new(&parameters.name, &parameters.version, StateKey::dataset_id(data_path));
+        if let Err(e) = store.save(key, strategy.state_version(), state) {
+            warn!("Failed to save state of {}: {}", parameters.name, e);
+        }
+    }
+    
+    /// Start this run's write-ahead log, when one is configured
+    fn open_wal<S: Strategy>(
+        &mut self,
+        strategy: &S,
+        data_path: &Path,
+        total_ticks: usize,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(config) = &self.wal_config else {
+            return Ok(());
+        };
+        let header = WalHeader {
+            run_id: config.run_id.clone(),
+            strategy: strategy.get_parameters().name.clone(),
+            data_path: data_path.to_string_lossy().to_string(),
+            start_date: self.config.start_date,
+            end_date: self.config.end_date,
+            total_ticks,
+            started_at: Utc::now(),
+        };
+        self.wal = Some(ActiveWal {
+            log: BacktestWal::create(config, header)?,
+            first_tick: self.tick_count,
+            next_checkpoint: config.checkpoint_ticks,
+        });
+        Ok(())
+    }
+    
+    /// Checkpoint the write-ahead log once another interval of ticks is processed
+    fn checkpoint_wal<S: Strategy>(&mut self, strategy: &S, processed: usize, last: &TickData) {
+        let Some(wal) = &mut self.wal else {
+            return;
+        };
+        if processed < wal.next_checkpoint {
+            return;
+        }
         
-        let filtered: Vec<_> = ticks.into_iter()
-            .filter(|t| t.timestamp >= start_nanos && t.timestamp <= end_nanos)
-            .collect();
+        let position = strategy.get_position();
+        let metrics = strategy.get_metrics();
+        let checkpoint = WalCheckpoint {
+            ticks_processed: processed,
+            last_timestamp: last.timestamp,
+            equity: self.executor.get_equity(position, last.price),
+            capital: self.executor.get_current_capital(),
+            total_pnl: metrics.total_pnl,
+            total_trades: metrics.total_trades,
+            position: position.size,
+            written_at: Utc::now(),
+        };
+        let interval = self.wal_config.as_ref().map_or(processed, |c| c.checkpoint_ticks.max(1));
+        wal.next_checkpoint = (processed / interval + 1) * interval;
+        if let Err(e) = wal.log.checkpoint(checkpoint) {
+            warn!("Stopped write-ahead logging after a failed checkpoint: {}", e);
+            self.wal = None;
+        }
+    }
+    
+    /// Send where the run stands to the progress subscriber, if any
+    fn report_progress(&mut self, processed: usize, total: usize, last: &TickData) {
+        let Some(sender) = &self.progress else {
+            return;
+        };
+        let metrics = &self.metrics;
+        let progress = RunProgress {
+            ticks_processed: processed,
+            total_ticks: total,
+            timestamp: Timestamp::from_nanos(last.timestamp).to_utc(),
+            equity: metrics.equity_curve.last().map_or(self.config.initial_capital, |(_, equity)| *equity),
+            total_return: metrics.total_return,
+            sharpe_ratio: metrics.sharpe_ratio,
+            current_drawdown: metrics.current_drawdown,
+            max_drawdown: metrics.max_drawdown,
+            fills: metrics.total_trades,
+        };
+        // Nobody listening any more
+        if sender.send(progress).is_err() {
+            self.progress = None;
+        }
+    }
+    
+    /// Load historical tick data
+    ///
+    /// With a dataset cache, a following `run_loaded` over the returned
+    /// ticks seeks the book from the cached checkpoints.
+    pub(crate) async fn load_data(
+        &mut self,
+        path: &Path,
+    ) -> Result<Arc<Vec<TickData>>, Box<dyn std::error::Error>> {
+        let parse_start = self.instrumented().then(Instant::now);
+        let ticks = match &self.dataset_cache {
+            Some(cache) => {
+                let dataset = cache.get_or_load(&path.to_string_lossy(), DEFAULT_CHECKPOINT_INTERVAL).await?;
+                let ticks = Arc::clone(&dataset.ticks);
+                self.cached = Some(dataset);
+                ticks
+            }
+            None => read_ticks(path, None, self.config.batch_size).await?,
+        };
+        
+        // Ingestion is batched, so parse latency is amortized across the ticks read
+        if let Some(parse_start) = parse_start.filter(|_| !ticks.is_empty()) {
+            let per_tick = parse_start.elapsed().as_nanos() as u64 / ticks.len() as u64;
+            self.latency.record_n(LatencyStage::Parse, per_tick, ticks.len() as u64);
+        }
         
-        Ok(filtered)
+        Ok(ticks)
+    }
+    
+    /// Ticks inside the configured date range, borrowed when the data is time-ordered
+    pub(crate) fn in_range<'a>(&self, ticks: &'a [TickData]) -> Cow<'a, [TickData]> {
+        let start_nanos = Timestamp::from(self.config.start_date).nanos();
+        let end_nanos = Timestamp::from(self.config.end_date).nanos();
+        
+        if ticks.is_sorted_by_key(|t| t.timestamp) {
+            let from = ticks.partition_point(|t| t.timestamp < start_nanos);
+            let to = ticks.partition_point(|t| t.timestamp <= end_nanos).max(from);
+            Cow::Borrowed(&ticks[from..to])
+        } else {
+            Cow::Owned(ticks.iter()
+                .filter(|t| t.timestamp >= start_nanos && t.timestamp <= end_nanos)
+                .cloned()
+                .collect())
+        }
     }
     
     /// Process a batch of ticks
@@ -200,28 +721,83 @@ impl BacktestEngine {
         strategy: &mut S,
         ticks: &[TickData],
     ) -> Result<(), Box<dyn std::error::Error>> {
+        let instrumented = self.instrumented();
+        
         for tick in ticks {
             // Update order book
-            self.order_book_manager.process_tick(tick);
-            let order_book = self.order_book_manager
-                .get_or_create(&tick.contract_month)
-                .get_state()
-                .clone();
+            let stage_start = instrumented.then(Instant::now);
+            let order_book = match self.book_depth {
+                BookDepth::TopOfBook => self.bbo.process_tick(tick).to_state(&tick.contract_month),
+                BookDepth::Full => {
+                    self.order_book_manager.process_tick(tick);
+                    self.order_book_manager
+                        .get_or_create(&tick.contract_month)
+                        .get_state()
+                        .clone()
+                }
+            };
+            if let Some(stage_start) = stage_start {
+                self.latency.record_since(LatencyStage::BookUpdate, stage_start);
+            }
+            let quote = Quote {
+                mid: order_book.mid_price(),
+                spread: order_book.spread(),
+            };
+            self.execution.on_tick(tick.timestamp, quote.mid);
+            
+            // Session boundaries are decided on the exchange clock
+            let timestamp = Timestamp::from_nanos(tick.timestamp);
+            let trade_date = self.config.session_calendar.trading_date(timestamp);
+            
+            // Resting orders wait out halts and pauses, then match on the
+            // first tick after the reopening
+            let trading = self.halts.as_mut()
+                .is_none_or(|halts| halts.on_tick(tick, trade_date).is_open());
+            if trading {
+                for fill in self.executor.on_market_tick(tick) {
+                    self.record_fill(strategy, fill, tick, quote);
+                }
+            }
+            
+            if trade_date.is_some() && trade_date != self.session.trade_date {
+                if self.session.trade_date.is_some() {
+                    strategy.on_session_end();
+                }
+                self.session = SessionStats {
+                    trade_date,
+                    ..Default::default()
+                };
+            }
+            if matches!(tick.mdt, MarketDataType::Trade) {
+                self.session.record_trade(tick);
+            }
             
             // Create strategy context
             let context = StrategyContext {
                 order_book,
-                timestamp: tick.timestamp,
-                session_high: None, // TODO: Track session stats
-                session_low: None,
-                session_volume: 0,
+                timestamp: timestamp.to_utc(),
+                session_high: self.session.high,
+                session_low: self.session.low,
+                session_volume: self.session.volume,
                 contract: tick.contract_month.clone(),
-                market_open: true,
+                market_open: trade_date.is_some() && trading,
+                lookahead: self.lookahead.clone(),
             };
             
             // Execute strategy
-            if let Some(order) = strategy.on_tick(tick, &context) {
-                self.process_order(strategy, order, tick);
+            let stage_start = instrumented.then(Instant::now);
+            let order = strategy.on_tick(tick, &context);
+            if let Some(stage_start) = stage_start {
+                self.latency.record_since(LatencyStage::StrategyCallback, stage_start);
+            }
+            self.lookahead.enforce()?;
+            
+            if let Some(order) = order {
+                let stage_start = instrumented.then(Instant::now);
+                self.process_order(strategy, order, tick, &context.order_book, quote);
+                if let Some(stage_start) = stage_start {
+                    self.latency.record_since(LatencyStage::OrderMatching, stage_start);
+                }
             }
             
             // Update metrics
@@ -239,28 +815,59 @@ impl BacktestEngine {
         strategy: &mut S,
         order: Order,
         tick: &TickData,
+        order_book: &OrderBookState,
+        quote: Quote,
     ) {
+        let admission = self.halts.as_mut()
+            .map_or(Admission::Accept, |halts| halts.admit(&order, tick.timestamp));
+        match admission {
+            Admission::Accept => {}
+            Admission::Defer => {
+                self.executor.hold_order(order);
+                return;
+            }
+            Admission::Reject(reason) => {
+                self.executor.reject_order(&order, tick, reason);
+                return;
+            }
+        }
+        
         // Simulate order execution with slippage and latency
-        let fill = self.executor.execute_order(order, tick, &self.config.slippage);
+        let fill = self.executor.execute_order(order, tick, Some(order_book));
         
         if let Some(fill) = fill {
-            // Notify strategy of fill
-            strategy.on_order_fill(&fill);
-            
-            // Update metrics
-            self.metrics.record_trade(&fill);
-            
-            if self.config.detailed_logging {
-                debug!("Order filled: {:?} {} @ {} (slippage: {})",
-                    fill.side, fill.quantity, fill.price, fill.slippage);
+            self.record_fill(strategy, fill, tick, quote);
+        }
+    }
+    
+    /// Pass a fill to the strategy, metrics and write-ahead log
+    fn record_fill<S: Strategy>(&mut self, strategy: &mut S, fill: OrderFill, tick: &TickData, quote: Quote) {
+        // Notify strategy of fill
+        strategy.on_order_fill(&fill);
+        
+        // Update metrics
+        self.metrics.record_trade(&fill);
+        self.positions.apply_fill_with_effect(&tick.contract_month, &fill, fill.position_effect);
+        self.execution.record_fill(&fill, tick.timestamp, quote);
+        
+        if let Some(wal) = &mut self.wal {
+            if let Err(e) = wal.log.record_fill(self.tick_count - wal.first_tick, &fill) {
+                warn!("Stopped write-ahead logging after a failed write: {}", e);
+                self.wal = None;
             }
         }
+        
+        if self.config.detailed_logging {
+            debug!("Order filled: {:?} {} @ {} (slippage: {})",
+                fill.side, fill.quantity, fill.price, fill.slippage);
+        }
     }
     
     /// Update performance metrics
     fn update_metrics<S: Strategy>(&mut self, strategy: &S, tick: &TickData) {
         let position = strategy.get_position();
         let equity = self.executor.get_equity(position, tick.price);
+        self.executor.mark_to_market(tick.price);
         
         self.metrics.update_equity(equity, tick.timestamp);
         self.metrics.update_position(position);
@@ -276,6 +883,21 @@ impl BacktestEngine {
         }
     }
     
+    /// Attach digests of the run's inputs, configuration and the result itself
+    fn seal<S: Strategy>(&self, result: &mut BacktestResult, strategy: &S, data_path: &Path, ticks: &[TickData]) {
+        let run_id = result.lineage_id.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let mut manifest = RunManifest::new(run_id);
+        manifest.add_ticks(data_path.display().to_string(), ticks);
+        
+        let hashed = manifest.add_json(ArtifactRole::Config, "backtest_config", &self.config)
+            .and_then(|_| manifest.add_json(ArtifactRole::Config, "strategy_config", strategy.get_parameters()))
+            .and_then(|_| manifest.add_json(ArtifactRole::Output, "result", &*result));
+        match hashed {
+            Ok(()) => result.integrity = Some(manifest),
+            Err(e) => warn!("Failed to hash backtest artifacts: {}", e),
+        }
+    }
+    
     /// Generate backtest results
     fn generate_results<S: Strategy>(&self, strategy: &S) -> BacktestResult {
         let strategy_metrics = strategy.get_metrics();
@@ -296,6 +918,41 @@ impl BacktestEngine {
             ticks_processed: self.tick_count,
             processing_time_secs: elapsed.as_secs_f64(),
             ticks_per_second: self.tick_count as f64 / elapsed.as_secs_f64(),
+            margin: self.executor.margin_report(),
+            lineage_id: None,
+            lookahead_violations: self.lookahead.violation_count(),
+            lookahead_samples: self.lookahead.violations(),
+            execution_quality: self.execution.report(),
+            state_resumed_from: None,
+            integrity: None,
+            book_depth: self.book_depth,
+            halts: self.halts.as_ref().map(|halts| halts.report().clone()).unwrap_or_default(),
+            quiet_periods: self.metrics.quiet_periods.report().clone(),
+            lot_closes: self.positions.closed_lots().into_iter().cloned().collect(),
+        }
+    }
+}
+
+/// Read every tick of a data file, through a dataset cache when one is given
+pub(crate) async fn read_ticks(
+    path: &Path,
+    cache: Option<&DatasetCache>,
+    batch_size: usize,
+) -> Result<Arc<Vec<TickData>>, Box<dyn std::error::Error>> {
+    match cache {
+        Some(cache) => {
+            let key = path.to_string_lossy().to_string();
+            let dataset = cache.get_or_load(&key, DEFAULT_CHECKPOINT_INTERVAL).await?;
+            Ok(Arc::clone(&dataset.ticks))
+        }
+        None => {
+            let config = IngestionConfig {
+                batch_size,
+                parallel: true,
+                ..Default::default()
+            };
+            let mut engine = DataIngestionEngine::new(config);
+            Ok(Arc::new(engine.ingest_file(path).await?))
         }
     }
 }
@@ -317,6 +974,38 @@ pub struct BacktestResult {
     pub ticks_processed: usize,
     pub processing_time_secs: f64,
     pub ticks_per_second: f64,
+    #[serde(default)]
+    pub margin: MarginReport,
+    /// Lineage graph node recorded for this run
+    #[serde(default)]
+    pub lineage_id: Option<String>,
+    /// Refused attempts to read future data
+    #[serde(default)]
+    pub lookahead_violations: usize,
+    /// First refused accesses, for debugging
+    #[serde(default)]
+    pub lookahead_samples: Vec<LookaheadViolation>,
+    /// Fill prices against the mid and markouts after each fill
+    #[serde(default)]
+    pub execution_quality: ExecutionQualityReport,
+    /// Dataset whose saved strategy state this run started from
+    #[serde(default)]
+    pub state_resumed_from: Option<String>,
+    /// Digests of the replayed ticks, configuration and this result
+    #[serde(default)]
+    pub integrity: Option<RunManifest>,
+    /// Whether the run reconstructed full depth or tracked the top of book
+    #[serde(default)]
+    pub book_depth: BookDepth,
+    /// Halts and price limits met when they were simulated
+    #[serde(default)]
+    pub halts: HaltReport,
+    /// Quiet-period policy the return statistics were computed under
+    #[serde(default)]
+    pub quiet_periods: QuietPeriodReport,
+    /// Lots closed under hedged accounting, with P&L per lot
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lot_closes: Vec<LotClose>,
 }
 
 impl Default for BacktestResult {
@@ -336,11 +1025,37 @@ impl Default for BacktestResult {
             ticks_processed: 0,
             processing_time_secs: 0.0,
             ticks_per_second: 0.0,
+            margin: MarginReport::default(),
+            lineage_id: None,
+            lookahead_violations: 0,
+            lookahead_samples: Vec::new(),
+            execution_quality: ExecutionQualityReport::default(),
+            state_resumed_from: None,
+            integrity: None,
+            book_depth: BookDepth::Full,
+            halts: HaltReport::default(),
+            quiet_periods: QuietPeriodReport::default(),
+            lot_closes: Vec::new(),
         }
     }
 }
 
 impl BacktestResult {
+    /// Re-hash this result against the digest recorded when it was produced
+    ///
+    /// `None` for results produced without integrity hashing.
+    pub fn verify_integrity(&self) -> Option<VerificationReport> {
+        let manifest = self.integrity.as_ref()?;
+        let unsealed = BacktestResult { integrity: None, ..self.clone() };
+        let bytes = canonical_json(&unsealed).ok();
+        let mut report = manifest.verify(|digest| match digest.name.as_str() {
+            "result" => bytes.clone(),
+            _ => None,
+        });
+        report.checks.retain(|check| check.role == ArtifactRole::Output);
+        Some(report)
+    }
+    
     /// Generate a summary report
     pub fn summary(&self) -> String {
         format!(
@@ -360,6 +1075,8 @@ Trade Statistics:
 Risk Metrics:
 - Sharpe Ratio: {:.2}
 - Max Drawdown: {}
+- Peak Margin Utilization: {:.1}%
+- Margin Rejections: {}
 
 Performance:
 - Ticks Processed: {}
@@ -379,9 +1096,48 @@ Performance:
             self.avg_trade_duration,
             self.sharpe_ratio,
             self.max_drawdown,
+            self.margin.max_utilization * 100.0,
+            self.margin.rejected_orders,
             self.ticks_processed,
             self.processing_time_secs,
             self.ticks_per_second
         )
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::DataLevel;
+    use crate::strategy::examples::VwapReversionStrategy;
+    use crate::strategy::StrategyConfig;
+
+    #[test]
+    fn test_stages_are_timed_only_for_a_registry() {
+        let start = Timestamp::from(Utc::now() - chrono::Duration::hours(1)).nanos();
+        let ticks: Vec<TickData> = (0..50)
+            .map(|i| TickData::new(
+                DataLevel::L1,
+                MarketDataType::Trade,
+                start + i * 1_000_000_000,
+                Decimal::from(18_000 + i % 5),
+                1,
+                "0624".to_string(),
+            ))
+            .collect();
+        let path = Path::new("data/MNQ/06-24/20240612.parquet");
+
+        let mut strategy = VwapReversionStrategy::new(StrategyConfig::default());
+        let mut engine = BacktestEngine::new(BacktestConfig::default());
+        engine.run_loaded(&mut strategy, path, &ticks).unwrap();
+        assert!(engine.latency().report().stages.is_empty());
+
+        let registry = LatencyRegistry::new();
+        let mut engine = BacktestEngine::new(BacktestConfig::default()).with_latency_registry(registry.clone());
+        engine.run_loaded(&mut strategy, path, &ticks).unwrap();
+        let report = registry.report();
+        let book = report.stages.iter().find(|stage| stage.stage == LatencyStage::BookUpdate).unwrap();
+        assert_eq!(book.count, ticks.len() as u64);
+        assert!(report.stages.iter().any(|stage| stage.stage == LatencyStage::StrategyCallback));
+    }
+}
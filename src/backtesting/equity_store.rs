@@ -0,0 +1,405 @@
+//! Compressed, multi-resolution storage of equity curves
+//!
+//! A backtest marks equity on every tick, so its curve has as many points as
+//! the run had ticks. Stored as rows that is far too much to keep or serve.
+//! A `CompressedEquityCurve` keeps every point as zigzag varint deltas of
+//! the timestamp and of the equity in fixed decimal units, which is a few
+//! bytes per tick and mostly one byte while flat, plus precomputed OHLC
+//! levels, each `LEVEL_FACTOR` times coarser than the one below. A zoomed
+//! out chart is served from the coarsest level that still has enough bars,
+//! a zoomed in one by decoding only the points in range from the nearest
+//! checkpoint.
+//!
+//! Curves are written to `<dir>/<result id>.equity`: a magic number, a
+//! version byte, the length-prefixed JSON header with the levels and
+//! checkpoints, then the encoded deltas.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// File extension of stored curves
+pub const EQUITY_EXTENSION: &str = "equity";
+
+/// Decimal places of account currency kept unless configured otherwise
+pub const DEFAULT_DECIMALS: u32 = 2;
+
+/// Points per bar of the finest level, and the ratio between levels
+pub const LEVEL_FACTOR: usize = 8;
+
+/// Levels stop once one has at most this many bars
+const COARSEST_BARS: usize = 256;
+
+/// Points between decoding checkpoints
+const CHECKPOINT_POINTS: usize = 4096;
+
+const MAGIC: &[u8; 4] = b"SLEQ";
+const FORMAT_VERSION: u8 = 1;
+
+/// Errors raised compressing, storing or reading equity curves
+#[derive(Debug, thiserror::Error)]
+pub enum EquityStoreError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("Invalid equity file: {0}")]
+    InvalidFile(String),
+    #[error("Invalid result id: {0}")]
+    InvalidId(String),
+    #[error("Equity {0} does not fit {1} decimal places")]
+    OutOfRange(Decimal, u32),
+}
+
+/// Equity over a span of points
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EquityBar {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+}
+
+impl EquityBar {
+    fn point(timestamp: DateTime<Utc>, equity: Decimal) -> Self {
+        Self { start: timestamp, end: timestamp, open: equity, high: equity, low: equity, close: equity }
+    }
+
+    fn merge(&mut self, other: &EquityBar) {
+        self.end = other.end;
+        self.high = self.high.max(other.high);
+        self.low = self.low.min(other.low);
+        self.close = other.close;
+    }
+
+    fn overlaps(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> bool {
+        self.end >= from && self.start <= to
+    }
+}
+
+/// One precomputed resolution of a curve
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EquityLevel {
+    pub points_per_bar: usize,
+    pub bars: Vec<EquityBar>,
+}
+
+/// Where decoding can start without reading the deltas before it
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Checkpoint {
+    index: usize,
+    offset: usize,
+    timestamp_ns: i64,
+    units: i64,
+}
+
+/// Part of a curve at the resolution a chart asked for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EquityView {
+    /// 1 when every point in range is returned
+    pub points_per_bar: usize,
+    pub bars: Vec<EquityBar>,
+}
+
+/// Every point of an equity curve, delta encoded, with coarser levels
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompressedEquityCurve {
+    pub points: usize,
+    /// Decimal places equity is stored to
+    pub decimals: u32,
+    /// Finest level first
+    pub levels: Vec<EquityLevel>,
+    checkpoints: Vec<Checkpoint>,
+    #[serde(skip)]
+    deltas: Vec<u8>,
+}
+
+impl CompressedEquityCurve {
+    /// Compress a curve of `(time, equity)` points in time order, keeping
+    /// `decimals` places of equity
+    pub fn compress(curve: &[(DateTime<Utc>, Decimal)], decimals: u32) -> Result<Self, EquityStoreError> {
+        let scale = Decimal::from(10i64.pow(decimals));
+        let mut deltas = Vec::with_capacity(curve.len() * 3);
+        let mut checkpoints = Vec::new();
+        let (mut previous_ns, mut previous_units) = (0i64, 0i64);
+
+        for (index, (timestamp, equity)) in curve.iter().enumerate() {
+            let timestamp_ns = timestamp.timestamp_nanos_opt().unwrap_or(i64::MAX);
+            let units = (equity * scale).round().to_i64()
+                .ok_or(EquityStoreError::OutOfRange(*equity, decimals))?;
+            if index.is_multiple_of(CHECKPOINT_POINTS) {
+                checkpoints.push(Checkpoint { index, offset: deltas.len(), timestamp_ns, units });
+            } else {
+                write_varint(&mut deltas, timestamp_ns.wrapping_sub(previous_ns));
+                write_varint(&mut deltas, units.wrapping_sub(previous_units));
+            }
+            previous_ns = timestamp_ns;
+            previous_units = units;
+        }
+
+        let mut curve = Self { points: curve.len(), decimals, levels: Vec::new(), checkpoints, deltas };
+        curve.levels = curve.build_levels()?;
+        Ok(curve)
+    }
+
+    fn build_levels(&self) -> Result<Vec<EquityLevel>, EquityStoreError> {
+        let mut levels: Vec<EquityLevel> = Vec::new();
+        let mut bars: Vec<EquityBar> = self.decode_from(0)?
+            .map(|(timestamp, equity)| EquityBar::point(timestamp, equity))
+            .collect();
+        let mut points_per_bar = 1;
+        while bars.len() > COARSEST_BARS {
+            bars = bars.chunks(LEVEL_FACTOR)
+                .map(|chunk| {
+                    let mut bar = chunk[0].clone();
+                    chunk[1..].iter().for_each(|next| bar.merge(next));
+                    bar
+                })
+                .collect();
+            points_per_bar *= LEVEL_FACTOR;
+            levels.push(EquityLevel { points_per_bar, bars: bars.clone() });
+        }
+        Ok(levels)
+    }
+
+    /// Approximate size of the encoded points in bytes
+    pub fn encoded_len(&self) -> usize {
+        self.deltas.len() + self.checkpoints.len() * 16
+    }
+
+    /// Every point of the curve
+    pub fn decode(&self) -> Result<Vec<(DateTime<Utc>, Decimal)>, EquityStoreError> {
+        Ok(self.decode_from(0)?.collect())
+    }
+
+    /// Points from the checkpoint `checkpoint` onwards
+    fn decode_from(&self, checkpoint: usize) -> Result<DecodeIter<'_>, EquityStoreError> {
+        if self.checkpoints.len() != self.points.div_ceil(CHECKPOINT_POINTS) {
+            return Err(EquityStoreError::InvalidFile("checkpoints do not match the point count".to_string()));
+        }
+        Ok(DecodeIter {
+            curve: self,
+            index: self.checkpoints.get(checkpoint).map_or(self.points, |c| c.index),
+            offset: self.checkpoints.get(checkpoint).map_or(0, |c| c.offset),
+            timestamp_ns: 0,
+            units: 0,
+        })
+    }
+
+    /// The curve between `from` and `to` in at most about `max_points` bars
+    ///
+    /// Uses the finest resolution that fits: every point when few enough
+    /// are in range, otherwise the finest precomputed level that fits. A
+    /// range too long even for the coarsest level gets the coarsest level.
+    pub fn view(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        max_points: usize,
+    ) -> Result<EquityView, EquityStoreError> {
+        let from = from.unwrap_or(DateTime::<Utc>::MIN_UTC);
+        let to = to.unwrap_or(DateTime::<Utc>::MAX_UTC);
+        let in_range = |level: &EquityLevel| level.bars.iter().filter(|bar| bar.overlaps(from, to)).count();
+
+        // Bars of the finest level bound the points in range
+        let full_resolution = match self.levels.first() {
+            Some(level) => in_range(level) * level.points_per_bar <= max_points,
+            None => true,
+        };
+        if full_resolution {
+            let from_ns = from.timestamp_nanos_opt().unwrap_or(i64::MIN);
+            let start = self.checkpoints.partition_point(|c| c.timestamp_ns <= from_ns).saturating_sub(1);
+            let bars = self.decode_from(start)?
+                .skip_while(|(timestamp, _)| *timestamp < from)
+                .take_while(|(timestamp, _)| *timestamp <= to)
+                .map(|(timestamp, equity)| EquityBar::point(timestamp, equity))
+                .collect();
+            return Ok(EquityView { points_per_bar: 1, bars });
+        }
+
+        let level = self.levels.iter()
+            .find(|level| in_range(level) <= max_points)
+            .or(self.levels.last())
+            .expect("curves too long for full resolution have levels");
+        Ok(EquityView {
+            points_per_bar: level.points_per_bar,
+            bars: level.bars.iter().filter(|bar| bar.overlaps(from, to)).cloned().collect(),
+        })
+    }
+
+    pub fn write_to(&self, path: &Path) -> Result<(), EquityStoreError> {
+        let header = serde_json::to_vec(self)?;
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&[FORMAT_VERSION])?;
+        file.write_all(&(header.len() as u64).to_le_bytes())?;
+        file.write_all(&header)?;
+        file.write_all(&self.deltas)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    pub fn read_from(path: &Path) -> Result<Self, EquityStoreError> {
+        let mut file = BufReader::new(File::open(path)?);
+        let mut preamble = [0u8; 13];
+        file.read_exact(&mut preamble)?;
+        if &preamble[..4] != MAGIC || preamble[4] != FORMAT_VERSION {
+            return Err(EquityStoreError::InvalidFile(format!("{} is not a version {} equity file", path.display(), FORMAT_VERSION)));
+        }
+        let header_len = u64::from_le_bytes(preamble[5..13].try_into().unwrap()) as usize;
+        let mut header = vec![0u8; header_len];
+        file.read_exact(&mut header)?;
+        let mut curve: Self = serde_json::from_slice(&header)?;
+        file.read_to_end(&mut curve.deltas)?;
+        Ok(curve)
+    }
+}
+
+struct DecodeIter<'a> {
+    curve: &'a CompressedEquityCurve,
+    index: usize,
+    offset: usize,
+    timestamp_ns: i64,
+    units: i64,
+}
+
+impl Iterator for DecodeIter<'_> {
+    type Item = (DateTime<Utc>, Decimal);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.curve.points {
+            return None;
+        }
+        if self.index.is_multiple_of(CHECKPOINT_POINTS) {
+            let checkpoint = self.curve.checkpoints[self.index / CHECKPOINT_POINTS];
+            self.timestamp_ns = checkpoint.timestamp_ns;
+            self.units = checkpoint.units;
+        } else {
+            // Truncated deltas end the curve early rather than inventing points
+            self.timestamp_ns = self.timestamp_ns.wrapping_add(read_varint(&self.curve.deltas, &mut self.offset)?);
+            self.units = self.units.wrapping_add(read_varint(&self.curve.deltas, &mut self.offset)?);
+        }
+        self.index += 1;
+        Some((DateTime::from_timestamp_nanos(self.timestamp_ns), Decimal::new(self.units, self.curve.decimals)))
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, value: i64) {
+    let mut zigzag = ((value << 1) ^ (value >> 63)) as u64;
+    while zigzag >= 0x80 {
+        out.push((zigzag as u8) | 0x80);
+        zigzag >>= 7;
+    }
+    out.push(zigzag as u8);
+}
+
+fn read_varint(bytes: &[u8], offset: &mut usize) -> Option<i64> {
+    let mut zigzag = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*offset)?;
+        *offset += 1;
+        zigzag |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some((zigzag >> 1) as i64 ^ -((zigzag & 1) as i64));
+        }
+    }
+    None
+}
+
+/// Compressed equity curves of finished backtests, one file per result
+#[derive(Debug, Clone)]
+pub struct EquityCurveStore {
+    dir: PathBuf,
+}
+
+impl EquityCurveStore {
+    /// Open (creating if needed) a store rooted at `dir`
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, EquityStoreError> {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(Self { dir: dir.as_ref().to_path_buf() })
+    }
+
+    fn path(&self, result_id: &str) -> Result<PathBuf, EquityStoreError> {
+        let valid = !result_id.is_empty()
+            && result_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(EquityStoreError::InvalidId(result_id.to_string()));
+        }
+        Ok(self.dir.join(format!("{}.{}", result_id, EQUITY_EXTENSION)))
+    }
+
+    /// Compress and store the curve of a result, replacing any earlier one
+    pub fn save(&self, result_id: &str, curve: &[(DateTime<Utc>, Decimal)]) -> Result<CompressedEquityCurve, EquityStoreError> {
+        let compressed = CompressedEquityCurve::compress(curve, DEFAULT_DECIMALS)?;
+        let path = self.path(result_id)?;
+        // Write beside the file and rename, so readers never see half a curve
+        let partial = path.with_extension(format!("{}.partial", EQUITY_EXTENSION));
+        compressed.write_to(&partial)?;
+        fs::rename(partial, path)?;
+        Ok(compressed)
+    }
+
+    /// The stored curve of a result, or `None` if it has none
+    pub fn load(&self, result_id: &str) -> Result<Option<CompressedEquityCurve>, EquityStoreError> {
+        match CompressedEquityCurve::read_from(&self.path(result_id)?) {
+            Ok(curve) => Ok(Some(curve)),
+            Err(EquityStoreError::Io(e)) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Delete the stored curve of a result, returning whether there was one
+    pub fn remove(&self, result_id: &str) -> Result<bool, EquityStoreError> {
+        match fs::remove_file(self.path(result_id)?) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_round_trip_and_views() {
+        let start = DateTime::parse_from_rfc3339("2024-06-12T13:30:00Z").unwrap().with_timezone(&Utc);
+        let curve: Vec<(DateTime<Utc>, Decimal)> = (0..10_000i64)
+            .map(|i| (start + Duration::microseconds(i * 250), Decimal::new(1_000_000 + (i % 97) * 125 - i / 3, 2)))
+            .collect();
+
+        let store = EquityCurveStore::open(std::env::temp_dir().join(format!("equity_store_{}", uuid::Uuid::new_v4()))).unwrap();
+        let saved = store.save("bt-1", &curve).unwrap();
+        assert!(saved.encoded_len() < curve.len() * 6);
+        assert_eq!(saved.levels.iter().map(|l| l.points_per_bar).collect::<Vec<_>>(), [8, 64]);
+
+        let loaded = store.load("bt-1").unwrap().unwrap();
+        assert_eq!(loaded.decode().unwrap(), curve);
+        assert!(store.load("bt-2").unwrap().is_none());
+        assert!(store.load("../bt-1").is_err());
+
+        // Zoomed out: the coarsest level that fits, keeping the extremes
+        let whole = loaded.view(None, None, 200).unwrap();
+        assert_eq!((whole.points_per_bar, whole.bars.len()), (64, 157));
+        let low = curve.iter().map(|(_, equity)| *equity).min().unwrap();
+        assert_eq!(whole.bars.iter().map(|bar| bar.low).min().unwrap(), low);
+
+        // Zoomed in across a checkpoint: every point in range
+        let (from, to) = (curve[4090].0, curve[4200].0);
+        let zoomed = loaded.view(Some(from), Some(to), 500).unwrap();
+        assert_eq!(zoomed.points_per_bar, 1);
+        assert_eq!(zoomed.bars.len(), 111);
+        assert_eq!(zoomed.bars[10].close, curve[4100].1);
+
+        assert!(store.remove("bt-1").unwrap());
+        assert!(!store.remove("bt-1").unwrap());
+    }
+}
@@ -0,0 +1,289 @@
+//! Execution quality of simulated fills
+//!
+//! Scores each fill against the prevailing mid: how far from mid it filled,
+//! how much of the quoted spread it captured, and where the mid went in the
+//! following milliseconds (markouts). All signed quantities are from the
+//! trader's point of view, so positive is good unless stated otherwise:
+//!
+//! - `fill_vs_mid = side * (price - mid)`: price paid over mid, a cost
+//! - `spread_capture = -2 * fill_vs_mid / quoted_spread`: +1 filled at the
+//!   near touch (bid for a buy), 0 at mid, -1 crossing to the far touch
+//! - `adverse_selection = -side * (mid_after - mid)`: how far the mid moved
+//!   against the position after the fill, a cost
+//! - `realized_spread = -2 * side * (price - mid_after)`: spread earned once
+//!   the mid has moved, i.e. effective spread minus adverse selection
+//!
+//! Scalpers tuning entry aggressiveness look for the point where extra
+//! spread capture from passive entries stops paying for the adverse
+//! selection it brings.
+
+use crate::strategy::traits::OrderFill;
+use crate::strategy::OrderSide;
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Markout horizons used unless configured otherwise
+pub const DEFAULT_MARKOUT_HORIZONS_MS: [u64; 3] = [100, 1_000, 5_000];
+
+/// Prevailing quote when a fill happens
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Quote {
+    pub mid: Option<Decimal>,
+    pub spread: Option<Decimal>,
+}
+
+/// Mid price some time after a fill
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Markout {
+    pub horizon_ms: u64,
+    /// First mid observed at or after the horizon; `None` if the data ended first
+    pub mid: Option<Decimal>,
+    pub adverse_selection: Option<Decimal>,
+    pub realized_spread: Option<Decimal>,
+}
+
+/// Execution metrics of one fill
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FillQuality {
+    pub order_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub side: String,
+    pub price: Decimal,
+    pub quantity: u32,
+    /// Mid when the fill happened; fills without a two-sided book have none
+    pub mid: Option<Decimal>,
+    pub quoted_spread: Option<Decimal>,
+    pub fill_vs_mid: Option<Decimal>,
+    pub spread_capture: Option<f64>,
+    pub markouts: Vec<Markout>,
+}
+
+/// Quantity-weighted markout averages at one horizon
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HorizonSummary {
+    pub horizon_ms: u64,
+    pub fills: usize,
+    pub avg_adverse_selection: f64,
+    pub avg_realized_spread: f64,
+}
+
+/// Execution quality section of a backtest report
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecutionQualityReport {
+    /// Fills that had a mid to compare against
+    pub scored_fills: usize,
+    pub unscored_fills: usize,
+    /// Quantity-weighted averages over scored fills
+    pub avg_fill_vs_mid: f64,
+    pub avg_effective_spread: f64,
+    pub avg_spread_capture: f64,
+    /// Share of scored fills that crossed to the far half of the spread
+    pub aggressive_fill_ratio: f64,
+    pub horizons: Vec<HorizonSummary>,
+    pub fills: Vec<FillQuality>,
+}
+
+fn side_sign(side: &OrderSide) -> Decimal {
+    if *side == OrderSide::Buy { Decimal::ONE } else { -Decimal::ONE }
+}
+
+fn to_f64(value: Decimal) -> f64 {
+    value.to_f64().unwrap_or(0.0)
+}
+
+/// A fill still waiting for some of its markouts
+#[derive(Debug, Clone, Copy)]
+struct PendingFill {
+    index: usize,
+    timestamp_ns: i64,
+    sign: Decimal,
+    next_horizon: usize,
+}
+
+/// Collects fills during a backtest and resolves their markouts as ticks arrive
+#[derive(Debug, Clone)]
+pub struct ExecutionQualityTracker {
+    horizons_ms: Vec<u64>,
+    fills: Vec<FillQuality>,
+    pending: Vec<PendingFill>,
+}
+
+impl Default for ExecutionQualityTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_MARKOUT_HORIZONS_MS.to_vec())
+    }
+}
+
+impl ExecutionQualityTracker {
+    pub fn new(mut horizons_ms: Vec<u64>) -> Self {
+        horizons_ms.sort_unstable();
+        horizons_ms.dedup();
+        Self {
+            horizons_ms,
+            fills: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.fills.clear();
+        self.pending.clear();
+    }
+
+    /// Record a fill at `timestamp_ns` against the quote prevailing then
+    pub fn record_fill(&mut self, fill: &OrderFill, timestamp_ns: i64, quote: Quote) {
+        let sign = side_sign(&fill.side);
+        let fill_vs_mid = quote.mid.map(|mid| sign * (fill.price - mid));
+        let spread_capture = match (fill_vs_mid, quote.spread) {
+            (Some(cost), Some(spread)) if spread > Decimal::ZERO => Some(-2.0 * to_f64(cost) / to_f64(spread)),
+            _ => None,
+        };
+
+        let markouts = self.horizons_ms.iter()
+            .map(|&horizon_ms| Markout {
+                horizon_ms,
+                mid: None,
+                adverse_selection: None,
+                realized_spread: None,
+            })
+            .collect();
+
+        self.fills.push(FillQuality {
+            order_id: fill.order_id.clone(),
+            timestamp: fill.timestamp,
+            side: if fill.side == OrderSide::Buy { "Buy" } else { "Sell" }.to_string(),
+            price: fill.price,
+            quantity: fill.quantity.unsigned_abs(),
+            mid: quote.mid,
+            quoted_spread: quote.spread,
+            fill_vs_mid,
+            spread_capture,
+            markouts,
+        });
+
+        // Markouts are only meaningful against a starting mid
+        if quote.mid.is_some() && !self.horizons_ms.is_empty() {
+            self.pending.push(PendingFill {
+                index: self.fills.len() - 1,
+                timestamp_ns,
+                sign,
+                next_horizon: 0,
+            });
+        }
+    }
+
+    /// Resolve markouts whose horizon has passed by `timestamp_ns`
+    pub fn on_tick(&mut self, timestamp_ns: i64, mid: Option<Decimal>) {
+        let Some(mid) = mid else {
+            return;
+        };
+
+        let horizons_ms = &self.horizons_ms;
+        let fills = &mut self.fills;
+        self.pending.retain_mut(|pending| {
+            while let Some(&horizon_ms) = horizons_ms.get(pending.next_horizon) {
+                let due_ns = pending.timestamp_ns.saturating_add(horizon_ms as i64 * 1_000_000);
+                if timestamp_ns < due_ns {
+                    return true;
+                }
+
+                let fill = &mut fills[pending.index];
+                let fill_mid = fill.mid.unwrap_or(mid);
+                let markout = &mut fill.markouts[pending.next_horizon];
+                markout.mid = Some(mid);
+                markout.adverse_selection = Some(-pending.sign * (mid - fill_mid));
+                markout.realized_spread = Some(Decimal::from(-2) * pending.sign * (fill.price - mid));
+                pending.next_horizon += 1;
+            }
+            false
+        });
+    }
+
+    /// Summarize every fill recorded so far
+    pub fn report(&self) -> ExecutionQualityReport {
+        let scored: Vec<&FillQuality> = self.fills.iter().filter(|f| f.fill_vs_mid.is_some()).collect();
+        let weight = |f: &FillQuality| f.quantity.max(1) as f64;
+        let total_weight: f64 = scored.iter().map(|f| weight(f)).sum();
+        let weighted_avg = |value: &dyn Fn(&FillQuality) -> Option<f64>| -> f64 {
+            let (sum, weights) = scored.iter()
+                .filter_map(|f| value(f).map(|v| (v * weight(f), weight(f))))
+                .fold((0.0, 0.0), |(s, w), (v, fw)| (s + v, w + fw));
+            if weights > 0.0 { sum / weights } else { 0.0 }
+        };
+
+        let avg_fill_vs_mid = weighted_avg(&|f| f.fill_vs_mid.map(to_f64));
+        let horizons = self.horizons_ms.iter().enumerate()
+            .map(|(i, &horizon_ms)| HorizonSummary {
+                horizon_ms,
+                fills: scored.iter().filter(|f| f.markouts[i].adverse_selection.is_some()).count(),
+                avg_adverse_selection: weighted_avg(&|f| f.markouts[i].adverse_selection.map(to_f64)),
+                avg_realized_spread: weighted_avg(&|f| f.markouts[i].realized_spread.map(to_f64)),
+            })
+            .collect();
+
+        let aggressive = scored.iter()
+            .filter(|f| f.spread_capture.is_some_and(|capture| capture < 0.0))
+            .map(|f| weight(f))
+            .sum::<f64>();
+
+        ExecutionQualityReport {
+            scored_fills: scored.len(),
+            unscored_fills: self.fills.len() - scored.len(),
+            avg_fill_vs_mid,
+            avg_effective_spread: 2.0 * avg_fill_vs_mid,
+            avg_spread_capture: weighted_avg(&|f| f.spread_capture),
+            aggressive_fill_ratio: if total_weight > 0.0 { aggressive / total_weight } else { 0.0 },
+            horizons,
+            fills: self.fills.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(value: &str) -> Decimal {
+        Decimal::from_str_exact(value).unwrap()
+    }
+
+    fn fill(side: OrderSide, price: Decimal) -> OrderFill {
+        OrderFill {
+            order_id: "o-1".to_string(),
+            timestamp: Utc::now(),
+            price,
+            quantity: 1,
+            side,
+            commission: Decimal::ZERO,
+            slippage: Decimal::ZERO,
+            tag: None,
+            position_effect: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_passive_buy_adversely_selected() {
+        let mut tracker = ExecutionQualityTracker::new(vec![1_000, 100]);
+        let quote = Quote { mid: Some(price("100.125")), spread: Some(price("0.25")) };
+
+        // Buy at the bid, then the mid drops a full tick
+        tracker.record_fill(&fill(OrderSide::Buy, price("100.00")), 0, quote);
+        tracker.on_tick(50_000_000, Some(price("100.125")));
+        tracker.on_tick(150_000_000, Some(price("99.875")));
+
+        let report = tracker.report();
+        assert_eq!(report.scored_fills, 1);
+        assert!((report.avg_spread_capture - 1.0).abs() < 1e-9);
+        assert!((report.avg_fill_vs_mid + 0.125).abs() < 1e-9);
+
+        let markouts = &report.fills[0].markouts;
+        assert_eq!(markouts[0].horizon_ms, 100);
+        assert_eq!(markouts[0].adverse_selection, Some(price("0.25")));
+        assert_eq!(markouts[0].realized_spread, Some(price("-0.25")));
+        // Data ended before the longer horizon
+        assert_eq!(markouts[1].mid, None);
+        assert_eq!(report.horizons[1].fills, 0);
+    }
+}
@@ -1,161 +1,162 @@
 //! Strategy execution with realistic order fills and transaction costs
 
 use crate::data::TickData;
-use crate::strategy::{Order, OrderSide, OrderType, Position};
+use crate::market::OrderBookState;
+use crate::strategy::orders::ExecutionReport;
+use crate::strategy::{Order, Position};
 use crate::strategy::traits::OrderFill;
 use crate::backtesting::{TransactionCostModel};
+use crate::backtesting::account::{Account, MarginConfig, MarginReport};
 use crate::backtesting::engine::SlippageConfig;
+use crate::backtesting::matching::{Match, MatchingEngine};
+use crate::backtesting::queue_fill::QueueFillConfig;
 use chrono::Utc;
 use rust_decimal::Decimal;
-use uuid::Uuid;
-use tracing::debug;
+use tracing::{debug, warn};
 
 /// Executes strategy orders with realistic fills
+///
+/// Matching is left to a `MatchingEngine`; the executor books what it
+/// matches against the account, charging commissions and enforcing margin.
 pub struct StrategyExecutor {
     transaction_model: TransactionCostModel,
-    current_capital: Decimal,
+    account: Account,
     initial_capital: Decimal,
-    pending_orders: Vec<Order>,
+    matching: MatchingEngine,
     filled_orders: Vec<OrderFill>,
 }
 
 impl StrategyExecutor {
     pub fn new(transaction_model: TransactionCostModel, initial_capital: Decimal) -> Self {
+        Self::with_margin(transaction_model, initial_capital, MarginConfig::default())
+    }
+    
+    /// Create an executor enforcing the given margin requirements
+    pub fn with_margin(
+        transaction_model: TransactionCostModel,
+        initial_capital: Decimal,
+        margin: MarginConfig,
+    ) -> Self {
         Self {
             transaction_model,
-            current_capital: initial_capital,
+            account: Account::new(initial_capital, margin),
             initial_capital,
-            pending_orders: Vec::new(),
+            matching: MatchingEngine::new(SlippageConfig::default()),
             filled_orders: Vec::new(),
         }
     }
     
+    /// Charge this slippage on market orders and triggered stops
+    pub fn with_slippage(mut self, config: SlippageConfig) -> Self {
+        self.matching.set_slippage(config);
+        self
+    }
+    
+    /// Fill resting limit orders only once the queue ahead of them trades
+    pub fn with_queue_model(mut self, config: &QueueFillConfig) -> Self {
+        self.matching = self.matching.with_queue_model(config);
+        self
+    }
+    
     /// Execute an order with simulated market conditions
+    ///
+    /// See `MatchingEngine::submit` for how `book` is used.
     pub fn execute_order(
         &mut self,
         order: Order,
         tick: &TickData,
-        slippage_config: &SlippageConfig,
+        book: Option<&OrderBookState>,
     ) -> Option<OrderFill> {
-        // Simulate order execution based on type
-        let fill_price = match order.order_type {
-            OrderType::Market => {
-                // Market orders execute immediately with slippage
-                self.calculate_fill_price(tick.price, order.side, order.quantity, slippage_config)
-            }
-            OrderType::Limit => {
-                // Check if limit price would fill
-                if let Some(limit) = order.limit_price {
-                    if self.would_limit_fill(limit, tick.price, order.side) {
-                        limit
-                    } else {
-                        // Order doesn't fill
-                        self.pending_orders.push(order);
-                        return None;
-                    }
-                } else {
-                    return None;
-                }
-            }
-            OrderType::Stop | OrderType::StopLimit => {
-                // Simplified stop order handling
-                if let Some(stop) = order.stop_price {
-                    if self.would_stop_trigger(stop, tick.price, order.side) {
-                        self.calculate_fill_price(tick.price, order.side, order.quantity, slippage_config)
-                    } else {
-                        self.pending_orders.push(order);
-                        return None;
-                    }
-                } else {
-                    return None;
-                }
-            }
-        };
-        
+        let matched = self.matching.submit(order, tick, book)?;
+        self.fill(matched, tick)
+    }
+    
+    /// Accept an order without matching it until the market is next advanced
+    pub fn hold_order(&mut self, order: Order) {
+        self.matching.hold(order);
+    }
+    
+    /// Refuse an order, recording why
+    pub fn reject_order(&mut self, order: &Order, tick: &TickData, reason: &str) {
+        self.matching.reject(order, tick, reason);
+    }
+    
+    /// Cancel a resting order, returning it if it had not traded yet
+    pub fn cancel_order(&mut self, order_id: &str, timestamp: i64) -> Option<Order> {
+        self.matching.cancel(order_id, timestamp)
+    }
+    
+    /// Book a match against the account, rejecting it without the margin
+    fn fill(&mut self, matched: Match, tick: &TickData) -> Option<OrderFill> {
+        let Match { order, quantity, price: fill_price, slippage } = matched;
         // Calculate transaction costs
-        let commission = self.transaction_model.calculate_commission(order.quantity);
-        let slippage = (fill_price - tick.price).abs();
+        let commission = self.transaction_model.calculate_commission(quantity);
+        
+        // Orders that exceed buying power are rejected outright
+        if !self.account.can_fill(order.side, quantity, fill_price, commission) {
+            warn!("Order rejected for insufficient margin: {:?} {} @ {} (buying power: {})",
+                order.side, quantity, fill_price, self.account.buying_power(fill_price));
+            self.account.reject(tick.timestamp, tick.price);
+            return None;
+        }
         
         // Create fill
         let fill = OrderFill {
             order_id: order.id.clone(),
             timestamp: tick.timestamp,
             price: fill_price,
-            quantity: order.quantity,
+            quantity,
             side: order.side,
             commission,
             slippage,
+            tag: order.tag.clone(),
+            position_effect: order.position_effect,
         };
         
-        // Update capital
-        let trade_value = fill_price * Decimal::from(order.quantity);
-        match order.side {
-            OrderSide::Buy => self.current_capital -= trade_value + commission,
-            OrderSide::Sell => self.current_capital += trade_value - commission,
-        }
+        // Update cash, position and margin
+        self.account.apply_fill(order.side, quantity, fill_price, commission, tick.timestamp);
         
         self.filled_orders.push(fill.clone());
         
         debug!("Order executed: {:?} {} @ {} (slippage: {}, commission: {})",
-            order.side, order.quantity, fill_price, slippage, commission);
+            order.side, quantity, fill_price, slippage, commission);
         
         Some(fill)
     }
     
-    /// Calculate fill price with slippage
-    fn calculate_fill_price(
-        &self,
-        market_price: Decimal,
-        side: OrderSide,
-        quantity: i32,
-        config: &SlippageConfig,
-    ) -> Decimal {
-        // Fixed slippage component
-        let mut slippage = config.fixed_slippage;
-        
-        // Volume-based slippage
-        let volume_slippage = Decimal::from_f64_retain(
-            quantity as f64 * config.volume_slippage
-        ).unwrap_or(Decimal::ZERO);
-        slippage += volume_slippage;
-        
-        // Market impact
-        let impact = Decimal::from_f64_retain(
-            quantity as f64 * config.market_impact * market_price.to_string().parse::<f64>().unwrap_or(0.0)
-        ).unwrap_or(Decimal::ZERO);
-        slippage += impact;
-        
-        // Apply slippage based on side
-        match side {
-            OrderSide::Buy => market_price + slippage,
-            OrderSide::Sell => market_price - slippage,
-        }
-    }
-    
-    /// Check if limit order would fill
-    fn would_limit_fill(&self, limit_price: Decimal, market_price: Decimal, side: OrderSide) -> bool {
-        match side {
-            OrderSide::Buy => market_price <= limit_price,
-            OrderSide::Sell => market_price >= limit_price,
-        }
+    /// Orders rejected or cancelled in whole or part, oldest first
+    pub fn execution_reports(&self) -> &[ExecutionReport] {
+        self.matching.reports()
     }
     
-    /// Check if stop order would trigger
-    fn would_stop_trigger(&self, stop_price: Decimal, market_price: Decimal, side: OrderSide) -> bool {
-        match side {
-            OrderSide::Buy => market_price >= stop_price,
-            OrderSide::Sell => market_price <= stop_price,
-        }
+    /// Matching engine holding the resting orders
+    pub fn matching(&self) -> &MatchingEngine {
+        &self.matching
     }
     
     /// Get current capital
     pub fn get_current_capital(&self) -> Decimal {
-        self.current_capital
+        self.account.cash()
+    }
+    
+    /// Account holding cash and margin state
+    pub fn account(&self) -> &Account {
+        &self.account
+    }
+    
+    /// Revalue the account at the latest price
+    pub fn mark_to_market(&mut self, price: Decimal) {
+        self.account.mark_to_market(price);
+    }
+    
+    /// Margin usage over the run
+    pub fn margin_report(&self) -> MarginReport {
+        self.account.report()
     }
     
     /// Get equity including open position
     pub fn get_equity(&self, position: &Position, current_price: Decimal) -> Decimal {
-        let mut equity = self.current_capital;
+        let mut equity = self.account.cash();
         
         if !position.is_flat() {
             // Add unrealized P&L
@@ -170,21 +171,11 @@ impl StrategyExecutor {
         equity
     }
     
-    /// Process pending orders
-    pub fn process_pending_orders(&mut self, tick: &TickData, slippage_config: &SlippageConfig) -> Vec<OrderFill> {
-        let mut fills = Vec::new();
-        let mut remaining_orders = Vec::new();
-        
-        for order in self.pending_orders.drain(..) {
-            if let Some(fill) = self.execute_order(order.clone(), tick, slippage_config) {
-                fills.push(fill);
-            } else {
-                remaining_orders.push(order);
-            }
-        }
-        
-        self.pending_orders = remaining_orders;
-        fills
+    /// Advance the market to `tick`, booking the resting orders it fills
+    pub fn on_market_tick(&mut self, tick: &TickData) -> Vec<OrderFill> {
+        self.matching.advance_to(tick).into_iter()
+            .filter_map(|matched| self.fill(matched, tick))
+            .collect()
     }
 }
 
@@ -196,4 +187,4 @@ pub struct ExecutionContext {
     pub session_high: Option<Decimal>,
     pub session_low: Option<Decimal>,
     pub session_volume: i64,
-}
\ No newline at end of file
+}
@@ -0,0 +1,488 @@
+//! Exchange trading halts and price limits
+//!
+//! CME equity index futures stop trading in three ways a strategy has to
+//! survive. Daily price limits sit 7%, 13% and 20% below the previous
+//! session's settlement during regular hours; reaching the first two halts
+//! trading for fifteen minutes (unless it happens late in the day) before
+//! the limit widens, and reaching the last halts it for the rest of the day.
+//! Outside regular hours the market can trade up or down 7% but not through
+//! it. Velocity logic pauses matching for a few seconds when the price moves
+//! too far too fast; orders can still be entered, but market orders are
+//! refused and nothing trades until the market reopens.
+//!
+//! While halted every new order is rejected. Resting orders stay on the
+//! book untouched and are matched again on the first tick after the
+//! reopening, so a stop passed by the halt triggers at the reopening price.
+//! Replayed data rarely contains a halt, so halts can also be scheduled at
+//! chosen times to see how a strategy copes with one.
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::data::{MarketDataType, TickData};
+use crate::strategy::{Order, OrderType};
+use crate::timestamp::Timestamp;
+
+/// Halt and price limit rules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HaltConfig {
+    /// Downside limits during regular hours, as fractions below the
+    /// reference price; reaching one halts trading
+    #[serde(default = "default_rth_limits")]
+    pub rth_limits: Vec<f64>,
+    /// Length of the halt at every regular-hours limit but the last
+    #[serde(default = "default_halt_minutes")]
+    pub halt_minutes: i64,
+    /// Regular trading hours on the exchange clock
+    #[serde(default = "default_rth_open")]
+    pub rth_open: NaiveTime,
+    #[serde(default = "default_rth_close")]
+    pub rth_close: NaiveTime,
+    /// From this time only the last limit halts; earlier ones just widen
+    #[serde(default = "default_late_cutoff")]
+    pub late_cutoff: NaiveTime,
+    /// Up and down limit outside regular hours, as a fraction of the
+    /// reference price
+    #[serde(default = "default_overnight_limit")]
+    pub overnight_limit: f64,
+    /// Velocity logic pauses; off when unset
+    #[serde(default)]
+    pub velocity: Option<VelocityLogicConfig>,
+    /// Halts imposed at fixed times
+    #[serde(default)]
+    pub scheduled: Vec<ScheduledHalt>,
+    /// Reference price of the first session; later sessions use the last
+    /// trade of the one before. Without it the first session has no limits
+    #[serde(default)]
+    pub reference_price: Option<Decimal>,
+}
+
+fn default_rth_limits() -> Vec<f64> {
+    vec![0.07, 0.13, 0.20]
+}
+
+fn default_halt_minutes() -> i64 {
+    15
+}
+
+fn default_rth_open() -> NaiveTime {
+    NaiveTime::from_hms_opt(8, 30, 0).unwrap()
+}
+
+fn default_rth_close() -> NaiveTime {
+    NaiveTime::from_hms_opt(15, 0, 0).unwrap()
+}
+
+fn default_late_cutoff() -> NaiveTime {
+    NaiveTime::from_hms_opt(14, 25, 0).unwrap()
+}
+
+fn default_overnight_limit() -> f64 {
+    0.07
+}
+
+impl Default for HaltConfig {
+    fn default() -> Self {
+        Self {
+            rth_limits: default_rth_limits(),
+            halt_minutes: default_halt_minutes(),
+            rth_open: default_rth_open(),
+            rth_close: default_rth_close(),
+            late_cutoff: default_late_cutoff(),
+            overnight_limit: default_overnight_limit(),
+            velocity: None,
+            scheduled: Vec::new(),
+            reference_price: None,
+        }
+    }
+}
+
+/// Pause in matching after a fast move
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VelocityLogicConfig {
+    /// Largest move, in points, allowed within the window
+    pub max_move: Decimal,
+    #[serde(default = "default_velocity_window_ms")]
+    pub window_ms: i64,
+    #[serde(default = "default_velocity_pause_ms")]
+    pub pause_ms: i64,
+}
+
+fn default_velocity_window_ms() -> i64 {
+    1_000
+}
+
+fn default_velocity_pause_ms() -> i64 {
+    2_000
+}
+
+/// A halt imposed regardless of price
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledHalt {
+    pub start: DateTime<Utc>,
+    pub minutes: i64,
+}
+
+/// Whether and how the market is trading
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum MarketState {
+    Open,
+    /// Orders are accepted but nothing trades until `until`, in nanoseconds
+    Paused { until: i64 },
+    /// No orders are accepted; `None` halts until the end of the session
+    Halted { until: Option<i64> },
+}
+
+impl MarketState {
+    pub fn is_open(&self) -> bool {
+        matches!(self, MarketState::Open)
+    }
+}
+
+/// Why trading stopped or was limited
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HaltKind {
+    /// A regular-hours limit was reached; `level` counts from 1
+    PriceLimit { level: usize, limit_price: Decimal },
+    /// The overnight limit was reached; trading continues at the limit
+    LimitReached { limit_price: Decimal },
+    VelocityLogic,
+    Scheduled,
+}
+
+/// One halt or limit event of a run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HaltEvent {
+    pub kind: HaltKind,
+    pub start: DateTime<Utc>,
+    /// When trading resumed; `None` for events that did not stop trading or
+    /// lasted to the end of the run
+    pub end: Option<DateTime<Utc>>,
+    /// Trade price that set the event off
+    pub trigger_price: Option<Decimal>,
+    pub reference_price: Option<Decimal>,
+}
+
+/// Halts of a run and the orders they affected
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HaltReport {
+    pub events: Vec<HaltEvent>,
+    /// Orders refused while halted or paused, or priced through a limit
+    pub orders_rejected: usize,
+    /// Orders accepted during a pause and left resting until the reopening
+    pub orders_deferred: usize,
+}
+
+/// What happens to an order submitted now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    Accept,
+    /// Rest the order without matching it
+    Defer,
+    Reject(&'static str),
+}
+
+/// Tracks the market state through a run
+#[derive(Debug, Clone)]
+pub struct HaltSimulator {
+    config: HaltConfig,
+    state: MarketState,
+    trade_date: Option<NaiveDate>,
+    reference: Option<Decimal>,
+    last_trade: Option<Decimal>,
+    /// Regular-hours limits reached this session
+    levels_hit: usize,
+    /// Overnight limits recorded this session, down and up
+    overnight_hit: (bool, bool),
+    recent: VecDeque<(i64, Decimal)>,
+    next_scheduled: usize,
+    report: HaltReport,
+}
+
+impl HaltSimulator {
+    pub fn new(mut config: HaltConfig) -> Self {
+        config.scheduled.sort_by_key(|halt| halt.start);
+        let reference = config.reference_price;
+        Self {
+            config,
+            state: MarketState::Open,
+            trade_date: None,
+            reference,
+            last_trade: None,
+            levels_hit: 0,
+            overnight_hit: (false, false),
+            recent: VecDeque::new(),
+            next_scheduled: 0,
+            report: HaltReport::default(),
+        }
+    }
+
+    /// Start a new run
+    pub fn reset(&mut self) {
+        *self = Self::new(self.config.clone());
+    }
+
+    pub fn state(&self) -> MarketState {
+        self.state
+    }
+
+    pub fn report(&self) -> &HaltReport {
+        &self.report
+    }
+
+    /// Advance to `tick`, returning the market state it trades in
+    ///
+    /// `trade_date` is the session the tick belongs to, `None` outside
+    /// trading hours.
+    pub fn on_tick(&mut self, tick: &TickData, trade_date: Option<NaiveDate>) -> MarketState {
+        let now = tick.timestamp;
+
+        if trade_date.is_some() && trade_date != self.trade_date {
+            if self.trade_date.is_some() {
+                self.reference = self.last_trade.or(self.reference);
+            }
+            self.trade_date = trade_date;
+            self.levels_hit = 0;
+            self.overnight_hit = (false, false);
+            self.recent.clear();
+            if self.state == (MarketState::Halted { until: None }) {
+                self.reopen(now);
+            }
+        }
+        match self.state {
+            MarketState::Paused { until } | MarketState::Halted { until: Some(until) } if now >= until => self.reopen(now),
+            _ => {}
+        }
+
+        while let Some(halt) = self.config.scheduled.get(self.next_scheduled) {
+            let start = Timestamp::from(halt.start).nanos();
+            if start > now {
+                break;
+            }
+            let until = start + halt.minutes * 60_000_000_000;
+            self.next_scheduled += 1;
+            if until > now {
+                self.halt(MarketState::Halted { until: Some(until) }, HaltKind::Scheduled, now, None);
+            }
+        }
+
+        if matches!(tick.mdt, MarketDataType::Trade) {
+            if self.state.is_open() {
+                self.check_velocity(now, tick.price);
+            }
+            if self.state.is_open() {
+                self.check_limits(now, tick.price);
+            }
+            self.last_trade = Some(tick.price);
+        }
+        self.state
+    }
+
+    /// Whether an order submitted now is accepted, held or refused
+    pub fn admit(&mut self, order: &Order, timestamp: i64) -> Admission {
+        let admission = match self.state {
+            MarketState::Halted { .. } => Admission::Reject("market is halted"),
+            MarketState::Paused { .. } if order.order_type == OrderType::Market || order.time_in_force.is_immediate() => {
+                Admission::Reject("market and immediate orders are not accepted while the market is paused")
+            }
+            MarketState::Paused { .. } => Admission::Defer,
+            MarketState::Open => {
+                let (lower, upper) = self.band(timestamp);
+                let outside = [order.limit_price, order.stop_price].into_iter().flatten()
+                    .any(|price| lower.is_some_and(|lower| price < lower) || upper.is_some_and(|upper| price > upper));
+                if outside {
+                    Admission::Reject("order priced through the daily price limit")
+                } else {
+                    Admission::Accept
+                }
+            }
+        };
+        match admission {
+            Admission::Reject(_) => self.report.orders_rejected += 1,
+            Admission::Defer => self.report.orders_deferred += 1,
+            Admission::Accept => {}
+        }
+        admission
+    }
+
+    /// Lowest and highest prices orders may be placed at now
+    pub fn band(&self, timestamp: i64) -> (Option<Decimal>, Option<Decimal>) {
+        let Some(reference) = self.reference else {
+            return (None, None);
+        };
+        if self.in_rth(timestamp) {
+            let lower = self.config.rth_limits.get(self.levels_hit)
+                .map(|&limit| limit_price(reference, -limit));
+            (lower, None)
+        } else {
+            let limit = self.config.overnight_limit;
+            (Some(limit_price(reference, -limit)), Some(limit_price(reference, limit)))
+        }
+    }
+
+    fn in_rth(&self, timestamp: i64) -> bool {
+        let time = Timestamp::from_nanos(timestamp).to_exchange().time();
+        time >= self.config.rth_open && time < self.config.rth_close
+    }
+
+    fn check_velocity(&mut self, now: i64, price: Decimal) {
+        let Some(velocity) = &self.config.velocity else {
+            return;
+        };
+        let window = velocity.window_ms * 1_000_000;
+        while self.recent.front().is_some_and(|&(time, _)| time < now - window) {
+            self.recent.pop_front();
+        }
+        self.recent.push_back((now, price));
+
+        let moved = self.recent.iter().any(|&(_, earlier)| (price - earlier).abs() > velocity.max_move);
+        if moved {
+            let until = now + velocity.pause_ms * 1_000_000;
+            self.recent.clear();
+            self.halt(MarketState::Paused { until }, HaltKind::VelocityLogic, now, Some(price));
+        }
+    }
+
+    fn check_limits(&mut self, now: i64, price: Decimal) {
+        let Some(reference) = self.reference else {
+            return;
+        };
+        if !self.in_rth(now) {
+            let limit = self.config.overnight_limit;
+            let (down, up) = (limit_price(reference, -limit), limit_price(reference, limit));
+            if price <= down && !self.overnight_hit.0 {
+                self.overnight_hit.0 = true;
+                self.record(HaltKind::LimitReached { limit_price: down }, now, Some(now), Some(price));
+            } else if price >= up && !self.overnight_hit.1 {
+                self.overnight_hit.1 = true;
+                self.record(HaltKind::LimitReached { limit_price: up }, now, Some(now), Some(price));
+            }
+            return;
+        }
+
+        // A gap can pass several limits at once; the deepest one decides
+        let reached = self.config.rth_limits.iter()
+            .skip(self.levels_hit)
+            .take_while(|&&limit| price <= limit_price(reference, -limit))
+            .count();
+        if reached == 0 {
+            return;
+        }
+        self.levels_hit += reached;
+        let level = self.levels_hit;
+        let kind = HaltKind::PriceLimit {
+            level,
+            limit_price: limit_price(reference, -self.config.rth_limits[level - 1]),
+        };
+        let late = Timestamp::from_nanos(now).to_exchange().time() >= self.config.late_cutoff;
+        if level == self.config.rth_limits.len() {
+            self.halt(MarketState::Halted { until: None }, kind, now, Some(price));
+        } else if !late {
+            let until = now + Duration::minutes(self.config.halt_minutes).num_nanoseconds().unwrap_or(i64::MAX);
+            self.halt(MarketState::Halted { until: Some(until) }, kind, now, Some(price));
+        } else {
+            self.record(kind, now, Some(now), Some(price));
+        }
+    }
+
+    fn halt(&mut self, state: MarketState, kind: HaltKind, now: i64, trigger_price: Option<Decimal>) {
+        if !self.state.is_open() {
+            self.close_event(now);
+        }
+        self.state = state;
+        self.record(kind, now, None, trigger_price);
+    }
+
+    fn reopen(&mut self, now: i64) {
+        self.state = MarketState::Open;
+        self.close_event(now);
+    }
+
+    fn close_event(&mut self, now: i64) {
+        if let Some(event) = self.report.events.last_mut().filter(|event| event.end.is_none()) {
+            event.end = Some(Timestamp::from_nanos(now).to_utc());
+        }
+    }
+
+    fn record(&mut self, kind: HaltKind, start: i64, end: Option<i64>, trigger_price: Option<Decimal>) {
+        self.report.events.push(HaltEvent {
+            kind,
+            start: Timestamp::from_nanos(start).to_utc(),
+            end: end.map(|end| Timestamp::from_nanos(end).to_utc()),
+            trigger_price,
+            reference_price: self.reference,
+        });
+    }
+}
+
+/// `reference` moved by `fraction`, e.g. -0.07 for the 7% down limit,
+/// to the cent
+fn limit_price(reference: Decimal, fraction: f64) -> Decimal {
+    (reference * Decimal::from_f64_retain(1.0 + fraction).unwrap_or(Decimal::ONE)).round_dp(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::DataLevel;
+    use crate::strategy::OrderSide;
+    use chrono::TimeZone;
+
+    /// Nanoseconds at a time of day on 5 March 2024, CST being UTC-6
+    fn at(hour: u32, minute: u32, second: u32) -> i64 {
+        Timestamp::from(Utc.with_ymd_and_hms(2024, 3, 5, hour + 6, minute, second).unwrap()).nanos()
+    }
+
+    fn trade(timestamp: i64, price: i64) -> TickData {
+        TickData::new(DataLevel::L1, MarketDataType::Trade, timestamp, Decimal::from(price), 1, "0624".to_string())
+    }
+
+    #[test]
+    fn test_limits_and_velocity_logic_stop_trading() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 5);
+        let config = HaltConfig {
+            reference_price: Some(Decimal::from(18_000)),
+            velocity: Some(VelocityLogicConfig { max_move: Decimal::from(50), window_ms: 1_000, pause_ms: 2_000 }),
+            ..Default::default()
+        };
+        let mut halts = HaltSimulator::new(config);
+
+        // A 60 point drop inside a second pauses matching for two seconds
+        assert!(halts.on_tick(&trade(at(9, 0, 0), 18_000), date).is_open());
+        assert_eq!(halts.on_tick(&trade(at(9, 0, 0) + 500_000_000, 17_940), date), MarketState::Paused { until: at(9, 0, 2) + 500_000_000 });
+        assert_eq!(halts.admit(&Order::market(OrderSide::Buy, 1), at(9, 0, 1)), Admission::Reject(
+            "market and immediate orders are not accepted while the market is paused",
+        ));
+        assert_eq!(halts.admit(&Order::limit(OrderSide::Buy, 1, Decimal::from(17_900)), at(9, 0, 1)), Admission::Defer);
+        assert!(halts.on_tick(&trade(at(9, 0, 3), 17_945), date).is_open());
+
+        // Orders cannot be placed below the 7% limit of 16,740
+        assert!(matches!(halts.admit(&Order::limit(OrderSide::Buy, 1, Decimal::from(16_700)), at(9, 1, 0)), Admission::Reject(_)));
+
+        // Reaching it halts trading for fifteen minutes, then the 13% limit applies
+        assert_eq!(halts.on_tick(&trade(at(10, 0, 0), 16_740), date), MarketState::Halted { until: Some(at(10, 15, 0)) });
+        assert!(matches!(halts.admit(&Order::limit(OrderSide::Sell, 1, Decimal::from(16_800)), at(10, 5, 0)), Admission::Reject(_)));
+        assert!(halts.on_tick(&trade(at(10, 15, 0), 16_760), date).is_open());
+        assert_eq!(halts.band(at(10, 15, 0)).0, Some(Decimal::from(15_660)));
+
+        // Past the late cutoff the 13% limit no longer halts, but the 20% one
+        // halts for the rest of the session
+        assert!(halts.on_tick(&trade(at(14, 30, 0), 15_600), date).is_open());
+        assert_eq!(halts.on_tick(&trade(at(14, 40, 0), 14_400), date), MarketState::Halted { until: None });
+
+        let report = halts.report();
+        assert_eq!((report.orders_rejected, report.orders_deferred), (3, 1));
+        let kinds: Vec<&HaltKind> = report.events.iter().map(|event| &event.kind).collect();
+        assert!(matches!(kinds[..], [
+            HaltKind::VelocityLogic,
+            HaltKind::PriceLimit { level: 1, .. },
+            HaltKind::PriceLimit { level: 2, .. },
+            HaltKind::PriceLimit { level: 3, .. },
+        ]));
+        assert_eq!(report.events[1].end, Some(Timestamp::from_nanos(at(10, 15, 0)).to_utc()));
+    }
+}
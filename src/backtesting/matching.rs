@@ -0,0 +1,349 @@
+//! Order matching against replayed market data
+//!
+//! The `MatchingEngine` decides when and at what price a strategy's orders
+//! trade, and nothing else: it knows no account, margin or commissions, so
+//! it can be driven directly by tests or by anything else replaying ticks.
+//! Orders are submitted against the current tick and book, resting orders
+//! are re-evaluated each time the engine is advanced to a new tick, and a
+//! resting order can be cancelled at any point.
+//!
+//! Market orders and triggered stops trade at the tick price plus slippage.
+//! Limit orders trade at their limit once marketable; with a queue model
+//! they only fill passively once the queue ahead of them has traded. IOC,
+//! FOK and post-only orders are matched against the book when it shows the
+//! side they would trade with: IOC fills what is displayed up to its limit
+//! and cancels the rest, FOK fills in full or not at all, and a post-only
+//! order that would cross is rejected.
+
+use crate::backtesting::engine::SlippageConfig;
+use crate::backtesting::queue_fill::{QueueFillConfig, QueuePositionModel};
+use crate::data::TickData;
+use crate::market::OrderBookState;
+use crate::strategy::orders::{ExecutionReport, OrderStatus};
+use crate::strategy::{Order, OrderSide, OrderType, TimeInForce};
+use chrono::DateTime;
+use rust_decimal::Decimal;
+use tracing::debug;
+
+/// An order, or part of one, the engine matched on a tick
+#[derive(Debug, Clone)]
+pub struct Match {
+    pub order: Order,
+    pub quantity: i32,
+    pub price: Decimal,
+    /// Distance from the tick price the order matched on
+    pub slippage: Decimal,
+}
+
+/// Matches orders against ticks and book snapshots
+pub struct MatchingEngine {
+    slippage: SlippageConfig,
+    resting: Vec<Order>,
+    /// Fills resting limit orders by estimated queue position when set
+    queue_model: Option<QueuePositionModel>,
+    /// Orders rejected or cancelled in whole or part, oldest first
+    reports: Vec<ExecutionReport>,
+}
+
+impl MatchingEngine {
+    pub fn new(slippage: SlippageConfig) -> Self {
+        Self {
+            slippage,
+            resting: Vec::new(),
+            queue_model: None,
+            reports: Vec::new(),
+        }
+    }
+
+    /// Fill resting limit orders only once the queue ahead of them trades
+    pub fn with_queue_model(mut self, config: &QueueFillConfig) -> Self {
+        self.queue_model = Some(QueuePositionModel::new(config));
+        self
+    }
+
+    pub fn set_slippage(&mut self, slippage: SlippageConfig) {
+        self.slippage = slippage;
+    }
+
+    /// Match a new order at `tick`, resting it if it does not trade
+    ///
+    /// `book` is only consulted for IOC, FOK and post-only orders; without
+    /// it they are matched on the tick price like any other order.
+    pub fn submit(&mut self, order: Order, tick: &TickData, book: Option<&OrderBookState>) -> Option<Match> {
+        self.match_order(order, tick, book, false)
+    }
+
+    /// Rest an order without matching it, as while the market is paused
+    pub fn hold(&mut self, order: Order) {
+        self.rest(order, false);
+    }
+
+    /// Withdraw a resting order, returning it if it was still resting
+    pub fn cancel(&mut self, order_id: &str, timestamp: i64) -> Option<Order> {
+        let index = self.resting.iter().position(|order| order.id == order_id)?;
+        let order = self.resting.remove(index);
+        if let Some(model) = &mut self.queue_model {
+            model.cancel(order_id);
+        }
+        self.record(&order, OrderStatus::Cancelled, 0, timestamp, None);
+        Some(order)
+    }
+
+    /// Move the market to `tick`, returning the resting orders it filled
+    ///
+    /// With a queue model, resting limits fill at their limit with no
+    /// slippage once the queue ahead of them trades; stops, and limits
+    /// without a queue model, are matched again on the tick price.
+    pub fn advance_to(&mut self, tick: &TickData) -> Vec<Match> {
+        let mut matches = Vec::new();
+        let queued = self.queue_model.as_mut().map(|model| model.on_tick(tick)).unwrap_or_default();
+
+        for order in std::mem::take(&mut self.resting) {
+            if self.queue_model.is_some() && order.order_type == OrderType::Limit {
+                match order.limit_price.filter(|_| queued.contains(&order.id)) {
+                    Some(limit) => matches.push(Match {
+                        quantity: order.quantity,
+                        price: limit,
+                        slippage: Decimal::ZERO,
+                        order,
+                    }),
+                    None => self.resting.push(order),
+                }
+                continue;
+            }
+            matches.extend(self.match_order(order, tick, None, true));
+        }
+        matches
+    }
+
+    /// Orders waiting to trade, in the order they were accepted
+    pub fn resting_orders(&self) -> &[Order] {
+        &self.resting
+    }
+
+    /// Orders rejected or cancelled in whole or part, oldest first
+    pub fn reports(&self) -> &[ExecutionReport] {
+        &self.reports
+    }
+
+    /// `resting` orders were accepted on an earlier tick, so post-only no
+    /// longer applies to them
+    fn match_order(
+        &mut self,
+        order: Order,
+        tick: &TickData,
+        book: Option<&OrderBookState>,
+        resting: bool,
+    ) -> Option<Match> {
+        let tif = order.time_in_force;
+        let book = book.filter(|_| tif.is_immediate() || tif == TimeInForce::PostOnly);
+
+        let price = match order.order_type {
+            OrderType::Market if tif == TimeInForce::PostOnly => {
+                self.reject(&order, tick, "post-only market order would take liquidity");
+                return None;
+            }
+            OrderType::Market => self.slipped_price(tick.price, order.side, order.quantity),
+            OrderType::Limit => {
+                let limit = order.limit_price?;
+                let marketable = match book.and_then(|book| touch(book, order.side)) {
+                    Some(touch) => limit_crossed(limit, touch, order.side),
+                    None => limit_crossed(limit, tick.price, order.side),
+                };
+                if marketable && tif == TimeInForce::PostOnly && !resting {
+                    self.reject(&order, tick, "post-only order would cross the book");
+                    return None;
+                } else if marketable {
+                    limit
+                } else if tif.is_immediate() {
+                    self.record(&order, OrderStatus::Cancelled, 0, tick.timestamp, None);
+                    return None;
+                } else {
+                    self.rest(order, resting);
+                    return None;
+                }
+            }
+            OrderType::Stop | OrderType::StopLimit => {
+                let stop = order.stop_price?;
+                if stop_triggered(stop, tick.price, order.side) {
+                    self.slipped_price(tick.price, order.side, order.quantity)
+                } else {
+                    self.rest(order, resting);
+                    return None;
+                }
+            }
+        };
+
+        // IOC and FOK only take the liquidity the book displays
+        let quantity = match book.and_then(|book| displayed_liquidity(book, order.side, order.limit_price)) {
+            Some(available) if tif.is_immediate() && available < order.quantity => {
+                if tif == TimeInForce::FOK || available == 0 {
+                    self.record(&order, OrderStatus::Cancelled, 0, tick.timestamp, None);
+                    return None;
+                }
+                available
+            }
+            _ => order.quantity,
+        };
+        if quantity < order.quantity {
+            self.record(&order, OrderStatus::Cancelled, quantity, tick.timestamp, None);
+        }
+
+        Some(Match {
+            slippage: (price - tick.price).abs(),
+            quantity,
+            price,
+            order,
+        })
+    }
+
+    /// Put an order on the resting list; orders already resting keep their
+    /// place in the queue
+    fn rest(&mut self, order: Order, resting: bool) {
+        if let (Some(model), Some(limit), false) = (&mut self.queue_model, order.limit_price, resting) {
+            if order.order_type == OrderType::Limit {
+                model.join(order.id.clone(), order.side, limit, order.quantity);
+            }
+        }
+        self.resting.push(order);
+    }
+
+    /// Record a post-only or other order refused on arrival
+    pub fn reject(&mut self, order: &Order, tick: &TickData, reason: &str) {
+        debug!("Order {} rejected: {}", order.id, reason);
+        self.record(order, OrderStatus::Rejected, 0, tick.timestamp, Some(reason));
+    }
+
+    /// Record an order ending with `filled` of its lots traded
+    fn record(&mut self, order: &Order, status: OrderStatus, filled: i32, timestamp: i64, reason: Option<&str>) {
+        if status == OrderStatus::Cancelled {
+            debug!("Order {} cancelled ({:?}) with {} of {} filled",
+                order.id, order.time_in_force, filled, order.quantity);
+        }
+        self.reports.push(ExecutionReport {
+            order_id: order.id.clone(),
+            status,
+            filled_quantity: filled,
+            remaining_quantity: order.quantity - filled,
+            average_price: None,
+            last_fill_price: None,
+            last_fill_quantity: None,
+            commission: Decimal::ZERO,
+            timestamp: DateTime::from_timestamp_nanos(timestamp),
+            reject_reason: reason.map(str::to_string),
+        });
+    }
+
+    /// Price an aggressive order trades at after slippage
+    fn slipped_price(&self, market_price: Decimal, side: OrderSide, quantity: i32) -> Decimal {
+        let config = &self.slippage;
+        // Fixed slippage component
+        let mut slippage = config.fixed_slippage;
+
+        // Volume-based slippage
+        slippage += Decimal::from_f64_retain(quantity as f64 * config.volume_slippage)
+            .unwrap_or(Decimal::ZERO);
+
+        // Market impact
+        slippage += Decimal::from_f64_retain(
+            quantity as f64 * config.market_impact * market_price.to_string().parse::<f64>().unwrap_or(0.0)
+        ).unwrap_or(Decimal::ZERO);
+
+        match side {
+            OrderSide::Buy => market_price + slippage,
+            OrderSide::Sell => market_price - slippage,
+        }
+    }
+}
+
+/// Whether a limit order on `side` trades against `market_price`
+fn limit_crossed(limit_price: Decimal, market_price: Decimal, side: OrderSide) -> bool {
+    match side {
+        OrderSide::Buy => market_price <= limit_price,
+        OrderSide::Sell => market_price >= limit_price,
+    }
+}
+
+/// Whether a stop order on `side` is triggered at `market_price`
+fn stop_triggered(stop_price: Decimal, market_price: Decimal, side: OrderSide) -> bool {
+    match side {
+        OrderSide::Buy => market_price >= stop_price,
+        OrderSide::Sell => market_price <= stop_price,
+    }
+}
+
+/// Best opposite price an order on `side` would trade with
+fn touch(book: &OrderBookState, side: OrderSide) -> Option<Decimal> {
+    match side {
+        OrderSide::Buy => book.best_ask,
+        OrderSide::Sell => book.best_bid,
+    }
+}
+
+/// Opposite-side volume displayed at `limit` or better, `None` when that
+/// side of the book is empty
+fn displayed_liquidity(book: &OrderBookState, side: OrderSide, limit: Option<Decimal>) -> Option<i32> {
+    let levels = match side {
+        OrderSide::Buy => &book.asks,
+        OrderSide::Sell => &book.bids,
+    };
+    if levels.is_empty() {
+        return None;
+    }
+    let within = |price: Decimal| limit.is_none_or(|limit| match side {
+        OrderSide::Buy => price <= limit,
+        OrderSide::Sell => price >= limit,
+    });
+    Some(levels.values().filter(|level| within(level.price)).map(|level| level.volume).sum())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{DataLevel, MarketDataType};
+    use crate::market::PriceLevel;
+    use chrono::Utc;
+
+    fn slippage() -> SlippageConfig {
+        SlippageConfig { fixed_slippage: Decimal::ZERO, volume_slippage: 0.0, market_impact: 0.0 }
+    }
+
+    fn trade(timestamp: i64, price: i64) -> TickData {
+        TickData::new(DataLevel::L1, MarketDataType::Trade, timestamp, Decimal::new(price, 2), 1, "0624".to_string())
+    }
+
+    #[test]
+    fn test_liquidity_within_the_limit() {
+        let mut book = OrderBookState::new("0624".to_string());
+        for (price, volume) in [(1_850_025, 3), (1_850_050, 5), (1_850_075, 8)] {
+            let price = Decimal::new(price, 2);
+            book.asks.insert(price, PriceLevel::new(price, volume, Utc::now()));
+        }
+
+        assert_eq!(displayed_liquidity(&book, OrderSide::Buy, Some(Decimal::new(1_850_050, 2))), Some(8));
+        assert_eq!(displayed_liquidity(&book, OrderSide::Buy, Some(Decimal::new(1_850_000, 2))), Some(0));
+        assert_eq!(displayed_liquidity(&book, OrderSide::Buy, None), Some(16));
+        // Nothing displayed on the bid says nothing about liquidity there
+        assert_eq!(displayed_liquidity(&book, OrderSide::Sell, None), None);
+    }
+
+    #[test]
+    fn test_resting_orders_trade_when_advanced_or_cancelled() {
+        let mut engine = MatchingEngine::new(slippage());
+        let stop = Order::stop(OrderSide::Sell, 1, Decimal::new(1_849_000, 2));
+        let limit = Order::limit(OrderSide::Buy, 2, Decimal::new(1_848_000, 2));
+
+        assert!(engine.submit(stop.clone(), &trade(0, 1_850_000), None).is_none());
+        assert!(engine.submit(limit.clone(), &trade(0, 1_850_000), None).is_none());
+        assert_eq!(engine.resting_orders().len(), 2);
+
+        let matches = engine.advance_to(&trade(1, 1_849_000));
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].order.id, stop.id);
+        assert_eq!(matches[0].price, Decimal::new(1_849_000, 2));
+
+        assert_eq!(engine.cancel(&limit.id, 2).map(|order| order.id), Some(limit.id));
+        assert!(engine.advance_to(&trade(3, 1_847_000)).is_empty());
+        assert_eq!(engine.reports().last().map(|report| report.status), Some(OrderStatus::Cancelled));
+    }
+}
@@ -0,0 +1,420 @@
+//! Registry of named performance metrics
+//!
+//! Rankings, optimization objectives and reports refer to metrics by name and
+//! resolve them here, so a metric registered once can be weighted in
+//! `RankingCriteria`, optimized through `ObjectiveFunction::Metric` and listed
+//! in a `BacktestReport` alike. Each metric pairs a value function with a
+//! scoring function that maps values onto 0-100 for composite rankings, and
+//! says which direction is better so optimizers can maximize it unscaled.
+
+use crate::backtesting::BacktestResult;
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+/// Errors raised resolving metrics
+#[derive(Debug, thiserror::Error)]
+pub enum MetricError {
+    #[error("Unknown metric: {0}")]
+    UnknownMetric(String),
+    #[error("Metric {0} is already registered")]
+    AlreadyRegistered(String),
+    #[error("Metric {0} cannot be computed from the available data")]
+    Unavailable(String),
+}
+
+/// Data a metric is computed from
+#[derive(Debug, Clone, Copy)]
+pub struct MetricInput<'a> {
+    pub result: &'a BacktestResult,
+    /// Account equity over time, oldest first
+    pub equity_curve: &'a [f64],
+    /// Net P&L of each closed trade
+    pub trade_pnls: &'a [f64],
+}
+
+impl<'a> MetricInput<'a> {
+    pub fn new(result: &'a BacktestResult) -> Self {
+        Self { result, equity_curve: &[], trade_pnls: &[] }
+    }
+
+    pub fn with_equity_curve(mut self, equity_curve: &'a [f64]) -> Self {
+        self.equity_curve = equity_curve;
+        self
+    }
+
+    pub fn with_trade_pnls(mut self, trade_pnls: &'a [f64]) -> Self {
+        self.trade_pnls = trade_pnls;
+        self
+    }
+}
+
+type ValueFn = Arc<dyn Fn(&MetricInput) -> Option<f64> + Send + Sync>;
+type ScoreFn = Arc<dyn Fn(f64) -> f64 + Send + Sync>;
+
+/// A named metric with its value and scoring functions
+#[derive(Clone)]
+pub struct MetricDefinition {
+    pub name: String,
+    pub description: String,
+    pub higher_is_better: bool,
+    value: ValueFn,
+    score: ScoreFn,
+}
+
+impl MetricDefinition {
+    /// A metric whose values are already on a 0-100 scale unless
+    /// `with_score` says otherwise
+    pub fn new<F>(name: impl Into<String>, description: impl Into<String>, value: F) -> Self
+    where
+        F: Fn(&MetricInput) -> Option<f64> + Send + Sync + 'static,
+    {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            higher_is_better: true,
+            value: Arc::new(value),
+            score: Arc::new(|value| value),
+        }
+    }
+
+    /// Map values onto 0-100, higher being better
+    pub fn with_score<F>(mut self, score: F) -> Self
+    where
+        F: Fn(f64) -> f64 + Send + Sync + 'static,
+    {
+        self.score = Arc::new(score);
+        self
+    }
+
+    /// Mark smaller values as better, as for drawdowns
+    pub fn lower_is_better(mut self) -> Self {
+        self.higher_is_better = false;
+        self
+    }
+
+    /// Value of the metric, or `None` when the input lacks the data it needs
+    pub fn value(&self, input: &MetricInput) -> Option<f64> {
+        (self.value)(input).filter(|value| value.is_finite())
+    }
+
+    pub fn score(&self, value: f64) -> f64 {
+        (self.score)(value).clamp(0.0, 100.0)
+    }
+
+    /// Value oriented so that larger is better
+    pub fn objective(&self, value: f64) -> f64 {
+        if self.higher_is_better { value } else { -value }
+    }
+}
+
+impl fmt::Debug for MetricDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MetricDefinition")
+            .field("name", &self.name)
+            .field("description", &self.description)
+            .field("higher_is_better", &self.higher_is_better)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Shared set of metrics, seeded with the built-in ones
+#[derive(Clone)]
+pub struct MetricRegistry {
+    metrics: Arc<RwLock<HashMap<String, MetricDefinition>>>,
+}
+
+impl MetricRegistry {
+    pub fn new() -> Self {
+        let registry = Self::empty();
+        {
+            let mut metrics = registry.metrics.write().unwrap();
+            for builtin in BUILTINS {
+                metrics.insert(builtin.name.to_string(), builtin.definition());
+            }
+        }
+        registry
+    }
+
+    /// A registry without the built-in metrics
+    pub fn empty() -> Self {
+        Self { metrics: Arc::new(RwLock::new(HashMap::new())) }
+    }
+
+    pub fn register(&self, metric: MetricDefinition) -> Result<(), MetricError> {
+        let mut metrics = self.metrics.write().unwrap();
+        if metrics.contains_key(&metric.name) {
+            return Err(MetricError::AlreadyRegistered(metric.name));
+        }
+        metrics.insert(metric.name.clone(), metric);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<MetricDefinition> {
+        self.metrics.read().unwrap().get(name).cloned()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.metrics.read().unwrap().contains_key(name)
+    }
+
+    /// Registered metric names, sorted
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.metrics.read().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    pub fn value(&self, name: &str, input: &MetricInput) -> Result<f64, MetricError> {
+        let metric = self.get(name).ok_or_else(|| MetricError::UnknownMetric(name.to_string()))?;
+        metric.value(input).ok_or_else(|| MetricError::Unavailable(name.to_string()))
+    }
+
+    /// Value of a metric on its 0-100 scale
+    pub fn score(&self, name: &str, input: &MetricInput) -> Result<f64, MetricError> {
+        let metric = self.get(name).ok_or_else(|| MetricError::UnknownMetric(name.to_string()))?;
+        metric.value(input)
+            .map(|value| metric.score(value))
+            .ok_or_else(|| MetricError::Unavailable(name.to_string()))
+    }
+
+    /// Value of a metric oriented so that larger is better
+    pub fn objective(&self, name: &str, input: &MetricInput) -> Result<f64, MetricError> {
+        let metric = self.get(name).ok_or_else(|| MetricError::UnknownMetric(name.to_string()))?;
+        metric.value(input)
+            .map(|value| metric.objective(value))
+            .ok_or_else(|| MetricError::Unavailable(name.to_string()))
+    }
+
+    /// Every metric that can be computed from `input`
+    pub fn evaluate(&self, input: &MetricInput) -> BTreeMap<String, f64> {
+        self.metrics.read().unwrap()
+            .values()
+            .filter_map(|metric| Some((metric.name.clone(), metric.value(input)?)))
+            .collect()
+    }
+}
+
+impl Default for MetricRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for MetricRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MetricRegistry").field("metrics", &self.names()).finish()
+    }
+}
+
+/// Oriented value of a built-in metric, for callers without a registry
+pub fn builtin_objective(name: &str, input: &MetricInput) -> Option<f64> {
+    let builtin = BUILTINS.iter().find(|builtin| builtin.name == name)?;
+    let value = (builtin.value)(input).filter(|value| value.is_finite())?;
+    Some(if builtin.higher_is_better { value } else { -value })
+}
+
+struct Builtin {
+    name: &'static str,
+    description: &'static str,
+    higher_is_better: bool,
+    value: fn(&MetricInput) -> Option<f64>,
+    score: fn(f64) -> f64,
+}
+
+impl Builtin {
+    fn definition(&self) -> MetricDefinition {
+        let metric = MetricDefinition::new(self.name, self.description, self.value).with_score(self.score);
+        if self.higher_is_better { metric } else { metric.lower_is_better() }
+    }
+}
+
+const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "sharpe_ratio",
+        description: "Annualized Sharpe ratio",
+        higher_is_better: true,
+        value: |input| Some(input.result.sharpe_ratio),
+        score: score_sharpe_ratio,
+    },
+    Builtin {
+        name: "max_drawdown",
+        description: "Largest peak-to-trough decline, percent",
+        higher_is_better: false,
+        value: |input| input.result.max_drawdown.to_f64(),
+        score: score_max_drawdown,
+    },
+    Builtin {
+        name: "win_rate",
+        description: "Share of trades that were profitable",
+        higher_is_better: true,
+        value: |input| Some(input.result.win_rate),
+        score: score_win_rate,
+    },
+    Builtin {
+        name: "profit_factor",
+        description: "Gross profit over gross loss",
+        higher_is_better: true,
+        value: |input| Some(input.result.profit_factor),
+        score: score_profit_factor,
+    },
+    Builtin {
+        name: "trade_frequency",
+        description: "Number of trades",
+        higher_is_better: true,
+        value: |input| Some(input.result.total_trades as f64),
+        score: score_trade_frequency,
+    },
+    Builtin {
+        name: "tail_ratio",
+        description: "95th percentile return over the magnitude of the 5th",
+        higher_is_better: true,
+        value: tail_ratio,
+        score: |ratio| (ratio - 0.5) * 100.0,
+    },
+    Builtin {
+        name: "ulcer_index",
+        description: "Root mean square of percentage drawdowns from the running peak",
+        higher_is_better: false,
+        value: ulcer_index,
+        score: |index| 100.0 - index * 10.0,
+    },
+    Builtin {
+        name: "sqn",
+        description: "System quality number: mean trade P&L over its deviation, times root trade count",
+        higher_is_better: true,
+        value: system_quality_number,
+        score: |sqn| sqn * 20.0,
+    },
+];
+
+fn score_sharpe_ratio(sharpe: f64) -> f64 {
+    match sharpe {
+        s if s >= 2.0 => 100.0,
+        s if s >= 1.5 => 85.0 + (s - 1.5) * 30.0,
+        s if s >= 1.0 => 70.0 + (s - 1.0) * 30.0,
+        s if s >= 0.5 => 50.0 + (s - 0.5) * 40.0,
+        s if s >= 0.0 => s * 100.0,
+        _ => 0.0,
+    }
+}
+
+fn score_max_drawdown(drawdown: f64) -> f64 {
+    match drawdown.abs() {
+        d if d <= 5.0 => 100.0,
+        d if d <= 10.0 => 90.0 - (d - 5.0) * 4.0,
+        d if d <= 15.0 => 70.0 - (d - 10.0) * 6.0,
+        d if d <= 25.0 => 40.0 - (d - 15.0) * 2.0,
+        _ => 0.0,
+    }
+}
+
+fn score_win_rate(win_rate: f64) -> f64 {
+    match win_rate * 100.0 {
+        w if w >= 70.0 => 100.0,
+        w if w >= 60.0 => 85.0 + (w - 60.0) * 1.5,
+        w if w >= 50.0 => 70.0 + (w - 50.0) * 1.5,
+        w if w >= 40.0 => 50.0 + (w - 40.0) * 2.0,
+        w => w,
+    }
+}
+
+fn score_profit_factor(pf: f64) -> f64 {
+    match pf {
+        p if p >= 2.0 => 100.0,
+        p if p >= 1.5 => 80.0 + (p - 1.5) * 40.0,
+        p if p >= 1.2 => 60.0 + (p - 1.2) * 66.7,
+        p if p >= 1.0 => 30.0 + (p - 1.0) * 150.0,
+        _ => 0.0,
+    }
+}
+
+fn score_trade_frequency(trades: f64) -> f64 {
+    match trades {
+        t if t >= 200.0 => 100.0,
+        t if t >= 100.0 => 80.0 + (t - 100.0) * 0.2,
+        t if t >= 50.0 => 60.0 + (t - 50.0) * 0.4,
+        t if t >= 20.0 => 30.0 + (t - 20.0),
+        t if t >= 10.0 => t * 2.0,
+        _ => 0.0,
+    }
+}
+
+/// Period returns of an equity curve
+fn returns(equity_curve: &[f64]) -> Vec<f64> {
+    equity_curve.windows(2)
+        .filter(|pair| pair[0] != 0.0)
+        .map(|pair| pair[1] / pair[0] - 1.0)
+        .collect()
+}
+
+fn tail_ratio(input: &MetricInput) -> Option<f64> {
+    let mut returns = returns(input.equity_curve);
+    if returns.len() < 20 {
+        return None;
+    }
+    returns.sort_by(f64::total_cmp);
+    let at = |q: f64| returns[((returns.len() - 1) as f64 * q).round() as usize];
+    let (right, left) = (at(0.95), at(0.05));
+    (left != 0.0).then(|| right.abs() / left.abs())
+}
+
+fn ulcer_index(input: &MetricInput) -> Option<f64> {
+    if input.equity_curve.len() < 2 {
+        return None;
+    }
+    let mut peak = f64::MIN;
+    let squared: f64 = input.equity_curve.iter()
+        .map(|&equity| {
+            peak = peak.max(equity);
+            let drawdown = if peak > 0.0 { (peak - equity) / peak * 100.0 } else { 0.0 };
+            drawdown * drawdown
+        })
+        .sum();
+    Some((squared / input.equity_curve.len() as f64).sqrt())
+}
+
+fn system_quality_number(input: &MetricInput) -> Option<f64> {
+    let pnls = input.trade_pnls;
+    if pnls.len() < 2 {
+        return None;
+    }
+    let n = pnls.len() as f64;
+    let mean = pnls.iter().sum::<f64>() / n;
+    let variance = pnls.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    // Tharp caps the count at 100 so long histories don't inflate the score
+    (variance > 0.0).then(|| mean / variance.sqrt() * n.min(100.0).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_metric_resolves_alongside_builtins() {
+        let registry = MetricRegistry::new();
+        registry.register(
+            MetricDefinition::new("trades_per_win", "Trades per winning trade", |input| {
+                (input.result.winning_trades > 0)
+                    .then(|| input.result.total_trades as f64 / input.result.winning_trades as f64)
+            })
+            .with_score(|value| 100.0 - value * 10.0),
+        ).unwrap();
+        assert!(matches!(
+            registry.register(MetricDefinition::new("sqn", "", |_| None)),
+            Err(MetricError::AlreadyRegistered(_))
+        ));
+
+        let result = BacktestResult { total_trades: 40, winning_trades: 20, ..Default::default() };
+        let pnls = [100.0, -50.0, 100.0, -50.0];
+        let input = MetricInput::new(&result).with_trade_pnls(&pnls);
+
+        assert_eq!(registry.value("trades_per_win", &input).unwrap(), 2.0);
+        assert_eq!(registry.score("trades_per_win", &input).unwrap(), 80.0);
+        // Mean 25 over a deviation of sqrt(7500), times root 4
+        assert!((registry.value("sqn", &input).unwrap() - 50.0 / 7500f64.sqrt()).abs() < 1e-12);
+        assert!(matches!(registry.value("ulcer_index", &input), Err(MetricError::Unavailable(_))));
+        assert!(!registry.evaluate(&input).contains_key("tail_ratio"));
+    }
+}
@@ -1,17 +1,96 @@
 //! Performance and risk metrics calculation
 
+use crate::backtesting::quiet_periods::{QuietAction, QuietPeriodConfig, QuietPeriods};
 use crate::strategy::Position;
 use crate::strategy::traits::OrderFill;
 use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+
+/// Equity curve resolution unless configured otherwise
+pub const DEFAULT_EQUITY_BAR_MS: u64 = 1_000;
+
+/// Periods per year returns are annualized over
+const PERIODS_PER_YEAR: f64 = 252.0;
+
+/// Running moments of a return series
+///
+/// Mean and variance are kept with Welford's method and the downside by its
+/// sum of squares, so Sharpe and Sortino ratios are available at any point
+/// of a run without keeping the returns.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReturnAccumulator {
+    count: u64,
+    mean: f64,
+    m2: f64,
+    downside_count: u64,
+    downside_sq: f64,
+}
+
+impl ReturnAccumulator {
+    pub fn push(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+        if value < 0.0 {
+            self.downside_count += 1;
+            self.downside_sq += value * value;
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Population standard deviation
+    pub fn std_dev(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            (self.m2 / self.count as f64).max(0.0).sqrt()
+        }
+    }
+
+    /// Annualized Sharpe ratio, zero without variation
+    pub fn sharpe_ratio(&self) -> f64 {
+        let std_dev = self.std_dev();
+        if std_dev == 0.0 {
+            0.0
+        } else {
+            self.mean / std_dev * PERIODS_PER_YEAR.sqrt()
+        }
+    }
+
+    /// Annualized Sortino ratio, zero without losing periods
+    pub fn sortino_ratio(&self) -> f64 {
+        if self.downside_count == 0 {
+            return 0.0;
+        }
+        let downside_dev = (self.downside_sq / self.downside_count as f64).sqrt();
+        if downside_dev == 0.0 {
+            0.0
+        } else {
+            self.mean / downside_dev * PERIODS_PER_YEAR.sqrt()
+        }
+    }
+}
 
 /// Performance metrics tracker
+///
+/// Every figure is updated as equity is marked and fills are recorded, so
+/// memory does not grow with the number of ticks and the final figures are
+/// ready the moment a run ends. The equity curve keeps the last mark of
+/// each `equity_bar_ms` bar.
 #[derive(Debug, Clone)]
 pub struct PerformanceMetrics {
     pub equity_curve: Vec<(DateTime<Utc>, Decimal)>,
-    pub returns: Vec<f64>,
+    pub returns: ReturnAccumulator,
     pub trades: Vec<TradeRecord>,
     pub high_water_mark: Decimal,
     pub current_drawdown: Decimal,
@@ -25,13 +104,19 @@ pub struct PerformanceMetrics {
     pub volatility: f64,
     pub beta: f64,
     pub alpha: f64,
+    /// Length of an equity curve bar; zero keeps every mark
+    pub equity_bar_ms: u64,
+    /// Handling of stretches without marks
+    pub quiet_periods: QuietPeriods,
+    first_equity: Option<Decimal>,
+    last_equity: Option<Decimal>,
 }
 
 impl PerformanceMetrics {
     pub fn new() -> Self {
         Self {
             equity_curve: Vec::new(),
-            returns: Vec::new(),
+            returns: ReturnAccumulator::default(),
             trades: Vec::new(),
             high_water_mark: Decimal::ZERO,
             current_drawdown: Decimal::ZERO,
@@ -45,12 +130,33 @@ impl PerformanceMetrics {
             volatility: 0.0,
             beta: 0.0,
             alpha: 0.0,
+            equity_bar_ms: DEFAULT_EQUITY_BAR_MS,
+            quiet_periods: QuietPeriods::new(QuietPeriodConfig::default()),
+            first_equity: None,
+            last_equity: None,
         }
     }
     
+    pub fn with_equity_bar_ms(mut self, equity_bar_ms: u64) -> Self {
+        self.equity_bar_ms = equity_bar_ms;
+        self
+    }
+    
+    pub fn with_quiet_periods(mut self, config: QuietPeriodConfig) -> Self {
+        self.quiet_periods = QuietPeriods::new(config);
+        self
+    }
+    
     /// Update equity curve
     pub fn update_equity(&mut self, equity: Decimal, timestamp: DateTime<Utc>) {
-        self.equity_curve.push((timestamp, equity));
+        let action = self.quiet_periods.on_mark(timestamp);
+        if let (QuietAction::ForwardFill(fills), Some(last_equity)) = (&action, self.last_equity) {
+            for &fill in fills {
+                self.record_equity_point(last_equity, fill);
+                self.returns.push(0.0);
+            }
+        }
+        self.record_equity_point(equity, timestamp);
         
         // Update drawdown
         if equity > self.high_water_mark {
@@ -64,14 +170,34 @@ impl PerformanceMetrics {
         }
         
         // Calculate return
-        if self.equity_curve.len() > 1 {
-            let prev_equity = self.equity_curve[self.equity_curve.len() - 2].1;
+        if let Some(prev_equity) = self.last_equity.filter(|_| action != QuietAction::ExcludeReturn) {
             if prev_equity > Decimal::ZERO {
-                let return_pct = ((equity - prev_equity) / prev_equity)
-                    .to_string().parse::<f64>().unwrap_or(0.0);
+                let return_pct = ((equity - prev_equity) / prev_equity).to_f64().unwrap_or(0.0);
                 self.returns.push(return_pct);
+                self.sharpe_ratio = self.returns.sharpe_ratio();
+                self.volatility = self.returns.std_dev();
             }
         }
+        let first_equity = *self.first_equity.get_or_insert(equity);
+        if first_equity > Decimal::ZERO {
+            self.total_return = ((equity - first_equity) / first_equity).to_f64().unwrap_or(0.0);
+        }
+        self.last_equity = Some(equity);
+    }
+    
+    /// Add a mark to the curve, replacing the previous one within the same bar
+    fn record_equity_point(&mut self, equity: Decimal, timestamp: DateTime<Utc>) {
+        let bar_ms = self.equity_bar_ms as i64;
+        let same_bar = bar_ms > 0 && self.equity_curve.last().is_some_and(|(last, _)| {
+            last.timestamp_millis().div_euclid(bar_ms) == timestamp.timestamp_millis().div_euclid(bar_ms)
+        });
+        if same_bar {
+            if let Some(point) = self.equity_curve.last_mut() {
+                *point = (timestamp, equity);
+            }
+        } else {
+            self.equity_curve.push((timestamp, equity));
+        }
     }
     
     /// Update position metrics
@@ -82,72 +208,27 @@ impl PerformanceMetrics {
     /// Record a trade
     pub fn record_trade(&mut self, fill: &OrderFill) {
         self.trades.push(TradeRecord::from_fill(fill));
+        self.total_trades += 1;
     }
     
     /// Calculate Sharpe ratio
     pub fn calculate_sharpe_ratio(&self) -> f64 {
-        if self.returns.is_empty() {
-            return 0.0;
-        }
-        
-        let mean_return = self.returns.iter().sum::<f64>() / self.returns.len() as f64;
-        let variance = self.returns.iter()
-            .map(|r| (r - mean_return).powi(2))
-            .sum::<f64>() / self.returns.len() as f64;
-        let std_dev = variance.sqrt();
-        
-        if std_dev == 0.0 {
-            0.0
-        } else {
-            // Annualized Sharpe (assuming daily returns, 252 trading days)
-            mean_return / std_dev * (252.0_f64).sqrt()
-        }
+        self.returns.sharpe_ratio()
     }
     
     /// Calculate Sortino ratio
     pub fn calculate_sortino_ratio(&self) -> f64 {
-        if self.returns.is_empty() {
-            return 0.0;
-        }
-        
-        let mean_return = self.returns.iter().sum::<f64>() / self.returns.len() as f64;
-        let downside_returns: Vec<f64> = self.returns.iter()
-            .filter(|&&r| r < 0.0)
-            .copied()
-            .collect();
-        
-        if downside_returns.is_empty() {
-            return 0.0;
-        }
-        
-        let downside_variance = downside_returns.iter()
-            .map(|r| r.powi(2))
-            .sum::<f64>() / downside_returns.len() as f64;
-        let downside_dev = downside_variance.sqrt();
-        
-        if downside_dev == 0.0 {
-            0.0
-        } else {
-            mean_return / downside_dev * (252.0_f64).sqrt()
-        }
+        self.returns.sortino_ratio()
     }
     
     /// Calculate Calmar ratio
     pub fn calculate_calmar_ratio(&self) -> f64 {
-        if self.max_drawdown == Decimal::ZERO || self.equity_curve.is_empty() {
+        if self.max_drawdown == Decimal::ZERO || self.last_equity.is_none() {
             return 0.0;
         }
         
-        let total_return = if self.equity_curve.len() >= 2 {
-            let start = self.equity_curve.first().unwrap().1;
-            let end = self.equity_curve.last().unwrap().1;
-            ((end - start) / start).to_string().parse::<f64>().unwrap_or(0.0)
-        } else {
-            0.0
-        };
-        
-        let max_dd = self.max_drawdown.to_string().parse::<f64>().unwrap_or(1.0);
-        total_return / max_dd
+        let max_dd = self.max_drawdown.to_f64().unwrap_or(1.0);
+        self.total_return / max_dd
     }
     
     /// Get equity curve
@@ -170,6 +251,9 @@ pub struct TradeRecord {
     pub price: Decimal,
     pub commission: Decimal,
     pub slippage: Decimal,
+    /// Tag of the order that filled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
 }
 
 impl TradeRecord {
@@ -181,6 +265,7 @@ impl TradeRecord {
             price: fill.price,
             commission: fill.commission,
             slippage: fill.slippage,
+            tag: fill.tag.clone(),
         }
     }
 }
@@ -210,4 +295,35 @@ pub struct TradeStatistics {
     pub profit_factor: f64,
     pub expectancy: Decimal,
     pub win_rate: f64,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_running_figures_match_the_full_series() {
+        let start = Utc::now();
+        let equity = [100_000, 101_000, 100_500, 99_000, 102_000, 101_500];
+        let mut metrics = PerformanceMetrics::new().with_equity_bar_ms(0);
+        for (i, value) in equity.iter().enumerate() {
+            metrics.update_equity(Decimal::from(*value), start + chrono::Duration::seconds(i as i64));
+        }
+
+        let returns: Vec<f64> = equity.windows(2).map(|pair| pair[1] as f64 / pair[0] as f64 - 1.0).collect();
+        let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+        let std_dev = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / returns.len() as f64).sqrt();
+        assert!((metrics.calculate_sharpe_ratio() - mean / std_dev * 252f64.sqrt()).abs() < 1e-9);
+        assert_eq!(metrics.max_drawdown, Decimal::from(2_000));
+        assert!((metrics.total_return - 0.015).abs() < 1e-12);
+        assert_eq!(metrics.equity_curve.len(), equity.len());
+
+        // Marks within one bar keep only the latest
+        let mut bars = PerformanceMetrics::new();
+        for (i, value) in equity.iter().enumerate() {
+            bars.update_equity(Decimal::from(*value), start + chrono::Duration::milliseconds(i as i64 * 400));
+        }
+        assert!(bars.equity_curve.len() < equity.len());
+        assert_eq!(bars.equity_curve.last().map(|(_, equity)| *equity), Some(Decimal::from(101_500)));
+        assert_eq!(bars.calculate_sharpe_ratio(), metrics.calculate_sharpe_ratio());
+    }
+}
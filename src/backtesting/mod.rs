@@ -4,14 +4,42 @@
 //! Provides accurate tick-by-tick backtesting with realistic transaction costs
 //! Processes 100K-500K ticks per second with nanosecond precision
 
+pub mod account;
 pub mod engine;
+pub mod equity_store;
+pub mod execution_quality;
 pub mod executor;
+pub mod halts;
+pub mod matching;
 pub mod models;
+pub mod queue_fill;
+pub mod quiet_periods;
+pub mod routing_delay;
 pub mod metrics;
+pub mod metric_registry;
 pub mod report;
+pub mod shared_scan;
+pub mod stress;
+pub mod wal;
+pub mod warm_session;
 
-pub use engine::{BacktestEngine, BacktestConfig, BacktestResult};
+pub use account::{Account, MarginConfig, MarginReport, MarginSnapshot};
+pub use engine::{BacktestEngine, BacktestConfig, BacktestResult, RunProgress};
+pub use equity_store::{CompressedEquityCurve, EquityBar, EquityCurveStore, EquityStoreError, EquityView};
+pub use execution_quality::{ExecutionQualityReport, ExecutionQualityTracker, FillQuality};
 pub use executor::{StrategyExecutor, ExecutionContext};
+pub use halts::{
+    Admission, HaltConfig, HaltEvent, HaltKind, HaltReport, HaltSimulator, MarketState, ScheduledHalt, VelocityLogicConfig,
+};
+pub use matching::{Match, MatchingEngine};
 pub use models::{TransactionCostModel, SlippageModel, LatencyModel};
-pub use metrics::{PerformanceMetrics, RiskMetrics, TradeStatistics};
-pub use report::BacktestReport;
\ No newline at end of file
+pub use queue_fill::{FillDecision, QueueFillConfig, QueuePosition, QueuePositionModel};
+pub use quiet_periods::{QuietAction, QuietPeriodConfig, QuietPeriodPolicy, QuietPeriodReport, QuietPeriods};
+pub use routing_delay::{load_empirical_routes, DelayDistribution, RouteDelay, RoutedAction, RoutingDelayError};
+pub use metrics::{PerformanceMetrics, ReturnAccumulator, RiskMetrics, TradeStatistics};
+pub use metric_registry::{MetricDefinition, MetricError, MetricInput, MetricRegistry};
+pub use report::BacktestReport;
+pub use shared_scan::SharedScan;
+pub use stress::{Shock, StressError, StressOutcome, StressReport, StressScenario, StressTester};
+pub use wal::{BacktestWal, RecoveredBacktest, WalConfig, WalError};
+pub use warm_session::{WarmRun, WarmSession, WarmSessionError, WarmSessionInfo, WarmSessions};
\ No newline at end of file
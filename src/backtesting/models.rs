@@ -1,8 +1,11 @@
 //! Transaction cost and slippage models
 
 use crate::backtesting::engine::TransactionCostConfig;
+use crate::backtesting::routing_delay::{load_empirical_routes, RouteDelay, RoutedAction, RoutingDelayError};
+use crate::strategy::OrderType;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 
 /// Model for calculating transaction costs
 #[derive(Debug, Clone)]
@@ -105,9 +108,51 @@ pub struct LatencyModel {
     
     /// Processing delay
     pub processing_ms: u32,
+    
+    /// Delays by order type and size; actions and sizes no route covers
+    /// use the base latency, jitter and processing delay
+    #[serde(default)]
+    pub routes: Vec<RouteDelay>,
 }
 
 impl LatencyModel {
+    /// Add routing delays by order type and size
+    pub fn with_routes(mut self, routes: Vec<RouteDelay>) -> Self {
+        self.routes.extend(routes);
+        self
+    }
+    
+    /// Add routing delays measured in a file; see [`load_empirical_routes`]
+    pub fn with_empirical_file(self, path: impl AsRef<Path>, size_buckets: &[u32]) -> Result<Self, RoutingDelayError> {
+        Ok(self.with_routes(load_empirical_routes(path, size_buckets)?))
+    }
+    
+    /// Route for `action` with the tightest size bucket covering `size`
+    pub fn route(&self, action: RoutedAction, size: u32) -> Option<&RouteDelay> {
+        self.routes.iter()
+            .filter(|route| route.covers(action, size))
+            .min_by_key(|route| route.max_size.unwrap_or(u32::MAX))
+    }
+    
+    /// Sample the delay in milliseconds of routing `action` for `size` contracts
+    pub fn sample_route_delay_ms(&self, action: RoutedAction, size: u32, rng: &mut impl rand::Rng) -> f64 {
+        match self.route(action, size) {
+            Some(route) => route.distribution.sample(rng),
+            None => (self.base_latency_ms + rng.gen_range(0..=self.jitter_ms) + self.processing_ms) as f64,
+        }
+    }
+    
+    /// Delay in milliseconds before a new order of `quantity` contracts reaches the exchange
+    pub fn order_latency_ms(&self, order_type: OrderType, quantity: i32) -> f64 {
+        let action = RoutedAction::for_order(order_type);
+        self.sample_route_delay_ms(action, quantity.unsigned_abs(), &mut rand::thread_rng())
+    }
+    
+    /// Delay in milliseconds before a cancel of an order of `quantity` contracts takes effect
+    pub fn cancel_latency_ms(&self, quantity: i32) -> f64 {
+        self.sample_route_delay_ms(RoutedAction::Cancel, quantity.unsigned_abs(), &mut rand::thread_rng())
+    }
+    
     /// Get total latency for order execution
     pub fn get_latency(&self) -> u32 {
         use rand::Rng;
@@ -133,6 +178,7 @@ impl Default for LatencyModel {
             base_latency_ms: 1,
             jitter_ms: 2,
             processing_ms: 1,
+            routes: Vec::new(),
         }
     }
 }
\ No newline at end of file
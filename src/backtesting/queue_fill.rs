@@ -0,0 +1,317 @@
+//! Queue-position-aware fills for resting limit orders
+//!
+//! A limit order that joins a price level sits behind the volume already
+//! displayed there and only fills once that volume has traded or been
+//! cancelled. Trades at the level are seen directly, but L2 updates only
+//! show the level shrinking, not where in the queue the cancellations came
+//! from. The model therefore carries a range for the volume still ahead of
+//! each order: cancellations shrink the optimistic end (everyone cancelling
+//! was ahead of us) while the pessimistic end only shrinks when the whole
+//! level drops below it. Assuming the true position is uniform over that
+//! range, a trade of `v` lots fills the order with probability
+//! `P(ahead < v)`. Whether that probability becomes a fill is decided by a
+//! fixed threshold or by a seeded draw.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::data::{DataLevel, MarketDataType, TickData};
+use crate::market::BookSide;
+use crate::strategy::OrderSide;
+
+/// How a fill probability is turned into a fill
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum FillDecision {
+    /// Fill whenever the probability reaches `threshold`
+    Threshold { threshold: f64 },
+    /// Fill with the estimated probability, drawing from a seeded generator
+    Random { seed: u64 },
+}
+
+impl Default for FillDecision {
+    fn default() -> Self {
+        FillDecision::Threshold { threshold: 0.5 }
+    }
+}
+
+/// Queue model settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueueFillConfig {
+    #[serde(default)]
+    pub decision: FillDecision,
+}
+
+/// Estimated position of one resting order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuePosition {
+    pub side: OrderSide,
+    pub price: Decimal,
+    pub quantity: i32,
+    /// Fewest lots that can still be ahead of the order
+    pub ahead_min: f64,
+    /// Most lots that can still be ahead of the order
+    pub ahead_max: f64,
+}
+
+impl QueuePosition {
+    /// Chance that a trade of `volume` lots at the order's price reaches it
+    pub fn fill_probability(&self, volume: f64) -> f64 {
+        if volume <= self.ahead_min {
+            0.0
+        } else if volume > self.ahead_max {
+            1.0
+        } else {
+            ((volume - self.ahead_min) / (self.ahead_max - self.ahead_min)).clamp(0.0, 1.0)
+        }
+    }
+
+    fn book_side(&self) -> BookSide {
+        match self.side {
+            OrderSide::Buy => BookSide::Bid,
+            OrderSide::Sell => BookSide::Ask,
+        }
+    }
+
+    /// Whether a trade at `price` happened through the order's limit
+    fn traded_through(&self, price: Decimal) -> bool {
+        match self.side {
+            OrderSide::Buy => price < self.price,
+            OrderSide::Sell => price > self.price,
+        }
+    }
+}
+
+/// Tracks displayed level volume and the queue position of resting orders
+#[derive(Debug)]
+pub struct QueuePositionModel {
+    decision: FillDecision,
+    rng: StdRng,
+    levels: HashMap<(BookSide, Decimal), i32>,
+    /// Volume traded at a level not yet seen leaving it through an L2 update
+    traded: HashMap<(BookSide, Decimal), i32>,
+    /// Ordered by id, so a seeded run draws for the same orders in the same order
+    orders: BTreeMap<String, QueuePosition>,
+}
+
+impl QueuePositionModel {
+    pub fn new(config: &QueueFillConfig) -> Self {
+        let seed = match config.decision {
+            FillDecision::Random { seed } => seed,
+            FillDecision::Threshold { .. } => 0,
+        };
+        Self {
+            decision: config.decision,
+            rng: StdRng::seed_from_u64(seed),
+            levels: HashMap::new(),
+            traded: HashMap::new(),
+            orders: BTreeMap::new(),
+        }
+    }
+
+    /// Volume displayed at a level
+    pub fn displayed(&self, side: BookSide, price: Decimal) -> i32 {
+        self.levels.get(&(side, price)).copied().unwrap_or(0)
+    }
+
+    /// Rest an order at the back of its level's queue
+    pub fn join(&mut self, order_id: impl Into<String>, side: OrderSide, price: Decimal, quantity: i32) {
+        let mut position = QueuePosition {
+            side,
+            price,
+            quantity,
+            ahead_min: 0.0,
+            ahead_max: 0.0,
+        };
+        let ahead = self.displayed(position.book_side(), price) as f64;
+        position.ahead_min = ahead;
+        position.ahead_max = ahead;
+        self.orders.insert(order_id.into(), position);
+    }
+
+    pub fn cancel(&mut self, order_id: &str) -> Option<QueuePosition> {
+        self.orders.remove(order_id)
+    }
+
+    pub fn position(&self, order_id: &str) -> Option<&QueuePosition> {
+        self.orders.get(order_id)
+    }
+
+    /// Apply a tick, returning the ids of the orders it filled
+    pub fn on_tick(&mut self, tick: &TickData) -> Vec<String> {
+        match (&tick.level, &tick.mdt) {
+            (_, MarketDataType::Trade) => self.on_trade(tick.price, tick.volume),
+            (DataLevel::L1, MarketDataType::BookReset) => {
+                self.levels.clear();
+                self.traded.clear();
+                Vec::new()
+            }
+            (DataLevel::L2, _) | (DataLevel::L1, MarketDataType::BidQuote | MarketDataType::AskQuote) => {
+                if let Some(side) = quote_side(&tick.mdt) {
+                    let volume = match tick.operation {
+                        Some(crate::data::OrderBookOperation::Remove) => 0,
+                        _ => tick.volume,
+                    };
+                    self.on_level(side, tick.price, volume);
+                }
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// A level's displayed volume changed to `volume`
+    pub fn on_level(&mut self, side: BookSide, price: Decimal, volume: i32) {
+        let key = (side, price);
+        let previous = self.levels.get(&key).copied().unwrap_or(0);
+        if volume > 0 {
+            self.levels.insert(key, volume);
+        } else {
+            self.levels.remove(&key);
+        }
+
+        // Shrinkage not explained by trades already applied is cancellation
+        let decrease = (previous - volume).max(0);
+        let traded = self.traded.remove(&key).unwrap_or(0);
+        let cancelled = (decrease - traded).max(0) as f64;
+        if traded > decrease {
+            self.traded.insert(key, traded - decrease);
+        }
+
+        for order in self.orders.values_mut().filter(|o| o.book_side() == side && o.price == price) {
+            order.ahead_min = (order.ahead_min - cancelled).max(0.0);
+            order.ahead_max = order.ahead_max.min(volume.max(0) as f64);
+        }
+    }
+
+    /// A trade printed at `price`, returning the ids of the orders it filled
+    pub fn on_trade(&mut self, price: Decimal, volume: i32) -> Vec<String> {
+        let volume = volume.max(0) as f64;
+        let mut filled = Vec::new();
+        let mut sides_at_price = Vec::new();
+
+        for (id, order) in self.orders.iter_mut() {
+            if order.traded_through(price) {
+                filled.push(id.clone());
+                continue;
+            }
+            if order.price != price {
+                continue;
+            }
+            if !sides_at_price.contains(&order.book_side()) {
+                sides_at_price.push(order.book_side());
+            }
+
+            let probability = order.fill_probability(volume);
+            let fills = match self.decision {
+                FillDecision::Threshold { threshold } => probability > 0.0 && probability >= threshold,
+                FillDecision::Random { .. } => probability > 0.0 && self.rng.gen::<f64>() < probability,
+            };
+            if fills {
+                filled.push(id.clone());
+            } else {
+                // Still resting, so at least `volume` lots were ahead
+                order.ahead_min = (order.ahead_min.max(volume) - volume).max(0.0);
+                order.ahead_max = (order.ahead_max - volume).max(0.0);
+            }
+        }
+
+        for side in sides_at_price {
+            *self.traded.entry((side, price)).or_default() += volume as i32;
+        }
+        for id in &filled {
+            self.orders.remove(id);
+        }
+        filled
+    }
+}
+
+fn quote_side(mdt: &MarketDataType) -> Option<BookSide> {
+    match mdt {
+        MarketDataType::BidQuote | MarketDataType::ImpliedBid => Some(BookSide::Bid),
+        MarketDataType::AskQuote | MarketDataType::ImpliedAsk => Some(BookSide::Ask),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_position_from_cancels_and_trades() {
+        let config = QueueFillConfig { decision: FillDecision::Threshold { threshold: 0.5 } };
+        let mut model = QueuePositionModel::new(&config);
+        let bid = Decimal::new(1_805_000, 2);
+
+        model.on_level(BookSide::Bid, bid, 20);
+        model.join("a", OrderSide::Buy, bid, 1);
+
+        // 10 lots cancelled from somewhere in the queue
+        model.on_level(BookSide::Bid, bid, 10);
+        let position = model.position("a").unwrap();
+        assert_eq!((position.ahead_min, position.ahead_max), (10.0, 10.0));
+
+        model.on_level(BookSide::Bid, bid, 4);
+        let position = model.position("a").unwrap().clone();
+        assert_eq!((position.ahead_min, position.ahead_max), (4.0, 4.0));
+
+        model.on_level(BookSide::Bid, bid, 12);
+        model.on_level(BookSide::Bid, bid, 8);
+        let position = model.position("a").unwrap().clone();
+        assert_eq!((position.ahead_min, position.ahead_max), (0.0, 4.0));
+        assert_eq!(position.fill_probability(1.0), 0.25);
+
+        // Too small to be likely, but the survivors narrow the range
+        assert!(model.on_trade(bid, 1).is_empty());
+        let position = model.position("a").unwrap();
+        assert_eq!((position.ahead_min, position.ahead_max), (0.0, 3.0));
+        model.on_level(BookSide::Bid, bid, 7);
+        assert_eq!(model.on_trade(bid, 2), vec!["a".to_string()]);
+
+        // A trade below the bid fills whatever queue is left
+        model.join("b", OrderSide::Buy, bid, 1);
+        assert_eq!(model.on_trade(bid - Decimal::new(25, 2), 1), vec!["b".to_string()]);
+
+        let mut seeded = QueuePositionModel::new(&QueueFillConfig { decision: FillDecision::Random { seed: 7 } });
+        let mut replay = QueuePositionModel::new(&QueueFillConfig { decision: FillDecision::Random { seed: 7 } });
+        for model in [&mut seeded, &mut replay] {
+            model.on_level(BookSide::Ask, bid, 10);
+            model.join("c", OrderSide::Sell, bid, 1);
+            model.on_level(BookSide::Ask, bid, 30);
+            model.on_level(BookSide::Ask, bid, 20);
+        }
+        let fills: Vec<_> = (0..4).map(|_| seeded.on_trade(bid, 3)).collect();
+        let replayed: Vec<_> = (0..4).map(|_| replay.on_trade(bid, 3)).collect();
+        assert_eq!(fills, replayed);
+    }
+
+    #[test]
+    fn test_seeded_fills_of_orders_at_one_level_replay_exactly() {
+        let price = Decimal::new(1_805_000, 2);
+        let ids = ["a", "b", "c", "d", "e", "f"];
+        let model = |ids: Vec<&str>| {
+            let mut model = QueuePositionModel::new(&QueueFillConfig { decision: FillDecision::Random { seed: 11 } });
+            model.on_level(BookSide::Bid, price, 12);
+            for id in ids {
+                model.join(id, OrderSide::Buy, price, 1);
+            }
+            // Between 8 and 12 lots ahead of every order
+            model.on_level(BookSide::Bid, price, 24);
+            model.on_level(BookSide::Bid, price, 20);
+            model
+        };
+        // Joined in opposite orders, so only the draw order can tell them apart
+        let mut seeded = model(ids.to_vec());
+        let mut replay = model(ids.iter().rev().copied().collect());
+
+        let fills: Vec<_> = (0..2).map(|_| seeded.on_trade(price, 10)).collect();
+        let replayed: Vec<_> = (0..2).map(|_| replay.on_trade(price, 10)).collect();
+        assert_eq!(fills, replayed);
+        assert_eq!(fills.concat().len(), ids.len());
+        assert!(fills.iter().all(|ids| ids.windows(2).all(|pair| pair[0] < pair[1])));
+    }
+}
@@ -0,0 +1,184 @@
+//! Quiet periods in the equity curve and return statistics
+//!
+//! Futures trade nearly around the clock but not quite: the daily
+//! maintenance break, weekends and holidays leave stretches without ticks.
+//! How those stretches enter the equity bars and the return series changes
+//! volatility and Sharpe. Skipping them treats the move across a weekend as
+//! one period; forward-filling adds flat periods that dilute volatility;
+//! marking them as gaps leaves the move across them out of the statistics.
+//! The policy is recorded with each result so runs are compared like for
+//! like.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// How a quiet period enters bars and returns
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuietPeriodPolicy {
+    /// Ignore the period; the first mark after it returns against the last
+    /// one before it
+    #[default]
+    Skip,
+    /// Carry the last equity across the period as flat bars with zero returns
+    ForwardFill,
+    /// Leave the return across the period out of the statistics
+    MarkGap,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietPeriodConfig {
+    #[serde(default)]
+    pub policy: QuietPeriodPolicy,
+    /// Time between marks that counts as a quiet period
+    #[serde(default = "default_threshold_ms")]
+    pub threshold_ms: u64,
+    /// Spacing of forward-filled bars
+    #[serde(default = "default_fill_interval_ms")]
+    pub fill_interval_ms: u64,
+}
+
+fn default_threshold_ms() -> u64 {
+    15 * 60 * 1_000
+}
+
+fn default_fill_interval_ms() -> u64 {
+    60 * 1_000
+}
+
+impl Default for QuietPeriodConfig {
+    fn default() -> Self {
+        Self {
+            policy: QuietPeriodPolicy::default(),
+            threshold_ms: default_threshold_ms(),
+            fill_interval_ms: default_fill_interval_ms(),
+        }
+    }
+}
+
+/// Quiet periods a run met and what the policy did with them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuietPeriodReport {
+    pub policy: QuietPeriodPolicy,
+    pub threshold_ms: u64,
+    pub periods: usize,
+    /// Total length of the quiet periods
+    pub quiet_ms: u64,
+    pub longest_ms: u64,
+    /// Flat bars added by forward-filling
+    pub filled_bars: usize,
+    /// Returns left out by marking gaps
+    pub excluded_returns: usize,
+}
+
+/// What to do with a mark
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuietAction {
+    Record,
+    /// Add flat bars at these times before recording the mark
+    ForwardFill(Vec<DateTime<Utc>>),
+    /// Record the mark without counting its return
+    ExcludeReturn,
+}
+
+/// Spots quiet periods between successive equity marks
+#[derive(Debug, Clone)]
+pub struct QuietPeriods {
+    config: QuietPeriodConfig,
+    report: QuietPeriodReport,
+    last_mark: Option<DateTime<Utc>>,
+}
+
+impl QuietPeriods {
+    pub fn new(config: QuietPeriodConfig) -> Self {
+        let report = QuietPeriodReport {
+            policy: config.policy,
+            threshold_ms: config.threshold_ms,
+            ..Default::default()
+        };
+        Self { config, report, last_mark: None }
+    }
+
+    /// Note a mark at `timestamp`, returning how to treat it
+    pub fn on_mark(&mut self, timestamp: DateTime<Utc>) -> QuietAction {
+        let Some(last) = self.last_mark.replace(timestamp) else {
+            return QuietAction::Record;
+        };
+        let gap_ms = (timestamp - last).num_milliseconds().max(0) as u64;
+        if gap_ms <= self.config.threshold_ms {
+            return QuietAction::Record;
+        }
+
+        self.report.periods += 1;
+        self.report.quiet_ms += gap_ms;
+        self.report.longest_ms = self.report.longest_ms.max(gap_ms);
+        match self.config.policy {
+            QuietPeriodPolicy::Skip => QuietAction::Record,
+            QuietPeriodPolicy::ForwardFill => {
+                let interval = self.config.fill_interval_ms.max(1);
+                let fills: Vec<DateTime<Utc>> = (1..gap_ms.div_ceil(interval))
+                    .map(|i| last + Duration::milliseconds((i * interval) as i64))
+                    .collect();
+                self.report.filled_bars += fills.len();
+                QuietAction::ForwardFill(fills)
+            }
+            QuietPeriodPolicy::MarkGap => {
+                self.report.excluded_returns += 1;
+                QuietAction::ExcludeReturn
+            }
+        }
+    }
+
+    pub fn report(&self) -> &QuietPeriodReport {
+        &self.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtesting::PerformanceMetrics;
+    use chrono::TimeZone;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_policies_change_volatility_over_the_maintenance_break() {
+        // Marks a minute apart either side of the 16:00-17:00 break, with a
+        // jump across it
+        let at = |h, m| Utc.with_ymd_and_hms(2024, 3, 12, h, m, 0).unwrap();
+        let marks = [
+            (at(15, 58), 10_000),
+            (at(15, 59), 10_010),
+            (at(17, 0), 10_200),
+            (at(17, 1), 10_190),
+        ];
+        let run = |policy| {
+            let config = QuietPeriodConfig { policy, ..Default::default() };
+            let mut metrics = PerformanceMetrics::new().with_equity_bar_ms(0).with_quiet_periods(config);
+            for (time, equity) in marks {
+                metrics.update_equity(Decimal::from(equity), time);
+            }
+            metrics
+        };
+
+        let skip = run(QuietPeriodPolicy::Skip);
+        let fill = run(QuietPeriodPolicy::ForwardFill);
+        let gap = run(QuietPeriodPolicy::MarkGap);
+
+        assert_eq!(skip.returns.count(), 3);
+        assert_eq!(skip.quiet_periods.report().periods, 1);
+        assert_eq!(skip.quiet_periods.report().longest_ms, 61 * 60 * 1_000);
+
+        // A flat bar each minute from 16:00 to 16:59
+        assert_eq!(fill.quiet_periods.report().filled_bars, 60);
+        assert_eq!(fill.equity_curve.len(), 64);
+        assert_eq!(fill.equity_curve[2], (at(16, 0), Decimal::from(10_010)));
+        assert_eq!(fill.returns.count(), 63);
+        assert!(fill.volatility < skip.volatility);
+
+        assert_eq!(gap.returns.count(), 2);
+        assert_eq!(gap.quiet_periods.report().excluded_returns, 1);
+        assert!(gap.volatility < skip.volatility);
+        assert_eq!(gap.equity_curve.len(), 4);
+    }
+}
@@ -1,8 +1,13 @@
-use crate::backtesting::{BacktestResult, PerformanceMetrics};
+use crate::analysis::{StreakConfig, StreakReport};
+use crate::backtesting::{
+    BacktestResult, ExecutionQualityReport, MetricInput, MetricRegistry, PerformanceMetrics, StressReport,
+};
+use crate::timestamp::{SessionCalendar, Timestamp};
 use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BacktestReport {
@@ -12,6 +17,17 @@ pub struct BacktestReport {
     pub equity_curve: Vec<EquityPoint>,
     pub drawdown_curve: Vec<DrawdownPoint>,
     pub monthly_returns: HashMap<String, Decimal>,
+    #[serde(default)]
+    pub execution_quality: ExecutionQualityReport,
+    /// Registry metrics computable from this run, by name
+    #[serde(default)]
+    pub metrics: BTreeMap<String, f64>,
+    /// Outcomes of the same strategy under injected gap, halt and crash scenarios
+    #[serde(default)]
+    pub stress: Option<StressReport>,
+    /// Expectancy after win and loss streaks, in drawdown, and under stop-after-losses rules
+    #[serde(default)]
+    pub streaks: Option<StreakReport>,
     pub recommendations: Vec<String>,
 }
 
@@ -67,13 +83,14 @@ impl BacktestReport {
         let mut trades = Vec::new();
         let mut equity_curve = Vec::new();
         let mut drawdown_curve = Vec::new();
-        let monthly_returns = HashMap::new(); // TODO: Calculate monthly returns
+        let mut monthly_returns = HashMap::new();
+        let calendar = SessionCalendar::default();
 
         // Convert trades
         for trade in &result.trades {
             trades.push(TradeReport {
-                entry_time: DateTime::from_timestamp_nanos(trade.entry_timestamp as i64).unwrap(),
-                exit_time: DateTime::from_timestamp_nanos(trade.exit_timestamp as i64).unwrap(),
+                entry_time: Timestamp::from_nanos(trade.entry_timestamp as i64).to_utc(),
+                exit_time: Timestamp::from_nanos(trade.exit_timestamp as i64).to_utc(),
                 side: if trade.side == crate::strategy::orders::OrderSide::Buy { "Buy" } else { "Sell" }.to_string(),
                 entry_price: trade.entry_price,
                 exit_price: trade.exit_price,
@@ -89,8 +106,15 @@ impl BacktestReport {
         let mut cumulative_pnl = Decimal::ZERO;
         for (i, trade) in result.trades.iter().enumerate() {
             cumulative_pnl += trade.pnl;
+            let exit = Timestamp::from_nanos(trade.exit_timestamp as i64);
+
+            // Months follow the exchange trade date, not the UTC calendar:
+            // a fill at 18:00 CT on the last day of a month counts toward the next
+            let trade_date = calendar.trading_date(exit).unwrap_or_else(|| exit.exchange_date());
+            *monthly_returns.entry(trade_date.format("%Y-%m").to_string()).or_insert(Decimal::ZERO) += trade.pnl;
+
             equity_curve.push(EquityPoint {
-                timestamp: DateTime::from_timestamp_nanos(trade.exit_timestamp as i64).unwrap(),
+                timestamp: exit.to_utc(),
                 equity: result.initial_capital + cumulative_pnl,
                 cumulative_pnl,
             });
@@ -106,14 +130,17 @@ impl BacktestReport {
 
         let summary = ReportSummary {
             strategy_name: strategy_name.to_string(),
-            test_period_start: DateTime::from_timestamp_nanos(result.start_time as i64).unwrap(),
-            test_period_end: DateTime::from_timestamp_nanos(result.end_time as i64).unwrap(),
+            test_period_start: Timestamp::from_nanos(result.start_time as i64).to_utc(),
+            test_period_end: Timestamp::from_nanos(result.end_time as i64).to_utc(),
             total_ticks_processed: result.ticks_processed,
             total_trades: result.trades.len() as u32,
             final_pnl: cumulative_pnl,
             confidence_level,
         };
 
+        let mut recommendations = Self::generate_recommendations(&result.performance);
+        recommendations.extend(Self::execution_recommendations(&result.execution_quality));
+
         Self {
             summary,
             performance: result.performance.clone(),
@@ -121,10 +148,55 @@ impl BacktestReport {
             equity_curve,
             drawdown_curve,
             monthly_returns,
-            recommendations: Self::generate_recommendations(&result.performance),
+            execution_quality: result.execution_quality.clone(),
+            metrics: BTreeMap::new(),
+            stress: None,
+            streaks: None,
+            recommendations,
         }
     }
 
+    /// Attach stress test outcomes to the risk section
+    pub fn with_stress(mut self, stress: StressReport) -> Self {
+        if let Some(worst) = stress.worst_case() {
+            let capital = self.equity_curve.first().map(|point| point.equity).unwrap_or(Decimal::ZERO);
+            let loss = stress.worst_case_loss();
+            if capital > Decimal::ZERO && loss > capital * Decimal::new(10, 2) {
+                self.recommendations.push(format!(
+                    "Stress scenario '{}' loses {} against the baseline, over 10% of capital. Consider stop-losses or halt handling",
+                    worst.scenario.name, loss
+                ));
+            }
+        }
+        self.stress = Some(stress);
+        self
+    }
+
+    /// Analyze the run's trades for streak and drawdown dependence, adding
+    /// any meta-rule that would have helped to the recommendations
+    pub fn with_streaks(mut self, config: &StreakConfig) -> Self {
+        let trips: Vec<(DateTime<Utc>, Decimal)> = self.trades.iter()
+            .map(|trade| (trade.exit_time, trade.pnl))
+            .collect();
+        let streaks = StreakReport::from_round_trips(&trips, &SessionCalendar::default(), config);
+        self.recommendations.extend(streaks.recommendations());
+        self.streaks = Some(streaks);
+        self
+    }
+
+    /// List every metric of `registry` the run's result, equity curve and trades support
+    pub fn with_metrics(mut self, result: &BacktestResult, registry: &MetricRegistry) -> Self {
+        let equity: Vec<f64> = self.equity_curve.iter()
+            .map(|point| point.equity.to_f64().unwrap_or(0.0))
+            .collect();
+        let pnls: Vec<f64> = self.trades.iter()
+            .map(|trade| trade.pnl.to_f64().unwrap_or(0.0))
+            .collect();
+        let input = MetricInput::new(result).with_equity_curve(&equity).with_trade_pnls(&pnls);
+        self.metrics = registry.evaluate(&input);
+        self
+    }
+
     fn generate_recommendations(metrics: &PerformanceMetrics) -> Vec<String> {
         let mut recommendations = Vec::new();
 
@@ -151,6 +223,136 @@ impl BacktestReport {
         recommendations
     }
 
+    fn execution_recommendations(execution: &ExecutionQualityReport) -> Vec<String> {
+        let mut recommendations = Vec::new();
+        if execution.scored_fills == 0 {
+            return recommendations;
+        }
+
+        // Realized spread at the longest horizon nets out adverse selection
+        if let Some(longest) = execution.horizons.iter().rev().find(|h| h.fills > 0) {
+            if execution.avg_spread_capture > 0.0 && longest.avg_realized_spread < 0.0 {
+                recommendations.push(format!(
+                    "Passive fills are adversely selected: spread captured is lost within {}ms. Consider more aggressive entries",
+                    longest.horizon_ms
+                ));
+            }
+        }
+
+        if execution.aggressive_fill_ratio > 0.8 {
+            recommendations.push("Most fills cross the spread. Consider resting orders to capture part of it".to_string());
+        }
+
+        recommendations
+    }
+
+    fn execution_quality_html(&self) -> String {
+        let execution = &self.execution_quality;
+        let horizons = execution.horizons.iter()
+            .map(|h| format!(
+                "<tr><td>{}ms</td><td>{}</td><td>{:.4}</td><td>{:.4}</td></tr>",
+                h.horizon_ms, h.fills, h.avg_adverse_selection, h.avg_realized_spread
+            ))
+            .collect::<Vec<_>>()
+            .join("");
+
+        format!(
+            r#"<div class="metric">Fills scored against mid: {} ({} without a quote)</div>
+        <div class="metric">Avg Fill vs Mid: {:.4}</div>
+        <div class="metric">Avg Effective Spread: {:.4}</div>
+        <div class="metric">Avg Spread Capture: {:.1}%</div>
+        <div class="metric">Fills Crossing the Spread: {:.1}%</div>
+        <table>
+            <tr><th>Horizon</th><th>Fills</th><th>Adverse Selection</th><th>Realized Spread</th></tr>
+            {}
+        </table>"#,
+            execution.scored_fills,
+            execution.unscored_fills,
+            execution.avg_fill_vs_mid,
+            execution.avg_effective_spread,
+            execution.avg_spread_capture * 100.0,
+            execution.aggressive_fill_ratio * 100.0,
+            horizons
+        )
+    }
+
+    fn metrics_html(&self) -> String {
+        let rows = self.metrics.iter()
+            .map(|(name, value)| format!("<tr><td>{}</td><td>{:.4}</td></tr>", name, value))
+            .collect::<Vec<_>>()
+            .join("");
+        format!("<table>\n            <tr><th>Metric</th><th>Value</th></tr>\n            {}\n        </table>", rows)
+    }
+
+    fn stress_html(&self) -> String {
+        let Some(stress) = &self.stress else {
+            return "<div class=\"metric\">No stress scenarios run</div>".to_string();
+        };
+        let rows = stress.outcomes.iter()
+            .map(|o| format!(
+                "<tr><td>{}</td><td>{}</td><td>${}</td><td>${}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                o.scenario.name, o.ticks_affected, o.total_pnl, o.pnl_impact, o.max_drawdown,
+                o.total_trades, o.margin_rejections
+            ))
+            .collect::<Vec<_>>()
+            .join("");
+
+        format!(
+            r#"<div class="metric">Baseline P&L: ${} (max drawdown {})</div>
+        <div class="metric">Worst-Case Loss vs Baseline: ${}</div>
+        <table>
+            <tr><th>Scenario</th><th>Ticks Affected</th><th>P&L</th><th>Impact</th><th>Max Drawdown</th><th>Trades</th><th>Margin Rejections</th></tr>
+            {}
+        </table>"#,
+            stress.baseline_pnl,
+            stress.baseline_max_drawdown,
+            stress.worst_case_loss(),
+            rows
+        )
+    }
+
+    fn streaks_html(&self) -> String {
+        let Some(streaks) = &self.streaks else {
+            return "<div class=\"metric\">No streak analysis run</div>".to_string();
+        };
+        let buckets = streaks.after_losses.iter()
+            .map(|b| format!(
+                "<tr><td>{}</td><td>{}</td><td>{:.2}</td><td>{:.1}%</td></tr>",
+                b.length, b.next_trade.trades, b.next_trade.expectancy, b.next_trade.win_rate * 100.0
+            ))
+            .collect::<Vec<_>>()
+            .join("");
+        let rules = streaks.stop_rules.iter()
+            .map(|r| format!(
+                "<tr><td>{}</td><td>{}</td><td>${:.2}</td><td>${:.2}</td><td>{:.2}</td></tr>",
+                r.after_losses, r.trades_skipped, r.skipped_pnl, r.net_pnl, r.max_drawdown
+            ))
+            .collect::<Vec<_>>()
+            .join("");
+
+        format!(
+            r#"<div class="metric">Longest Streaks: {} wins, {} losses</div>
+        <div class="metric">Expectancy at Equity Highs: {:.2} ({} trades)</div>
+        <div class="metric">Expectancy in Drawdown: {:.2} ({} trades)</div>
+        <table>
+            <tr><th>Losses in a Row</th><th>Next Trades</th><th>Expectancy</th><th>Win Rate</th></tr>
+            {}
+        </table>
+        <table>
+            <tr><th>Stop After Losses</th><th>Trades Skipped</th><th>Skipped P&L</th><th>Net P&L</th><th>Max Drawdown</th></tr>
+            {}
+        </table>"#,
+            streaks.longest_win_streak,
+            streaks.longest_loss_streak,
+            streaks.at_equity_high.expectancy,
+            streaks.at_equity_high.trades,
+            streaks.in_drawdown.expectancy,
+            streaks.in_drawdown.trades,
+            buckets,
+            rules
+        )
+    }
+
     pub fn to_html(&self) -> String {
         // Simple HTML report generation
         format!(
@@ -180,6 +382,26 @@ impl BacktestReport {
         <div class="metric">Win Rate: {:.1}%</div>
     </div>
     
+    <div class="section">
+        <h2>Execution Quality</h2>
+        {}
+    </div>
+    
+    <div class="section">
+        <h2>Metrics</h2>
+        {}
+    </div>
+    
+    <div class="section">
+        <h2>Risk: Stress Scenarios</h2>
+        {}
+    </div>
+    
+    <div class="section">
+        <h2>Streaks and Drawdown Behavior</h2>
+        {}
+    </div>
+    
     <div class="section">
         <h2>Recommendations</h2>
         <ul>
@@ -196,6 +418,10 @@ impl BacktestReport {
             self.performance.sharpe_ratio,
             self.performance.max_drawdown * 100.0,
             self.performance.win_rate * 100.0,
+            self.execution_quality_html(),
+            self.metrics_html(),
+            self.stress_html(),
+            self.streaks_html(),
             self.recommendations.iter().map(|r| format!("<li>{}</li>", r)).collect::<Vec<_>>().join("")
         )
     }
@@ -0,0 +1,188 @@
+//! Order routing delays by order type and size
+//!
+//! A market order, a limit order and a cancel take different paths through a
+//! broker's and the exchange's gateways, and large orders can be held longer
+//! by risk checks. For a scalper, a cancel that arrives late turns a
+//! would-be miss into a fill at a stale price, so one latency number for
+//! everything flatters the results. Routes give each action, and each size
+//! bucket of it, its own delay distribution, which can be fitted or taken
+//! straight from measured latencies in a file.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use statrs::distribution::LogNormal;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::strategy::OrderType;
+
+/// Errors raised loading measured routing delays
+#[derive(Debug, thiserror::Error)]
+pub enum RoutingDelayError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Line {line}: {reason}")]
+    Parse { line: usize, reason: String },
+    #[error("No latency samples in {0}")]
+    Empty(String),
+}
+
+/// Message whose routing delay is sampled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoutedAction {
+    Market,
+    Limit,
+    Cancel,
+}
+
+impl RoutedAction {
+    /// Route of a new order; stops are routed as the order they trigger
+    pub fn for_order(order_type: OrderType) -> Self {
+        match order_type {
+            OrderType::Market | OrderType::Stop => RoutedAction::Market,
+            OrderType::Limit | OrderType::StopLimit => RoutedAction::Limit,
+        }
+    }
+}
+
+impl std::str::FromStr for RoutedAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "market" => Ok(RoutedAction::Market),
+            "limit" => Ok(RoutedAction::Limit),
+            "cancel" => Ok(RoutedAction::Cancel),
+            other => Err(format!("unknown action '{}'", other)),
+        }
+    }
+}
+
+/// Distribution of one route's delay, in milliseconds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DelayDistribution {
+    /// Always the same delay
+    Fixed { ms: f64 },
+    Uniform { min_ms: f64, max_ms: f64 },
+    /// Right-skewed delays with the given median; `sigma` is the standard
+    /// deviation of the log delay
+    LogNormal { median_ms: f64, sigma: f64 },
+    /// Measured delays, drawn from with replacement
+    Empirical { samples_ms: Vec<f64> },
+}
+
+impl DelayDistribution {
+    pub fn sample(&self, rng: &mut impl Rng) -> f64 {
+        let delay = match self {
+            DelayDistribution::Fixed { ms } => *ms,
+            DelayDistribution::Uniform { min_ms, max_ms } if max_ms > min_ms => rng.gen_range(*min_ms..=*max_ms),
+            DelayDistribution::Uniform { min_ms, .. } => *min_ms,
+            DelayDistribution::LogNormal { median_ms, sigma } => {
+                match LogNormal::new(median_ms.max(f64::MIN_POSITIVE).ln(), *sigma) {
+                    Ok(distribution) => rng.sample(distribution),
+                    Err(_) => *median_ms,
+                }
+            }
+            DelayDistribution::Empirical { samples_ms } if !samples_ms.is_empty() => {
+                samples_ms[rng.gen_range(0..samples_ms.len())]
+            }
+            DelayDistribution::Empirical { .. } => 0.0,
+        };
+        delay.max(0.0)
+    }
+}
+
+/// Delay distribution of one action for orders up to a size
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteDelay {
+    pub action: RoutedAction,
+    /// Largest order size, in contracts, the route covers; any size when unset
+    #[serde(default)]
+    pub max_size: Option<u32>,
+    pub distribution: DelayDistribution,
+}
+
+impl RouteDelay {
+    pub fn covers(&self, action: RoutedAction, size: u32) -> bool {
+        self.action == action && self.max_size.map_or(true, |max| size <= max)
+    }
+}
+
+/// Routes built from measured delays in `path`, one per action and size
+/// bucket with samples
+///
+/// Each line is `action,size,latency_ms`, e.g. `cancel,2,0.84`; a header
+/// line, blank lines and lines starting with `#` are skipped. `size_buckets`
+/// are the upper bounds of the size buckets, with sizes above the largest
+/// falling in an unbounded one.
+pub fn load_empirical_routes(path: impl AsRef<Path>, size_buckets: &[u32]) -> Result<Vec<RouteDelay>, RoutingDelayError> {
+    let contents = std::fs::read_to_string(path.as_ref())?;
+    let mut bounds = size_buckets.to_vec();
+    bounds.sort_unstable();
+    bounds.dedup();
+
+    let mut samples: BTreeMap<(RoutedAction, Option<u32>), Vec<f64>> = BTreeMap::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || (index == 0 && line.to_ascii_lowercase().starts_with("action")) {
+            continue;
+        }
+        let parse_error = |reason: String| RoutingDelayError::Parse { line: index + 1, reason };
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [action, size, latency] = fields[..] else {
+            return Err(parse_error(format!("expected 3 fields, found {}", fields.len())));
+        };
+        let action: RoutedAction = action.parse().map_err(parse_error)?;
+        let size: u32 = size.parse().map_err(|_| parse_error(format!("invalid size '{}'", size)))?;
+        let latency: f64 = latency.parse().map_err(|_| parse_error(format!("invalid latency '{}'", latency)))?;
+        if !latency.is_finite() || latency < 0.0 {
+            return Err(parse_error(format!("latency must be a non-negative number, got {}", latency)));
+        }
+
+        let bucket = bounds.iter().copied().find(|bound| size <= *bound);
+        samples.entry((action, bucket)).or_default().push(latency);
+    }
+    if samples.is_empty() {
+        return Err(RoutingDelayError::Empty(path.as_ref().display().to_string()));
+    }
+
+    Ok(samples.into_iter()
+        .map(|((action, max_size), samples_ms)| RouteDelay {
+            action,
+            max_size,
+            distribution: DelayDistribution::Empirical { samples_ms },
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtesting::LatencyModel;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_routes_by_action_and_size() {
+        let path = std::env::temp_dir().join(format!("routing-{}.csv", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "action,size,latency_ms\nmarket,1,0.4\ncancel,1,2.0\ncancel,3,2.0\ncancel,20,9.0\n# slow day\nlimit,50,1.5\n").unwrap();
+        let routes = load_empirical_routes(&path, &[5]).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(routes.len(), 4);
+
+        let model = LatencyModel::default().with_routes(routes);
+        let mut rng = StdRng::seed_from_u64(3);
+        assert_eq!(model.sample_route_delay_ms(RoutedAction::Cancel, 2, &mut rng), 2.0);
+        assert_eq!(model.sample_route_delay_ms(RoutedAction::Cancel, 10, &mut rng), 9.0);
+        assert_eq!(model.sample_route_delay_ms(RoutedAction::Market, 1, &mut rng), 0.4);
+        // No market route for large orders: falls back to base + jitter + processing
+        let fallback = model.sample_route_delay_ms(RoutedAction::Market, 10, &mut rng);
+        assert!((2.0..=4.0).contains(&fallback));
+
+        std::fs::write(&path, "cancel,two,1.0\n").unwrap();
+        assert!(matches!(load_empirical_routes(&path, &[]), Err(RoutingDelayError::Parse { line: 1, .. })));
+        std::fs::remove_file(&path).ok();
+    }
+}
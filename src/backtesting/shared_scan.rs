@@ -0,0 +1,79 @@
+//! Shared dataset scans for co-scheduled backtests
+//!
+//! Batch sweeps queue many backtests over the same data file. Running them
+//! one after another re-reads and re-parses that file for every job. A
+//! `SharedScan` reads the file once and fans the tick stream out to each
+//! participating engine, which run in parallel over the same buffer.
+
+use crate::backtesting::engine::read_ticks;
+use crate::backtesting::{BacktestEngine, BacktestResult};
+use crate::performance::warm_cache::DatasetCache;
+use crate::strategy::Strategy;
+use rayon::prelude::*;
+use std::path::PathBuf;
+use std::time::Instant;
+use tracing::info;
+
+/// Tick batch size used for the shared read
+const SHARED_READ_BATCH_SIZE: usize = 10_000;
+
+/// Backtests over one dataset sharing a single read pass
+pub struct SharedScan<S: Strategy> {
+    data_path: PathBuf,
+    dataset_cache: Option<DatasetCache>,
+    participants: Vec<(BacktestEngine, S)>,
+}
+
+impl<S: Strategy> SharedScan<S> {
+    pub fn new(data_path: impl Into<PathBuf>) -> Self {
+        Self {
+            data_path: data_path.into(),
+            dataset_cache: None,
+            participants: Vec::new(),
+        }
+    }
+
+    /// Read through a preloaded dataset cache, loading into it on a miss
+    pub fn with_dataset_cache(mut self, cache: DatasetCache) -> Self {
+        self.dataset_cache = Some(cache);
+        self
+    }
+
+    /// Add a backtest to the scan
+    pub fn add(&mut self, engine: BacktestEngine, strategy: S) {
+        self.participants.push((engine, strategy));
+    }
+
+    pub fn len(&self) -> usize {
+        self.participants.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.participants.is_empty()
+    }
+
+    /// Read the dataset once and run every backtest over it
+    ///
+    /// Results are in the order backtests were added. A failed read fails the
+    /// whole scan; a failure inside one backtest only fails that entry.
+    pub async fn run(self) -> Result<Vec<Result<BacktestResult, String>>, Box<dyn std::error::Error>> {
+        let read_start = Instant::now();
+        let ticks = read_ticks(&self.data_path, self.dataset_cache.as_ref(), SHARED_READ_BATCH_SIZE).await?;
+        info!("Shared scan read {} ticks from {} in {:.2}s for {} backtests",
+            ticks.len(),
+            self.data_path.display(),
+            read_start.elapsed().as_secs_f64(),
+            self.participants.len()
+        );
+
+        let data_path = self.data_path;
+        let mut participants = self.participants;
+        let results = participants.par_iter_mut()
+            .map(|(engine, strategy)| {
+                engine.run_loaded(strategy, &data_path, &ticks).map_err(|e| e.to_string())
+            })
+            .collect();
+
+        Ok(results)
+    }
+}
@@ -0,0 +1,333 @@
+//! Scenario stress testing
+//!
+//! Replays historical data with a synthetic shock injected into one session
+//! (an opening gap, a limit move followed by a halt, or a flash crash and
+//! recovery) and compares each run against the unshocked baseline. The
+//! resulting `StressReport` goes into the risk section of a `BacktestReport`.
+
+use crate::backtesting::engine::read_ticks;
+use crate::backtesting::{BacktestConfig, BacktestEngine, BacktestResult};
+use crate::data::TickData;
+use crate::strategy::Strategy;
+use crate::timestamp::{SessionCalendar, Timestamp, TimestampError};
+use chrono::{Duration, NaiveDate};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::info;
+
+/// MNQ minimum price increment
+const DEFAULT_TICK_SIZE: Decimal = Decimal::from_parts(25, 0, 0, false, 2);
+
+/// Errors raised while injecting a scenario
+#[derive(Debug, thiserror::Error)]
+pub enum StressError {
+    #[error("No trading session in the replay to inject {0} into")]
+    NoSession(String),
+    #[error("Session bounds of {date}: {source}")]
+    Session { date: NaiveDate, source: TimestampError },
+}
+
+/// Synthetic price shock
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Shock {
+    /// Every price from the shock onwards shifted by `points`
+    Gap { points: Decimal },
+    /// Prices jump by `move_pct` (e.g. -0.07 for limit down), then trading
+    /// halts for `halt_mins` and resumes at the shifted level
+    LimitMove { move_pct: f64, halt_mins: u32 },
+    /// Prices fall by `drop_pct` over `fall_secs`, then recover linearly over
+    /// `recovery_secs`
+    FlashCrash { drop_pct: f64, fall_secs: u32, recovery_secs: u32 },
+}
+
+/// A shock and the moment it is injected at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StressScenario {
+    pub name: String,
+    /// Trade date of the session to shock; the first session replayed when absent
+    #[serde(default)]
+    pub date: Option<NaiveDate>,
+    /// Minutes after the session open the shock starts
+    #[serde(default)]
+    pub after_open_mins: u32,
+    pub shock: Shock,
+}
+
+impl StressScenario {
+    pub fn new(name: impl Into<String>, shock: Shock) -> Self {
+        Self {
+            name: name.into(),
+            date: None,
+            after_open_mins: 0,
+            shock,
+        }
+    }
+
+    /// Opening gap of `points` at the session open
+    pub fn opening_gap(points: Decimal) -> Self {
+        Self::new(format!("Opening gap {}", points), Shock::Gap { points })
+    }
+
+    pub fn limit_move(move_pct: f64, halt_mins: u32) -> Self {
+        let direction = if move_pct < 0.0 { "down" } else { "up" };
+        Self::new(
+            format!("Limit {} {:.1}%", direction, move_pct.abs() * 100.0),
+            Shock::LimitMove { move_pct, halt_mins },
+        )
+    }
+
+    pub fn flash_crash(drop_pct: f64, fall_secs: u32, recovery_secs: u32) -> Self {
+        Self::new(
+            format!("Flash crash {:.1}%", drop_pct * 100.0),
+            Shock::FlashCrash { drop_pct, fall_secs, recovery_secs },
+        )
+    }
+
+    pub fn on(mut self, date: NaiveDate) -> Self {
+        self.date = Some(date);
+        self
+    }
+
+    pub fn after_open(mut self, minutes: u32) -> Self {
+        self.after_open_mins = minutes;
+        self
+    }
+
+    /// Copy of `ticks` with the shock applied, and the number of ticks changed or dropped
+    ///
+    /// `default_date` is the session used when the scenario names none.
+    pub fn inject(
+        &self,
+        ticks: &[TickData],
+        calendar: &SessionCalendar,
+        default_date: Option<NaiveDate>,
+        tick_size: Decimal,
+    ) -> Result<(Vec<TickData>, usize), StressError> {
+        let date = self.date.or(default_date)
+            .ok_or_else(|| StressError::NoSession(self.name.clone()))?;
+        let (open, _) = calendar.session_bounds(date)
+            .map_err(|source| StressError::Session { date, source })?;
+        let start = (open + Duration::minutes(self.after_open_mins as i64)).nanos();
+
+        let mut affected = 0;
+        let mut shocked = Vec::with_capacity(ticks.len());
+        for tick in ticks {
+            let elapsed = tick.timestamp - start;
+            if elapsed < 0 {
+                shocked.push(tick.clone());
+                continue;
+            }
+
+            let price = match &self.shock {
+                Shock::Gap { points } => Some(tick.price + points),
+                Shock::LimitMove { move_pct, halt_mins } => {
+                    let halted = elapsed < *halt_mins as i64 * 60_000_000_000;
+                    (!halted).then(|| scale(tick.price, *move_pct, tick_size))
+                }
+                Shock::FlashCrash { drop_pct, fall_secs, recovery_secs } => {
+                    let depth = crash_depth(elapsed, *fall_secs, *recovery_secs);
+                    if depth == 0.0 {
+                        shocked.push(tick.clone());
+                        continue;
+                    }
+                    Some(scale(tick.price, -drop_pct * depth, tick_size))
+                }
+            };
+
+            affected += 1;
+            if let Some(price) = price {
+                let mut tick = tick.clone();
+                tick.price = price;
+                shocked.push(tick);
+            }
+        }
+
+        Ok((shocked, affected))
+    }
+}
+
+/// Share of the full drop reached `elapsed_nanos` into a flash crash
+fn crash_depth(elapsed_nanos: i64, fall_secs: u32, recovery_secs: u32) -> f64 {
+    let secs = elapsed_nanos as f64 / 1e9;
+    let fall = fall_secs as f64;
+    let recovery = recovery_secs as f64;
+    if secs < fall {
+        secs / fall
+    } else if secs < fall + recovery {
+        1.0 - (secs - fall) / recovery
+    } else {
+        0.0
+    }
+}
+
+/// `price` moved by `pct`, rounded to the tick grid
+fn scale(price: Decimal, pct: f64, tick_size: Decimal) -> Decimal {
+    let factor = Decimal::from_f64_retain(1.0 + pct).unwrap_or(Decimal::ONE);
+    ((price * factor) / tick_size).round() * tick_size
+}
+
+/// How a strategy fared under one scenario
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StressOutcome {
+    pub scenario: StressScenario,
+    /// Ticks repriced or dropped by the shock
+    pub ticks_affected: usize,
+    pub total_pnl: Decimal,
+    /// P&L relative to the baseline replay
+    pub pnl_impact: Decimal,
+    pub max_drawdown: Decimal,
+    pub total_trades: u32,
+    pub margin_rejections: u32,
+    pub peak_margin_utilization: f64,
+}
+
+impl StressOutcome {
+    fn from_result(
+        scenario: StressScenario,
+        ticks_affected: usize,
+        result: &BacktestResult,
+        baseline: &BacktestResult,
+    ) -> Self {
+        Self {
+            scenario,
+            ticks_affected,
+            total_pnl: result.total_pnl,
+            pnl_impact: result.total_pnl - baseline.total_pnl,
+            max_drawdown: result.max_drawdown,
+            total_trades: result.total_trades,
+            margin_rejections: result.margin.rejected_orders,
+            peak_margin_utilization: result.margin.max_utilization,
+        }
+    }
+}
+
+/// Baseline and per-scenario results of a stress test
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StressReport {
+    pub baseline_pnl: Decimal,
+    pub baseline_max_drawdown: Decimal,
+    pub outcomes: Vec<StressOutcome>,
+}
+
+impl StressReport {
+    /// Scenario with the largest P&L loss against the baseline
+    pub fn worst_case(&self) -> Option<&StressOutcome> {
+        self.outcomes.iter().min_by_key(|outcome| outcome.pnl_impact)
+    }
+
+    /// Largest P&L loss against the baseline across scenarios, zero if none lost
+    pub fn worst_case_loss(&self) -> Decimal {
+        self.worst_case()
+            .map_or(Decimal::ZERO, |outcome| (-outcome.pnl_impact).max(Decimal::ZERO))
+    }
+}
+
+/// Runs a strategy over a dataset once unshocked and once per scenario
+pub struct StressTester {
+    config: BacktestConfig,
+    scenarios: Vec<StressScenario>,
+    tick_size: Decimal,
+}
+
+impl StressTester {
+    pub fn new(config: BacktestConfig) -> Self {
+        Self {
+            config,
+            scenarios: Vec::new(),
+            tick_size: DEFAULT_TICK_SIZE,
+        }
+    }
+
+    pub fn with_scenario(mut self, scenario: StressScenario) -> Self {
+        self.scenarios.push(scenario);
+        self
+    }
+
+    /// Price increment shocked prices are rounded to
+    pub fn with_tick_size(mut self, tick_size: Decimal) -> Self {
+        self.tick_size = tick_size;
+        self
+    }
+
+    /// Replay `data_path` with each scenario, building a fresh strategy per run
+    pub async fn run<S, F>(
+        &self,
+        make_strategy: F,
+        data_path: impl AsRef<Path>,
+    ) -> Result<StressReport, Box<dyn std::error::Error>>
+    where
+        S: Strategy,
+        F: Fn() -> S,
+    {
+        let data_path = data_path.as_ref();
+        let ticks = read_ticks(data_path, None, self.config.batch_size).await?;
+        let baseline = BacktestEngine::new(self.config.clone())
+            .run_loaded(&mut make_strategy(), data_path, &ticks)?;
+
+        let calendar = &self.config.session_calendar;
+        let start = Timestamp::from(self.config.start_date).nanos();
+        let first_session = ticks.iter()
+            .filter(|tick| tick.timestamp >= start)
+            .find_map(|tick| calendar.trading_date(Timestamp::from_nanos(tick.timestamp)));
+
+        let mut outcomes = Vec::with_capacity(self.scenarios.len());
+        for scenario in &self.scenarios {
+            let (shocked, affected) = scenario.inject(&ticks, calendar, first_session, self.tick_size)?;
+            let result = BacktestEngine::new(self.config.clone())
+                .run_loaded(&mut make_strategy(), data_path, &shocked)?;
+            info!("Stress scenario {}: P&L {} ({} vs baseline)",
+                scenario.name, result.total_pnl, result.total_pnl - baseline.total_pnl);
+            outcomes.push(StressOutcome::from_result(scenario.clone(), affected, &result, &baseline));
+        }
+
+        Ok(StressReport {
+            baseline_pnl: baseline.total_pnl,
+            baseline_max_drawdown: baseline.max_drawdown,
+            outcomes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::test_support::trade;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_shocks_start_at_the_session_open() {
+        let calendar = SessionCalendar::cme_equity_futures();
+        let date = NaiveDate::from_ymd_opt(2024, 6, 12).unwrap();
+        let (open, _) = calendar.session_bounds(date).unwrap();
+        let at = |secs: i64| (open + Duration::seconds(secs)).nanos();
+        let ticks = vec![
+            trade("18000.00", 1, at(-60)),
+            trade("18000.00", 1, at(30)),
+            trade("18000.00", 1, at(120)),
+            trade("18000.00", 1, at(900)),
+        ];
+
+        let gap = StressScenario::opening_gap(Decimal::from(-150));
+        let (shocked, affected) = gap.inject(&ticks, &calendar, Some(date), DEFAULT_TICK_SIZE).unwrap();
+        assert_eq!(affected, 3);
+        assert_eq!(shocked[0].price, Decimal::from(18000));
+        assert_eq!(shocked[3].price, Decimal::from(17850));
+
+        // The halt swallows the first ten minutes after the move
+        let limit = StressScenario::limit_move(-0.07, 10);
+        let (shocked, _) = limit.inject(&ticks, &calendar, Some(date), DEFAULT_TICK_SIZE).unwrap();
+        assert_eq!(shocked.len(), 2);
+        assert_eq!(shocked[1].price, Decimal::from(16740));
+
+        // Bottom of the crash at 60s, fully recovered by 180s
+        let crash = StressScenario::flash_crash(0.05, 60, 120);
+        let (shocked, affected) = crash.inject(&ticks, &calendar, Some(date), DEFAULT_TICK_SIZE).unwrap();
+        assert_eq!(affected, 2);
+        assert_eq!(shocked[1].price, Decimal::from_str("17550.00").unwrap());
+        assert_eq!(shocked[3].price, Decimal::from(18000));
+
+        assert!(gap.inject(&ticks, &calendar, None, DEFAULT_TICK_SIZE).is_err());
+    }
+}
@@ -0,0 +1,328 @@
+//! Write-ahead log of in-progress backtests
+//!
+//! A backtest with a WAL appends every fill and a periodic metric checkpoint
+//! to `<dir>/<run_id>.wal` as it runs, one JSON record per line. If the
+//! process dies mid-run the log survives, and `RecoveredBacktest::read`
+//! turns it back into the fills and progress made so far. A run that
+//! finishes removes its log, so any log left in the directory belongs to an
+//! interrupted run.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use crate::strategy::traits::OrderFill;
+
+/// File extension of backtest logs
+pub const WAL_EXTENSION: &str = "wal";
+
+/// Errors raised writing or reading a backtest log
+#[derive(Debug, thiserror::Error)]
+pub enum WalError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("Log {0} does not start with a run header")]
+    MissingHeader(PathBuf),
+}
+
+/// Where and how often a backtest is logged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalConfig {
+    pub dir: PathBuf,
+    /// Identifies the run, normally the id of the job running it
+    pub run_id: String,
+    /// Ticks between metric checkpoints
+    #[serde(default = "default_checkpoint_ticks")]
+    pub checkpoint_ticks: usize,
+}
+
+fn default_checkpoint_ticks() -> usize {
+    100_000
+}
+
+impl WalConfig {
+    pub fn new(dir: impl Into<PathBuf>, run_id: impl Into<String>) -> Self {
+        Self {
+            dir: dir.into(),
+            run_id: run_id.into(),
+            checkpoint_ticks: default_checkpoint_ticks(),
+        }
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(format!("{}.{}", self.run_id, WAL_EXTENSION))
+    }
+}
+
+/// What a run set out to do
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalHeader {
+    pub run_id: String,
+    pub strategy: String,
+    pub data_path: String,
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    /// Ticks inside the date range
+    pub total_ticks: usize,
+    pub started_at: DateTime<Utc>,
+}
+
+/// Progress and metrics at a point in the run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalCheckpoint {
+    pub ticks_processed: usize,
+    /// Timestamp of the last processed tick, nanoseconds since epoch
+    pub last_timestamp: i64,
+    pub equity: Decimal,
+    pub capital: Decimal,
+    pub total_pnl: Decimal,
+    pub total_trades: u32,
+    pub position: i32,
+    pub written_at: DateTime<Utc>,
+}
+
+/// One line of the log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+pub enum WalRecord {
+    Started(WalHeader),
+    Fill {
+        /// Index of the tick the fill happened on, within the date range
+        tick_index: usize,
+        fill: OrderFill,
+    },
+    Checkpoint(WalCheckpoint),
+}
+
+/// Appends records for one run
+///
+/// Fills are handed to the OS as soon as they happen, which survives the
+/// process dying; checkpoints are also synced to disk, which survives the
+/// host going down.
+pub struct BacktestWal {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl BacktestWal {
+    /// Start a log for a run, replacing any earlier log with the same id
+    pub fn create(config: &WalConfig, header: WalHeader) -> Result<Self, WalError> {
+        fs::create_dir_all(&config.dir)?;
+        let path = config.path();
+        let file = OpenOptions::new().create(true).write(true).truncate(true).open(&path)?;
+
+        let mut wal = Self { path, writer: BufWriter::new(file) };
+        wal.append(&WalRecord::Started(header))?;
+        wal.sync()?;
+        Ok(wal)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record_fill(&mut self, tick_index: usize, fill: &OrderFill) -> Result<(), WalError> {
+        self.append(&WalRecord::Fill { tick_index, fill: fill.clone() })
+    }
+
+    pub fn checkpoint(&mut self, checkpoint: WalCheckpoint) -> Result<(), WalError> {
+        self.append(&WalRecord::Checkpoint(checkpoint))?;
+        self.sync()
+    }
+
+    /// Remove the log of a run that completed
+    pub fn finish(self) -> Result<(), WalError> {
+        let Self { path, writer } = self;
+        drop(writer);
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    fn append(&mut self, record: &WalRecord) -> Result<(), WalError> {
+        serde_json::to_writer(&mut self.writer, record)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn sync(&mut self) -> Result<(), WalError> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_data()?;
+        Ok(())
+    }
+}
+
+/// What an interrupted run got through
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveredBacktest {
+    pub header: WalHeader,
+    pub fills: Vec<OrderFill>,
+    /// Tick index of the last fill, when later than the last checkpoint
+    pub last_fill_tick: Option<usize>,
+    pub last_checkpoint: Option<WalCheckpoint>,
+    /// Whether the log ended in a partly written record
+    pub torn_tail: bool,
+}
+
+impl RecoveredBacktest {
+    /// Read a log, keeping every record before the first unreadable one
+    pub fn read(path: &Path) -> Result<Self, WalError> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut lines = reader.lines();
+
+        let header = match lines.next().transpose()?.map(|line| serde_json::from_str(&line)) {
+            Some(Ok(WalRecord::Started(header))) => header,
+            _ => return Err(WalError::MissingHeader(path.to_path_buf())),
+        };
+
+        let mut recovered = Self {
+            header,
+            fills: Vec::new(),
+            last_fill_tick: None,
+            last_checkpoint: None,
+            torn_tail: false,
+        };
+        for line in lines {
+            // The process can die halfway through a line; stop there
+            let record = match line.map(|line| serde_json::from_str::<WalRecord>(&line)) {
+                Ok(Ok(record)) => record,
+                Ok(Err(_)) | Err(_) => {
+                    recovered.torn_tail = true;
+                    break;
+                }
+            };
+            match record {
+                WalRecord::Started(_) => {}
+                WalRecord::Fill { tick_index, fill } => {
+                    recovered.last_fill_tick = Some(tick_index);
+                    recovered.fills.push(fill);
+                }
+                WalRecord::Checkpoint(checkpoint) => {
+                    recovered.last_fill_tick = None;
+                    recovered.last_checkpoint = Some(checkpoint);
+                }
+            }
+        }
+        Ok(recovered)
+    }
+
+    /// Logs of interrupted runs in `dir`
+    pub fn scan(dir: &Path) -> Result<Vec<(PathBuf, Result<Self, WalError>)>, WalError> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut logs = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) == Some(WAL_EXTENSION) {
+                let recovered = Self::read(&path);
+                logs.push((path, recovered));
+            }
+        }
+        logs.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(logs)
+    }
+
+    /// Ticks known to have been processed
+    pub fn ticks_processed(&self) -> usize {
+        let checkpointed = self.last_checkpoint.as_ref().map_or(0, |c| c.ticks_processed);
+        self.last_fill_tick.map_or(checkpointed, |tick| checkpointed.max(tick + 1))
+    }
+
+    /// Share of the run's ticks processed, from 0 to 1
+    pub fn progress(&self) -> f64 {
+        if self.header.total_ticks == 0 {
+            return 0.0;
+        }
+        (self.ticks_processed() as f64 / self.header.total_ticks as f64).min(1.0)
+    }
+
+    /// One-line account of how far the run got
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "interrupted after {} of {} ticks ({:.1}%), {} fills",
+            self.ticks_processed(),
+            self.header.total_ticks,
+            self.progress() * 100.0,
+            self.fills.len()
+        );
+        if let Some(checkpoint) = &self.last_checkpoint {
+            summary.push_str(&format!(", P&L {} over {} trades at the last checkpoint",
+                checkpoint.total_pnl, checkpoint.total_trades));
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::OrderSide;
+    use std::io::Write;
+
+    fn fill(order_id: &str) -> OrderFill {
+        OrderFill {
+            order_id: order_id.to_string(),
+            timestamp: Utc::now(),
+            price: Decimal::new(1850025, 2),
+            quantity: 1,
+            side: OrderSide::Buy,
+            commission: Decimal::new(52, 2),
+            slippage: Decimal::ZERO,
+            tag: None,
+            position_effect: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_recovers_records_before_torn_tail() {
+        let dir = std::env::temp_dir().join(format!("backtest_wal_{}", uuid::Uuid::new_v4()));
+        let config = WalConfig::new(&dir, "job-1");
+        let header = WalHeader {
+            run_id: "job-1".to_string(),
+            strategy: "Order Book Imbalance".to_string(),
+            data_path: "mnq_20240612.parquet".to_string(),
+            start_date: Utc::now(),
+            end_date: Utc::now(),
+            total_ticks: 1_000,
+            started_at: Utc::now(),
+        };
+
+        let mut wal = BacktestWal::create(&config, header).unwrap();
+        wal.record_fill(10, &fill("a")).unwrap();
+        wal.checkpoint(WalCheckpoint {
+            ticks_processed: 400,
+            last_timestamp: 0,
+            equity: Decimal::new(10_000, 0),
+            capital: Decimal::new(10_000, 0),
+            total_pnl: Decimal::new(25, 0),
+            total_trades: 1,
+            position: 0,
+            written_at: Utc::now(),
+        }).unwrap();
+        wal.record_fill(450, &fill("b")).unwrap();
+        drop(wal);
+
+        // Simulate dying in the middle of a write
+        let mut file = OpenOptions::new().append(true).open(config.path()).unwrap();
+        file.write_all(b"{\"record\":\"fill\",\"tick_in").unwrap();
+
+        let recovered = RecoveredBacktest::read(&config.path()).unwrap();
+        assert_eq!(recovered.fills.len(), 2);
+        assert!(recovered.torn_tail);
+        assert_eq!(recovered.ticks_processed(), 451);
+        assert!((recovered.progress() - 0.451).abs() < 1e-9);
+
+        let logs = RecoveredBacktest::scan(&dir).unwrap();
+        assert_eq!(logs.len(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,290 @@
+//! Warm engine sessions for interactive iteration
+//!
+//! Tweaking a parameter and re-running from the frontend should feel
+//! immediate, but every fresh backtest reads, parses and range-filters its
+//! data before the first tick is processed. A `WarmSession` does that once:
+//! the ticks of a chosen range stay resident, and each run builds a fresh
+//! engine over them, so a run starts as soon as it is asked for. Sessions
+//! are held by a `WarmSessions` registry that caps how many stay resident
+//! and closes idle ones.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::backtesting::engine::read_ticks;
+use crate::backtesting::{BacktestConfig, BacktestEngine, BacktestResult};
+use crate::data::TickData;
+use crate::performance::warm_cache::DatasetCache;
+use crate::strategy::Strategy;
+
+/// Sessions kept resident unless configured otherwise
+pub const DEFAULT_MAX_SESSIONS: usize = 4;
+
+/// Minutes a session may go unused before it is closed
+pub const DEFAULT_MAX_IDLE_MINUTES: i64 = 30;
+
+/// Errors raised by warm sessions
+#[derive(Debug, thiserror::Error)]
+pub enum WarmSessionError {
+    #[error("Warm session not found: {0}")]
+    NotFound(String),
+    #[error("At most {0} warm sessions can be open; close one first")]
+    TooManySessions(usize),
+    #[error("Failed to load {path}: {reason}")]
+    Load { path: String, reason: String },
+    #[error("Backtest failed: {0}")]
+    Run(String),
+}
+
+/// Summary of an open session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmSessionInfo {
+    pub id: String,
+    pub workspace_id: String,
+    pub data_path: String,
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    /// Resident ticks inside the session's range
+    pub ticks: usize,
+    pub load_ms: u64,
+    pub opened_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub runs: u64,
+}
+
+/// One run over a session's resident data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmRun {
+    pub result: BacktestResult,
+    /// Time from the request to the first tick, in milliseconds
+    pub start_ms: f64,
+    pub run_ms: f64,
+}
+
+/// Ticks of one range held in memory, with the engine configuration to run them
+///
+/// Cheap to clone; clones share the resident ticks.
+#[derive(Debug, Clone)]
+pub struct WarmSession {
+    data_path: PathBuf,
+    config: BacktestConfig,
+    ticks: Arc<Vec<TickData>>,
+    load_ms: u64,
+}
+
+impl WarmSession {
+    /// Read `data_path` and keep the ticks inside the configured date range
+    ///
+    /// Reads go through `cache` when given, so a dataset already warmed for
+    /// another run is not parsed again.
+    pub async fn open(
+        data_path: impl Into<PathBuf>,
+        config: BacktestConfig,
+        cache: Option<&DatasetCache>,
+    ) -> Result<Self, WarmSessionError> {
+        let data_path = data_path.into();
+        let load_start = Instant::now();
+        let all = read_ticks(&data_path, cache, config.batch_size).await
+            .map_err(|e| WarmSessionError::Load {
+                path: data_path.display().to_string(),
+                reason: e.to_string(),
+            })?;
+        let ticks = Arc::new(BacktestEngine::new(config.clone()).in_range(&all).into_owned());
+        let load_ms = load_start.elapsed().as_millis() as u64;
+        info!("Warm session holds {} ticks of {} ({}ms to load)", ticks.len(), data_path.display(), load_ms);
+
+        Ok(Self { data_path, config, ticks, load_ms })
+    }
+
+    pub fn config(&self) -> &BacktestConfig {
+        &self.config
+    }
+
+    pub fn ticks(&self) -> usize {
+        self.ticks.len()
+    }
+
+    pub fn data_path(&self) -> &Path {
+        &self.data_path
+    }
+
+    /// The resident ticks, shared rather than copied
+    pub fn resident_ticks(&self) -> Arc<Vec<TickData>> {
+        Arc::clone(&self.ticks)
+    }
+
+    /// Run a strategy over the resident ticks with the session's configuration
+    pub fn run<S: Strategy>(&self, strategy: &mut S) -> Result<WarmRun, WarmSessionError> {
+        self.run_with_config(strategy, self.config.clone())
+    }
+
+    /// Run with a different engine configuration, e.g. other costs
+    ///
+    /// Only the resident ticks are available, so a date range wider than
+    /// the session's is cut to it.
+    pub fn run_with_config<S: Strategy>(&self, strategy: &mut S, config: BacktestConfig) -> Result<WarmRun, WarmSessionError> {
+        let requested = Instant::now();
+        let mut engine = BacktestEngine::new(config);
+        let start_ms = requested.elapsed().as_secs_f64() * 1000.0;
+        let result = engine.run_loaded(strategy, &self.data_path, &self.ticks)
+            .map_err(|e| WarmSessionError::Run(e.to_string()))?;
+        Ok(WarmRun {
+            result,
+            start_ms,
+            run_ms: requested.elapsed().as_secs_f64() * 1000.0 - start_ms,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    session: WarmSession,
+    info: WarmSessionInfo,
+}
+
+/// Open warm sessions, shared by the API and its jobs
+#[derive(Debug, Clone)]
+pub struct WarmSessions {
+    inner: Arc<RwLock<HashMap<String, Entry>>>,
+    max_sessions: usize,
+}
+
+impl Default for WarmSessions {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_SESSIONS)
+    }
+}
+
+impl WarmSessions {
+    pub fn new(max_sessions: usize) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(HashMap::new())),
+            max_sessions,
+        }
+    }
+
+    /// Keep a loaded session resident, returning its summary
+    pub fn insert(&self, workspace_id: &str, session: WarmSession) -> Result<WarmSessionInfo, WarmSessionError> {
+        let mut sessions = self.inner.write().unwrap();
+        if sessions.len() >= self.max_sessions {
+            return Err(WarmSessionError::TooManySessions(self.max_sessions));
+        }
+        let now = Utc::now();
+        let info = WarmSessionInfo {
+            id: Uuid::new_v4().to_string(),
+            workspace_id: workspace_id.to_string(),
+            data_path: session.data_path.display().to_string(),
+            start_date: session.config.start_date,
+            end_date: session.config.end_date,
+            ticks: session.ticks(),
+            load_ms: session.load_ms,
+            opened_at: now,
+            last_used_at: now,
+            runs: 0,
+        };
+        sessions.insert(info.id.clone(), Entry { session, info: info.clone() });
+        Ok(info)
+    }
+
+    /// Whether another session can be opened
+    pub fn has_capacity(&self) -> bool {
+        self.inner.read().unwrap().len() < self.max_sessions
+    }
+
+    /// A session of the workspace, counted as used
+    pub fn checkout(&self, workspace_id: &str, id: &str) -> Result<WarmSession, WarmSessionError> {
+        let mut sessions = self.inner.write().unwrap();
+        let entry = sessions.get_mut(id)
+            .filter(|entry| entry.info.workspace_id == workspace_id)
+            .ok_or_else(|| WarmSessionError::NotFound(id.to_string()))?;
+        entry.info.last_used_at = Utc::now();
+        entry.info.runs += 1;
+        Ok(entry.session.clone())
+    }
+
+    pub fn get(&self, workspace_id: &str, id: &str) -> Option<WarmSessionInfo> {
+        self.inner.read().unwrap().get(id)
+            .filter(|entry| entry.info.workspace_id == workspace_id)
+            .map(|entry| entry.info.clone())
+    }
+
+    /// Sessions of a workspace, oldest first
+    pub fn list(&self, workspace_id: &str) -> Vec<WarmSessionInfo> {
+        let mut sessions: Vec<_> = self.inner.read().unwrap().values()
+            .filter(|entry| entry.info.workspace_id == workspace_id)
+            .map(|entry| entry.info.clone())
+            .collect();
+        sessions.sort_by_key(|info| info.opened_at);
+        sessions
+    }
+
+    /// Close a session, releasing its ticks once running backtests finish
+    pub fn close(&self, workspace_id: &str, id: &str) -> bool {
+        let mut sessions = self.inner.write().unwrap();
+        if sessions.get(id).is_some_and(|entry| entry.info.workspace_id == workspace_id) {
+            sessions.remove(id);
+            return true;
+        }
+        false
+    }
+
+    /// Close sessions unused for longer than `max_idle`, returning how many
+    pub fn close_idle(&self, max_idle: Duration) -> usize {
+        let cutoff = Utc::now() - max_idle;
+        let mut sessions = self.inner.write().unwrap();
+        let before = sessions.len();
+        sessions.retain(|_, entry| entry.info.last_used_at >= cutoff);
+        before - sessions.len()
+    }
+
+    /// Close sessions idle for longer than `max_idle`, checking every minute, forever
+    pub async fn run_idle_reaper(self, max_idle: Duration) {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            let closed = self.close_idle(max_idle);
+            if closed > 0 {
+                info!("Closed {} warm sessions idle for over {} minutes", closed, max_idle.num_minutes());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(ticks: usize) -> WarmSession {
+        WarmSession {
+            data_path: PathBuf::from("data/MNQ/06-24/20240612.parquet"),
+            config: BacktestConfig::default(),
+            ticks: Arc::new(Vec::with_capacity(ticks)),
+            load_ms: 120,
+        }
+    }
+
+    #[test]
+    fn test_sessions_are_capped_scoped_and_closed_when_idle() {
+        let sessions = WarmSessions::new(2);
+        let first = sessions.insert("desk", session(0)).unwrap();
+        let second = sessions.insert("other", session(0)).unwrap();
+        assert!(matches!(sessions.insert("desk", session(0)), Err(WarmSessionError::TooManySessions(2))));
+        assert!(!sessions.has_capacity());
+
+        assert!(sessions.checkout("other", &first.id).is_err());
+        sessions.checkout("desk", &first.id).unwrap();
+        assert_eq!(sessions.get("desk", &first.id).unwrap().runs, 1);
+        assert_eq!(sessions.list("desk").len(), 1);
+
+        assert!(!sessions.close("desk", &second.id));
+        assert_eq!(sessions.close_idle(Duration::hours(1)), 0);
+        assert_eq!(sessions.close_idle(Duration::milliseconds(-1)), 2);
+        assert!(sessions.has_capacity());
+    }
+}
@@ -14,6 +14,7 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use tower_http::cors::CorsLayer;
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
@@ -327,8 +328,8 @@ async fn main() {
     let addr = "0.0.0.0:8001";
     println!("API Server listening on http://{}", addr);
     
-    axum::Server::bind(&addr.parse().unwrap())
-        .serve(app.into_make_service())
+    axum::Server::bind(&addr.parse::<SocketAddr>().unwrap())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .unwrap();
 }
\ No newline at end of file
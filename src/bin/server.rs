@@ -1,15 +1,12 @@
 //! Strategy Lab Server
 
 use strategy_lab::api;
-use tracing::Level;
-use tracing_subscriber;
+use strategy_lab::telemetry::{self, TelemetryConfig};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_max_level(Level::INFO)
-        .init();
+    // Initialize logging, and span export when OTEL_EXPORTER_OTLP_ENDPOINT is set
+    let _telemetry = telemetry::init(&TelemetryConfig::from_env())?;
     
     println!("🚀 Strategy Lab Server v{}", strategy_lab::VERSION);
     println!("Starting API server on port 8080...");
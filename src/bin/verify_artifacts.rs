@@ -0,0 +1,48 @@
+//! Verify saved run manifests
+//!
+//! Re-hashes the files recorded in each manifest given on the command line
+//! and reports any that changed or disappeared. Exits non-zero on failure.
+//! The server writes a manifest per finished backtest to
+//! `STRATEGY_LAB_MANIFEST_DIR` (`data/manifests` by default).
+
+use strategy_lab::lineage::{DigestStatus, RunManifest};
+
+fn main() {
+    let paths: Vec<String> = std::env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("usage: verify_artifacts <manifest.json>...");
+        std::process::exit(2);
+    }
+
+    let mut failed = false;
+    for path in &paths {
+        let manifest = match RunManifest::load(path) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                eprintln!("{}: could not read manifest: {}", path, e);
+                failed = true;
+                continue;
+            }
+        };
+
+        let report = manifest.verify_files();
+        for check in &report.checks {
+            match &check.status {
+                DigestStatus::Intact => println!("ok        {}", check.name),
+                DigestStatus::Modified { actual } => {
+                    println!("MODIFIED  {} (expected {}, found {})", check.name, check.expected, actual)
+                }
+                DigestStatus::Missing => println!("MISSING   {}", check.name),
+            }
+        }
+        if !report.is_intact() {
+            failed = true;
+        }
+        println!("{}: {} of {} files intact", manifest.run_id,
+            report.checks.len() - report.failures().count(), report.checks.len());
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
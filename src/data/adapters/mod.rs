@@ -0,0 +1,161 @@
+//! Ingestion adapters for vendor tick formats
+//!
+//! Many traders already hold years of ticks in their charting platform's own
+//! files. Each adapter reads one vendor format into `TickData` through the
+//! common `TickSource` trait, so those files go through validation,
+//! normalization and cataloging like any other source instead of being
+//! re-downloaded.
+
+pub mod ninjatrader;
+pub mod sierra;
+
+pub use ninjatrader::NinjaTraderReader;
+pub use sierra::SierraChartReader;
+
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use crate::data::{MarketDataType, TickData};
+use crate::timestamp::EXCHANGE_TZ;
+
+/// Errors raised reading vendor tick files
+#[derive(Debug, thiserror::Error)]
+pub enum AdapterError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid header: {0}")]
+    InvalidHeader(String),
+    #[error("Truncated record at byte {0}")]
+    Truncated(u64),
+    #[error("Line {line}: {reason}")]
+    Parse { line: usize, reason: String },
+    #[error("Cannot tell the contract month of {0:?}; pass it explicitly")]
+    UnknownContract(String),
+    #[error("Unsupported file {0:?}")]
+    UnsupportedFormat(String),
+}
+
+/// A stream of ticks from some source, oldest first
+pub trait TickSource {
+    /// Vendor name, for logs and ingestion summaries
+    fn vendor(&self) -> &'static str;
+
+    /// Next tick, or `None` once the source is exhausted
+    fn next_tick(&mut self) -> Result<Option<TickData>, AdapterError>;
+
+    /// Every remaining tick
+    fn read_all(&mut self) -> Result<Vec<TickData>, AdapterError> {
+        let mut ticks = Vec::new();
+        while let Some(tick) = self.next_tick()? {
+            ticks.push(tick);
+        }
+        Ok(ticks)
+    }
+}
+
+/// How vendor files are turned into ticks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdapterOptions {
+    /// Contract month in `MMYY` form; taken from the file name when unset
+    #[serde(default)]
+    pub contract_month: Option<String>,
+    /// Zone of text exports' wall-clock timestamps; binary formats are UTC
+    #[serde(default = "default_zone")]
+    pub zone: Tz,
+    /// Prices stored as floats are rounded to this increment
+    #[serde(default = "default_tick_size")]
+    pub tick_size: Decimal,
+    /// Also emit the bid and ask recorded alongside each trade
+    #[serde(default)]
+    pub include_quotes: bool,
+}
+
+fn default_zone() -> Tz {
+    EXCHANGE_TZ
+}
+
+fn default_tick_size() -> Decimal {
+    Decimal::new(25, 2)
+}
+
+impl Default for AdapterOptions {
+    fn default() -> Self {
+        Self {
+            contract_month: None,
+            zone: default_zone(),
+            tick_size: default_tick_size(),
+            include_quotes: false,
+        }
+    }
+}
+
+/// Open a vendor file as a tick source, picking the adapter by extension
+///
+/// `.scid` files are read as Sierra Chart intraday data; `.txt` and `.csv`
+/// as NinjaTrader exports, where a `.Bid.` or `.Ask.` in the name marks a
+/// quote export.
+pub fn open_tick_source(path: impl AsRef<Path>, options: &AdapterOptions) -> Result<Box<dyn TickSource>, AdapterError> {
+    let path = path.as_ref();
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default().to_string();
+    let contract_month = match &options.contract_month {
+        Some(contract_month) => contract_month.clone(),
+        None => contract_month_from_symbol(&name).ok_or_else(|| AdapterError::UnknownContract(name.clone()))?,
+    };
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default().to_ascii_lowercase();
+    let reader = BufReader::new(File::open(path)?);
+
+    match extension.as_str() {
+        "scid" => Ok(Box::new(
+            SierraChartReader::new(reader, contract_month)?
+                .with_tick_size(options.tick_size)
+                .with_quotes(options.include_quotes),
+        )),
+        "txt" | "csv" => {
+            let data_type = if name.contains(".Bid.") {
+                MarketDataType::BidQuote
+            } else if name.contains(".Ask.") {
+                MarketDataType::AskQuote
+            } else {
+                MarketDataType::Trade
+            };
+            Ok(Box::new(
+                NinjaTraderReader::new(reader, contract_month, options.zone)
+                    .with_data_type(data_type)
+                    .with_quotes(options.include_quotes),
+            ))
+        }
+        _ => Err(AdapterError::UnsupportedFormat(name)),
+    }
+}
+
+/// Contract month in `MMYY` form from a vendor symbol
+///
+/// Understands month-code symbols such as `MNQM24` or `MNQM24-CME` (Sierra
+/// Chart) and `MNQ 06-24` (NinjaTrader), with or without a file extension.
+pub fn contract_month_from_symbol(symbol: &str) -> Option<String> {
+    let symbol = symbol.split('.').next()?.trim();
+
+    // NinjaTrader: "<root> MM-YY"
+    if let Some((_, expiry)) = symbol.rsplit_once(' ') {
+        let (month, year) = expiry.split_once('-')?;
+        let valid = month.len() == 2 && year.len() == 2
+            && month.chars().chain(year.chars()).all(|c| c.is_ascii_digit())
+            && (1..=12).contains(&month.parse::<u32>().ok()?);
+        return valid.then(|| format!("{}{}", month, year));
+    }
+
+    // Month code and two-digit year, before any exchange suffix
+    let code = symbol.split('-').next()?.as_bytes();
+    let [.., month, tens, units] = code else {
+        return None;
+    };
+    if code.len() < 4 || !tens.is_ascii_digit() || !units.is_ascii_digit() {
+        return None;
+    }
+    let month = b"FGHJKMNQUVXZ".iter().position(|c| c == month)? + 1;
+    Some(format!("{:02}{}{}", month, *tens as char, *units as char))
+}
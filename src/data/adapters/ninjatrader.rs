@@ -0,0 +1,173 @@
+//! NinjaTrader 8 tick exports
+//!
+//! The Historical Data window exports ticks as `;`-separated text, one tick
+//! per line, stamped in the wall-clock time of the zone the platform is set
+//! to. Tick exports with bid and ask read
+//! `yyyyMMdd HHmmss fffffff;last;bid;ask;volume`, where the fraction is in
+//! 100 ns units; plain exports read `yyyyMMdd HHmmss[ fffffff];price;volume`
+//! and carry the last price, or the bid or ask in `.Bid` / `.Ask` exports.
+//! The native `.ncd` cache files are undocumented and not read.
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+use std::io::BufRead;
+
+use super::{AdapterError, TickSource};
+use crate::data::{DataLevel, MarketDataType, TickData};
+use crate::timestamp::Timestamp;
+
+/// Reads ticks from a NinjaTrader text export
+pub struct NinjaTraderReader<R> {
+    lines: std::io::Lines<R>,
+    line: usize,
+    contract_month: String,
+    zone: Tz,
+    data_type: MarketDataType,
+    include_quotes: bool,
+    pending: VecDeque<TickData>,
+}
+
+impl<R: BufRead> NinjaTraderReader<R> {
+    pub fn new(reader: R, contract_month: impl Into<String>, zone: Tz) -> Self {
+        Self {
+            lines: reader.lines(),
+            line: 0,
+            contract_month: contract_month.into(),
+            zone,
+            data_type: MarketDataType::Trade,
+            include_quotes: false,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// What the price of a three-field line is; trades unless set
+    pub fn with_data_type(mut self, data_type: MarketDataType) -> Self {
+        self.data_type = data_type;
+        self
+    }
+
+    /// Also emit the bid and ask of five-field lines
+    pub fn with_quotes(mut self, include_quotes: bool) -> Self {
+        self.include_quotes = include_quotes;
+        self
+    }
+
+    fn parse_error(&self, reason: impl Into<String>) -> AdapterError {
+        AdapterError::Parse { line: self.line, reason: reason.into() }
+    }
+
+    /// `yyyyMMdd HHmmss[ fffffff]` in the export's zone, as UTC nanoseconds
+    fn parse_time(&self, field: &str) -> Result<i64, AdapterError> {
+        let mut parts = field.split_whitespace();
+        let (Some(date), Some(time)) = (parts.next(), parts.next()) else {
+            return Err(self.parse_error(format!("invalid time '{}'", field)));
+        };
+        let date = NaiveDate::parse_from_str(date, "%Y%m%d")
+            .map_err(|_| self.parse_error(format!("invalid date '{}'", date)))?;
+        let time = NaiveTime::parse_from_str(time, "%H%M%S")
+            .map_err(|_| self.parse_error(format!("invalid time '{}'", time)))?;
+        let ticks_100ns: i64 = match parts.next() {
+            Some(fraction) if fraction.len() <= 7 && fraction.chars().all(|c| c.is_ascii_digit()) => {
+                format!("{:0<7}", fraction).parse().unwrap_or(0)
+            }
+            Some(fraction) => return Err(self.parse_error(format!("invalid fraction '{}'", fraction))),
+            None => 0,
+        };
+
+        // Repeated wall-clock times at the end of DST resolve to the first
+        let local = NaiveDateTime::new(date, time);
+        let zoned = self.zone.from_local_datetime(&local).earliest()
+            .ok_or_else(|| self.parse_error(format!("{} does not exist in {}", local, self.zone)))?;
+        Ok(Timestamp::from_datetime(zoned).nanos() + ticks_100ns * 100)
+    }
+
+    fn parse_price(&self, field: &str) -> Result<Decimal, AdapterError> {
+        field.parse().map_err(|_| self.parse_error(format!("invalid price '{}'", field)))
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), AdapterError> {
+        let fields: Vec<&str> = line.split(';').map(str::trim).collect();
+        match fields[..] {
+            [time, price, volume] => {
+                let timestamp = self.parse_time(time)?;
+                let price = self.parse_price(price)?;
+                let volume = volume.parse().map_err(|_| self.parse_error(format!("invalid volume '{}'", volume)))?;
+                self.pending.push_back(self.tick(self.data_type, timestamp, price, volume));
+            }
+            [time, last, bid, ask, volume] => {
+                let timestamp = self.parse_time(time)?;
+                let last = self.parse_price(last)?;
+                let volume = volume.parse().map_err(|_| self.parse_error(format!("invalid volume '{}'", volume)))?;
+                if self.include_quotes {
+                    let bid = self.parse_price(bid)?;
+                    let ask = self.parse_price(ask)?;
+                    self.pending.push_back(self.tick(MarketDataType::BidQuote, timestamp, bid, 0));
+                    self.pending.push_back(self.tick(MarketDataType::AskQuote, timestamp, ask, 0));
+                }
+                self.pending.push_back(self.tick(MarketDataType::Trade, timestamp, last, volume));
+            }
+            [_, _, _, _, _, _] => return Err(self.parse_error("bar export; export ticks instead")),
+            _ => return Err(self.parse_error(format!("expected 3 or 5 fields, found {}", fields.len()))),
+        }
+        Ok(())
+    }
+
+    fn tick(&self, data_type: MarketDataType, timestamp: i64, price: Decimal, volume: i32) -> TickData {
+        TickData::new(DataLevel::L1, data_type, timestamp, price, volume, self.contract_month.clone())
+    }
+}
+
+impl<R: BufRead> TickSource for NinjaTraderReader<R> {
+    fn vendor(&self) -> &'static str {
+        "ninjatrader"
+    }
+
+    fn next_tick(&mut self) -> Result<Option<TickData>, AdapterError> {
+        loop {
+            if let Some(tick) = self.pending.pop_front() {
+                return Ok(Some(tick));
+            }
+            let Some(line) = self.lines.next() else {
+                return Ok(None);
+            };
+            let line = line?;
+            self.line += 1;
+            if !line.trim().is_empty() {
+                self.parse_line(&line)?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::adapters::contract_month_from_symbol;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_reads_tick_exports() {
+        let export = "20240612 083000 0025000;18950.25;18950;18950.25;3\n\n20240612 083000 0026000;18950.5;18950.25;18950.5;1\n";
+        let mut reader = NinjaTraderReader::new(Cursor::new(export), "0624", chrono_tz::America::Chicago);
+        let ticks = reader.read_all().unwrap();
+        assert_eq!(ticks.len(), 2);
+        // 08:30 CDT is 13:30 UTC; the fraction is in 100 ns units
+        assert_eq!(ticks[0].timestamp, 1_718_199_000_002_500_000);
+        assert_eq!((ticks[1].price, ticks[1].volume), (Decimal::new(189_505, 1), 1));
+
+        let quotes = NinjaTraderReader::new(Cursor::new("20240612 083000;18950;7\n"), "0624", chrono_tz::UTC)
+            .with_data_type(MarketDataType::BidQuote)
+            .read_all()
+            .unwrap();
+        assert_eq!(quotes[0].mdt, MarketDataType::BidQuote);
+
+        let mut bars = NinjaTraderReader::new(Cursor::new("20240612 083100;1;2;0.5;1.5;10\n"), "0624", chrono_tz::UTC);
+        assert!(matches!(bars.next_tick(), Err(AdapterError::Parse { line: 1, .. })));
+
+        assert_eq!(contract_month_from_symbol("MNQ 06-24.Last.txt").as_deref(), Some("0624"));
+        assert_eq!(contract_month_from_symbol("MNQZ24-CME.scid").as_deref(), Some("1224"));
+        assert_eq!(contract_month_from_symbol("MNQ.scid"), None);
+    }
+}
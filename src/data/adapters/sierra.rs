@@ -0,0 +1,206 @@
+//! Sierra Chart intraday data (`.scid`)
+//!
+//! A 56-byte header (`SCID`, header size, record size, version) is followed
+//! by fixed 40-byte little-endian records: a date-time in microseconds since
+//! 1899-12-30 UTC, open, high, low and close as `f32`, then the number of
+//! trades and the total, bid and ask volume as `u32`. In tick-by-tick files
+//! each record is one trade at the close, with the ask in the high and the
+//! bid in the low. Files stored at a coarser time unit hold one record per
+//! interval, which is read as a single trade at the close.
+
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Read};
+
+use super::{AdapterError, TickSource};
+use crate::data::{DataLevel, MarketDataType, TickData};
+
+const HEADER_ID: &[u8; 4] = b"SCID";
+const HEADER_SIZE: usize = 56;
+const RECORD_SIZE: usize = 40;
+
+/// Open value marking a record as a single trade with bid and ask
+const SINGLE_TRADE_WITH_BID_ASK: f32 = -1.999_001e37;
+
+/// Microseconds from the Sierra Chart epoch (1899-12-30) to the Unix epoch
+const UNIX_EPOCH_OFFSET_US: i64 = 25_569 * 86_400 * 1_000_000;
+
+/// Reads ticks from a `.scid` file
+pub struct SierraChartReader<R> {
+    reader: R,
+    contract_month: String,
+    record_size: usize,
+    tick_size: Decimal,
+    include_quotes: bool,
+    offset: u64,
+    pending: VecDeque<TickData>,
+}
+
+impl<R: Read> SierraChartReader<R> {
+    /// Read and check the header
+    pub fn new(mut reader: R, contract_month: impl Into<String>) -> Result<Self, AdapterError> {
+        let mut header = [0u8; HEADER_SIZE];
+        reader.read_exact(&mut header).map_err(|e| match e.kind() {
+            ErrorKind::UnexpectedEof => AdapterError::InvalidHeader("file is shorter than the header".to_string()),
+            _ => AdapterError::Io(e),
+        })?;
+        if &header[..4] != HEADER_ID {
+            return Err(AdapterError::InvalidHeader("missing SCID file id".to_string()));
+        }
+        let header_size = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+        let record_size = u32::from_le_bytes(header[8..12].try_into().unwrap()) as usize;
+        if header_size < HEADER_SIZE || record_size < RECORD_SIZE {
+            return Err(AdapterError::InvalidHeader(format!(
+                "header size {} and record size {} are too small for int64 date-time records",
+                header_size, record_size
+            )));
+        }
+        // Skip any header bytes newer versions add
+        std::io::copy(&mut (&mut reader).take((header_size - HEADER_SIZE) as u64), &mut std::io::sink())?;
+
+        Ok(Self {
+            reader,
+            contract_month: contract_month.into(),
+            record_size,
+            tick_size: Decimal::new(25, 2),
+            include_quotes: false,
+            offset: header_size as u64,
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Round prices to this increment; stored prices are `f32`
+    pub fn with_tick_size(mut self, tick_size: Decimal) -> Self {
+        self.tick_size = tick_size;
+        self
+    }
+
+    /// Also emit the bid and ask of tick-by-tick records
+    pub fn with_quotes(mut self, include_quotes: bool) -> Self {
+        self.include_quotes = include_quotes;
+        self
+    }
+
+    fn price(&self, value: f32) -> Option<Decimal> {
+        let price = Decimal::from_f32(value).filter(|price| *price > Decimal::ZERO)?;
+        if self.tick_size <= Decimal::ZERO {
+            return Some(price);
+        }
+        Some((price / self.tick_size).round() * self.tick_size)
+    }
+
+    /// Read the next record into `pending`; false at the end of the file
+    fn read_record(&mut self) -> Result<bool, AdapterError> {
+        let mut record = vec![0u8; self.record_size];
+        let mut filled = 0;
+        while filled < record.len() {
+            match self.reader.read(&mut record[filled..]) {
+                Ok(0) if filled == 0 => return Ok(false),
+                Ok(0) => return Err(AdapterError::Truncated(self.offset)),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        self.offset += self.record_size as u64;
+
+        let f32_at = |at: usize| f32::from_le_bytes(record[at..at + 4].try_into().unwrap());
+        let u32_at = |at: usize| u32::from_le_bytes(record[at..at + 4].try_into().unwrap());
+        let date_time_us = i64::from_le_bytes(record[0..8].try_into().unwrap());
+        let timestamp = date_time_us.saturating_sub(UNIX_EPOCH_OFFSET_US).saturating_mul(1_000);
+        let (open, high, low, close) = (f32_at(8), f32_at(12), f32_at(16), f32_at(20));
+        let total_volume = i32::try_from(u32_at(28)).unwrap_or(i32::MAX);
+
+        let is_tick = open == 0.0 || open == SINGLE_TRADE_WITH_BID_ASK;
+        if self.include_quotes && is_tick {
+            for (data_type, value) in [(MarketDataType::BidQuote, low), (MarketDataType::AskQuote, high)] {
+                if let Some(price) = self.price(value) {
+                    self.pending.push_back(self.tick(data_type, timestamp, price, 0));
+                }
+            }
+        }
+        if let Some(price) = self.price(close).filter(|_| total_volume > 0) {
+            self.pending.push_back(self.tick(MarketDataType::Trade, timestamp, price, total_volume));
+        }
+        Ok(true)
+    }
+
+    fn tick(&self, data_type: MarketDataType, timestamp: i64, price: Decimal, volume: i32) -> TickData {
+        TickData::new(DataLevel::L1, data_type, timestamp, price, volume, self.contract_month.clone())
+    }
+}
+
+impl<R: Read> TickSource for SierraChartReader<R> {
+    fn vendor(&self) -> &'static str {
+        "sierra_chart"
+    }
+
+    fn next_tick(&mut self) -> Result<Option<TickData>, AdapterError> {
+        loop {
+            if let Some(tick) = self.pending.pop_front() {
+                return Ok(Some(tick));
+            }
+            if !self.read_record()? {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn header() -> Vec<u8> {
+        let mut header = vec![0u8; HEADER_SIZE];
+        header[..4].copy_from_slice(HEADER_ID);
+        header[4..8].copy_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+        header[8..12].copy_from_slice(&(RECORD_SIZE as u32).to_le_bytes());
+        header[12..14].copy_from_slice(&1u16.to_le_bytes());
+        header
+    }
+
+    fn record(unix_us: i64, open: f32, ask: f32, bid: f32, last: f32, volume: u32) -> Vec<u8> {
+        let mut record = Vec::with_capacity(RECORD_SIZE);
+        record.extend_from_slice(&(unix_us + UNIX_EPOCH_OFFSET_US).to_le_bytes());
+        for value in [open, ask, bid, last] {
+            record.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [1, volume, 0, volume] {
+            record.extend_from_slice(&value.to_le_bytes());
+        }
+        record
+    }
+
+    #[test]
+    fn test_reads_trades_and_quotes() {
+        // 2024-06-12 13:30:00.000250 UTC
+        let at = 1_718_199_000_000_250;
+        let mut bytes = header();
+        bytes.extend(record(at, SINGLE_TRADE_WITH_BID_ASK, 18_950.25, 18_950.0, 18_950.25, 3));
+        bytes.extend(record(at + 10, 0.0, 18_950.5, 18_950.25, 18_950.249, 1));
+
+        let ticks = SierraChartReader::new(Cursor::new(bytes.clone()), "0624").unwrap().read_all().unwrap();
+        assert_eq!(ticks.len(), 2);
+        assert_eq!(ticks[0].timestamp, at * 1_000);
+        assert_eq!(ticks[0].volume, 3);
+        assert_eq!(ticks[1].price, Decimal::new(1_895_025, 2));
+        assert!(ticks.iter().all(|tick| tick.mdt == MarketDataType::Trade && tick.contract_month == "0624"));
+
+        let mut reader = SierraChartReader::new(Cursor::new(bytes.clone()), "0624").unwrap().with_quotes(true);
+        let ticks = reader.read_all().unwrap();
+        assert_eq!(ticks.len(), 6);
+        assert_eq!((ticks[0].mdt, ticks[0].price), (MarketDataType::BidQuote, Decimal::new(18_950, 0)));
+
+        bytes.truncate(bytes.len() - 7);
+        let mut truncated = SierraChartReader::new(Cursor::new(bytes), "0624").unwrap();
+        assert!(truncated.next_tick().unwrap().is_some());
+        assert!(matches!(truncated.next_tick(), Err(AdapterError::Truncated(96))));
+        assert!(matches!(
+            SierraChartReader::new(Cursor::new(vec![0u8; 60]), "0624"),
+            Err(AdapterError::InvalidHeader(_))
+        ));
+    }
+}
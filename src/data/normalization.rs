@@ -0,0 +1,304 @@
+//! Cross-provider tick normalization
+//!
+//! When the same session is recorded by several data providers, the feeds
+//! disagree on clocks (exchange vs receive time), repeat the same trades and
+//! publish overlapping depth updates. This pass reconciles them into a single
+//! canonical tick stream per instrument/day and records which provider each
+//! surviving tick came from.
+
+use crate::data::{DataLevel, MarketDataType, OrderBookOperation, TickData};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::mem::{discriminant, Discriminant};
+use tracing::info;
+
+/// Which clock a provider stamps its ticks with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampSource {
+    /// Matching engine time published by the exchange
+    Exchange,
+    /// Local time the provider received the message
+    Receive,
+}
+
+/// Description of a data provider feeding a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataProvider {
+    pub name: String,
+
+    /// Clock the provider's timestamps are based on
+    pub timestamp_source: TimestampSource,
+
+    /// Average receive latency in nanoseconds, subtracted from receive-time stamps
+    pub receive_latency_ns: i64,
+
+    /// Fixed clock offset in nanoseconds to correct provider clock skew
+    pub clock_offset_ns: i64,
+
+    /// Lower values win when the same event is reported by several providers
+    pub priority: u8,
+}
+
+/// Ticks for one instrument/day as delivered by a single provider
+#[derive(Debug, Clone)]
+pub struct ProviderFeed {
+    pub provider: DataProvider,
+    pub ticks: Vec<TickData>,
+}
+
+/// Configuration for the normalization pass
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizationConfig {
+    /// Events from different providers within this window are considered the same event
+    pub dedup_window_ns: i64,
+
+    /// Deduplicate trades (L1 trade prints)
+    pub dedup_trades: bool,
+
+    /// Merge L2 depth updates across providers
+    pub merge_depth: bool,
+}
+
+impl Default for NormalizationConfig {
+    fn default() -> Self {
+        Self {
+            dedup_window_ns: 1_000_000, // 1ms
+            dedup_trades: true,
+            merge_depth: true,
+        }
+    }
+}
+
+/// A canonical tick with the provenance it was selected from
+#[derive(Debug, Clone)]
+pub struct NormalizedTick {
+    pub tick: TickData,
+    pub provider: String,
+    pub original_timestamp: i64,
+    /// Other providers that reported the same event
+    pub confirmed_by: Vec<String>,
+}
+
+/// Per-provider contribution statistics
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProviderStats {
+    pub ticks_received: usize,
+    pub ticks_selected: usize,
+    pub duplicates_dropped: usize,
+}
+
+/// Summary of what the normalization pass did
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NormalizationReport {
+    pub input_ticks: usize,
+    pub output_ticks: usize,
+    pub duplicate_trades: usize,
+    pub duplicate_depth_updates: usize,
+    pub providers: HashMap<String, ProviderStats>,
+}
+
+/// Canonical dataset for one instrument/day
+#[derive(Debug, Clone)]
+pub struct NormalizedDataset {
+    pub ticks: Vec<NormalizedTick>,
+    pub report: NormalizationReport,
+}
+
+impl NormalizedDataset {
+    /// Plain ticks in canonical order, dropping provenance
+    pub fn into_ticks(self) -> Vec<TickData> {
+        self.ticks.into_iter().map(|t| t.tick).collect()
+    }
+}
+
+/// Dedup key for events that should match across providers
+///
+/// Everything but the clock has to agree: a bid and an ask at the same
+/// price, an add and a remove of the same level, or the same print on two
+/// contract months are different events.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct EventKey {
+    is_trade: bool,
+    is_l2: bool,
+    mdt: Discriminant<MarketDataType>,
+    operation: Option<Discriminant<OrderBookOperation>>,
+    depth: Option<u8>,
+    contract_month: String,
+    price: Decimal,
+    volume: i32,
+}
+
+/// Reconciles multiple provider feeds into one canonical stream
+pub struct TickNormalizer {
+    config: NormalizationConfig,
+}
+
+impl TickNormalizer {
+    pub fn new(config: NormalizationConfig) -> Self {
+        Self { config }
+    }
+
+    /// Normalize all provider feeds for a session
+    pub fn normalize(&self, feeds: Vec<ProviderFeed>) -> NormalizedDataset {
+        let mut report = NormalizationReport::default();
+        let mut candidates = Vec::new();
+
+        for feed in feeds {
+            let stats = report.providers.entry(feed.provider.name.clone()).or_default();
+            stats.ticks_received = feed.ticks.len();
+            report.input_ticks += feed.ticks.len();
+
+            for tick in feed.ticks {
+                let original_timestamp = tick.timestamp;
+                let mut tick = tick;
+                tick.timestamp = Self::to_exchange_time(&feed.provider, original_timestamp);
+                candidates.push((feed.provider.priority, NormalizedTick {
+                    tick,
+                    provider: feed.provider.name.clone(),
+                    original_timestamp,
+                    confirmed_by: Vec::new(),
+                }));
+            }
+        }
+
+        // Exchange time first, then provider priority so the preferred copy is seen first
+        candidates.sort_by(|a, b| {
+            a.1.tick.timestamp.cmp(&b.1.tick.timestamp).then(a.0.cmp(&b.0))
+        });
+
+        let mut output: Vec<NormalizedTick> = Vec::with_capacity(candidates.len());
+        // Most recent accepted event per key: (output index, exchange timestamp)
+        let mut recent: HashMap<EventKey, (usize, i64)> = HashMap::new();
+
+        for (_, candidate) in candidates {
+            let key = Self::event_key(&candidate.tick);
+            let dedup_enabled = if key.is_l2 {
+                self.config.merge_depth
+            } else {
+                key.is_trade && self.config.dedup_trades
+            };
+
+            if dedup_enabled {
+                if let Some(&(index, timestamp)) = recent.get(&key) {
+                    let existing = &mut output[index];
+                    let within_window = candidate.tick.timestamp - timestamp <= self.config.dedup_window_ns;
+                    let other_provider = existing.provider != candidate.provider
+                        && !existing.confirmed_by.contains(&candidate.provider);
+
+                    if within_window && other_provider {
+                        existing.confirmed_by.push(candidate.provider.clone());
+                        if key.is_l2 {
+                            report.duplicate_depth_updates += 1;
+                        } else {
+                            report.duplicate_trades += 1;
+                        }
+                        if let Some(stats) = report.providers.get_mut(&candidate.provider) {
+                            stats.duplicates_dropped += 1;
+                        }
+                        continue;
+                    }
+                }
+                recent.insert(key, (output.len(), candidate.tick.timestamp));
+            }
+
+            if let Some(stats) = report.providers.get_mut(&candidate.provider) {
+                stats.ticks_selected += 1;
+            }
+            output.push(candidate);
+        }
+
+        report.output_ticks = output.len();
+
+        info!(
+            "Normalized {} ticks into {} ({} duplicate trades, {} duplicate depth updates)",
+            report.input_ticks, report.output_ticks,
+            report.duplicate_trades, report.duplicate_depth_updates
+        );
+
+        NormalizedDataset { ticks: output, report }
+    }
+
+    /// Convert a provider timestamp onto the exchange clock
+    fn to_exchange_time(provider: &DataProvider, timestamp: i64) -> i64 {
+        let corrected = timestamp + provider.clock_offset_ns;
+        match provider.timestamp_source {
+            TimestampSource::Exchange => corrected,
+            TimestampSource::Receive => corrected - provider.receive_latency_ns,
+        }
+    }
+
+    fn event_key(tick: &TickData) -> EventKey {
+        EventKey {
+            is_trade: tick.mdt == MarketDataType::Trade,
+            is_l2: matches!(tick.level, DataLevel::L2),
+            mdt: discriminant(&tick.mdt),
+            operation: tick.operation.as_ref().map(discriminant),
+            depth: tick.depth,
+            contract_month: tick.contract_month.clone(),
+            price: tick.price,
+            volume: tick.volume,
+        }
+    }
+}
+
+impl Default for TickNormalizer {
+    fn default() -> Self {
+        Self::new(NormalizationConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(name: &str, priority: u8) -> DataProvider {
+        DataProvider {
+            name: name.to_string(),
+            timestamp_source: TimestampSource::Exchange,
+            receive_latency_ns: 0,
+            clock_offset_ns: 0,
+            priority,
+        }
+    }
+
+    fn trade(contract: &str, timestamp: i64) -> TickData {
+        TickData::new(DataLevel::L1, MarketDataType::Trade, timestamp, Decimal::from(18_000), 2, contract.to_string())
+    }
+
+    fn depth(mdt: MarketDataType, operation: OrderBookOperation, depth: u8, timestamp: i64) -> TickData {
+        TickData::new(DataLevel::L2, mdt, timestamp, Decimal::from(18_000), 5, "0624".to_string())
+            .with_l2_data(operation, depth)
+    }
+
+    #[test]
+    fn test_only_identical_events_are_merged() {
+        let primary = ProviderFeed {
+            provider: provider("rithmic", 0),
+            ticks: vec![
+                trade("0624", 1_000),
+                trade("0924", 1_000),
+                depth(MarketDataType::BidQuote, OrderBookOperation::Add, 0, 2_000),
+                depth(MarketDataType::AskQuote, OrderBookOperation::Add, 0, 2_000),
+                depth(MarketDataType::BidQuote, OrderBookOperation::Remove, 0, 3_000),
+                depth(MarketDataType::BidQuote, OrderBookOperation::Add, 1, 3_000),
+            ],
+        };
+        let secondary = ProviderFeed {
+            provider: provider("dtn", 1),
+            ticks: vec![
+                trade("0624", 1_200),
+                depth(MarketDataType::BidQuote, OrderBookOperation::Add, 0, 2_100),
+                depth(MarketDataType::BidQuote, OrderBookOperation::Update, 0, 2_100),
+            ],
+        };
+
+        let dataset = TickNormalizer::default().normalize(vec![primary, secondary]);
+        assert_eq!(dataset.report.duplicate_trades, 1);
+        assert_eq!(dataset.report.duplicate_depth_updates, 1);
+        assert_eq!(dataset.report.output_ticks, 7);
+        assert_eq!(dataset.ticks[0].confirmed_by, vec!["dtn".to_string()]);
+        assert!(dataset.ticks.iter().any(|t| t.tick.contract_month == "0924"));
+    }
+}
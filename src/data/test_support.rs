@@ -0,0 +1,22 @@
+//! Tick fixtures shared by unit tests
+
+use crate::data::{DataLevel, MarketDataType, TickData};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+/// Build an L1 tick for the June contract
+pub fn tick(mdt: MarketDataType, price: &str, volume: i32, timestamp: i64) -> TickData {
+    TickData::new(
+        DataLevel::L1,
+        mdt,
+        timestamp,
+        Decimal::from_str(price).unwrap(),
+        volume,
+        "0624".to_string(),
+    )
+}
+
+/// Build an L1 trade print for the June contract
+pub fn trade(price: &str, volume: i32, timestamp: i64) -> TickData {
+    tick(MarketDataType::Trade, price, volume, timestamp)
+}
@@ -0,0 +1,400 @@
+//! Tick validation at ingestion time
+//!
+//! Every tick read from a raw file is checked for a fixed set of anomaly
+//! classes. The `ValidationLevel` chosen in `IngestionConfig` decides what
+//! happens to each class:
+//!
+//! | Anomaly              | Strict | Standard | Permissive |
+//! |----------------------|--------|----------|------------|
+//! | Non-positive price   | abort  | abort    | drop       |
+//! | Negative volume      | abort  | abort    | drop       |
+//! | Zero-volume trade    | abort  | drop     | flag       |
+//! | Out-of-order time    | abort  | flag     | flag       |
+//! | Duplicate tick       | abort  | drop     | flag       |
+//! | Crossed quote        | abort  | flag     | flag       |
+//! | Price spike          | abort  | flag     | flag       |
+//!
+//! Dropped ticks are removed from the output, flagged ticks are kept. Both
+//! are counted per class in the `IngestionSummary`.
+
+use crate::data::{DataIngestionEngine, IngestionConfig, MarketDataType, TickData, ValidationLevel};
+use crate::performance::{PoolKind, ThreadPools};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// Maximum number of anomalies kept as samples in the summary
+const MAX_SAMPLES: usize = 50;
+
+/// Class of data problem found in a tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyClass {
+    /// Price is zero or negative
+    NonPositivePrice,
+    /// Volume is negative
+    NegativeVolume,
+    /// Trade print with no volume
+    ZeroVolumeTrade,
+    /// Timestamp earlier than the previous tick
+    OutOfOrderTimestamp,
+    /// Identical to the previous tick
+    DuplicateTick,
+    /// Bid at or above the ask
+    CrossedQuote,
+    /// Trade price moved further than the spike threshold from the previous trade
+    PriceSpike,
+}
+
+/// What ingestion does with a tick showing an anomaly
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyAction {
+    /// Stop ingestion with an error
+    Abort,
+    /// Discard the tick and continue
+    Drop,
+    /// Keep the tick and count it
+    Flag,
+}
+
+impl AnomalyClass {
+    pub const ALL: [AnomalyClass; 7] = [
+        AnomalyClass::NonPositivePrice,
+        AnomalyClass::NegativeVolume,
+        AnomalyClass::ZeroVolumeTrade,
+        AnomalyClass::OutOfOrderTimestamp,
+        AnomalyClass::DuplicateTick,
+        AnomalyClass::CrossedQuote,
+        AnomalyClass::PriceSpike,
+    ];
+
+    /// How this anomaly is handled at a validation level
+    pub fn action(self, level: ValidationLevel) -> AnomalyAction {
+        use AnomalyAction::*;
+        use AnomalyClass::*;
+
+        match level {
+            ValidationLevel::Strict => Abort,
+            ValidationLevel::Standard => match self {
+                NonPositivePrice | NegativeVolume => Abort,
+                ZeroVolumeTrade | DuplicateTick => Drop,
+                OutOfOrderTimestamp | CrossedQuote | PriceSpike => Flag,
+            },
+            ValidationLevel::Permissive => match self {
+                NonPositivePrice | NegativeVolume => Drop,
+                _ => Flag,
+            },
+        }
+    }
+}
+
+/// An anomaly found at a position in the input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Anomaly {
+    pub class: AnomalyClass,
+    pub action: AnomalyAction,
+    /// Index of the tick in the ingested input
+    pub index: usize,
+    pub timestamp: i64,
+    pub detail: String,
+}
+
+/// Validation outcome of an ingestion run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestionSummary {
+    pub level: ValidationLevel,
+    pub ticks_read: usize,
+    pub ticks_accepted: usize,
+    pub ticks_dropped: usize,
+    pub ticks_flagged: usize,
+    /// Number of occurrences of each anomaly class
+    pub anomaly_counts: BTreeMap<AnomalyClass, usize>,
+    /// The first anomalies found, up to a fixed limit
+    pub samples: Vec<Anomaly>,
+}
+
+impl IngestionSummary {
+    fn new(level: ValidationLevel) -> Self {
+        Self {
+            level,
+            ticks_read: 0,
+            ticks_accepted: 0,
+            ticks_dropped: 0,
+            ticks_flagged: 0,
+            anomaly_counts: AnomalyClass::ALL.iter().map(|c| (*c, 0)).collect(),
+            samples: Vec::new(),
+        }
+    }
+
+    pub fn count(&self, class: AnomalyClass) -> usize {
+        self.anomaly_counts.get(&class).copied().unwrap_or(0)
+    }
+
+    pub fn total_anomalies(&self) -> usize {
+        self.anomaly_counts.values().sum()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ValidationError {
+    #[error("Ingestion aborted at tick {}: {:?} ({})", anomaly.index, anomaly.class, anomaly.detail)]
+    Aborted {
+        anomaly: Anomaly,
+        /// Counts up to and including the aborting tick
+        summary: Box<IngestionSummary>,
+    },
+}
+
+/// Thresholds for the anomaly checks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationThresholds {
+    /// Relative move between consecutive trades treated as a spike
+    pub max_trade_jump: Decimal,
+}
+
+impl Default for ValidationThresholds {
+    fn default() -> Self {
+        Self {
+            max_trade_jump: Decimal::new(5, 2), // 5%
+        }
+    }
+}
+
+/// Applies a validation level to an ingested tick stream
+#[derive(Debug, Clone)]
+pub struct TickValidator {
+    level: ValidationLevel,
+    thresholds: ValidationThresholds,
+}
+
+impl TickValidator {
+    pub fn new(level: ValidationLevel) -> Self {
+        Self {
+            level,
+            thresholds: ValidationThresholds::default(),
+        }
+    }
+
+    pub fn with_thresholds(mut self, thresholds: ValidationThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// Validate ticks in input order, returning the accepted ticks and the summary
+    pub fn validate(&self, ticks: Vec<TickData>) -> Result<(Vec<TickData>, IngestionSummary), ValidationError> {
+        let mut summary = IngestionSummary::new(self.level);
+        let mut accepted = Vec::with_capacity(ticks.len());
+
+        let mut last_timestamp: Option<i64> = None;
+        let mut last_trade: Option<Decimal> = None;
+        let mut best_bid: Option<Decimal> = None;
+        let mut best_ask: Option<Decimal> = None;
+        let mut previous: Option<TickData> = None;
+
+        for (index, tick) in ticks.into_iter().enumerate() {
+            summary.ticks_read += 1;
+
+            let found = self.inspect(&tick, last_timestamp, last_trade, best_bid, best_ask, previous.as_ref());
+            let flagged = !found.is_empty();
+            let mut drop = false;
+
+            for (class, detail) in found {
+                let action = class.action(self.level);
+                let anomaly = Anomaly {
+                    class,
+                    action,
+                    index,
+                    timestamp: tick.timestamp,
+                    detail,
+                };
+                *summary.anomaly_counts.entry(class).or_insert(0) += 1;
+
+                match action {
+                    AnomalyAction::Abort => {
+                        warn!("Ingestion aborted: {:?} at tick {}", class, index);
+                        return Err(ValidationError::Aborted {
+                            anomaly,
+                            summary: Box::new(summary),
+                        });
+                    }
+                    AnomalyAction::Drop => drop = true,
+                    AnomalyAction::Flag => {}
+                }
+
+                if summary.samples.len() < MAX_SAMPLES {
+                    summary.samples.push(anomaly);
+                }
+            }
+
+            if drop {
+                summary.ticks_dropped += 1;
+                continue;
+            }
+            if flagged {
+                summary.ticks_flagged += 1;
+            }
+
+            last_timestamp = Some(last_timestamp.map_or(tick.timestamp, |t| t.max(tick.timestamp)));
+            match tick.mdt {
+                MarketDataType::Trade => last_trade = Some(tick.price),
+                MarketDataType::BidQuote => best_bid = Some(tick.price),
+                MarketDataType::AskQuote => best_ask = Some(tick.price),
+                MarketDataType::BookReset => {
+                    best_bid = None;
+                    best_ask = None;
+                }
+                _ => {}
+            }
+            previous = Some(tick.clone());
+            accepted.push(tick);
+        }
+
+        summary.ticks_accepted = accepted.len();
+        Ok((accepted, summary))
+    }
+
+    fn inspect(
+        &self,
+        tick: &TickData,
+        last_timestamp: Option<i64>,
+        last_trade: Option<Decimal>,
+        best_bid: Option<Decimal>,
+        best_ask: Option<Decimal>,
+        previous: Option<&TickData>,
+    ) -> Vec<(AnomalyClass, String)> {
+        let mut found = Vec::new();
+        let is_price_event = !matches!(tick.mdt, MarketDataType::BookReset | MarketDataType::Volume);
+
+        if is_price_event && tick.price <= Decimal::ZERO {
+            found.push((AnomalyClass::NonPositivePrice, format!("price {}", tick.price)));
+        }
+        if tick.volume < 0 {
+            found.push((AnomalyClass::NegativeVolume, format!("volume {}", tick.volume)));
+        }
+        if tick.mdt == MarketDataType::Trade && tick.volume == 0 {
+            found.push((AnomalyClass::ZeroVolumeTrade, format!("trade at {}", tick.price)));
+        }
+        if let Some(last) = last_timestamp {
+            if tick.timestamp < last {
+                found.push((AnomalyClass::OutOfOrderTimestamp,
+                    format!("{} ns before previous tick", last - tick.timestamp)));
+            }
+        }
+        if let Some(prev) = previous {
+            if prev.timestamp == tick.timestamp
+                && prev.mdt == tick.mdt
+                && prev.price == tick.price
+                && prev.volume == tick.volume
+            {
+                found.push((AnomalyClass::DuplicateTick, format!("{:?} at {}", tick.mdt, tick.price)));
+            }
+        }
+
+        let (bid, ask) = match tick.mdt {
+            MarketDataType::BidQuote => (Some(tick.price), best_ask),
+            MarketDataType::AskQuote => (best_bid, Some(tick.price)),
+            _ => (None, None),
+        };
+        if let (Some(bid), Some(ask)) = (bid, ask) {
+            if bid >= ask {
+                found.push((AnomalyClass::CrossedQuote, format!("bid {} >= ask {}", bid, ask)));
+            }
+        }
+
+        if tick.mdt == MarketDataType::Trade && tick.price > Decimal::ZERO {
+            if let Some(last) = last_trade.filter(|p| *p > Decimal::ZERO) {
+                let jump = ((tick.price - last) / last).abs();
+                if jump > self.thresholds.max_trade_jump {
+                    found.push((AnomalyClass::PriceSpike, format!("{} -> {}", last, tick.price)));
+                }
+            }
+        }
+
+        found
+    }
+}
+
+/// Ingest a file and validate it at the level set in `config`
+///
+/// With `pools`, the ticks are validated on the ingestion pool and counted
+/// towards its throughput. The summary is what a data ingestion job reports
+/// back as its result.
+pub async fn ingest_validated<P: AsRef<std::path::Path>>(
+    path: P,
+    config: IngestionConfig,
+    pools: Option<&ThreadPools>,
+) -> Result<(Vec<TickData>, IngestionSummary), Box<dyn std::error::Error>> {
+    let validator = TickValidator::new(config.validation_level);
+    let mut engine = DataIngestionEngine::new(config);
+    let ticks = engine.ingest_file(path).await?;
+
+    let (ticks, summary) = match pools {
+        Some(pools) => {
+            let pools = pools.clone();
+            let checked = ticks.len() as u64;
+            tokio::task::spawn_blocking(move || pools.run(PoolKind::Ingestion, || (validator.validate(ticks), checked)))
+                .await??
+        }
+        None => validator.validate(ticks)?,
+    };
+    info!("Ingested {} ticks at {:?} validation: {} dropped, {} flagged",
+        summary.ticks_accepted, summary.level, summary.ticks_dropped, summary.ticks_flagged);
+
+    Ok((ticks, summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::test_support::tick;
+
+    fn sample() -> Vec<TickData> {
+        vec![
+            tick(MarketDataType::BidQuote, "100.00", 5, 1),
+            tick(MarketDataType::AskQuote, "100.25", 5, 2),
+            tick(MarketDataType::Trade, "100.25", 1, 3),
+            tick(MarketDataType::Trade, "100.25", 1, 3),
+            tick(MarketDataType::Trade, "100.00", 2, 2),
+            tick(MarketDataType::Trade, "0", 1, 4),
+        ]
+    }
+
+    #[test]
+    fn test_permissive_drops_and_flags() {
+        let (ticks, summary) = TickValidator::new(ValidationLevel::Permissive)
+            .validate(sample())
+            .unwrap();
+
+        assert_eq!(summary.ticks_read, 6);
+        assert_eq!(ticks.len(), 5);
+        assert_eq!(summary.ticks_dropped, 1);
+        assert_eq!(summary.count(AnomalyClass::NonPositivePrice), 1);
+        assert_eq!(summary.count(AnomalyClass::DuplicateTick), 1);
+        assert_eq!(summary.count(AnomalyClass::OutOfOrderTimestamp), 1);
+    }
+
+    #[test]
+    fn test_standard_aborts_on_bad_price() {
+        let err = TickValidator::new(ValidationLevel::Standard)
+            .validate(sample())
+            .unwrap_err();
+
+        let ValidationError::Aborted { anomaly, summary } = err;
+        assert_eq!(anomaly.class, AnomalyClass::NonPositivePrice);
+        assert_eq!(anomaly.index, 5);
+        assert_eq!(summary.count(AnomalyClass::DuplicateTick), 1);
+    }
+
+    #[test]
+    fn test_strict_aborts_on_first_anomaly() {
+        let err = TickValidator::new(ValidationLevel::Strict)
+            .validate(sample())
+            .unwrap_err();
+
+        let ValidationError::Aborted { anomaly, .. } = err;
+        assert_eq!(anomaly.class, AnomalyClass::DuplicateTick);
+    }
+}
@@ -0,0 +1,236 @@
+//! Saved analysis views
+//!
+//! A view records how a researcher laid out their analysis: the metrics they
+//! picked, chart settings and the results being compared, so the frontend
+//! can restore it in a later session. Views are private to their owner until
+//! shared; a shared view can be opened by id by anyone in the workspace, but
+//! only its owner can change or delete it.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use super::DbPool;
+
+/// Errors raised by the analysis view store
+#[derive(Debug, thiserror::Error)]
+pub enum AnalysisViewError {
+    #[error("No analysis view {0}")]
+    NotFound(Uuid),
+    #[error("Analysis view {0} can only be changed by its owner")]
+    NotOwner(Uuid),
+    #[error("Analysis view {0} was modified concurrently")]
+    Conflict(Uuid),
+    #[error("Analysis views need a name")]
+    MissingName,
+    #[error("Analysis views require a database")]
+    Unavailable,
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// One chart of a view; `options` holds frontend settings such as axes,
+/// scales and colors, stored as given
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChartConfig {
+    pub kind: String,
+    #[serde(default)]
+    pub metrics: Vec<String>,
+    #[serde(default)]
+    pub options: serde_json::Value,
+}
+
+/// What a view shows
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ViewDefinition {
+    #[serde(default)]
+    pub metrics: Vec<String>,
+    #[serde(default)]
+    pub charts: Vec<ChartConfig>,
+    /// Backtest result ids compared side by side
+    #[serde(default)]
+    pub comparison: Vec<String>,
+}
+
+/// A stored analysis view
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AnalysisView {
+    pub id: Uuid,
+    pub workspace_id: String,
+    pub owner: String,
+    pub name: String,
+    pub shared: bool,
+    pub version: i32,
+    pub definition: Json<ViewDefinition>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Values for creating or replacing a view
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisViewDraft {
+    pub name: String,
+    #[serde(default)]
+    pub shared: bool,
+    #[serde(default)]
+    pub definition: ViewDefinition,
+}
+
+impl AnalysisViewDraft {
+    fn validate(&self) -> Result<(), AnalysisViewError> {
+        if self.name.trim().is_empty() {
+            return Err(AnalysisViewError::MissingName);
+        }
+        Ok(())
+    }
+}
+
+const VIEW_COLUMNS: &str = "id, workspace_id, owner, name, shared, version, definition, created_at, updated_at";
+
+/// Analysis views persisted in Postgres
+#[derive(Debug, Clone)]
+pub struct AnalysisViewStore {
+    pool: DbPool,
+}
+
+impl AnalysisViewStore {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        workspace_id: &str,
+        owner: &str,
+        draft: AnalysisViewDraft,
+    ) -> Result<AnalysisView, AnalysisViewError> {
+        draft.validate()?;
+        let query = format!(
+            "INSERT INTO analysis_views (workspace_id, owner, name, shared, definition)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING {}",
+            VIEW_COLUMNS
+        );
+
+        let view = sqlx::query_as::<_, AnalysisView>(&query)
+            .bind(workspace_id)
+            .bind(owner)
+            .bind(draft.name.trim())
+            .bind(draft.shared)
+            .bind(Json(&draft.definition))
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(view)
+    }
+
+    /// Views `user` owns plus views shared in the workspace
+    pub async fn list(&self, workspace_id: &str, user: &str) -> Result<Vec<AnalysisView>, AnalysisViewError> {
+        let query = format!(
+            "SELECT {} FROM analysis_views
+             WHERE workspace_id = $1 AND (owner = $2 OR shared)
+             ORDER BY name",
+            VIEW_COLUMNS
+        );
+
+        let views = sqlx::query_as::<_, AnalysisView>(&query)
+            .bind(workspace_id)
+            .bind(user)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(views)
+    }
+
+    /// A view `user` can see; other users' private views are reported as
+    /// missing so their ids don't leak
+    pub async fn get(&self, workspace_id: &str, user: &str, id: Uuid) -> Result<AnalysisView, AnalysisViewError> {
+        let query = format!(
+            "SELECT {} FROM analysis_views
+             WHERE workspace_id = $1 AND id = $2 AND (owner = $3 OR shared)",
+            VIEW_COLUMNS
+        );
+
+        sqlx::query_as::<_, AnalysisView>(&query)
+            .bind(workspace_id)
+            .bind(id)
+            .bind(user)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(AnalysisViewError::NotFound(id))
+    }
+
+    /// Replace a view, bumping its version
+    pub async fn update(
+        &self,
+        workspace_id: &str,
+        user: &str,
+        id: Uuid,
+        draft: AnalysisViewDraft,
+    ) -> Result<AnalysisView, AnalysisViewError> {
+        draft.validate()?;
+        let current = self.owned(workspace_id, user, id).await?;
+        let query = format!(
+            "UPDATE analysis_views SET name = $3, shared = $4, definition = $5, version = version + 1
+             WHERE workspace_id = $1 AND id = $2 AND version = $6
+             RETURNING {}",
+            VIEW_COLUMNS
+        );
+
+        // As with workflow templates, a concurrent save becomes a conflict
+        // rather than a lost update
+        sqlx::query_as::<_, AnalysisView>(&query)
+            .bind(workspace_id)
+            .bind(id)
+            .bind(draft.name.trim())
+            .bind(draft.shared)
+            .bind(Json(&draft.definition))
+            .bind(current.version)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(AnalysisViewError::Conflict(id))
+    }
+
+    pub async fn delete(&self, workspace_id: &str, user: &str, id: Uuid) -> Result<(), AnalysisViewError> {
+        self.owned(workspace_id, user, id).await?;
+        let result = sqlx::query("DELETE FROM analysis_views WHERE workspace_id = $1 AND id = $2")
+            .bind(workspace_id)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AnalysisViewError::NotFound(id));
+        }
+        Ok(())
+    }
+
+    /// A view `user` can see and owns
+    async fn owned(&self, workspace_id: &str, user: &str, id: Uuid) -> Result<AnalysisView, AnalysisViewError> {
+        let view = self.get(workspace_id, user, id).await?;
+        if view.owner != user {
+            return Err(AnalysisViewError::NotOwner(id));
+        }
+        Ok(view)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draft_defaults_to_private_empty_view() {
+        let draft: AnalysisViewDraft = serde_json::from_value(serde_json::json!({
+            "name": "Sharpe by session",
+            "definition": { "charts": [{ "kind": "line", "options": { "log_scale": true } }] }
+        })).unwrap();
+
+        assert!(!draft.shared);
+        assert!(draft.definition.metrics.is_empty());
+        assert_eq!(draft.definition.charts[0].options["log_scale"], true);
+        assert!(draft.validate().is_ok());
+
+        let unnamed = AnalysisViewDraft { name: "  ".to_string(), ..draft };
+        assert!(matches!(unnamed.validate(), Err(AnalysisViewError::MissingName)));
+    }
+}
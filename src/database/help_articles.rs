@@ -0,0 +1,204 @@
+//! Editable help articles
+//!
+//! Contextual help for the guided workflows lives here instead of in the
+//! binary, so it can be extended and corrected without a release. Articles
+//! are checked before every write, indexed for full-text search and tagged
+//! with the workflow step types they cover. Help is shared by every
+//! workspace. Each update bumps the version.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+
+use super::DbPool;
+use crate::workflow::{HelpArticle, HelpArticleIssue, WorkflowStepType};
+
+/// Most search hits returned
+pub const MAX_SEARCH_RESULTS: i64 = 50;
+
+/// Errors raised by the help article store
+#[derive(Debug, thiserror::Error)]
+pub enum HelpArticleError {
+    #[error("No help article {0}")]
+    NotFound(String),
+    #[error("Help article {0} already exists")]
+    AlreadyExists(String),
+    #[error("Help article {0} was modified concurrently")]
+    Conflict(String),
+    #[error("Invalid help article ({} issues)", .0.len())]
+    Invalid(Vec<HelpArticleIssue>),
+    #[error("Help articles require a database")]
+    Unavailable,
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// A stored help article
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StoredHelpArticle {
+    pub id: String,
+    pub version: i32,
+    pub content: Json<HelpArticle>,
+    pub updated_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// An article matching a search, best first
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct HelpSearchHit {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub article: StoredHelpArticle,
+    pub rank: f32,
+    /// Matching passages of the body with the search terms in `<b>` tags
+    pub snippet: String,
+}
+
+const ARTICLE_COLUMNS: &str = "id, version, content, updated_by, created_at, updated_at";
+
+/// Help articles persisted in Postgres
+#[derive(Debug, Clone)]
+pub struct HelpArticleStore {
+    pool: DbPool,
+}
+
+impl HelpArticleStore {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    fn check(article: &HelpArticle) -> Result<(), HelpArticleError> {
+        let issues = article.check();
+        if !issues.is_empty() {
+            return Err(HelpArticleError::Invalid(issues));
+        }
+        Ok(())
+    }
+
+    /// Step types as stored, for indexed lookups
+    fn step_types(article: &HelpArticle) -> Vec<String> {
+        article.step_types.iter().map(|step| step.as_str().to_string()).collect()
+    }
+
+    pub async fn create(&self, author: &str, article: HelpArticle) -> Result<StoredHelpArticle, HelpArticleError> {
+        Self::check(&article)?;
+        let query = format!(
+            "INSERT INTO help_articles (id, title, body, tags, step_types, difficulty_level, content, updated_by)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+             ON CONFLICT (id) DO NOTHING
+             RETURNING {}",
+            ARTICLE_COLUMNS
+        );
+
+        sqlx::query_as::<_, StoredHelpArticle>(&query)
+            .bind(article.id())
+            .bind(article.content.title.trim())
+            .bind(&article.content.content)
+            .bind(&article.content.tags)
+            .bind(Self::step_types(&article))
+            .bind(article.difficulty_level.map(|level| level.as_str()))
+            .bind(Json(&article))
+            .bind(author)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| HelpArticleError::AlreadyExists(article.id().to_string()))
+    }
+
+    /// Replace an article, bumping its version
+    pub async fn update(&self, author: &str, article: HelpArticle) -> Result<StoredHelpArticle, HelpArticleError> {
+        Self::check(&article)?;
+        let current = self.get(article.id()).await?;
+        let query = format!(
+            "UPDATE help_articles
+             SET title = $2, body = $3, tags = $4, step_types = $5, difficulty_level = $6, content = $7,
+                 updated_by = $8, version = version + 1
+             WHERE id = $1 AND version = $9
+             RETURNING {}",
+            ARTICLE_COLUMNS
+        );
+
+        // As with workflow templates, a concurrent edit becomes a conflict
+        // rather than a lost update
+        sqlx::query_as::<_, StoredHelpArticle>(&query)
+            .bind(article.id())
+            .bind(article.content.title.trim())
+            .bind(&article.content.content)
+            .bind(&article.content.tags)
+            .bind(Self::step_types(&article))
+            .bind(article.difficulty_level.map(|level| level.as_str()))
+            .bind(Json(&article))
+            .bind(author)
+            .bind(current.version)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| HelpArticleError::Conflict(article.id().to_string()))
+    }
+
+    /// Every article, or those covering one step type, by title
+    pub async fn list(&self, step_type: Option<&WorkflowStepType>) -> Result<Vec<StoredHelpArticle>, HelpArticleError> {
+        let query = format!(
+            "SELECT {} FROM help_articles
+             WHERE $1::text IS NULL OR $1 = ANY(step_types)
+             ORDER BY title",
+            ARTICLE_COLUMNS
+        );
+
+        let articles = sqlx::query_as::<_, StoredHelpArticle>(&query)
+            .bind(step_type.map(|step| step.as_str()))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(articles)
+    }
+
+    pub async fn get(&self, id: &str) -> Result<StoredHelpArticle, HelpArticleError> {
+        let query = format!("SELECT {} FROM help_articles WHERE id = $1", ARTICLE_COLUMNS);
+
+        sqlx::query_as::<_, StoredHelpArticle>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| HelpArticleError::NotFound(id.to_string()))
+    }
+
+    /// Full-text search of titles, tags and bodies
+    ///
+    /// `terms` takes web search syntax: quoted phrases, `or` and `-` to
+    /// exclude a word. Titles weigh more than tags, tags more than bodies.
+    pub async fn search(
+        &self,
+        terms: &str,
+        step_type: Option<&WorkflowStepType>,
+        limit: i64,
+    ) -> Result<Vec<HelpSearchHit>, HelpArticleError> {
+        let query = format!(
+            "SELECT {}, ts_rank(search_vector, query) AS rank,
+                    ts_headline('english', body, query, 'MaxFragments=2, MinWords=5, MaxWords=25') AS snippet
+             FROM help_articles, websearch_to_tsquery('english', $1) AS query
+             WHERE search_vector @@ query AND ($2::text IS NULL OR $2 = ANY(step_types))
+             ORDER BY rank DESC, title
+             LIMIT $3",
+            ARTICLE_COLUMNS
+        );
+
+        let hits = sqlx::query_as::<_, HelpSearchHit>(&query)
+            .bind(terms)
+            .bind(step_type.map(|step| step.as_str()))
+            .bind(limit.clamp(1, MAX_SEARCH_RESULTS))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(hits)
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<(), HelpArticleError> {
+        let result = sqlx::query("DELETE FROM help_articles WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(HelpArticleError::NotFound(id.to_string()));
+        }
+        Ok(())
+    }
+}
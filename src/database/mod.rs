@@ -5,6 +5,17 @@ pub type DbPool = Pool<Postgres>;
 
 pub mod tests;
 pub mod integration_test;
+pub mod analysis_views;
+pub mod help_articles;
+pub mod presets;
+pub mod workflow_templates;
+
+pub use analysis_views::{
+    AnalysisView, AnalysisViewDraft, AnalysisViewError, AnalysisViewStore, ChartConfig, ViewDefinition,
+};
+pub use help_articles::{HelpArticleError, HelpArticleStore, HelpSearchHit, StoredHelpArticle, MAX_SEARCH_RESULTS};
+pub use presets::{ParameterPreset, PresetDraft, PresetError, PresetStore};
+pub use workflow_templates::{WorkflowTemplate, WorkflowTemplateError, WorkflowTemplateStore};
 
 pub struct Database {
     pub pool: DbPool,
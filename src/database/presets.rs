@@ -0,0 +1,224 @@
+//! Named strategy parameter presets
+//!
+//! A preset is a saved set of parameter values for one strategy, referenced
+//! by name from backtest and optimization requests. Presets promoted from an
+//! optimization remember the run they came from.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::DbPool;
+
+/// Errors raised by the preset store
+#[derive(Debug, thiserror::Error)]
+pub enum PresetError {
+    #[error("No preset named {name} for strategy {strategy_id}")]
+    NotFound { strategy_id: String, name: String },
+    #[error("Preset parameters must be a JSON object")]
+    InvalidParameters,
+    #[error("Parameter presets require a database")]
+    Unavailable,
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// A saved parameter preset
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ParameterPreset {
+    pub id: Uuid,
+    pub workspace_id: String,
+    pub strategy_id: String,
+    pub name: String,
+    pub parameters: serde_json::Value,
+    pub favorite: bool,
+    /// Optimization the preset was promoted from
+    pub source_optimization_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ParameterPreset {
+    /// The preset's parameters with `overrides` applied on top
+    pub fn merged_with(&self, overrides: &serde_json::Value) -> serde_json::Value {
+        let mut merged = self.parameters.clone();
+        if let (Some(base), Some(overrides)) = (merged.as_object_mut(), overrides.as_object()) {
+            for (name, value) in overrides {
+                base.insert(name.clone(), value.clone());
+            }
+        }
+        merged
+    }
+}
+
+/// Values for creating or replacing a preset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresetDraft {
+    pub name: String,
+    pub parameters: serde_json::Value,
+    #[serde(default)]
+    pub favorite: bool,
+    #[serde(default)]
+    pub source_optimization_id: Option<String>,
+}
+
+const PRESET_COLUMNS: &str = "id, workspace_id, strategy_id, name, parameters, favorite, \
+    source_optimization_id, created_at, updated_at";
+
+/// Presets persisted in Postgres
+#[derive(Debug, Clone)]
+pub struct PresetStore {
+    pool: DbPool,
+}
+
+impl PresetStore {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Save a preset, replacing any preset of the same name
+    pub async fn save(
+        &self,
+        workspace_id: &str,
+        strategy_id: &str,
+        draft: PresetDraft,
+    ) -> Result<ParameterPreset, PresetError> {
+        if !draft.parameters.is_object() {
+            return Err(PresetError::InvalidParameters);
+        }
+
+        let query = format!(
+            "INSERT INTO parameter_presets
+                (workspace_id, strategy_id, name, parameters, favorite, source_optimization_id)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (workspace_id, strategy_id, name) DO UPDATE SET
+                parameters = EXCLUDED.parameters,
+                favorite = EXCLUDED.favorite,
+                source_optimization_id = EXCLUDED.source_optimization_id
+             RETURNING {}",
+            PRESET_COLUMNS
+        );
+
+        let preset = sqlx::query_as::<_, ParameterPreset>(&query)
+            .bind(workspace_id)
+            .bind(strategy_id)
+            .bind(&draft.name)
+            .bind(&draft.parameters)
+            .bind(draft.favorite)
+            .bind(&draft.source_optimization_id)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(preset)
+    }
+
+    /// Presets of a strategy, favorites first
+    pub async fn list(&self, workspace_id: &str, strategy_id: &str) -> Result<Vec<ParameterPreset>, PresetError> {
+        let query = format!(
+            "SELECT {} FROM parameter_presets
+             WHERE workspace_id = $1 AND strategy_id = $2
+             ORDER BY favorite DESC, name",
+            PRESET_COLUMNS
+        );
+
+        let presets = sqlx::query_as::<_, ParameterPreset>(&query)
+            .bind(workspace_id)
+            .bind(strategy_id)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(presets)
+    }
+
+    pub async fn get(
+        &self,
+        workspace_id: &str,
+        strategy_id: &str,
+        name: &str,
+    ) -> Result<ParameterPreset, PresetError> {
+        let query = format!(
+            "SELECT {} FROM parameter_presets
+             WHERE workspace_id = $1 AND strategy_id = $2 AND name = $3",
+            PRESET_COLUMNS
+        );
+
+        sqlx::query_as::<_, ParameterPreset>(&query)
+            .bind(workspace_id)
+            .bind(strategy_id)
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| not_found(strategy_id, name))
+    }
+
+    pub async fn set_favorite(
+        &self,
+        workspace_id: &str,
+        strategy_id: &str,
+        name: &str,
+        favorite: bool,
+    ) -> Result<ParameterPreset, PresetError> {
+        let query = format!(
+            "UPDATE parameter_presets SET favorite = $4
+             WHERE workspace_id = $1 AND strategy_id = $2 AND name = $3
+             RETURNING {}",
+            PRESET_COLUMNS
+        );
+
+        sqlx::query_as::<_, ParameterPreset>(&query)
+            .bind(workspace_id)
+            .bind(strategy_id)
+            .bind(name)
+            .bind(favorite)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| not_found(strategy_id, name))
+    }
+
+    pub async fn delete(&self, workspace_id: &str, strategy_id: &str, name: &str) -> Result<(), PresetError> {
+        let result = sqlx::query(
+            "DELETE FROM parameter_presets
+             WHERE workspace_id = $1 AND strategy_id = $2 AND name = $3"
+        )
+        .bind(workspace_id)
+        .bind(strategy_id)
+        .bind(name)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(not_found(strategy_id, name));
+        }
+        Ok(())
+    }
+}
+
+fn not_found(strategy_id: &str, name: &str) -> PresetError {
+    PresetError::NotFound {
+        strategy_id: strategy_id.to_string(),
+        name: name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_overrides_replace_only_the_values_they_name() {
+        let preset = ParameterPreset {
+            id: Uuid::new_v4(),
+            workspace_id: "default".to_string(),
+            strategy_id: "vwap".to_string(),
+            name: "tight".to_string(),
+            parameters: json!({ "fast": 5, "slow": 20 }),
+            favorite: false,
+            source_optimization_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        assert_eq!(preset.merged_with(&json!({ "slow": 30, "stop": 2.5 })), json!({ "fast": 5, "slow": 30, "stop": 2.5 }));
+        assert_eq!(preset.merged_with(&serde_json::Value::Null), preset.parameters);
+        assert_eq!(preset.merged_with(&json!([1, 2])), preset.parameters);
+    }
+}
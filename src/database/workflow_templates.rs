@@ -0,0 +1,159 @@
+//! User-defined workflow templates
+//!
+//! Templates are validated before every write, so anything read back can be
+//! registered with the workflow engine as is. Each update bumps the version.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+
+use super::DbPool;
+use crate::workflow::{check_user_workflow, TemplateIssue, Workflow, WorkflowTemplateDraft};
+
+/// Errors raised by the workflow template store
+#[derive(Debug, thiserror::Error)]
+pub enum WorkflowTemplateError {
+    #[error("No workflow template {0}")]
+    NotFound(String),
+    #[error("Workflow template {0} already exists")]
+    AlreadyExists(String),
+    #[error("Workflow template {0} was modified concurrently")]
+    Conflict(String),
+    #[error("Invalid workflow template ({} issues)", .0.len())]
+    Invalid(Vec<TemplateIssue>),
+    #[error("Workflow templates require a database")]
+    Unavailable,
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// A stored workflow template
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WorkflowTemplate {
+    pub workspace_id: String,
+    pub id: String,
+    pub name: String,
+    pub version: i32,
+    pub definition: Json<Workflow>,
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+const TEMPLATE_COLUMNS: &str = "workspace_id, id, name, version, definition, created_by, created_at, updated_at";
+
+/// Workflow templates persisted in Postgres
+#[derive(Debug, Clone)]
+pub struct WorkflowTemplateStore {
+    pool: DbPool,
+}
+
+impl WorkflowTemplateStore {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Validate a draft into a workflow definition at `version`
+    fn build(draft: WorkflowTemplateDraft, author: &str, version: i32) -> Result<Workflow, WorkflowTemplateError> {
+        let workflow = draft.into_workflow(author, version.max(1) as u32);
+        check_user_workflow(&workflow).map_err(WorkflowTemplateError::Invalid)?;
+        Ok(workflow)
+    }
+
+    pub async fn create(
+        &self,
+        workspace_id: &str,
+        author: &str,
+        draft: WorkflowTemplateDraft,
+    ) -> Result<WorkflowTemplate, WorkflowTemplateError> {
+        let workflow = Self::build(draft, author, 1)?;
+        let query = format!(
+            "INSERT INTO workflow_templates (workspace_id, id, name, version, definition, created_by)
+             VALUES ($1, $2, $3, 1, $4, $5)
+             ON CONFLICT (workspace_id, id) DO NOTHING
+             RETURNING {}",
+            TEMPLATE_COLUMNS
+        );
+
+        sqlx::query_as::<_, WorkflowTemplate>(&query)
+            .bind(workspace_id)
+            .bind(&workflow.id)
+            .bind(&workflow.name)
+            .bind(Json(&workflow))
+            .bind(author)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(WorkflowTemplateError::AlreadyExists(workflow.id))
+    }
+
+    /// Replace a template's definition, bumping its version
+    pub async fn update(
+        &self,
+        workspace_id: &str,
+        author: &str,
+        draft: WorkflowTemplateDraft,
+    ) -> Result<WorkflowTemplate, WorkflowTemplateError> {
+        let current = self.get(workspace_id, &draft.id).await?;
+        let mut workflow = Self::build(draft, author, current.version + 1)?;
+        workflow.metadata.created_at = current.definition.metadata.created_at;
+
+        let query = format!(
+            "UPDATE workflow_templates SET name = $3, definition = $4, version = version + 1
+             WHERE workspace_id = $1 AND id = $2 AND version = $5
+             RETURNING {}",
+            TEMPLATE_COLUMNS
+        );
+
+        // The version check turns a concurrent update into a conflict instead
+        // of silently overwriting it
+        sqlx::query_as::<_, WorkflowTemplate>(&query)
+            .bind(workspace_id)
+            .bind(&workflow.id)
+            .bind(&workflow.name)
+            .bind(Json(&workflow))
+            .bind(current.version)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(WorkflowTemplateError::Conflict(workflow.id))
+    }
+
+    pub async fn list(&self, workspace_id: &str) -> Result<Vec<WorkflowTemplate>, WorkflowTemplateError> {
+        let query = format!(
+            "SELECT {} FROM workflow_templates WHERE workspace_id = $1 ORDER BY name",
+            TEMPLATE_COLUMNS
+        );
+
+        let templates = sqlx::query_as::<_, WorkflowTemplate>(&query)
+            .bind(workspace_id)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(templates)
+    }
+
+    pub async fn get(&self, workspace_id: &str, id: &str) -> Result<WorkflowTemplate, WorkflowTemplateError> {
+        let query = format!(
+            "SELECT {} FROM workflow_templates WHERE workspace_id = $1 AND id = $2",
+            TEMPLATE_COLUMNS
+        );
+
+        sqlx::query_as::<_, WorkflowTemplate>(&query)
+            .bind(workspace_id)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| WorkflowTemplateError::NotFound(id.to_string()))
+    }
+
+    pub async fn delete(&self, workspace_id: &str, id: &str) -> Result<(), WorkflowTemplateError> {
+        let result = sqlx::query("DELETE FROM workflow_templates WHERE workspace_id = $1 AND id = $2")
+            .bind(workspace_id)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(WorkflowTemplateError::NotFound(id.to_string()));
+        }
+        Ok(())
+    }
+}
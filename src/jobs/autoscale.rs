@@ -0,0 +1,435 @@
+//! Autoscaling signals for the job queue
+//!
+//! Workers register themselves in Redis when they start and refresh their
+//! entry while they run, so the number of live workers and the job slots
+//! they offer are known without asking the cloud provider. The autoscaler
+//! compares queue depth and the estimated time to drain it against
+//! thresholds, and when a scale up or down is due it appends the signal to
+//! the job event stream, posts it to a webhook and publishes it as a
+//! monitoring update, leaving the provisioning itself to whatever listens.
+
+use chrono::{DateTime, Utc};
+use redis::AsyncCommands;
+use redis::RedisResult;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use super::JobQueue;
+use crate::monitoring::{AnomalyMonitor, MonitoringUpdate, UpdateType};
+
+/// Completed job durations kept per queue for the backlog estimate
+const DURATION_SAMPLES: isize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoscaleConfig {
+    /// Queued jobs at or above which more workers are asked for
+    #[serde(default = "default_scale_up_depth")]
+    pub scale_up_depth: u64,
+    /// Estimated seconds to drain the queue at or above which more workers
+    /// are asked for; also the backlog the suggested worker count aims at
+    #[serde(default = "default_scale_up_backlog_secs")]
+    pub scale_up_backlog_secs: f64,
+    /// Queued jobs at or below which workers beyond `min_workers` may go
+    #[serde(default)]
+    pub scale_down_depth: u64,
+    #[serde(default)]
+    pub min_workers: usize,
+    #[serde(default = "default_max_workers")]
+    pub max_workers: usize,
+    /// Job duration assumed until the queue has completed any
+    #[serde(default = "default_fallback_job_secs")]
+    pub fallback_job_secs: f64,
+    /// Minimum time between two signals in the same direction
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+    /// Time between evaluations of the background task
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// A worker not seen for this long is no longer counted
+    #[serde(default = "default_worker_ttl_secs")]
+    pub worker_ttl_secs: u64,
+    /// Scale signals are POSTed here as JSON
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+fn default_scale_up_depth() -> u64 {
+    100
+}
+
+fn default_scale_up_backlog_secs() -> f64 {
+    900.0
+}
+
+fn default_max_workers() -> usize {
+    32
+}
+
+fn default_fallback_job_secs() -> f64 {
+    60.0
+}
+
+fn default_cooldown_secs() -> u64 {
+    300
+}
+
+fn default_interval_secs() -> u64 {
+    30
+}
+
+fn default_worker_ttl_secs() -> u64 {
+    60
+}
+
+impl Default for AutoscaleConfig {
+    fn default() -> Self {
+        Self {
+            scale_up_depth: default_scale_up_depth(),
+            scale_up_backlog_secs: default_scale_up_backlog_secs(),
+            scale_down_depth: 0,
+            min_workers: 0,
+            max_workers: default_max_workers(),
+            fallback_job_secs: default_fallback_job_secs(),
+            cooldown_secs: default_cooldown_secs(),
+            interval_secs: default_interval_secs(),
+            worker_ttl_secs: default_worker_ttl_secs(),
+            webhook_url: None,
+        }
+    }
+}
+
+/// A worker process taking jobs from a queue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkerInstance {
+    pub id: String,
+    /// Host or cloud instance the worker runs on
+    #[serde(default)]
+    pub host: Option<String>,
+    /// Jobs the worker runs at once
+    #[serde(default = "default_capacity")]
+    pub capacity: u32,
+    pub registered_at: u64,
+    pub last_seen: u64,
+}
+
+fn default_capacity() -> u32 {
+    1
+}
+
+impl WorkerInstance {
+    pub fn new(id: &str, capacity: u32) -> Self {
+        let now = chrono::Utc::now().timestamp_millis() as u64;
+        Self {
+            id: id.to_string(),
+            host: None,
+            capacity: capacity.max(1),
+            registered_at: now,
+            last_seen: now,
+        }
+    }
+
+    pub fn with_host(mut self, host: &str) -> Self {
+        self.host = Some(host.to_string());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScaleDirection {
+    Up,
+    Down,
+    Hold,
+}
+
+/// What the autoscaler made of a queue at one evaluation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScaleSignal {
+    pub queue: String,
+    pub direction: ScaleDirection,
+    pub queue_depth: u64,
+    /// Live registered workers
+    pub workers: usize,
+    /// Job slots across the live workers
+    pub capacity: u32,
+    /// Mean duration of recently completed jobs
+    pub mean_job_secs: f64,
+    /// Estimated seconds to drain the queue with the current capacity
+    pub backlog_secs: f64,
+    /// Workers needed to bring the backlog under `scale_up_backlog_secs`
+    pub desired_workers: usize,
+    /// Thresholds that were crossed
+    pub reasons: Vec<String>,
+    pub evaluated_at: DateTime<Utc>,
+}
+
+impl ScaleSignal {
+    pub fn evaluate(
+        config: &AutoscaleConfig,
+        queue: &str,
+        queue_depth: u64,
+        workers: &[WorkerInstance],
+        job_secs: &[f64],
+    ) -> Self {
+        let capacity: u32 = workers.iter().map(|worker| worker.capacity.max(1)).sum();
+        let mean_job_secs = if job_secs.is_empty() {
+            config.fallback_job_secs
+        } else {
+            job_secs.iter().sum::<f64>() / job_secs.len() as f64
+        };
+        let work_secs = queue_depth as f64 * mean_job_secs;
+        let backlog_secs = work_secs / capacity.max(1) as f64;
+
+        let slots_per_worker = if workers.is_empty() { 1.0 } else { capacity as f64 / workers.len() as f64 };
+        let needed = (work_secs / config.scale_up_backlog_secs.max(1.0) / slots_per_worker).ceil() as usize;
+        let max_workers = config.max_workers.max(config.min_workers);
+        let desired_workers = needed.clamp(config.min_workers, max_workers);
+
+        let mut reasons = Vec::new();
+        if queue_depth >= config.scale_up_depth {
+            reasons.push(format!("queue depth {} at or above {}", queue_depth, config.scale_up_depth));
+        }
+        if backlog_secs >= config.scale_up_backlog_secs {
+            reasons.push(format!(
+                "backlog of {:.0}s at or above {:.0}s",
+                backlog_secs, config.scale_up_backlog_secs
+            ));
+        }
+        if workers.is_empty() && queue_depth > 0 {
+            reasons.push("jobs queued with no live workers".to_string());
+        }
+
+        let direction = if !reasons.is_empty() && workers.len() < max_workers {
+            ScaleDirection::Up
+        } else if queue_depth <= config.scale_down_depth && workers.len() > config.min_workers {
+            reasons.clear();
+            reasons.push(format!(
+                "queue depth {} at or below {} with {} workers",
+                queue_depth, config.scale_down_depth, workers.len()
+            ));
+            ScaleDirection::Down
+        } else {
+            ScaleDirection::Hold
+        };
+
+        Self {
+            queue: queue.to_string(),
+            direction,
+            queue_depth,
+            workers: workers.len(),
+            capacity,
+            mean_job_secs,
+            backlog_secs,
+            desired_workers,
+            reasons,
+            evaluated_at: Utc::now(),
+        }
+    }
+
+    fn event_type(&self) -> &'static str {
+        match self.direction {
+            ScaleDirection::Up => "ScaleUp",
+            ScaleDirection::Down => "ScaleDown",
+            ScaleDirection::Hold => "ScaleHold",
+        }
+    }
+}
+
+impl JobQueue {
+    pub(super) fn workers_key(&self) -> String {
+        format!("workers:{}", self.queue_name)
+    }
+
+    /// Count `worker` among the queue's live workers
+    pub async fn register_worker(&mut self, worker: &WorkerInstance) -> RedisResult<()> {
+        let key = self.workers_key();
+        let json = serde_json::to_string(worker).unwrap();
+        self.redis_conn.hset(&key, &worker.id, &json).await
+    }
+
+    /// Record that a registered worker is still alive
+    ///
+    /// Returns false when the worker is not registered, for instance after
+    /// it was pruned for going quiet.
+    pub async fn touch_worker(&mut self, worker_id: &str) -> RedisResult<bool> {
+        let key = self.workers_key();
+        let json: Option<String> = self.redis_conn.hget(&key, worker_id).await?;
+        let Some(mut worker) = json.and_then(|json| serde_json::from_str::<WorkerInstance>(&json).ok()) else {
+            return Ok(false);
+        };
+        worker.last_seen = chrono::Utc::now().timestamp_millis() as u64;
+        self.register_worker(&worker).await?;
+        Ok(true)
+    }
+
+    pub async fn deregister_worker(&mut self, worker_id: &str) -> RedisResult<bool> {
+        let key = self.workers_key();
+        let removed: usize = self.redis_conn.hdel(&key, worker_id).await?;
+        let _: () = self.redis_conn.del(self.heartbeat_key(worker_id)).await?;
+        Ok(removed > 0)
+    }
+
+    /// Workers seen within `ttl_secs`; older entries are removed
+    pub async fn live_workers(&mut self, ttl_secs: u64) -> RedisResult<Vec<WorkerInstance>> {
+        let key = self.workers_key();
+        let entries: HashMap<String, String> = self.redis_conn.hgetall(&key).await?;
+        let now = chrono::Utc::now().timestamp_millis() as u64;
+
+        let mut live = Vec::new();
+        for (id, json) in entries {
+            match serde_json::from_str::<WorkerInstance>(&json) {
+                Ok(worker) if now.saturating_sub(worker.last_seen) <= ttl_secs * 1000 => live.push(worker),
+                _ => {
+                    let _: () = self.redis_conn.hdel(&key, &id).await?;
+                }
+            }
+        }
+        live.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(live)
+    }
+
+    /// Remember how long a finished job ran, for backlog estimates
+    pub(super) async fn record_duration(&mut self, seconds: f64) -> RedisResult<()> {
+        let key = format!("durations:{}", self.queue_name);
+        let _: () = self.redis_conn.lpush(&key, seconds).await?;
+        self.redis_conn.ltrim(&key, 0, DURATION_SAMPLES - 1).await
+    }
+
+    /// Durations in seconds of the most recently completed jobs
+    pub async fn recent_job_durations(&mut self) -> RedisResult<Vec<f64>> {
+        let key = format!("durations:{}", self.queue_name);
+        self.redis_conn.lrange(&key, 0, DURATION_SAMPLES - 1).await
+    }
+}
+
+/// Emits scale signals for a job queue
+pub struct Autoscaler {
+    config: AutoscaleConfig,
+    monitor: Option<AnomalyMonitor>,
+    client: reqwest::Client,
+    last_emitted: Option<(ScaleDirection, Instant)>,
+}
+
+impl Autoscaler {
+    pub fn new(config: AutoscaleConfig) -> Self {
+        Self {
+            config,
+            monitor: None,
+            client: reqwest::Client::new(),
+            last_emitted: None,
+        }
+    }
+
+    /// Publish every evaluation as a monitoring update
+    pub fn with_monitor(mut self, monitor: AnomalyMonitor) -> Self {
+        self.monitor = Some(monitor);
+        self
+    }
+
+    /// Evaluate `queue` once, emitting a scale signal when one is due
+    pub async fn evaluate(&mut self, queue: &mut JobQueue) -> RedisResult<ScaleSignal> {
+        let depth = queue.get_queue_length().await?;
+        let workers = queue.live_workers(self.config.worker_ttl_secs).await?;
+        let durations = queue.recent_job_durations().await?;
+        let signal = ScaleSignal::evaluate(&self.config, &queue.queue_name, depth, &workers, &durations);
+
+        if let Some(monitor) = &self.monitor {
+            monitor.publish(MonitoringUpdate::new(
+                UpdateType::Autoscaling,
+                serde_json::to_value(&signal).unwrap_or_default(),
+            ));
+        }
+
+        if self.is_due(signal.direction) {
+            info!(
+                "Scale {:?} for queue {}: {} queued, {} workers, want {} ({})",
+                signal.direction,
+                signal.queue,
+                signal.queue_depth,
+                signal.workers,
+                signal.desired_workers,
+                signal.reasons.join("; ")
+            );
+            queue.events().publish(signal.event_type(), &signal).await?;
+            self.notify_webhook(&signal).await;
+            self.last_emitted = Some((signal.direction, Instant::now()));
+        }
+        Ok(signal)
+    }
+
+    /// Whether a signal in `direction` is wanted, given the last one sent
+    fn is_due(&self, direction: ScaleDirection) -> bool {
+        if direction == ScaleDirection::Hold {
+            return false;
+        }
+        match self.last_emitted {
+            Some((last, at)) if last == direction => at.elapsed() >= Duration::from_secs(self.config.cooldown_secs),
+            _ => true,
+        }
+    }
+
+    async fn notify_webhook(&self, signal: &ScaleSignal) {
+        let Some(url) = &self.config.webhook_url else {
+            return;
+        };
+        let sent = self.client.post(url)
+            .timeout(Duration::from_secs(10))
+            .json(signal)
+            .send()
+            .await;
+        match sent {
+            Ok(response) if !response.status().is_success() => {
+                warn!("Autoscaling webhook {} answered {}", url, response.status());
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Autoscaling webhook {} failed: {}", url, e),
+        }
+    }
+
+    /// Evaluate `queue` every `interval_secs` in the background
+    pub fn spawn(mut self, mut queue: JobQueue) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(self.config.interval_secs.max(1)));
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.evaluate(&mut queue).await {
+                    warn!("Autoscaling evaluation failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backlog_drives_scale_signals() {
+        let config = AutoscaleConfig { scale_up_depth: 500, scale_up_backlog_secs: 600.0, ..Default::default() };
+        let workers = vec![WorkerInstance::new("a", 2), WorkerInstance::new("b", 2)];
+
+        // 60 jobs of 120s over four slots is a half hour backlog
+        let durations = [100.0, 140.0];
+        let signal = ScaleSignal::evaluate(&config, "backtests", 60, &workers, &durations);
+        assert_eq!(signal.direction, ScaleDirection::Up);
+        assert_eq!(signal.capacity, 4);
+        assert!((signal.backlog_secs - 1800.0).abs() < 1e-9);
+        // 7200s of work in 600s at two slots a worker
+        assert_eq!(signal.desired_workers, 6);
+
+        let signal = ScaleSignal::evaluate(&config, "backtests", 10, &workers, &durations);
+        assert_eq!(signal.direction, ScaleDirection::Hold);
+
+        let signal = ScaleSignal::evaluate(&config, "backtests", 0, &workers, &durations);
+        assert_eq!(signal.direction, ScaleDirection::Down);
+        assert_eq!(signal.desired_workers, 0);
+
+        // Queued work with nobody to run it always asks for a worker
+        let signal = ScaleSignal::evaluate(&config, "backtests", 1, &[], &[]);
+        assert_eq!(signal.direction, ScaleDirection::Up);
+        assert_eq!(signal.desired_workers, 1);
+    }
+}
@@ -0,0 +1,216 @@
+//! Durable event bus on Redis Streams
+//!
+//! Pub/sub drops messages for subscribers that are offline. Events appended
+//! to a stream are kept (up to a capped length), so consumers can replay
+//! from the last id they saw, and consumer groups let workers share a
+//! stream and re-claim events a crashed consumer never acknowledged.
+
+use redis::aio::ConnectionManager;
+use redis::streams::{
+    StreamAutoClaimOptions, StreamAutoClaimReply, StreamId, StreamMaxlen, StreamRangeReply,
+    StreamReadOptions, StreamReadReply,
+};
+use redis::{AsyncCommands, RedisResult};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Approximate number of events retained per stream
+const DEFAULT_MAX_LEN: usize = 100_000;
+
+/// An event read back from a stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamEvent {
+    /// Stream entry id, usable as a replay offset
+    pub id: String,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+}
+
+impl StreamEvent {
+    fn from_entry(entry: &StreamId) -> Self {
+        let payload = entry.get::<String>("payload")
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or(serde_json::Value::Null);
+
+        Self {
+            id: entry.id.clone(),
+            event_type: entry.get("type").unwrap_or_default(),
+            payload,
+        }
+    }
+
+    /// Deserialize the payload into a typed event
+    pub fn decode<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_value(self.payload.clone())
+    }
+}
+
+/// Publisher and consumer for one Redis stream
+#[derive(Clone)]
+pub struct EventBus {
+    redis_conn: ConnectionManager,
+    stream: String,
+    max_len: usize,
+}
+
+impl EventBus {
+    pub fn new(redis_conn: ConnectionManager, stream: &str) -> Self {
+        Self {
+            redis_conn,
+            stream: stream.to_string(),
+            max_len: DEFAULT_MAX_LEN,
+        }
+    }
+
+    /// Cap the stream at roughly `max_len` events
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    pub fn stream(&self) -> &str {
+        &self.stream
+    }
+
+    /// Append an event, returning its stream id
+    pub async fn publish<T: Serialize>(&mut self, event_type: &str, payload: &T) -> RedisResult<String> {
+        let json = serde_json::to_string(payload).map_err(|e| {
+            redis::RedisError::from((redis::ErrorKind::TypeError, "event serialization failed", e.to_string()))
+        })?;
+
+        self.redis_conn.xadd_maxlen(
+            &self.stream,
+            StreamMaxlen::Approx(self.max_len),
+            "*",
+            &[("type", event_type), ("payload", json.as_str())],
+        ).await
+    }
+
+    /// Events after `offset` (exclusive), oldest first; `None` starts at the beginning
+    pub async fn replay(&mut self, offset: Option<&str>, count: usize) -> RedisResult<Vec<StreamEvent>> {
+        let start = match offset {
+            Some(id) => format!("({}", id),
+            None => "-".to_string(),
+        };
+
+        let reply: StreamRangeReply = self.redis_conn
+            .xrange_count(&self.stream, start, "+", count)
+            .await?;
+
+        Ok(reply.ids.iter().map(StreamEvent::from_entry).collect())
+    }
+
+    /// Id of the newest event, or `0-0` while the stream is empty
+    pub async fn last_id(&mut self) -> RedisResult<String> {
+        let reply: StreamRangeReply = self.redis_conn
+            .xrevrange_count(&self.stream, "+", "-", 1)
+            .await?;
+
+        Ok(reply.ids.first().map_or_else(|| "0-0".to_string(), |entry| entry.id.clone()))
+    }
+
+    /// Wait up to `block_ms` for events after `offset`, outside any group
+    ///
+    /// Every reader sees every event, for fanning a stream out rather than
+    /// sharing its work. Blocking holds up other commands on the same
+    /// connection, so tail a stream on a bus of its own.
+    pub async fn read_after(&mut self, offset: &str, count: usize, block_ms: usize) -> RedisResult<Vec<StreamEvent>> {
+        let options = StreamReadOptions::default().count(count).block(block_ms);
+        let reply: Option<StreamReadReply> = self.redis_conn
+            .xread_options(&[&self.stream], &[offset], &options)
+            .await?;
+
+        Ok(events_of(reply))
+    }
+
+    /// Create a consumer group; `start_id` is "$" for new events only or "0" for the full history
+    pub async fn ensure_group(&mut self, group: &str, start_id: &str) -> RedisResult<()> {
+        let created: RedisResult<()> = self.redis_conn
+            .xgroup_create_mkstream(&self.stream, group, start_id)
+            .await;
+
+        match created {
+            Err(e) if e.code() == Some("BUSYGROUP") => Ok(()),
+            other => other,
+        }
+    }
+
+    /// Read events not yet delivered to any consumer of the group
+    pub async fn read_group(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        count: usize,
+        block_ms: usize,
+    ) -> RedisResult<Vec<StreamEvent>> {
+        self.read_group_from(group, consumer, ">", count, Some(block_ms)).await
+    }
+
+    /// Events delivered to this consumer that it has not acknowledged yet
+    ///
+    /// Call on startup to finish work interrupted by a crash.
+    pub async fn read_pending(&mut self, group: &str, consumer: &str, count: usize) -> RedisResult<Vec<StreamEvent>> {
+        self.read_group_from(group, consumer, "0", count, None).await
+    }
+
+    /// Take over events another consumer has held unacknowledged for `min_idle_ms`
+    pub async fn claim_stale(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        min_idle_ms: usize,
+        count: usize,
+    ) -> RedisResult<Vec<StreamEvent>> {
+        let reply: StreamAutoClaimReply = self.redis_conn
+            .xautoclaim_options(
+                &self.stream,
+                group,
+                consumer,
+                min_idle_ms,
+                "0-0",
+                StreamAutoClaimOptions::default().count(count),
+            )
+            .await?;
+
+        Ok(reply.claimed.iter().map(StreamEvent::from_entry).collect())
+    }
+
+    /// Acknowledge processed events
+    pub async fn ack(&mut self, group: &str, ids: &[String]) -> RedisResult<usize> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        self.redis_conn.xack(&self.stream, group, ids).await
+    }
+
+    async fn read_group_from(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        id: &str,
+        count: usize,
+        block_ms: Option<usize>,
+    ) -> RedisResult<Vec<StreamEvent>> {
+        let mut options = StreamReadOptions::default()
+            .group(group, consumer)
+            .count(count);
+        if let Some(ms) = block_ms {
+            options = options.block(ms);
+        }
+
+        let reply: Option<StreamReadReply> = self.redis_conn
+            .xread_options(&[&self.stream], &[id], &options)
+            .await?;
+
+        Ok(events_of(reply))
+    }
+}
+
+fn events_of(reply: Option<StreamReadReply>) -> Vec<StreamEvent> {
+    reply
+        .map(|reply| {
+            reply.keys.iter()
+                .flat_map(|key| key.ids.iter().map(StreamEvent::from_entry))
+                .collect()
+        })
+        .unwrap_or_default()
+}
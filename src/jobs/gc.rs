@@ -0,0 +1,345 @@
+//! Garbage collection of orphaned job state in Redis
+//!
+//! Queue entries can outlive the job they point at, finished jobs can be
+//! left queued, and a worker that dies mid-run leaves its job marked running
+//! forever. The collector sweeps the queue and job keys, checks abandoned
+//! runs against the run tables in Postgres, and requeues or settles them.
+//!
+//! Workers heartbeat each job they run, so only a job whose worker stopped
+//! beating goes stale. Settling writes the job back only if it is unchanged
+//! since the sweep read it, so a heartbeat or result landing in between
+//! wins over the collector.
+
+use redis::AsyncCommands;
+use redis::RedisResult;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::{Job, JobEventType, JobQueue, JobStatus, JobType};
+use crate::database::DbPool;
+use crate::monitoring::{AnomalyMonitor, MonitoringUpdate, UpdateType};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GcConfig {
+    /// A running job without a heartbeat for this long is abandoned
+    #[serde(default = "default_running_ttl_secs")]
+    pub running_ttl_secs: u64,
+    /// Time between sweeps of the background task
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+}
+
+fn default_running_ttl_secs() -> u64 {
+    1800
+}
+
+fn default_interval_secs() -> u64 {
+    300
+}
+
+impl Default for GcConfig {
+    fn default() -> Self {
+        Self {
+            running_ttl_secs: default_running_ttl_secs(),
+            interval_secs: default_interval_secs(),
+        }
+    }
+}
+
+/// What one sweep found and did
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GcReport {
+    /// Job keys inspected
+    pub jobs_scanned: usize,
+    /// Queue entries whose job details had expired
+    pub expired_queue_entries: usize,
+    /// Queue entries of jobs that had already finished
+    pub finished_queue_entries: usize,
+    /// Pending or retrying jobs that had fallen out of the queue
+    pub orphans_requeued: usize,
+    /// Abandoned running jobs put back on the queue
+    pub stale_requeued: usize,
+    /// Abandoned running jobs failed for having no retries left
+    pub stale_failed: usize,
+    /// Abandoned running jobs whose outcome was already recorded in Postgres
+    pub reconciled: usize,
+}
+
+impl GcReport {
+    pub fn total_repaired(&self) -> usize {
+        self.expired_queue_entries
+            + self.finished_queue_entries
+            + self.orphans_requeued
+            + self.stale_requeued
+            + self.stale_failed
+            + self.reconciled
+    }
+}
+
+/// How an abandoned running job is settled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Settlement {
+    /// Postgres already has the run's final status
+    Recorded(RecordedOutcome),
+    Requeue,
+    Fail,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RecordedOutcome {
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl RecordedOutcome {
+    fn parse(status: &str) -> Option<Self> {
+        match status {
+            "completed" => Some(RecordedOutcome::Completed),
+            "failed" => Some(RecordedOutcome::Failed),
+            "cancelled" => Some(RecordedOutcome::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+fn is_finished(status: &JobStatus) -> bool {
+    matches!(status, JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled)
+}
+
+/// Whether a job last seen alive at `last_seen` has gone quiet for `ttl_ms`
+fn is_stale(job: &Job, now_ms: u64, ttl_ms: u64) -> bool {
+    let last_seen = job.heartbeat_at.or(job.started_at).unwrap_or(job.created_at);
+    now_ms.saturating_sub(last_seen) > ttl_ms
+}
+
+fn settle(job: &Job, recorded: Option<&str>) -> Settlement {
+    if let Some(outcome) = recorded.and_then(RecordedOutcome::parse) {
+        Settlement::Recorded(outcome)
+    } else if job.retry_count < job.max_retries {
+        Settlement::Requeue
+    } else {
+        Settlement::Fail
+    }
+}
+
+/// Sweeps a job queue for orphaned state
+pub struct JobGarbageCollector {
+    config: GcConfig,
+    database: Option<DbPool>,
+    monitor: Option<AnomalyMonitor>,
+}
+
+impl JobGarbageCollector {
+    pub fn new(config: GcConfig) -> Self {
+        Self {
+            config,
+            database: None,
+            monitor: None,
+        }
+    }
+
+    /// Check abandoned runs against the run tables before requeueing them
+    pub fn with_database(mut self, database: DbPool) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Publish each sweep's counts as a monitoring update
+    pub fn with_monitor(mut self, monitor: AnomalyMonitor) -> Self {
+        self.monitor = Some(monitor);
+        self
+    }
+
+    /// Sweep `queue` once
+    pub async fn collect(&self, queue: &mut JobQueue) -> RedisResult<GcReport> {
+        let mut report = GcReport::default();
+        let now = chrono::Utc::now().timestamp_millis() as u64;
+        let ttl_ms = self.config.running_ttl_secs * 1000;
+        let queue_key = format!("queue:{}", queue.queue_name);
+
+        // Queue entries pointing at expired or finished jobs
+        let queued: Vec<String> = queue.redis_conn.zrange(&queue_key, 0, -1).await?;
+        let mut still_queued = HashSet::new();
+        for job_id in queued {
+            let job_json: Option<String> = queue.redis_conn.get(format!("job:{}", job_id)).await?;
+            match job_json.and_then(|json| serde_json::from_str::<Job>(&json).ok()) {
+                None => {
+                    let _: () = queue.redis_conn.zrem(&queue_key, &job_id).await?;
+                    report.expired_queue_entries += 1;
+                }
+                Some(job) if is_finished(&job.status) => {
+                    let _: () = queue.redis_conn.zrem(&queue_key, &job_id).await?;
+                    report.finished_queue_entries += 1;
+                }
+                Some(_) => {
+                    still_queued.insert(job_id);
+                }
+            }
+        }
+
+        let keys: Vec<String> = {
+            let mut iter = queue.redis_conn.scan_match::<_, String>("job:*").await?;
+            let mut keys = Vec::new();
+            while let Some(key) = iter.next_item().await {
+                keys.push(key);
+            }
+            keys
+        };
+
+        for key in keys {
+            let job_json: Option<String> = queue.redis_conn.get(&key).await?;
+            let Some(json) = job_json else {
+                continue;
+            };
+            let Ok(mut job) = serde_json::from_str::<Job>(&json) else {
+                continue;
+            };
+            report.jobs_scanned += 1;
+
+            match job.status {
+                // Left out of the queue, e.g. by a crash between dequeue and
+                // start; young jobs may simply be starting right now
+                JobStatus::Pending | JobStatus::Retrying
+                    if !still_queued.contains(&job.id) && is_stale(&job, now, ttl_ms) =>
+                {
+                    let _: () = queue.redis_conn.zadd(&queue_key, &job.id, -job.priority).await?;
+                    report.orphans_requeued += 1;
+                }
+                JobStatus::Running if is_stale(&job, now, ttl_ms) => {
+                    let recorded = self.recorded_status(&job).await;
+                    let settlement = settle(&job, recorded.as_deref());
+                    let worker_id = job.worker_id.take();
+                    // A late result from the abandoned run is discarded
+                    job.attempt_key = None;
+                    let event = match settlement {
+                        Settlement::Recorded(outcome) => {
+                            job.status = match outcome {
+                                RecordedOutcome::Completed => JobStatus::Completed,
+                                RecordedOutcome::Failed => JobStatus::Failed,
+                                RecordedOutcome::Cancelled => JobStatus::Cancelled,
+                            };
+                            job.completed_at = Some(now);
+                            match outcome {
+                                RecordedOutcome::Completed => JobEventType::Completed,
+                                RecordedOutcome::Failed => JobEventType::Failed,
+                                RecordedOutcome::Cancelled => JobEventType::Cancelled,
+                            }
+                        }
+                        Settlement::Requeue => {
+                            job.retry_count += 1;
+                            job.status = JobStatus::Retrying;
+                            job.started_at = None;
+                            job.heartbeat_at = None;
+                            JobEventType::Retrying
+                        }
+                        Settlement::Fail => {
+                            job.status = JobStatus::Failed;
+                            job.completed_at = Some(now);
+                            job.error = Some(format!(
+                                "Abandoned: no heartbeat for over {}s and no retries left",
+                                self.config.running_ttl_secs
+                            ));
+                            JobEventType::Failed
+                        }
+                    };
+
+                    // Settle only if no heartbeat or result landed since the read
+                    if !queue.replace_job(&key, &json, &job).await? {
+                        continue;
+                    }
+                    queue.release_quota(&job);
+                    if let Some(worker_id) = worker_id {
+                        queue.untrack_in_flight(&worker_id, &job.id).await?;
+                    }
+                    match settlement {
+                        Settlement::Recorded(_) => report.reconciled += 1,
+                        Settlement::Requeue => {
+                            let _: () = queue.redis_conn.zadd(&queue_key, &job.id, -job.priority).await?;
+                            report.stale_requeued += 1;
+                        }
+                        Settlement::Fail => report.stale_failed += 1,
+                    }
+                    queue.publish_event(event, &job.id).await?;
+                }
+                _ => {}
+            }
+        }
+
+        if report.total_repaired() > 0 {
+            info!("Job garbage collection repaired {} entries: {:?}", report.total_repaired(), report);
+        }
+        if let Some(monitor) = &self.monitor {
+            monitor.publish(MonitoringUpdate::new(
+                UpdateType::JobMaintenance,
+                serde_json::json!({
+                    "queue": queue.queue_name,
+                    "report": report,
+                }),
+            ));
+        }
+
+        Ok(report)
+    }
+
+    /// Final status Postgres holds for the job's run, if any
+    async fn recorded_status(&self, job: &Job) -> Option<String> {
+        let database = self.database.as_ref()?;
+        let table = match job.job_type {
+            JobType::Backtest => "backtest_runs",
+            JobType::Optimization | JobType::WalkForward => "optimization_runs",
+            _ => return None,
+        };
+        let id = Uuid::parse_str(&job.id).ok()?;
+
+        let query = format!("SELECT status FROM {} WHERE id = $1", table);
+        match sqlx::query_scalar::<_, String>(&query).bind(id).fetch_optional(database).await {
+            Ok(status) => status,
+            Err(e) => {
+                warn!("Could not reconcile job {} against {}: {}", job.id, table, e);
+                None
+            }
+        }
+    }
+
+    /// Sweep `queue` every `interval_secs` in the background
+    pub fn spawn(self, mut queue: JobQueue) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(self.config.interval_secs.max(1)));
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.collect(&mut queue).await {
+                    warn!("Job garbage collection failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_abandoned_jobs_are_settled() {
+        let now = 10_000_000;
+        let mut job = Job {
+            status: JobStatus::Running,
+            started_at: Some(now - 4_000_000),
+            heartbeat_at: Some(now - 1_000),
+            max_retries: 1,
+            ..Default::default()
+        };
+        assert!(!is_stale(&job, now, 1_800_000));
+        job.heartbeat_at = None;
+        assert!(is_stale(&job, now, 1_800_000));
+
+        assert_eq!(settle(&job, Some("completed")), Settlement::Recorded(RecordedOutcome::Completed));
+        assert_eq!(settle(&job, Some("running")), Settlement::Requeue);
+        job.retry_count = 1;
+        assert_eq!(settle(&job, None), Settlement::Fail);
+    }
+}
@@ -0,0 +1,37 @@
+//! Data ingestion jobs
+//!
+//! A `DataIngestion` job names a raw file under `data_path` and optionally
+//! a `validation_level`. Workers run these jobs themselves rather than
+//! handing them to their processor: the file is ingested and validated,
+//! and the job's result is the validation summary with its per-class
+//! anomaly counts.
+
+use tracing::info;
+
+use super::{Job, DATASET_PAYLOAD_KEY};
+use crate::data::validation::ingest_validated;
+use crate::data::{IngestionConfig, ValidationLevel};
+use crate::performance::ThreadPools;
+
+/// Payload field overriding the validation level of an ingestion job
+pub const VALIDATION_LEVEL_PAYLOAD_KEY: &str = "validation_level";
+
+/// Ingest and validate the file of a `DataIngestion` job, returning the
+/// validation summary as the job's result
+///
+/// With `pools`, validation runs on the engine's ingestion pool.
+pub async fn run_ingestion(job: &Job, pools: Option<&ThreadPools>) -> Result<serde_json::Value, String> {
+    let path = job.payload.get(DATASET_PAYLOAD_KEY)
+        .and_then(|path| path.as_str())
+        .ok_or_else(|| format!("Ingestion job {} names no {}", job.id, DATASET_PAYLOAD_KEY))?;
+    let mut config = IngestionConfig::default();
+    if let Some(level) = job.payload.get(VALIDATION_LEVEL_PAYLOAD_KEY) {
+        config.validation_level = serde_json::from_value::<ValidationLevel>(level.clone())
+            .map_err(|e| format!("Invalid {}: {}", VALIDATION_LEVEL_PAYLOAD_KEY, e))?;
+    }
+
+    let (ticks, summary) = ingest_validated(path, config, pools).await
+        .map_err(|e| e.to_string())?;
+    info!("Ingestion job {} accepted {} of {} ticks from {}", job.id, ticks.len(), summary.ticks_read, path);
+    serde_json::to_value(&summary).map_err(|e| e.to_string())
+}
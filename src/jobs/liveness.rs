@@ -0,0 +1,357 @@
+//! Worker heartbeats and reaping of dead workers
+//!
+//! A registered worker keeps a heartbeat key in Redis that expires after a
+//! few missed beats, and lists the jobs it is running in an in-flight set.
+//! The reaper looks for workers whose heartbeat has expired, puts their
+//! in-flight jobs back on the queue and drops them from the registry.
+//!
+//! Every run of a job carries an attempt key. The reaper claims the key of
+//! the run it requeues, so concurrent reapers requeue a run only once, and
+//! a worker that was only presumed dead has its late result discarded
+//! rather than overwriting the retry.
+
+use redis::AsyncCommands;
+use redis::RedisResult;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use super::{Job, JobEventType, JobQueue, JobStatus, WorkerInstance, WORKER_HEARTBEAT};
+use crate::monitoring::{AnomalyMonitor, MonitoringUpdate, UpdateType};
+
+/// Beats a worker may miss before it is considered dead
+const MISSED_HEARTBEATS: u64 = 3;
+
+/// Lifetime of a heartbeat key
+pub const HEARTBEAT_TTL_SECS: u64 = WORKER_HEARTBEAT.as_secs() * MISSED_HEARTBEATS;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReaperConfig {
+    /// Time between sweeps of the background task
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+}
+
+fn default_interval_secs() -> u64 {
+    15
+}
+
+impl Default for ReaperConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: default_interval_secs(),
+        }
+    }
+}
+
+/// A worker as seen by the fleet status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerStatus {
+    pub id: String,
+    /// Registry entry; missing for a worker pruned while jobs were in flight
+    pub instance: Option<WorkerInstance>,
+    pub alive: bool,
+    /// Time of the last heartbeat, while its key has not expired
+    pub last_heartbeat: Option<u64>,
+    /// Jobs the worker has started and not yet finished
+    pub in_flight: Vec<String>,
+}
+
+/// Workers of one queue and the work they hold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FleetStatus {
+    pub queue: String,
+    pub queue_depth: u64,
+    pub live_workers: usize,
+    pub dead_workers: usize,
+    /// Job slots offered by live workers
+    pub capacity: u32,
+    /// Jobs running on live workers
+    pub busy: usize,
+    pub workers: Vec<WorkerStatus>,
+}
+
+/// What one sweep of the reaper did
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReapReport {
+    /// Workers found without a heartbeat and removed
+    pub dead_workers: Vec<String>,
+    /// In-flight jobs put back on the queue
+    pub requeued: Vec<String>,
+    /// In-flight jobs failed for having no retries left
+    pub failed: Vec<String>,
+    /// In-flight entries whose run had finished, moved on, or been
+    /// requeued by another reaper
+    pub skipped: usize,
+}
+
+/// How an in-flight job of a dead worker is handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Abandoned {
+    /// The run finished or was taken over before the worker died
+    Settled,
+    Requeue,
+    Fail,
+}
+
+fn abandoned(job: &Job, worker_id: &str) -> Abandoned {
+    if !matches!(job.status, JobStatus::Running) || job.worker_id.as_deref() != Some(worker_id) {
+        Abandoned::Settled
+    } else if job.retry_count < job.max_retries {
+        Abandoned::Requeue
+    } else {
+        Abandoned::Fail
+    }
+}
+
+impl JobQueue {
+    pub(super) fn heartbeat_key(&self, worker_id: &str) -> String {
+        format!("heartbeat:{}:{}", self.queue_name, worker_id)
+    }
+
+    fn in_flight_key(&self, worker_id: &str) -> String {
+        format!("inflight:{}:{}", self.queue_name, worker_id)
+    }
+
+    /// Refresh `worker`'s registry entry and heartbeat, registering it again
+    /// if it was pruned
+    pub async fn beat(&mut self, worker: &WorkerInstance) -> RedisResult<()> {
+        if !self.touch_worker(&worker.id).await? {
+            self.register_worker(worker).await?;
+        }
+        let key = self.heartbeat_key(&worker.id);
+        let now = chrono::Utc::now().timestamp_millis() as u64;
+        self.redis_conn.set_ex(&key, now, HEARTBEAT_TTL_SECS).await
+    }
+
+    /// Beat for `worker` every `WORKER_HEARTBEAT` from a background task
+    ///
+    /// Jobs run on the worker's own task, so a long job does not stop its
+    /// heartbeat. Abort the handle when the worker stops.
+    pub fn spawn_heartbeat(&self, worker: WorkerInstance) -> tokio::task::JoinHandle<()> {
+        let mut queue = self.detached();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(WORKER_HEARTBEAT);
+            loop {
+                interval.tick().await;
+                if let Err(e) = queue.beat(&worker).await {
+                    warn!("Failed to send heartbeat of worker {}: {}", worker.id, e);
+                }
+            }
+        })
+    }
+
+    pub(super) async fn track_in_flight(&mut self, worker_id: &str, job_id: &str) -> RedisResult<()> {
+        let key = self.in_flight_key(worker_id);
+        self.redis_conn.sadd(&key, job_id).await
+    }
+
+    pub(super) async fn untrack_in_flight(&mut self, worker_id: &str, job_id: &str) -> RedisResult<()> {
+        let key = self.in_flight_key(worker_id);
+        self.redis_conn.srem(&key, job_id).await
+    }
+
+    /// Registered workers and workers with jobs in flight, with their liveness
+    pub async fn fleet_status(&mut self) -> RedisResult<FleetStatus> {
+        let registry: HashMap<String, String> = self.redis_conn.hgetall(self.workers_key()).await?;
+        let mut ids: BTreeSet<String> = registry.keys().cloned().collect();
+
+        let prefix = self.in_flight_key("");
+        let in_flight_keys: Vec<String> = {
+            let mut iter = self.redis_conn.scan_match::<_, String>(format!("{}*", prefix)).await?;
+            let mut keys = Vec::new();
+            while let Some(key) = iter.next_item().await {
+                keys.push(key);
+            }
+            keys
+        };
+        ids.extend(in_flight_keys.iter().filter_map(|key| key.strip_prefix(&prefix).map(str::to_string)));
+
+        let mut status = FleetStatus {
+            queue: self.queue_name.clone(),
+            queue_depth: self.get_queue_length().await?,
+            live_workers: 0,
+            dead_workers: 0,
+            capacity: 0,
+            busy: 0,
+            workers: Vec::with_capacity(ids.len()),
+        };
+        for id in ids {
+            let instance = registry.get(&id)
+                .and_then(|json| serde_json::from_str::<WorkerInstance>(json).ok());
+            let last_heartbeat: Option<u64> = self.redis_conn.get(self.heartbeat_key(&id)).await?;
+            let mut in_flight: Vec<String> = self.redis_conn.smembers(self.in_flight_key(&id)).await?;
+            in_flight.sort();
+
+            let alive = last_heartbeat.is_some();
+            if alive {
+                status.live_workers += 1;
+                status.capacity += instance.as_ref().map_or(0, |worker| worker.capacity);
+                status.busy += in_flight.len();
+            } else {
+                status.dead_workers += 1;
+            }
+            status.workers.push(WorkerStatus { id, instance, alive, last_heartbeat, in_flight });
+        }
+        Ok(status)
+    }
+}
+
+/// Finds dead workers and requeues the jobs they held
+pub struct WorkerReaper {
+    config: ReaperConfig,
+    monitor: Option<AnomalyMonitor>,
+}
+
+impl WorkerReaper {
+    pub fn new(config: ReaperConfig) -> Self {
+        Self {
+            config,
+            monitor: None,
+        }
+    }
+
+    /// Publish each sweep that found dead workers as a monitoring update
+    pub fn with_monitor(mut self, monitor: AnomalyMonitor) -> Self {
+        self.monitor = Some(monitor);
+        self
+    }
+
+    /// Sweep `queue` once
+    pub async fn reap(&self, queue: &mut JobQueue) -> RedisResult<ReapReport> {
+        let mut report = ReapReport::default();
+        let fleet = queue.fleet_status().await?;
+
+        for worker in fleet.workers.into_iter().filter(|worker| !worker.alive) {
+            for job_id in &worker.in_flight {
+                self.settle(queue, &worker.id, job_id, &mut report).await?;
+            }
+            let _: () = queue.redis_conn.del(queue.in_flight_key(&worker.id)).await?;
+            queue.deregister_worker(&worker.id).await?;
+            warn!("Worker {} missed its heartbeats; {} jobs were in flight", worker.id, worker.in_flight.len());
+            report.dead_workers.push(worker.id);
+        }
+
+        if !report.dead_workers.is_empty() {
+            info!("Reaped {} dead workers: {:?}", report.dead_workers.len(), report);
+            if let Some(monitor) = &self.monitor {
+                monitor.publish(MonitoringUpdate::new(
+                    UpdateType::JobMaintenance,
+                    serde_json::json!({
+                        "queue": queue.queue_name,
+                        "reaped": report,
+                    }),
+                ));
+            }
+        }
+        Ok(report)
+    }
+
+    async fn settle(
+        &self,
+        queue: &mut JobQueue,
+        worker_id: &str,
+        job_id: &str,
+        report: &mut ReapReport,
+    ) -> RedisResult<()> {
+        let job_key = format!("job:{}", job_id);
+        let job_json: Option<String> = queue.redis_conn.get(&job_key).await?;
+        let Some((json, job)) = job_json.and_then(|json| {
+            let job = serde_json::from_str::<Job>(&json).ok()?;
+            Some((json, job))
+        }) else {
+            report.skipped += 1;
+            return Ok(());
+        };
+        let outcome = abandoned(&job, worker_id);
+        if outcome == Abandoned::Settled {
+            report.skipped += 1;
+            return Ok(());
+        }
+
+        // Claim the run so that only one reaper settles it
+        let claim_key = format!("reaped:{}:{}", job.id, job.attempt_key.as_deref().unwrap_or_default());
+        let claimed: Option<String> = redis::cmd("SET")
+            .arg(&claim_key)
+            .arg(worker_id)
+            .arg("NX")
+            .arg("EX")
+            .arg(86400)
+            .query_async(&mut queue.redis_conn)
+            .await?;
+        if claimed.is_none() {
+            report.skipped += 1;
+            return Ok(());
+        }
+
+        let mut settled = job.clone();
+        let event = if outcome == Abandoned::Requeue {
+            settled.retry_count += 1;
+            settled.status = JobStatus::Retrying;
+            settled.started_at = None;
+            settled.heartbeat_at = None;
+            settled.worker_id = None;
+            settled.attempt_key = None;
+            JobEventType::Retrying
+        } else {
+            settled.status = JobStatus::Failed;
+            settled.completed_at = Some(chrono::Utc::now().timestamp_millis() as u64);
+            settled.error = Some(format!("Worker {} stopped sending heartbeats and no retries are left", worker_id));
+            JobEventType::Failed
+        };
+
+        // Settle only if no result, cancellation or heartbeat landed since the read
+        if !queue.replace_job(&job_key, &json, &settled).await? {
+            report.skipped += 1;
+            return Ok(());
+        }
+        queue.release_quota(&job);
+        if outcome == Abandoned::Requeue {
+            let queue_key = format!("queue:{}", queue.queue_name);
+            let _: () = queue.redis_conn.zadd(&queue_key, &job.id, -job.priority).await?;
+            report.requeued.push(job.id.clone());
+        } else {
+            report.failed.push(job.id.clone());
+        }
+        queue.publish_event(event, &job.id).await?;
+        Ok(())
+    }
+
+    /// Sweep `queue` every `interval_secs` in the background
+    pub fn spawn(self, mut queue: JobQueue) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(self.config.interval_secs.max(1)));
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.reap(&mut queue).await {
+                    warn!("Reaping dead workers failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_the_dead_workers_runs_are_requeued() {
+        let mut job = Job {
+            status: JobStatus::Running,
+            worker_id: Some("worker-a".to_string()),
+            attempt_key: Some("attempt-1".to_string()),
+            max_retries: 1,
+            ..Default::default()
+        };
+        assert_eq!(abandoned(&job, "worker-a"), Abandoned::Requeue);
+        // Already taken over by another worker after an earlier requeue
+        assert_eq!(abandoned(&job, "worker-b"), Abandoned::Settled);
+
+        job.retry_count = 1;
+        assert_eq!(abandoned(&job, "worker-a"), Abandoned::Fail);
+        job.status = JobStatus::Completed;
+        assert_eq!(abandoned(&job, "worker-a"), Abandoned::Settled);
+    }
+}
@@ -1,8 +1,60 @@
+pub mod autoscale;
+pub mod event_bus;
+pub mod gc;
+pub mod ingestion;
+pub mod liveness;
+
 use redis::{aio::ConnectionManager, AsyncCommands, RedisResult};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
+use tracing::{field, info, info_span, warn, Instrument};
 use uuid::Uuid;
 
+use crate::backtesting::{RecoveredBacktest, WalConfig, WalError};
+use crate::monitoring::{AnomalyMonitor, MetricKind};
+use crate::performance::ThreadPools;
+use crate::telemetry::{self, TraceContext};
+use crate::workspace::{WorkspaceError, WorkspaceRegistry};
+
+pub use autoscale::{AutoscaleConfig, Autoscaler, ScaleDirection, ScaleSignal, WorkerInstance};
+pub use event_bus::{EventBus, StreamEvent};
+pub use gc::{GcConfig, GcReport, JobGarbageCollector};
+pub use ingestion::{run_ingestion, VALIDATION_LEVEL_PAYLOAD_KEY};
+pub use liveness::{FleetStatus, ReapReport, ReaperConfig, WorkerReaper, WorkerStatus, HEARTBEAT_TTL_SECS};
+
+/// Time between a registered worker's heartbeats
+const WORKER_HEARTBEAT: Duration = Duration::from_secs(10);
+
+/// Time between heartbeats of each job a worker is running
+const JOB_HEARTBEAT: Duration = Duration::from_secs(30);
+
+/// Times a heartbeat retries when the job changes between read and write
+const HEARTBEAT_ATTEMPTS: usize = 3;
+
+/// Replace a job's stored JSON only if it still reads `ARGV[1]`
+///
+/// Status changes read a job and write it back; the comparison makes the
+/// write fail rather than overwrite a change made in between by another
+/// worker, the reaper or the garbage collector.
+const REPLACE_JOB_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    redis.call('SET', KEYS[1], ARGV[2], 'EX', ARGV[3])
+    return 1
+end
+return 0
+"#;
+
+/// Stream job lifecycle events are appended to
+pub const JOB_EVENTS_STREAM: &str = "job_events";
+
+/// Queued jobs inspected per dequeue when looking for one within its workspace quota
+const DEQUEUE_CANDIDATES: isize = 32;
+
+/// Payload field naming the data file a backtest job reads
+pub const DATASET_PAYLOAD_KEY: &str = "data_path";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Job {
     pub id: String,
@@ -17,6 +69,56 @@ pub struct Job {
     pub result: Option<serde_json::Value>,
     pub retry_count: u32,
     pub max_retries: u32,
+    /// Workspace the job runs in and is charged to
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+    /// Last time the worker running the job reported it alive
+    #[serde(default)]
+    pub heartbeat_at: Option<u64>,
+    /// Trace of the request that queued the job, continued by the worker
+    #[serde(default)]
+    pub trace_context: Option<TraceContext>,
+    /// Registered worker running the job
+    #[serde(default)]
+    pub worker_id: Option<String>,
+    /// Idempotency key of the current run; results reported under an
+    /// earlier run's key are discarded
+    #[serde(default)]
+    pub attempt_key: Option<String>,
+}
+
+impl Job {
+    /// Data file a backtest job reads, if its payload names one
+    pub fn dataset(&self) -> Option<&str> {
+        match self.job_type {
+            JobType::Backtest => self.payload.get(DATASET_PAYLOAD_KEY)?.as_str(),
+            _ => None,
+        }
+    }
+
+    /// Write-ahead log for a backtest job's run, kept in `dir`
+    ///
+    /// The log is named after the job, so `recover_interrupted` can settle
+    /// the job from it if the run is cut short.
+    pub fn write_ahead_log(&self, dir: &Path) -> Option<WalConfig> {
+        matches!(self.job_type, JobType::Backtest).then(|| WalConfig::new(dir, self.id.clone()))
+    }
+
+    /// Span the worker processes the job in, joined to the trace it was
+    /// queued under
+    fn span(&self) -> tracing::Span {
+        let span = info_span!(
+            "job",
+            job_id = %self.id,
+            job_type = ?self.job_type,
+            trace_id = field::Empty,
+        );
+        if let Some(context) = &self.trace_context {
+            span.record("trace_id", context.trace_id.as_str());
+            telemetry::attach(&span, context);
+        }
+        span
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +128,7 @@ pub enum JobType {
     DataIngestion,
     ReportGeneration,
     WalkForward,
+    CacheWarmup,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,20 +144,78 @@ pub enum JobStatus {
 pub struct JobQueue {
     redis_conn: ConnectionManager,
     queue_name: String,
+    events: EventBus,
+    quotas: Option<WorkspaceRegistry>,
+    anomalies: Option<AnomalyMonitor>,
+    /// Registered worker the queue starts jobs for
+    worker_id: Option<String>,
+    /// Attempt keys of the runs started through this handle, by job id
+    attempts: HashMap<String, String>,
 }
 
 impl JobQueue {
     pub async fn new(redis_url: &str, queue_name: &str) -> RedisResult<Self> {
         let client = redis::Client::open(redis_url)?;
         let redis_conn = ConnectionManager::new(client).await?;
+        let events = EventBus::new(redis_conn.clone(), JOB_EVENTS_STREAM);
         
         Ok(Self {
             redis_conn,
             queue_name: queue_name.to_string(),
+            events,
+            quotas: None,
+            anomalies: None,
+            worker_id: None,
+            attempts: HashMap::new(),
         })
     }
+    
+    /// Enforce workspace quotas when jobs are enqueued and started
+    pub fn with_quotas(mut self, quotas: WorkspaceRegistry) -> Self {
+        self.quotas = Some(quotas);
+        self
+    }
+    
+    /// Feed completed job latencies to an anomaly monitor
+    pub fn with_anomaly_monitor(mut self, anomalies: AnomalyMonitor) -> Self {
+        self.anomalies = Some(anomalies);
+        self
+    }
+    
+    /// Durable stream of job lifecycle events
+    pub fn events(&self) -> EventBus {
+        self.events.clone()
+    }
+    
+    /// Job events after `offset` (a stream id), for consumers catching up after downtime
+    pub async fn replay_events(
+        &mut self,
+        offset: Option<&str>,
+        count: usize,
+    ) -> RedisResult<Vec<(String, JobEvent)>> {
+        let events = self.events.replay(offset, count).await?;
+        Ok(events.into_iter()
+            .filter_map(|event| Some((event.id.clone(), event.decode().ok()?)))
+            .collect())
+    }
+    
+    async fn publish_event(&mut self, event_type: JobEventType, job_id: &str) -> RedisResult<String> {
+        let event = JobEvent {
+            event_type,
+            job_id: job_id.to_string(),
+            timestamp: chrono::Utc::now().timestamp_millis() as u64,
+        };
+        self.events.publish(&format!("{:?}", event.event_type), &event).await
+    }
 
-    pub async fn enqueue(&mut self, job: Job) -> RedisResult<String> {
+    pub async fn enqueue(&mut self, mut job: Job) -> RedisResult<String> {
+        if let (Some(quotas), Some(workspace)) = (&self.quotas, &job.workspace_id) {
+            quotas.check_admission(workspace).map_err(quota_error)?;
+        }
+        if job.trace_context.is_none() {
+            job.trace_context = TraceContext::current();
+        }
+        
         let job_id = job.id.clone();
         let job_json = serde_json::to_string(&job).unwrap();
         
@@ -66,60 +227,262 @@ impl JobQueue {
         let queue_key = format!("queue:{}", self.queue_name);
         self.redis_conn.zadd(&queue_key, &job_id, -job.priority).await?; // Negative for higher priority first
         
-        // Publish job event
-        let event = JobEvent {
-            event_type: JobEventType::Enqueued,
-            job_id: job_id.clone(),
-            timestamp: chrono::Utc::now().timestamp_millis() as u64,
-        };
-        let event_json = serde_json::to_string(&event).unwrap();
-        self.redis_conn.publish("job_events", event_json).await?;
+        // Publish event
+        self.publish_event(JobEventType::Enqueued, &job_id).await?;
         
         Ok(job_id)
     }
 
     pub async fn dequeue(&mut self) -> RedisResult<Option<Job>> {
+        Ok(self.dequeue_cohort(1).await?.into_iter().next())
+    }
+    
+    /// Dequeue the next job together with queued backtests of the same dataset
+    ///
+    /// The returned backtests can share a single read of their data file (see
+    /// `SharedScan`). Candidates are still taken in priority order; jobs of
+    /// other types or datasets stay queued. At most `max_jobs` are returned.
+    pub async fn dequeue_cohort(&mut self, max_jobs: usize) -> RedisResult<Vec<Job>> {
         let queue_key = format!("queue:{}", self.queue_name);
         
-        // Get highest priority job
+        // Get highest priority jobs
         let job_ids: Vec<String> = self.redis_conn
-            .zrange_limit(&queue_key, 0, 1)
+            .zrange_limit(&queue_key, 0, DEQUEUE_CANDIDATES)
             .await?;
         
-        if job_ids.is_empty() {
-            return Ok(None);
+        let mut cohort: Vec<Job> = Vec::new();
+        if let Err(e) = self.claim_cohort(&queue_key, &job_ids, max_jobs, &mut cohort).await {
+            self.return_to_queue(&cohort).await;
+            return Err(e);
         }
         
-        let job_id = &job_ids[0];
+        // A job that fails to start goes back on the queue with the rest of
+        // the cohort; those already started are worked on
+        let mut started = Vec::with_capacity(cohort.len());
+        let mut pending = cohort.into_iter();
+        while let Some(job) = pending.next() {
+            match self.start(job.clone()).await {
+                Ok(job) => started.push(job),
+                Err(e) => {
+                    self.undo_start(&job).await;
+                    let unstarted: Vec<Job> = std::iter::once(job).chain(pending.by_ref()).collect();
+                    self.return_to_queue(&unstarted).await;
+                    if started.is_empty() {
+                        return Err(e);
+                    }
+                    warn!("Returned {} jobs to the queue after failing to start them: {}", unstarted.len(), e);
+                    break;
+                }
+            }
+        }
+        Ok(started)
+    }
+    
+    /// Take jobs off the queue into `cohort`, holding a quota slot for each
+    ///
+    /// On error `cohort` holds the jobs already taken, for the caller to
+    /// return.
+    async fn claim_cohort(
+        &mut self,
+        queue_key: &str,
+        job_ids: &[String],
+        max_jobs: usize,
+        cohort: &mut Vec<Job>,
+    ) -> RedisResult<()> {
+        for job_id in job_ids {
+            if cohort.len() >= max_jobs {
+                break;
+            }
+            
+            let job_key = format!("job:{}", job_id);
+            let job_json: Option<String> = self.redis_conn.get(&job_key).await?;
+            let Some(json) = job_json else {
+                // Expired job details, drop the stale queue entry
+                self.redis_conn.zrem(queue_key, job_id).await?;
+                continue;
+            };
+            
+            let job: Job = serde_json::from_str(&json).unwrap();
+            
+            // Once a leader is picked only backtests of its dataset may join
+            if let Some(leader) = cohort.first() {
+                let Some(dataset) = leader.dataset() else {
+                    break;
+                };
+                if job.dataset() != Some(dataset) {
+                    continue;
+                }
+            }
+            
+            // Leave jobs of workspaces at their quota queued for a later pass
+            if let (Some(quotas), Some(workspace)) = (&self.quotas, &job.workspace_id) {
+                if quotas.start_job(workspace).is_err() {
+                    continue;
+                }
+            }
+            
+            // Remove from queue
+            if let Err(e) = self.redis_conn.zrem::<_, _, ()>(queue_key, job_id).await {
+                self.release_quota(&job);
+                return Err(e);
+            }
+            cohort.push(job);
+        }
+        Ok(())
+    }
+    
+    /// Put dequeued jobs that were not started back on the queue and
+    /// release their quota slots
+    async fn return_to_queue(&mut self, jobs: &[Job]) {
+        let queue_key = format!("queue:{}", self.queue_name);
+        for job in jobs {
+            self.release_quota(job);
+            if let Err(e) = self.redis_conn.zadd::<_, _, _, ()>(&queue_key, &job.id, -job.priority).await {
+                warn!("Failed to return job {} to the queue: {}", job.id, e);
+            }
+        }
+    }
+    
+    /// Undo whatever part of `start` went through for `job`, as it was
+    /// before starting
+    async fn undo_start(&mut self, job: &Job) {
+        self.attempts.remove(&job.id);
+        if let Some(worker_id) = self.worker_id.clone() {
+            if let Err(e) = self.untrack_in_flight(&worker_id, &job.id).await {
+                warn!("Failed to untrack job {} on worker {}: {}", job.id, worker_id, e);
+            }
+        }
+        let job_key = format!("job:{}", job.id);
+        let job_json = serde_json::to_string(job).unwrap();
+        if let Err(e) = self.redis_conn.set_ex::<_, _, ()>(&job_key, &job_json, 86400).await {
+            warn!("Failed to restore job {}: {}", job.id, e);
+        }
+    }
+    
+    async fn start(&mut self, mut job: Job) -> RedisResult<Job> {
+        let job_key = format!("job:{}", job.id);
+        job.status = JobStatus::Running;
+        job.started_at = Some(chrono::Utc::now().timestamp_millis() as u64);
+        job.heartbeat_at = job.started_at;
+        job.worker_id = self.worker_id.clone();
+        let attempt_key = Uuid::new_v4().to_string();
+        job.attempt_key = Some(attempt_key.clone());
         
-        // Remove from queue
-        self.redis_conn.zrem(&queue_key, job_id).await?;
+        // List the job as in flight before it is marked running, so a dead
+        // worker never holds a running job the reaper cannot find
+        if let Some(worker_id) = job.worker_id.clone() {
+            self.track_in_flight(&worker_id, &job.id).await?;
+        }
+        self.attempts.insert(job.id.clone(), attempt_key);
         
-        // Get job details
-        let job_key = format!("job:{}", job_id);
-        let job_json: Option<String> = self.redis_conn.get(&job_key).await?;
+        // Update job status
+        let updated_json = serde_json::to_string(&job).unwrap();
+        let _: () = self.redis_conn.set_ex(&job_key, &updated_json, 86400).await?;
         
-        if let Some(json) = job_json {
-            let mut job: Job = serde_json::from_str(&json).unwrap();
-            job.status = JobStatus::Running;
-            job.started_at = Some(chrono::Utc::now().timestamp_millis() as u64);
-            
-            // Update job status
-            let updated_json = serde_json::to_string(&job).unwrap();
-            self.redis_conn.set_ex(&job_key, &updated_json, 86400).await?;
-            
-            // Publish event
-            let event = JobEvent {
-                event_type: JobEventType::Started,
-                job_id: job_id.clone(),
-                timestamp: chrono::Utc::now().timestamp_millis() as u64,
+        // Publish event
+        self.publish_event(JobEventType::Started, &job.id).await?;
+        
+        Ok(job)
+    }
+    
+    /// Record that a running job is still being worked on
+    ///
+    /// Running jobs without a heartbeat for longer than the garbage
+    /// collector's TTL are treated as abandoned. The write only lands if the
+    /// job is unchanged since it was read, so a heartbeat never undoes a
+    /// status change made concurrently.
+    pub async fn heartbeat(&mut self, job_id: &str) -> RedisResult<bool> {
+        let job_key = format!("job:{}", job_id);
+        for _ in 0..HEARTBEAT_ATTEMPTS {
+            let job_json: Option<String> = self.redis_conn.get(&job_key).await?;
+            let Some(json) = job_json else {
+                return Ok(false);
             };
-            let event_json = serde_json::to_string(&event).unwrap();
-            self.redis_conn.publish("job_events", event_json).await?;
+            let Ok(mut job) = serde_json::from_str::<Job>(&json) else {
+                return Ok(false);
+            };
+            if !matches!(job.status, JobStatus::Running) {
+                return Ok(false);
+            }
             
-            Ok(Some(job))
-        } else {
-            Ok(None)
+            job.heartbeat_at = Some(chrono::Utc::now().timestamp_millis() as u64);
+            if self.replace_job(&job_key, &json, &job).await? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+    
+    /// Store `job` under `job_key` if the key still holds `current`
+    pub(super) async fn replace_job(&mut self, job_key: &str, current: &str, job: &Job) -> RedisResult<bool> {
+        let updated_json = serde_json::to_string(job).unwrap();
+        let replaced: i32 = redis::Script::new(REPLACE_JOB_SCRIPT)
+            .key(job_key)
+            .arg(current)
+            .arg(updated_json)
+            .arg(86400)
+            .invoke_async(&mut self.redis_conn)
+            .await?;
+        Ok(replaced == 1)
+    }
+    
+    /// Heartbeat `job_ids` every `JOB_HEARTBEAT` from a background task
+    ///
+    /// Jobs run on the worker's own task, so the beats come from another
+    /// one. Abort the handle once the jobs are finished.
+    fn spawn_job_heartbeat(&self, job_ids: Vec<String>) -> tokio::task::JoinHandle<()> {
+        let mut queue = self.detached();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(JOB_HEARTBEAT);
+            // The first tick is immediate and the job was just started
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                for job_id in &job_ids {
+                    if let Err(e) = queue.heartbeat(job_id).await {
+                        warn!("Failed to send heartbeat of job {}: {}", job_id, e);
+                    }
+                }
+            }
+        })
+    }
+    
+    /// A handle on the same queue for background tasks, without quotas or
+    /// attempt tracking
+    pub(super) fn detached(&self) -> JobQueue {
+        JobQueue {
+            redis_conn: self.redis_conn.clone(),
+            queue_name: self.queue_name.clone(),
+            events: self.events.clone(),
+            quotas: None,
+            anomalies: None,
+            worker_id: None,
+            attempts: HashMap::new(),
+        }
+    }
+    
+    /// Whether `job` was cancelled or requeued after this handle started it,
+    /// e.g. by the reaper while this worker was presumed dead
+    fn superseded(&self, job: &Job) -> bool {
+        self.attempts.get(&job.id).is_some_and(|key| job.attempt_key.as_ref() != Some(key))
+    }
+    
+    /// Forget a run started through this handle once its outcome is reported
+    async fn finish_attempt(&mut self, job_id: &str) -> RedisResult<()> {
+        if self.attempts.remove(job_id).is_some() {
+            if let Some(worker_id) = self.worker_id.clone() {
+                self.untrack_in_flight(&worker_id, job_id).await?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Return a finished job's slot to its workspace and charge the time it ran
+    fn release_quota(&self, job: &Job) {
+        if let (Some(quotas), Some(workspace)) = (&self.quotas, &job.workspace_id) {
+            let now = chrono::Utc::now().timestamp_millis() as u64;
+            let seconds = job.started_at.map_or(0.0, |start| now.saturating_sub(start) as f64 / 1000.0);
+            let _ = quotas.finish_job(workspace, seconds);
         }
     }
 
@@ -129,24 +492,31 @@ impl JobQueue {
         
         if let Some(json) = job_json {
             let mut job: Job = serde_json::from_str(&json).unwrap();
+            if self.superseded(&job) {
+                warn!("Discarding result of job {}, which was cancelled or requeued while it ran", job_id);
+                return self.finish_attempt(job_id).await;
+            }
             job.status = JobStatus::Completed;
             job.completed_at = Some(chrono::Utc::now().timestamp_millis() as u64);
             job.result = Some(result);
+            self.release_quota(&job);
+            
+            if let Some(started) = job.started_at {
+                let latency_ms = job.completed_at.unwrap_or(started).saturating_sub(started);
+                if let Some(anomalies) = &self.anomalies {
+                    anomalies.observe(MetricKind::JobLatencyMs, latency_ms as f64);
+                }
+                self.record_duration(latency_ms as f64 / 1000.0).await?;
+            }
             
             let updated_json = serde_json::to_string(&job).unwrap();
             self.redis_conn.set_ex(&job_key, &updated_json, 86400).await?;
             
             // Publish event
-            let event = JobEvent {
-                event_type: JobEventType::Completed,
-                job_id: job_id.to_string(),
-                timestamp: chrono::Utc::now().timestamp_millis() as u64,
-            };
-            let event_json = serde_json::to_string(&event).unwrap();
-            self.redis_conn.publish("job_events", event_json).await?;
+            self.publish_event(JobEventType::Completed, job_id).await?;
         }
         
-        Ok(())
+        self.finish_attempt(job_id).await
     }
 
     pub async fn fail_job(&mut self, job_id: &str, error: String) -> RedisResult<()> {
@@ -155,6 +525,11 @@ impl JobQueue {
         
         if let Some(json) = job_json {
             let mut job: Job = serde_json::from_str(&json).unwrap();
+            if self.superseded(&job) {
+                warn!("Discarding failure of job {}, which was cancelled or requeued while it ran", job_id);
+                return self.finish_attempt(job_id).await;
+            }
+            self.release_quota(&job);
             
             if job.retry_count < job.max_retries {
                 // Retry the job
@@ -169,13 +544,7 @@ impl JobQueue {
                 self.redis_conn.zadd(&queue_key, &job_id, -(job.priority - 10)).await?;
                 
                 // Publish retry event
-                let event = JobEvent {
-                    event_type: JobEventType::Retrying,
-                    job_id: job_id.to_string(),
-                    timestamp: chrono::Utc::now().timestamp_millis() as u64,
-                };
-                let event_json = serde_json::to_string(&event).unwrap();
-                self.redis_conn.publish("job_events", event_json).await?;
+                self.publish_event(JobEventType::Retrying, job_id).await?;
             } else {
                 // Mark as failed
                 job.status = JobStatus::Failed;
@@ -186,17 +555,79 @@ impl JobQueue {
                 self.redis_conn.set_ex(&job_key, &updated_json, 86400).await?;
                 
                 // Publish failure event
-                let event = JobEvent {
-                    event_type: JobEventType::Failed,
-                    job_id: job_id.to_string(),
-                    timestamp: chrono::Utc::now().timestamp_millis() as u64,
-                };
-                let event_json = serde_json::to_string(&event).unwrap();
-                self.redis_conn.publish("job_events", event_json).await?;
+                self.publish_event(JobEventType::Failed, job_id).await?;
             }
         }
         
-        Ok(())
+        self.finish_attempt(job_id).await
+    }
+
+    /// Settle backtest jobs whose run was interrupted, from the write-ahead logs in `wal_dir`
+    ///
+    /// Called on startup, before workers dequeue. A job still marked running
+    /// is put back on the queue when it has retries left, and failed with an
+    /// account of how far it got otherwise; either way the recovered fills and
+    /// last checkpoint are stored as its partial result. Each log is removed
+    /// once its job is settled.
+    pub async fn recover_interrupted(&mut self, wal_dir: &Path) -> RedisResult<Vec<InterruptedJob>> {
+        let logs = RecoveredBacktest::scan(wal_dir).map_err(wal_error)?;
+        let mut interrupted = Vec::new();
+        
+        for (path, recovered) in logs {
+            let recovered = match recovered {
+                Ok(recovered) => recovered,
+                Err(e) => {
+                    warn!("Skipping unreadable write-ahead log {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            
+            let job_id = recovered.header.run_id.clone();
+            let job_key = format!("job:{}", job_id);
+            let job_json: Option<String> = self.redis_conn.get(&job_key).await?;
+            let Some(mut job) = job_json.and_then(|json| serde_json::from_str::<Job>(&json).ok()) else {
+                warn!("Discarding write-ahead log of unknown job {}", job_id);
+                let _ = std::fs::remove_file(&path);
+                continue;
+            };
+            if !matches!(job.status, JobStatus::Running) {
+                let _ = std::fs::remove_file(&path);
+                continue;
+            }
+            
+            let summary = recovered.summary();
+            job.result = Some(serde_json::json!({ "partial": &recovered }));
+            job.error = Some(summary.clone());
+            self.release_quota(&job);
+            
+            let resumed = job.retry_count < job.max_retries;
+            if resumed {
+                job.retry_count += 1;
+                job.status = JobStatus::Retrying;
+                job.started_at = None;
+            } else {
+                job.status = JobStatus::Failed;
+                job.completed_at = Some(chrono::Utc::now().timestamp_millis() as u64);
+            }
+            
+            let updated_json = serde_json::to_string(&job).unwrap();
+            let _: () = self.redis_conn.set_ex(&job_key, &updated_json, 86400).await?;
+            if resumed {
+                let queue_key = format!("queue:{}", self.queue_name);
+                let _: () = self.redis_conn.zadd(&queue_key, &job_id, -job.priority).await?;
+                self.publish_event(JobEventType::Retrying, &job_id).await?;
+            } else {
+                self.publish_event(JobEventType::Failed, &job_id).await?;
+            }
+            
+            info!("Recovered job {}: {}", job_id, summary);
+            if let Err(e) = std::fs::remove_file(&path) {
+                warn!("Failed to remove write-ahead log {}: {}", path.display(), e);
+            }
+            interrupted.push(InterruptedJob { job_id, resumed, recovered });
+        }
+        
+        Ok(interrupted)
     }
 
     pub async fn get_job_status(&mut self, job_id: &str) -> RedisResult<Option<Job>> {
@@ -221,6 +652,11 @@ impl JobQueue {
         self.redis_conn.zrange_limit(&queue_key, 0, limit).await
     }
 
+    /// Cancel a pending or running job
+    ///
+    /// A running job's attempt key is cleared, so the result its worker
+    /// reports later is discarded as superseded; the quota it holds is
+    /// released here, once.
     pub async fn cancel_job(&mut self, job_id: &str) -> RedisResult<bool> {
         let job_key = format!("job:{}", job_id);
         let job_json: Option<String> = self.redis_conn.get(&job_key).await?;
@@ -229,26 +665,34 @@ impl JobQueue {
             let mut job: Job = serde_json::from_str(&json).unwrap();
             
             if matches!(job.status, JobStatus::Pending | JobStatus::Running) {
+                let pending = matches!(job.status, JobStatus::Pending);
+                let running = !pending;
                 job.status = JobStatus::Cancelled;
                 job.completed_at = Some(chrono::Utc::now().timestamp_millis() as u64);
+                if running {
+                    job.attempt_key = None;
+                }
                 
-                let updated_json = serde_json::to_string(&job).unwrap();
-                self.redis_conn.set_ex(&job_key, &updated_json, 86400).await?;
+                if !self.replace_job(&job_key, &json, &job).await? {
+                    // The job finished or was requeued meanwhile
+                    return Ok(false);
+                }
+                
+                if running {
+                    self.release_quota(&job);
+                    if let Some(worker_id) = job.worker_id.clone() {
+                        self.untrack_in_flight(&worker_id, job_id).await?;
+                    }
+                }
                 
                 // Remove from queue if pending
-                if matches!(job.status, JobStatus::Pending) {
+                if pending {
                     let queue_key = format!("queue:{}", self.queue_name);
                     self.redis_conn.zrem(&queue_key, job_id).await?;
                 }
                 
                 // Publish cancellation event
-                let event = JobEvent {
-                    event_type: JobEventType::Cancelled,
-                    job_id: job_id.to_string(),
-                    timestamp: chrono::Utc::now().timestamp_millis() as u64,
-                };
-                let event_json = serde_json::to_string(&event).unwrap();
-                self.redis_conn.publish("job_events", event_json).await?;
+                self.publish_event(JobEventType::Cancelled, job_id).await?;
                 
                 Ok(true)
             } else {
@@ -260,6 +704,15 @@ impl JobQueue {
     }
 }
 
+/// A backtest job found interrupted on startup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterruptedJob {
+    pub job_id: String,
+    /// Whether the job was queued again rather than failed
+    pub resumed: bool,
+    pub recovered: RecoveredBacktest,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JobEvent {
     pub event_type: JobEventType,
@@ -280,6 +733,9 @@ pub enum JobEventType {
 pub struct JobWorker {
     queue: JobQueue,
     running: bool,
+    instance: Option<WorkerInstance>,
+    wal_dir: Option<PathBuf>,
+    thread_pools: Option<ThreadPools>,
 }
 
 impl JobWorker {
@@ -288,22 +744,92 @@ impl JobWorker {
         Ok(Self {
             queue,
             running: false,
+            instance: None,
+            wal_dir: None,
+            thread_pools: None,
         })
     }
 
+    /// Settle jobs interrupted mid-run from the write-ahead logs in `dir`
+    /// before taking new work
+    ///
+    /// Processors log backtest runs there with `Job::write_ahead_log`. Each
+    /// worker needs a directory of its own, as every log found there is
+    /// taken to be of a run that died.
+    pub fn with_write_ahead_log(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.wal_dir = Some(dir.into());
+        self
+    }
+
+    /// Validate the files of data ingestion jobs on the engine's ingestion pool
+    pub fn with_thread_pools(mut self, thread_pools: ThreadPools) -> Self {
+        self.thread_pools = Some(thread_pools);
+        self
+    }
+
+    async fn recover(&mut self) {
+        let Some(dir) = &self.wal_dir else {
+            return;
+        };
+        match self.queue.recover_interrupted(dir).await {
+            Ok(interrupted) if !interrupted.is_empty() => {
+                info!("Recovered {} interrupted jobs from {}", interrupted.len(), dir.display());
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to recover interrupted jobs from {}: {}", dir.display(), e),
+        }
+    }
+
+    /// Register as `instance` among the queue's live workers while running
+    ///
+    /// Autoscaling counts registered workers, and the reaper requeues the
+    /// jobs of a registered worker whose heartbeat stops. The entry is kept
+    /// fresh while the worker runs and removed when it stops.
+    pub fn with_registration(mut self, instance: WorkerInstance) -> Self {
+        self.queue.worker_id = Some(instance.id.clone());
+        self.instance = Some(instance);
+        self
+    }
+
+    /// Start sending heartbeats if the worker is registered
+    fn spawn_heartbeat(&self) -> Option<tokio::task::JoinHandle<()>> {
+        self.instance.as_ref().map(|instance| self.queue.spawn_heartbeat(instance.clone()))
+    }
+
+    async fn deregister(&mut self, heartbeat: Option<tokio::task::JoinHandle<()>>) {
+        if let Some(heartbeat) = heartbeat {
+            heartbeat.abort();
+        }
+        if let Some(instance) = &self.instance {
+            if let Err(e) = self.queue.deregister_worker(&instance.id).await {
+                warn!("Failed to deregister worker {}: {}", instance.id, e);
+            }
+        }
+    }
+
     pub async fn start<F>(&mut self, processor: F) 
     where
         F: Fn(Job) -> Result<serde_json::Value, String> + Send + 'static,
     {
+        self.recover().await;
         self.running = true;
+        let heartbeat = self.spawn_heartbeat();
         
         while self.running {
             match self.queue.dequeue().await {
                 Ok(Some(job)) => {
                     let job_id = job.id.clone();
+                    let span = job.span();
+                    let beats = self.queue.spawn_job_heartbeat(vec![job_id.clone()]);
                     
-                    // Process job
-                    match processor(job) {
+                    // Ingestion jobs are run here and report their validation summary
+                    let outcome = if matches!(job.job_type, JobType::DataIngestion) {
+                        run_ingestion(&job, self.thread_pools.as_ref()).instrument(span).await
+                    } else {
+                        span.in_scope(|| processor(job))
+                    };
+                    beats.abort();
+                    match outcome {
                         Ok(result) => {
                             if let Err(e) = self.queue.complete_job(&job_id, result).await {
                                 eprintln!("Failed to mark job as complete: {}", e);
@@ -326,6 +852,63 @@ impl JobWorker {
                 }
             }
         }
+        self.deregister(heartbeat).await;
+    }
+
+    /// Process jobs in dataset cohorts of up to `max_cohort` jobs
+    ///
+    /// `processor` returns one result per job, in the order given, so
+    /// backtests over the same data file can be run as one shared scan.
+    pub async fn start_cohorts<F>(&mut self, max_cohort: usize, processor: F)
+    where
+        F: Fn(Vec<Job>) -> Vec<Result<serde_json::Value, String>> + Send + 'static,
+    {
+        self.recover().await;
+        self.running = true;
+        let heartbeat = self.spawn_heartbeat();
+        
+        while self.running {
+            match self.queue.dequeue_cohort(max_cohort).await {
+                Ok(jobs) if !jobs.is_empty() => {
+                    let job_ids: Vec<String> = jobs.iter().map(|job| job.id.clone()).collect();
+                    // One shared scan serves every job, so it runs in a span
+                    // linked to each job's trace
+                    let cohort = info_span!("job_cohort", jobs = jobs.len());
+                    for job in &jobs {
+                        cohort.follows_from(&job.span());
+                    }
+                    let beats = self.queue.spawn_job_heartbeat(job_ids.clone());
+                    // Only backtests share a cohort, so an ingestion job is alone in its own
+                    let ingestion = matches!(jobs.as_slice(), [job] if matches!(job.job_type, JobType::DataIngestion));
+                    let mut results = if ingestion {
+                        vec![run_ingestion(&jobs[0], self.thread_pools.as_ref()).instrument(cohort).await]
+                    } else {
+                        cohort.in_scope(|| processor(jobs))
+                    }.into_iter();
+                    beats.abort();
+                    
+                    for job_id in job_ids {
+                        let outcome = match results.next() {
+                            Some(Ok(result)) => self.queue.complete_job(&job_id, result).await,
+                            Some(Err(error)) => self.queue.fail_job(&job_id, error).await,
+                            None => self.queue.fail_job(&job_id, "No result returned for job".to_string()).await,
+                        };
+                        if let Err(e) = outcome {
+                            warn!("Failed to record result of job {}: {}", job_id, e);
+                        }
+                    }
+                }
+                Ok(_) => {
+                    // No jobs available, wait a bit
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+                Err(e) => {
+                    warn!("Error dequeuing jobs: {}", e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+        self.deregister(heartbeat).await;
     }
 
     pub fn stop(&mut self) {
@@ -348,6 +931,91 @@ impl Default for Job {
             result: None,
             retry_count: 0,
             max_retries: 3,
+            workspace_id: None,
+            heartbeat_at: None,
+            trace_context: None,
+            worker_id: None,
+            attempt_key: None,
         }
     }
-}
\ No newline at end of file
+}
+
+fn quota_error(e: WorkspaceError) -> redis::RedisError {
+    redis::RedisError::from((redis::ErrorKind::ClientError, "workspace quota exceeded", e.to_string()))
+}
+
+fn wal_error(e: WalError) -> redis::RedisError {
+    redis::RedisError::from((redis::ErrorKind::IoError, "write-ahead log scan failed", e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace::ResourceQuota;
+
+    /// A fresh queue on the Redis at `REDIS_URL`, or `None` without one
+    async fn test_queue(workspaces: &WorkspaceRegistry) -> Option<JobQueue> {
+        let url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string());
+        let name = format!("test-{}", Uuid::new_v4());
+        let queue = tokio::time::timeout(Duration::from_secs(2), JobQueue::new(&url, &name)).await;
+        queue.ok()?.ok().map(|queue| queue.with_quotas(workspaces.clone()))
+    }
+
+    #[tokio::test]
+    async fn test_jobs_that_fail_to_start_are_requeued_with_their_quota() {
+        let workspaces = WorkspaceRegistry::new();
+        let quota = ResourceQuota { max_concurrent_jobs: Some(1), ..Default::default() };
+        let workspace = workspaces.create("desk", "alice", quota);
+        let Some(mut queue) = test_queue(&workspaces).await else {
+            println!("Redis not available, skipping dequeue test");
+            return;
+        };
+        queue.worker_id = Some("worker-1".to_string());
+        let job = Job { workspace_id: Some(workspace.id.clone()), ..Default::default() };
+        let job_id = queue.enqueue(job).await.unwrap();
+
+        // In-flight jobs are tracked in a set, so a string there makes starting fail
+        let in_flight = format!("inflight:{}:worker-1", queue.queue_name);
+        let queue_key = format!("queue:{}", queue.queue_name);
+        let _: () = queue.redis_conn.set(&in_flight, "not a set").await.unwrap();
+        assert!(queue.dequeue().await.is_err());
+        let score: Option<f64> = queue.redis_conn.zscore(&queue_key, &job_id).await.unwrap();
+        assert!(score.is_some());
+        assert_eq!(workspaces.get(&workspace.id).unwrap().usage.running_jobs, 0);
+        let stored = queue.get_job_status(&job_id).await.unwrap().unwrap();
+        assert!(matches!(stored.status, JobStatus::Pending));
+
+        let _: () = queue.redis_conn.del(&in_flight).await.unwrap();
+        let started = queue.dequeue().await.unwrap().unwrap();
+        assert_eq!(started.id, job_id);
+        assert_eq!(workspaces.get(&workspace.id).unwrap().usage.running_jobs, 1);
+
+        let _: () = queue.redis_conn.del(&[in_flight, queue_key, format!("job:{}", job_id)]).await.unwrap();
+    }
+    #[tokio::test]
+    async fn test_results_of_cancelled_jobs_are_dropped() {
+        let workspaces = WorkspaceRegistry::new();
+        let workspace = workspaces.create("desk", "alice", ResourceQuota::default());
+        let Some(mut queue) = test_queue(&workspaces).await else {
+            println!("Redis not available, skipping cancellation test");
+            return;
+        };
+        queue.worker_id = Some("worker-1".to_string());
+        let job = Job { workspace_id: Some(workspace.id.clone()), ..Default::default() };
+        let job_id = queue.enqueue(job).await.unwrap();
+        queue.dequeue().await.unwrap().unwrap();
+        assert_eq!(workspaces.get(&workspace.id).unwrap().usage.running_jobs, 1);
+
+        assert!(queue.cancel_job(&job_id).await.unwrap());
+        assert_eq!(workspaces.get(&workspace.id).unwrap().usage.running_jobs, 0);
+        queue.complete_job(&job_id, serde_json::json!({ "late": true })).await.unwrap();
+
+        let stored = queue.get_job_status(&job_id).await.unwrap().unwrap();
+        assert!(matches!(stored.status, JobStatus::Cancelled));
+        assert!(stored.result.is_none());
+        assert_eq!(workspaces.get(&workspace.id).unwrap().usage.running_jobs, 0);
+
+        let queue_key = format!("queue:{}", queue.queue_name);
+        let _: () = queue.redis_conn.del(&[queue_key, format!("job:{}", job_id)]).await.unwrap();
+    }
+}
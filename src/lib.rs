@@ -6,6 +6,13 @@
 //! - Strategy template system for rapid development
 //! - Multi-algorithm parameter optimization
 //! - Real-time performance monitoring
+//!
+//! The HTTP API, Redis job queue, Postgres storage and monitoring WebSocket
+//! server sit behind the `api`, `jobs`, `database` and `websocket` features,
+//! all on by default. With `default-features = false` the crate builds the
+//! backtesting engine and data layer alone. The embedded DuckDB analytics
+//! database is opt-in through the `analytics` feature, and OTLP export of
+//! tracing spans through the `telemetry` feature.
 
 pub mod data;
 pub mod market;
@@ -13,15 +20,25 @@ pub mod strategy;
 pub mod backtesting;
 pub mod optimization;
 pub mod monitoring;
+#[cfg(feature = "api")]
 pub mod api;
 pub mod reporting;
 pub mod analysis;
 pub mod workflow;
+#[cfg(feature = "database")]
 pub mod database;
+#[cfg(feature = "jobs")]
 pub mod jobs;
 pub mod statistics;
 pub mod performance;
 pub mod fault_tolerance;
+pub mod lineage;
+pub mod workspace;
+pub mod timestamp;
+pub mod subscription;
+pub mod telemetry;
+#[cfg(feature = "analytics")]
+pub mod analytics;
 
 // Re-export commonly used types
 pub use data::{TickData, DataLevel, MarketDataType, IngestionConfig};
@@ -0,0 +1,302 @@
+//! Artifact integrity hashing
+//!
+//! Every run records a manifest of SHA-256 digests: the slice of input data
+//! it read, the configuration it ran with and the outputs it produced.
+//! Re-hashing the stored artifacts later and comparing against the manifest
+//! detects results that were silently corrupted or edited after the fact.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use crate::data::TickData;
+
+/// What part of a run an artifact is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactRole {
+    InputData,
+    Config,
+    Output,
+}
+
+/// Content hash of one artifact
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactDigest {
+    pub role: ArtifactRole,
+    pub name: String,
+    /// Hex-encoded SHA-256
+    pub sha256: String,
+    /// Bytes hashed
+    pub size: u64,
+    /// File the digest was taken from, re-read on verification
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+}
+
+/// Digests of everything a run read and produced
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunManifest {
+    pub run_id: String,
+    pub created_at: DateTime<Utc>,
+    pub artifacts: Vec<ArtifactDigest>,
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Bytes `value` is hashed as; object keys come out sorted, so maps hash
+/// the same whatever their iteration order
+pub fn canonical_json<T: Serialize>(value: &T) -> serde_json::Result<Vec<u8>> {
+    serde_json::to_vec(&serde_json::to_value(value)?)
+}
+
+impl RunManifest {
+    pub fn new(run_id: impl Into<String>) -> Self {
+        Self {
+            run_id: run_id.into(),
+            created_at: Utc::now(),
+            artifacts: Vec::new(),
+        }
+    }
+
+    pub fn add_bytes(&mut self, role: ArtifactRole, name: impl Into<String>, bytes: &[u8]) {
+        self.artifacts.push(ArtifactDigest {
+            role,
+            name: name.into(),
+            sha256: sha256_hex(bytes),
+            size: bytes.len() as u64,
+            path: None,
+        });
+    }
+
+    pub fn add_json<T: Serialize>(
+        &mut self,
+        role: ArtifactRole,
+        name: impl Into<String>,
+        value: &T,
+    ) -> serde_json::Result<()> {
+        self.add_bytes(role, name, &canonical_json(value)?);
+        Ok(())
+    }
+
+    /// Hash a file, keeping its path so verification can re-read it
+    pub fn add_file(&mut self, role: ArtifactRole, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)?;
+        self.add_bytes(role, path.display().to_string(), &bytes);
+        if let Some(digest) = self.artifacts.last_mut() {
+            digest.path = Some(path.to_path_buf());
+        }
+        Ok(())
+    }
+
+    /// Hash the ticks a run replayed: timestamp, price and volume of each
+    pub fn add_ticks(&mut self, name: impl Into<String>, ticks: &[TickData]) {
+        let mut hasher = Sha256::new();
+        for tick in ticks {
+            hasher.update(tick.timestamp.to_le_bytes());
+            hasher.update(tick.price.serialize());
+            hasher.update(tick.volume.to_le_bytes());
+        }
+        self.artifacts.push(ArtifactDigest {
+            role: ArtifactRole::InputData,
+            name: name.into(),
+            sha256: format!("{:x}", hasher.finalize()),
+            size: ticks.len() as u64,
+            path: None,
+        });
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ArtifactDigest> {
+        self.artifacts.iter().find(|digest| digest.name == name)
+    }
+
+    /// Re-hash each artifact from the bytes `current` returns for it
+    ///
+    /// Artifacts `current` has no bytes for are read from their recorded
+    /// path; those with neither are reported missing.
+    pub fn verify<F>(&self, mut current: F) -> VerificationReport
+    where
+        F: FnMut(&ArtifactDigest) -> Option<Vec<u8>>,
+    {
+        let checks = self.artifacts.iter()
+            .map(|digest| {
+                let bytes = current(digest)
+                    .or_else(|| digest.path.as_ref().and_then(|path| std::fs::read(path).ok()));
+                let status = match bytes.map(|bytes| sha256_hex(&bytes)) {
+                    None => DigestStatus::Missing,
+                    Some(actual) if actual == digest.sha256 => DigestStatus::Intact,
+                    Some(actual) => DigestStatus::Modified { actual },
+                };
+                ArtifactCheck {
+                    role: digest.role,
+                    name: digest.name.clone(),
+                    expected: digest.sha256.clone(),
+                    status,
+                }
+            })
+            .collect();
+
+        VerificationReport {
+            run_id: self.run_id.clone(),
+            checked_at: Utc::now(),
+            checks,
+        }
+    }
+
+    /// Re-hash only the artifacts recorded from files
+    pub fn verify_files(&self) -> VerificationReport {
+        let mut report = self.verify(|_| None);
+        report.checks.retain(|check| {
+            self.get(&check.name).is_some_and(|digest| digest.path.is_some())
+        });
+        report
+    }
+}
+
+/// Result of re-hashing one artifact
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum DigestStatus {
+    Intact,
+    Modified { actual: String },
+    /// Nothing left to re-hash, e.g. a deleted file
+    Missing,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactCheck {
+    pub role: ArtifactRole,
+    pub name: String,
+    pub expected: String,
+    pub status: DigestStatus,
+}
+
+/// Outcome of verifying a run's manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerificationReport {
+    pub run_id: String,
+    pub checked_at: DateTime<Utc>,
+    pub checks: Vec<ArtifactCheck>,
+}
+
+impl VerificationReport {
+    /// Whether every artifact still matches its recorded digest
+    pub fn is_intact(&self) -> bool {
+        self.checks.iter().all(|check| check.status == DigestStatus::Intact)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &ArtifactCheck> {
+        self.checks.iter().filter(|check| check.status != DigestStatus::Intact)
+    }
+}
+
+/// Manifests of finished runs, keyed by run id
+#[derive(Debug, Clone, Default)]
+pub struct IntegrityStore {
+    inner: Arc<RwLock<HashMap<String, RunManifest>>>,
+    /// Directory manifests are also written to, for `verify_artifacts`
+    dir: Option<PathBuf>,
+}
+
+impl IntegrityStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A store that writes every manifest to `<dir>/<run id>.json`
+    pub fn open(dir: impl AsRef<Path>) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir.as_ref())?;
+        Ok(Self {
+            inner: Default::default(),
+            dir: Some(dir.as_ref().to_path_buf()),
+        })
+    }
+
+    /// File a run's manifest is written to, if the store has a directory
+    pub fn path(&self, run_id: &str) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(format!("{}.json", run_id)))
+    }
+
+    /// Keep a run's manifest, writing it out if the store has a directory
+    ///
+    /// The manifest is kept in memory even if writing it fails.
+    pub fn record(&self, manifest: RunManifest) -> std::io::Result<()> {
+        let saved = match self.path(&manifest.run_id) {
+            Some(path) => manifest.save(path),
+            None => Ok(()),
+        };
+        self.inner.write().unwrap().insert(manifest.run_id.clone(), manifest);
+        saved
+    }
+
+    pub fn get(&self, run_id: &str) -> Option<RunManifest> {
+        self.inner.read().unwrap().get(run_id).cloned()
+    }
+
+    pub fn remove(&self, run_id: &str) -> Option<RunManifest> {
+        self.inner.write().unwrap().remove(run_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verification_detects_changes() {
+        let dir = std::env::temp_dir().join(format!("integrity-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let data = dir.join("ticks.csv");
+        std::fs::write(&data, "1,100.25,3\n").unwrap();
+
+        let config = serde_json::json!({ "b": 2, "a": 1 });
+        let mut manifest = RunManifest::new("run");
+        manifest.add_file(ArtifactRole::InputData, &data).unwrap();
+        manifest.add_json(ArtifactRole::Config, "config", &config).unwrap();
+        manifest.add_bytes(ArtifactRole::Output, "result", b"pnl=10");
+
+        let current = |digest: &ArtifactDigest| match digest.name.as_str() {
+            "config" => canonical_json(&serde_json::json!({ "a": 1, "b": 2 })).ok(),
+            "result" => Some(b"pnl=10".to_vec()),
+            _ => None,
+        };
+        assert!(manifest.verify(current).is_intact());
+
+        std::fs::write(&data, "1,100.25,4\n").unwrap();
+        let report = manifest.verify(current);
+        let failed: Vec<_> = report.failures().map(|check| check.name.clone()).collect();
+        assert_eq!(failed, vec![data.display().to_string()]);
+        assert_eq!(manifest.verify_files().checks.len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(manifest.verify(|_| None).checks[0].status, DigestStatus::Missing));
+    }
+
+    #[test]
+    fn test_recorded_manifests_are_written_for_the_cli() {
+        let dir = std::env::temp_dir().join(format!("manifests-{}", uuid::Uuid::new_v4()));
+        let store = IntegrityStore::open(&dir).unwrap();
+        let mut manifest = RunManifest::new("run-1");
+        manifest.add_bytes(ArtifactRole::Output, "result", b"pnl=10");
+
+        store.record(manifest.clone()).unwrap();
+        assert_eq!(store.get("run-1"), Some(manifest.clone()));
+        assert_eq!(RunManifest::load(store.path("run-1").unwrap()).unwrap(), manifest);
+        assert!(IntegrityStore::new().path("run-1").is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
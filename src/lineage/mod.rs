@@ -0,0 +1,384 @@
+//! Data lineage tracking
+//!
+//! Records how results were produced: raw data files feed normalized
+//! datasets, datasets feed backtests, backtests feed optimizations and
+//! reports. When a data problem is found, the graph answers "what depends
+//! on this file?" so affected results can be invalidated or rerun.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::workspace::DEFAULT_WORKSPACE;
+
+pub mod integrity;
+
+pub use integrity::{
+    ArtifactCheck, ArtifactDigest, ArtifactRole, DigestStatus, IntegrityStore, RunManifest, VerificationReport,
+};
+
+/// Kind of artifact tracked in the lineage graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    RawFile,
+    Dataset,
+    Backtest,
+    Optimization,
+    Report,
+}
+
+/// Whether an artifact can still be trusted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ArtifactStatus {
+    Valid,
+    Invalidated {
+        reason: String,
+        /// Artifact whose invalidation caused this one
+        source: String,
+        at: DateTime<Utc>,
+    },
+}
+
+/// Errors raised by lineage operations
+#[derive(Debug, thiserror::Error)]
+pub enum LineageError {
+    #[error("Unknown artifact: {0}")]
+    UnknownArtifact(String),
+}
+
+/// An artifact in the lineage graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineageNode {
+    pub id: String,
+    pub kind: ArtifactKind,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    /// Content fingerprint for raw files (size and modification time)
+    pub fingerprint: Option<String>,
+    pub metadata: serde_json::Value,
+    pub status: ArtifactStatus,
+    /// Artifacts this one was derived from
+    pub inputs: Vec<String>,
+    /// Workspace the artifact belongs to
+    #[serde(default = "default_workspace")]
+    pub workspace_id: String,
+}
+
+fn default_workspace() -> String {
+    DEFAULT_WORKSPACE.to_string()
+}
+
+impl LineageNode {
+    /// Whether `workspace` may read the artifact: its own, or one shared
+    /// through the default workspace
+    pub fn visible_to(&self, workspace: &str) -> bool {
+        self.workspace_id == workspace || self.workspace_id == DEFAULT_WORKSPACE
+    }
+}
+
+/// Artifact together with everything upstream and downstream of it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineageTrace {
+    pub node: LineageNode,
+    pub upstream: Vec<LineageNode>,
+    pub downstream: Vec<LineageNode>,
+}
+
+/// Directed graph of artifacts and the inputs they were derived from
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LineageGraph {
+    nodes: HashMap<String, LineageNode>,
+    children: HashMap<String, Vec<String>>,
+}
+
+impl LineageGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a derived artifact; unknown inputs are ignored with a warning
+    pub fn record(
+        &mut self,
+        kind: ArtifactKind,
+        name: impl Into<String>,
+        inputs: &[String],
+        metadata: serde_json::Value,
+    ) -> String {
+        self.record_in(DEFAULT_WORKSPACE, kind, name, inputs, metadata)
+    }
+
+    /// Record a derived artifact belonging to `workspace`
+    pub fn record_in(
+        &mut self,
+        workspace: &str,
+        kind: ArtifactKind,
+        name: impl Into<String>,
+        inputs: &[String],
+        metadata: serde_json::Value,
+    ) -> String {
+        let id = Uuid::new_v4().to_string();
+        let inputs: Vec<String> = inputs.iter()
+            .filter(|input| {
+                let known = self.nodes.contains_key(*input);
+                if !known {
+                    warn!("Lineage input {} is not tracked", input);
+                }
+                known
+            })
+            .cloned()
+            .collect();
+
+        for input in &inputs {
+            self.children.entry(input.clone()).or_default().push(id.clone());
+        }
+
+        self.nodes.insert(id.clone(), LineageNode {
+            id: id.clone(),
+            kind,
+            name: name.into(),
+            created_at: Utc::now(),
+            fingerprint: None,
+            metadata,
+            status: ArtifactStatus::Valid,
+            inputs,
+            workspace_id: workspace.to_string(),
+        });
+
+        id
+    }
+
+    /// Record a raw data file, reusing the existing node if its content is unchanged
+    pub fn record_raw_file<P: AsRef<Path>>(&mut self, path: P) -> String {
+        let path = path.as_ref();
+        let name = path.display().to_string();
+        let fingerprint = file_fingerprint(path);
+
+        if let Some(existing) = self.nodes.values()
+            .find(|n| n.kind == ArtifactKind::RawFile && n.name == name && n.fingerprint == fingerprint)
+        {
+            return existing.id.clone();
+        }
+
+        let id = self.record(ArtifactKind::RawFile, name, &[], serde_json::Value::Null);
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.fingerprint = fingerprint;
+        }
+        id
+    }
+
+    pub fn get(&self, id: &str) -> Option<&LineageNode> {
+        self.nodes.get(id)
+    }
+
+    /// All artifacts, newest first
+    pub fn nodes(&self) -> Vec<LineageNode> {
+        let mut nodes: Vec<_> = self.nodes.values().cloned().collect();
+        nodes.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        nodes
+    }
+
+    /// Artifacts with the given name (e.g. a raw file path)
+    pub fn find_by_name(&self, name: &str) -> Vec<LineageNode> {
+        self.nodes.values().filter(|n| n.name == name).cloned().collect()
+    }
+
+    /// Every artifact this one was derived from, directly or transitively
+    pub fn upstream(&self, id: &str) -> Vec<LineageNode> {
+        self.walk(id, |node| node.inputs.clone())
+    }
+
+    /// Every artifact that depends on this one, directly or transitively
+    pub fn downstream(&self, id: &str) -> Vec<LineageNode> {
+        self.walk(id, |node| self.children.get(&node.id).cloned().unwrap_or_default())
+    }
+
+    pub fn trace(&self, id: &str) -> Result<LineageTrace, LineageError> {
+        let node = self.nodes.get(id)
+            .cloned()
+            .ok_or_else(|| LineageError::UnknownArtifact(id.to_string()))?;
+
+        Ok(LineageTrace {
+            upstream: self.upstream(id),
+            downstream: self.downstream(id),
+            node,
+        })
+    }
+
+    /// Mark an artifact and everything derived from it as invalid
+    ///
+    /// Returns the ids of all invalidated artifacts.
+    pub fn invalidate(&mut self, id: &str, reason: &str) -> Result<Vec<String>, LineageError> {
+        if !self.nodes.contains_key(id) {
+            return Err(LineageError::UnknownArtifact(id.to_string()));
+        }
+
+        let mut affected = vec![id.to_string()];
+        affected.extend(self.downstream(id).into_iter().map(|n| n.id));
+
+        let now = Utc::now();
+        for artifact in &affected {
+            if let Some(node) = self.nodes.get_mut(artifact) {
+                node.status = ArtifactStatus::Invalidated {
+                    reason: reason.to_string(),
+                    source: id.to_string(),
+                    at: now,
+                };
+            }
+        }
+
+        info!("Invalidated {} artifacts derived from {}", affected.len(), id);
+        Ok(affected)
+    }
+
+    /// Breadth-first traversal excluding the starting node
+    fn walk<F>(&self, id: &str, next: F) -> Vec<LineageNode>
+    where
+        F: Fn(&LineageNode) -> Vec<String>,
+    {
+        let mut visited = HashSet::from([id.to_string()]);
+        let mut queue = VecDeque::from([id.to_string()]);
+        let mut found = Vec::new();
+
+        while let Some(current) = queue.pop_front() {
+            let Some(node) = self.nodes.get(&current) else { continue };
+            for neighbour in next(node) {
+                if visited.insert(neighbour.clone()) {
+                    if let Some(n) = self.nodes.get(&neighbour) {
+                        found.push(n.clone());
+                    }
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        found
+    }
+}
+
+/// Cheap content fingerprint from file size and modification time
+fn file_fingerprint(path: &Path) -> Option<String> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    Some(format!("{}-{}", metadata.len(), modified))
+}
+
+/// Process-wide lineage graph shared between engines and the API
+#[derive(Debug, Clone, Default)]
+pub struct LineageTracker {
+    inner: Arc<RwLock<LineageGraph>>,
+}
+
+impl LineageTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(
+        &self,
+        kind: ArtifactKind,
+        name: impl Into<String>,
+        inputs: &[String],
+        metadata: serde_json::Value,
+    ) -> String {
+        self.inner.write().unwrap().record(kind, name, inputs, metadata)
+    }
+
+    pub fn record_in(
+        &self,
+        workspace: &str,
+        kind: ArtifactKind,
+        name: impl Into<String>,
+        inputs: &[String],
+        metadata: serde_json::Value,
+    ) -> String {
+        self.inner.write().unwrap().record_in(workspace, kind, name, inputs, metadata)
+    }
+
+    pub fn record_raw_file<P: AsRef<Path>>(&self, path: P) -> String {
+        self.inner.write().unwrap().record_raw_file(path)
+    }
+
+    pub fn get(&self, id: &str) -> Option<LineageNode> {
+        self.inner.read().unwrap().get(id).cloned()
+    }
+
+    pub fn nodes(&self) -> Vec<LineageNode> {
+        self.inner.read().unwrap().nodes()
+    }
+
+    pub fn find_by_name(&self, name: &str) -> Vec<LineageNode> {
+        self.inner.read().unwrap().find_by_name(name)
+    }
+
+    pub fn downstream(&self, id: &str) -> Vec<LineageNode> {
+        self.inner.read().unwrap().downstream(id)
+    }
+
+    pub fn trace(&self, id: &str) -> Result<LineageTrace, LineageError> {
+        self.inner.read().unwrap().trace(id)
+    }
+
+    pub fn invalidate(&self, id: &str, reason: &str) -> Result<Vec<String>, LineageError> {
+        self.inner.write().unwrap().invalidate(id, reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn test_invalidation_propagates_downstream() {
+        let mut graph = LineageGraph::new();
+        let raw = graph.record(ArtifactKind::RawFile, "mnq_0624.parquet", &[], Value::Null);
+        let other = graph.record(ArtifactKind::RawFile, "mnq_0924.parquet", &[], Value::Null);
+        let dataset = graph.record(ArtifactKind::Dataset, "normalized", &[raw.clone()], Value::Null);
+        let backtest = graph.record(ArtifactKind::Backtest, "bt", &[dataset.clone()], Value::Null);
+        let clean = graph.record(ArtifactKind::Backtest, "bt-clean", &[other.clone()], Value::Null);
+        let report = graph.record(ArtifactKind::Report, "report", &[backtest.clone(), clean.clone()], Value::Null);
+
+        let affected = graph.invalidate(&raw, "bad prints").unwrap();
+
+        assert_eq!(affected.len(), 4);
+        assert!(affected.contains(&report));
+        assert_eq!(graph.get(&clean).unwrap().status, ArtifactStatus::Valid);
+        assert!(matches!(graph.get(&report).unwrap().status, ArtifactStatus::Invalidated { .. }));
+    }
+
+    #[test]
+    fn test_trace_upstream() {
+        let mut graph = LineageGraph::new();
+        let raw = graph.record(ArtifactKind::RawFile, "raw", &[], Value::Null);
+        let backtest = graph.record(ArtifactKind::Backtest, "bt", &[raw.clone()], Value::Null);
+        let optimization = graph.record(ArtifactKind::Optimization, "opt", &[backtest.clone()], Value::Null);
+
+        let trace = graph.trace(&optimization).unwrap();
+        let upstream: Vec<_> = trace.upstream.iter().map(|n| n.id.clone()).collect();
+
+        assert_eq!(upstream, vec![backtest, raw]);
+        assert!(trace.downstream.is_empty());
+        assert!(graph.trace("missing").is_err());
+    }
+
+    #[test]
+    fn test_artifacts_are_visible_to_their_workspace_and_shared_ones_to_all() {
+        let mut graph = LineageGraph::new();
+        let raw = graph.record(ArtifactKind::RawFile, "raw", &[], Value::Null);
+        let backtest = graph.record_in("desk", ArtifactKind::Backtest, "bt", &[raw.clone()], Value::Null);
+
+        assert!(graph.get(&raw).unwrap().visible_to("desk"));
+        assert!(graph.get(&raw).unwrap().visible_to("research"));
+        assert!(graph.get(&backtest).unwrap().visible_to("desk"));
+        assert!(!graph.get(&backtest).unwrap().visible_to("research"));
+    }
+}
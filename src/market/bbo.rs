@@ -0,0 +1,168 @@
+//! Top-of-book fast path
+//!
+//! Many strategies only look at the best bid and ask. Reconstructing full
+//! depth for them means maintaining price level maps, validating them and
+//! cloning the whole book into every strategy context. A `BboTracker` keeps
+//! just the best quote of each contract from L1 quotes, and builds a
+//! one-level `OrderBookState` for the context, so an L1-only backtest
+//! spends its time in the strategy rather than in the book.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::data::{DataLevel, MarketDataType, TickData};
+use crate::market::types::{OrderBookState, PriceLevel};
+use crate::timestamp::Timestamp;
+
+/// How much of the book a strategy or run needs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BookDepth {
+    /// Every price level, reconstructed from L2 operations
+    #[default]
+    Full,
+    /// Best bid and ask only, from L1 quotes
+    TopOfBook,
+}
+
+impl BookDepth {
+    /// Depth a run over `ticks` needs when the strategy asks for `requested`
+    ///
+    /// Data without L2 operations only ever has a top of book, so it takes
+    /// the fast path whatever the strategy asks for.
+    pub fn for_run(requested: BookDepth, ticks: &[TickData]) -> BookDepth {
+        if requested == BookDepth::TopOfBook || ticks.iter().all(|t| matches!(t.level, DataLevel::L1)) {
+            BookDepth::TopOfBook
+        } else {
+            BookDepth::Full
+        }
+    }
+}
+
+/// Best bid and ask of one contract
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bbo {
+    pub bid: Option<(Decimal, i32)>,
+    pub ask: Option<(Decimal, i32)>,
+    /// Exchange timestamp of the last quote, in nanoseconds
+    pub last_update: i64,
+    /// Quotes and resets applied
+    pub sequence: u64,
+}
+
+impl Bbo {
+    pub fn spread(&self) -> Option<Decimal> {
+        Some(self.ask?.0 - self.bid?.0)
+    }
+
+    pub fn mid_price(&self) -> Option<Decimal> {
+        Some((self.bid?.0 + self.ask?.0) / Decimal::from(2))
+    }
+
+    /// The quote as a book of at most one level a side
+    ///
+    /// Depth totals are left at zero, as they are when the full book is
+    /// built from L1 quotes alone.
+    pub fn to_state(&self, contract: &str) -> OrderBookState {
+        let updated: DateTime<Utc> = Timestamp::from_nanos(self.last_update).to_utc();
+        let level = |(price, volume): (Decimal, i32)| BTreeMap::from([(price, PriceLevel::new(price, volume, updated))]);
+
+        let mut state = OrderBookState::new(contract.to_string());
+        state.bids = self.bid.map(level).unwrap_or_default();
+        state.asks = self.ask.map(level).unwrap_or_default();
+        state.best_bid = self.bid.map(|(price, _)| price);
+        state.best_ask = self.ask.map(|(price, _)| price);
+        state.last_update = updated;
+        state.sequence = self.sequence;
+        state
+    }
+}
+
+/// Best quotes by contract, updated from L1 quotes
+///
+/// L2 operations are ignored; datasets that carry depth also carry the L1
+/// quotes the tracker follows.
+#[derive(Debug, Clone, Default)]
+pub struct BboTracker {
+    quotes: HashMap<String, Bbo>,
+}
+
+impl BboTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a tick, returning the contract's quote after it
+    pub fn process_tick(&mut self, tick: &TickData) -> &Bbo {
+        if !self.quotes.contains_key(&tick.contract_month) {
+            self.quotes.insert(tick.contract_month.clone(), Bbo::default());
+        }
+        let bbo = self.quotes.get_mut(&tick.contract_month).unwrap();
+
+        if matches!(tick.level, DataLevel::L1) {
+            match tick.mdt {
+                MarketDataType::BidQuote => bbo.bid = Some((tick.price, tick.volume)),
+                MarketDataType::AskQuote => bbo.ask = Some((tick.price, tick.volume)),
+                MarketDataType::BookReset => {
+                    bbo.bid = None;
+                    bbo.ask = None;
+                }
+                _ => return bbo,
+            }
+            bbo.last_update = tick.timestamp;
+            bbo.sequence += 1;
+        }
+        bbo
+    }
+
+    pub fn get(&self, contract: &str) -> Option<&Bbo> {
+        self.quotes.get(contract)
+    }
+
+    pub fn active_contracts(&self) -> Vec<String> {
+        self.quotes.keys().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::order_book::OrderBookManager;
+
+    #[test]
+    fn test_top_of_book_matches_full_book_on_l1_quotes() {
+        let quote = |mdt, ts, price: i64, volume| {
+            TickData::new(DataLevel::L1, mdt, ts, Decimal::new(price, 2), volume, "0624".to_string())
+        };
+        let ticks = vec![
+            quote(MarketDataType::BidQuote, 1, 1_800_000, 12),
+            quote(MarketDataType::AskQuote, 2, 1_800_025, 8),
+            quote(MarketDataType::Trade, 3, 1_800_025, 2),
+            quote(MarketDataType::BidQuote, 4, 1_800_025, 3),
+            quote(MarketDataType::AskQuote, 5, 1_800_050, 20),
+        ];
+        assert_eq!(BookDepth::for_run(BookDepth::Full, &ticks), BookDepth::TopOfBook);
+
+        let mut full = OrderBookManager::new(false);
+        let mut tracker = BboTracker::new();
+        for tick in &ticks {
+            full.process_tick(tick);
+            let top = tracker.process_tick(tick).to_state("0624");
+            let book = full.get_or_create("0624").get_state();
+            assert_eq!((top.best_bid, top.best_ask), (book.best_bid, book.best_ask));
+            assert_eq!(top.spread(), book.spread());
+            assert_eq!(top.bids.len(), book.bids.len());
+        }
+
+        let bbo = tracker.get("0624").unwrap();
+        assert_eq!(bbo.bid, Some((Decimal::new(1_800_025, 2), 3)));
+        assert_eq!(bbo.mid_price(), Some(Decimal::new(18_000_375, 3)));
+        // Trades do not move the quote
+        assert_eq!(bbo.sequence, 4);
+
+        tracker.process_tick(&quote(MarketDataType::BookReset, 6, 0, 0));
+        assert_eq!(tracker.get("0624").unwrap().spread(), None);
+    }
+}
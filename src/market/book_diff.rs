@@ -0,0 +1,331 @@
+//! Level-by-level comparison of two order book states
+//!
+//! When reconstruction is suspected of drifting from the exchange, our book
+//! at some instant is held against a reference for the same instant,
+//! usually a vendor depth snapshot, and every level that is missing, extra
+//! or sized differently is reported with its side, price and distance from
+//! the touch. Only the top `max_depth` levels of each side are compared, so
+//! a reference that stops at ten levels does not flag the rest of our book.
+
+use crate::data::{DataLevel, MarketDataType, TickData};
+use crate::market::event_log::BookImage;
+use crate::market::order_book::OrderBook;
+use crate::market::types::{BookSide, OrderBookState, PriceLevel};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+/// How strictly two books are compared
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookDiffConfig {
+    /// Levels per side compared, counted from the touch
+    #[serde(default = "default_max_depth")]
+    pub max_depth: usize,
+    /// Size difference at a level still counted as a match
+    #[serde(default)]
+    pub volume_tolerance: i32,
+    /// Also compare order counts where both books report them
+    #[serde(default)]
+    pub compare_order_counts: bool,
+}
+
+fn default_max_depth() -> usize {
+    10
+}
+
+impl Default for BookDiffConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: default_max_depth(),
+            volume_tolerance: 0,
+            compare_order_counts: false,
+        }
+    }
+}
+
+/// One price level as either book shows it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LevelQuote {
+    pub price: Decimal,
+    pub volume: i32,
+    #[serde(default)]
+    pub order_count: Option<u32>,
+}
+
+/// Depth of one book, best level first on each side
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookSnapshot {
+    #[serde(default)]
+    pub bids: Vec<LevelQuote>,
+    #[serde(default)]
+    pub asks: Vec<LevelQuote>,
+}
+
+impl BookSnapshot {
+    pub fn from_state(state: &OrderBookState) -> Self {
+        let quote = |level: &PriceLevel| LevelQuote {
+            price: level.price,
+            volume: level.volume,
+            order_count: Some(level.order_count),
+        };
+        Self {
+            bids: state.bids.values().rev().map(quote).collect(),
+            asks: state.asks.values().map(quote).collect(),
+        }
+    }
+
+    pub fn from_image(image: &BookImage) -> Self {
+        let quote = |(price, volume): (&Decimal, &i32)| LevelQuote { price: *price, volume: *volume, order_count: None };
+        Self {
+            bids: image.bids.iter().rev().map(quote).collect(),
+            asks: image.asks.iter().map(quote).collect(),
+        }
+    }
+
+    /// Levels of one side with size, best first, whatever order they were given in
+    fn side(&self, side: BookSide) -> Vec<LevelQuote> {
+        let levels = match side {
+            BookSide::Bid => &self.bids,
+            BookSide::Ask => &self.asks,
+        };
+        let mut levels: Vec<LevelQuote> = levels.iter().filter(|level| level.volume > 0).copied().collect();
+        match side {
+            BookSide::Bid => levels.sort_by_key(|level| Reverse(level.price)),
+            BookSide::Ask => levels.sort_by_key(|level| level.price),
+        }
+        levels
+    }
+}
+
+/// How a level differs between the books
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MismatchKind {
+    /// In the reference but not in our book
+    MissingLevel,
+    /// In our book but not in the reference
+    ExtraLevel,
+    VolumeMismatch,
+    OrderCountMismatch,
+}
+
+/// A level the books disagree on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LevelMismatch {
+    pub side: BookSide,
+    pub price: Decimal,
+    /// Levels from the touch, in the reference when it has the price
+    pub depth: usize,
+    pub kind: MismatchKind,
+    pub ours: Option<LevelQuote>,
+    pub reference: Option<LevelQuote>,
+}
+
+/// Totals over the compared levels of one side
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SideDiff {
+    pub levels_compared: usize,
+    pub levels_matched: usize,
+    pub our_volume: i64,
+    pub reference_volume: i64,
+}
+
+/// Structured result of comparing our book with a reference
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookDiffReport {
+    pub config: BookDiffConfig,
+    pub our_best_bid: Option<Decimal>,
+    pub our_best_ask: Option<Decimal>,
+    pub reference_best_bid: Option<Decimal>,
+    pub reference_best_ask: Option<Decimal>,
+    pub bids: SideDiff,
+    pub asks: SideDiff,
+    /// Mismatches by side, best price first
+    pub mismatches: Vec<LevelMismatch>,
+    pub counts: BTreeMap<String, usize>,
+}
+
+impl BookDiffReport {
+    pub fn compare(ours: &BookSnapshot, reference: &BookSnapshot, config: &BookDiffConfig) -> Self {
+        let (our_bids, our_asks) = (ours.side(BookSide::Bid), ours.side(BookSide::Ask));
+        let (reference_bids, reference_asks) = (reference.side(BookSide::Bid), reference.side(BookSide::Ask));
+
+        let mut mismatches = Vec::new();
+        let bids = compare_side(BookSide::Bid, &our_bids, &reference_bids, config, &mut mismatches);
+        let asks = compare_side(BookSide::Ask, &our_asks, &reference_asks, config, &mut mismatches);
+
+        let mut counts = BTreeMap::new();
+        for mismatch in &mismatches {
+            let kind = serde_json::to_value(mismatch.kind).ok()
+                .and_then(|kind| kind.as_str().map(String::from))
+                .unwrap_or_default();
+            *counts.entry(kind).or_insert(0) += 1;
+        }
+
+        Self {
+            config: config.clone(),
+            our_best_bid: our_bids.first().map(|level| level.price),
+            our_best_ask: our_asks.first().map(|level| level.price),
+            reference_best_bid: reference_bids.first().map(|level| level.price),
+            reference_best_ask: reference_asks.first().map(|level| level.price),
+            bids,
+            asks,
+            mismatches,
+            counts,
+        }
+    }
+
+    pub fn is_match(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    /// Whether both books agree on the best bid and ask prices
+    pub fn touch_matches(&self) -> bool {
+        self.our_best_bid == self.reference_best_bid && self.our_best_ask == self.reference_best_ask
+    }
+
+    /// Levels from the touch of the shallowest mismatch on either side
+    pub fn first_mismatch_depth(&self) -> Option<usize> {
+        self.mismatches.iter().map(|mismatch| mismatch.depth).min()
+    }
+}
+
+/// Compare the top levels of one side, both given best first
+fn compare_side(
+    side: BookSide,
+    ours: &[LevelQuote],
+    reference: &[LevelQuote],
+    config: &BookDiffConfig,
+    mismatches: &mut Vec<LevelMismatch>,
+) -> SideDiff {
+    let depth = config.max_depth.max(1);
+    // A side cut off at `depth` levels only covers down to its last one, so
+    // compare no deeper than the shallower cut-off
+    let better_or_equal = |a: Decimal, b: Decimal| match side {
+        BookSide::Bid => a >= b,
+        BookSide::Ask => a <= b,
+    };
+    let boundary = [ours, reference].into_iter()
+        .filter_map(|levels| levels.get(depth - 1).map(|level| level.price))
+        .reduce(|a, b| if better_or_equal(a, b) { a } else { b });
+    let in_range = |level: &&LevelQuote| boundary.is_none_or(|boundary| better_or_equal(level.price, boundary));
+    let ours: Vec<&LevelQuote> = ours.iter().take(depth).filter(in_range).collect();
+    let reference: Vec<&LevelQuote> = reference.iter().take(depth).filter(in_range).collect();
+
+    let our_levels: HashMap<Decimal, (usize, &LevelQuote)> = ours.iter().enumerate()
+        .map(|(depth, level)| (level.price, (depth, *level)))
+        .collect();
+    let reference_levels: HashMap<Decimal, (usize, &LevelQuote)> = reference.iter().enumerate()
+        .map(|(depth, level)| (level.price, (depth, *level)))
+        .collect();
+    let mut prices: Vec<Decimal> = our_levels.keys().chain(reference_levels.keys()).copied().collect();
+    prices.sort();
+    prices.dedup();
+    if side == BookSide::Bid {
+        prices.reverse();
+    }
+
+    let mut diff = SideDiff {
+        levels_compared: prices.len(),
+        our_volume: ours.iter().map(|level| level.volume as i64).sum(),
+        reference_volume: reference.iter().map(|level| level.volume as i64).sum(),
+        ..Default::default()
+    };
+    for price in prices {
+        let ours = our_levels.get(&price);
+        let reference = reference_levels.get(&price);
+        let kind = match (ours, reference) {
+            (Some(_), None) => Some(MismatchKind::ExtraLevel),
+            (None, Some(_)) => Some(MismatchKind::MissingLevel),
+            (Some((_, ours)), Some((_, reference))) => {
+                if (ours.volume - reference.volume).abs() > config.volume_tolerance {
+                    Some(MismatchKind::VolumeMismatch)
+                } else if config.compare_order_counts
+                    && ours.order_count.zip(reference.order_count).is_some_and(|(a, b)| a != b)
+                {
+                    Some(MismatchKind::OrderCountMismatch)
+                } else {
+                    None
+                }
+            }
+            (None, None) => None,
+        };
+        match kind {
+            Some(kind) => mismatches.push(LevelMismatch {
+                side,
+                price,
+                depth: reference.or(ours).map(|(depth, _)| *depth).unwrap_or_default(),
+                kind,
+                ours: ours.map(|(_, level)| **level),
+                reference: reference.map(|(_, level)| **level),
+            }),
+            None => diff.levels_matched += 1,
+        }
+    }
+    diff
+}
+
+/// Our reconstruction of `contract` after every tick at or before `at_ns`
+///
+/// Only depth updates and book resets are replayed, as the book is built
+/// in backtests.
+pub fn reconstruct_book(ticks: &[TickData], contract: &str, at_ns: i64) -> OrderBookState {
+    let mut book = OrderBook::new(contract.to_string(), false);
+    for tick in ticks.iter().filter(|tick| tick.contract_month == contract && tick.timestamp <= at_ns) {
+        if updates_book(tick) {
+            book.process_tick(tick);
+        }
+    }
+    book.get_state().clone()
+}
+
+/// Whether `tick` is a depth update or book reset
+pub(crate) fn updates_book(tick: &TickData) -> bool {
+    match tick.mdt {
+        MarketDataType::Trade => false,
+        MarketDataType::BookReset => true,
+        _ => matches!(tick.level, DataLevel::L2),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(price: i64, volume: i32) -> LevelQuote {
+        LevelQuote { price: Decimal::new(price, 2), volume, order_count: None }
+    }
+
+    #[test]
+    fn test_reports_each_differing_level() {
+        let ours = BookSnapshot {
+            bids: vec![level(1850000, 10), level(1849975, 7), level(1849950, 4)],
+            asks: vec![level(1850025, 12), level(1850050, 9)],
+        };
+        let reference = BookSnapshot {
+            bids: vec![level(1850000, 10), level(1849975, 9), level(1849925, 3)],
+            asks: vec![level(1850025, 12), level(1850050, 9), level(1850075, 5)],
+        };
+
+        let config = BookDiffConfig { max_depth: 3, ..Default::default() };
+        let report = BookDiffReport::compare(&ours, &reference, &config);
+        assert!(report.touch_matches());
+        let found: Vec<(BookSide, MismatchKind, usize)> = report.mismatches.iter()
+            .map(|mismatch| (mismatch.side, mismatch.kind, mismatch.depth))
+            .collect();
+        assert_eq!(found, vec![
+            (BookSide::Bid, MismatchKind::VolumeMismatch, 1),
+            (BookSide::Bid, MismatchKind::ExtraLevel, 2),
+            // Our asks end above the reference's third level, so it counts as missing;
+            // its third bid is below our cut-off and is not compared
+            (BookSide::Ask, MismatchKind::MissingLevel, 2),
+        ]);
+        assert_eq!(report.first_mismatch_depth(), Some(1));
+        assert_eq!(report.bids.levels_matched, 1);
+
+        // A reference cut at two levels says nothing about deeper ones
+        let config = BookDiffConfig { max_depth: 2, volume_tolerance: 2, ..Default::default() };
+        assert!(BookDiffReport::compare(&ours, &reference, &config).is_match());
+    }
+}
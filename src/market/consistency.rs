@@ -0,0 +1,503 @@
+//! Consistency of L1 trades and quotes with L2 depth
+//!
+//! Vendor depth feeds are reconstructed independently of the L1 tape, so
+//! the two can disagree: depth updates go missing, arrive late or carry the
+//! wrong sizes. The checker replays only the L2 updates through an order
+//! book and holds every L1 event against it:
+//!
+//! - trades should print at the touch, not inside the spread or through a
+//!   queue large enough to fill them
+//! - the size traded at a price should not exceed what the book displayed
+//!   there
+//! - the book should react at a traded price soon after the print
+//! - L1 best bid and ask should show up as the L2 touch soon after they change
+//!
+//! Findings are counted per exchange session with a verdict, so sessions
+//! whose depth cannot be trusted can be discarded.
+
+use crate::data::{DataLevel, MarketDataType, TickData};
+use crate::market::order_book::OrderBook;
+use crate::market::types::{BookSide, OrderBookState};
+use crate::timestamp::{SessionCalendar, Timestamp};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+const NANOS_PER_MILLI: i64 = 1_000_000;
+
+/// Consistency check settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsistencyConfig {
+    /// Time the L2 feed has to reflect a trade or an L1 quote change
+    #[serde(default = "default_reaction_window_ms")]
+    pub reaction_window_ms: u64,
+    /// Volume traded at a price may exceed the displayed queue by this factor
+    /// before it is flagged, allowing for hidden and refilled size
+    #[serde(default = "default_queue_tolerance")]
+    pub queue_tolerance: f64,
+    /// Sessions with fewer consistent events than this share are suspect
+    #[serde(default = "default_suspect_below")]
+    pub suspect_below: f64,
+    /// Sessions with fewer consistent events than this share are unreliable
+    #[serde(default = "default_unreliable_below")]
+    pub unreliable_below: f64,
+    /// Inconsistencies kept as samples per session
+    #[serde(default = "default_max_samples")]
+    pub max_samples: usize,
+    #[serde(default)]
+    pub calendar: SessionCalendar,
+}
+
+fn default_reaction_window_ms() -> u64 {
+    500
+}
+
+fn default_queue_tolerance() -> f64 {
+    1.5
+}
+
+fn default_suspect_below() -> f64 {
+    0.98
+}
+
+fn default_unreliable_below() -> f64 {
+    0.9
+}
+
+fn default_max_samples() -> usize {
+    20
+}
+
+impl Default for ConsistencyConfig {
+    fn default() -> Self {
+        Self {
+            reaction_window_ms: default_reaction_window_ms(),
+            queue_tolerance: default_queue_tolerance(),
+            suspect_below: default_suspect_below(),
+            unreliable_below: default_unreliable_below(),
+            max_samples: default_max_samples(),
+            calendar: SessionCalendar::default(),
+        }
+    }
+}
+
+/// Way an L1 event disagrees with the L2 book
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InconsistencyKind {
+    /// Trade printed while the L2 book lacked a bid or an ask
+    NoBook,
+    /// Trade printed strictly between the L2 bid and ask
+    InsideSpread,
+    /// Trade printed beyond the touch while the touch queue could fill it
+    ThroughTouch,
+    /// More volume traded at a price than the L2 book displayed there
+    ExceedsQueue,
+    /// No L2 update at the traded price within the reaction window
+    NoBookReaction,
+    /// L1 best bid or ask never became the L2 touch within the reaction window
+    QuoteMismatch,
+}
+
+/// One inconsistency found
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Inconsistency {
+    pub kind: InconsistencyKind,
+    pub timestamp: i64,
+    pub price: Decimal,
+    pub volume: i32,
+    pub detail: String,
+}
+
+/// How far a session's depth data can be trusted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsistencyVerdict {
+    Consistent,
+    Suspect,
+    Unreliable,
+}
+
+/// Findings for one exchange session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionConsistency {
+    pub trade_date: NaiveDate,
+    pub trades: usize,
+    pub l1_quotes: usize,
+    pub l2_updates: usize,
+    /// Trades and L1 quote changes checked against the book
+    pub checked_events: usize,
+    /// Checked events with at least one inconsistency
+    pub inconsistent_events: usize,
+    /// Share of checked events without an inconsistency
+    pub consistency_rate: f64,
+    pub counts: BTreeMap<InconsistencyKind, usize>,
+    pub samples: Vec<Inconsistency>,
+    pub verdict: ConsistencyVerdict,
+}
+
+/// Findings for one contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsistencyReport {
+    pub contract: String,
+    pub config: ConsistencyConfig,
+    pub sessions: Vec<SessionConsistency>,
+}
+
+impl ConsistencyReport {
+    /// Check every contract in `ticks`
+    pub fn check(ticks: &[TickData], config: &ConsistencyConfig) -> Vec<Self> {
+        let mut checkers: BTreeMap<&str, ConsistencyChecker> = BTreeMap::new();
+        for tick in ticks {
+            checkers.entry(tick.contract_month.as_str())
+                .or_insert_with(|| ConsistencyChecker::new(tick.contract_month.clone(), config.clone()))
+                .push(tick);
+        }
+        checkers.into_values().map(ConsistencyChecker::finish).collect()
+    }
+
+    /// Sessions whose depth data should not be used
+    pub fn unreliable_sessions(&self) -> Vec<NaiveDate> {
+        self.sessions.iter()
+            .filter(|s| s.verdict == ConsistencyVerdict::Unreliable)
+            .map(|s| s.trade_date)
+            .collect()
+    }
+}
+
+/// Trade waiting for the book to react at its price
+#[derive(Debug, Clone)]
+struct PendingTrade {
+    side: BookSide,
+    /// Already counted as inconsistent when it printed
+    flagged: bool,
+    price: Decimal,
+    volume: i32,
+    timestamp: i64,
+    date: Option<NaiveDate>,
+}
+
+/// L1 quote waiting to appear as the L2 touch
+#[derive(Debug, Clone)]
+struct PendingQuote {
+    price: Decimal,
+    timestamp: i64,
+    date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Default)]
+struct SessionTally {
+    trades: usize,
+    l1_quotes: usize,
+    l2_updates: usize,
+    checked: usize,
+    inconsistent: usize,
+    counts: BTreeMap<InconsistencyKind, usize>,
+    samples: Vec<Inconsistency>,
+}
+
+/// Streams one contract's ticks and checks L1 against L2
+pub struct ConsistencyChecker {
+    contract: String,
+    config: ConsistencyConfig,
+    /// Book rebuilt from L2 updates only
+    book: OrderBook,
+    /// Volume traded at each side and price since the book last updated it
+    consumed: HashMap<(usize, Decimal), i64>,
+    pending_trades: VecDeque<PendingTrade>,
+    pending_quotes: [Option<PendingQuote>; 2],
+    sessions: BTreeMap<NaiveDate, SessionTally>,
+}
+
+impl ConsistencyChecker {
+    pub fn new(contract: String, config: ConsistencyConfig) -> Self {
+        Self {
+            book: OrderBook::new(contract.clone(), false),
+            contract,
+            config,
+            consumed: HashMap::new(),
+            pending_trades: VecDeque::new(),
+            pending_quotes: [None, None],
+            sessions: BTreeMap::new(),
+        }
+    }
+
+    pub fn push(&mut self, tick: &TickData) {
+        self.expire(tick.timestamp);
+        let date = self.config.calendar.trading_date(Timestamp::from_nanos(tick.timestamp));
+
+        match (&tick.level, &tick.mdt) {
+            (_, MarketDataType::Trade) => self.on_trade(tick, date),
+            (DataLevel::L2, _) => self.on_depth(tick, date),
+            (DataLevel::L1, MarketDataType::BidQuote) => self.on_quote(tick, BookSide::Bid, date),
+            (DataLevel::L1, MarketDataType::AskQuote) => self.on_quote(tick, BookSide::Ask, date),
+            (DataLevel::L1, MarketDataType::BookReset) => {
+                self.book.process_tick(tick);
+                self.consumed.clear();
+            }
+            _ => {}
+        }
+    }
+
+    /// Settle what can be settled and report per session
+    ///
+    /// Checks still inside their reaction window when the data ends are not
+    /// counted either way.
+    pub fn finish(self) -> ConsistencyReport {
+        let config = self.config;
+        let sessions = self.sessions.into_iter()
+            .map(|(trade_date, tally)| {
+                let consistency_rate = if tally.checked == 0 {
+                    1.0
+                } else {
+                    1.0 - tally.inconsistent as f64 / tally.checked as f64
+                };
+                let verdict = if consistency_rate < config.unreliable_below {
+                    ConsistencyVerdict::Unreliable
+                } else if consistency_rate < config.suspect_below {
+                    ConsistencyVerdict::Suspect
+                } else {
+                    ConsistencyVerdict::Consistent
+                };
+                SessionConsistency {
+                    trade_date,
+                    trades: tally.trades,
+                    l1_quotes: tally.l1_quotes,
+                    l2_updates: tally.l2_updates,
+                    checked_events: tally.checked,
+                    inconsistent_events: tally.inconsistent,
+                    consistency_rate,
+                    counts: tally.counts,
+                    samples: tally.samples,
+                    verdict,
+                }
+            })
+            .collect();
+
+        ConsistencyReport { contract: self.contract, config, sessions }
+    }
+
+    fn on_trade(&mut self, tick: &TickData, date: Option<NaiveDate>) {
+        if let Some(tally) = self.tally(date) {
+            tally.trades += 1;
+            tally.checked += 1;
+        }
+
+        let state = self.book.get_state();
+        let (Some(bid), Some(ask)) = (best(state, BookSide::Bid), best(state, BookSide::Ask)) else {
+            self.flag(date, InconsistencyKind::NoBook, tick, "no two-sided L2 book".to_string());
+            return;
+        };
+        let side = if tick.price >= ask {
+            BookSide::Ask
+        } else if tick.price <= bid {
+            BookSide::Bid
+        } else {
+            self.flag(date, InconsistencyKind::InsideSpread, tick, format!("L2 spread {} / {}", bid, ask));
+            return;
+        };
+        let touch = if side == BookSide::Ask { ask } else { bid };
+
+        let consumed = {
+            let consumed = self.consumed.entry((side_index(side), tick.price)).or_insert(0);
+            *consumed += tick.volume as i64;
+            *consumed
+        };
+        let state = self.book.get_state();
+        let displayed = queue(state, side, tick.price);
+        let touch_queue = queue(state, side, touch);
+
+        let mut issue = None;
+        if tick.price != touch && consumed <= touch_queue {
+            issue = Some((
+                InconsistencyKind::ThroughTouch,
+                format!("printed beyond touch {} holding {}", touch, touch_queue),
+            ));
+        } else if consumed as f64 > displayed as f64 * self.config.queue_tolerance {
+            issue = Some((
+                InconsistencyKind::ExceedsQueue,
+                format!("{} traded against {} displayed", consumed, displayed),
+            ));
+        }
+        let flagged = issue.is_some();
+        if let Some((kind, detail)) = issue {
+            self.flag(date, kind, tick, detail);
+        }
+
+        self.pending_trades.push_back(PendingTrade {
+            side,
+            flagged,
+            price: tick.price,
+            volume: tick.volume,
+            timestamp: tick.timestamp,
+            date,
+        });
+    }
+
+    fn on_depth(&mut self, tick: &TickData, date: Option<NaiveDate>) {
+        if let Some(tally) = self.tally(date) {
+            tally.l2_updates += 1;
+        }
+        self.book.process_tick(tick);
+
+        let side = match tick.mdt {
+            MarketDataType::BidQuote | MarketDataType::ImpliedBid => BookSide::Bid,
+            MarketDataType::AskQuote | MarketDataType::ImpliedAsk => BookSide::Ask,
+            _ => return,
+        };
+        self.consumed.remove(&(side_index(side), tick.price));
+        self.pending_trades.retain(|trade| !(trade.side == side && trade.price == tick.price));
+
+        let index = side_index(side);
+        let touch = best(self.book.get_state(), side);
+        if self.pending_quotes[index].as_ref().is_some_and(|quote| Some(quote.price) == touch) {
+            self.pending_quotes[index] = None;
+        }
+    }
+
+    fn on_quote(&mut self, tick: &TickData, side: BookSide, date: Option<NaiveDate>) {
+        if let Some(tally) = self.tally(date) {
+            tally.l1_quotes += 1;
+        }
+
+        // Only changes of the best price are checked; size-only updates would
+        // count the same level many times over
+        let index = side_index(side);
+        if self.pending_quotes[index].as_ref().is_some_and(|quote| quote.price == tick.price) {
+            return;
+        }
+        if let Some(tally) = self.tally(date) {
+            tally.checked += 1;
+        }
+        self.pending_quotes[index] = (best(self.book.get_state(), side) != Some(tick.price))
+            .then(|| PendingQuote { price: tick.price, timestamp: tick.timestamp, date });
+    }
+
+    /// Flag checks whose reaction window closed before `now`
+    fn expire(&mut self, now: i64) {
+        let window = self.config.reaction_window_ms as i64 * NANOS_PER_MILLI;
+
+        while self.pending_trades.front().is_some_and(|trade| now - trade.timestamp > window) {
+            let trade = self.pending_trades.pop_front().unwrap();
+            self.record(trade.date, !trade.flagged, Inconsistency {
+                kind: InconsistencyKind::NoBookReaction,
+                timestamp: trade.timestamp,
+                price: trade.price,
+                volume: trade.volume,
+                detail: format!("no {:?} update at the traded price within {}ms", trade.side, self.config.reaction_window_ms),
+            });
+        }
+
+        for index in 0..self.pending_quotes.len() {
+            if self.pending_quotes[index].as_ref().is_some_and(|quote| now - quote.timestamp > window) {
+                let quote = self.pending_quotes[index].take().unwrap();
+                let side = if index == 0 { BookSide::Bid } else { BookSide::Ask };
+                let touch = best(self.book.get_state(), side);
+                self.record(quote.date, true, Inconsistency {
+                    kind: InconsistencyKind::QuoteMismatch,
+                    timestamp: quote.timestamp,
+                    price: quote.price,
+                    volume: 0,
+                    detail: format!("L1 {:?} never matched; L2 touch {:?}", side, touch),
+                });
+            }
+        }
+    }
+
+    fn flag(&mut self, date: Option<NaiveDate>, kind: InconsistencyKind, tick: &TickData, detail: String) {
+        self.record(date, true, Inconsistency {
+            kind,
+            timestamp: tick.timestamp,
+            price: tick.price,
+            volume: tick.volume,
+            detail,
+        });
+    }
+
+    /// Count a finding, and its event unless the event was already counted
+    fn record(&mut self, date: Option<NaiveDate>, new_event: bool, inconsistency: Inconsistency) {
+        let max_samples = self.config.max_samples;
+        let Some(tally) = self.tally(date) else {
+            return;
+        };
+        if new_event {
+            tally.inconsistent += 1;
+        }
+        *tally.counts.entry(inconsistency.kind).or_insert(0) += 1;
+        if tally.samples.len() < max_samples {
+            tally.samples.push(inconsistency);
+        }
+    }
+
+    /// Tally of a session; events outside trading hours are not counted
+    fn tally(&mut self, date: Option<NaiveDate>) -> Option<&mut SessionTally> {
+        date.map(|date| self.sessions.entry(date).or_default())
+    }
+}
+
+fn side_index(side: BookSide) -> usize {
+    match side {
+        BookSide::Bid => 0,
+        BookSide::Ask => 1,
+    }
+}
+
+fn best(state: &OrderBookState, side: BookSide) -> Option<Decimal> {
+    match side {
+        BookSide::Bid => state.bids.keys().next_back().copied(),
+        BookSide::Ask => state.asks.keys().next().copied(),
+    }
+}
+
+fn queue(state: &OrderBookState, side: BookSide, price: Decimal) -> i64 {
+    let levels = match side {
+        BookSide::Bid => &state.bids,
+        BookSide::Ask => &state.asks,
+    };
+    levels.get(&price).map_or(0, |level| level.volume as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::types::OrderBookOperation;
+    use chrono::{TimeZone, Utc};
+
+    fn tick(level: DataLevel, mdt: MarketDataType, ms: i64, price: i64, volume: i32) -> TickData {
+        // 10:00 CT on a weekday, inside the regular session
+        let base = Utc.with_ymd_and_hms(2024, 6, 12, 15, 0, 0).unwrap().timestamp_nanos_opt().unwrap();
+        let l2 = matches!(level, DataLevel::L2);
+        let tick = TickData::new(level, mdt, base + ms * NANOS_PER_MILLI, Decimal::new(price, 2), volume, "0624".to_string());
+        if l2 { tick.with_l2_data(OrderBookOperation::Update, 0) } else { tick }
+    }
+
+    #[test]
+    fn test_flags_trades_the_book_does_not_support() {
+        let ticks = vec![
+            tick(DataLevel::L2, MarketDataType::BidQuote, 0, 1850000, 10),
+            tick(DataLevel::L2, MarketDataType::AskQuote, 0, 1850025, 10),
+            tick(DataLevel::L1, MarketDataType::BidQuote, 1, 1850000, 10),
+            // At the touch, and the book reacts
+            tick(DataLevel::L1, MarketDataType::Trade, 10, 1850025, 4),
+            tick(DataLevel::L2, MarketDataType::AskQuote, 20, 1850025, 6),
+            // Inside the spread
+            tick(DataLevel::L1, MarketDataType::Trade, 30, 1850010, 1),
+            // At the touch but far larger than the queue, and never reflected
+            tick(DataLevel::L1, MarketDataType::Trade, 40, 1850000, 50),
+            tick(DataLevel::L2, MarketDataType::AskQuote, 2_000, 1850025, 6),
+        ];
+
+        let reports = ConsistencyReport::check(&ticks, &ConsistencyConfig::default());
+        assert_eq!(reports.len(), 1);
+        let session = &reports[0].sessions[0];
+
+        assert_eq!(session.trades, 3);
+        assert_eq!(session.checked_events, 4);
+        assert_eq!(session.counts.get(&InconsistencyKind::InsideSpread), Some(&1));
+        assert_eq!(session.counts.get(&InconsistencyKind::ExceedsQueue), Some(&1));
+        assert_eq!(session.counts.get(&InconsistencyKind::NoBookReaction), Some(&1));
+        assert_eq!(session.counts.get(&InconsistencyKind::QuoteMismatch), None);
+        assert_eq!(session.verdict, ConsistencyVerdict::Unreliable);
+    }
+}
@@ -0,0 +1,359 @@
+//! Event-sourced order book storage
+//!
+//! Each session's raw book operations are appended to
+//! `<dir>/<contract>/<session>.events`, one JSON update per line, and never
+//! rewritten. Reading a log back builds two indexes over it: periodic
+//! snapshots of the book, so the state at any time T is rebuilt by
+//! replaying from the nearest snapshot rather than the session open, and a
+//! per-level history, so every change to one price level between T1 and T2
+//! is a lookup rather than a scan. The replay debugger and feature
+//! computation both query sessions through `SessionEventLog`.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use super::operations::{OrderBookOperation, OrderBookUpdate};
+use super::types::BookSide;
+
+/// File extension of session event logs
+pub const EVENT_LOG_EXTENSION: &str = "events";
+
+/// Events between book snapshots when none is configured
+pub const DEFAULT_SNAPSHOT_INTERVAL: usize = 10_000;
+
+/// Errors raised writing or reading an event log
+#[derive(Debug, thiserror::Error)]
+pub enum EventLogError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// Location of one session's log under `dir`
+pub fn session_path(dir: &Path, contract: &str, session: NaiveDate) -> PathBuf {
+    dir.join(contract).join(format!("{}.{}", session.format("%Y%m%d"), EVENT_LOG_EXTENSION))
+}
+
+/// Appends the book operations of one session
+pub struct EventLogWriter {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    events: u64,
+}
+
+impl EventLogWriter {
+    /// Open a session's log for appending, creating it if needed
+    pub fn open(dir: &Path, contract: &str, session: NaiveDate) -> Result<Self, EventLogError> {
+        let path = session_path(dir, contract, session);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self { path, writer: BufWriter::new(file), events: 0 })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Events appended through this writer
+    pub fn events_written(&self) -> u64 {
+        self.events
+    }
+
+    pub fn append(&mut self, update: &OrderBookUpdate) -> Result<(), EventLogError> {
+        serde_json::to_writer(&mut self.writer, update)?;
+        self.writer.write_all(b"\n")?;
+        self.events += 1;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), EventLogError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Aggregate volume per price on each side
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BookImage {
+    pub bids: BTreeMap<Decimal, i32>,
+    pub asks: BTreeMap<Decimal, i32>,
+}
+
+impl BookImage {
+    fn side_mut(&mut self, side: BookSide) -> &mut BTreeMap<Decimal, i32> {
+        match side {
+            BookSide::Bid => &mut self.bids,
+            BookSide::Ask => &mut self.asks,
+        }
+    }
+
+    fn apply(&mut self, update: &OrderBookUpdate) {
+        match update.operation {
+            OrderBookOperation::Add | OrderBookOperation::Update if update.volume > 0 => {
+                self.side_mut(update.side).insert(update.price, update.volume);
+            }
+            OrderBookOperation::Add | OrderBookOperation::Update | OrderBookOperation::Remove => {
+                self.side_mut(update.side).remove(&update.price);
+            }
+            OrderBookOperation::Reset => {
+                self.bids.clear();
+                self.asks.clear();
+            }
+        }
+    }
+
+    pub fn best_bid(&self) -> Option<(Decimal, i32)> {
+        self.bids.iter().next_back().map(|(price, volume)| (*price, *volume))
+    }
+
+    pub fn best_ask(&self) -> Option<(Decimal, i32)> {
+        self.asks.iter().next().map(|(price, volume)| (*price, *volume))
+    }
+}
+
+/// The book as it stood at a point in the session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookAtTime {
+    pub timestamp: DateTime<Utc>,
+    /// Events applied, i.e. index of the first event not yet seen
+    pub events_applied: usize,
+    pub book: BookImage,
+}
+
+/// One change to a price level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelChange {
+    pub timestamp: DateTime<Utc>,
+    pub sequence: u64,
+    pub operation: OrderBookOperation,
+    pub volume_before: i32,
+    pub volume_after: i32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LevelEntry {
+    event: usize,
+    volume_after: i32,
+}
+
+/// A session's events with snapshot and per-level indexes
+#[derive(Debug, Clone)]
+pub struct SessionEventLog {
+    events: Vec<OrderBookUpdate>,
+    snapshot_interval: usize,
+    /// `snapshots[i]` is the book after the first `i * snapshot_interval` events
+    snapshots: Vec<BookImage>,
+    levels: HashMap<(BookSide, Decimal), Vec<LevelEntry>>,
+    /// Events whose timestamp ran backwards, kept in log order
+    out_of_order: usize,
+}
+
+impl SessionEventLog {
+    /// Index events in the order they were logged
+    pub fn from_events(events: Vec<OrderBookUpdate>) -> Self {
+        Self::with_snapshot_interval(events, DEFAULT_SNAPSHOT_INTERVAL)
+    }
+
+    pub fn with_snapshot_interval(events: Vec<OrderBookUpdate>, snapshot_interval: usize) -> Self {
+        let snapshot_interval = snapshot_interval.max(1);
+        let mut book = BookImage::default();
+        let mut snapshots = vec![book.clone()];
+        let mut levels: HashMap<(BookSide, Decimal), Vec<LevelEntry>> = HashMap::new();
+        let mut out_of_order = 0;
+
+        for (index, update) in events.iter().enumerate() {
+            if index > 0 && update.timestamp < events[index - 1].timestamp {
+                out_of_order += 1;
+            }
+
+            if update.operation == OrderBookOperation::Reset {
+                // Every level still standing drops to zero
+                for (side, prices) in [(BookSide::Bid, &book.bids), (BookSide::Ask, &book.asks)] {
+                    for price in prices.keys() {
+                        levels.entry((side, *price)).or_default()
+                            .push(LevelEntry { event: index, volume_after: 0 });
+                    }
+                }
+            }
+            book.apply(update);
+            if update.operation != OrderBookOperation::Reset {
+                let volume_after = book.side_mut(update.side).get(&update.price).copied().unwrap_or(0);
+                levels.entry((update.side, update.price)).or_default()
+                    .push(LevelEntry { event: index, volume_after });
+            }
+
+            if (index + 1) % snapshot_interval == 0 {
+                snapshots.push(book.clone());
+            }
+        }
+
+        Self { events, snapshot_interval, snapshots, levels, out_of_order }
+    }
+
+    /// Read a session's log, stopping at a partly written final line
+    pub fn read(path: &Path) -> Result<Self, EventLogError> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut events = Vec::new();
+        for line in reader.lines() {
+            match serde_json::from_str::<OrderBookUpdate>(&line?) {
+                Ok(update) => events.push(update),
+                Err(_) => break,
+            }
+        }
+        Ok(Self::from_events(events))
+    }
+
+    pub fn open(dir: &Path, contract: &str, session: NaiveDate) -> Result<Self, EventLogError> {
+        Self::read(&session_path(dir, contract, session))
+    }
+
+    pub fn events(&self) -> &[OrderBookUpdate] {
+        &self.events
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Events logged with an earlier timestamp than the one before them
+    pub fn out_of_order_events(&self) -> usize {
+        self.out_of_order
+    }
+
+    /// Number of events at or before `at`
+    fn events_through(&self, at: DateTime<Utc>) -> usize {
+        self.events.partition_point(|update| update.timestamp <= at)
+    }
+
+    /// The book after the first `count` events
+    pub fn state_after(&self, count: usize) -> BookImage {
+        let count = count.min(self.events.len());
+        let snapshot = (count / self.snapshot_interval).min(self.snapshots.len() - 1);
+        let mut book = self.snapshots[snapshot].clone();
+        for update in &self.events[snapshot * self.snapshot_interval..count] {
+            book.apply(update);
+        }
+        book
+    }
+
+    /// The book after every event up to and including `at`
+    pub fn state_at(&self, at: DateTime<Utc>) -> BookAtTime {
+        let events_applied = self.events_through(at);
+        BookAtTime {
+            timestamp: at,
+            events_applied,
+            book: self.state_after(events_applied),
+        }
+    }
+
+    /// Every change to one price level within `[from, to]`
+    pub fn level_changes(
+        &self,
+        side: BookSide,
+        price: Decimal,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<LevelChange> {
+        let Some(history) = self.levels.get(&(side, price)) else {
+            return Vec::new();
+        };
+        let first = self.events.partition_point(|update| update.timestamp < from);
+        let last = self.events_through(to);
+        let start = history.partition_point(|entry| entry.event < first);
+
+        let mut volume_before = start.checked_sub(1).map(|i| history[i].volume_after).unwrap_or(0);
+        history[start..].iter()
+            .take_while(|entry| entry.event < last)
+            .map(|entry| {
+                let update = &self.events[entry.event];
+                let change = LevelChange {
+                    timestamp: update.timestamp,
+                    sequence: update.sequence,
+                    operation: update.operation,
+                    volume_before,
+                    volume_after: entry.volume_after,
+                };
+                volume_before = entry.volume_after;
+                change
+            })
+            .collect()
+    }
+
+    /// Events within `[from, to]`, in log order
+    pub fn range(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> &[OrderBookUpdate] {
+        let first = self.events.partition_point(|update| update.timestamp < from);
+        let last = self.events_through(to).max(first);
+        &self.events[first..last]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn update(second: u32, operation: OrderBookOperation, side: BookSide, price: i64, volume: i32) -> OrderBookUpdate {
+        OrderBookUpdate {
+            timestamp: Utc.with_ymd_and_hms(2024, 3, 1, 14, 30, second).unwrap(),
+            operation,
+            side,
+            price: Decimal::new(price, 2),
+            volume,
+            depth: 0,
+            market_maker: None,
+            sequence: second as u64,
+        }
+    }
+
+    #[test]
+    fn test_time_travel_queries() {
+        use OrderBookOperation::*;
+        let events = vec![
+            update(0, Add, BookSide::Bid, 500_000, 10),
+            update(1, Add, BookSide::Ask, 500_025, 8),
+            update(2, Update, BookSide::Bid, 500_000, 4),
+            update(3, Reset, BookSide::Bid, 0, 0),
+            update(4, Add, BookSide::Bid, 500_000, 6),
+            update(5, Remove, BookSide::Ask, 500_025, 0),
+        ];
+
+        let dir = std::env::temp_dir().join(format!("event-log-{}", uuid::Uuid::new_v4()));
+        let session = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let mut writer = EventLogWriter::open(&dir, "ESM4", session).unwrap();
+        for event in &events {
+            writer.append(event).unwrap();
+        }
+        writer.flush().unwrap();
+
+        let log = SessionEventLog::open(&dir, "ESM4", session).unwrap();
+        let indexed = SessionEventLog::with_snapshot_interval(events, 2);
+        assert_eq!(log.len(), 6);
+
+        let at = |second| Utc.with_ymd_and_hms(2024, 3, 1, 14, 30, second).unwrap();
+        for second in 0..6 {
+            assert_eq!(log.state_at(at(second)), indexed.state_at(at(second)));
+        }
+        let before_reset = indexed.state_at(at(2)).book;
+        assert_eq!(before_reset.best_bid(), Some((Decimal::new(500_000, 2), 4)));
+        assert_eq!(before_reset.best_ask(), Some((Decimal::new(500_025, 2), 8)));
+        assert!(indexed.state_at(at(3)).book.bids.is_empty());
+
+        let changes = indexed.level_changes(BookSide::Bid, Decimal::new(500_000, 2), at(2), at(4));
+        let volumes: Vec<_> = changes.iter().map(|c| (c.volume_before, c.volume_after)).collect();
+        assert_eq!(volumes, vec![(10, 4), (4, 0), (0, 6)]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,518 @@
+//! Order book liquidity and resiliency metrics
+//!
+//! Replays a session through the order book and samples, per time interval,
+//! how much size rests near the touch, how it is split between bids and
+//! asks, and how quickly the book refills after large trades sweep it. The
+//! resulting profile is stored next to the dataset so sessions can be
+//! compared without replaying them again.
+
+use crate::data::TickData;
+use crate::market::order_book::OrderBook;
+use crate::market::order_flow::{AggressorSide, TapeBuilder};
+use crate::market::types::{BookSide, OrderBookState};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+const NANOS_PER_MILLI: i64 = 1_000_000;
+
+/// Errors raised reading or writing a stored liquidity profile
+#[derive(Debug, thiserror::Error)]
+pub enum LiquidityError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// Liquidity metric settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiquidityConfig {
+    /// Price increment; MNQ trades in 0.25 point ticks
+    #[serde(default = "default_tick_size")]
+    pub tick_size: Decimal,
+    /// Depth counts levels at most this many ticks behind the best price
+    #[serde(default = "default_depth_ticks")]
+    pub depth_ticks: u32,
+    /// Length of each sample
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// Trades of at least this many contracts are followed for replenishment
+    #[serde(default = "default_large_trade_volume")]
+    pub large_trade_volume: i32,
+    /// Share of the pre-trade depth the book must get back to count as replenished
+    #[serde(default = "default_recovery_fraction")]
+    pub recovery_fraction: f64,
+    /// Sweeps not replenished within this window count as unreplenished
+    #[serde(default = "default_recovery_horizon_ms")]
+    pub recovery_horizon_ms: u64,
+}
+
+fn default_tick_size() -> Decimal {
+    Decimal::new(25, 2)
+}
+
+fn default_depth_ticks() -> u32 {
+    10
+}
+
+fn default_interval_secs() -> u64 {
+    60
+}
+
+fn default_large_trade_volume() -> i32 {
+    20
+}
+
+fn default_recovery_fraction() -> f64 {
+    0.9
+}
+
+fn default_recovery_horizon_ms() -> u64 {
+    10_000
+}
+
+impl Default for LiquidityConfig {
+    fn default() -> Self {
+        Self {
+            tick_size: default_tick_size(),
+            depth_ticks: default_depth_ticks(),
+            interval_secs: default_interval_secs(),
+            large_trade_volume: default_large_trade_volume(),
+            recovery_fraction: default_recovery_fraction(),
+            recovery_horizon_ms: default_recovery_horizon_ms(),
+        }
+    }
+}
+
+/// Book liquidity over one interval
+///
+/// Depths and spread are time-weighted averages over the interval.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiquiditySample {
+    /// Interval start, nanoseconds since epoch
+    pub start: i64,
+    pub bid_depth: f64,
+    pub ask_depth: f64,
+    pub total_depth: f64,
+    /// Bid depth divided by ask depth; `None` without resting asks
+    pub depth_ratio: Option<f64>,
+    pub spread_ticks: Option<f64>,
+    /// Large trades that started in this interval
+    pub large_trades: usize,
+    /// Of those, sweeps the book recovered from within the horizon
+    pub replenished: usize,
+    pub mean_replenishment_ms: Option<f64>,
+}
+
+/// Session-wide liquidity figures, kept in the dataset catalog
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LiquiditySummary {
+    pub samples: usize,
+    pub mean_depth: f64,
+    pub median_depth: f64,
+    /// Depth exceeded in 90% of samples; how thin the book gets
+    pub p10_depth: f64,
+    pub mean_depth_ratio: Option<f64>,
+    pub mean_spread_ticks: Option<f64>,
+    pub large_trades: usize,
+    /// Share of large trades the book replenished after
+    pub replenishment_rate: Option<f64>,
+    pub median_replenishment_ms: Option<f64>,
+}
+
+/// Liquidity time series of one dataset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiquidityProfile {
+    /// Contract the book was reconstructed for
+    pub contract: String,
+    pub config: LiquidityConfig,
+    pub summary: LiquiditySummary,
+    pub samples: Vec<LiquiditySample>,
+}
+
+impl LiquidityProfile {
+    /// Replay `ticks` and sample liquidity of the most active contract
+    pub fn compute(ticks: &[TickData], config: &LiquidityConfig) -> Self {
+        let mut analyzer = LiquidityAnalyzer::new(most_active_contract(ticks), config.clone());
+        for tick in ticks {
+            analyzer.push(tick);
+        }
+        analyzer.finish()
+    }
+
+    /// Samples starting within `[start, end]`, nanoseconds since epoch
+    pub fn samples_between(&self, start: Option<i64>, end: Option<i64>) -> Vec<LiquiditySample> {
+        let start = start.unwrap_or(i64::MIN);
+        let end = end.unwrap_or(i64::MAX);
+        self.samples.iter()
+            .filter(|sample| sample.start >= start && sample.start <= end)
+            .cloned()
+            .collect()
+    }
+
+    /// Where the profile of a data file is stored: `<file>.liquidity.json`
+    pub fn sidecar_path(data_path: &Path) -> PathBuf {
+        let mut name = data_path.file_name().unwrap_or_default().to_os_string();
+        name.push(".liquidity.json");
+        data_path.with_file_name(name)
+    }
+
+    /// Store the profile next to its data file
+    pub fn save(&self, data_path: &Path) -> Result<PathBuf, LiquidityError> {
+        let path = Self::sidecar_path(data_path);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(self)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(path)
+    }
+
+    /// The stored profile of a data file, if one was computed
+    pub fn load(data_path: &Path) -> Result<Option<Self>, LiquidityError> {
+        match fs::read(Self::sidecar_path(data_path)) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Contract with the most ticks, the first by name on a tie
+pub(crate) fn most_active_contract(ticks: &[TickData]) -> String {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for tick in ticks {
+        *counts.entry(tick.contract_month.as_str()).or_default() += 1;
+    }
+    counts.into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+        .map(|(contract, _)| contract.to_string())
+        .unwrap_or_default()
+}
+
+/// Book state credited to the time until the next tick
+#[derive(Debug, Clone, Copy)]
+struct Snapshot {
+    bid_depth: i64,
+    ask_depth: i64,
+    spread_ticks: Option<f64>,
+}
+
+#[derive(Debug, Default)]
+struct Accumulator {
+    covered_ns: i64,
+    bid_depth_ns: f64,
+    ask_depth_ns: f64,
+    spread_covered_ns: i64,
+    spread_ns: f64,
+    large_trades: usize,
+    replenished: usize,
+    replenishment_ms: f64,
+}
+
+/// A large trade waiting for the book to refill
+#[derive(Debug)]
+struct PendingSweep {
+    bucket: i64,
+    side: BookSide,
+    timestamp: i64,
+    target: f64,
+    /// Whether depth has dropped below the target since the trade
+    depleted: bool,
+}
+
+/// Incremental liquidity sampling over a tick stream
+pub struct LiquidityAnalyzer {
+    contract: String,
+    config: LiquidityConfig,
+    interval_ns: i64,
+    book: OrderBook,
+    tape: TapeBuilder,
+    last: Option<(i64, Snapshot)>,
+    buckets: BTreeMap<i64, Accumulator>,
+    pending: Vec<PendingSweep>,
+    replenishment_times: Vec<f64>,
+}
+
+impl LiquidityAnalyzer {
+    pub fn new(contract: String, config: LiquidityConfig) -> Self {
+        let interval_ns = (config.interval_secs.max(1) as i64).saturating_mul(1_000 * NANOS_PER_MILLI);
+        Self {
+            book: OrderBook::new(contract.clone(), false),
+            contract,
+            config,
+            interval_ns,
+            tape: TapeBuilder::new(),
+            last: None,
+            buckets: BTreeMap::new(),
+            pending: Vec::new(),
+            replenishment_times: Vec::new(),
+        }
+    }
+
+    /// Feed one tick; ticks of other contracts are ignored
+    pub fn push(&mut self, tick: &TickData) {
+        if tick.contract_month != self.contract {
+            return;
+        }
+
+        if let Some((since, snapshot)) = self.last {
+            self.credit(since, tick.timestamp, snapshot);
+        }
+
+        // Depth before the trade is what a sweep has to be replenished to
+        let trade = self.tape.push(tick);
+        if let Some(trade) = trade.filter(|trade| trade.volume >= self.config.large_trade_volume) {
+            self.track_sweep(trade.aggressor, tick.timestamp);
+        }
+
+        self.book.process_tick(tick);
+        let snapshot = self.snapshot();
+        self.resolve_sweeps(tick.timestamp, &snapshot);
+        self.last = Some((tick.timestamp, snapshot));
+    }
+
+    pub fn finish(mut self) -> LiquidityProfile {
+        let now = self.last.map(|(timestamp, _)| timestamp).unwrap_or_default();
+        for sweep in std::mem::take(&mut self.pending) {
+            self.close_sweep(sweep, now, false);
+        }
+
+        let samples: Vec<LiquiditySample> = self.buckets.iter()
+            .filter(|(_, acc)| acc.covered_ns > 0 || acc.large_trades > 0)
+            .map(|(&start, acc)| sample(start, acc))
+            .collect();
+        let summary = summarize(&samples, &mut self.replenishment_times);
+
+        LiquidityProfile {
+            contract: self.contract,
+            config: self.config,
+            summary,
+            samples,
+        }
+    }
+
+    fn bucket_start(&self, timestamp: i64) -> i64 {
+        timestamp.div_euclid(self.interval_ns) * self.interval_ns
+    }
+
+    /// Credit `snapshot` to the time from `from` to `to`
+    ///
+    /// A gap longer than one interval, such as a trading halt, is credited
+    /// only to the intervals at either end of it.
+    fn credit(&mut self, from: i64, to: i64, snapshot: Snapshot) {
+        if to <= from {
+            return;
+        }
+        let first = self.bucket_start(from);
+        let first_end = (first + self.interval_ns).min(to);
+        self.bucket(first).add(first_end - from, &snapshot);
+
+        let last = self.bucket_start(to);
+        if last > first && to > last {
+            self.bucket(last).add(to - last, &snapshot);
+        }
+    }
+
+    fn bucket(&mut self, start: i64) -> &mut Accumulator {
+        self.buckets.entry(start).or_default()
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let state = self.book.get_state();
+        let spread_ticks = state.spread()
+            .filter(|_| !self.config.tick_size.is_zero())
+            .and_then(|spread| (spread / self.config.tick_size).to_f64());
+        Snapshot {
+            bid_depth: self.depth(state, BookSide::Bid),
+            ask_depth: self.depth(state, BookSide::Ask),
+            spread_ticks,
+        }
+    }
+
+    /// Size resting within `depth_ticks` of the best price on one side
+    fn depth(&self, state: &OrderBookState, side: BookSide) -> i64 {
+        let reach = self.config.tick_size * Decimal::from(self.config.depth_ticks);
+        let volume = |level: &crate::market::types::PriceLevel| level.volume.max(0) as i64;
+        match side {
+            BookSide::Bid => state.best_bid
+                .map(|best| state.bids.range(best - reach..).map(|(_, level)| volume(level)).sum())
+                .unwrap_or(0),
+            BookSide::Ask => state.best_ask
+                .map(|best| state.asks.range(..=best + reach).map(|(_, level)| volume(level)).sum())
+                .unwrap_or(0),
+        }
+    }
+
+    fn track_sweep(&mut self, aggressor: AggressorSide, timestamp: i64) {
+        // Buyers take liquidity from the asks, sellers from the bids
+        let side = match aggressor {
+            AggressorSide::Buy => BookSide::Ask,
+            AggressorSide::Sell => BookSide::Bid,
+            AggressorSide::Unknown => return,
+        };
+        let depth = match (side, self.last) {
+            (BookSide::Ask, Some((_, snapshot))) => snapshot.ask_depth,
+            (BookSide::Bid, Some((_, snapshot))) => snapshot.bid_depth,
+            (_, None) => 0,
+        };
+        if depth == 0 {
+            return;
+        }
+
+        let bucket = self.bucket_start(timestamp);
+        self.bucket(bucket).large_trades += 1;
+        self.pending.push(PendingSweep {
+            bucket,
+            side,
+            timestamp,
+            target: depth as f64 * self.config.recovery_fraction,
+            depleted: false,
+        });
+    }
+
+    fn resolve_sweeps(&mut self, now: i64, snapshot: &Snapshot) {
+        let horizon_ns = (self.config.recovery_horizon_ms as i64).saturating_mul(NANOS_PER_MILLI);
+        for mut sweep in std::mem::take(&mut self.pending) {
+            let depth = match sweep.side {
+                BookSide::Bid => snapshot.bid_depth,
+                BookSide::Ask => snapshot.ask_depth,
+            } as f64;
+
+            if now - sweep.timestamp > horizon_ns {
+                self.close_sweep(sweep, now, false);
+            } else if depth < sweep.target {
+                sweep.depleted = true;
+                self.pending.push(sweep);
+            } else if sweep.depleted {
+                self.close_sweep(sweep, now, true);
+            } else {
+                self.pending.push(sweep);
+            }
+        }
+    }
+
+    /// Record the outcome of a sweep
+    ///
+    /// A trade the book absorbed without dropping below the target counts as
+    /// replenished immediately.
+    fn close_sweep(&mut self, sweep: PendingSweep, now: i64, recovered: bool) {
+        let elapsed_ms = if recovered {
+            Some((now - sweep.timestamp) as f64 / NANOS_PER_MILLI as f64)
+        } else if !sweep.depleted {
+            Some(0.0)
+        } else {
+            None
+        };
+
+        if let Some(elapsed_ms) = elapsed_ms {
+            let bucket = self.bucket(sweep.bucket);
+            bucket.replenished += 1;
+            bucket.replenishment_ms += elapsed_ms;
+            self.replenishment_times.push(elapsed_ms);
+        }
+    }
+}
+
+impl Accumulator {
+    fn add(&mut self, duration_ns: i64, snapshot: &Snapshot) {
+        let weight = duration_ns as f64;
+        self.covered_ns += duration_ns;
+        self.bid_depth_ns += snapshot.bid_depth as f64 * weight;
+        self.ask_depth_ns += snapshot.ask_depth as f64 * weight;
+        if let Some(spread) = snapshot.spread_ticks {
+            self.spread_covered_ns += duration_ns;
+            self.spread_ns += spread * weight;
+        }
+    }
+}
+
+fn sample(start: i64, acc: &Accumulator) -> LiquiditySample {
+    let average = |total: f64, covered: i64| if covered > 0 { total / covered as f64 } else { 0.0 };
+    let bid_depth = average(acc.bid_depth_ns, acc.covered_ns);
+    let ask_depth = average(acc.ask_depth_ns, acc.covered_ns);
+
+    LiquiditySample {
+        start,
+        bid_depth,
+        ask_depth,
+        total_depth: bid_depth + ask_depth,
+        depth_ratio: (ask_depth > 0.0).then(|| bid_depth / ask_depth),
+        spread_ticks: (acc.spread_covered_ns > 0).then(|| average(acc.spread_ns, acc.spread_covered_ns)),
+        large_trades: acc.large_trades,
+        replenished: acc.replenished,
+        mean_replenishment_ms: (acc.replenished > 0).then(|| acc.replenishment_ms / acc.replenished as f64),
+    }
+}
+
+fn summarize(samples: &[LiquiditySample], replenishment_times: &mut [f64]) -> LiquiditySummary {
+    let mean = |values: &[f64]| (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64);
+
+    let mut depths: Vec<f64> = samples.iter().map(|s| s.total_depth).collect();
+    depths.sort_by(|a, b| a.total_cmp(b));
+    let ratios: Vec<f64> = samples.iter().filter_map(|s| s.depth_ratio).collect();
+    let spreads: Vec<f64> = samples.iter().filter_map(|s| s.spread_ticks).collect();
+    let large_trades = samples.iter().map(|s| s.large_trades).sum::<usize>();
+    replenishment_times.sort_by(|a, b| a.total_cmp(b));
+
+    LiquiditySummary {
+        samples: samples.len(),
+        mean_depth: mean(&depths).unwrap_or(0.0),
+        median_depth: percentile(&depths, 0.5).unwrap_or(0.0),
+        p10_depth: percentile(&depths, 0.1).unwrap_or(0.0),
+        mean_depth_ratio: mean(&ratios),
+        mean_spread_ticks: mean(&spreads),
+        large_trades,
+        replenishment_rate: (large_trades > 0)
+            .then(|| replenishment_times.len() as f64 / large_trades as f64),
+        median_replenishment_ms: percentile(replenishment_times, 0.5),
+    }
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[f64], q: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let index = ((sorted.len() - 1) as f64 * q).round() as usize;
+    Some(sorted[index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::test_support::tick;
+    use crate::data::MarketDataType;
+
+    const SECOND: i64 = 1_000_000_000;
+
+    #[test]
+    fn test_depth_and_replenishment() {
+        let ticks = vec![
+            tick(MarketDataType::BidQuote, "100.00", 10, 0),
+            tick(MarketDataType::AskQuote, "100.25", 30, 0),
+            // A 25 lot lifts the offer, which refills 600ms later
+            tick(MarketDataType::Trade, "100.25", 25, 30 * SECOND),
+            tick(MarketDataType::AskQuote, "100.25", 5, 30 * SECOND + 100_000_000),
+            tick(MarketDataType::AskQuote, "100.25", 30, 30 * SECOND + 600_000_000),
+            tick(MarketDataType::Trade, "100.25", 1, 90 * SECOND),
+        ];
+
+        let profile = LiquidityProfile::compute(&ticks, &LiquidityConfig::default());
+        assert_eq!(profile.contract, "0624");
+        assert_eq!(profile.samples.len(), 2);
+
+        let first = &profile.samples[0];
+        assert_eq!(first.bid_depth, 10.0);
+        assert!(first.ask_depth < 30.0 && first.ask_depth > 29.0);
+        assert_eq!(first.spread_ticks, Some(1.0));
+        assert_eq!((first.large_trades, first.replenished), (1, 1));
+        assert!((first.mean_replenishment_ms.unwrap() - 600.0).abs() < 1e-6);
+
+        assert_eq!(profile.summary.replenishment_rate, Some(1.0));
+        assert_eq!(profile.samples_between(Some(60 * SECOND), None).len(), 1);
+    }
+}
@@ -8,8 +8,37 @@ pub mod order_book;
 pub mod types;
 pub mod operations;
 pub mod validation;
+pub mod order_flow;
+pub mod liquidity;
+pub mod consistency;
+pub mod spread;
+pub mod pool;
+pub mod event_log;
+pub mod book_diff;
+pub mod bbo;
 
-pub use order_book::{OrderBook, OrderBookBuilder};
+pub use order_book::{OrderBook, OrderBookBuilder, OrderBookManager};
 pub use types::{OrderBookState, PriceLevel, BookSide, MarketDepth};
 pub use operations::{OrderBookOperation, OrderBookUpdate};
-pub use validation::OrderBookValidator;
\ No newline at end of file
+pub use bbo::{Bbo, BboTracker, BookDepth};
+pub use pool::{Pool, PoolStats, Recycle, DEFAULT_LEVEL_POOL_CAPACITY};
+pub use validation::OrderBookValidator;
+pub use book_diff::{
+    reconstruct_book, BookDiffConfig, BookDiffReport, BookSnapshot, LevelMismatch, LevelQuote, MismatchKind, SideDiff,
+};
+pub use event_log::{
+    BookAtTime, BookImage, EventLogError, EventLogWriter, LevelChange, SessionEventLog,
+    DEFAULT_SNAPSHOT_INTERVAL,
+};
+pub use order_flow::{
+    AggressorSide, TapeBuilder, TapeTrade, FootprintBar, FootprintConfig, FootprintLevel,
+    FootprintWindow, build_footprint,
+};
+pub use liquidity::{
+    LiquidityAnalyzer, LiquidityConfig, LiquidityError, LiquidityProfile, LiquiditySample, LiquiditySummary,
+};
+pub use spread::{SessionSpread, SpreadAnalyzer, SpreadConfig, SpreadFilter};
+pub use consistency::{
+    ConsistencyChecker, ConsistencyConfig, ConsistencyReport, ConsistencyVerdict, Inconsistency,
+    InconsistencyKind, SessionConsistency,
+};
@@ -1,6 +1,7 @@
 //! Order book operations and updates
 
 use crate::data::{OrderBookOperation as DataOperation, TickData};
+use crate::market::pool::{Pool, PoolStats};
 use crate::market::types::{BookSide, OrderBookState, PriceLevel};
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
@@ -41,12 +42,23 @@ impl From<DataOperation> for OrderBookOperation {
 /// Processes order book operations
 pub struct OrderBookProcessor {
     stats: OrderBookStatistics,
+    /// Levels removed from the book, reused by later adds
+    levels: Pool<PriceLevel>,
 }
 
 impl OrderBookProcessor {
     pub fn new() -> Self {
         Self {
             stats: OrderBookStatistics::default(),
+            levels: Pool::default(),
+        }
+    }
+    
+    /// Keep up to `capacity` removed levels for reuse; zero disables pooling
+    pub fn with_level_pool(capacity: usize) -> Self {
+        Self {
+            stats: OrderBookStatistics::default(),
+            levels: Pool::new(capacity),
         }
     }
     
@@ -70,7 +82,7 @@ impl OrderBookProcessor {
                         self.process_l1_quote(book, tick, BookSide::Ask);
                     }
                     MarketDataType::BookReset => {
-                        book.clear(tick.timestamp);
+                        self.clear_book(book, tick.timestamp);
                         self.stats.book_resets += 1;
                     }
                     _ => {}
@@ -113,7 +125,7 @@ impl OrderBookProcessor {
                 self.stats.remove_operations += 1;
             }
             OrderBookOperation::Reset => {
-                book.clear(tick.timestamp);
+                self.clear_book(book, tick.timestamp);
                 self.stats.book_resets += 1;
             }
         }
@@ -130,26 +142,34 @@ impl OrderBookProcessor {
                 // Clear old best bid if different
                 if let Some(old_best) = book.best_bid {
                     if old_best != tick.price {
-                        book.bids.remove(&old_best);
+                        if let Some(old) = book.bids.remove(&old_best) {
+                            self.levels.release(old);
+                        }
                     }
                 }
                 
                 // Insert new best bid
-                let level = PriceLevel::new(tick.price, tick.volume, tick.timestamp);
-                book.bids.insert(tick.price, level);
+                let level = self.levels.level(tick.price, tick.volume, tick.timestamp);
+                if let Some(old) = book.bids.insert(tick.price, level) {
+                    self.levels.release(old);
+                }
                 book.best_bid = Some(tick.price);
             }
             BookSide::Ask => {
                 // Clear old best ask if different
                 if let Some(old_best) = book.best_ask {
                     if old_best != tick.price {
-                        book.asks.remove(&old_best);
+                        if let Some(old) = book.asks.remove(&old_best) {
+                            self.levels.release(old);
+                        }
                     }
                 }
                 
                 // Insert new best ask
-                let level = PriceLevel::new(tick.price, tick.volume, tick.timestamp);
-                book.asks.insert(tick.price, level);
+                let level = self.levels.level(tick.price, tick.volume, tick.timestamp);
+                if let Some(old) = book.asks.insert(tick.price, level) {
+                    self.levels.release(old);
+                }
                 book.best_ask = Some(tick.price);
             }
         }
@@ -165,17 +185,20 @@ impl OrderBookProcessor {
         timestamp: DateTime<Utc>,
         _depth: u8,
     ) {
-        let level = PriceLevel::new(price, volume, timestamp);
+        let level = self.levels.level(price, volume, timestamp);
         
-        match side {
+        let replaced = match side {
             BookSide::Bid => {
-                book.bids.insert(price, level);
                 book.total_bid_volume += volume as i64;
+                book.bids.insert(price, level)
             }
             BookSide::Ask => {
-                book.asks.insert(price, level);
                 book.total_ask_volume += volume as i64;
+                book.asks.insert(price, level)
             }
+        };
+        if let Some(old) = replaced {
+            self.levels.release(old);
         }
     }
     
@@ -225,16 +248,25 @@ impl OrderBookProcessor {
             BookSide::Bid => {
                 if let Some(level) = book.bids.remove(&price) {
                     book.total_bid_volume -= level.volume as i64;
+                    self.levels.release(level);
                 }
             }
             BookSide::Ask => {
                 if let Some(level) = book.asks.remove(&price) {
                     book.total_ask_volume -= level.volume as i64;
+                    self.levels.release(level);
                 }
             }
         }
     }
     
+    /// Clear the book, returning its levels to the pool
+    fn clear_book(&mut self, book: &mut OrderBookState, timestamp: DateTime<Utc>) {
+        self.levels.release_all(std::mem::take(&mut book.bids).into_values());
+        self.levels.release_all(std::mem::take(&mut book.asks).into_values());
+        book.clear(timestamp);
+    }
+    
     /// Determine the side of the book from tick data
     fn determine_side(&self, tick: &TickData) -> BookSide {
         // Use MDT to determine side if available
@@ -254,6 +286,11 @@ impl OrderBookProcessor {
     pub fn get_stats(&self) -> &OrderBookStatistics {
         &self.stats
     }
+    
+    /// Allocations made and avoided by the level pool
+    pub fn level_pool_stats(&self) -> PoolStats {
+        self.levels.stats()
+    }
 }
 
 /// Statistics for order book processing
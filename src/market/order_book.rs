@@ -3,6 +3,7 @@
 use crate::data::TickData;
 use crate::market::{
     operations::{OrderBookProcessor, OrderBookStatistics},
+    pool::{PoolStats, DEFAULT_LEVEL_POOL_CAPACITY},
     types::{BookSide, MarketDepth, OrderBookState, OrderBookStats},
 };
 use chrono::{DateTime, Utc};
@@ -45,6 +46,19 @@ impl OrderBook {
         }
     }
     
+    /// Resume from a state captured earlier with `get_state`
+    pub fn from_state(state: OrderBookState, validation_enabled: bool) -> Self {
+        let mut book = Self::new(state.contract.clone(), validation_enabled);
+        book.state = state;
+        book
+    }
+    
+    /// Keep up to `capacity` removed price levels for reuse; zero disables pooling
+    pub fn with_level_pool(mut self, capacity: usize) -> Self {
+        self.processor = OrderBookProcessor::with_level_pool(capacity);
+        self
+    }
+    
     /// Process a single tick
     pub fn process_tick(&mut self, tick: &TickData) {
         let process_start = Instant::now();
@@ -180,6 +194,11 @@ impl OrderBook {
         &self.stats
     }
     
+    /// Price level allocations made and avoided
+    pub fn level_pool_stats(&self) -> PoolStats {
+        self.processor.level_pool_stats()
+    }
+    
     /// Get processing rate (operations per second)
     pub fn get_processing_rate(&self) -> f64 {
         let elapsed = self.start_time.elapsed().as_secs_f64();
@@ -273,6 +292,7 @@ pub struct OrderBookBuilder {
     contract: String,
     validation_enabled: bool,
     max_depth: Option<usize>,
+    level_pool_capacity: usize,
 }
 
 impl OrderBookBuilder {
@@ -281,6 +301,7 @@ impl OrderBookBuilder {
             contract,
             validation_enabled: true,
             max_depth: None,
+            level_pool_capacity: DEFAULT_LEVEL_POOL_CAPACITY,
         }
     }
     
@@ -294,8 +315,14 @@ impl OrderBookBuilder {
         self
     }
     
+    pub fn with_level_pool(mut self, capacity: usize) -> Self {
+        self.level_pool_capacity = capacity;
+        self
+    }
+    
     pub fn build(self) -> OrderBook {
         OrderBook::new(self.contract, self.validation_enabled)
+            .with_level_pool(self.level_pool_capacity)
     }
 }
 
@@ -326,6 +353,18 @@ impl OrderBookManager {
         book.process_tick(tick);
     }
     
+    /// Books of every contract seen so far
+    pub fn states(&self) -> Vec<OrderBookState> {
+        self.books.values().map(|book| book.get_state().clone()).collect()
+    }
+    
+    /// Replace the books with states captured earlier with `states`
+    pub fn restore(&mut self, states: impl IntoIterator<Item = OrderBookState>) {
+        self.books = states.into_iter()
+            .map(|state| (state.contract.clone(), OrderBook::from_state(state, self.validation_enabled)))
+            .collect();
+    }
+    
     /// Get all active contracts
     pub fn active_contracts(&self) -> Vec<String> {
         self.books.keys().cloned().collect()
@@ -0,0 +1,332 @@
+//! Order flow tape reconstruction and footprint aggregation
+//!
+//! Trades are classified by aggressor side against the prevailing quote
+//! (trade at or above the ask is a buyer lifting the offer, at or below the
+//! bid a seller hitting it), falling back to the tick rule inside the spread.
+//! The classified tape is then aggregated into footprint bars: per-price bid
+//! and ask volume for each time, volume or trade-count window.
+
+use crate::data::{MarketDataType, TickData};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Side that initiated a trade
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AggressorSide {
+    /// Buyer lifted the offer
+    Buy,
+    /// Seller hit the bid
+    Sell,
+    /// No quote or prior price to classify against
+    Unknown,
+}
+
+/// How the aggressor side of a trade was inferred
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClassificationMethod {
+    /// Trade price compared with the prevailing bid/ask
+    Quote,
+    /// Trade price compared with the previous trade price
+    TickRule,
+}
+
+/// A trade on the reconstructed tape
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TapeTrade {
+    pub timestamp: i64,
+    pub price: Decimal,
+    pub volume: i32,
+    pub aggressor: AggressorSide,
+    pub method: ClassificationMethod,
+    pub best_bid: Option<Decimal>,
+    pub best_ask: Option<Decimal>,
+}
+
+/// Builds a classified trade tape from a tick stream
+#[derive(Debug, Clone, Default)]
+pub struct TapeBuilder {
+    best_bid: Option<Decimal>,
+    best_ask: Option<Decimal>,
+    last_price: Option<Decimal>,
+    last_aggressor: Option<AggressorSide>,
+}
+
+impl TapeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reconstruct the tape for a whole tick sequence
+    pub fn reconstruct(ticks: &[TickData]) -> Vec<TapeTrade> {
+        let mut builder = Self::new();
+        ticks.iter().filter_map(|tick| builder.push(tick)).collect()
+    }
+
+    /// Feed one tick, returning a tape entry for trades
+    pub fn push(&mut self, tick: &TickData) -> Option<TapeTrade> {
+        match tick.mdt {
+            MarketDataType::BidQuote => {
+                self.best_bid = Some(tick.price);
+                None
+            }
+            MarketDataType::AskQuote => {
+                self.best_ask = Some(tick.price);
+                None
+            }
+            MarketDataType::BookReset => {
+                self.best_bid = None;
+                self.best_ask = None;
+                None
+            }
+            MarketDataType::Trade => {
+                let (aggressor, method) = self.classify(tick.price);
+                self.last_price = Some(tick.price);
+                if aggressor != AggressorSide::Unknown {
+                    self.last_aggressor = Some(aggressor);
+                }
+
+                Some(TapeTrade {
+                    timestamp: tick.timestamp,
+                    price: tick.price,
+                    volume: tick.volume,
+                    aggressor,
+                    method,
+                    best_bid: self.best_bid,
+                    best_ask: self.best_ask,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn classify(&self, price: Decimal) -> (AggressorSide, ClassificationMethod) {
+        match (self.best_bid, self.best_ask) {
+            (_, Some(ask)) if price >= ask => return (AggressorSide::Buy, ClassificationMethod::Quote),
+            (Some(bid), _) if price <= bid => return (AggressorSide::Sell, ClassificationMethod::Quote),
+            _ => {}
+        }
+
+        // Inside the spread or without quotes: up-tick is a buy, down-tick a sell,
+        // and an unchanged price keeps the previous classification
+        let side = match self.last_price {
+            Some(last) if price > last => AggressorSide::Buy,
+            Some(last) if price < last => AggressorSide::Sell,
+            _ => self.last_aggressor.unwrap_or(AggressorSide::Unknown),
+        };
+        (side, ClassificationMethod::TickRule)
+    }
+}
+
+/// How trades are grouped into footprint bars
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FootprintWindow {
+    /// Fixed time buckets aligned to multiples of `seconds`
+    Time { seconds: u64 },
+    /// A new bar every `contracts` traded
+    Volume { contracts: i64 },
+    /// A new bar every `count` trades
+    Trades { count: usize },
+}
+
+/// Footprint aggregation settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FootprintConfig {
+    pub window: FootprintWindow,
+    /// Price bucket size; MNQ trades in 0.25 point ticks
+    pub price_increment: Decimal,
+}
+
+impl Default for FootprintConfig {
+    fn default() -> Self {
+        Self {
+            window: FootprintWindow::Time { seconds: 60 },
+            price_increment: Decimal::new(25, 2),
+        }
+    }
+}
+
+/// Volume traded at one price within a bar
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FootprintLevel {
+    pub price: Decimal,
+    /// Volume sold into the bid
+    pub bid_volume: i64,
+    /// Volume bought from the ask
+    pub ask_volume: i64,
+    /// Volume that could not be classified
+    pub unknown_volume: i64,
+    pub trades: u32,
+}
+
+impl FootprintLevel {
+    pub fn total_volume(&self) -> i64 {
+        self.bid_volume + self.ask_volume + self.unknown_volume
+    }
+
+    pub fn delta(&self) -> i64 {
+        self.ask_volume - self.bid_volume
+    }
+}
+
+/// Footprint of one window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FootprintBar {
+    pub start: i64,
+    pub end: i64,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    pub buy_volume: i64,
+    pub sell_volume: i64,
+    pub delta: i64,
+    /// Price with the most volume
+    pub point_of_control: Decimal,
+    /// Levels from highest to lowest price
+    pub levels: Vec<FootprintLevel>,
+}
+
+/// Aggregate a classified tape into footprint bars
+pub fn build_footprint(tape: &[TapeTrade], config: &FootprintConfig) -> Vec<FootprintBar> {
+    let mut bars = Vec::new();
+    let mut current: Vec<&TapeTrade> = Vec::new();
+    let mut current_key: Option<i64> = None;
+    let mut window_volume = 0i64;
+
+    for trade in tape {
+        let closes_current = match config.window {
+            FootprintWindow::Time { seconds } => {
+                let bucket_ns = (seconds.max(1) as i64) * 1_000_000_000;
+                let key = trade.timestamp.div_euclid(bucket_ns);
+                let new_bucket = current_key.is_some_and(|k| k != key);
+                current_key = Some(key);
+                new_bucket
+            }
+            FootprintWindow::Volume { contracts } => window_volume >= contracts.max(1),
+            FootprintWindow::Trades { count } => current.len() >= count.max(1),
+        };
+
+        if closes_current && !current.is_empty() {
+            bars.push(aggregate_bar(&current, config.price_increment));
+            current.clear();
+            window_volume = 0;
+        }
+
+        current.push(trade);
+        window_volume += trade.volume as i64;
+    }
+
+    if !current.is_empty() {
+        bars.push(aggregate_bar(&current, config.price_increment));
+    }
+
+    bars
+}
+
+fn bucket_price(price: Decimal, increment: Decimal) -> Decimal {
+    if increment <= Decimal::ZERO {
+        return price;
+    }
+    (price / increment).floor() * increment
+}
+
+fn aggregate_bar(trades: &[&TapeTrade], increment: Decimal) -> FootprintBar {
+    let first = trades[0];
+    let last = trades[trades.len() - 1];
+    let mut levels: BTreeMap<Decimal, FootprintLevel> = BTreeMap::new();
+    let (mut high, mut low) = (first.price, first.price);
+
+    for trade in trades {
+        high = high.max(trade.price);
+        low = low.min(trade.price);
+
+        let price = bucket_price(trade.price, increment);
+        let level = levels.entry(price).or_insert_with(|| FootprintLevel {
+            price,
+            ..Default::default()
+        });
+        let volume = trade.volume as i64;
+        match trade.aggressor {
+            AggressorSide::Buy => level.ask_volume += volume,
+            AggressorSide::Sell => level.bid_volume += volume,
+            AggressorSide::Unknown => level.unknown_volume += volume,
+        }
+        level.trades += 1;
+    }
+
+    let buy_volume = levels.values().map(|l| l.ask_volume).sum();
+    let sell_volume = levels.values().map(|l| l.bid_volume).sum();
+    let point_of_control = levels.values()
+        .max_by_key(|l| l.total_volume())
+        .map(|l| l.price)
+        .unwrap_or(first.price);
+
+    FootprintBar {
+        start: first.timestamp,
+        end: last.timestamp,
+        open: first.price,
+        high,
+        low,
+        close: last.price,
+        buy_volume,
+        sell_volume,
+        delta: buy_volume - sell_volume,
+        point_of_control,
+        levels: levels.into_values().rev().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::test_support::tick;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_aggressor_classification() {
+        let ticks = vec![
+            tick(MarketDataType::BidQuote, "100.00", 10, 0),
+            tick(MarketDataType::AskQuote, "100.50", 10, 1),
+            tick(MarketDataType::Trade, "100.50", 3, 2),
+            tick(MarketDataType::Trade, "100.00", 2, 3),
+            // Inside the spread, above the last trade: tick rule says buy
+            tick(MarketDataType::Trade, "100.25", 1, 4),
+        ];
+
+        let tape = TapeBuilder::reconstruct(&ticks);
+        let sides: Vec<_> = tape.iter().map(|t| t.aggressor).collect();
+
+        assert_eq!(sides, vec![AggressorSide::Buy, AggressorSide::Sell, AggressorSide::Buy]);
+        assert_eq!(tape[2].method, ClassificationMethod::TickRule);
+    }
+
+    #[test]
+    fn test_footprint_by_trade_count() {
+        let ticks = vec![
+            tick(MarketDataType::BidQuote, "100.00", 10, 0),
+            tick(MarketDataType::AskQuote, "100.25", 10, 0),
+            tick(MarketDataType::Trade, "100.25", 5, 1),
+            tick(MarketDataType::Trade, "100.25", 4, 2),
+            tick(MarketDataType::Trade, "100.00", 2, 3),
+            tick(MarketDataType::Trade, "100.00", 7, 4),
+        ];
+        let tape = TapeBuilder::reconstruct(&ticks);
+        let config = FootprintConfig {
+            window: FootprintWindow::Trades { count: 3 },
+            ..Default::default()
+        };
+
+        let bars = build_footprint(&tape, &config);
+
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].buy_volume, 9);
+        assert_eq!(bars[0].sell_volume, 2);
+        assert_eq!(bars[0].point_of_control, Decimal::from_str("100.25").unwrap());
+        assert_eq!(bars[0].levels[0].price, Decimal::from_str("100.25").unwrap());
+        assert_eq!(bars[1].delta, -7);
+    }
+}
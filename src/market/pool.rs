@@ -0,0 +1,208 @@
+//! Object pools for order book structures
+//!
+//! Depth feeds add and remove price levels at 100K+ updates per second, and
+//! each removal frees a `PriceLevel` that the next add allocates again. A
+//! `Pool` keeps released objects on a free list, together with any buffers
+//! they own, so steady-state book updates reuse memory instead of going
+//! through the allocator.
+
+use crate::market::operations::{OrderBookOperation, OrderBookUpdate};
+use crate::market::types::{BookSide, PriceLevel};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Free-list size of the level pool each order book starts with
+pub const DEFAULT_LEVEL_POOL_CAPACITY: usize = 1024;
+
+/// Objects that can be cleared for reuse while keeping their allocations
+pub trait Recycle {
+    fn recycle(&mut self);
+}
+
+impl Recycle for PriceLevel {
+    fn recycle(&mut self) {
+        // Keeps the market maker list's capacity
+        self.market_makers.clear();
+    }
+}
+
+impl Recycle for OrderBookUpdate {
+    fn recycle(&mut self) {
+        if let Some(market_maker) = &mut self.market_maker {
+            market_maker.clear();
+        }
+    }
+}
+
+/// Allocation counters of a pool
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolStats {
+    /// Objects built because the free list was empty
+    pub allocated: u64,
+    /// Objects served from the free list
+    pub reused: u64,
+    /// Objects returned to the free list
+    pub released: u64,
+    /// Objects dropped because the free list was full
+    pub discarded: u64,
+}
+
+impl PoolStats {
+    /// Share of acquisitions served without allocating
+    pub fn reuse_ratio(&self) -> f64 {
+        let total = self.allocated + self.reused;
+        if total == 0 {
+            0.0
+        } else {
+            self.reused as f64 / total as f64
+        }
+    }
+}
+
+/// Free list of recycled objects, bounded by `capacity`
+///
+/// A capacity of zero disables pooling: every acquisition allocates and
+/// every release drops.
+#[derive(Debug)]
+pub struct Pool<T> {
+    free: Vec<T>,
+    capacity: usize,
+    stats: PoolStats,
+}
+
+impl<T: Recycle> Pool<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            free: Vec::with_capacity(capacity),
+            capacity,
+            stats: PoolStats::default(),
+        }
+    }
+
+    /// A recycled object, or `None` when the caller has to build one
+    pub fn take(&mut self) -> Option<T> {
+        let item = self.free.pop();
+        match item {
+            Some(_) => self.stats.reused += 1,
+            None => self.stats.allocated += 1,
+        }
+        item
+    }
+
+    /// Return an object for reuse
+    pub fn release(&mut self, mut item: T) {
+        if self.free.len() < self.capacity {
+            item.recycle();
+            self.free.push(item);
+            self.stats.released += 1;
+        } else {
+            self.stats.discarded += 1;
+        }
+    }
+
+    /// Return every object yielded by `items`
+    pub fn release_all(&mut self, items: impl IntoIterator<Item = T>) {
+        for item in items {
+            self.release(item);
+        }
+    }
+
+    /// Objects waiting on the free list
+    pub fn available(&self) -> usize {
+        self.free.len()
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        self.stats
+    }
+}
+
+impl<T: Recycle> Default for Pool<T> {
+    fn default() -> Self {
+        Self::new(DEFAULT_LEVEL_POOL_CAPACITY)
+    }
+}
+
+impl Pool<PriceLevel> {
+    /// A price level holding a single order, reusing a released level when one is free
+    pub fn level(&mut self, price: Decimal, volume: i32, timestamp: DateTime<Utc>) -> PriceLevel {
+        match self.take() {
+            Some(mut level) => {
+                level.price = price;
+                level.volume = volume;
+                level.order_count = 1;
+                level.last_update = timestamp;
+                level
+            }
+            None => PriceLevel::new(price, volume, timestamp),
+        }
+    }
+}
+
+impl Pool<OrderBookUpdate> {
+    /// A book update, reusing a released one and its market maker buffer when one is free
+    #[allow(clippy::too_many_arguments)]
+    pub fn update(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        operation: OrderBookOperation,
+        side: BookSide,
+        price: Decimal,
+        volume: i32,
+        depth: u8,
+        market_maker: Option<&str>,
+        sequence: u64,
+    ) -> OrderBookUpdate {
+        let mut update = self.take().unwrap_or_else(|| OrderBookUpdate {
+            timestamp,
+            operation,
+            side,
+            price,
+            volume,
+            depth,
+            market_maker: None,
+            sequence,
+        });
+        update.timestamp = timestamp;
+        update.operation = operation;
+        update.side = side;
+        update.price = price;
+        update.volume = volume;
+        update.depth = depth;
+        update.sequence = sequence;
+        match (market_maker, &mut update.market_maker) {
+            (Some(name), Some(buffer)) => buffer.push_str(name),
+            (Some(name), slot) => *slot = Some(name.to_string()),
+            (None, slot) => *slot = None,
+        }
+        update
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_released_levels_are_reused() {
+        let now = Utc::now();
+        let mut pool: Pool<PriceLevel> = Pool::new(1);
+
+        let mut level = pool.level(Decimal::from(18000), 5, now);
+        level.market_makers.push("MM1".to_string());
+        let second = pool.level(Decimal::from(18001), 3, now);
+        pool.release(level);
+        pool.release(second);
+
+        let reused = pool.level(Decimal::from(18002), 7, now);
+        assert_eq!(reused.price, Decimal::from(18002));
+        assert_eq!(reused.order_count, 1);
+        assert!(reused.market_makers.is_empty());
+        assert!(reused.market_makers.capacity() > 0);
+
+        let stats = pool.stats();
+        assert_eq!((stats.allocated, stats.reused, stats.released, stats.discarded), (2, 1, 1, 1));
+        assert_eq!(pool.available(), 0);
+    }
+}
@@ -0,0 +1,238 @@
+//! Bid-ask spread regimes per session
+//!
+//! Bid-ask bounce and other touch-trading strategies only work while the
+//! spread sits at its minimum most of the time. Replaying a dataset through
+//! the book, each spread is weighted by how long it was displayed and the
+//! result is summarized per exchange session, so sessions can be filtered
+//! by regime. Locked, crossed and one-sided books are not counted.
+
+use crate::data::TickData;
+use crate::market::liquidity::most_active_contract;
+use crate::market::order_book::OrderBook;
+use crate::timestamp::{SessionCalendar, Timestamp};
+use chrono::NaiveDate;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const NANOS_PER_SEC: i64 = 1_000_000_000;
+
+/// Spread statistics settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpreadConfig {
+    /// Price increment; MNQ trades in 0.25 point ticks
+    #[serde(default = "default_tick_size")]
+    pub tick_size: Decimal,
+    /// Longest time a spread is credited without another tick; quieter
+    /// stretches are treated as gaps in the feed
+    #[serde(default = "default_max_gap_secs")]
+    pub max_gap_secs: u64,
+}
+
+fn default_tick_size() -> Decimal {
+    Decimal::new(25, 2)
+}
+
+fn default_max_gap_secs() -> u64 {
+    60
+}
+
+impl Default for SpreadConfig {
+    fn default() -> Self {
+        Self {
+            tick_size: default_tick_size(),
+            max_gap_secs: default_max_gap_secs(),
+        }
+    }
+}
+
+/// Time-weighted spread of one exchange session, in ticks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionSpread {
+    pub trade_date: NaiveDate,
+    /// Time a two-sided book was displayed
+    pub observed_secs: f64,
+    pub mean_spread_ticks: f64,
+    pub median_spread_ticks: f64,
+    /// Share of the observed time the spread was a single tick
+    pub one_tick_share: f64,
+    /// Standard deviation of the spread
+    pub spread_volatility_ticks: f64,
+    pub max_spread_ticks: f64,
+}
+
+impl SessionSpread {
+    /// Replay `ticks` and summarize the spread of the most active contract
+    /// per session of `calendar`
+    pub fn compute(ticks: &[TickData], config: &SpreadConfig, calendar: &SessionCalendar) -> Vec<Self> {
+        let mut analyzer = SpreadAnalyzer::new(most_active_contract(ticks), config.clone(), calendar.clone());
+        for tick in ticks {
+            analyzer.push(tick);
+        }
+        analyzer.finish()
+    }
+
+    /// Summarize the time each spread was displayed for
+    fn from_durations(trade_date: NaiveDate, durations: &BTreeMap<Decimal, i64>) -> Option<Self> {
+        let total: i64 = durations.values().sum();
+        if total <= 0 {
+            return None;
+        }
+        let total_f = total as f64;
+        let ticks = |spread: &Decimal| spread.to_f64().unwrap_or_default();
+
+        let mean = durations.iter().map(|(spread, ns)| ticks(spread) * *ns as f64).sum::<f64>() / total_f;
+        let variance = durations.iter()
+            .map(|(spread, ns)| (ticks(spread) - mean).powi(2) * *ns as f64)
+            .sum::<f64>() / total_f;
+
+        let mut elapsed = 0;
+        let median = durations.iter()
+            .find(|(_, ns)| {
+                elapsed += **ns;
+                elapsed * 2 >= total
+            })
+            .map(|(spread, _)| ticks(spread))
+            .unwrap_or_default();
+
+        Some(Self {
+            trade_date,
+            observed_secs: total_f / NANOS_PER_SEC as f64,
+            mean_spread_ticks: mean,
+            median_spread_ticks: median,
+            one_tick_share: durations.get(&Decimal::ONE).copied().unwrap_or(0) as f64 / total_f,
+            spread_volatility_ticks: variance.sqrt(),
+            max_spread_ticks: durations.keys().next_back().map(ticks).unwrap_or_default(),
+        })
+    }
+}
+
+/// Bounds a session's spread must fall within; unset bounds always pass
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpreadFilter {
+    #[serde(default)]
+    pub min_one_tick_share: Option<f64>,
+    #[serde(default)]
+    pub max_mean_spread_ticks: Option<f64>,
+    #[serde(default)]
+    pub max_spread_volatility_ticks: Option<f64>,
+}
+
+impl SpreadFilter {
+    pub fn is_empty(&self) -> bool {
+        self.min_one_tick_share.is_none()
+            && self.max_mean_spread_ticks.is_none()
+            && self.max_spread_volatility_ticks.is_none()
+    }
+
+    pub fn matches(&self, session: &SessionSpread) -> bool {
+        self.min_one_tick_share.is_none_or(|min| session.one_tick_share >= min)
+            && self.max_mean_spread_ticks.is_none_or(|max| session.mean_spread_ticks <= max)
+            && self.max_spread_volatility_ticks.is_none_or(|max| session.spread_volatility_ticks <= max)
+    }
+}
+
+/// Incremental spread accounting over a tick stream
+pub struct SpreadAnalyzer {
+    contract: String,
+    config: SpreadConfig,
+    calendar: SessionCalendar,
+    book: OrderBook,
+    /// Time of the last tick and the spread in ticks it left displayed
+    last: Option<(i64, Option<Decimal>)>,
+    /// Nanoseconds each spread was displayed, per session
+    sessions: BTreeMap<NaiveDate, BTreeMap<Decimal, i64>>,
+}
+
+impl SpreadAnalyzer {
+    pub fn new(contract: String, config: SpreadConfig, calendar: SessionCalendar) -> Self {
+        Self {
+            book: OrderBook::new(contract.clone(), false),
+            contract,
+            config,
+            calendar,
+            last: None,
+            sessions: BTreeMap::new(),
+        }
+    }
+
+    /// Feed one tick; ticks of other contracts are ignored
+    pub fn push(&mut self, tick: &TickData) {
+        if tick.contract_month != self.contract {
+            return;
+        }
+
+        if let Some((since, Some(spread))) = self.last {
+            self.credit(since, tick.timestamp, spread);
+        }
+
+        self.book.process_tick(tick);
+        let spread = self.book.get_state().spread()
+            .filter(|spread| *spread > Decimal::ZERO && !self.config.tick_size.is_zero())
+            .map(|spread| (spread / self.config.tick_size).normalize());
+        self.last = Some((tick.timestamp, spread));
+    }
+
+    /// Sessions with a two-sided book, by trade date
+    pub fn finish(self) -> Vec<SessionSpread> {
+        self.sessions.iter()
+            .filter_map(|(trade_date, durations)| SessionSpread::from_durations(*trade_date, durations))
+            .collect()
+    }
+
+    /// Credit `spread` to the time from `from` to `to`, within the session
+    /// `from` falls in
+    fn credit(&mut self, from: i64, to: i64, spread: Decimal) {
+        let (previous, next) = (Timestamp::from_nanos(from), Timestamp::from_nanos(to));
+        let Some(trade_date) = self.calendar.trading_date(previous) else {
+            return;
+        };
+        if !self.calendar.is_open(previous) || self.calendar.crosses_session(previous, next) {
+            return;
+        }
+        let max_gap_ns = (self.config.max_gap_secs as i64).saturating_mul(NANOS_PER_SEC);
+        let duration = (to - from).min(max_gap_ns);
+        if duration > 0 {
+            *self.sessions.entry(trade_date).or_default().entry(spread).or_default() += duration;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{DataLevel, MarketDataType};
+    use chrono::{TimeZone, Utc};
+
+    fn quote(mdt: MarketDataType, secs: i64, price: i64) -> TickData {
+        // 10:00 CT on a weekday, inside the regular session
+        let base = Utc.with_ymd_and_hms(2024, 6, 12, 15, 0, 0).unwrap().timestamp_nanos_opt().unwrap();
+        TickData::new(DataLevel::L1, mdt, base + secs * NANOS_PER_SEC, Decimal::new(price, 2), 5, "0624".to_string())
+    }
+
+    #[test]
+    fn test_spread_is_weighted_by_time_displayed() {
+        let ticks = vec![
+            quote(MarketDataType::BidQuote, 0, 1_850_000),
+            quote(MarketDataType::AskQuote, 0, 1_850_025),
+            // One tick wide for 30s, then two ticks wide for 10s
+            quote(MarketDataType::AskQuote, 30, 1_850_050),
+            quote(MarketDataType::AskQuote, 40, 1_850_025),
+            quote(MarketDataType::BidQuote, 70, 1_850_000),
+        ];
+        let sessions = SessionSpread::compute(&ticks, &SpreadConfig::default(), &SessionCalendar::default());
+
+        assert_eq!(sessions.len(), 1);
+        let session = &sessions[0];
+        assert_eq!(session.trade_date, NaiveDate::from_ymd_opt(2024, 6, 12).unwrap());
+        assert_eq!(session.observed_secs, 70.0);
+        assert_eq!(session.median_spread_ticks, 1.0);
+        assert_eq!(session.max_spread_ticks, 2.0);
+        assert!((session.one_tick_share - 60.0 / 70.0).abs() < 1e-9);
+        assert!((session.mean_spread_ticks - 80.0 / 70.0).abs() < 1e-9);
+
+        let filter = SpreadFilter { min_one_tick_share: Some(0.9), ..Default::default() };
+        assert!(!filter.matches(session));
+    }
+}
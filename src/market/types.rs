@@ -47,7 +47,7 @@ impl PriceLevel {
 }
 
 /// Side of the order book (bid or ask)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BookSide {
     Bid,
     Ask,
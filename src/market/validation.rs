@@ -1,5 +1,6 @@
 //! Order book validation module
 
+use crate::market::book_diff::{BookDiffConfig, BookDiffReport, BookSnapshot, LevelMismatch};
 use crate::market::types::{OrderBookState, BookSide};
 use rust_decimal::Decimal;
 use std::collections::HashSet;
@@ -146,6 +147,21 @@ impl OrderBookValidator {
             warnings: Vec::new(),
         }
     }
+    
+    /// Validate order book state and compare it level by level with a
+    /// reference snapshot of the same instant, such as a vendor's
+    pub fn validate_against(
+        &self,
+        book: &OrderBookState,
+        reference: &BookSnapshot,
+        config: &BookDiffConfig,
+    ) -> ValidationResult {
+        let mut result = self.validate(book);
+        let diff = BookDiffReport::compare(&BookSnapshot::from_state(book), reference, config);
+        result.errors.extend(diff.mismatches.into_iter().map(ValidationError::ReferenceMismatch));
+        result.is_valid = result.errors.is_empty();
+        result
+    }
 }
 
 #[derive(Debug)]
@@ -186,6 +202,7 @@ pub enum ValidationError {
         stored: i64,
         side: BookSide,
     },
+    ReferenceMismatch(LevelMismatch),
 }
 
 #[derive(Debug, Clone)]
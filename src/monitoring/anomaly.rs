@@ -0,0 +1,430 @@
+//! Anomaly detection on live monitoring metrics
+//!
+//! Static thresholds only fire once a resource is already exhausted. The
+//! detector instead learns what normal looks like for each metric, using an
+//! exponentially weighted mean/variance and a rolling median/MAD, and flags
+//! samples that sit far outside both. A sample is anomalous only when its
+//! EWMA z-score and its robust z-score both exceed the threshold in the
+//! harmful direction, which keeps single noisy spikes in a short window from
+//! alerting. Sustained anomalies are escalated to fault tolerance.
+
+use crate::fault_tolerance::error_recovery::{
+    ErrorContext, ErrorSeverity, ErrorType, SystemState, UserImpact,
+};
+use crate::fault_tolerance::ErrorRecoveryManager;
+#[cfg(feature = "jobs")]
+use crate::jobs::EventBus;
+use crate::monitoring::{MonitoringUpdate, UpdateType};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+#[cfg(feature = "jobs")]
+use tokio::sync::mpsc;
+use tracing::warn;
+
+/// Scales a median absolute deviation to a standard deviation for normal data
+const MAD_TO_STD: f64 = 1.4826;
+
+/// Metrics the detector learns baselines for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricKind {
+    /// Ticks or evaluations processed per second
+    Throughput,
+    MemoryGb,
+    JobLatencyMs,
+}
+
+impl MetricKind {
+    /// Whether high values are the harmful direction
+    fn high_is_bad(self) -> bool {
+        !matches!(self, MetricKind::Throughput)
+    }
+
+    fn error_type(self) -> ErrorType {
+        match self {
+            MetricKind::MemoryGb => ErrorType::MemoryExhaustion,
+            MetricKind::Throughput | MetricKind::JobLatencyMs => ErrorType::SystemOverload,
+        }
+    }
+}
+
+/// Detector tuning
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnomalyConfig {
+    /// EWMA smoothing factor; higher adapts faster
+    pub ewma_alpha: f64,
+    /// Samples kept for the rolling median/MAD
+    pub window: usize,
+    /// Samples needed before a metric can be flagged
+    pub warmup_samples: usize,
+    /// z-score both estimators must exceed
+    pub z_threshold: f64,
+    /// Lower bound on the spread, as a fraction of the median, so flat series don't alert on noise
+    pub min_spread_fraction: f64,
+    /// Consecutive anomalies of one metric before escalating to fault tolerance
+    pub escalate_after: u32,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            ewma_alpha: 0.1,
+            window: 120,
+            warmup_samples: 30,
+            z_threshold: 3.5,
+            min_spread_fraction: 0.01,
+            escalate_after: 3,
+        }
+    }
+}
+
+/// A metric sample far outside its learned range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricAnomaly {
+    pub metric: MetricKind,
+    pub value: f64,
+    /// Baseline value the sample was compared against
+    pub expected: f64,
+    /// Smaller of the EWMA and robust z-scores, in the harmful direction
+    pub score: f64,
+    pub severity: ErrorSeverity,
+    /// Anomalies of this metric in a row, including this one
+    pub consecutive: u32,
+    pub detected_at: DateTime<Utc>,
+}
+
+impl MetricAnomaly {
+    pub fn message(&self) -> String {
+        format!(
+            "Anomalous {:?}: {:.2} (expected ~{:.2}, z={:.1})",
+            self.metric, self.value, self.expected, self.score
+        )
+    }
+}
+
+/// Learned normal range of one metric
+#[derive(Debug, Clone, Default)]
+struct MetricBaseline {
+    samples: usize,
+    ewma_mean: f64,
+    ewma_var: f64,
+    window: VecDeque<f64>,
+    consecutive: u32,
+}
+
+impl MetricBaseline {
+    /// Score `value` against the EWMA and the rolling median
+    fn score(&self, value: f64, config: &AnomalyConfig) -> Score {
+        let center = median(self.window.iter().copied().collect());
+        let mad = median(self.window.iter().map(|v| (v - center).abs()).collect());
+        let floor = (center.abs() * config.min_spread_fraction).max(f64::EPSILON);
+        let spread = (mad * MAD_TO_STD).max(floor);
+
+        Score {
+            ewma_z: (value - self.ewma_mean) / self.ewma_var.sqrt().max(floor),
+            robust_z: (value - center) / spread,
+            center,
+            spread,
+        }
+    }
+
+    fn update(&mut self, value: f64, config: &AnomalyConfig) {
+        if self.samples == 0 {
+            self.ewma_mean = value;
+        } else {
+            let diff = value - self.ewma_mean;
+            let increment = config.ewma_alpha * diff;
+            self.ewma_mean += increment;
+            self.ewma_var = (1.0 - config.ewma_alpha) * (self.ewma_var + diff * increment);
+        }
+
+        self.window.push_back(value);
+        if self.window.len() > config.window {
+            self.window.pop_front();
+        }
+        self.samples += 1;
+    }
+}
+
+struct Score {
+    ewma_z: f64,
+    robust_z: f64,
+    center: f64,
+    spread: f64,
+}
+
+fn median(mut values: Vec<f64>) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+/// Learns per-metric baselines and flags outliers
+#[derive(Debug, Clone, Default)]
+pub struct AnomalyDetector {
+    config: AnomalyConfig,
+    baselines: HashMap<MetricKind, MetricBaseline>,
+}
+
+impl AnomalyDetector {
+    pub fn new(config: AnomalyConfig) -> Self {
+        Self {
+            config,
+            baselines: HashMap::new(),
+        }
+    }
+
+    /// Score a sample against its baseline, then learn from it
+    ///
+    /// Every sample is folded into the baseline, clipped to the threshold, so a
+    /// lasting shift gradually becomes the new normal.
+    pub fn observe(&mut self, metric: MetricKind, value: f64) -> Option<MetricAnomaly> {
+        if !value.is_finite() {
+            return None;
+        }
+
+        let config = &self.config;
+        let baseline = self.baselines.entry(metric).or_default();
+
+        let mut anomaly = None;
+        let mut learned = value;
+        if baseline.samples >= config.warmup_samples {
+            let Score { ewma_z, robust_z, center, spread } = baseline.score(value, config);
+            let sign = if metric.high_is_bad() { 1.0 } else { -1.0 };
+            let score = (sign * ewma_z).min(sign * robust_z);
+
+            // Outliers in either direction are clipped before learning so one
+            // spike doesn't widen the range enough to hide the next
+            let limit = config.z_threshold * spread;
+            learned = value.clamp(center - limit, center + limit);
+
+            if score > config.z_threshold {
+                baseline.consecutive += 1;
+                anomaly = Some(MetricAnomaly {
+                    metric,
+                    value,
+                    expected: center,
+                    score,
+                    severity: if score > 2.0 * config.z_threshold {
+                        ErrorSeverity::High
+                    } else {
+                        ErrorSeverity::Medium
+                    },
+                    consecutive: baseline.consecutive,
+                    detected_at: Utc::now(),
+                });
+            } else {
+                baseline.consecutive = 0;
+            }
+        }
+
+        baseline.update(learned, config);
+        anomaly
+    }
+
+    pub fn config(&self) -> &AnomalyConfig {
+        &self.config
+    }
+}
+
+/// Shared detector that publishes alerts and escalates sustained anomalies
+///
+/// Alerts go out as `UpdateType::Alert` monitoring updates to every
+/// subscriber, and to the Redis event stream when one is attached. Once a
+/// metric stays anomalous for `escalate_after` samples in a row, it is
+/// reported to the attached `ErrorRecoveryManager`.
+#[derive(Clone)]
+pub struct AnomalyMonitor {
+    detector: Arc<Mutex<AnomalyDetector>>,
+    alerts: broadcast::Sender<MonitoringUpdate>,
+    recovery: Option<ErrorRecoveryManager>,
+    /// Updates waiting to be appended to the event stream, in order
+    #[cfg(feature = "jobs")]
+    stream: Option<mpsc::UnboundedSender<MonitoringUpdate>>,
+}
+
+impl Default for AnomalyMonitor {
+    fn default() -> Self {
+        Self::new(AnomalyConfig::default())
+    }
+}
+
+impl AnomalyMonitor {
+    pub fn new(config: AnomalyConfig) -> Self {
+        Self {
+            detector: Arc::new(Mutex::new(AnomalyDetector::new(config))),
+            alerts: broadcast::channel(64).0,
+            recovery: None,
+            #[cfg(feature = "jobs")]
+            stream: None,
+        }
+    }
+
+    /// Also append every update to `events`, where consumers that were
+    /// offline can replay what they missed
+    ///
+    /// Needs an async runtime to append from; without one updates are only
+    /// broadcast.
+    #[cfg(feature = "jobs")]
+    pub fn with_event_stream(mut self, events: EventBus) -> Self {
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                let (sender, receiver) = mpsc::unbounded_channel();
+                runtime.spawn(append_updates(events, receiver));
+                self.stream = Some(sender);
+            }
+            Err(_) => warn!("No async runtime to append monitoring updates to {} on", events.stream()),
+        }
+        self
+    }
+
+    /// Escalate sustained anomalies through fault tolerance recovery
+    pub fn with_escalation(mut self, recovery: ErrorRecoveryManager) -> Self {
+        self.recovery = Some(recovery);
+        self
+    }
+
+    /// Receive alert updates
+    pub fn subscribe(&self) -> broadcast::Receiver<MonitoringUpdate> {
+        self.alerts.subscribe()
+    }
+
+    /// Send an update that is not an anomaly, such as maintenance counts,
+    /// to the same subscribers
+    pub fn publish(&self, update: MonitoringUpdate) {
+        #[cfg(feature = "jobs")]
+        if let Some(stream) = &self.stream {
+            let _ = stream.send(update.clone());
+        }
+        // No subscribers is not an error
+        let _ = self.alerts.send(update);
+    }
+
+    /// Record a sample, alerting and escalating if it is anomalous
+    pub fn observe(&self, metric: MetricKind, value: f64) -> Option<MetricAnomaly> {
+        let (anomaly, escalate_after) = {
+            let mut detector = self.detector.lock().unwrap();
+            (detector.observe(metric, value)?, detector.config().escalate_after)
+        };
+
+        warn!("{}", anomaly.message());
+        self.publish(MonitoringUpdate::new(
+            UpdateType::Alert,
+            serde_json::json!({
+                "message": anomaly.message(),
+                "anomaly": anomaly,
+            }),
+        ));
+
+        if anomaly.consecutive == escalate_after {
+            self.escalate(&anomaly);
+        }
+
+        Some(anomaly)
+    }
+
+    fn escalate(&self, anomaly: &MetricAnomaly) {
+        let Some(recovery) = self.recovery.clone() else {
+            return;
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            warn!("No async runtime to escalate {:?} anomaly on", anomaly.metric);
+            return;
+        };
+
+        let context = ErrorContext {
+            error_id: uuid::Uuid::new_v4(),
+            error_type: anomaly.metric.error_type(),
+            timestamp: anomaly.detected_at,
+            severity: anomaly.severity.clone(),
+            component: "monitoring".to_string(),
+            message: format!("Sustained anomaly: {}", anomaly.message()),
+            metadata: HashMap::from([
+                ("metric".to_string(), format!("{:?}", anomaly.metric)),
+                ("value".to_string(), anomaly.value.to_string()),
+                ("expected".to_string(), anomaly.expected.to_string()),
+                ("consecutive".to_string(), anomaly.consecutive.to_string()),
+            ]),
+            stack_trace: None,
+            user_impact: UserImpact::PerformanceDegraded,
+            system_state: SystemState {
+                cpu_usage_percent: 0.0,
+                memory_usage_mb: 0,
+                active_connections: 0,
+                pending_jobs: 0,
+                error_rate_per_minute: 0.0,
+            },
+        };
+
+        runtime.spawn(async move {
+            if let Err(e) = recovery.handle_error(context).await {
+                warn!("Anomaly escalation failed: {}", e);
+            }
+        });
+    }
+}
+
+/// Append updates to the event stream until every monitor is dropped
+#[cfg(feature = "jobs")]
+async fn append_updates(mut events: EventBus, mut updates: mpsc::UnboundedReceiver<MonitoringUpdate>) {
+    while let Some(update) = updates.recv().await {
+        if let Err(e) = events.publish(&format!("{:?}", update.update_type), &update).await {
+            warn!("Failed to append monitoring update to {}: {}", events.stream(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn warmed_up(metric: MetricKind) -> AnomalyDetector {
+        let mut detector = AnomalyDetector::default();
+        for i in 0..60 {
+            // Small oscillation around 100
+            let value = 100.0 + (i % 5) as f64 - 2.0;
+            assert!(detector.observe(metric, value).is_none());
+        }
+        detector
+    }
+
+    #[test]
+    fn test_flags_only_harmful_direction() {
+        let mut latency = warmed_up(MetricKind::JobLatencyMs);
+        assert!(latency.observe(MetricKind::JobLatencyMs, 103.0).is_none());
+        assert!(latency.observe(MetricKind::JobLatencyMs, 20.0).is_none());
+        let anomaly = latency.observe(MetricKind::JobLatencyMs, 250.0).unwrap();
+        assert!(anomaly.score > 3.5);
+        assert!((anomaly.expected - 100.0).abs() < 2.0);
+
+        let mut throughput = warmed_up(MetricKind::Throughput);
+        assert!(throughput.observe(MetricKind::Throughput, 250.0).is_none());
+        assert!(throughput.observe(MetricKind::Throughput, 20.0).is_some());
+    }
+
+    #[test]
+    fn test_consecutive_count_and_warmup() {
+        let mut detector = AnomalyDetector::default();
+        assert!(detector.observe(MetricKind::MemoryGb, 1_000.0).is_none());
+
+        let mut detector = warmed_up(MetricKind::MemoryGb);
+        let counts: Vec<u32> = (0..3)
+            .filter_map(|_| detector.observe(MetricKind::MemoryGb, 400.0))
+            .map(|a| a.consecutive)
+            .collect();
+        assert_eq!(counts, vec![1, 2, 3]);
+
+        assert!(detector.observe(MetricKind::MemoryGb, 100.0).is_none());
+        assert_eq!(detector.observe(MetricKind::MemoryGb, 900.0).unwrap().consecutive, 1);
+    }
+}
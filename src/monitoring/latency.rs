@@ -0,0 +1,167 @@
+//! Nanosecond latency histograms for the tick-processing hot path
+//!
+//! The engine records into a `LatencyHistograms` it owns (no locking per tick)
+//! and periodically merges it into a shared `LatencyRegistry`, which the
+//! monitoring API reads to report percentiles per processing stage.
+
+use hdrhistogram::Histogram;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Highest latency tracked before values are clamped (10 seconds)
+const MAX_TRACKABLE_NS: u64 = 10_000_000_000;
+
+/// Significant figures kept by each histogram
+const SIGNIFICANT_FIGURES: u8 = 3;
+
+/// Stages of per-tick processing that are timed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LatencyStage {
+    /// Decoding raw data into ticks (amortized per tick)
+    Parse,
+    /// Applying the tick to the order book
+    BookUpdate,
+    /// Strategy `on_tick` callback
+    StrategyCallback,
+    /// Simulated order execution and fill handling
+    OrderMatching,
+}
+
+impl LatencyStage {
+    pub const ALL: [LatencyStage; 4] = [
+        LatencyStage::Parse,
+        LatencyStage::BookUpdate,
+        LatencyStage::StrategyCallback,
+        LatencyStage::OrderMatching,
+    ];
+}
+
+/// Percentile summary of one stage, in nanoseconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageLatency {
+    pub stage: LatencyStage,
+    pub count: u64,
+    pub min_ns: u64,
+    pub mean_ns: f64,
+    pub p50_ns: u64,
+    pub p90_ns: u64,
+    pub p99_ns: u64,
+    pub p999_ns: u64,
+    pub max_ns: u64,
+}
+
+/// Latency summary across all stages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyReport {
+    pub stages: Vec<StageLatency>,
+}
+
+/// Per-stage HDR histograms owned by a single processing loop
+#[derive(Debug, Clone)]
+pub struct LatencyHistograms {
+    histograms: BTreeMap<LatencyStage, Histogram<u64>>,
+}
+
+impl LatencyHistograms {
+    pub fn new() -> Self {
+        let histograms = LatencyStage::ALL.iter()
+            .map(|stage| {
+                let histogram = Histogram::new_with_bounds(1, MAX_TRACKABLE_NS, SIGNIFICANT_FIGURES)
+                    .expect("valid histogram bounds");
+                (*stage, histogram)
+            })
+            .collect();
+
+        Self { histograms }
+    }
+
+    /// Record a single latency sample
+    #[inline]
+    pub fn record(&mut self, stage: LatencyStage, nanos: u64) {
+        if let Some(histogram) = self.histograms.get_mut(&stage) {
+            histogram.saturating_record(nanos.max(1));
+        }
+    }
+
+    /// Record the same latency for `count` events (e.g. amortized batch parsing)
+    pub fn record_n(&mut self, stage: LatencyStage, nanos: u64, count: u64) {
+        if let Some(histogram) = self.histograms.get_mut(&stage) {
+            let _ = histogram.record_n(nanos.clamp(1, MAX_TRACKABLE_NS), count);
+        }
+    }
+
+    /// Record the time elapsed since `start`
+    #[inline]
+    pub fn record_since(&mut self, stage: LatencyStage, start: Instant) {
+        self.record(stage, start.elapsed().as_nanos() as u64);
+    }
+
+    /// Fold another set of histograms into this one
+    pub fn merge(&mut self, other: &LatencyHistograms) {
+        for (stage, histogram) in &other.histograms {
+            if let Some(target) = self.histograms.get_mut(stage) {
+                let _ = target.add(histogram);
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for histogram in self.histograms.values_mut() {
+            histogram.reset();
+        }
+    }
+
+    /// Percentile summary of every stage that has samples
+    pub fn report(&self) -> LatencyReport {
+        let stages = self.histograms.iter()
+            .filter(|(_, histogram)| !histogram.is_empty())
+            .map(|(stage, histogram)| StageLatency {
+                stage: *stage,
+                count: histogram.len(),
+                min_ns: histogram.min(),
+                mean_ns: histogram.mean(),
+                p50_ns: histogram.value_at_quantile(0.50),
+                p90_ns: histogram.value_at_quantile(0.90),
+                p99_ns: histogram.value_at_quantile(0.99),
+                p999_ns: histogram.value_at_quantile(0.999),
+                max_ns: histogram.max(),
+            })
+            .collect();
+
+        LatencyReport { stages }
+    }
+}
+
+impl Default for LatencyHistograms {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Process-wide latency histograms shared with the monitoring API
+#[derive(Debug, Clone, Default)]
+pub struct LatencyRegistry {
+    inner: Arc<Mutex<LatencyHistograms>>,
+}
+
+impl LatencyRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Publish samples collected by a processing loop
+    pub fn publish(&self, histograms: &LatencyHistograms) {
+        self.inner.lock().unwrap().merge(histograms);
+    }
+
+    pub fn report(&self) -> LatencyReport {
+        self.inner.lock().unwrap().report()
+    }
+
+    pub fn reset(&self) {
+        self.inner.lock().unwrap().reset();
+    }
+}
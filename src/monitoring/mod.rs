@@ -8,15 +8,26 @@ pub mod monitor;
 pub mod metrics;
 pub mod resource;
 pub mod progress;
+#[cfg(feature = "websocket")]
 pub mod websocket;
+#[cfg(all(feature = "websocket", feature = "jobs"))]
 pub mod websocket_tests;
+#[cfg(feature = "websocket")]
 pub mod dashboard;
 pub mod types;
+pub mod latency;
+pub mod anomaly;
+pub mod risk_stream;
 
 pub use monitor::{PerformanceMonitor, MonitorConfig};
 pub use metrics::{SystemMetrics, OptimizationMetrics};
 pub use resource::{ResourceMonitor, ResourceUsage};
 pub use progress::ProgressTracker;
-pub use websocket::WebSocketServer;
+#[cfg(feature = "websocket")]
+pub use websocket::{MessagePriority, TlsConfig, WebSocketError, WebSocketServer, WebSocketServerBuilder};
+#[cfg(feature = "websocket")]
 pub use dashboard::DashboardData;
-pub use types::{MonitoringUpdate, UpdateType};
\ No newline at end of file
+pub use types::{MonitoringUpdate, UpdateType, MONITORING_EVENTS_STREAM};
+pub use latency::{LatencyHistograms, LatencyRegistry, LatencyReport, LatencyStage};
+pub use anomaly::{AnomalyConfig, AnomalyDetector, AnomalyMonitor, MetricAnomaly, MetricKind};
+pub use risk_stream::{RiskBoard, RiskCutoff, RiskHeadroom, RiskLimits, RiskSnapshot, SessionMode};
\ No newline at end of file
@@ -1,10 +1,16 @@
 //! Core performance monitoring implementation
 
+use crate::fault_tolerance::ErrorRecoveryManager;
+use crate::performance::ThreadPools;
 use crate::monitoring::{
     ResourceMonitor, ProgressTracker, SystemMetrics, OptimizationMetrics,
-    WebSocketServer, MonitoringUpdate, UpdateType
+    MonitoringUpdate, UpdateType, AnomalyConfig, AnomalyMonitor, MetricKind, RiskBoard
 };
+#[cfg(feature = "websocket")]
+use crate::monitoring::WebSocketServer;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "websocket")]
+use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
@@ -23,7 +29,8 @@ pub struct MonitorConfig {
     /// Enable progress tracking
     pub track_progress: bool,
     
-    /// WebSocket port for real-time updates
+    /// WebSocket port for real-time updates; ignored without the
+    /// `websocket` feature
     pub websocket_port: Option<u16>,
     
     /// Resource usage thresholds
@@ -31,6 +38,18 @@ pub struct MonitorConfig {
     
     /// Save monitoring data to file
     pub save_to_file: bool,
+    
+    /// Learned-baseline anomaly detection on top of the fixed thresholds
+    #[serde(default)]
+    pub anomaly_detection: AnomalyConfig,
+    
+    /// How often the risk of paper and live sessions is streamed, in milliseconds
+    #[serde(default = "default_risk_stream_interval_ms")]
+    pub risk_stream_interval_ms: u64,
+}
+
+fn default_risk_stream_interval_ms() -> u64 {
+    1000
 }
 
 /// Resource usage thresholds for alerts
@@ -54,6 +73,8 @@ impl Default for MonitorConfig {
                 max_disk_io_mb_s: 100.0,
             },
             save_to_file: true,
+            anomaly_detection: AnomalyConfig::default(),
+            risk_stream_interval_ms: default_risk_stream_interval_ms(),
         }
     }
 }
@@ -65,7 +86,11 @@ pub struct PerformanceMonitor {
     progress_tracker: Arc<Mutex<ProgressTracker>>,
     system_metrics: Arc<Mutex<SystemMetrics>>,
     optimization_metrics: Arc<Mutex<OptimizationMetrics>>,
+    #[cfg(feature = "websocket")]
     websocket_server: Option<WebSocketServer>,
+    anomalies: AnomalyMonitor,
+    risk_board: RiskBoard,
+    thread_pools: Option<ThreadPools>,
     start_time: Instant,
     is_running: Arc<Mutex<bool>>,
     is_paused: Arc<Mutex<bool>>,
@@ -74,7 +99,16 @@ pub struct PerformanceMonitor {
 impl PerformanceMonitor {
     /// Create new performance monitor
     pub fn new(config: MonitorConfig) -> Self {
-        let websocket_server = config.websocket_port.map(WebSocketServer::new);
+        let risk_board = RiskBoard::new();
+        #[cfg(feature = "websocket")]
+        let websocket_server = config.websocket_port.map(|port| {
+            WebSocketServer::builder()
+                .bind(SocketAddr::from(([127, 0, 0, 1], port)).to_string())
+                .risk_board(risk_board.clone())
+                .risk_interval(Duration::from_millis(config.risk_stream_interval_ms.max(1)))
+                .build()
+        });
+        let anomalies = AnomalyMonitor::new(config.anomaly_detection.clone());
         
         Self {
             config,
@@ -82,13 +116,41 @@ impl PerformanceMonitor {
             progress_tracker: Arc::new(Mutex::new(ProgressTracker::new())),
             system_metrics: Arc::new(Mutex::new(SystemMetrics::default())),
             optimization_metrics: Arc::new(Mutex::new(OptimizationMetrics::default())),
+            #[cfg(feature = "websocket")]
             websocket_server,
+            anomalies,
+            risk_board,
+            thread_pools: None,
             start_time: Instant::now(),
             is_running: Arc::new(Mutex::new(false)),
             is_paused: Arc::new(Mutex::new(false)),
         }
     }
     
+    /// Escalate sustained metric anomalies through fault tolerance recovery
+    pub fn with_escalation(mut self, recovery: ErrorRecoveryManager) -> Self {
+        self.anomalies = self.anomalies.with_escalation(recovery);
+        self
+    }
+    
+    /// Report the engine pools' layout and measured throughput with each update
+    pub fn with_thread_pools(mut self, thread_pools: ThreadPools) -> Self {
+        self.thread_pools = Some(thread_pools);
+        self
+    }
+    
+    /// Anomaly monitor fed by this monitor; share it with job queues and
+    /// engines so their throughput and latency are learned too
+    pub fn anomalies(&self) -> &AnomalyMonitor {
+        &self.anomalies
+    }
+    
+    /// Board paper and live sessions report fills and marks to; its
+    /// snapshots are streamed over the WebSocket every risk stream interval
+    pub fn risk_board(&self) -> &RiskBoard {
+        &self.risk_board
+    }
+    
     /// Start monitoring
     pub async fn start(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         *self.is_running.lock().unwrap() = true;
@@ -97,8 +159,9 @@ impl PerformanceMonitor {
         info!("Starting performance monitoring");
         
         // Start WebSocket server if configured
+        #[cfg(feature = "websocket")]
         if let Some(ref mut ws) = self.websocket_server {
-            ws.start("127.0.0.1:8080").await?;
+            ws.listen().await?;
         }
         
         // Start monitoring loop
@@ -112,6 +175,8 @@ impl PerformanceMonitor {
         let optimization_metrics = Arc::clone(&self.optimization_metrics);
         let is_running = Arc::clone(&self.is_running);
         let is_paused = Arc::clone(&self.is_paused);
+        let anomalies = self.anomalies.clone();
+        let thread_pools = self.thread_pools.clone();
         
         tokio::spawn(async move {
             let mut interval = interval(Duration::from_millis(config.update_interval_ms));
@@ -130,6 +195,8 @@ impl PerformanceMonitor {
                     &system_metrics,
                     &optimization_metrics,
                     &config.resource_thresholds,
+                    &anomalies,
+                    thread_pools.as_ref(),
                 ).await;
                 
                 // Send update
@@ -153,6 +220,7 @@ impl PerformanceMonitor {
     pub async fn stop(&mut self) {
         *self.is_running.lock().unwrap() = false;
         
+        #[cfg(feature = "websocket")]
         if let Some(ref mut ws) = self.websocket_server {
             ws.stop().await;
         }
@@ -179,6 +247,8 @@ impl PerformanceMonitor {
         system_metrics: &Arc<Mutex<SystemMetrics>>,
         optimization_metrics: &Arc<Mutex<OptimizationMetrics>>,
         thresholds: &ResourceThresholds,
+        anomalies: &AnomalyMonitor,
+        thread_pools: Option<&ThreadPools>,
     ) -> MonitoringUpdate {
         let resources = resource_monitor.get_current_usage();
         let progress = progress_tracker.lock().unwrap().get_all_progress();
@@ -202,6 +272,11 @@ impl PerformanceMonitor {
             ));
         }
         
+        // Flag unusual growth well before the fixed threshold is reached
+        if let Some(anomaly) = anomalies.observe(MetricKind::MemoryGb, resources.memory_gb) {
+            alerts.push(anomaly.message());
+        }
+        
         MonitoringUpdate {
             update_type: UpdateType::SystemMetrics,
             timestamp: chrono::Utc::now(),
@@ -211,6 +286,7 @@ impl PerformanceMonitor {
                 "system_metrics": sys_metrics,
                 "optimization_metrics": opt_metrics,
                 "alerts": alerts,
+                "threading": thread_pools.map(ThreadPools::report),
             }),
         }
     }
@@ -229,6 +305,7 @@ impl PerformanceMonitor {
         }
         
         // Send to WebSocket clients
+        #[cfg(feature = "websocket")]
         if let Some(ref mut ws) = self.websocket_server {
             // ws.broadcast(update.clone()).await;
             // TODO: Implement broadcast method
@@ -0,0 +1,286 @@
+//! Real-time risk of paper and live sessions
+//!
+//! A running session reports its fills and marks to a shared `RiskBoard`,
+//! which keeps its position, open P&L and P&L for the trading day. The
+//! monitoring WebSocket samples the board at a configurable interval and
+//! streams one `RiskSnapshot` per session, including how far the session is
+//! from each risk cutoff, so guardrails can be watched while they matter
+//! rather than reconstructed from the ledger afterwards.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::strategy::config::RiskConstraints;
+use crate::strategy::traits::OrderFill;
+use crate::strategy::Position;
+
+/// Whether a session trades real money
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionMode {
+    Paper,
+    Live,
+}
+
+/// Cutoffs a session is held to, in contracts and account currency
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RiskLimits {
+    pub max_position: i32,
+    /// Loss for the day at which trading stops
+    #[serde(default)]
+    pub max_daily_loss: Option<Decimal>,
+    /// Drop from the day's best P&L at which trading stops
+    #[serde(default)]
+    pub max_drawdown: Option<Decimal>,
+}
+
+impl From<&RiskConstraints> for RiskLimits {
+    fn from(constraints: &RiskConstraints) -> Self {
+        Self {
+            max_position: constraints.max_position,
+            max_daily_loss: constraints.max_daily_loss,
+            max_drawdown: Some(constraints.max_drawdown),
+        }
+    }
+}
+
+/// A risk cutoff
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskCutoff {
+    Position,
+    DailyLoss,
+    Drawdown,
+}
+
+/// Room left before each cutoff; negative once it is breached
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RiskHeadroom {
+    /// Contracts that can still be added
+    pub position: i32,
+    /// Further loss the day can take; unset without a daily loss limit
+    pub daily_loss: Option<Decimal>,
+    /// Further drop from the day's best P&L; unset without a drawdown limit
+    pub drawdown: Option<Decimal>,
+}
+
+/// Risk of one session at a point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiskSnapshot {
+    pub session_id: String,
+    pub strategy_id: String,
+    pub mode: SessionMode,
+    pub timestamp: DateTime<Utc>,
+    /// Signed position in contracts
+    pub position: i32,
+    pub avg_entry_price: Decimal,
+    pub last_price: Option<Decimal>,
+    pub open_pnl: Decimal,
+    /// Realized and open P&L since the day started, net of commission
+    pub daily_pnl: Decimal,
+    /// Best `daily_pnl` of the day
+    pub daily_peak_pnl: Decimal,
+    pub limits: RiskLimits,
+    pub headroom: RiskHeadroom,
+    /// Cutoffs at or past their limit
+    pub breached: Vec<RiskCutoff>,
+}
+
+impl RiskSnapshot {
+    pub fn is_breached(&self) -> bool {
+        !self.breached.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SessionRisk {
+    strategy_id: String,
+    mode: SessionMode,
+    limits: RiskLimits,
+    position: Position,
+    last_price: Option<Decimal>,
+    /// Position P&L when the day started, subtracted to get the day's P&L
+    day_start_pnl: Decimal,
+    daily_peak_pnl: Decimal,
+}
+
+impl SessionRisk {
+    fn daily_pnl(&self) -> Decimal {
+        self.position.total_pnl() - self.day_start_pnl
+    }
+
+    fn track_peak(&mut self) {
+        self.daily_peak_pnl = self.daily_peak_pnl.max(self.daily_pnl());
+    }
+
+    fn snapshot(&self, session_id: &str, timestamp: DateTime<Utc>) -> RiskSnapshot {
+        let daily_pnl = self.daily_pnl();
+        let headroom = RiskHeadroom {
+            position: self.limits.max_position - self.position.size.abs(),
+            daily_loss: self.limits.max_daily_loss.map(|limit| limit + daily_pnl),
+            drawdown: self.limits.max_drawdown.map(|limit| limit - (self.daily_peak_pnl - daily_pnl)),
+        };
+        let mut breached = Vec::new();
+        if headroom.position < 0 {
+            breached.push(RiskCutoff::Position);
+        }
+        if headroom.daily_loss.is_some_and(|room| room <= Decimal::ZERO) {
+            breached.push(RiskCutoff::DailyLoss);
+        }
+        if headroom.drawdown.is_some_and(|room| room <= Decimal::ZERO) {
+            breached.push(RiskCutoff::Drawdown);
+        }
+
+        RiskSnapshot {
+            session_id: session_id.to_string(),
+            strategy_id: self.strategy_id.clone(),
+            mode: self.mode,
+            timestamp,
+            position: self.position.size,
+            avg_entry_price: self.position.avg_entry_price,
+            last_price: self.last_price,
+            open_pnl: self.position.unrealized_pnl,
+            daily_pnl,
+            daily_peak_pnl: self.daily_peak_pnl,
+            limits: self.limits.clone(),
+            headroom,
+            breached,
+        }
+    }
+}
+
+/// Shared risk state of running sessions
+///
+/// Cheap to clone; every clone sees the same sessions.
+#[derive(Debug, Clone, Default)]
+pub struct RiskBoard {
+    sessions: Arc<RwLock<HashMap<String, SessionRisk>>>,
+}
+
+impl RiskBoard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start tracking a session, replacing any earlier one with the same id
+    pub fn register(&self, session_id: impl Into<String>, strategy_id: impl Into<String>, mode: SessionMode, limits: RiskLimits) {
+        self.sessions.write().unwrap().insert(session_id.into(), SessionRisk {
+            strategy_id: strategy_id.into(),
+            mode,
+            limits,
+            position: Position::new(),
+            last_price: None,
+            day_start_pnl: Decimal::ZERO,
+            daily_peak_pnl: Decimal::ZERO,
+        });
+    }
+
+    /// Stop tracking a session
+    pub fn remove(&self, session_id: &str) {
+        self.sessions.write().unwrap().remove(session_id);
+    }
+
+    /// Apply a fill to the session's position; unknown sessions are ignored
+    pub fn record_fill(&self, session_id: &str, fill: &OrderFill) {
+        if let Some(session) = self.sessions.write().unwrap().get_mut(session_id) {
+            session.position.apply_fill(fill);
+            if let Some(price) = session.last_price {
+                session.position.update_unrealized_pnl(price);
+            }
+            session.track_peak();
+        }
+    }
+
+    /// Mark the session's open position to the latest price
+    pub fn mark(&self, session_id: &str, price: Decimal) {
+        if let Some(session) = self.sessions.write().unwrap().get_mut(session_id) {
+            session.last_price = Some(price);
+            session.position.update_unrealized_pnl(price);
+            session.track_peak();
+        }
+    }
+
+    /// Start a new trading day: daily P&L and its peak restart from zero,
+    /// the position carries over
+    pub fn start_day(&self, session_id: &str) {
+        if let Some(session) = self.sessions.write().unwrap().get_mut(session_id) {
+            session.day_start_pnl = session.position.total_pnl();
+            session.daily_peak_pnl = Decimal::ZERO;
+        }
+    }
+
+    pub fn snapshot(&self, session_id: &str) -> Option<RiskSnapshot> {
+        self.sessions.read().unwrap().get(session_id).map(|session| session.snapshot(session_id, Utc::now()))
+    }
+
+    /// Snapshots of every session, ordered by session id
+    pub fn snapshots(&self) -> Vec<RiskSnapshot> {
+        let now = Utc::now();
+        let mut snapshots: Vec<RiskSnapshot> = self.sessions.read().unwrap().iter()
+            .map(|(id, session)| session.snapshot(id, now))
+            .collect();
+        snapshots.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+        snapshots
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.read().unwrap().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::OrderSide;
+
+    fn fill(side: OrderSide, quantity: i32, price: i64) -> OrderFill {
+        OrderFill {
+            order_id: "o".to_string(),
+            timestamp: Utc::now(),
+            price: Decimal::from(price),
+            quantity,
+            side,
+            commission: Decimal::ZERO,
+            slippage: Decimal::ZERO,
+            tag: None,
+            position_effect: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_headroom_and_breaches() {
+        let board = RiskBoard::new();
+        board.register("paper-1", "scalper", SessionMode::Paper, RiskLimits {
+            max_position: 2,
+            max_daily_loss: Some(Decimal::from(100)),
+            max_drawdown: Some(Decimal::from(60)),
+        });
+
+        board.record_fill("paper-1", &fill(OrderSide::Buy, 2, 1000));
+        board.mark("paper-1", Decimal::from(1040));
+        let snapshot = board.snapshot("paper-1").unwrap();
+        assert_eq!(snapshot.open_pnl, Decimal::from(80));
+        assert_eq!(snapshot.headroom.position, 0);
+        assert_eq!(snapshot.headroom.daily_loss, Some(Decimal::from(180)));
+        assert!(!snapshot.is_breached());
+
+        // Giving back 70 from the day's best breaches the drawdown cutoff only
+        board.mark("paper-1", Decimal::from(1005));
+        let snapshot = board.snapshot("paper-1").unwrap();
+        assert_eq!(snapshot.daily_pnl, Decimal::from(10));
+        assert_eq!(snapshot.headroom.drawdown, Some(Decimal::from(-10)));
+        assert_eq!(snapshot.breached, vec![RiskCutoff::Drawdown]);
+
+        // A new day carries the position but not the P&L
+        board.start_day("paper-1");
+        board.mark("paper-1", Decimal::from(945));
+        let snapshot = board.snapshot("paper-1").unwrap();
+        assert_eq!(snapshot.position, 2);
+        assert_eq!(snapshot.daily_pnl, Decimal::from(-120));
+        assert_eq!(snapshot.breached, vec![RiskCutoff::DailyLoss, RiskCutoff::Drawdown]);
+        assert!(board.snapshot("missing").is_none());
+    }
+}
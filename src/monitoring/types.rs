@@ -3,6 +3,9 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
+/// Redis stream monitoring updates are appended to
+pub const MONITORING_EVENTS_STREAM: &str = "monitoring_events";
+
 /// Update message for monitoring system
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitoringUpdate {
@@ -19,6 +22,11 @@ pub enum UpdateType {
     TradeExecution,
     Alert,
     Status,
+    JobMaintenance,
+    /// Queue depth, backlog and worker count seen by the autoscaler
+    Autoscaling,
+    /// Advice raised for a strategy, such as new parameters to review
+    Recommendation,
 }
 
 impl MonitoringUpdate {
@@ -1,73 +1,834 @@
-use tokio_tungstenite::{accept_async, WebSocketStream};
-use tokio::net::TcpStream;
-use tokio_tungstenite::tungstenite::{Message, Result};
+//! Monitoring WebSocket server
+//!
+//! Streams job progress, system metrics, monitoring updates and the risk of
+//! paper and live sessions to dashboard clients. The server can be embedded by any binary: configure it with
+//! `WebSocketServer::builder()`, optionally with TLS, and stop it with
+//! `stop` or by cancelling the shutdown token it was built with. Given the
+//! Redis stream monitoring updates are appended to, the server relays them
+//! with their stream ids, and clients that reconnect ask for a `Replay` of
+//! the updates after the last id they saw.
+
 use futures::{SinkExt, StreamExt};
-use serde_json;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
 use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinHandle;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::TlsAcceptor;
+use tokio_tungstenite::accept_async;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
 
+#[cfg(feature = "jobs")]
+use crate::jobs::EventBus;
 use crate::monitoring::{
-    progress::{ProgressManager, ProgressUpdate},
     metrics::{MetricsCollector, SystemMetrics},
+    progress::{ProgressManager, ProgressUpdateType},
+    risk_stream::{RiskBoard, RiskSnapshot},
+    types::{MonitoringUpdate, UpdateType},
 };
 
+/// Address the server binds to unless configured otherwise
+pub const DEFAULT_BIND_ADDR: &str = "127.0.0.1:8080";
+
+/// How long `stop` waits for open connections to close
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// Messages buffered per client before slow clients start missing updates
+const UPDATE_CHANNEL_CAPACITY: usize = 1024;
+
+/// How often session risk is sampled unless configured otherwise
+pub const DEFAULT_RISK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Pending messages that force a batch out before the flush interval
+const MAX_BATCH_MESSAGES: usize = 500;
+
+/// Most updates sent for one `Replay` request
+#[cfg(feature = "jobs")]
+const MAX_REPLAY_EVENTS: usize = 1000;
+
+/// How long one read of the event stream waits for new updates
+#[cfg(feature = "jobs")]
+const TAIL_BLOCK_MS: usize = 1000;
+
+/// Errors raised by the monitoring WebSocket server
+#[derive(Debug, thiserror::Error)]
+pub enum WebSocketError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("TLS configuration error: {0}")]
+    Tls(String),
+    #[error("WebSocket server is already running on {0}")]
+    AlreadyRunning(SocketAddr),
+}
+
+/// Certificate and key used to serve `wss://`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// PEM certificate chain, leaf first
+    pub cert_path: PathBuf,
+    /// PEM private key
+    pub key_path: PathBuf,
+}
+
+impl TlsConfig {
+    pub fn new(cert_path: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Self {
+        Self {
+            cert_path: cert_path.into(),
+            key_path: key_path.into(),
+        }
+    }
+
+    fn acceptor(&self) -> Result<TlsAcceptor, WebSocketError> {
+        let tls_error = |e: &dyn std::fmt::Display| WebSocketError::Tls(e.to_string());
+
+        let certs = CertificateDer::pem_file_iter(&self.cert_path)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| tls_error(&format!("{}: {}", self.cert_path.display(), e)))?;
+        let key = PrivateKeyDer::from_pem_file(&self.key_path)
+            .map_err(|e| tls_error(&format!("{}: {}", self.key_path.display(), e)))?;
+
+        let config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| tls_error(&e))?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .map_err(|e| tls_error(&e))?;
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+}
+
+/// Configures a `WebSocketServer`
+pub struct WebSocketServerBuilder {
+    bind_addr: String,
+    tls: Option<TlsConfig>,
+    heartbeat_interval: Duration,
+    batch_interval: Option<Duration>,
+    progress_manager: Option<Arc<ProgressManager>>,
+    metrics_collector: Option<Arc<RwLock<MetricsCollector>>>,
+    risk_board: Option<RiskBoard>,
+    risk_interval: Duration,
+    #[cfg(feature = "jobs")]
+    events: Option<EventBus>,
+    shutdown: CancellationToken,
+}
+
+impl Default for WebSocketServerBuilder {
+    fn default() -> Self {
+        Self {
+            bind_addr: DEFAULT_BIND_ADDR.to_string(),
+            tls: None,
+            heartbeat_interval: Duration::from_secs(30),
+            batch_interval: None,
+            progress_manager: None,
+            metrics_collector: None,
+            risk_board: None,
+            risk_interval: DEFAULT_RISK_INTERVAL,
+            #[cfg(feature = "jobs")]
+            events: None,
+            shutdown: CancellationToken::new(),
+        }
+    }
+}
+
+impl WebSocketServerBuilder {
+    /// Address to listen on; port 0 picks a free port
+    pub fn bind(mut self, addr: impl Into<String>) -> Self {
+        self.bind_addr = addr.into();
+        self
+    }
+
+    /// Serve `wss://` with this certificate
+    pub fn tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    pub fn heartbeat_interval(mut self, interval: Duration) -> Self {
+        self.heartbeat_interval = interval;
+        self
+    }
+
+    /// Coalesce updates into one `Batch` frame per interval
+    ///
+    /// High-priority messages such as job failures and alerts still go out
+    /// immediately. Without this every update is its own frame.
+    pub fn batch_interval(mut self, interval: Duration) -> Self {
+        self.batch_interval = Some(interval);
+        self
+    }
+
+    /// Stream job progress from this manager; a private one is used otherwise
+    pub fn progress_manager(mut self, progress_manager: Arc<ProgressManager>) -> Self {
+        self.progress_manager = Some(progress_manager);
+        self
+    }
+
+    pub fn metrics_collector(mut self, metrics_collector: Arc<RwLock<MetricsCollector>>) -> Self {
+        self.metrics_collector = Some(metrics_collector);
+        self
+    }
+
+    /// Stream a `RiskSnapshot` of every session on this board each risk interval
+    pub fn risk_board(mut self, risk_board: RiskBoard) -> Self {
+        self.risk_board = Some(risk_board);
+        self
+    }
+
+    /// How often session risk is streamed; `DEFAULT_RISK_INTERVAL` otherwise
+    pub fn risk_interval(mut self, interval: Duration) -> Self {
+        self.risk_interval = interval;
+        self
+    }
+
+    /// Relay monitoring updates appended to this stream and replay them on
+    /// request
+    ///
+    /// The server blocks reading the stream, so give it a bus on a
+    /// connection of its own.
+    #[cfg(feature = "jobs")]
+    pub fn event_stream(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Stop the server when this token is cancelled, e.g. on process shutdown
+    pub fn shutdown_token(mut self, token: CancellationToken) -> Self {
+        self.shutdown = token;
+        self
+    }
+
+    pub fn build(self) -> WebSocketServer {
+        WebSocketServer {
+            bind_addr: self.bind_addr,
+            tls: self.tls,
+            heartbeat_interval: self.heartbeat_interval,
+            batch_interval: self.batch_interval,
+            progress_manager: self.progress_manager.unwrap_or_else(|| Arc::new(ProgressManager::new())),
+            metrics_collector: self.metrics_collector
+                .unwrap_or_else(|| Arc::new(RwLock::new(MetricsCollector::new()))),
+            risk_board: self.risk_board,
+            risk_interval: self.risk_interval,
+            #[cfg(feature = "jobs")]
+            events: self.events,
+            parent_shutdown: self.shutdown,
+            connections: Arc::new(RwLock::new(HashMap::new())),
+            updates: broadcast::channel(UPDATE_CHANNEL_CAPACITY).0,
+            is_running: Arc::new(AtomicBool::new(false)),
+            running: None,
+        }
+    }
+}
+
+/// State of a running server, dropped on stop
+struct Running {
+    local_addr: SocketAddr,
+    shutdown: CancellationToken,
+    tasks: TaskTracker,
+    accept_loop: JoinHandle<()>,
+}
+
+/// Monitoring WebSocket server
 pub struct WebSocketServer {
+    bind_addr: String,
+    tls: Option<TlsConfig>,
+    heartbeat_interval: Duration,
+    batch_interval: Option<Duration>,
     progress_manager: Arc<ProgressManager>,
     metrics_collector: Arc<RwLock<MetricsCollector>>,
-    connections: Arc<RwLock<HashMap<String, WebSocketConnection>>>,
-    is_running: Arc<AtomicBool>,
+    risk_board: Option<RiskBoard>,
+    risk_interval: Duration,
+    #[cfg(feature = "jobs")]
+    events: Option<EventBus>,
+    parent_shutdown: CancellationToken,
+    pub(crate) connections: Arc<RwLock<HashMap<Uuid, ConnectionState>>>,
+    updates: broadcast::Sender<WebSocketMessage>,
+    pub(crate) is_running: Arc<AtomicBool>,
+    running: Option<Running>,
+}
+
+impl Default for WebSocketServer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl WebSocketServer {
-    pub fn new(
-        progress_manager: Arc<ProgressManager>,
-        metrics_collector: Arc<RwLock<MetricsCollector>>,
-    ) -> Self {
-        Self {
-            progress_manager,
-            metrics_collector,
-            connections: Arc::new(RwLock::new(HashMap::new())),
-            is_running: Arc::new(AtomicBool::new(false)),
+    /// A plain-text server on `DEFAULT_BIND_ADDR`
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    pub fn builder() -> WebSocketServerBuilder {
+        WebSocketServerBuilder::default()
+    }
+
+    /// Bind `addr` and start accepting connections in the background
+    ///
+    /// Shorthand for configuring the address with `bind` and calling
+    /// `listen`, kept for servers created with `new`.
+    pub async fn start(&mut self, addr: &str) -> Result<SocketAddr, WebSocketError> {
+        self.bind_addr = addr.to_string();
+        self.listen().await
+    }
+
+    /// Bind the configured address and start accepting connections in the
+    /// background
+    ///
+    /// Returns the bound address, which differs from the configured one when
+    /// binding port 0. A stopped server can be started again.
+    pub async fn listen(&mut self) -> Result<SocketAddr, WebSocketError> {
+        if let Some(running) = &self.running {
+            if !running.shutdown.is_cancelled() {
+                return Err(WebSocketError::AlreadyRunning(running.local_addr));
+            }
+            // Stopped through the token; finish closing the old connections
+            self.stop().await;
         }
+
+        let acceptor = self.tls.as_ref().map(TlsConfig::acceptor).transpose()?;
+        let listener = TcpListener::bind(&self.bind_addr).await?;
+        let local_addr = listener.local_addr()?;
+        let shutdown = self.parent_shutdown.child_token();
+        let tasks = TaskTracker::new();
+
+        let context = ConnectionContext {
+            progress_manager: Arc::clone(&self.progress_manager),
+            metrics_collector: Arc::clone(&self.metrics_collector),
+            connections: Arc::clone(&self.connections),
+            updates: self.updates.clone(),
+            batch_interval: self.batch_interval,
+            #[cfg(feature = "jobs")]
+            events: self.events.clone(),
+            shutdown: shutdown.clone(),
+        };
+        tasks.spawn(forward_progress(context.clone()));
+        #[cfg(feature = "jobs")]
+        if let Some(events) = &self.events {
+            tasks.spawn(tail_events(events.clone(), self.updates.clone(), shutdown.clone()));
+        }
+        tasks.spawn(heartbeat(self.updates.clone(), self.heartbeat_interval, shutdown.clone()));
+        if let Some(board) = &self.risk_board {
+            tasks.spawn(stream_risk(board.clone(), self.updates.clone(), self.risk_interval, shutdown.clone()));
+        }
+
+        self.is_running.store(true, Ordering::SeqCst);
+        let accept_loop = tokio::spawn(accept_connections(
+            listener,
+            acceptor,
+            context,
+            tasks.clone(),
+            Arc::clone(&self.is_running),
+        ));
+
+        info!("Monitoring WebSocket server listening on {}://{}",
+            if self.tls.is_some() { "wss" } else { "ws" }, local_addr);
+        self.running = Some(Running { local_addr, shutdown, tasks, accept_loop });
+        Ok(local_addr)
     }
-    
-    pub async fn start(&mut self, addr: &str) -> Result<(), Box<dyn std::error::Error>> {
-        self.is_running.store(true, std::sync::atomic::Ordering::SeqCst);
-        // WebSocket server implementation would go here
+
+    /// Run until the shutdown token is cancelled, then close connections
+    pub async fn serve(mut self) -> Result<(), WebSocketError> {
+        self.listen().await?;
+        self.parent_shutdown.cancelled().await;
+        self.stop().await;
         Ok(())
     }
-    
-    pub async fn stop(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        self.is_running.store(false, std::sync::atomic::Ordering::SeqCst);
-        // Cleanup connections
+
+    /// Stop accepting, close open connections and wait for them to finish
+    pub async fn stop(&mut self) {
+        let Some(running) = self.running.take() else {
+            return;
+        };
+
+        running.shutdown.cancel();
+        let _ = running.accept_loop.await;
+        running.tasks.close();
+        if tokio::time::timeout(SHUTDOWN_GRACE, running.tasks.wait()).await.is_err() {
+            warn!("Monitoring WebSocket connections did not close within {:?}", SHUTDOWN_GRACE);
+        }
+
         self.connections.write().await.clear();
-        Ok(())
+        self.is_running.store(false, Ordering::SeqCst);
+        info!("Monitoring WebSocket server on {} stopped", running.local_addr);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.is_running.load(Ordering::SeqCst)
+    }
+
+    /// Address the server is listening on, while running
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.running.as_ref().map(|running| running.local_addr)
+    }
+
+    /// Token that stops the running server when cancelled
+    pub fn shutdown_token(&self) -> CancellationToken {
+        match &self.running {
+            Some(running) => running.shutdown.clone(),
+            None => self.parent_shutdown.clone(),
+        }
+    }
+
+    /// Send a monitoring update to subscribed clients, returning how many received it
+    pub async fn broadcast_update(&self, update: MonitoringUpdate) -> Result<usize, WebSocketError> {
+        // Sending only fails when no client is connected
+        Ok(self.updates.send(WebSocketMessage::MonitoringUpdate { update, event_id: None }).unwrap_or(0))
+    }
+
+    pub async fn broadcast_system_metrics(&self) {
+        let metrics = self.metrics_collector.write().await.collect_system_metrics();
+        let _ = self.updates.send(WebSocketMessage::SystemMetrics { metrics });
+    }
+
+    pub async fn get_connection_count(&self) -> usize {
+        self.connections.read().await.len()
+    }
+}
+
+/// Handles shared by the accept loop and every connection
+#[derive(Clone)]
+struct ConnectionContext {
+    progress_manager: Arc<ProgressManager>,
+    metrics_collector: Arc<RwLock<MetricsCollector>>,
+    connections: Arc<RwLock<HashMap<Uuid, ConnectionState>>>,
+    updates: broadcast::Sender<WebSocketMessage>,
+    batch_interval: Option<Duration>,
+    #[cfg(feature = "jobs")]
+    events: Option<EventBus>,
+    shutdown: CancellationToken,
+}
+
+async fn accept_connections(
+    listener: TcpListener,
+    acceptor: Option<TlsAcceptor>,
+    context: ConnectionContext,
+    tasks: TaskTracker,
+    is_running: Arc<AtomicBool>,
+) {
+    loop {
+        let (stream, peer) = tokio::select! {
+            _ = context.shutdown.cancelled() => break,
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!("Failed to accept monitoring connection: {}", e);
+                    continue;
+                }
+            },
+        };
+
+        let context = context.clone();
+        let acceptor = acceptor.clone();
+        tasks.spawn(async move {
+            let result = match acceptor {
+                Some(acceptor) => match acceptor.accept(stream).await {
+                    Ok(stream) => handle_connection(stream, context).await,
+                    Err(e) => {
+                        debug!("TLS handshake with {} failed: {}", peer, e);
+                        return;
+                    }
+                },
+                None => handle_connection(stream, context).await,
+            };
+            if let Err(e) = result {
+                debug!("Monitoring connection from {} closed: {}", peer, e);
+            }
+        });
+    }
+
+    is_running.store(false, Ordering::SeqCst);
+}
+
+async fn handle_connection<S>(stream: S, context: ConnectionContext) -> Result<(), tokio_tungstenite::tungstenite::Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let ws_stream = accept_async(stream).await?;
+    let (mut ws_sender, mut ws_receiver) = ws_stream.split();
+    let mut updates = context.updates.subscribe();
+
+    let id = Uuid::new_v4();
+    let now = chrono::Utc::now();
+    context.connections.write().await.insert(id, ConnectionState {
+        id,
+        connected_at: now,
+        last_ping: now,
+        subscriptions: Vec::new(),
+    });
+
+    let mut batcher = MessageBatcher::new(context.batch_interval.is_some());
+    // The guard keeps the flush branch idle when batching is off
+    let mut flush = tokio::time::interval(context.batch_interval.unwrap_or(Duration::from_secs(3600)));
+    flush.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    let result = loop {
+        tokio::select! {
+            _ = context.shutdown.cancelled() => {
+                if let Some(frame) = batcher.flush() {
+                    let _ = send_message(&mut ws_sender, &frame).await;
+                }
+                let _ = ws_sender.send(Message::Close(None)).await;
+                break Ok(());
+            }
+            _ = flush.tick(), if context.batch_interval.is_some() => {
+                if let Some(frame) = batcher.flush() {
+                    if let Err(e) = send_message(&mut ws_sender, &frame).await {
+                        break Err(e);
+                    }
+                }
+            }
+            update = updates.recv() => {
+                let message = match update {
+                    Ok(message) => message,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        debug!("Monitoring client {} missed {} updates", id, missed);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break Ok(()),
+                };
+                let wanted = context.connections.read().await
+                    .get(&id)
+                    .is_some_and(|state| message.is_for(&state.subscriptions));
+                if wanted {
+                    let mut sent = Ok(());
+                    for frame in batcher.push(message) {
+                        sent = send_message(&mut ws_sender, &frame).await;
+                        if sent.is_err() {
+                            break;
+                        }
+                    }
+                    if let Err(e) = sent {
+                        break Err(e);
+                    }
+                }
+            }
+            incoming = ws_receiver.next() => {
+                let text = match incoming {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | None => break Ok(()),
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => break Err(e),
+                };
+                let mut replies = Vec::new();
+                for reply in handle_request(&context, id, &text).await {
+                    replies.push(send_message(&mut ws_sender, &reply).await);
+                }
+                if let Some(Err(e)) = replies.into_iter().find(Result::is_err) {
+                    break Err(e);
+                }
+            }
+        }
+    };
+
+    context.connections.write().await.remove(&id);
+    result
+}
+
+async fn send_message<S>(sink: &mut S, message: &WebSocketMessage) -> Result<(), tokio_tungstenite::tungstenite::Error>
+where
+    S: SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin,
+{
+    match serde_json::to_string(message) {
+        Ok(json) => sink.send(Message::Text(json)).await,
+        Err(e) => {
+            warn!("Failed to serialize monitoring message: {}", e);
+            Ok(())
+        }
+    }
+}
+
+/// Per-connection buffer of messages waiting for the next batch frame
+struct MessageBatcher {
+    enabled: bool,
+    pending: Vec<WebSocketMessage>,
+}
+
+impl MessageBatcher {
+    fn new(enabled: bool) -> Self {
+        Self { enabled, pending: Vec::new() }
+    }
+
+    /// Queue a message, returning the frames to send right away
+    ///
+    /// A high-priority message flushes the pending batch ahead of itself so
+    /// clients still see updates in the order they happened.
+    fn push(&mut self, message: WebSocketMessage) -> Vec<WebSocketMessage> {
+        if !self.enabled {
+            return vec![message];
+        }
+        match message.priority() {
+            MessagePriority::High => {
+                let mut frames: Vec<_> = self.flush().into_iter().collect();
+                frames.push(message);
+                frames
+            }
+            MessagePriority::Normal => {
+                self.pending.push(message);
+                if self.pending.len() >= MAX_BATCH_MESSAGES {
+                    self.flush().into_iter().collect()
+                } else {
+                    Vec::new()
+                }
+            }
+        }
+    }
+
+    /// Take the pending messages as one frame; a lone message is sent as is
+    fn flush(&mut self) -> Option<WebSocketMessage> {
+        match self.pending.len() {
+            0 => None,
+            1 => self.pending.pop(),
+            _ => Some(WebSocketMessage::Batch { messages: std::mem::take(&mut self.pending) }),
+        }
+    }
+}
+
+/// Apply a client request, returning the replies for that client
+async fn handle_request(context: &ConnectionContext, client: Uuid, text: &str) -> Vec<WebSocketMessage> {
+    let request: WebSocketRequest = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(e) => {
+            return vec![WebSocketMessage::Error {
+                message: format!("Invalid request format: {}", e),
+            }];
+        }
+    };
+
+    match request {
+        WebSocketRequest::Subscribe { subscriptions } => {
+            let mut connections = context.connections.write().await;
+            let mut unknown = Vec::new();
+            if let Some(state) = connections.get_mut(&client) {
+                for subscription in subscriptions {
+                    if SubscriptionType::parse(&subscription).is_none() {
+                        unknown.push(subscription);
+                    } else if !state.subscriptions.contains(&subscription) {
+                        state.subscriptions.push(subscription);
+                    }
+                }
+            }
+            unknown.into_iter()
+                .map(|subscription| WebSocketMessage::Error {
+                    message: format!("Unknown subscription '{}'", subscription),
+                })
+                .collect()
+        }
+        WebSocketRequest::Unsubscribe { subscriptions } => {
+            if let Some(state) = context.connections.write().await.get_mut(&client) {
+                state.subscriptions.retain(|s| !subscriptions.contains(s));
+            }
+            Vec::new()
+        }
+        WebSocketRequest::GetJobStatus { job_id } => {
+            match context.progress_manager.get_job_status(&job_id).await {
+                Some(job) => vec![WebSocketMessage::JobStatusUpdate { job_id, status: job.status }],
+                None => vec![WebSocketMessage::Error { message: format!("No job {}", job_id) }],
+            }
+        }
+        WebSocketRequest::GetAllJobs => {
+            context.progress_manager.get_all_jobs().await
+                .into_iter()
+                .map(|job| WebSocketMessage::JobStatusUpdate { job_id: job.job_id, status: job.status })
+                .collect()
+        }
+        WebSocketRequest::GetSystemMetrics => {
+            let metrics = context.metrics_collector.write().await.collect_system_metrics();
+            vec![WebSocketMessage::SystemMetrics { metrics }]
+        }
+        WebSocketRequest::ControlJob { job_id, action } => {
+            let result = match action {
+                JobAction::Pause => context.progress_manager.pause_job(&job_id).await,
+                JobAction::Resume => context.progress_manager.resume_job(&job_id).await,
+                JobAction::Cancel => context.progress_manager.cancel_job(&job_id).await,
+            };
+            match result {
+                Ok(()) => Vec::new(),
+                Err(e) => vec![WebSocketMessage::Error {
+                    message: format!("Failed to control job {}: {}", job_id, e),
+                }],
+            }
+        }
+        WebSocketRequest::Replay { after } => {
+            let subscriptions = context.connections.read().await
+                .get(&client)
+                .map(|state| state.subscriptions.clone())
+                .unwrap_or_default();
+            match replay_events(context, after.as_deref()).await {
+                Ok(messages) => messages.into_iter().filter(|m| m.is_for(&subscriptions)).collect(),
+                Err(message) => vec![WebSocketMessage::Error { message }],
+            }
+        }
     }
 }
 
-#[derive(Debug)]
-pub struct WebSocketConnection {
-    pub client_id: String,
-    pub subscriptions: Vec<SubscriptionType>,
-    pub sender: broadcast::Sender<WebSocketMessage>,
+/// Updates in the event stream after `after`, as relayed to clients
+#[cfg(feature = "jobs")]
+async fn replay_events(context: &ConnectionContext, after: Option<&str>) -> Result<Vec<WebSocketMessage>, String> {
+    let Some(events) = &context.events else {
+        return Err("No event stream to replay from".to_string());
+    };
+    let replayed = events.clone().replay(after, MAX_REPLAY_EVENTS).await
+        .map_err(|e| format!("Failed to replay monitoring updates: {}", e))?;
+    Ok(replayed.into_iter().filter_map(relayed).collect())
+}
+
+#[cfg(not(feature = "jobs"))]
+async fn replay_events(_context: &ConnectionContext, _after: Option<&str>) -> Result<Vec<WebSocketMessage>, String> {
+    Err("No event stream to replay from".to_string())
+}
+
+/// A monitoring update read from the event stream, tagged with its id
+#[cfg(feature = "jobs")]
+fn relayed(event: crate::jobs::StreamEvent) -> Option<WebSocketMessage> {
+    match event.decode::<MonitoringUpdate>() {
+        Ok(update) => Some(WebSocketMessage::MonitoringUpdate { update, event_id: Some(event.id) }),
+        Err(e) => {
+            debug!("Skipping undecodable monitoring event {}: {}", event.id, e);
+            None
+        }
+    }
+}
+
+/// Relay updates appended to the event stream to connected clients
+///
+/// Reads from the newest event when the server starts, so updates appended
+/// while it was down are only sent on `Replay`.
+#[cfg(feature = "jobs")]
+async fn tail_events(mut events: EventBus, updates: broadcast::Sender<WebSocketMessage>, shutdown: CancellationToken) {
+    let mut offset = match events.last_id().await {
+        Ok(id) => id,
+        Err(e) => {
+            warn!("Failed to read the end of {}, relaying new updates only: {}", events.stream(), e);
+            "$".to_string()
+        }
+    };
+    loop {
+        let read = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            read = events.read_after(&offset, MAX_REPLAY_EVENTS, TAIL_BLOCK_MS) => read,
+        };
+        match read {
+            Ok(batch) => {
+                for event in batch {
+                    offset = event.id.clone();
+                    if let Some(message) = relayed(event) {
+                        let _ = updates.send(message);
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("Failed to read monitoring updates from {}: {}", events.stream(), e);
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = tokio::time::sleep(Duration::from_secs(1)) => {}
+                }
+            }
+        }
+    }
+}
+
+/// Relay job progress from the progress manager to connected clients
+async fn forward_progress(context: ConnectionContext) {
+    let mut progress = context.progress_manager.subscribe_to_updates();
+    loop {
+        let update = tokio::select! {
+            _ = context.shutdown.cancelled() => break,
+            update = progress.recv() => match update {
+                Ok(update) => update,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        };
+
+        let job_id = update.job_id;
+        let message = match update.update_type {
+            ProgressUpdateType::ProgressUpdated(progress) => WebSocketMessage::ProgressUpdate { job_id, progress },
+            ProgressUpdateType::StatusChanged(status) => WebSocketMessage::JobStatusUpdate { job_id, status },
+            ProgressUpdateType::JobCompleted => WebSocketMessage::JobCompleted { job_id, final_metrics: None },
+            ProgressUpdateType::JobFailed(error) => WebSocketMessage::JobFailed { job_id, error },
+            _ => continue,
+        };
+        let _ = context.updates.send(message);
+    }
+}
+
+async fn heartbeat(updates: broadcast::Sender<WebSocketMessage>, every: Duration, shutdown: CancellationToken) {
+    let mut interval = tokio::time::interval(every);
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = interval.tick() => {
+                let _ = updates.send(WebSocketMessage::Heartbeat {
+                    timestamp: chrono::Utc::now().timestamp_millis() as u64,
+                });
+            }
+        }
+    }
+}
+
+/// Send a snapshot of every session on the board each interval
+async fn stream_risk(board: RiskBoard, updates: broadcast::Sender<WebSocketMessage>, every: Duration, shutdown: CancellationToken) {
+    let mut interval = tokio::time::interval(every);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = interval.tick() => {
+                for snapshot in board.snapshots() {
+                    let _ = updates.send(WebSocketMessage::RiskSnapshot { snapshot });
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct ConnectionState {
-    pub id: uuid::Uuid,
+    pub id: Uuid,
     pub connected_at: chrono::DateTime<chrono::Utc>,
     pub last_ping: chrono::DateTime<chrono::Utc>,
+    /// Subscription names as sent by the client, see `SubscriptionType::parse`
     pub subscriptions: Vec<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum SubscriptionType {
     ProgressUpdates,
     SystemMetrics,
     JobStatusUpdates(String), // specific job ID
     AllJobUpdates,
+    /// Risk of every paper and live session
+    AllRisk,
+    RiskSession(String), // specific session ID
+}
+
+impl SubscriptionType {
+    /// Parse `progress_updates`, `system_metrics`, `all_jobs`, `job_<id>`,
+    /// `risk` or `risk_<session id>`
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "progress_updates" => Some(SubscriptionType::ProgressUpdates),
+            "system_metrics" => Some(SubscriptionType::SystemMetrics),
+            "all_jobs" => Some(SubscriptionType::AllJobUpdates),
+            "risk" => Some(SubscriptionType::AllRisk),
+            _ => name.strip_prefix("job_").map(|job_id| SubscriptionType::JobStatusUpdates(job_id.to_string()))
+                .or_else(|| name.strip_prefix("risk_").map(|id| SubscriptionType::RiskSession(id.to_string()))),
+        }
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -92,12 +853,90 @@ pub enum WebSocketMessage {
         job_id: String,
         error: String,
     },
+    MonitoringUpdate {
+        update: MonitoringUpdate,
+        /// Stream id of an update relayed from the event stream, the offset
+        /// to `Replay` after
+        #[serde(skip_serializing_if = "Option::is_none")]
+        event_id: Option<String>,
+    },
+    RiskSnapshot {
+        snapshot: RiskSnapshot,
+    },
     Heartbeat {
         timestamp: u64,
     },
     Error {
         message: String,
     },
+    /// Updates coalesced by a server configured with `batch_interval`
+    Batch {
+        messages: Vec<WebSocketMessage>,
+    },
+}
+
+/// Whether a message may wait for the next batch frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessagePriority {
+    Normal,
+    /// Sent immediately, bypassing the batch
+    High,
+}
+
+impl WebSocketMessage {
+    /// Job failures, alerts, risk snapshots past a cutoff and errors are high priority
+    pub fn priority(&self) -> MessagePriority {
+        match self {
+            WebSocketMessage::JobFailed { .. } | WebSocketMessage::Error { .. } => MessagePriority::High,
+            WebSocketMessage::MonitoringUpdate { update, .. } if matches!(update.update_type, UpdateType::Alert) => {
+                MessagePriority::High
+            }
+            WebSocketMessage::RiskSnapshot { snapshot } if snapshot.is_breached() => MessagePriority::High,
+            _ => MessagePriority::Normal,
+        }
+    }
+
+    /// Whether a client with these subscriptions receives this message
+    ///
+    /// Clients that have not subscribed to anything receive everything.
+    fn is_for(&self, subscriptions: &[String]) -> bool {
+        if subscriptions.is_empty() {
+            return true;
+        }
+        let subscribed = |wanted: &dyn Fn(&SubscriptionType) -> bool| {
+            subscriptions.iter().filter_map(|s| SubscriptionType::parse(s)).any(|s| wanted(&s))
+        };
+        let job = |job_id: &str| subscribed(&|s| match s {
+            SubscriptionType::ProgressUpdates | SubscriptionType::AllJobUpdates => true,
+            SubscriptionType::JobStatusUpdates(id) => id == job_id,
+            SubscriptionType::SystemMetrics | SubscriptionType::AllRisk | SubscriptionType::RiskSession(_) => false,
+        });
+
+        match self {
+            WebSocketMessage::ProgressUpdate { job_id, .. }
+            | WebSocketMessage::JobStatusUpdate { job_id, .. }
+            | WebSocketMessage::JobCompleted { job_id, .. }
+            | WebSocketMessage::JobFailed { job_id, .. } => job(job_id),
+            WebSocketMessage::SystemMetrics { .. } => subscribed(&|s| *s == SubscriptionType::SystemMetrics),
+            WebSocketMessage::MonitoringUpdate { update, .. } => match update.update_type {
+                UpdateType::SystemMetrics | UpdateType::ResourceUsage => {
+                    subscribed(&|s| *s == SubscriptionType::SystemMetrics)
+                }
+                UpdateType::OptimizationProgress => subscribed(&|s| {
+                    matches!(s, SubscriptionType::ProgressUpdates | SubscriptionType::AllJobUpdates)
+                }),
+                UpdateType::JobMaintenance | UpdateType::Autoscaling => subscribed(&|s| *s == SubscriptionType::AllJobUpdates),
+                _ => true,
+            },
+            WebSocketMessage::RiskSnapshot { snapshot } => subscribed(&|s| match s {
+                SubscriptionType::AllRisk => true,
+                SubscriptionType::RiskSession(id) => *id == snapshot.session_id,
+                _ => false,
+            }),
+            WebSocketMessage::Batch { messages } => messages.iter().any(|m| m.is_for(subscriptions)),
+            WebSocketMessage::Heartbeat { .. } | WebSocketMessage::Error { .. } => true,
+        }
+    }
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -118,6 +957,12 @@ pub enum WebSocketRequest {
         job_id: String,
         action: JobAction,
     },
+    /// Monitoring updates in the event stream after `after`, or from the
+    /// start of the stream
+    Replay {
+        #[serde(default)]
+        after: Option<String>,
+    },
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -128,275 +973,136 @@ pub enum JobAction {
     Cancel,
 }
 
-impl WebSocketServer {
-    pub fn new(
-        progress_manager: Arc<ProgressManager>,
-        metrics_collector: Arc<RwLock<MetricsCollector>>,
-    ) -> Self {
-        Self {
-            progress_manager,
-            metrics_collector,
-            connections: Arc::new(RwLock::new(HashMap::new())),
-        }
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    pub async fn handle_connection(
-        &self,
-        stream: TcpStream,
-        client_id: String,
-    ) -> Result<()> {
-        let ws_stream = accept_async(stream).await?;
-        let (mut ws_sender, mut ws_receiver) = ws_stream.split();
-        
-        let (client_sender, mut client_receiver) = broadcast::channel::<WebSocketMessage>(100);
-        
-        // Register the connection
-        {
-            let mut connections = self.connections.write().await;
-            connections.insert(client_id.clone(), WebSocketConnection {
-                client_id: client_id.clone(),
-                subscriptions: Vec::new(),
-                sender: client_sender.clone(),
-            });
-        }
+    #[tokio::test]
+    async fn test_server_restarts_and_stops_on_token() {
+        let token = CancellationToken::new();
+        let mut server = WebSocketServer::builder()
+            .bind("127.0.0.1:0")
+            .shutdown_token(token.clone())
+            .build();
 
-        // Subscribe to progress updates
-        let mut progress_receiver = self.progress_manager.subscribe_to_updates();
-        
-        // Spawn task to handle progress updates
-        let client_sender_clone = client_sender.clone();
-        let client_id_clone = client_id.clone();
-        tokio::spawn(async move {
-            while let Ok(progress_update) = progress_receiver.recv().await {
-                let message = match progress_update.update_type {
-                    crate::monitoring::progress::ProgressUpdateType::ProgressUpdated(progress) => {
-                        WebSocketMessage::ProgressUpdate {
-                            job_id: progress_update.job_id,
-                            progress,
-                        }
-                    },
-                    crate::monitoring::progress::ProgressUpdateType::StatusChanged(status) => {
-                        WebSocketMessage::JobStatusUpdate {
-                            job_id: progress_update.job_id,
-                            status,
-                        }
-                    },
-                    crate::monitoring::progress::ProgressUpdateType::JobCompleted => {
-                        WebSocketMessage::JobCompleted {
-                            job_id: progress_update.job_id,
-                            final_metrics: None,
-                        }
-                    },
-                    crate::monitoring::progress::ProgressUpdateType::JobFailed(error) => {
-                        WebSocketMessage::JobFailed {
-                            job_id: progress_update.job_id,
-                            error,
-                        }
-                    },
-                    _ => continue,
-                };
+        server.listen().await.unwrap();
+        assert!(matches!(server.listen().await, Err(WebSocketError::AlreadyRunning(_))));
+        server.stop().await;
+        assert!(!server.is_running());
 
-                if let Err(_) = client_sender_clone.send(message) {
-                    break; // Client disconnected
-                }
-            }
-        });
-
-        // Spawn task to send messages to WebSocket
-        let client_id_clone2 = client_id.clone();
-        tokio::spawn(async move {
-            while let Ok(message) = client_receiver.recv().await {
-                let json_message = match serde_json::to_string(&message) {
-                    Ok(json) => json,
-                    Err(_) => continue,
-                };
+        let addr = server.listen().await.unwrap();
+        let (mut client, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr)).await.unwrap();
 
-                if let Err(_) = ws_sender.send(Message::Text(json_message)).await {
-                    break; // Connection closed
+        // Cancelling the embedder's token closes the connection
+        token.cancel();
+        let closed = tokio::time::timeout(Duration::from_secs(5), async {
+            while let Some(Ok(message)) = client.next().await {
+                if message.is_close() {
+                    return true;
                 }
             }
-        });
-
-        // Handle incoming messages
-        while let Some(message) = ws_receiver.next().await {
-            match message? {
-                Message::Text(text) => {
-                    if let Err(e) = self.handle_text_message(&client_id, text, &client_sender).await {
-                        eprintln!("Error handling WebSocket message: {}", e);
-                    }
-                },
-                Message::Close(_) => {
-                    break;
-                },
-                _ => {
-                    // Ignore other message types
-                }
-            }
-        }
-
-        // Clean up connection
-        {
-            let mut connections = self.connections.write().await;
-            connections.remove(&client_id);
-        }
-
-        Ok(())
+            true
+        }).await;
+        assert_eq!(closed, Ok(true));
+        server.stop().await;
+        assert!(!server.is_running());
     }
 
-    async fn handle_text_message(
-        &self,
-        client_id: &str,
-        text: String,
-        sender: &broadcast::Sender<WebSocketMessage>,
-    ) -> Result<()> {
-        let request: WebSocketRequest = match serde_json::from_str(&text) {
-            Ok(req) => req,
-            Err(e) => {
-                let _ = sender.send(WebSocketMessage::Error {
-                    message: format!("Invalid request format: {}", e),
-                });
-                return Ok(());
-            }
-        };
+    #[test]
+    fn test_batcher_lets_high_priority_bypass() {
+        let tick = |timestamp| WebSocketMessage::Heartbeat { timestamp };
+        let mut batcher = MessageBatcher::new(true);
+        assert!(batcher.push(tick(1)).is_empty());
+        assert!(batcher.push(tick(2)).is_empty());
 
-        match request {
-            WebSocketRequest::Subscribe { subscriptions } => {
-                self.handle_subscribe(client_id, subscriptions).await;
-            },
-            WebSocketRequest::Unsubscribe { subscriptions } => {
-                self.handle_unsubscribe(client_id, subscriptions).await;
-            },
-            WebSocketRequest::GetJobStatus { job_id } => {
-                if let Some(status) = self.progress_manager.get_job_status(&job_id).await {
-                    let _ = sender.send(WebSocketMessage::JobStatusUpdate {
-                        job_id,
-                        status: status.status,
-                    });
-                }
-            },
-            WebSocketRequest::GetAllJobs => {
-                let jobs = self.progress_manager.get_all_jobs().await;
-                for job in jobs {
-                    let _ = sender.send(WebSocketMessage::JobStatusUpdate {
-                        job_id: job.job_id,
-                        status: job.status,
-                    });
-                }
-            },
-            WebSocketRequest::GetSystemMetrics => {
-                let mut collector = self.metrics_collector.write().await;
-                let metrics = collector.collect_system_metrics();
-                let _ = sender.send(WebSocketMessage::SystemMetrics { metrics });
-            },
-            WebSocketRequest::ControlJob { job_id, action } => {
-                let result = match action {
-                    JobAction::Pause => self.progress_manager.pause_job(&job_id).await,
-                    JobAction::Resume => self.progress_manager.resume_job(&job_id).await,
-                    JobAction::Cancel => self.progress_manager.cancel_job(&job_id).await,
-                };
+        let frames = batcher.push(WebSocketMessage::JobFailed {
+            job_id: "job".to_string(),
+            error: "boom".to_string(),
+        });
+        assert_eq!(frames.len(), 2);
+        assert!(matches!(&frames[0], WebSocketMessage::Batch { messages } if messages.len() == 2));
+        assert_eq!(frames[1].priority(), MessagePriority::High);
+        assert!(batcher.flush().is_none());
 
-                if let Err(e) = result {
-                    let _ = sender.send(WebSocketMessage::Error {
-                        message: format!("Failed to control job {}: {}", job_id, e),
-                    });
-                }
-            },
-        }
+        assert_eq!(MessageBatcher::new(false).push(tick(3)).len(), 1);
+    }
 
-        Ok(())
+    /// A bus on a fresh stream of the Redis at `REDIS_URL`, or `None` without one
+    #[cfg(feature = "jobs")]
+    async fn test_stream(stream: &str) -> Option<EventBus> {
+        let url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string());
+        let client = redis::Client::open(url).ok()?;
+        let connection = tokio::time::timeout(Duration::from_secs(2), redis::aio::ConnectionManager::new(client));
+        Some(EventBus::new(connection.await.ok()?.ok()?, stream))
     }
 
-    async fn handle_subscribe(&self, client_id: &str, subscriptions: Vec<String>) {
-        let mut connections = self.connections.write().await;
-        if let Some(connection) = connections.get_mut(client_id) {
-            for sub in subscriptions {
-                let subscription_type = match sub.as_str() {
-                    "progress_updates" => SubscriptionType::ProgressUpdates,
-                    "system_metrics" => SubscriptionType::SystemMetrics,
-                    "all_jobs" => SubscriptionType::AllJobUpdates,
-                    _ if sub.starts_with("job_") => {
-                        let job_id = sub.strip_prefix("job_").unwrap_or(&sub);
-                        SubscriptionType::JobStatusUpdates(job_id.to_string())
-                    },
-                    _ => continue,
+    /// Monitoring updates a client receives, up to `count` of them
+    #[cfg(feature = "jobs")]
+    async fn monitoring_updates<S>(client: &mut S, count: usize) -> Vec<serde_json::Value>
+    where
+        S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        let mut updates = Vec::new();
+        let received = tokio::time::timeout(Duration::from_secs(5), async {
+            while updates.len() < count {
+                let Some(Ok(message)) = client.next().await else {
+                    break;
+                };
+                let Ok(text) = message.into_text() else {
+                    continue;
                 };
-                
-                if !connection.subscriptions.contains(&subscription_type) {
-                    connection.subscriptions.push(subscription_type);
+                let message: serde_json::Value = serde_json::from_str(&text).unwrap_or_default();
+                if message["type"] == "MonitoringUpdate" {
+                    updates.push(message);
                 }
             }
-        }
+        }).await;
+        assert!(received.is_ok(), "timed out waiting for monitoring updates");
+        updates
     }
 
-    async fn handle_unsubscribe(&self, client_id: &str, subscriptions: Vec<String>) {
-        let mut connections = self.connections.write().await;
-        if let Some(connection) = connections.get_mut(client_id) {
-            for sub in subscriptions {
-                let subscription_type = match sub.as_str() {
-                    "progress_updates" => SubscriptionType::ProgressUpdates,
-                    "system_metrics" => SubscriptionType::SystemMetrics,
-                    "all_jobs" => SubscriptionType::AllJobUpdates,
-                    _ if sub.starts_with("job_") => {
-                        let job_id = sub.strip_prefix("job_").unwrap_or(&sub);
-                        SubscriptionType::JobStatusUpdates(job_id.to_string())
-                    },
-                    _ => continue,
-                };
-                
-                connection.subscriptions.retain(|s| !matches!(s, subscription_type));
-            }
-        }
-    }
+    #[cfg(feature = "jobs")]
+    #[tokio::test]
+    async fn test_stream_updates_are_relayed_and_replayed() {
+        use crate::monitoring::AnomalyMonitor;
 
-    pub async fn broadcast_system_metrics(&self) {
-        let mut collector = self.metrics_collector.write().await;
-        let metrics = collector.collect_system_metrics();
-        
-        let message = WebSocketMessage::SystemMetrics { metrics };
-        
-        let connections = self.connections.read().await;
-        for connection in connections.values() {
-            if connection.subscriptions.contains(&SubscriptionType::SystemMetrics) {
-                let _ = connection.sender.send(message.clone());
+        let stream = format!("test_monitoring_{}", Uuid::new_v4());
+        let (Some(producer), Some(tail), Some(mut reader)) =
+            (test_stream(&stream).await, test_stream(&stream).await, test_stream(&stream).await) else {
+            println!("Redis not available, skipping event stream test");
+            return;
+        };
+        let monitor = AnomalyMonitor::default().with_event_stream(producer);
+        let update = |n: u32| MonitoringUpdate::new(UpdateType::Status, serde_json::json!({ "n": n }));
+
+        // An update appended while no server was relaying
+        monitor.publish(update(1));
+        for _ in 0..50 {
+            if !reader.replay(None, 10).await.unwrap().is_empty() {
+                break;
             }
+            tokio::time::sleep(Duration::from_millis(20)).await;
         }
-    }
 
-    pub async fn start_heartbeat(&self) {
-        let connections = self.connections.clone();
-        
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
-            
-            loop {
-                interval.tick().await;
-                
-                let heartbeat = WebSocketMessage::Heartbeat {
-                    timestamp: chrono::Utc::now().timestamp_millis() as u64,
-                };
-                
-                let conns = connections.read().await;
-                for connection in conns.values() {
-                    let _ = connection.sender.send(heartbeat.clone());
-                }
-            }
-        });
-    }
+        let mut server = WebSocketServer::builder().bind("127.0.0.1:0").event_stream(tail).build();
+        let addr = server.listen().await.unwrap();
+        let (mut client, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
 
-    pub async fn get_connection_count(&self) -> usize {
-        self.connections.read().await.len()
-    }
-}
+        // Only updates appended since the server started are relayed live
+        monitor.publish(update(2));
+        let live = monitoring_updates(&mut client, 1).await;
+        assert_eq!(live[0]["update"]["data"]["n"], 2);
+        assert!(live[0]["event_id"].is_string());
 
-// Comparison implementation for SubscriptionType (required for contains())
-impl PartialEq for SubscriptionType {
-    fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (SubscriptionType::ProgressUpdates, SubscriptionType::ProgressUpdates) => true,
-            (SubscriptionType::SystemMetrics, SubscriptionType::SystemMetrics) => true,
-            (SubscriptionType::AllJobUpdates, SubscriptionType::AllJobUpdates) => true,
-            (SubscriptionType::JobStatusUpdates(a), SubscriptionType::JobStatusUpdates(b)) => a == b,
-            _ => false,
-        }
+        // Replay catches a client up on what it missed
+        let request = serde_json::json!({ "type": "Replay" }).to_string();
+        client.send(Message::Text(request)).await.unwrap();
+        let replayed: Vec<_> = monitoring_updates(&mut client, 2).await.iter()
+            .map(|message| message["update"]["data"]["n"].clone())
+            .collect();
+        assert_eq!(replayed, vec![serde_json::json!(1), serde_json::json!(2)]);
+
+        server.stop().await;
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,410 @@
+//! Batched objective evaluation for vectorized strategies
+//!
+//! Strategies that reduce to array arithmetic over a price series (moving
+//! average crossovers, band breakouts, ...) do not need the full tick-level
+//! backtest engine during a sweep. They can be evaluated for hundreds of
+//! parameter sets at once: on the GPU when the crate is built with the `gpu`
+//! feature and an adapter is available, otherwise across CPU cores. The
+//! fallback is transparent; callers get the same metrics either way.
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::optimization::ParameterSet;
+
+#[cfg(feature = "gpu")]
+use crate::optimization::gpu::GpuContext;
+
+/// Errors raised by batch evaluation
+#[derive(Debug, thiserror::Error)]
+pub enum BatchEvalError {
+    #[error("GPU evaluation failed: {0}")]
+    Gpu(String),
+    #[error("Parameter set is missing {0}")]
+    MissingParameter(String),
+}
+
+/// Metrics of one parameter set over the price series
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct BatchMetrics {
+    pub total_pnl: f64,
+    pub trades: u32,
+    pub max_drawdown: f64,
+}
+
+/// A strategy whose evaluation is a pure function of a price series and parameters
+pub trait VectorizedStrategy: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Parameters in the order the kernels read them
+    fn parameter_names(&self) -> &[&'static str];
+
+    /// Reference implementation, also used when no GPU is available
+    fn evaluate_cpu(&self, prices: &[f32], params: &[f32]) -> BatchMetrics;
+
+    /// WGSL compute kernel with the same semantics as `evaluate_cpu`
+    ///
+    /// The kernel reads a `Dims { n_prices, n_sets, n_params, point_value }`
+    /// uniform at binding 0, prices at 1 and flattened parameters at 2, and
+    /// writes `pnl, trades, max_drawdown` per set to binding 3.
+    fn wgsl_kernel(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Contract multiplier passed to the kernel
+    fn point_value(&self) -> f32;
+}
+
+/// Moving average crossover, always in the market: long when the fast
+/// average is above the slow one, short otherwise
+#[derive(Debug, Clone)]
+pub struct MovingAverageCrossover {
+    /// Dollars per point; MNQ is $2
+    pub point_value: f32,
+}
+
+impl Default for MovingAverageCrossover {
+    fn default() -> Self {
+        Self { point_value: 2.0 }
+    }
+}
+
+const MA_CROSSOVER_WGSL: &str = r#"
+struct Dims {
+    n_prices: u32,
+    n_sets: u32,
+    n_params: u32,
+    point_value: f32,
+}
+
+@group(0) @binding(0) var<uniform> dims: Dims;
+@group(0) @binding(1) var<storage, read> prices: array<f32>;
+@group(0) @binding(2) var<storage, read> params: array<f32>;
+@group(0) @binding(3) var<storage, read_write> results: array<f32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= dims.n_sets) {
+        return;
+    }
+
+    let fast = max(u32(params[i * dims.n_params]), 1u);
+    let slow = max(u32(params[i * dims.n_params + 1u]), fast + 1u);
+
+    var fast_sum = 0.0;
+    var slow_sum = 0.0;
+    var position = 0.0;
+    var entry = 0.0;
+    var pnl = 0.0;
+    var peak = 0.0;
+    var max_dd = 0.0;
+    var trades = 0.0;
+
+    for (var t = 0u; t < dims.n_prices; t++) {
+        let p = prices[t];
+        fast_sum += p;
+        slow_sum += p;
+        if (t >= fast) {
+            fast_sum -= prices[t - fast];
+        }
+        if (t >= slow) {
+            slow_sum -= prices[t - slow];
+        }
+        if (t + 1u < slow) {
+            continue;
+        }
+
+        let signal = select(-1.0, 1.0, fast_sum / f32(fast) > slow_sum / f32(slow));
+        if (signal != position) {
+            if (position != 0.0) {
+                pnl += position * (p - entry) * dims.point_value;
+                trades += 1.0;
+            }
+            position = signal;
+            entry = p;
+        }
+
+        let equity = pnl + position * (p - entry) * dims.point_value;
+        peak = max(peak, equity);
+        max_dd = max(max_dd, peak - equity);
+    }
+
+    if (position != 0.0) {
+        pnl += position * (prices[dims.n_prices - 1u] - entry) * dims.point_value;
+        trades += 1.0;
+    }
+
+    results[i * 3u] = pnl;
+    results[i * 3u + 1u] = trades;
+    results[i * 3u + 2u] = max_dd;
+}
+"#;
+
+impl VectorizedStrategy for MovingAverageCrossover {
+    fn name(&self) -> &str {
+        "ma_crossover"
+    }
+
+    fn parameter_names(&self) -> &[&'static str] {
+        &["fast_period", "slow_period"]
+    }
+
+    fn evaluate_cpu(&self, prices: &[f32], params: &[f32]) -> BatchMetrics {
+        // Mirrors the WGSL kernel step for step, in f32, so both paths agree
+        let fast = (params[0] as u32).max(1) as usize;
+        let slow = (params[1] as u32).max(fast as u32 + 1) as usize;
+
+        let (mut fast_sum, mut slow_sum) = (0.0f32, 0.0f32);
+        let (mut position, mut entry, mut pnl) = (0.0f32, 0.0f32, 0.0f32);
+        let (mut peak, mut max_dd, mut trades) = (0.0f32, 0.0f32, 0u32);
+
+        for (t, &p) in prices.iter().enumerate() {
+            fast_sum += p;
+            slow_sum += p;
+            if t >= fast {
+                fast_sum -= prices[t - fast];
+            }
+            if t >= slow {
+                slow_sum -= prices[t - slow];
+            }
+            if t + 1 < slow {
+                continue;
+            }
+
+            let signal = if fast_sum / fast as f32 > slow_sum / slow as f32 { 1.0 } else { -1.0 };
+            if signal != position {
+                if position != 0.0 {
+                    pnl += position * (p - entry) * self.point_value;
+                    trades += 1;
+                }
+                position = signal;
+                entry = p;
+            }
+
+            let equity = pnl + position * (p - entry) * self.point_value;
+            peak = peak.max(equity);
+            max_dd = max_dd.max(peak - equity);
+        }
+
+        if position != 0.0 {
+            pnl += position * (prices[prices.len() - 1] - entry) * self.point_value;
+            trades += 1;
+        }
+
+        BatchMetrics {
+            total_pnl: pnl as f64,
+            trades,
+            max_drawdown: max_dd as f64,
+        }
+    }
+
+    fn wgsl_kernel(&self) -> Option<&'static str> {
+        Some(MA_CROSSOVER_WGSL)
+    }
+
+    fn point_value(&self) -> f32 {
+        self.point_value
+    }
+}
+
+/// Where batches are evaluated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvalBackend {
+    Cpu,
+    Gpu,
+}
+
+/// Batch evaluation settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchEvalConfig {
+    /// Use the GPU when built with the `gpu` feature and an adapter is found
+    pub prefer_gpu: bool,
+    /// Parameter sets per GPU dispatch
+    pub batch_size: usize,
+}
+
+impl Default for BatchEvalConfig {
+    fn default() -> Self {
+        Self {
+            prefer_gpu: true,
+            batch_size: 512,
+        }
+    }
+}
+
+/// A parameter set with its batch-evaluated metrics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchEvaluation {
+    pub parameters: ParameterSet,
+    pub metrics: BatchMetrics,
+}
+
+/// Evaluates parameter sets in batches, on the GPU when possible
+pub struct BatchEvaluator {
+    config: BatchEvalConfig,
+    #[cfg(feature = "gpu")]
+    gpu: Option<GpuContext>,
+}
+
+impl BatchEvaluator {
+    pub fn new(config: BatchEvalConfig) -> Self {
+        #[cfg(feature = "gpu")]
+        let gpu = if config.prefer_gpu {
+            match GpuContext::new() {
+                Ok(context) => {
+                    info!("Batch evaluation on GPU: {}", context.adapter_name());
+                    Some(context)
+                }
+                Err(e) => {
+                    warn!("No usable GPU, evaluating on CPU: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        #[cfg(not(feature = "gpu"))]
+        if config.prefer_gpu {
+            info!("Built without the gpu feature, evaluating on CPU");
+        }
+
+        Self {
+            config,
+            #[cfg(feature = "gpu")]
+            gpu,
+        }
+    }
+
+    pub fn backend(&self) -> EvalBackend {
+        #[cfg(feature = "gpu")]
+        if self.gpu.is_some() {
+            return EvalBackend::Gpu;
+        }
+        EvalBackend::Cpu
+    }
+
+    /// Evaluate every parameter set over `prices`, in input order
+    pub fn evaluate(
+        &mut self,
+        strategy: &dyn VectorizedStrategy,
+        prices: &[f32],
+        parameter_sets: &[ParameterSet],
+    ) -> Result<Vec<BatchEvaluation>, BatchEvalError> {
+        let n_params = strategy.parameter_names().len();
+        let flat = flatten_parameters(strategy.parameter_names(), parameter_sets)?;
+        let mut metrics = Vec::with_capacity(parameter_sets.len());
+
+        if prices.is_empty() || n_params == 0 {
+            let fixed = if prices.is_empty() { BatchMetrics::default() } else { strategy.evaluate_cpu(prices, &[]) };
+            metrics.resize(parameter_sets.len(), fixed);
+        } else {
+            for chunk in flat.chunks(self.config.batch_size.max(1) * n_params) {
+                metrics.extend(self.evaluate_batch(strategy, prices, chunk, n_params));
+            }
+        }
+
+        Ok(parameter_sets.iter()
+            .cloned()
+            .zip(metrics)
+            .map(|(parameters, metrics)| BatchEvaluation { parameters, metrics })
+            .collect())
+    }
+
+    fn evaluate_batch(
+        &mut self,
+        strategy: &dyn VectorizedStrategy,
+        prices: &[f32],
+        params: &[f32],
+        n_params: usize,
+    ) -> Vec<BatchMetrics> {
+        #[cfg(feature = "gpu")]
+        if let (Some(gpu), Some(kernel)) = (&self.gpu, strategy.wgsl_kernel()) {
+            match gpu.evaluate(kernel, strategy.point_value(), prices, params, n_params) {
+                Ok(metrics) => return metrics,
+                Err(e) => {
+                    // A lost device stays lost; finish the sweep on the CPU
+                    warn!("GPU batch failed, falling back to CPU: {}", e);
+                    self.gpu = None;
+                }
+            }
+        }
+
+        params.par_chunks(n_params)
+            .map(|set| strategy.evaluate_cpu(prices, set))
+            .collect()
+    }
+}
+
+/// Lay parameter sets out row by row in the strategy's parameter order
+fn flatten_parameters(names: &[&'static str], sets: &[ParameterSet]) -> Result<Vec<f32>, BatchEvalError> {
+    let mut flat = Vec::with_capacity(names.len() * sets.len());
+    for set in sets {
+        for name in names {
+            let value = set.get_float(name)
+                .ok_or_else(|| BatchEvalError::MissingParameter(name.to_string()))?;
+            flat.push(value as f32);
+        }
+    }
+    Ok(flat)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::config::ParameterValue;
+
+    fn params(fast: f64, slow: f64) -> ParameterSet {
+        let mut set = ParameterSet::new();
+        set.parameters.insert("fast_period".to_string(), ParameterValue::Float(fast));
+        set.parameters.insert("slow_period".to_string(), ParameterValue::Float(slow));
+        set
+    }
+
+    #[test]
+    fn test_crossover_on_trend_reversal() {
+        // Rise then fall: long through the rally, short through the decline
+        let prices: Vec<f32> = (0..50).map(|i| 100.0 + i as f32)
+            .chain((0..50).map(|i| 150.0 - i as f32))
+            .collect();
+        let strategy = MovingAverageCrossover::default();
+
+        let metrics = strategy.evaluate_cpu(&prices, &[2.0, 5.0]);
+
+        assert!(metrics.total_pnl > 0.0);
+        assert!(metrics.trades >= 2);
+    }
+
+    #[test]
+    fn test_evaluator_preserves_order_across_batches() {
+        let prices: Vec<f32> = (0..200).map(|i| 100.0 + (i as f32 * 0.1).sin() * 5.0).collect();
+        let sets: Vec<_> = (1..=10).map(|f| params(f as f64, 20.0)).collect();
+        let strategy = MovingAverageCrossover::default();
+        let mut evaluator = BatchEvaluator::new(BatchEvalConfig {
+            prefer_gpu: false,
+            batch_size: 3,
+        });
+
+        let results = evaluator.evaluate(&strategy, &prices, &sets).unwrap();
+
+        assert_eq!(evaluator.backend(), EvalBackend::Cpu);
+        assert_eq!(results.len(), 10);
+        for (result, set) in results.iter().zip(&sets) {
+            let fast = set.get_float("fast_period").unwrap() as f32;
+            assert_eq!(result.metrics, strategy.evaluate_cpu(&prices, &[fast, 20.0]));
+        }
+    }
+
+    #[test]
+    fn test_missing_parameter() {
+        let mut set = ParameterSet::new();
+        set.parameters.insert("fast_period".to_string(), ParameterValue::Float(3.0));
+        let mut evaluator = BatchEvaluator::new(BatchEvalConfig::default());
+
+        let err = evaluator.evaluate(&MovingAverageCrossover::default(), &[1.0, 2.0], &[set]);
+        assert!(matches!(err, Err(BatchEvalError::MissingParameter(_))));
+    }
+}
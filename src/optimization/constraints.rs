@@ -0,0 +1,331 @@
+//! Constraints between optimized parameters
+//!
+//! Some parameter sets are meaningless, e.g. a fast moving average longer
+//! than the slow one. Constraints declare which sets are valid, and the
+//! handling strategy decides what an optimizer does with a set that breaks
+//! them: draw another, evaluate it with the objective reduced in proportion
+//! to how far it is out, or repair it into a valid set first.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use crate::optimization::ParameterSet;
+use crate::optimization::steering::clamp_to_bounds;
+use crate::strategy::config::ParameterValue;
+
+/// A relation every evaluated parameter set must satisfy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ParameterConstraint {
+    /// `left` must be at least `gap` below `right`
+    LessThan {
+        left: String,
+        right: String,
+        #[serde(default)]
+        gap: f64,
+    },
+    /// The named parameters may add up to at most `max`
+    MaxSum { parameters: Vec<String>, max: f64 },
+}
+
+impl ParameterConstraint {
+    /// How far `params` is outside the constraint, zero when satisfied or
+    /// when a parameter it names is missing
+    pub fn violation(&self, params: &ParameterSet) -> f64 {
+        match self {
+            ParameterConstraint::LessThan { left, right, gap } => {
+                match (params.get_float(left), params.get_float(right)) {
+                    (Some(l), Some(r)) => (l + gap - r).max(0.0),
+                    _ => 0.0,
+                }
+            }
+            ParameterConstraint::MaxSum { parameters, max } => {
+                let sum: f64 = parameters.iter().filter_map(|name| params.get_float(name)).sum();
+                (sum - max).max(0.0)
+            }
+        }
+    }
+
+    /// Move `params` onto the constraint's boundary: `LessThan` spreads the
+    /// pair `gap` apart around their midpoint, `MaxSum` scales the named
+    /// parameters down in proportion
+    pub fn repair(&self, params: &mut ParameterSet) {
+        if self.violation(params) == 0.0 {
+            return;
+        }
+        match self {
+            ParameterConstraint::LessThan { left, right, gap } => {
+                let (Some(l), Some(r)) = (params.get_float(left), params.get_float(right)) else {
+                    return;
+                };
+                let mid = (l + r) / 2.0;
+                set_value(params, left, mid - gap / 2.0);
+                set_value(params, right, mid + gap / 2.0);
+            }
+            ParameterConstraint::MaxSum { parameters, max } => {
+                let sum: f64 = parameters.iter().filter_map(|name| params.get_float(name)).sum();
+                if sum <= 0.0 {
+                    return;
+                }
+                for name in parameters {
+                    if let Some(v) = params.get_float(name) {
+                        set_value(params, name, v * max / sum);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            ParameterConstraint::LessThan { left, right, gap } if *gap == 0.0 => format!("{} <= {}", left, right),
+            ParameterConstraint::LessThan { left, right, gap } => format!("{} + {} <= {}", left, gap, right),
+            ParameterConstraint::MaxSum { parameters, max } => format!("{} <= {}", parameters.join(" + "), max),
+        }
+    }
+}
+
+/// Overwrite a numeric parameter, keeping integers integral
+fn set_value(params: &mut ParameterSet, name: &str, value: f64) {
+    let value = match params.parameters.get(name) {
+        Some(ParameterValue::Integer(_)) => ParameterValue::Integer(value.round() as i64),
+        _ => ParameterValue::Float(value),
+    };
+    params.parameters.insert(name.to_string(), value);
+}
+
+/// What an optimizer does with a parameter set that breaks a constraint
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum ConstraintHandling {
+    /// Discard the set and draw another, up to `max_attempts` times; grid
+    /// search cannot draw and skips the combination
+    Reject {
+        #[serde(default = "default_max_attempts")]
+        max_attempts: usize,
+    },
+    /// Evaluate the set anyway, subtracting `weight` times the total
+    /// violation from its objective
+    Penalty { weight: f64 },
+    /// Repair the set into a valid one before evaluating it
+    Repair,
+}
+
+fn default_max_attempts() -> usize {
+    20
+}
+
+impl Default for ConstraintHandling {
+    fn default() -> Self {
+        ConstraintHandling::Reject { max_attempts: default_max_attempts() }
+    }
+}
+
+/// Counts of how constraint handling went during a run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ViolationStats {
+    /// Parameter sets checked, not counting resampled draws
+    pub checked: usize,
+    /// Checked sets that broke at least one constraint
+    pub violating: usize,
+    /// Sets dropped without being evaluated
+    pub rejected: usize,
+    /// Replacement draws made while resampling
+    pub resampled: usize,
+    pub penalized: usize,
+    pub repaired: usize,
+    /// Violations per constraint, keyed by its description
+    pub by_constraint: BTreeMap<String, usize>,
+}
+
+impl ViolationStats {
+    /// Share of checked sets that broke a constraint
+    pub fn violation_rate(&self) -> f64 {
+        if self.checked == 0 {
+            0.0
+        } else {
+            self.violating as f64 / self.checked as f64
+        }
+    }
+}
+
+/// Repair function replacing the built-in per-constraint repairs
+pub type RepairFn = Arc<dyn Fn(&mut ParameterSet) + Send + Sync>;
+
+/// A parameter set cleared for evaluation
+#[derive(Debug, Clone)]
+pub struct Admitted {
+    pub parameters: ParameterSet,
+    /// Amount to subtract from the set's objective
+    pub penalty: f64,
+    /// Whether the set was resampled or repaired rather than the one checked
+    pub modified: bool,
+}
+
+/// Applies a run's constraints and handling strategy to parameter sets
+#[derive(Clone)]
+pub struct ConstraintHandler {
+    constraints: Vec<ParameterConstraint>,
+    handling: ConstraintHandling,
+    bounds: HashMap<String, (f64, f64)>,
+    repair_fn: Option<RepairFn>,
+}
+
+impl ConstraintHandler {
+    /// Handler for sets whose parameters must also stay within `bounds`
+    pub fn new(
+        constraints: Vec<ParameterConstraint>,
+        handling: ConstraintHandling,
+        bounds: HashMap<String, (f64, f64)>,
+    ) -> Self {
+        Self {
+            constraints,
+            handling,
+            bounds,
+            repair_fn: None,
+        }
+    }
+
+    /// Repair sets with `repair` instead of the constraints' own repairs
+    pub fn with_repair_fn(mut self, repair: RepairFn) -> Self {
+        self.repair_fn = Some(repair);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.constraints.is_empty()
+    }
+
+    /// Constraints `params` breaks, with how far it is out of each
+    pub fn violations(&self, params: &ParameterSet) -> Vec<(&ParameterConstraint, f64)> {
+        self.constraints.iter()
+            .map(|c| (c, c.violation(params)))
+            .filter(|(_, amount)| *amount > 0.0)
+            .collect()
+    }
+
+    /// Repair `params` and clamp it back inside the bounds
+    pub fn repair(&self, params: &mut ParameterSet) {
+        match &self.repair_fn {
+            Some(repair) => repair(params),
+            None => {
+                for constraint in &self.constraints {
+                    constraint.repair(params);
+                }
+            }
+        }
+        clamp_to_bounds(params, &self.bounds);
+    }
+
+    /// Decide what to evaluate in place of `params`, drawing replacements
+    /// from `resample` when rejecting; `None` when the set is dropped
+    pub fn admit(
+        &self,
+        params: ParameterSet,
+        mut resample: impl FnMut() -> Option<ParameterSet>,
+        stats: &mut ViolationStats,
+    ) -> Option<Admitted> {
+        stats.checked += 1;
+        let violations = self.violations(&params);
+        if violations.is_empty() {
+            return Some(Admitted { parameters: params, penalty: 0.0, modified: false });
+        }
+        stats.violating += 1;
+        for (constraint, _) in &violations {
+            *stats.by_constraint.entry(constraint.describe()).or_default() += 1;
+        }
+
+        match self.handling {
+            ConstraintHandling::Penalty { weight } => {
+                stats.penalized += 1;
+                let total: f64 = violations.iter().map(|(_, amount)| amount).sum();
+                Some(Admitted { parameters: params, penalty: weight * total, modified: false })
+            }
+            ConstraintHandling::Repair => {
+                let mut params = params;
+                self.repair(&mut params);
+                if self.violations(&params).is_empty() {
+                    stats.repaired += 1;
+                    Some(Admitted { parameters: params, penalty: 0.0, modified: true })
+                } else {
+                    stats.rejected += 1;
+                    None
+                }
+            }
+            ConstraintHandling::Reject { max_attempts } => {
+                for _ in 0..max_attempts {
+                    let Some(candidate) = resample() else {
+                        break;
+                    };
+                    stats.resampled += 1;
+                    if self.violations(&candidate).is_empty() {
+                        return Some(Admitted { parameters: candidate, penalty: 0.0, modified: true });
+                    }
+                }
+                stats.rejected += 1;
+                None
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for ConstraintHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConstraintHandler")
+            .field("constraints", &self.constraints)
+            .field("handling", &self.handling)
+            .field("bounds", &self.bounds)
+            .field("repair_fn", &self.repair_fn.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(fast: f64, slow: f64) -> ParameterSet {
+        let mut set = ParameterSet::new();
+        set.parameters.insert("fast".to_string(), ParameterValue::Float(fast));
+        set.parameters.insert("slow".to_string(), ParameterValue::Integer(slow as i64));
+        set
+    }
+
+    #[test]
+    fn test_constraint_handling_strategies() {
+        let constraints = vec![ParameterConstraint::LessThan {
+            left: "fast".to_string(),
+            right: "slow".to_string(),
+            gap: 2.0,
+        }];
+        let bounds: HashMap<_, _> = [("fast".to_string(), (1.0, 50.0)), ("slow".to_string(), (5.0, 100.0))].into();
+        let handler = |handling| ConstraintHandler::new(constraints.clone(), handling, bounds.clone());
+        let mut stats = ViolationStats::default();
+
+        // Valid sets pass untouched
+        let ok = handler(ConstraintHandling::Repair).admit(params(5.0, 20.0), || None, &mut stats).unwrap();
+        assert_eq!((ok.penalty, ok.modified), (0.0, false));
+
+        let penalized = handler(ConstraintHandling::Penalty { weight: 0.5 })
+            .admit(params(20.0, 10.0), || None, &mut stats)
+            .unwrap();
+        assert_eq!(penalized.penalty, 6.0);
+
+        // Pulled apart around the midpoint
+        let repaired = handler(ConstraintHandling::Repair).admit(params(20.0, 10.0), || None, &mut stats).unwrap();
+        assert_eq!(repaired.parameters.get_float("fast"), Some(14.0));
+        assert_eq!(repaired.parameters.get_float("slow"), Some(16.0));
+
+        let mut draws = vec![params(8.0, 30.0), params(9.0, 10.0)];
+        let resampled = handler(ConstraintHandling::Reject { max_attempts: 3 })
+            .admit(params(20.0, 10.0), || draws.pop(), &mut stats)
+            .unwrap();
+        assert_eq!(resampled.parameters.get_float("fast"), Some(8.0));
+        assert!(handler(ConstraintHandling::default()).admit(params(20.0, 10.0), || None, &mut stats).is_none());
+
+        assert_eq!((stats.checked, stats.violating, stats.rejected), (5, 4, 1));
+        assert_eq!((stats.penalized, stats.repaired, stats.resampled), (1, 1, 2));
+        assert_eq!(stats.by_constraint["fast + 2 <= slow"], 4);
+    }
+}
@@ -0,0 +1,136 @@
+//! Convergence curves of optimization runs
+//!
+//! After every genetic generation, and every few grid evaluations, the run
+//! records the best and mean objective of that step alongside the best seen
+//! so far. The curve built from those points shows whether the best value
+//! is still climbing or has flattened out, which is what decides whether a
+//! longer run is worth it.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::optimization::eval_store::EvaluationStore;
+
+/// Grid evaluations per convergence point
+pub const GRID_CONVERGENCE_INTERVAL: usize = 10;
+
+/// Improvement of the best objective, relative to its size, that counts as progress
+const IMPROVEMENT_TOLERANCE: f64 = 1e-3;
+
+/// Objective values at one generation or batch of evaluations
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConvergencePoint {
+    /// Generation number, or batch number for grid search
+    pub step: usize,
+    /// Best objective evaluated in this step
+    pub best_objective: f64,
+    pub mean_objective: f64,
+    /// Best objective evaluated so far in the run
+    pub best_so_far: f64,
+    /// Evaluations made up to and including this step
+    pub evaluations: usize,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl ConvergencePoint {
+    /// Point for a step whose evaluations scored `objectives`
+    pub fn from_objectives(step: usize, objectives: &[f64], best_before: Option<f64>, evaluations: usize) -> Self {
+        let best = objectives.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let mean = if objectives.is_empty() {
+            0.0
+        } else {
+            objectives.iter().sum::<f64>() / objectives.len() as f64
+        };
+        Self {
+            step,
+            best_objective: best,
+            mean_objective: mean,
+            best_so_far: best_before.map_or(best, |before| before.max(best)),
+            evaluations,
+            recorded_at: Utc::now(),
+        }
+    }
+}
+
+/// A run's convergence points and whether the best objective has plateaued
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConvergenceCurve {
+    pub points: Vec<ConvergencePoint>,
+    /// Steps since the best objective last improved meaningfully
+    pub steps_since_improvement: usize,
+    /// Gain in the best objective over the last quarter of the steps
+    pub recent_improvement: f64,
+    /// Whether the best objective improved recently enough that more
+    /// iterations would plausibly find something better
+    pub still_improving: bool,
+}
+
+impl ConvergenceCurve {
+    pub fn from_points(mut points: Vec<ConvergencePoint>) -> Self {
+        points.sort_by_key(|p| p.step);
+        let Some(last) = points.last() else {
+            return Self::default();
+        };
+
+        let mut steps_since_improvement = 0;
+        let mut best = f64::NEG_INFINITY;
+        for point in &points {
+            let threshold = best + IMPROVEMENT_TOLERANCE * best.abs().max(1.0);
+            if point.best_so_far > threshold || best == f64::NEG_INFINITY {
+                best = point.best_so_far;
+                steps_since_improvement = 0;
+            } else {
+                steps_since_improvement += 1;
+            }
+        }
+
+        let window = (points.len() / 4).max(1);
+        let start = &points[points.len().saturating_sub(window + 1)];
+        let recent_improvement = last.best_so_far - start.best_so_far;
+        let patience = (points.len() / 4).max(3);
+
+        Self {
+            steps_since_improvement,
+            recent_improvement,
+            still_improving: steps_since_improvement < patience,
+            points,
+        }
+    }
+}
+
+/// Persist a convergence point when the run has an evaluation store
+pub(crate) fn record_convergence(eval_store: &Option<(EvaluationStore, String)>, point: &ConvergencePoint) {
+    if let Some((store, optimization_id)) = eval_store {
+        if let Err(e) = store.record_convergence(optimization_id, point) {
+            warn!("Failed to persist convergence for {}: {}", optimization_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curve_detects_plateau() {
+        let mut best = None;
+        let mut points = Vec::new();
+        for (step, top) in [1.0, 1.5, 1.8, 1.9, 1.9, 1.9, 1.9, 1.9].into_iter().enumerate() {
+            let point = ConvergencePoint::from_objectives(step, &[top, top - 1.0], best, (step + 1) * 2);
+            best = Some(point.best_so_far);
+            points.push(point);
+        }
+        assert!((points[3].mean_objective - 1.4).abs() < 1e-12);
+
+        let curve = ConvergenceCurve::from_points(points.clone());
+        assert_eq!(curve.steps_since_improvement, 4);
+        assert_eq!(curve.recent_improvement, 0.0);
+        assert!(!curve.still_improving);
+
+        let climbing = ConvergenceCurve::from_points(points[..4].to_vec());
+        assert_eq!(climbing.steps_since_improvement, 0);
+        assert!(climbing.still_improving);
+        assert!(ConvergenceCurve::from_points(Vec::new()).points.is_empty());
+    }
+}
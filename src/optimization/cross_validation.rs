@@ -0,0 +1,377 @@
+//! Purged k-fold cross-validation over time blocks
+//!
+//! Walk-forward only ever tests on the tail of each window, which leaves few
+//! out-of-sample periods on a short history. K-fold splits the whole range
+//! into contiguous blocks and tests on each in turn, training on the rest.
+//! Two gaps keep test information out of training:
+//!
+//! - purge: training data just before a test block is dropped, since a
+//!   trade opened there can still be open inside the test block
+//! - embargo: training data just after a test block is dropped, since
+//!   market state right after the block is correlated with it
+//!
+//! For every fold the best candidate on the training blocks is picked and
+//! then scored on the test block.
+
+use crate::backtesting::{BacktestConfig, BacktestEngine, BacktestResult, MetricRegistry};
+use crate::data::TickData;
+use crate::optimization::{ObjectiveFunction, ParameterSet};
+use crate::statistics::{ConfidenceInterval, StatisticalAnalyzer};
+use crate::strategy::Strategy;
+use crate::timestamp::Timestamp;
+use chrono::{DateTime, Duration, Utc};
+use rayon::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::{debug, info};
+
+/// Errors raised by cross-validation
+#[derive(Debug, thiserror::Error)]
+pub enum CrossValidationError {
+    #[error("Cross-validation needs at least 2 folds, got {0}")]
+    TooFewFolds(usize),
+    #[error("Range {start} to {end} is too short for {folds} folds")]
+    RangeTooShort { start: DateTime<Utc>, end: DateTime<Utc>, folds: usize },
+    #[error("No parameter sets to evaluate")]
+    NoCandidates,
+    #[error("No parameter set reached the minimum trade count on fold {0}")]
+    NoValidCandidate(usize),
+    #[error("Backtest failed on fold {fold}: {message}")]
+    Backtest { fold: usize, message: String },
+}
+
+/// Purged k-fold configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrossValidationConfig {
+    pub folds: usize,
+    /// Training data this close before a test block is dropped; at least the
+    /// longest time a position is held
+    pub purge_minutes: i64,
+    /// Training data this long after a test block is dropped
+    pub embargo_minutes: i64,
+    pub objective: ObjectiveFunction,
+    /// Candidates with fewer training trades are not eligible
+    pub min_trades: u32,
+    /// Confidence level of the out-of-sample estimate
+    pub confidence_level: f64,
+}
+
+impl Default for CrossValidationConfig {
+    fn default() -> Self {
+        Self {
+            folds: 5,
+            purge_minutes: 60,
+            embargo_minutes: 240,
+            objective: ObjectiveFunction::SharpeRatio,
+            min_trades: 10,
+            confidence_level: 0.95,
+        }
+    }
+}
+
+/// Half-open time range `[start, end)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeBlock {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl TimeBlock {
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+
+    fn contains_nanos(&self, nanos: i64) -> bool {
+        nanos >= Timestamp::from(self.start).nanos() && nanos < Timestamp::from(self.end).nanos()
+    }
+}
+
+/// Training and test blocks of one fold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FoldSplit {
+    pub fold: usize,
+    pub test: TimeBlock,
+    /// Everything outside the test block, minus purge and embargo
+    pub train: Vec<TimeBlock>,
+}
+
+impl FoldSplit {
+    pub fn train_duration(&self) -> Duration {
+        self.train.iter().fold(Duration::zero(), |total, block| total + block.duration())
+    }
+}
+
+/// Split `[start, end)` into `config.folds` purged and embargoed folds
+pub fn purged_k_fold(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    config: &CrossValidationConfig,
+) -> Result<Vec<FoldSplit>, CrossValidationError> {
+    let folds = config.folds;
+    if folds < 2 {
+        return Err(CrossValidationError::TooFewFolds(folds));
+    }
+
+    let total = (end - start).num_milliseconds();
+    if total < folds as i64 {
+        return Err(CrossValidationError::RangeTooShort { start, end, folds });
+    }
+
+    let boundary = |i: usize| start + Duration::milliseconds(total * i as i64 / folds as i64);
+    let purge = Duration::minutes(config.purge_minutes.max(0));
+    let embargo = Duration::minutes(config.embargo_minutes.max(0));
+
+    let splits = (0..folds)
+        .map(|fold| {
+            let test = TimeBlock { start: boundary(fold), end: boundary(fold + 1) };
+            let before = TimeBlock { start, end: test.start - purge };
+            let after = TimeBlock { start: test.end + embargo, end };
+            let train = [before, after].into_iter()
+                .filter(|block| block.end > block.start)
+                .collect();
+            FoldSplit { fold, test, train }
+        })
+        .collect();
+    Ok(splits)
+}
+
+/// Outcome of one fold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FoldResult {
+    pub split: FoldSplit,
+    /// Best candidate on the training blocks
+    pub parameters: ParameterSet,
+    pub in_sample_objective: f64,
+    pub out_of_sample_objective: f64,
+    pub in_sample_performance: BacktestResult,
+    pub out_of_sample_performance: BacktestResult,
+}
+
+/// Fold-level results and the pooled out-of-sample estimate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrossValidationResult {
+    pub folds: Vec<FoldResult>,
+    pub mean_in_sample_objective: f64,
+    pub mean_out_of_sample_objective: f64,
+    /// Corrected for the overlap between the folds' training sets
+    pub out_of_sample_interval: ConfidenceInterval,
+    /// Relative drop from in-sample to out-of-sample objective
+    pub out_of_sample_degradation: f64,
+    pub out_of_sample_pnl: Decimal,
+    /// Folds whose test block was profitable
+    pub profitable_folds: usize,
+}
+
+/// Runs purged k-fold cross-validation over an in-memory tick series
+pub struct PurgedKFoldValidator {
+    config: CrossValidationConfig,
+    backtest_config: BacktestConfig,
+    metrics: MetricRegistry,
+}
+
+impl PurgedKFoldValidator {
+    pub fn new(config: CrossValidationConfig, backtest_config: BacktestConfig) -> Self {
+        Self { config, backtest_config, metrics: MetricRegistry::new() }
+    }
+
+    /// Resolve `ObjectiveFunction::Metric` objectives through `metrics`
+    pub fn with_metric_registry(mut self, metrics: MetricRegistry) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Cross-validate `candidates` over `ticks`, which were read from `data_path`
+    ///
+    /// The folds span the first to the last tick. Candidates are evaluated in
+    /// parallel on each fold's training blocks.
+    pub fn run<S, F>(
+        &self,
+        strategy_factory: F,
+        candidates: &[ParameterSet],
+        data_path: &Path,
+        ticks: &[TickData],
+    ) -> Result<CrossValidationResult, CrossValidationError>
+    where
+        S: Strategy,
+        F: Fn(ParameterSet) -> S + Sync,
+    {
+        if candidates.is_empty() {
+            return Err(CrossValidationError::NoCandidates);
+        }
+        let (Some(first), Some(last)) = (
+            ticks.iter().map(|t| t.timestamp).min(),
+            ticks.iter().map(|t| t.timestamp).max(),
+        ) else {
+            return Err(CrossValidationError::NoCandidates);
+        };
+        // The last tick belongs in the final block, which is half-open
+        let start = Timestamp::from_nanos(first).to_utc();
+        let end = Timestamp::from_nanos(last + 1).to_utc();
+        let splits = purged_k_fold(start, end, &self.config)?;
+
+        let mut folds = Vec::with_capacity(splits.len());
+        for split in splits {
+            folds.push(self.run_fold(&strategy_factory, candidates, data_path, ticks, split)?);
+        }
+
+        let result = self.summarize(folds);
+        info!("Cross-validation completed: {} folds, out-of-sample {:.3} [{:.3}, {:.3}], degradation {:.1}%",
+            result.folds.len(),
+            result.mean_out_of_sample_objective,
+            result.out_of_sample_interval.lower_bound,
+            result.out_of_sample_interval.upper_bound,
+            result.out_of_sample_degradation * 100.0
+        );
+        Ok(result)
+    }
+
+    fn run_fold<S, F>(
+        &self,
+        strategy_factory: &F,
+        candidates: &[ParameterSet],
+        data_path: &Path,
+        ticks: &[TickData],
+        split: FoldSplit,
+    ) -> Result<FoldResult, CrossValidationError>
+    where
+        S: Strategy,
+        F: Fn(ParameterSet) -> S + Sync,
+    {
+        let fold = split.fold;
+        let train_ticks: Vec<TickData> = ticks.iter()
+            .filter(|t| split.train.iter().any(|block| block.contains_nanos(t.timestamp)))
+            .cloned()
+            .collect();
+        let test_ticks: Vec<TickData> = ticks.iter()
+            .filter(|t| split.test.contains_nanos(t.timestamp))
+            .cloned()
+            .collect();
+        debug!("Fold {}: {} training ticks, {} test ticks", fold, train_ticks.len(), test_ticks.len());
+
+        let train_span = TimeBlock {
+            start: split.train.first().map_or(split.test.start, |b| b.start),
+            end: split.train.last().map_or(split.test.end, |b| b.end),
+        };
+
+        let evaluations: Vec<(usize, BacktestResult)> = candidates.par_iter()
+            .enumerate()
+            .filter_map(|(index, parameters)| {
+                let result = self.backtest(strategy_factory(parameters.clone()), train_span, data_path, &train_ticks).ok()?;
+                (result.total_trades >= self.config.min_trades).then_some((index, result))
+            })
+            .collect();
+
+        let (best, in_sample) = evaluations.into_iter()
+            .max_by(|(_, a), (_, b)| self.objective(a).total_cmp(&self.objective(b)))
+            .ok_or(CrossValidationError::NoValidCandidate(fold))?;
+
+        let parameters = candidates[best].clone();
+        let out_of_sample = self.backtest(strategy_factory(parameters.clone()), split.test, data_path, &test_ticks)
+            .map_err(|message| CrossValidationError::Backtest { fold, message })?;
+
+        Ok(FoldResult {
+            in_sample_objective: self.objective(&in_sample),
+            out_of_sample_objective: self.objective(&out_of_sample),
+            split,
+            parameters,
+            in_sample_performance: in_sample,
+            out_of_sample_performance: out_of_sample,
+        })
+    }
+
+    fn objective(&self, result: &BacktestResult) -> f64 {
+        self.config.objective.calculate_with(result, &self.metrics)
+    }
+
+    fn backtest<S: Strategy>(
+        &self,
+        mut strategy: S,
+        span: TimeBlock,
+        data_path: &Path,
+        ticks: &[TickData],
+    ) -> Result<BacktestResult, String> {
+        let config = BacktestConfig {
+            start_date: span.start,
+            end_date: span.end,
+            ..self.backtest_config.clone()
+        };
+        BacktestEngine::new(config)
+            .run_loaded(&mut strategy, data_path, ticks)
+            .map_err(|e| e.to_string())
+    }
+
+    fn summarize(&self, folds: Vec<FoldResult>) -> CrossValidationResult {
+        let k = folds.len().max(1) as f64;
+        let mean_in_sample_objective = folds.iter().map(|f| f.in_sample_objective).sum::<f64>() / k;
+        let mean_out_of_sample_objective = folds.iter().map(|f| f.out_of_sample_objective).sum::<f64>() / k;
+
+        // Average test-to-train size ratio for the variance correction
+        let test_train_ratio = folds.iter()
+            .map(|f| {
+                let train = f.split.train_duration().num_milliseconds().max(1) as f64;
+                f.split.test.duration().num_milliseconds() as f64 / train
+            })
+            .sum::<f64>() / k;
+        let scores: Vec<f64> = folds.iter().map(|f| f.out_of_sample_objective).collect();
+        let out_of_sample_interval = StatisticalAnalyzer::cross_validated_interval(
+            &scores,
+            test_train_ratio,
+            self.config.confidence_level,
+        );
+
+        let out_of_sample_degradation = if mean_in_sample_objective != 0.0 {
+            (mean_in_sample_objective - mean_out_of_sample_objective) / mean_in_sample_objective.abs()
+        } else {
+            0.0
+        };
+
+        CrossValidationResult {
+            mean_in_sample_objective,
+            mean_out_of_sample_objective,
+            out_of_sample_interval,
+            out_of_sample_degradation,
+            out_of_sample_pnl: folds.iter().map(|f| f.out_of_sample_performance.total_pnl).sum(),
+            profitable_folds: folds.iter().filter(|f| f.out_of_sample_performance.total_pnl > Decimal::ZERO).count(),
+            folds,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_purged_k_fold_splits() {
+        let start = Utc.with_ymd_and_hms(2024, 6, 3, 0, 0, 0).unwrap();
+        let end = start + Duration::days(5);
+        let config = CrossValidationConfig {
+            folds: 5,
+            purge_minutes: 60,
+            embargo_minutes: 120,
+            ..Default::default()
+        };
+
+        let splits = purged_k_fold(start, end, &config).unwrap();
+        assert_eq!(splits.len(), 5);
+
+        // Test blocks tile the range
+        assert_eq!(splits[0].test.start, start);
+        assert_eq!(splits[4].test.end, end);
+        assert!(splits.windows(2).all(|w| w[0].test.end == w[1].test.start));
+
+        // Edge folds train on one side only
+        assert_eq!(splits[0].train, vec![TimeBlock { start: start + Duration::days(1) + Duration::hours(2), end }]);
+        assert_eq!(splits[4].train.len(), 1);
+
+        let middle = &splits[2];
+        assert_eq!(middle.train[0].end, middle.test.start - Duration::hours(1));
+        assert_eq!(middle.train[1].start, middle.test.end + Duration::hours(2));
+        assert_eq!(middle.train_duration(), Duration::days(4) - Duration::hours(3));
+
+        assert!(matches!(purged_k_fold(start, end, &CrossValidationConfig { folds: 1, ..config }),
+            Err(CrossValidationError::TooFewFolds(1))));
+    }
+}
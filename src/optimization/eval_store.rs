@@ -0,0 +1,280 @@
+//! Persistent store of optimization evaluations
+//!
+//! Every evaluated parameter set is appended as one JSON line to
+//! `<dir>/<optimization id>.jsonl` as soon as it finishes. Readers can pull
+//! the current leaders while the optimizer is still running, and the
+//! evaluations survive a restart of the process that produced them.
+//! Convergence points go to `<dir>/<optimization id>.convergence.jsonl` and
+//! genetic population snapshots to `<dir>/<optimization id>.population.jsonl`.
+//! The workspace that started an optimization is kept in
+//! `<dir>/<optimization id>.workspace`; optimizations recorded before owners
+//! were kept belong to the default workspace.
+
+use crate::backtesting::BacktestResult;
+use crate::optimization::convergence::ConvergencePoint;
+use crate::optimization::genetic::PopulationSnapshot;
+use crate::optimization::{OptimizationResult, ParameterSet};
+use crate::workspace::DEFAULT_WORKSPACE;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Errors raised by the evaluation store
+#[derive(Debug, thiserror::Error)]
+pub enum EvalStoreError {
+    #[error("Invalid optimization id: {0}")]
+    InvalidId(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// Headline metrics of one evaluation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvaluationMetrics {
+    pub total_pnl: Decimal,
+    pub total_trades: u32,
+    pub win_rate: f64,
+    pub sharpe_ratio: f64,
+    pub max_drawdown: Decimal,
+    pub profit_factor: f64,
+}
+
+impl From<&BacktestResult> for EvaluationMetrics {
+    fn from(result: &BacktestResult) -> Self {
+        Self {
+            total_pnl: result.total_pnl,
+            total_trades: result.total_trades,
+            win_rate: result.win_rate,
+            sharpe_ratio: result.sharpe_ratio,
+            max_drawdown: result.max_drawdown,
+            profit_factor: result.profit_factor,
+        }
+    }
+}
+
+/// One evaluated parameter set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvaluationRecord {
+    pub parameters: ParameterSet,
+    pub objective_value: f64,
+    pub metrics: EvaluationMetrics,
+    pub evaluated_at: DateTime<Utc>,
+}
+
+impl From<&OptimizationResult> for EvaluationRecord {
+    fn from(result: &OptimizationResult) -> Self {
+        Self {
+            parameters: result.parameters.clone(),
+            objective_value: result.objective_value,
+            metrics: EvaluationMetrics::from(&result.backtest_result),
+            evaluated_at: result.timestamp,
+        }
+    }
+}
+
+/// Append-only evaluation log, one file per optimization
+#[derive(Debug, Clone)]
+pub struct EvaluationStore {
+    dir: PathBuf,
+    /// Serializes appends so concurrent workers never interleave lines
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl EvaluationStore {
+    /// Open (creating if needed) a store rooted at `dir`
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, EvalStoreError> {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+            write_lock: Arc::new(Mutex::new(())),
+        })
+    }
+
+    fn path(&self, optimization_id: &str) -> Result<PathBuf, EvalStoreError> {
+        self.path_with_suffix(optimization_id, "jsonl")
+    }
+
+    fn path_with_suffix(&self, optimization_id: &str, suffix: &str) -> Result<PathBuf, EvalStoreError> {
+        let valid = !optimization_id.is_empty()
+            && optimization_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(EvalStoreError::InvalidId(optimization_id.to_string()));
+        }
+        Ok(self.dir.join(format!("{}.{}", optimization_id, suffix)))
+    }
+
+    /// Append an evaluation
+    pub fn record(&self, optimization_id: &str, record: &EvaluationRecord) -> Result<(), EvalStoreError> {
+        self.append(&self.path(optimization_id)?, record)
+    }
+
+    /// Append a convergence point
+    pub fn record_convergence(&self, optimization_id: &str, point: &ConvergencePoint) -> Result<(), EvalStoreError> {
+        self.append(&self.path_with_suffix(optimization_id, "convergence.jsonl")?, point)
+    }
+
+    /// Append a generation's population snapshot
+    pub fn record_population(&self, optimization_id: &str, snapshot: &PopulationSnapshot) -> Result<(), EvalStoreError> {
+        self.append(&self.path_with_suffix(optimization_id, "population.jsonl")?, snapshot)
+    }
+
+    fn append<T: Serialize>(&self, path: &Path, value: &T) -> Result<(), EvalStoreError> {
+        let mut line = serde_json::to_vec(value)?;
+        line.push(b'\n');
+
+        let _guard = self.write_lock.lock().unwrap();
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(&line)?;
+        Ok(())
+    }
+
+    /// All evaluations recorded so far, or `None` if the optimization has none
+    pub fn load(&self, optimization_id: &str) -> Result<Option<Vec<EvaluationRecord>>, EvalStoreError> {
+        Self::read_lines(&self.path(optimization_id)?)
+    }
+
+    /// Ids of every optimization with recorded evaluations
+    pub fn optimization_ids(&self) -> Result<Vec<String>, EvalStoreError> {
+        let mut ids = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let name = entry?.file_name();
+            let Some(name) = name.to_str() else { continue };
+            if let Some(id) = name.strip_suffix(".jsonl") {
+                if !id.contains('.') {
+                    ids.push(id.to_string());
+                }
+            }
+        }
+        ids.sort();
+        Ok(ids)
+    }
+
+    /// Record the workspace an optimization runs in
+    pub fn record_owner(&self, optimization_id: &str, workspace_id: &str) -> Result<(), EvalStoreError> {
+        fs::write(self.path_with_suffix(optimization_id, "workspace")?, workspace_id)?;
+        Ok(())
+    }
+
+    /// Workspace an optimization runs in
+    pub fn owner(&self, optimization_id: &str) -> Result<String, EvalStoreError> {
+        match fs::read_to_string(self.path_with_suffix(optimization_id, "workspace")?) {
+            Ok(owner) => Ok(owner),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(DEFAULT_WORKSPACE.to_string()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Ids of the optimizations of `workspace_id` with recorded evaluations
+    pub fn optimization_ids_in(&self, workspace_id: &str) -> Result<Vec<String>, EvalStoreError> {
+        let mut ids = Vec::new();
+        for id in self.optimization_ids()? {
+            if self.owner(&id)? == workspace_id {
+                ids.push(id);
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Convergence points recorded so far, or `None` if the optimization has none
+    pub fn load_convergence(&self, optimization_id: &str) -> Result<Option<Vec<ConvergencePoint>>, EvalStoreError> {
+        Self::read_lines(&self.path_with_suffix(optimization_id, "convergence.jsonl")?)
+    }
+
+    /// Population snapshots recorded so far, or `None` if the optimization has none
+    pub fn load_populations(&self, optimization_id: &str) -> Result<Option<Vec<PopulationSnapshot>>, EvalStoreError> {
+        Self::read_lines(&self.path_with_suffix(optimization_id, "population.jsonl")?)
+    }
+
+    fn read_lines<T: serde::de::DeserializeOwned>(path: &Path) -> Result<Option<Vec<T>>, EvalStoreError> {
+        let file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut records = Vec::new();
+        for line in BufReader::new(file).lines() {
+            // A line still being appended by a running optimizer fails to
+            // parse; it will be complete on the next read
+            if let Ok(record) = serde_json::from_str(&line?) {
+                records.push(record);
+            }
+        }
+        Ok(Some(records))
+    }
+
+    /// The `k` best evaluations by objective, with the total evaluated so far
+    pub fn top_k(
+        &self,
+        optimization_id: &str,
+        k: usize,
+    ) -> Result<Option<(Vec<EvaluationRecord>, usize)>, EvalStoreError> {
+        Ok(self.load(optimization_id)?.map(|mut records| {
+            let total = records.len();
+            records.sort_by(|a, b| b.objective_value.total_cmp(&a.objective_value));
+            records.truncate(k);
+            (records, total)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(fast: f64, objective: f64) -> EvaluationRecord {
+        EvaluationRecord {
+            parameters: ParameterSet::from_hashmap([("fast".to_string(), fast)].into()),
+            objective_value: objective,
+            metrics: EvaluationMetrics::from(&BacktestResult::default()),
+            evaluated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_top_k_while_running() {
+        let dir = std::env::temp_dir().join(format!("eval_store_{}", uuid::Uuid::new_v4()));
+        let store = EvaluationStore::open(&dir).unwrap();
+
+        assert!(store.top_k("opt-1", 3).unwrap().is_none());
+        for (fast, objective) in [(5.0, 0.4), (10.0, 1.2), (15.0, -0.3), (20.0, 0.9)] {
+            store.record("opt-1", &record(fast, objective)).unwrap();
+        }
+
+        let (top, total) = store.top_k("opt-1", 2).unwrap().unwrap();
+        assert_eq!(total, 4);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].parameters.get_float("fast"), Some(10.0));
+        assert_eq!(top[1].parameters.get_float("fast"), Some(20.0));
+
+        // Reopening sees the same evaluations
+        let reopened = EvaluationStore::open(&dir).unwrap();
+        assert_eq!(reopened.load("opt-1").unwrap().unwrap().len(), 4);
+        assert!(matches!(reopened.load("../opt-1"), Err(EvalStoreError::InvalidId(_))));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_optimizations_are_listed_by_workspace() {
+        let dir = std::env::temp_dir().join(format!("eval_store_{}", uuid::Uuid::new_v4()));
+        let store = EvaluationStore::open(&dir).unwrap();
+        for id in ["opt-a", "opt-b", "opt-legacy"] {
+            store.record(id, &record(5.0, 0.4)).unwrap();
+        }
+        store.record_owner("opt-a", "team").unwrap();
+        store.record_owner("opt-b", "other").unwrap();
+
+        assert_eq!(store.optimization_ids_in("team").unwrap(), vec!["opt-a"]);
+        assert_eq!(store.optimization_ids_in(DEFAULT_WORKSPACE).unwrap(), vec!["opt-legacy"]);
+        assert_eq!(store.owner("opt-b").unwrap(), "other");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
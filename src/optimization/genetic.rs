@@ -1,14 +1,28 @@
 //! Genetic algorithm optimization
+//!
+//! Every random draw comes from the run's seed: the initial population from
+//! the seed itself, and the breeding of each generation from a stream
+//! derived from the seed and the generation number. A run with the same
+//! seed, bounds and data therefore evolves the same populations, and a run
+//! can be resumed from a `PopulationSnapshot` of any generation without
+//! replaying the generations before it.
 
-use crate::backtesting::{BacktestEngine, BacktestConfig, BacktestResult, PerformanceMetrics};
+use crate::backtesting::{BacktestEngine, BacktestConfig, BacktestResult, MetricRegistry, PerformanceMetrics};
 use crate::strategy::Strategy;
 use crate::strategy::config::ParameterValue;
 use crate::optimization::{OptimizationResult, ParameterSet, ObjectiveFunction};
+use crate::optimization::steering::{OptimizationControl, clamp_to_bounds};
+use crate::optimization::convergence::{record_convergence, ConvergencePoint};
+use crate::optimization::eval_store::{EvaluationMetrics, EvaluationRecord, EvaluationStore};
+use crate::optimization::constraints::{
+    ConstraintHandler, ConstraintHandling, ParameterConstraint, RepairFn, ViolationStats,
+};
 use rand::prelude::*;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use tracing::{info, debug};
+use std::sync::Arc;
+use tracing::{info, debug, warn};
 
 /// Genetic algorithm configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +53,23 @@ pub struct GeneticConfig {
     
     /// Parameter bounds
     pub parameter_bounds: HashMap<String, (f64, f64)>,
+    
+    /// Constraints every evaluated individual must satisfy
+    #[serde(default)]
+    pub parameter_constraints: Vec<ParameterConstraint>,
+    
+    /// What to do with individuals that break a constraint
+    #[serde(default)]
+    pub constraint_handling: ConstraintHandling,
+    
+    /// Fixed seed for reproducible runs; drawn at random when unset and
+    /// reported by `GeneticOptimizer::seed`
+    #[serde(default)]
+    pub seed: Option<u64>,
+    
+    /// Keep a snapshot of each generation's population
+    #[serde(default)]
+    pub snapshot_populations: bool,
 }
 
 /// Selection strategies
@@ -55,6 +86,8 @@ struct Individual {
     parameters: ParameterSet,
     fitness: Option<f64>,
     backtest_result: Option<BacktestResult>,
+    /// Subtracted from the objective for breaking a constraint
+    penalty: f64,
 }
 
 impl Individual {
@@ -63,21 +96,65 @@ impl Individual {
             parameters,
             fitness: None,
             backtest_result: None,
+            penalty: 0.0,
         }
     }
     
     /// Create random individual
-    fn random(bounds: &HashMap<String, (f64, f64)>) -> Self {
-        let mut rng = thread_rng();
+    fn random(bounds: &HashMap<String, (f64, f64)>, rng: &mut StdRng) -> Self {
         let mut parameters = HashMap::new();
         
-        for (name, (min, max)) in bounds {
-            let value = rng.gen_range(*min..=*max);
-            parameters.insert(name.clone(), ParameterValue::Float(value));
+        // Draw in name order so the same seed gives the same values
+        for name in sorted_names(bounds) {
+            let (min, max) = bounds[name];
+            parameters.insert(name.clone(), ParameterValue::Float(rng.gen_range(min..=max)));
         }
         
         Self::new(ParameterSet { parameters })
     }
+    
+    fn snapshot(&self) -> SnapshotIndividual {
+        SnapshotIndividual {
+            parameters: self.parameters.clone(),
+            fitness: self.fitness,
+            penalty: self.penalty,
+        }
+    }
+}
+
+/// Keys of a map in sorted order, for draws that must not depend on hashing
+fn sorted_names<V>(map: &HashMap<String, V>) -> Vec<&String> {
+    let mut names: Vec<&String> = map.keys().collect();
+    names.sort();
+    names
+}
+
+/// Random stream used to breed `generation`
+fn generation_rng(seed: u64, generation: usize) -> StdRng {
+    StdRng::seed_from_u64(seed ^ (generation as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// An individual as recorded in a population snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotIndividual {
+    pub parameters: ParameterSet,
+    /// Fitness carried from an earlier generation, e.g. by elites
+    pub fitness: Option<f64>,
+    pub penalty: f64,
+}
+
+/// A generation's population as it was about to be evaluated
+///
+/// Together with the seed it holds everything needed to resume the run from
+/// that generation, including candidates and bounds injected by steering.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PopulationSnapshot {
+    pub seed: u64,
+    pub generation: usize,
+    pub parameter_bounds: HashMap<String, (f64, f64)>,
+    pub individuals: Vec<SnapshotIndividual>,
+    /// Statistics of the generations before this one
+    pub history: Vec<GenerationStats>,
 }
 
 /// Genetic algorithm optimizer
@@ -87,22 +164,129 @@ pub struct GeneticOptimizer {
     generation: usize,
     best_individual: Option<Individual>,
     history: Vec<GenerationStats>,
+    control: Option<OptimizationControl>,
+    metrics: MetricRegistry,
+    repair_fn: Option<RepairFn>,
+    violations: ViolationStats,
+    /// Where evaluations and convergence are persisted, with the run's id
+    eval_store: Option<(EvaluationStore, String)>,
+    /// Backtests run so far
+    evaluations: usize,
+    seed: u64,
+    /// Stream the initial population and its resamples are drawn from
+    initial_rng: StdRng,
+    snapshots: Vec<PopulationSnapshot>,
 }
 
 impl GeneticOptimizer {
     pub fn new(config: GeneticConfig) -> Self {
-        // Initialize random population
-        let population: Vec<Individual> = (0..config.population_size)
-            .map(|_| Individual::random(&config.parameter_bounds))
-            .collect();
-        
-        Self {
+        let seed = config.seed.unwrap_or_else(rand::random);
+        let mut optimizer = Self {
             config,
-            population,
+            population: Vec::new(),
             generation: 0,
             best_individual: None,
             history: Vec::new(),
-        }
+            control: None,
+            metrics: MetricRegistry::new(),
+            repair_fn: None,
+            violations: ViolationStats::default(),
+            eval_store: None,
+            evaluations: 0,
+            seed,
+            initial_rng: StdRng::seed_from_u64(seed),
+            snapshots: Vec::new(),
+        };
+        optimizer.seed_population(seed);
+        optimizer
+    }
+    
+    /// Draw a fresh random population from `seed`
+    fn seed_population(&mut self, seed: u64) {
+        self.seed = seed;
+        self.config.seed = Some(seed);
+        self.initial_rng = StdRng::seed_from_u64(seed);
+        let bounds = &self.config.parameter_bounds;
+        let rng = &mut self.initial_rng;
+        self.population = (0..self.config.population_size)
+            .map(|_| Individual::random(bounds, rng))
+            .collect();
+    }
+    
+    /// Continue a run from a snapshot of one of its generations
+    ///
+    /// The snapshot's seed and bounds replace the configured ones, and its
+    /// population is evaluated again as the snapshot's generation, so the
+    /// run carries on as the original did from there as long as it is given
+    /// the same data and steered the same way.
+    pub fn resume(mut config: GeneticConfig, snapshot: PopulationSnapshot) -> Self {
+        config.seed = Some(snapshot.seed);
+        config.parameter_bounds = snapshot.parameter_bounds;
+        let mut optimizer = Self::new(config);
+        optimizer.population = snapshot.individuals.into_iter()
+            .map(|individual| {
+                let mut resumed = Individual::new(individual.parameters);
+                resumed.penalty = individual.penalty;
+                resumed
+            })
+            .collect();
+        optimizer.generation = snapshot.generation;
+        optimizer.history = snapshot.history;
+        optimizer
+    }
+    
+    /// Use `seed` for every random draw of the run, redrawing the initial
+    /// population from it
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed_population(seed);
+        self
+    }
+    
+    /// Seed of the run, to record alongside its results
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    
+    /// Populations recorded so far when `snapshot_populations` is set
+    pub fn population_snapshots(&self) -> &[PopulationSnapshot] {
+        &self.snapshots
+    }
+    
+    /// Attach a steering handle so the run can be paused and redirected live
+    pub fn with_control(mut self, control: OptimizationControl) -> Self {
+        self.control = Some(control);
+        self
+    }
+    
+    /// Persist each evaluation and generation's convergence under `optimization_id`
+    pub fn with_evaluation_store(mut self, store: EvaluationStore, optimization_id: impl Into<String>) -> Self {
+        self.eval_store = Some((store, optimization_id.into()));
+        self
+    }
+    
+    /// Resolve `ObjectiveFunction::Metric` objectives through `metrics`
+    pub fn with_metric_registry(mut self, metrics: MetricRegistry) -> Self {
+        self.metrics = metrics;
+        self
+    }
+    
+    /// Repair constraint-breaking individuals with `repair` instead of the
+    /// constraints' own repairs when handling is `Repair`
+    pub fn with_repair_fn(mut self, repair: RepairFn) -> Self {
+        self.repair_fn = Some(repair);
+        self
+    }
+    
+    /// Constraint violations seen so far
+    pub fn violation_stats(&self) -> &ViolationStats {
+        &self.violations
+    }
+    
+    /// Create a steering handle bound to this optimizer's parameter bounds
+    pub fn control(&mut self) -> OptimizationControl {
+        self.control
+            .get_or_insert_with(|| OptimizationControl::new(self.config.parameter_bounds.clone()))
+            .clone()
     }
     
     /// Run genetic algorithm optimization
@@ -120,10 +304,28 @@ impl GeneticOptimizer {
         info!("Population size: {}, Generations: {}", 
             self.config.population_size, self.config.generations);
         
-        for gen in 0..self.config.generations {
+        info!("Seed: {}", self.seed);
+        
+        let first_generation = self.generation;
+        if first_generation == 0 {
+            self.admit_initial_population();
+        }
+        if self.population.is_empty() {
+            warn!("No individual of the initial population satisfies the parameter constraints");
+            return Ok(Vec::new());
+        }
+        
+        for gen in first_generation..self.config.generations {
             self.generation = gen;
             debug!("Generation {}/{}", gen + 1, self.config.generations);
             
+            // Honour pause requests and pick up steering changes
+            self.apply_steering().await;
+            
+            if self.config.snapshot_populations {
+                self.record_snapshot();
+            }
+            
             // Evaluate fitness
             self.evaluate_population(&strategy_factory, &backtest_config, data_path).await?;
             
@@ -134,6 +336,13 @@ impl GeneticOptimizer {
             info!("Gen {}: Best fitness: {:.4}, Avg: {:.4}",
                 gen, stats.best_fitness, stats.avg_fitness);
             
+            let fitnesses: Vec<f64> = self.population.iter().filter_map(|ind| ind.fitness).collect();
+            let best_so_far = self.best_individual.as_ref().and_then(|ind| ind.fitness);
+            record_convergence(
+                &self.eval_store,
+                &ConvergencePoint::from_objectives(gen, &fitnesses, best_so_far, self.evaluations),
+            );
+            
             // Check for convergence
             if self.check_convergence() {
                 info!("Converged at generation {}", gen);
@@ -145,6 +354,12 @@ impl GeneticOptimizer {
             self.population = new_population;
         }
         
+        if self.violations.violating > 0 {
+            info!("{} of {} individuals broke a constraint: {} rejected, {} resampled, {} penalized, {} repaired",
+                self.violations.violating, self.violations.checked, self.violations.rejected,
+                self.violations.resampled, self.violations.penalized, self.violations.repaired);
+        }
+        
         // Convert to optimization results
         let results = self.population.iter()
             .filter_map(|ind| {
@@ -179,6 +394,8 @@ impl GeneticOptimizer {
         S: Strategy + Send + 'static,
         F: Fn(ParameterSet) -> S + Send + Sync,
     {
+        let pending: Vec<bool> = self.population.iter().map(|ind| ind.fitness.is_none()).collect();
+        
         // Parallel evaluation
         let results: Vec<_> = self.population
             .par_iter_mut()
@@ -198,7 +415,8 @@ impl GeneticOptimizer {
                 });
                 
                 if let Ok(backtest_result) = result {
-                    individual.fitness = Some(self.config.objective.calculate(&backtest_result));
+                    let objective = self.config.objective.calculate_with(&backtest_result, &self.metrics);
+                    individual.fitness = Some(objective - individual.penalty);
                     individual.backtest_result = Some(backtest_result);
                 }
                 
@@ -206,6 +424,24 @@ impl GeneticOptimizer {
             })
             .collect::<Result<Vec<_>, _>>()?;
         
+        self.evaluations += pending.iter().filter(|p| **p).count();
+        if let Some((store, optimization_id)) = &self.eval_store {
+            for (individual, _) in self.population.iter().zip(&pending).filter(|(_, pending)| **pending) {
+                let (Some(fitness), Some(result)) = (individual.fitness, &individual.backtest_result) else {
+                    continue;
+                };
+                let record = EvaluationRecord {
+                    parameters: individual.parameters.clone(),
+                    objective_value: fitness,
+                    metrics: EvaluationMetrics::from(result),
+                    evaluated_at: chrono::Utc::now(),
+                };
+                if let Err(e) = store.record(optimization_id, &record) {
+                    warn!("Failed to persist evaluation for {}: {}", optimization_id, e);
+                }
+            }
+        }
+        
         // Update best individual
         if let Some(best) = self.population.iter()
             .filter(|ind| ind.fitness.is_some())
@@ -221,10 +457,104 @@ impl GeneticOptimizer {
         Ok(())
     }
     
+    /// Record the population about to be evaluated, persisting it with the
+    /// evaluations when a store is attached
+    fn record_snapshot(&mut self) {
+        let snapshot = PopulationSnapshot {
+            seed: self.seed,
+            generation: self.generation,
+            parameter_bounds: self.config.parameter_bounds.clone(),
+            individuals: self.population.iter().map(Individual::snapshot).collect(),
+            history: self.history.clone(),
+        };
+        if let Some((store, optimization_id)) = &self.eval_store {
+            if let Err(e) = store.record_population(optimization_id, &snapshot) {
+                warn!("Failed to persist population of generation {} for {}: {}", self.generation, optimization_id, e);
+            }
+        }
+        self.snapshots.push(snapshot);
+    }
+    
+    /// Apply pending steering commands before the next generation is evaluated
+    async fn apply_steering(&mut self) {
+        let control = match &self.control {
+            Some(control) => control.clone(),
+            None => return,
+        };
+        
+        control.wait_while_paused().await;
+        
+        if let Some(bounds) = control.take_bound_update() {
+            self.config.parameter_bounds = bounds;
+            for individual in &mut self.population {
+                clamp_to_bounds(&mut individual.parameters, &self.config.parameter_bounds);
+                // Clamped individuals may have moved, so they need re-evaluation
+                individual.fitness = None;
+                individual.backtest_result = None;
+            }
+        }
+        
+        let candidates = control.take_candidates();
+        if candidates.is_empty() {
+            return;
+        }
+        
+        // Injected candidates replace the weakest individuals, never the elite
+        self.population.sort_by(|a, b| b.fitness.partial_cmp(&a.fitness).unwrap());
+        let replaceable = self.population.len().saturating_sub(self.config.elite_size);
+        let count = candidates.len().min(replaceable);
+        let start = self.population.len() - count;
+        for (slot, candidate) in self.population[start..].iter_mut().zip(candidates) {
+            *slot = Individual::new(candidate);
+        }
+        info!("Injected {} candidate(s) into generation {}", count, self.generation);
+    }
+    
+    /// Handler for the configured constraints within the current bounds
+    fn constraint_handler(&self) -> ConstraintHandler {
+        let handler = ConstraintHandler::new(
+            self.config.parameter_constraints.clone(),
+            self.config.constraint_handling,
+            self.config.parameter_bounds.clone(),
+        );
+        match &self.repair_fn {
+            Some(repair) => handler.with_repair_fn(Arc::clone(repair)),
+            None => handler,
+        }
+    }
+    
+    /// Bring the random starting population inside the constraints,
+    /// dropping individuals that could not be resampled or repaired
+    fn admit_initial_population(&mut self) {
+        let handler = self.constraint_handler();
+        if handler.is_empty() {
+            return;
+        }
+        
+        let bounds = &self.config.parameter_bounds;
+        let stats = &mut self.violations;
+        let rng = &mut self.initial_rng;
+        self.population = std::mem::take(&mut self.population)
+            .into_iter()
+            .filter_map(|individual| {
+                let admitted = handler.admit(
+                    individual.parameters,
+                    || Some(Individual::random(bounds, rng).parameters),
+                    stats,
+                )?;
+                let mut individual = Individual::new(admitted.parameters);
+                individual.penalty = admitted.penalty;
+                Some(individual)
+            })
+            .collect();
+    }
+    
     /// Evolve population to next generation
-    fn evolve(&self) -> Vec<Individual> {
+    fn evolve(&mut self) -> Vec<Individual> {
         let mut new_population = Vec::new();
-        let mut rng = thread_rng();
+        let handler = self.constraint_handler();
+        let mut stats = std::mem::take(&mut self.violations);
+        let mut rng = generation_rng(self.seed, self.generation);
         
         // Elitism - preserve best individuals
         let mut sorted = self.population.clone();
@@ -236,42 +566,67 @@ impl GeneticOptimizer {
         
         // Generate rest of population
         while new_population.len() < self.config.population_size {
-            // Selection
-            let parent1 = self.select_parent();
-            let parent2 = self.select_parent();
-            
-            // Crossover
-            let mut offspring = if rng.gen::<f64>() < self.config.crossover_rate {
-                self.crossover(&parent1, &parent2)
-            } else {
-                parent1.clone()
-            };
-            
-            // Mutation
-            if rng.gen::<f64>() < self.config.mutation_rate {
-                self.mutate(&mut offspring);
+            let (parent1, mut offspring) = self.breed(&mut rng);
+            if handler.is_empty() {
+                new_population.push(offspring);
+                continue;
             }
             
-            new_population.push(offspring);
+            // Offspring that cannot be made valid give way to their parent
+            let admitted = handler.admit(offspring.parameters.clone(), || Some(self.breed(&mut rng).1.parameters), &mut stats);
+            new_population.push(match admitted {
+                Some(admitted) if !admitted.modified => {
+                    offspring.penalty = admitted.penalty;
+                    offspring
+                }
+                Some(admitted) => {
+                    let mut offspring = Individual::new(admitted.parameters);
+                    offspring.penalty = admitted.penalty;
+                    offspring
+                }
+                None => parent1,
+            });
         }
         
+        self.violations = stats;
         new_population
     }
     
+    /// Select two parents and produce one offspring, returning the first
+    /// parent with it
+    fn breed(&self, rng: &mut StdRng) -> (Individual, Individual) {
+        // Selection
+        let parent1 = self.select_parent(rng);
+        let parent2 = self.select_parent(rng);
+        
+        // Crossover
+        let mut offspring = if rng.gen::<f64>() < self.config.crossover_rate {
+            self.crossover(&parent1, &parent2, rng)
+        } else {
+            parent1.clone()
+        };
+        
+        // Mutation
+        if rng.gen::<f64>() < self.config.mutation_rate {
+            self.mutate(&mut offspring, rng);
+        }
+        
+        (parent1, offspring)
+    }
+    
     /// Select parent using configured strategy
-    fn select_parent(&self) -> Individual {
+    fn select_parent(&self, rng: &mut StdRng) -> Individual {
         match self.config.selection_strategy {
-            SelectionStrategy::Tournament => self.tournament_selection(),
-            SelectionStrategy::RouletteWheel => self.roulette_selection(),
-            SelectionStrategy::RankBased => self.rank_selection(),
+            SelectionStrategy::Tournament => self.tournament_selection(rng),
+            SelectionStrategy::RouletteWheel => self.roulette_selection(rng),
+            SelectionStrategy::RankBased => self.rank_selection(rng),
         }
     }
     
     /// Tournament selection
-    fn tournament_selection(&self) -> Individual {
-        let mut rng = thread_rng();
+    fn tournament_selection(&self, rng: &mut StdRng) -> Individual {
         let tournament: Vec<_> = (0..self.config.tournament_size)
-            .map(|_| self.population.choose(&mut rng).unwrap())
+            .map(|_| self.population.choose(rng).unwrap())
             .collect();
         
         tournament.into_iter()
@@ -281,8 +636,7 @@ impl GeneticOptimizer {
     }
     
     /// Roulette wheel selection
-    fn roulette_selection(&self) -> Individual {
-        let mut rng = thread_rng();
+    fn roulette_selection(&self, rng: &mut StdRng) -> Individual {
         let total_fitness: f64 = self.population.iter()
             .filter_map(|ind| ind.fitness)
             .sum();
@@ -303,17 +657,17 @@ impl GeneticOptimizer {
     }
     
     /// Rank-based selection
-    fn rank_selection(&self) -> Individual {
+    fn rank_selection(&self, rng: &mut StdRng) -> Individual {
         // Simplified rank selection
-        self.tournament_selection()
+        self.tournament_selection(rng)
     }
     
     /// Crossover two parents
-    fn crossover(&self, parent1: &Individual, parent2: &Individual) -> Individual {
-        let mut rng = thread_rng();
+    fn crossover(&self, parent1: &Individual, parent2: &Individual, rng: &mut StdRng) -> Individual {
         let mut offspring_params = HashMap::new();
         
-        for (name, value1) in &parent1.parameters.parameters {
+        for name in sorted_names(&parent1.parameters.parameters) {
+            let value1 = &parent1.parameters.parameters[name];
             if let Some(value2) = parent2.parameters.parameters.get(name) {
                 // Uniform crossover
                 let use_parent1 = rng.gen::<bool>();
@@ -330,11 +684,11 @@ impl GeneticOptimizer {
     }
     
     /// Mutate an individual
-    fn mutate(&self, individual: &mut Individual) {
-        let mut rng = thread_rng();
-        
-        for (name, value) in &mut individual.parameters.parameters {
-            if let Some((min, max)) = self.config.parameter_bounds.get(name) {
+    fn mutate(&self, individual: &mut Individual, rng: &mut StdRng) {
+        let names: Vec<String> = sorted_names(&individual.parameters.parameters).into_iter().cloned().collect();
+        for name in names {
+            let value = individual.parameters.parameters.get_mut(&name).unwrap();
+            if let Some((min, max)) = self.config.parameter_bounds.get(&name) {
                 if let ParameterValue::Float(v) = value {
                     // Gaussian mutation
                     let std_dev = (max - min) * 0.1;
@@ -401,4 +755,69 @@ pub struct GenerationStats {
     pub worst_fitness: f64,
     pub avg_fitness: f64,
     pub std_dev: f64,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(seed: u64) -> GeneticConfig {
+        GeneticConfig {
+            population_size: 12,
+            generations: 5,
+            mutation_rate: 0.3,
+            crossover_rate: 0.8,
+            selection_strategy: SelectionStrategy::Tournament,
+            elite_size: 2,
+            tournament_size: 3,
+            objective: ObjectiveFunction::SharpeRatio,
+            parameter_bounds: [("fast".to_string(), (2.0, 20.0)), ("slow".to_string(), (20.0, 100.0)), ("stop".to_string(), (1.0, 8.0))].into(),
+            parameter_constraints: Vec::new(),
+            constraint_handling: ConstraintHandling::default(),
+            seed: Some(seed),
+            snapshot_populations: true,
+        }
+    }
+
+    fn parameters(population: &[Individual]) -> serde_json::Value {
+        serde_json::to_value(population.iter().map(|ind| &ind.parameters).collect::<Vec<_>>()).unwrap()
+    }
+
+    /// Evaluate without backtests: fitness is a fixed function of the parameters
+    fn score(optimizer: &mut GeneticOptimizer) {
+        for individual in &mut optimizer.population {
+            let value = |name: &str| individual.parameters.parameters[name].as_f64().unwrap();
+            individual.fitness.get_or_insert(value("slow") / value("fast") - value("stop"));
+        }
+    }
+
+    #[test]
+    fn test_same_seed_evolves_same_populations_and_resumes() {
+        let mut first = GeneticOptimizer::new(config(7));
+        let mut second = GeneticOptimizer::new(config(7));
+        assert_eq!(first.seed(), 7);
+        assert_eq!(parameters(&first.population), parameters(&second.population));
+        assert_ne!(parameters(&first.population), parameters(&GeneticOptimizer::new(config(8)).population));
+
+        for generation in 0..3 {
+            for optimizer in [&mut first, &mut second] {
+                optimizer.generation = generation;
+                optimizer.record_snapshot();
+                score(optimizer);
+                optimizer.population = optimizer.evolve();
+            }
+        }
+        assert_eq!(parameters(&first.population), parameters(&second.population));
+
+        // Resuming from generation 1 breeds the same generation 2 onwards
+        let snapshot = first.population_snapshots()[1].clone();
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let mut resumed = GeneticOptimizer::resume(config(99), serde_json::from_str(&json).unwrap());
+        assert_eq!((resumed.seed(), resumed.generation), (7, 1));
+        for generation in 1..3 {
+            resumed.generation = generation;
+            score(&mut resumed);
+            resumed.population = resumed.evolve();
+        }
+        assert_eq!(parameters(&resumed.population), parameters(&first.population));
+    }
+}
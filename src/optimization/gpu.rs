@@ -0,0 +1,191 @@
+//! wgpu compute backend for batch objective evaluation
+//!
+//! Only built with the `gpu` feature. Runs a strategy's WGSL kernel with one
+//! invocation per parameter set and reads the metrics back.
+
+use wgpu::util::DeviceExt;
+
+use crate::optimization::batch_eval::{BatchEvalError, BatchMetrics};
+
+/// Must match `@workgroup_size` in the kernels
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Values written per parameter set: pnl, trades, max drawdown
+const OUTPUTS_PER_SET: usize = 3;
+
+/// An open GPU device and queue
+pub struct GpuContext {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    adapter_name: String,
+}
+
+fn gpu_error(e: impl std::fmt::Display) -> BatchEvalError {
+    BatchEvalError::Gpu(e.to_string())
+}
+
+fn to_bytes(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_ne_bytes()).collect()
+}
+
+impl GpuContext {
+    /// Open the highest-performance adapter available
+    pub fn new() -> Result<Self, BatchEvalError> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter = futures::executor::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        }))
+        .map_err(gpu_error)?;
+
+        let (device, queue) = futures::executor::block_on(
+            adapter.request_device(&wgpu::DeviceDescriptor::default()),
+        )
+        .map_err(gpu_error)?;
+
+        Ok(Self {
+            device,
+            queue,
+            adapter_name: adapter.get_info().name,
+        })
+    }
+
+    pub fn adapter_name(&self) -> &str {
+        &self.adapter_name
+    }
+
+    /// Run `kernel` over one batch of flattened parameter sets
+    pub fn evaluate(
+        &self,
+        kernel: &str,
+        point_value: f32,
+        prices: &[f32],
+        params: &[f32],
+        n_params: usize,
+    ) -> Result<Vec<BatchMetrics>, BatchEvalError> {
+        let n_sets = params.len() / n_params;
+        let output_size = (n_sets * OUTPUTS_PER_SET * std::mem::size_of::<f32>()) as u64;
+
+        let mut dims = Vec::with_capacity(16);
+        dims.extend_from_slice(&(prices.len() as u32).to_ne_bytes());
+        dims.extend_from_slice(&(n_sets as u32).to_ne_bytes());
+        dims.extend_from_slice(&(n_params as u32).to_ne_bytes());
+        dims.extend_from_slice(&point_value.to_ne_bytes());
+
+        let device = &self.device;
+        let dims_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("batch_eval_dims"),
+            contents: &dims,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let prices_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("batch_eval_prices"),
+            contents: &to_bytes(prices),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("batch_eval_params"),
+            contents: &to_bytes(params),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let results_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("batch_eval_results"),
+            size: output_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("batch_eval_readback"),
+            size: output_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("batch_eval_kernel"),
+            source: wgpu::ShaderSource::Wgsl(kernel.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("batch_eval_pipeline"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("batch_eval_bindings"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: dims_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: prices_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: results_buffer.as_entire_binding() },
+            ],
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("batch_eval_encoder"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            pass.set_pipeline(&pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups((n_sets as u32).div_ceil(WORKGROUP_SIZE), 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&results_buffer, 0, &readback_buffer, 0, output_size);
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = readback_buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        device.poll(wgpu::PollType::Wait).map_err(gpu_error)?;
+        rx.recv().map_err(gpu_error)?.map_err(gpu_error)?;
+
+        let metrics = {
+            let data = slice.get_mapped_range();
+            data.chunks_exact(OUTPUTS_PER_SET * std::mem::size_of::<f32>())
+                .map(|row| {
+                    let value = |i: usize| f32::from_ne_bytes([row[i * 4], row[i * 4 + 1], row[i * 4 + 2], row[i * 4 + 3]]);
+                    BatchMetrics {
+                        total_pnl: value(0) as f64,
+                        trades: value(1) as u32,
+                        max_drawdown: value(2) as f64,
+                    }
+                })
+                .collect()
+        };
+        readback_buffer.unmap();
+
+        Ok(metrics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::optimization::batch_eval::{MovingAverageCrossover, VectorizedStrategy};
+
+    #[test]
+    fn test_gpu_matches_cpu() {
+        // Machines without an adapter have nothing to compare
+        let Ok(gpu) = GpuContext::new() else {
+            return;
+        };
+        let strategy = MovingAverageCrossover::default();
+        let prices: Vec<f32> = (0..500).map(|i| 100.0 + (i as f32 * 0.05).sin() * 10.0).collect();
+        let params: Vec<f32> = (1..=8).flat_map(|f| [f as f32, 30.0]).collect();
+
+        let gpu_metrics = gpu.evaluate(strategy.wgsl_kernel().unwrap(), strategy.point_value(), &prices, &params, 2)
+            .unwrap();
+
+        for (metrics, set) in gpu_metrics.iter().zip(params.chunks(2)) {
+            let cpu = strategy.evaluate_cpu(&prices, set);
+            assert_eq!(metrics.trades, cpu.trades);
+            assert!((metrics.total_pnl - cpu.total_pnl).abs() < 1e-2);
+        }
+    }
+}
@@ -1,9 +1,19 @@
 //! Grid search optimization implementation
 
-use crate::backtesting::{BacktestEngine, BacktestConfig, BacktestResult, PerformanceMetrics};
+use crate::backtesting::{BacktestEngine, BacktestConfig, BacktestResult, MetricRegistry, PerformanceMetrics};
 use crate::strategy::{Strategy, StrategyConfig};
 use crate::strategy::config::ParameterValue;
 use crate::optimization::{OptimizationResult, ParameterSet, ObjectiveFunction};
+use crate::optimization::eval_store::{EvaluationRecord, EvaluationStore};
+use crate::optimization::convergence::{record_convergence, ConvergencePoint, GRID_CONVERGENCE_INTERVAL};
+use crate::optimization::top_k::{ObjectiveDistribution, TDigest, TopK, DEFAULT_TOP_K};
+use crate::optimization::constraints::{
+    Admitted, ConstraintHandler, ConstraintHandling, ParameterConstraint, RepairFn, ViolationStats,
+};
+use crate::performance::{PoolKind, ThreadPools};
+use crate::optimization::batch_eval::{
+    BatchEvalConfig, BatchEvalError, BatchEvaluation, BatchEvaluator, VectorizedStrategy,
+};
 use rayon::prelude::*;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
@@ -32,6 +42,23 @@ pub struct GridSearchConfig {
     
     /// Minimum trades required for valid result
     pub min_trades: u32,
+    
+    /// Constraints every evaluated combination must satisfy
+    #[serde(default)]
+    pub parameter_constraints: Vec<ParameterConstraint>,
+    
+    /// What to do with combinations that break a constraint
+    #[serde(default)]
+    pub constraint_handling: ConstraintHandling,
+    
+    /// Results kept in memory, best first; every objective still feeds the
+    /// distribution statistics
+    #[serde(default = "default_top_k")]
+    pub top_k: usize,
+}
+
+fn default_top_k() -> usize {
+    DEFAULT_TOP_K
 }
 
 /// Parameter range for grid search
@@ -62,6 +89,65 @@ impl ParameterRange {
     }
 }
 
+/// The cartesian product of the parameter ranges, walked by index
+///
+/// Each combination is decoded from its index when drawn, so the grid is
+/// never held in memory.
+struct GridIndex {
+    axes: Arc<[(String, Vec<f64>)]>,
+    next: usize,
+    len: usize,
+}
+
+impl GridIndex {
+    fn new(parameters: &HashMap<String, ParameterRange>) -> Self {
+        let axes: Arc<[(String, Vec<f64>)]> = parameters.iter()
+            .map(|(name, range)| (name.clone(), range.generate_values()))
+            .collect();
+        let len = axes.iter()
+            .try_fold(1usize, |len, (_, values)| len.checked_mul(values.len()))
+            .unwrap_or(usize::MAX);
+        Self { axes, next: 0, len }
+    }
+
+    /// Combination `index`, with the last parameter varying fastest
+    fn combination(&self, mut index: usize) -> ParameterSet {
+        let mut combination = ParameterSet::new();
+        for (name, values) in self.axes.iter().rev() {
+            combination.parameters.insert(name.clone(), ParameterValue::Float(values[index % values.len()]));
+            index /= values.len();
+        }
+        combination
+    }
+}
+
+impl Iterator for GridIndex {
+    type Item = ParameterSet;
+
+    fn next(&mut self) -> Option<ParameterSet> {
+        if self.next >= self.len {
+            return None;
+        }
+        let combination = self.combination(self.next);
+        self.next += 1;
+        Some(combination)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+/// Whether every parameter of `parameters` sits on a point of `axes`
+fn on_grid(axes: &[(String, Vec<f64>)], parameters: &ParameterSet) -> bool {
+    axes.iter().all(|(name, values)| {
+        parameters.parameters.get(name)
+            .and_then(|value| value.as_f64())
+            .is_some_and(|value| values.contains(&value))
+    })
+}
+
 /// Early stopping configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EarlyStoppingConfig {
@@ -75,26 +161,81 @@ pub struct EarlyStoppingConfig {
     pub max_drawdown: Decimal,
 }
 
+/// Results of a run held in bounded memory
+struct GridResults {
+    top: TopK<OptimizationResult>,
+    distribution: TDigest,
+    /// Objectives since the last convergence point
+    batch: Vec<f64>,
+}
+
+impl GridResults {
+    fn new(top_k: usize) -> Self {
+        Self {
+            top: TopK::new(top_k),
+            distribution: TDigest::default(),
+            batch: Vec::with_capacity(GRID_CONVERGENCE_INTERVAL),
+        }
+    }
+}
+
 /// Grid search optimizer
 pub struct GridSearchOptimizer {
     config: GridSearchConfig,
-    results: Arc<Mutex<Vec<OptimizationResult>>>,
+    results: Arc<Mutex<GridResults>>,
     best_result: Arc<Mutex<Option<OptimizationResult>>>,
     evaluations: Arc<Mutex<usize>>,
     start_time: Instant,
+    /// Where evaluations are persisted as they finish, with the run's id
+    eval_store: Option<(EvaluationStore, String)>,
+    metrics: MetricRegistry,
+    /// Shared engine pools used instead of a private `num_workers` pool
+    thread_pools: Option<ThreadPools>,
+    repair_fn: Option<RepairFn>,
+    violations: Arc<Mutex<ViolationStats>>,
 }
 
 impl GridSearchOptimizer {
     pub fn new(config: GridSearchConfig) -> Self {
         Self {
+            results: Arc::new(Mutex::new(GridResults::new(config.top_k))),
             config,
-            results: Arc::new(Mutex::new(Vec::new())),
             best_result: Arc::new(Mutex::new(None)),
             evaluations: Arc::new(Mutex::new(0)),
             start_time: Instant::now(),
+            eval_store: None,
+            metrics: MetricRegistry::new(),
+            thread_pools: None,
+            repair_fn: None,
+            violations: Arc::new(Mutex::new(ViolationStats::default())),
         }
     }
     
+    /// Persist each evaluation under `optimization_id` as soon as it finishes
+    pub fn with_evaluation_store(mut self, store: EvaluationStore, optimization_id: impl Into<String>) -> Self {
+        self.eval_store = Some((store, optimization_id.into()));
+        self
+    }
+    
+    /// Resolve `ObjectiveFunction::Metric` objectives through `metrics`
+    pub fn with_metric_registry(mut self, metrics: MetricRegistry) -> Self {
+        self.metrics = metrics;
+        self
+    }
+    
+    /// Evaluate on the engine's shared evaluation pool and report throughput to it
+    pub fn with_thread_pools(mut self, thread_pools: ThreadPools) -> Self {
+        self.thread_pools = Some(thread_pools);
+        self
+    }
+    
+    /// Repair constraint-breaking combinations with `repair` instead of the
+    /// constraints' own repairs when handling is `Repair`
+    pub fn with_repair_fn(mut self, repair: RepairFn) -> Self {
+        self.repair_fn = Some(repair);
+        self
+    }
+    
     /// Run grid search optimization
     pub async fn optimize<S, F>(
         &mut self,
@@ -109,33 +250,40 @@ impl GridSearchOptimizer {
         info!("Starting grid search optimization with {} parameters",
             self.config.parameters.len());
         
-        // Generate all parameter combinations
-        let combinations = self.generate_combinations();
-        let total_combinations = combinations.len();
+        // Combinations are drawn from the grid as workers take them
+        let grid = self.generate_combinations();
+        let total_combinations = grid.len;
         
-        info!("Generated {} parameter combinations", total_combinations);
+        info!("Searching a grid of {} parameter combinations", total_combinations);
         
         if let Some(max) = self.config.max_combinations {
             if total_combinations > max {
                 warn!("Limiting to {} combinations (from {})", max, total_combinations);
             }
         }
+        let combinations = self.admit_combinations(grid)
+            .take(self.config.max_combinations.unwrap_or(usize::MAX));
         
         // Set up thread pool
-        let pool = rayon::ThreadPoolBuilder::new()
-            .num_threads(self.config.num_workers)
-            .build()?;
+        let pool = match &self.thread_pools {
+            Some(thread_pools) => thread_pools.pool(PoolKind::Evaluation),
+            None => Arc::new(rayon::ThreadPoolBuilder::new()
+                .num_threads(self.config.num_workers)
+                .build()?),
+        };
+        let pool_started = Instant::now();
         
         // Process combinations in parallel
         let results = Arc::clone(&self.results);
         let best_result = Arc::clone(&self.best_result);
         let evaluations = Arc::clone(&self.evaluations);
         let config = self.config.clone();
+        let eval_store = self.eval_store.clone();
+        let metrics = self.metrics.clone();
         
         pool.install(|| {
-            combinations.par_iter()
-                .take(config.max_combinations.unwrap_or(usize::MAX))
-                .for_each(|params| {
+            combinations.par_bridge()
+                .for_each(|Admitted { parameters: params, penalty, .. }| {
                     // Check early stopping
                     if let Some(early_stop) = &config.early_stopping {
                         let evals = evaluations.lock().unwrap();
@@ -165,7 +313,7 @@ impl GridSearchOptimizer {
                             let opt_result = OptimizationResult {
                                 parameters: params.clone(),
                                 backtest_result: backtest_result.clone(),
-                                objective_value: config.objective.calculate(&backtest_result),
+                                objective_value: config.objective.calculate_with(&backtest_result, &metrics) - penalty,
                                 timestamp: chrono::Utc::now(),
                                 metrics: PerformanceMetrics::new(),
                                 equity_curve: Vec::new(),
@@ -173,63 +321,163 @@ impl GridSearchOptimizer {
                                 parameter_sensitivity: None,
                             };
                             
-                            // Update results
-                            let mut res = results.lock().unwrap();
-                            res.push(opt_result.clone());
-                            
-                            // Update best result
-                            let mut best = best_result.lock().unwrap();
-                            if best.is_none() || opt_result.objective_value > best.as_ref().unwrap().objective_value {
-                                *best = Some(opt_result);
+                            if let Some((store, optimization_id)) = &eval_store {
+                                if let Err(e) = store.record(optimization_id, &EvaluationRecord::from(&opt_result)) {
+                                    warn!("Failed to persist evaluation for {}: {}", optimization_id, e);
+                                }
                             }
                             
-                            // Update evaluation count
-                            let mut evals = evaluations.lock().unwrap();
-                            *evals += 1;
-                            
-                            if *evals % 10 == 0 {
-                                debug!("Evaluated {} / {} combinations", evals, total_combinations);
+                            let convergence = {
+                                // Update results
+                                let mut res = results.lock().unwrap();
+                                let objective = opt_result.objective_value;
+                                res.distribution.add(objective);
+                                res.batch.push(objective);
+                                
+                                // Update best result
+                                let mut best = best_result.lock().unwrap();
+                                if best.is_none() || objective > best.as_ref().unwrap().objective_value {
+                                    *best = Some(opt_result.clone());
+                                }
+                                res.top.push(objective, opt_result);
+                                
+                                // Update evaluation count
+                                let mut evals = evaluations.lock().unwrap();
+                                *evals += 1;
+                                
+                                if *evals % 10 == 0 {
+                                    debug!("Evaluated {} / {} combinations", evals, total_combinations);
+                                }
+                                
+                                (*evals % GRID_CONVERGENCE_INTERVAL == 0).then(|| {
+                                    let batch = std::mem::take(&mut res.batch);
+                                    let best_so_far = best.as_ref().map(|b| b.objective_value);
+                                    let step = *evals / GRID_CONVERGENCE_INTERVAL - 1;
+                                    ConvergencePoint::from_objectives(step, &batch, best_so_far, *evals)
+                                })
+                            };
+                            // Written once the locks are released, so other workers never wait on the store
+                            if let Some(point) = convergence {
+                                record_convergence(&eval_store, &point);
                             }
                         }
                     }
                 });
         });
         
+        // Close the curve with the evaluations after the last full batch
+        let evaluated = *self.evaluations.lock().unwrap();
+        let remainder = evaluated % GRID_CONVERGENCE_INTERVAL;
+        if remainder > 0 {
+            let batch = std::mem::take(&mut self.results.lock().unwrap().batch);
+            let best_so_far = self.best_result.lock().unwrap().as_ref().map(|b| b.objective_value);
+            record_convergence(
+                &self.eval_store,
+                &ConvergencePoint::from_objectives(evaluated / GRID_CONVERGENCE_INTERVAL, &batch, best_so_far, evaluated),
+            );
+        }
+        
+        self.log_violations();
+        if let Some(thread_pools) = &self.thread_pools {
+            let evaluated = evaluated as u64;
+            thread_pools.record(PoolKind::Evaluation, evaluated, pool_started.elapsed());
+        }
+        
         let elapsed = self.start_time.elapsed();
-        let final_results = self.results.lock().unwrap().clone();
+        let final_results = self.get_results();
         
-        info!("Grid search completed: {} combinations in {:.2}s ({:.1} comb/sec)",
-            final_results.len(),
+        info!("Grid search completed: {} combinations in {:.2}s ({:.1} comb/sec), keeping the best {}",
+            evaluated,
             elapsed.as_secs_f64(),
-            final_results.len() as f64 / elapsed.as_secs_f64()
+            evaluated as f64 / elapsed.as_secs_f64(),
+            final_results.len()
         );
         
         Ok(final_results)
     }
     
-    /// Generate all parameter combinations
-    fn generate_combinations(&self) -> Vec<ParameterSet> {
-        let mut combinations = vec![ParameterSet::new()];
+    /// Evaluate every combination of a vectorized strategy over a price series
+    ///
+    /// Skips the tick-level backtest engine; batches run on the GPU when one
+    /// is available and on the CPU otherwise. Results are sorted by PnL.
+    pub fn optimize_vectorized(
+        &self,
+        strategy: &dyn VectorizedStrategy,
+        prices: &[f32],
+        eval_config: BatchEvalConfig,
+    ) -> Result<Vec<BatchEvaluation>, BatchEvalError> {
+        // Vectorized sweeps rank by P&L, so penalties do not apply here
+        let combinations: Vec<_> = self.admit_combinations(self.generate_combinations())
+            .map(|admitted| admitted.parameters)
+            .take(self.config.max_combinations.unwrap_or(usize::MAX))
+            .collect();
+        self.log_violations();
+        
+        let start = Instant::now();
+        let mut evaluator = BatchEvaluator::new(eval_config);
+        let mut results = evaluator.evaluate(strategy, prices, &combinations)?;
+        
+        info!("Vectorized {} sweep: {} combinations in {:.2}s on {:?}",
+            strategy.name(),
+            results.len(),
+            start.elapsed().as_secs_f64(),
+            evaluator.backend()
+        );
         
-        for (name, range) in &self.config.parameters {
-            let values = range.generate_values();
-            let mut new_combinations = Vec::new();
-            
-            for combo in &combinations {
-                for value in &values {
-                    let mut new_combo = combo.clone();
-                    new_combo.parameters.insert(
-                        name.clone(),
-                        ParameterValue::Float(*value),
-                    );
-                    new_combinations.push(new_combo);
-                }
+        results.sort_by(|a, b| b.metrics.total_pnl.total_cmp(&a.metrics.total_pnl));
+        Ok(results)
+    }
+    
+    /// Every parameter combination, decoded lazily from the grid's index space
+    fn generate_combinations(&self) -> GridIndex {
+        GridIndex::new(&self.config.parameters)
+    }
+    
+    /// Apply the configured constraint handling to each combination as it is drawn
+    ///
+    /// The grid cannot draw replacements, so rejected combinations are
+    /// skipped. A combination repaired onto another grid point is dropped, as
+    /// that point is evaluated in its own turn; repairs landing between grid
+    /// points are kept.
+    fn admit_combinations(&self, combinations: GridIndex) -> impl Iterator<Item = Admitted> + Send + 'static {
+        let handler = (!self.config.parameter_constraints.is_empty()).then(|| {
+            let bounds = self.config.parameters.iter()
+                .map(|(name, range)| (name.clone(), (range.min, range.max)))
+                .collect();
+            let handler = ConstraintHandler::new(
+                self.config.parameter_constraints.clone(),
+                self.config.constraint_handling,
+                bounds,
+            );
+            match &self.repair_fn {
+                Some(repair) => handler.with_repair_fn(Arc::clone(repair)),
+                None => handler,
             }
-            
-            combinations = new_combinations;
-        }
+        });
+        let violations = Arc::clone(&self.violations);
+        let axes = Arc::clone(&combinations.axes);
         
-        combinations
+        combinations.filter_map(move |parameters| {
+            let Some(handler) = &handler else {
+                return Some(Admitted { parameters, penalty: 0.0, modified: false });
+            };
+            let admitted = handler.admit(parameters, || None, &mut violations.lock().unwrap())?;
+            (!admitted.modified || !on_grid(&axes, &admitted.parameters)).then_some(admitted)
+        })
+    }
+    
+    /// Report the constraint violations seen while admitting combinations
+    fn log_violations(&self) {
+        let stats = self.violations.lock().unwrap();
+        if stats.violating > 0 {
+            info!("{} of {} combinations break a constraint: {} rejected, {} penalized, {} repaired",
+                stats.violating, stats.checked, stats.rejected, stats.penalized, stats.repaired);
+        }
+    }
+    
+    /// Constraint violations seen so far
+    pub fn violation_stats(&self) -> ViolationStats {
+        self.violations.lock().unwrap().clone()
     }
     
     /// Get best result found so far
@@ -237,9 +485,14 @@ impl GridSearchOptimizer {
         self.best_result.lock().unwrap().clone()
     }
     
-    /// Get all results
+    /// Get the best `top_k` results, best first
     pub fn get_results(&self) -> Vec<OptimizationResult> {
-        self.results.lock().unwrap().clone()
+        self.results.lock().unwrap().top.sorted()
+    }
+    
+    /// Distribution of the objective over every evaluated combination
+    pub fn objective_distribution(&self) -> ObjectiveDistribution {
+        self.results.lock().unwrap().distribution.summary()
     }
     
     /// Get optimization progress
@@ -254,6 +507,7 @@ impl GridSearchOptimizer {
             best_objective: self.best_result.lock().unwrap()
                 .as_ref()
                 .map(|r| r.objective_value),
+            objective_distribution: Some(self.objective_distribution()),
         }
     }
 }
@@ -265,4 +519,35 @@ pub struct OptimizationProgress {
     pub elapsed_secs: f64,
     pub evaluations_per_sec: f64,
     pub best_objective: Option<f64>,
-}
\ No newline at end of file
+    /// Objective percentiles over every evaluation so far
+    #[serde(default)]
+    pub objective_distribution: Option<ObjectiveDistribution>,
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid_index_decodes_every_combination_once() {
+        let parameters = HashMap::from([
+            ("fast".to_string(), ParameterRange { min: 1.0, max: 3.0, step: 1.0 }),
+            ("slow".to_string(), ParameterRange { min: 10.0, max: 20.0, step: 10.0 }),
+        ]);
+        let grid = GridIndex::new(&parameters);
+        let axes = Arc::clone(&grid.axes);
+        assert_eq!(grid.size_hint(), (6, Some(6)));
+
+        let mut keys: Vec<_> = grid
+            .inspect(|combination| assert!(on_grid(&axes, combination)))
+            .map(|combination| (combination.get_float("fast").unwrap() as i64, combination.get_float("slow").unwrap() as i64))
+            .collect();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), 6);
+
+        let mut off_grid = ParameterSet::new();
+        off_grid.parameters.insert("fast".to_string(), ParameterValue::Float(1.5));
+        off_grid.parameters.insert("slow".to_string(), ParameterValue::Float(10.0));
+        assert!(!on_grid(&axes, &off_grid));
+    }
+}
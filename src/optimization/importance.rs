@@ -0,0 +1,342 @@
+//! Parameter importance analysis on evaluated parameter sets
+//!
+//! Fits a quadratic surrogate (main effects, curvature and pairwise
+//! interactions) to the objective over standardized parameters, then
+//! decomposes every prediction into exact Shapley values. A parameter's
+//! importance is its mean absolute contribution across all evaluations,
+//! which tells users which parameters are worth optimizing further.
+
+use crate::optimization::OptimizationResult;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Ridge penalty keeping the surrogate fit stable with collinear samples
+const RIDGE_LAMBDA: f64 = 1e-6;
+
+/// Errors raised when importance cannot be estimated
+#[derive(Debug, thiserror::Error)]
+pub enum ImportanceError {
+    #[error("Need at least {needed} evaluations, got {available}")]
+    InsufficientSamples { needed: usize, available: usize },
+    #[error("No numeric parameter varies across the evaluations")]
+    NoVariation,
+}
+
+/// Surrogate model used for the decomposition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SurrogateModel {
+    /// Main effects only (too few samples for higher-order terms)
+    Linear,
+    /// Main effects, squared terms and pairwise interactions
+    Quadratic,
+}
+
+/// Contribution of a single parameter to the objective
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterImportance {
+    pub parameter: String,
+    /// Mean absolute Shapley value, in objective units
+    pub importance: f64,
+    /// Fraction of total importance across all parameters
+    pub share: f64,
+    /// Correlation between the parameter value and its contribution;
+    /// positive means larger values improve the objective
+    pub direction: f64,
+}
+
+/// Strength of the joint effect of two parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractionEffect {
+    pub parameters: (String, String),
+    /// Mean absolute interaction term, in objective units
+    pub strength: f64,
+}
+
+/// Result of the importance analysis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportanceAnalysis {
+    pub model: SurrogateModel,
+    pub samples: usize,
+    /// Fraction of objective variance explained by the surrogate
+    pub r_squared: f64,
+    /// Parameters ordered from most to least important
+    pub parameters: Vec<ParameterImportance>,
+    /// Parameter pairs ordered from strongest to weakest interaction
+    pub interactions: Vec<InteractionEffect>,
+}
+
+impl ImportanceAnalysis {
+    /// Analyze the numeric parameters of a set of optimization results
+    pub fn from_results(results: &[OptimizationResult]) -> Result<Self, ImportanceError> {
+        let names: Vec<String> = results.iter()
+            .flat_map(|r| r.parameters.parameters.keys().cloned())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter(|name| results.iter().all(|r| r.parameters.get_float(name).is_some()))
+            .collect();
+
+        let samples: Vec<Vec<f64>> = results.iter()
+            .map(|r| names.iter().map(|name| r.parameters.get_float(name).unwrap_or(0.0)).collect())
+            .collect();
+        let objectives: Vec<f64> = results.iter().map(|r| r.objective_value).collect();
+
+        Self::analyze(&names, &samples, &objectives)
+    }
+
+    /// Analyze raw samples: `samples[k][i]` is parameter `names[i]` of evaluation `k`
+    pub fn analyze(
+        names: &[String],
+        samples: &[Vec<f64>],
+        objectives: &[f64],
+    ) -> Result<Self, ImportanceError> {
+        let n = samples.len().min(objectives.len());
+
+        // Standardize each parameter, dropping those that never vary
+        let mut varying = Vec::new();
+        for (i, name) in names.iter().enumerate() {
+            let column: Vec<f64> = samples[..n].iter().map(|s| s[i]).collect();
+            let (mean, std) = mean_std(&column);
+            if std > 1e-12 {
+                varying.push((name.clone(), column.iter().map(|v| (v - mean) / std).collect::<Vec<_>>()));
+            }
+        }
+
+        let p = varying.len();
+        if p == 0 {
+            return Err(ImportanceError::NoVariation);
+        }
+
+        let linear_terms = 1 + p;
+        let quadratic_terms = 1 + 2 * p + p * (p - 1) / 2;
+        let model = if n > quadratic_terms {
+            SurrogateModel::Quadratic
+        } else if n > linear_terms {
+            SurrogateModel::Linear
+        } else {
+            return Err(ImportanceError::InsufficientSamples {
+                needed: linear_terms + 1,
+                available: n,
+            });
+        };
+
+        let pairs: Vec<(usize, usize)> = match model {
+            SurrogateModel::Linear => Vec::new(),
+            SurrogateModel::Quadratic => (0..p)
+                .flat_map(|i| (i + 1..p).map(move |j| (i, j)))
+                .collect(),
+        };
+
+        // Design matrix: [1, z_i..., z_i^2..., z_i*z_j...]
+        let z = |k: usize, i: usize| varying[i].1[k];
+        let rows: Vec<Vec<f64>> = (0..n)
+            .map(|k| {
+                let mut row = Vec::with_capacity(quadratic_terms);
+                row.push(1.0);
+                row.extend((0..p).map(|i| z(k, i)));
+                if model == SurrogateModel::Quadratic {
+                    row.extend((0..p).map(|i| z(k, i).powi(2)));
+                    row.extend(pairs.iter().map(|&(i, j)| z(k, i) * z(k, j)));
+                }
+                row
+            })
+            .collect();
+
+        let beta = ridge_regression(&rows, &objectives[..n], RIDGE_LAMBDA);
+        let linear = &beta[1..1 + p];
+        let (squared, interaction) = match model {
+            SurrogateModel::Linear => (&[][..], &[][..]),
+            SurrogateModel::Quadratic => (&beta[1 + p..1 + 2 * p], &beta[1 + 2 * p..]),
+        };
+
+        let r_squared = {
+            let (mean_y, _) = mean_std(&objectives[..n]);
+            let ss_tot: f64 = objectives[..n].iter().map(|y| (y - mean_y).powi(2)).sum();
+            let ss_res: f64 = rows.iter()
+                .zip(&objectives[..n])
+                .map(|(row, y)| (y - dot(row, &beta)).powi(2))
+                .sum();
+            if ss_tot > 0.0 { 1.0 - ss_res / ss_tot } else { 0.0 }
+        };
+
+        // Exact Shapley values of the surrogate with an independent background.
+        // With standardized inputs E[z] = 0 and E[z^2] = 1, so
+        //   phi_i = a_i z_i + q_i (z_i^2 - 1) + sum_j c_ij z_i z_j / 2
+        let mut shapley = vec![vec![0.0; p]; n];
+        let mut interaction_sums = vec![0.0; pairs.len()];
+        for (k, phi) in shapley.iter_mut().enumerate() {
+            for i in 0..p {
+                phi[i] = linear[i] * z(k, i);
+                if let Some(q) = squared.get(i) {
+                    phi[i] += q * (z(k, i).powi(2) - 1.0);
+                }
+            }
+            for (pair_index, &(i, j)) in pairs.iter().enumerate() {
+                let term = interaction[pair_index] * z(k, i) * z(k, j);
+                phi[i] += term / 2.0;
+                phi[j] += term / 2.0;
+                interaction_sums[pair_index] += term.abs();
+            }
+        }
+
+        let importances: Vec<f64> = (0..p)
+            .map(|i| shapley.iter().map(|phi| phi[i].abs()).sum::<f64>() / n as f64)
+            .collect();
+        let total: f64 = importances.iter().sum();
+
+        let mut parameters: Vec<ParameterImportance> = varying.iter()
+            .enumerate()
+            .map(|(i, (name, values))| {
+                let contributions: Vec<f64> = shapley.iter().map(|phi| phi[i]).collect();
+                ParameterImportance {
+                    parameter: name.clone(),
+                    importance: importances[i],
+                    share: if total > 0.0 { importances[i] / total } else { 0.0 },
+                    direction: correlation(values, &contributions),
+                }
+            })
+            .collect();
+        parameters.sort_by(|a, b| b.importance.total_cmp(&a.importance));
+
+        let mut interactions: Vec<InteractionEffect> = pairs.iter()
+            .zip(&interaction_sums)
+            .map(|(&(i, j), sum)| InteractionEffect {
+                parameters: (varying[i].0.clone(), varying[j].0.clone()),
+                strength: sum / n as f64,
+            })
+            .collect();
+        interactions.sort_by(|a, b| b.strength.total_cmp(&a.strength));
+
+        Ok(Self {
+            model,
+            samples: n,
+            r_squared,
+            parameters,
+            interactions,
+        })
+    }
+
+    /// Importance entry for a parameter, if it varied in the evaluations
+    pub fn get(&self, parameter: &str) -> Option<&ParameterImportance> {
+        self.parameters.iter().find(|p| p.parameter == parameter)
+    }
+}
+
+fn mean_std(values: &[f64]) -> (f64, f64) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+    (mean, variance.sqrt())
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn correlation(x: &[f64], y: &[f64]) -> f64 {
+    let (mean_x, std_x) = mean_std(x);
+    let (mean_y, std_y) = mean_std(y);
+    if std_x == 0.0 || std_y == 0.0 {
+        return 0.0;
+    }
+    let covariance = x.iter()
+        .zip(y)
+        .map(|(a, b)| (a - mean_x) * (b - mean_y))
+        .sum::<f64>() / x.len() as f64;
+    covariance / (std_x * std_y)
+}
+
+/// Solve (X'X + lambda I) beta = X'y, leaving the intercept unpenalized
+fn ridge_regression(rows: &[Vec<f64>], y: &[f64], lambda: f64) -> Vec<f64> {
+    let m = rows[0].len();
+    let mut a = vec![vec![0.0; m + 1]; m];
+
+    for (row, target) in rows.iter().zip(y) {
+        for i in 0..m {
+            for j in 0..m {
+                a[i][j] += row[i] * row[j];
+            }
+            a[i][m] += row[i] * target;
+        }
+    }
+    for (i, equation) in a.iter_mut().enumerate().skip(1) {
+        equation[i] += lambda * rows.len() as f64;
+    }
+
+    // Gauss-Jordan elimination with partial pivoting
+    for col in 0..m {
+        let pivot = (col..m)
+            .max_by(|&r1, &r2| a[r1][col].abs().total_cmp(&a[r2][col].abs()))
+            .unwrap_or(col);
+        a.swap(col, pivot);
+
+        let diagonal = a[col][col];
+        if diagonal.abs() < 1e-12 {
+            continue;
+        }
+        for value in a[col].iter_mut() {
+            *value /= diagonal;
+        }
+        let pivot_row = a[col].clone();
+        for (row, equation) in a.iter_mut().enumerate() {
+            let factor = equation[col];
+            if row != col && factor != 0.0 {
+                for (value, pivot_value) in equation.iter_mut().zip(&pivot_row).skip(col) {
+                    *value -= factor * pivot_value;
+                }
+            }
+        }
+    }
+
+    a.iter().map(|equation| equation[m]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("p{}", i)).collect()
+    }
+
+    /// Deterministic grid of samples over [0, 1)^dims
+    fn grid(count: usize, dims: usize) -> Vec<Vec<f64>> {
+        (0..count)
+            .map(|k| (0..dims).map(|d| ((k * (2 * d + 3) + d * 7) % 17) as f64 / 17.0).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_ranks_dominant_parameter_first() {
+        let samples = grid(60, 3);
+        let objectives: Vec<f64> = samples.iter().map(|s| 5.0 * s[1] + 0.5 * s[0]).collect();
+
+        let analysis = ImportanceAnalysis::analyze(&names(3), &samples, &objectives).unwrap();
+
+        assert_eq!(analysis.model, SurrogateModel::Quadratic);
+        assert!(analysis.r_squared > 0.999);
+        assert_eq!(analysis.parameters[0].parameter, "p1");
+        assert!(analysis.parameters[0].direction > 0.99);
+        assert!(analysis.get("p2").unwrap().importance < 1e-3);
+    }
+
+    #[test]
+    fn test_detects_interaction() {
+        let samples = grid(80, 3);
+        let objectives: Vec<f64> = samples.iter().map(|s| 4.0 * s[0] * s[2] + 0.1 * s[1]).collect();
+
+        let analysis = ImportanceAnalysis::analyze(&names(3), &samples, &objectives).unwrap();
+
+        assert_eq!(analysis.interactions[0].parameters, ("p0".to_string(), "p2".to_string()));
+        assert!(analysis.interactions[0].strength > 10.0 * analysis.interactions[1].strength);
+    }
+
+    #[test]
+    fn test_too_few_samples() {
+        let samples = grid(3, 3);
+        let result = ImportanceAnalysis::analyze(&names(3), &samples, &[1.0, 2.0, 3.0]);
+        assert!(matches!(result, Err(ImportanceError::InsufficientSamples { .. })));
+    }
+}
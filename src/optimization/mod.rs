@@ -7,13 +7,50 @@
 pub mod grid_search;
 pub mod genetic;
 pub mod walk_forward;
+pub mod reoptimization;
+pub mod cross_validation;
+pub mod two_stage;
 pub mod parallel;
 pub mod objective;
 pub mod results;
+pub mod steering;
+pub mod importance;
+pub mod batch_eval;
+pub mod eval_store;
+pub mod constraints;
+pub mod convergence;
+pub mod top_k;
+pub mod zoom;
+#[cfg(feature = "gpu")]
+pub mod gpu;
 
 pub use grid_search::{GridSearchOptimizer, GridSearchConfig};
-pub use genetic::{GeneticOptimizer, GeneticConfig};
+pub use genetic::{GeneticOptimizer, GeneticConfig, PopulationSnapshot};
 pub use walk_forward::{WalkForwardAnalysis, WalkForwardConfig};
+pub use reoptimization::{
+    Cadence, LiveStrategy, Recommendation, RecommendedAction, ReoptimizationOutcome, ReoptimizationRunner,
+    ReoptimizationSchedule, ReoptimizationScheduler,
+};
+pub use cross_validation::{
+    purged_k_fold, CrossValidationConfig, CrossValidationError, CrossValidationResult, FoldResult, FoldSplit,
+    PurgedKFoldValidator, TimeBlock,
+};
+pub use two_stage::{
+    EvaluationStage, StagedEvaluation, TwoStageConfig, TwoStageError, TwoStageOptimizer, TwoStageResult,
+};
 pub use parallel::ParallelOptimizer;
 pub use objective::{ObjectiveFunction, OptimizationObjective};
-pub use results::{OptimizationResult, ParameterSet, OptimizationReport};
\ No newline at end of file
+pub use results::{OptimizationResult, ParameterSet, OptimizationReport};
+pub use steering::{OptimizationControl, SteeringCommand, SteeringStatus};
+pub use importance::{ImportanceAnalysis, ParameterImportance, InteractionEffect};
+pub use batch_eval::{
+    BatchEvalConfig, BatchEvaluation, BatchEvaluator, BatchMetrics, EvalBackend, MovingAverageCrossover,
+    VectorizedStrategy,
+};
+pub use constraints::{
+    Admitted, ConstraintHandler, ConstraintHandling, ParameterConstraint, RepairFn, ViolationStats,
+};
+pub use convergence::{ConvergenceCurve, ConvergencePoint};
+pub use top_k::{ObjectiveDistribution, TDigest, TopK};
+pub use eval_store::{EvalStoreError, EvaluationMetrics, EvaluationRecord, EvaluationStore};
+pub use zoom::{AxisMove, ZoomConfig, ZoomError, ZoomResult, ZoomRound, ZoomSearch};
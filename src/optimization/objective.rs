@@ -1,11 +1,12 @@
 //! Objective functions for optimization
 
 use crate::backtesting::BacktestResult;
+use crate::backtesting::metric_registry::{self, MetricInput, MetricRegistry};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 /// Objective function for optimization
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ObjectiveFunction {
     SharpeRatio,
     TotalPnl,
@@ -15,6 +16,8 @@ pub enum ObjectiveFunction {
     CalmarRatio,
     SortinoRatio,
     Custom,
+    /// A metric from a `MetricRegistry`, negated when lower is better
+    Metric(String),
 }
 
 impl ObjectiveFunction {
@@ -41,6 +44,21 @@ impl ObjectiveFunction {
                 // Custom weighted combination
                 self.calculate_custom_objective(result)
             }
+            ObjectiveFunction::Metric(name) => {
+                // Without a registry only the built-in metrics resolve
+                metric_registry::builtin_objective(name, &MetricInput::new(result))
+                    .unwrap_or(f64::NEG_INFINITY)
+            }
+        }
+    }
+    
+    /// Calculate objective value, resolving `Metric` through `registry`
+    pub fn calculate_with(&self, result: &BacktestResult, registry: &MetricRegistry) -> f64 {
+        match self {
+            ObjectiveFunction::Metric(name) => registry
+                .objective(name, &MetricInput::new(result))
+                .unwrap_or(f64::NEG_INFINITY),
+            _ => self.calculate(result),
         }
     }
     
@@ -0,0 +1,450 @@
+//! Scheduled walk-forward re-optimization of live strategies
+//!
+//! Parameters chosen at promotion go stale as markets change. Strategies
+//! running live or on paper can be scheduled for walk-forward
+//! re-optimization on a cadence; each run compares the newly optimal
+//! parameters with the ones in use on the latest out-of-sample window and
+//! raises a recommendation, with the walk-forward evidence, when switching
+//! looks worthwhile. Nothing is applied automatically.
+
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Utc, Weekday};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tracing::{info, warn};
+
+use super::walk_forward::{WalkForwardConfig, WalkForwardResult};
+use crate::backtesting::BacktestResult;
+use crate::monitoring::{AnomalyMonitor, MonitoringUpdate, UpdateType};
+
+/// Strategy statuses eligible for scheduled re-optimization
+pub const SCHEDULED_STATUSES: &[&str] = &["live", "paper"];
+
+/// Recommendations kept per strategy
+const MAX_RECOMMENDATIONS: usize = 20;
+
+/// When re-optimization runs, in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "every", rename_all = "snake_case")]
+pub enum Cadence {
+    Daily { hour: u32 },
+    Weekly { weekday: Weekday, hour: u32 },
+    Days { days: u32 },
+}
+
+impl Cadence {
+    /// First run time strictly after `after`
+    pub fn next_after(&self, after: DateTime<Utc>) -> DateTime<Utc> {
+        let at_hour = |days: i64, hour: u32| {
+            let time = NaiveTime::from_hms_opt(hour.min(23), 0, 0).unwrap_or(NaiveTime::MIN);
+            (after.date_naive() + Duration::days(days)).and_time(time).and_utc()
+        };
+        match *self {
+            Cadence::Daily { hour } => {
+                let today = at_hour(0, hour);
+                if today > after { today } else { at_hour(1, hour) }
+            }
+            Cadence::Weekly { weekday, hour } => {
+                let ahead = (weekday.num_days_from_monday() as i64 - after.weekday().num_days_from_monday() as i64)
+                    .rem_euclid(7);
+                let candidate = at_hour(ahead, hour);
+                if candidate > after { candidate } else { at_hour(ahead + 7, hour) }
+            }
+            Cadence::Days { days } => after + Duration::days(days.max(1) as i64),
+        }
+    }
+}
+
+/// Re-optimization settings of one strategy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReoptimizationSchedule {
+    pub strategy_id: String,
+    pub cadence: Cadence,
+    /// History the walk-forward analysis covers, ending at the run time
+    pub lookback_days: u32,
+    pub walk_forward: WalkForwardConfig,
+    /// (min, max, step) of each optimized parameter
+    pub parameter_ranges: HashMap<String, (f64, f64, f64)>,
+    /// Out-of-sample Sharpe gain needed before a switch is recommended
+    #[serde(default = "default_min_sharpe_improvement")]
+    pub min_sharpe_improvement: f64,
+    /// Relative change below which a parameter counts as unchanged
+    #[serde(default = "default_parameter_tolerance")]
+    pub parameter_tolerance: f64,
+}
+
+fn default_min_sharpe_improvement() -> f64 {
+    0.25
+}
+
+fn default_parameter_tolerance() -> f64 {
+    0.05
+}
+
+/// A strategy as the scheduler sees it when runs come due
+#[derive(Debug, Clone)]
+pub struct LiveStrategy {
+    pub id: String,
+    pub status: String,
+    pub parameters: HashMap<String, f64>,
+}
+
+/// Work handed to the runner
+#[derive(Debug, Clone)]
+pub struct ReoptimizationRequest {
+    pub strategy_id: String,
+    pub current_parameters: HashMap<String, f64>,
+    pub parameter_ranges: HashMap<String, (f64, f64, f64)>,
+    pub walk_forward: WalkForwardConfig,
+    pub data_start: DateTime<Utc>,
+    pub data_end: DateTime<Utc>,
+}
+
+/// What a re-optimization run found
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReoptimizationOutcome {
+    pub walk_forward: WalkForwardResult,
+    /// The parameters in use, backtested over the latest testing window
+    pub current_out_of_sample: BacktestResult,
+}
+
+/// Runs walk-forward re-optimization for the scheduler
+pub trait ReoptimizationRunner: Send + Sync {
+    fn reoptimize(&self, request: ReoptimizationRequest) -> BoxFuture<'static, Result<ReoptimizationOutcome, String>>;
+}
+
+/// What the evidence suggests doing with the parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecommendedAction {
+    /// The current parameters are still optimal or as good
+    Keep,
+    /// New parameters do better out of sample and the analysis is robust
+    Update,
+    /// New parameters do better but the analysis is not robust
+    Review,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParameterChange {
+    pub name: String,
+    pub current: Option<f64>,
+    pub proposed: f64,
+}
+
+/// Walk-forward figures backing a recommendation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecommendationEvidence {
+    pub windows: usize,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub current_sharpe: f64,
+    pub proposed_sharpe: f64,
+    pub out_of_sample_degradation: f64,
+    pub consistency_score: f64,
+    pub robustness_score: f64,
+    pub robust: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recommendation {
+    pub strategy_id: String,
+    pub generated_at: DateTime<Utc>,
+    pub action: RecommendedAction,
+    pub proposed_parameters: HashMap<String, f64>,
+    /// Parameters that moved beyond the schedule's tolerance
+    pub changes: Vec<ParameterChange>,
+    pub evidence: RecommendationEvidence,
+}
+
+/// Compare the latest window's optimum against the parameters in use
+///
+/// `None` when the walk-forward analysis produced no windows.
+pub fn recommend(
+    schedule: &ReoptimizationSchedule,
+    current: &HashMap<String, f64>,
+    outcome: &ReoptimizationOutcome,
+) -> Option<Recommendation> {
+    let analysis = &outcome.walk_forward;
+    let latest = analysis.windows.last()?;
+
+    let mut changes: Vec<ParameterChange> = latest.optimal_parameters.iter()
+        .filter(|(name, proposed)| match current.get(*name) {
+            Some(value) => (*proposed - value).abs() > schedule.parameter_tolerance * value.abs().max(f64::EPSILON),
+            None => true,
+        })
+        .map(|(name, proposed)| ParameterChange {
+            name: name.clone(),
+            current: current.get(name).copied(),
+            proposed: *proposed,
+        })
+        .collect();
+    changes.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let evidence = RecommendationEvidence {
+        windows: analysis.windows.len(),
+        window_start: latest.testing_start,
+        window_end: latest.testing_end,
+        current_sharpe: outcome.current_out_of_sample.sharpe_ratio,
+        proposed_sharpe: latest.out_of_sample_performance.sharpe_ratio,
+        out_of_sample_degradation: analysis.out_of_sample_degradation,
+        consistency_score: analysis.overall_performance.consistency_score,
+        robustness_score: analysis.robustness_score,
+        robust: analysis.is_robust(),
+    };
+    let improved = evidence.proposed_sharpe - evidence.current_sharpe >= schedule.min_sharpe_improvement;
+    let action = match (changes.is_empty() || !improved, evidence.robust) {
+        (true, _) => RecommendedAction::Keep,
+        (false, true) => RecommendedAction::Update,
+        (false, false) => RecommendedAction::Review,
+    };
+
+    Some(Recommendation {
+        strategy_id: schedule.strategy_id.clone(),
+        generated_at: Utc::now(),
+        action,
+        proposed_parameters: latest.optimal_parameters.clone(),
+        changes,
+        evidence,
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledRun {
+    pub schedule: ReoptimizationSchedule,
+    pub next_run: DateTime<Utc>,
+    pub last_run: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+/// Schedules and recommendations, shared between the scheduler task and callers
+#[derive(Clone)]
+pub struct ReoptimizationScheduler {
+    runs: Arc<RwLock<HashMap<String, ScheduledRun>>>,
+    recommendations: Arc<RwLock<HashMap<String, Vec<Recommendation>>>>,
+    runner: Arc<dyn ReoptimizationRunner>,
+    monitor: Option<AnomalyMonitor>,
+}
+
+impl ReoptimizationScheduler {
+    pub fn new(runner: Arc<dyn ReoptimizationRunner>) -> Self {
+        Self {
+            runs: Arc::default(),
+            recommendations: Arc::default(),
+            runner,
+            monitor: None,
+        }
+    }
+
+    /// Publish recommendations to act on as monitoring updates
+    pub fn with_monitor(mut self, monitor: AnomalyMonitor) -> Self {
+        self.monitor = Some(monitor);
+        self
+    }
+
+    /// Add or replace a strategy's schedule; the first run is the next cadence slot
+    pub fn schedule(&self, schedule: ReoptimizationSchedule) -> ScheduledRun {
+        let run = ScheduledRun {
+            next_run: schedule.cadence.next_after(Utc::now()),
+            schedule,
+            last_run: None,
+            last_error: None,
+        };
+        self.runs.write().unwrap().insert(run.schedule.strategy_id.clone(), run.clone());
+        run
+    }
+
+    pub fn unschedule(&self, strategy_id: &str) -> bool {
+        self.runs.write().unwrap().remove(strategy_id).is_some()
+    }
+
+    pub fn scheduled(&self) -> Vec<ScheduledRun> {
+        self.runs.read().unwrap().values().cloned().collect()
+    }
+
+    /// Recommendations for a strategy, newest first
+    pub fn recommendations(&self, strategy_id: &str) -> Vec<Recommendation> {
+        let mut recommendations = self.recommendations.read().unwrap().get(strategy_id).cloned().unwrap_or_default();
+        recommendations.reverse();
+        recommendations
+    }
+
+    /// Re-optimize every live or paper strategy whose run is due at `now`
+    pub async fn run_due(&self, now: DateTime<Utc>, strategies: &[LiveStrategy]) -> Vec<Recommendation> {
+        let due: Vec<(ReoptimizationSchedule, &LiveStrategy)> = {
+            let runs = self.runs.read().unwrap();
+            strategies.iter()
+                .filter(|strategy| SCHEDULED_STATUSES.contains(&strategy.status.as_str()))
+                .filter_map(|strategy| {
+                    let run = runs.get(&strategy.id).filter(|run| run.next_run <= now)?;
+                    Some((run.schedule.clone(), strategy))
+                })
+                .collect()
+        };
+
+        let mut raised = Vec::new();
+        for (schedule, strategy) in due {
+            let request = ReoptimizationRequest {
+                strategy_id: strategy.id.clone(),
+                current_parameters: strategy.parameters.clone(),
+                parameter_ranges: schedule.parameter_ranges.clone(),
+                walk_forward: schedule.walk_forward.clone(),
+                data_start: now - Duration::days(schedule.lookback_days as i64),
+                data_end: now,
+            };
+            info!("Re-optimizing {} over the last {} days", strategy.id, schedule.lookback_days);
+            let result = self.runner.reoptimize(request).await;
+
+            let error = match &result {
+                Ok(outcome) => match recommend(&schedule, &strategy.parameters, outcome) {
+                    Some(recommendation) => {
+                        self.raise(&recommendation);
+                        raised.push(recommendation);
+                        None
+                    }
+                    None => Some("walk-forward analysis produced no windows".to_string()),
+                },
+                Err(e) => Some(e.clone()),
+            };
+            if let Some(e) = &error {
+                warn!("Re-optimization of {} failed: {}", strategy.id, e);
+            }
+
+            if let Some(run) = self.runs.write().unwrap().get_mut(&strategy.id) {
+                run.last_run = Some(now);
+                run.last_error = error;
+                run.next_run = run.schedule.cadence.next_after(now);
+            }
+        }
+        raised
+    }
+
+    fn raise(&self, recommendation: &Recommendation) {
+        {
+            let mut recommendations = self.recommendations.write().unwrap();
+            let history = recommendations.entry(recommendation.strategy_id.clone()).or_default();
+            history.push(recommendation.clone());
+            if history.len() > MAX_RECOMMENDATIONS {
+                history.remove(0);
+            }
+        }
+
+        if recommendation.action == RecommendedAction::Keep {
+            return;
+        }
+        info!("Re-optimization recommends {:?} for {}: {} parameters changed, OOS Sharpe {:.2} -> {:.2}",
+            recommendation.action,
+            recommendation.strategy_id,
+            recommendation.changes.len(),
+            recommendation.evidence.current_sharpe,
+            recommendation.evidence.proposed_sharpe);
+        if let Some(monitor) = &self.monitor {
+            match serde_json::to_value(recommendation) {
+                Ok(data) => monitor.publish(MonitoringUpdate::new(UpdateType::Recommendation, data)),
+                Err(e) => warn!("Failed to serialize recommendation: {}", e),
+            }
+        }
+    }
+
+    /// Check for due runs every `check_every`, re-reading the strategies each time
+    pub fn spawn<F>(self, check_every: std::time::Duration, strategies: F) -> tokio::task::JoinHandle<()>
+    where
+        F: Fn() -> BoxFuture<'static, Vec<LiveStrategy>> + Send + 'static,
+    {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(check_every);
+            loop {
+                interval.tick().await;
+                let strategies = strategies().await;
+                self.run_due(Utc::now(), &strategies).await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::optimization::walk_forward::{
+        OptimizationMetric, ParameterStabilityAnalysis, WalkForwardSummary, WalkForwardWindow,
+    };
+    use chrono::TimeZone;
+
+    fn schedule() -> ReoptimizationSchedule {
+        ReoptimizationSchedule {
+            strategy_id: "mr".to_string(),
+            cadence: Cadence::Weekly { weekday: Weekday::Sun, hour: 22 },
+            lookback_days: 180,
+            walk_forward: WalkForwardConfig {
+                training_window_days: 60,
+                testing_window_days: 20,
+                step_size_days: 20,
+                min_trades_per_window: 10,
+                optimization_metric: OptimizationMetric::SharpeRatio,
+            },
+            parameter_ranges: HashMap::new(),
+            min_sharpe_improvement: default_min_sharpe_improvement(),
+            parameter_tolerance: default_parameter_tolerance(),
+        }
+    }
+
+    #[test]
+    fn test_cadence_and_recommendation() {
+        // Wednesday
+        let now = Utc.with_ymd_and_hms(2024, 3, 6, 12, 0, 0).unwrap();
+        assert_eq!(
+            Cadence::Weekly { weekday: Weekday::Sun, hour: 22 }.next_after(now),
+            Utc.with_ymd_and_hms(2024, 3, 10, 22, 0, 0).unwrap()
+        );
+        assert_eq!(Cadence::Daily { hour: 6 }.next_after(now), Utc.with_ymd_and_hms(2024, 3, 7, 6, 0, 0).unwrap());
+
+        let oos = |sharpe_ratio| BacktestResult { sharpe_ratio, ..Default::default() };
+        let outcome = ReoptimizationOutcome {
+            walk_forward: WalkForwardResult {
+                windows: vec![WalkForwardWindow {
+                    window_id: 0,
+                    training_start: now - Duration::days(80),
+                    training_end: now - Duration::days(20),
+                    testing_start: now - Duration::days(20),
+                    testing_end: now,
+                    optimal_parameters: HashMap::from([("lookback".to_string(), 30.0), ("z".to_string(), 2.0)]),
+                    in_sample_performance: oos(2.0),
+                    out_of_sample_performance: oos(1.6),
+                    parameter_sensitivity: 0.1,
+                }],
+                overall_performance: WalkForwardSummary {
+                    total_windows: 1,
+                    successful_windows: 1,
+                    average_in_sample_sharpe: 2.0,
+                    average_out_of_sample_sharpe: 1.6,
+                    consistency_score: 1.0,
+                    overall_out_of_sample_return: 4.0,
+                },
+                parameter_stability: ParameterStabilityAnalysis {
+                    parameter_volatility: HashMap::new(),
+                    correlation_matrix: HashMap::new(),
+                    stability_score: 0.8,
+                },
+                out_of_sample_degradation: 0.2,
+                statistical_significance: None,
+                predictive_power: 0.5,
+                robustness_score: 0.8,
+            },
+            current_out_of_sample: oos(0.9),
+        };
+
+        let current = HashMap::from([("lookback".to_string(), 20.0), ("z".to_string(), 2.05)]);
+        let recommendation = recommend(&schedule(), &current, &outcome).unwrap();
+        assert_eq!(recommendation.action, RecommendedAction::Update);
+        assert_eq!(recommendation.changes.len(), 1);
+        assert_eq!(recommendation.changes[0].name, "lookback");
+
+        let mut weak = outcome.clone();
+        weak.current_out_of_sample = oos(1.5);
+        assert_eq!(recommend(&schedule(), &current, &weak).unwrap().action, RecommendedAction::Keep);
+        weak.current_out_of_sample = oos(0.9);
+        weak.walk_forward.robustness_score = 0.3;
+        assert_eq!(recommend(&schedule(), &current, &weak).unwrap().action, RecommendedAction::Review);
+    }
+}
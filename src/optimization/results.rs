@@ -1,6 +1,7 @@
 //! Optimization results and reporting
 
 use crate::backtesting::{BacktestResult, PerformanceMetrics};
+use crate::optimization::importance::ImportanceAnalysis;
 use crate::strategy::config::ParameterValue;
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
@@ -58,6 +59,9 @@ pub struct OptimizationReport {
     pub parameter_sensitivity: ParameterSensitivity,
     pub convergence_analysis: ConvergenceAnalysis,
     pub statistical_significance: StatisticalSignificance,
+    /// Surrogate-based parameter importance, if enough evaluations were run
+    #[serde(default)]
+    pub parameter_importance: Option<ImportanceAnalysis>,
 }
 
 /// Summary of optimization run
@@ -113,6 +117,7 @@ impl OptimizationReport {
         let sensitivity = Self::analyze_sensitivity(results);
         let convergence = Self::analyze_convergence(results);
         let significance = Self::test_significance(&best_results);
+        let importance = ImportanceAnalysis::from_results(results).ok();
         
         Self {
             summary,
@@ -120,6 +125,7 @@ impl OptimizationReport {
             parameter_sensitivity: sensitivity,
             convergence_analysis: convergence,
             statistical_significance: significance,
+            parameter_importance: importance,
         }
     }
     
@@ -185,7 +191,7 @@ impl OptimizationReport {
     
     /// Generate text report
     pub fn to_text(&self) -> String {
-        format!(
+        let mut text = format!(
             r#"
 Optimization Report
 ===================
@@ -206,6 +212,19 @@ Top Results
             self.summary.std_dev,
             self.summary.runtime_seconds,
             self.summary.evaluations_per_second
-        )
+        );
+        
+        if let Some(importance) = &self.parameter_importance {
+            text.push_str(&format!(
+                "\nParameter Importance (R² = {:.2})\n--------------------\n",
+                importance.r_squared
+            ));
+            for parameter in &importance.parameters {
+                text.push_str(&format!("{}: {:.4} ({:.1}%)\n",
+                    parameter.parameter, parameter.importance, parameter.share * 100.0));
+            }
+        }
+        
+        text
     }
 }
\ No newline at end of file
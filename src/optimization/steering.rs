@@ -0,0 +1,291 @@
+//! Live steering of running optimizations
+//!
+//! An `OptimizationControl` is a cheap, cloneable handle shared between the
+//! API layer and a running optimizer. The optimizer polls it between
+//! generations/batches to honour pause requests, pick up narrowed parameter
+//! ranges and pull user-suggested candidates into its population.
+
+use crate::optimization::ParameterSet;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::info;
+
+/// Steering command sent to a running optimization
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum SteeringCommand {
+    Pause,
+    Resume,
+    NarrowRange {
+        parameter: String,
+        min: f64,
+        max: f64,
+    },
+    InjectCandidate {
+        parameters: HashMap<String, f64>,
+    },
+}
+
+/// Errors raised when a steering command cannot be applied
+#[derive(Debug, thiserror::Error)]
+pub enum SteeringError {
+    #[error("Unknown parameter: {0}")]
+    UnknownParameter(String),
+    #[error("Invalid range for {parameter}: [{min}, {max}]")]
+    InvalidRange { parameter: String, min: f64, max: f64 },
+    #[error("Candidate is missing parameter: {0}")]
+    IncompleteCandidate(String),
+}
+
+/// Serializable view of the current steering state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SteeringStatus {
+    pub paused: bool,
+    pub parameter_bounds: HashMap<String, (f64, f64)>,
+    pub pending_candidates: usize,
+    pub injected_total: usize,
+}
+
+#[derive(Debug)]
+struct SteeringState {
+    paused: bool,
+    bounds: HashMap<String, (f64, f64)>,
+    bounds_changed: bool,
+    pending_candidates: Vec<ParameterSet>,
+    injected_total: usize,
+}
+
+/// Shared control handle for a running optimization
+#[derive(Debug, Clone)]
+pub struct OptimizationControl {
+    state: Arc<Mutex<SteeringState>>,
+}
+
+impl OptimizationControl {
+    /// Create a control handle seeded with the optimizer's initial bounds
+    pub fn new(parameter_bounds: HashMap<String, (f64, f64)>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(SteeringState {
+                paused: false,
+                bounds: parameter_bounds,
+                bounds_changed: false,
+                pending_candidates: Vec::new(),
+                injected_total: 0,
+            })),
+        }
+    }
+
+    /// Apply a steering command
+    pub fn apply(&self, command: SteeringCommand) -> Result<(), SteeringError> {
+        match command {
+            SteeringCommand::Pause => {
+                self.pause();
+                Ok(())
+            }
+            SteeringCommand::Resume => {
+                self.resume();
+                Ok(())
+            }
+            SteeringCommand::NarrowRange { parameter, min, max } => {
+                self.narrow_range(&parameter, min, max)
+            }
+            SteeringCommand::InjectCandidate { parameters } => {
+                self.inject_candidate(ParameterSet::from_hashmap(parameters))
+            }
+        }
+    }
+
+    /// Pause the optimization at the next checkpoint
+    pub fn pause(&self) {
+        self.state.lock().unwrap().paused = true;
+        info!("Optimization paused");
+    }
+
+    /// Resume a paused optimization
+    pub fn resume(&self) {
+        self.state.lock().unwrap().paused = false;
+        info!("Optimization resumed");
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.lock().unwrap().paused
+    }
+
+    /// Block the calling optimizer until the run is resumed
+    pub async fn wait_while_paused(&self) {
+        while self.is_paused() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    /// Narrow the search range of a parameter
+    ///
+    /// The new range must lie inside the current one so steering can only
+    /// focus a search, never widen it past the configured bounds.
+    pub fn narrow_range(&self, parameter: &str, min: f64, max: f64) -> Result<(), SteeringError> {
+        let mut state = self.state.lock().unwrap();
+        let (current_min, current_max) = *state.bounds.get(parameter)
+            .ok_or_else(|| SteeringError::UnknownParameter(parameter.to_string()))?;
+
+        if !(min <= max && min >= current_min && max <= current_max) {
+            return Err(SteeringError::InvalidRange {
+                parameter: parameter.to_string(),
+                min,
+                max,
+            });
+        }
+
+        state.bounds.insert(parameter.to_string(), (min, max));
+        state.bounds_changed = true;
+        info!("Narrowed {} to [{}, {}]", parameter, min, max);
+        Ok(())
+    }
+
+    /// Queue a user-suggested candidate for evaluation
+    ///
+    /// Values are clamped into the current bounds.
+    pub fn inject_candidate(&self, mut candidate: ParameterSet) -> Result<(), SteeringError> {
+        let mut state = self.state.lock().unwrap();
+
+        for name in state.bounds.keys() {
+            if !candidate.parameters.contains_key(name) {
+                return Err(SteeringError::IncompleteCandidate(name.clone()));
+            }
+        }
+        for name in candidate.parameters.keys() {
+            if !state.bounds.contains_key(name) {
+                return Err(SteeringError::UnknownParameter(name.clone()));
+            }
+        }
+
+        clamp_to_bounds(&mut candidate, &state.bounds);
+        state.pending_candidates.push(candidate);
+        state.injected_total += 1;
+        Ok(())
+    }
+
+    /// Current parameter bounds
+    pub fn bounds(&self) -> HashMap<String, (f64, f64)> {
+        self.state.lock().unwrap().bounds.clone()
+    }
+
+    /// Take narrowed bounds if they changed since the last call
+    pub fn take_bound_update(&self) -> Option<HashMap<String, (f64, f64)>> {
+        let mut state = self.state.lock().unwrap();
+        if state.bounds_changed {
+            state.bounds_changed = false;
+            Some(state.bounds.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Drain candidates queued since the last call
+    pub fn take_candidates(&self) -> Vec<ParameterSet> {
+        std::mem::take(&mut self.state.lock().unwrap().pending_candidates)
+    }
+
+    pub fn status(&self) -> SteeringStatus {
+        let state = self.state.lock().unwrap();
+        SteeringStatus {
+            paused: state.paused,
+            parameter_bounds: state.bounds.clone(),
+            pending_candidates: state.pending_candidates.len(),
+            injected_total: state.injected_total,
+        }
+    }
+}
+
+/// Clamp every numeric parameter of a set into the given bounds
+///
+/// Integers stay integers: they are clamped to the whole numbers inside the
+/// bounds, or to the nearest one when the bounds hold none.
+pub fn clamp_to_bounds(parameters: &mut ParameterSet, bounds: &HashMap<String, (f64, f64)>) {
+    use crate::strategy::config::ParameterValue;
+
+    for (name, value) in parameters.parameters.iter_mut() {
+        if let Some(&(min, max)) = bounds.get(name) {
+            if let ParameterValue::Integer(v) = value {
+                let (low, high) = (min.ceil(), max.floor());
+                *v = if low <= high {
+                    (*v).clamp(low as i64, high as i64)
+                } else {
+                    (*v as f64).clamp(min, max).round() as i64
+                };
+            } else if let Some(v) = value.as_f64() {
+                *value = ParameterValue::Float(v.clamp(min, max));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn control() -> OptimizationControl {
+        let mut bounds = HashMap::new();
+        bounds.insert("threshold".to_string(), (0.0, 10.0));
+        bounds.insert("window".to_string(), (5.0, 50.0));
+        OptimizationControl::new(bounds)
+    }
+
+    #[test]
+    fn test_narrow_range() {
+        let control = control();
+        assert!(control.narrow_range("threshold", 2.0, 4.0).is_ok());
+        assert_eq!(control.take_bound_update().unwrap()["threshold"], (2.0, 4.0));
+        assert!(control.take_bound_update().is_none());
+
+        // Cannot widen past the current bounds
+        assert!(control.narrow_range("threshold", 1.0, 4.0).is_err());
+        assert!(control.narrow_range("unknown", 1.0, 4.0).is_err());
+    }
+
+    #[test]
+    fn test_inject_candidate_is_clamped() {
+        let control = control();
+        let mut params = HashMap::new();
+        params.insert("threshold".to_string(), 25.0);
+        params.insert("window".to_string(), 20.0);
+        control.apply(SteeringCommand::InjectCandidate { parameters: params }).unwrap();
+
+        let candidates = control.take_candidates();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].get_float("threshold"), Some(10.0));
+        assert!(control.take_candidates().is_empty());
+        assert_eq!(control.status().injected_total, 1);
+
+        let mut partial = HashMap::new();
+        partial.insert("threshold".to_string(), 1.0);
+        assert!(control.apply(SteeringCommand::InjectCandidate { parameters: partial }).is_err());
+    }
+
+    #[test]
+    fn test_integers_are_clamped_to_whole_bounds() {
+        use crate::strategy::config::ParameterValue;
+
+        let bounds = HashMap::from([("window".to_string(), (5.5, 50.5)), ("lag".to_string(), (2.2, 2.8))]);
+        let mut parameters = ParameterSet::new();
+        parameters.parameters.insert("window".to_string(), ParameterValue::Integer(3));
+        parameters.parameters.insert("lag".to_string(), ParameterValue::Integer(9));
+        clamp_to_bounds(&mut parameters, &bounds);
+        assert!(matches!(parameters.parameters["window"], ParameterValue::Integer(6)));
+        assert!(matches!(parameters.parameters["lag"], ParameterValue::Integer(3)));
+
+        parameters.parameters.insert("window".to_string(), ParameterValue::Integer(80));
+        clamp_to_bounds(&mut parameters, &bounds);
+        assert!(matches!(parameters.parameters["window"], ParameterValue::Integer(50)));
+    }
+
+    #[test]
+    fn test_pause_resume() {
+        let control = control();
+        control.apply(SteeringCommand::Pause).unwrap();
+        assert!(control.clone().is_paused());
+        control.apply(SteeringCommand::Resume).unwrap();
+        assert!(!control.is_paused());
+    }
+}
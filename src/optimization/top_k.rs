@@ -0,0 +1,332 @@
+//! Bounded result tracking for large sweeps
+//!
+//! A grid over a few hundred thousand combinations cannot keep every
+//! result in memory. `TopK` keeps only the best `k` results by objective in
+//! a min-heap, evicting the worst as better ones arrive, and `TDigest`
+//! summarizes the distribution of every objective seen in a fixed number of
+//! centroids, so ranking and percentile statistics stay available while
+//! memory stays flat however many combinations are evaluated.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Results kept by default
+pub const DEFAULT_TOP_K: usize = 100;
+
+/// Centroid budget of a digest; higher is more accurate and larger
+pub const DEFAULT_COMPRESSION: f64 = 100.0;
+
+/// Buffered values per unit of compression before they are merged in
+const BUFFER_FACTOR: usize = 5;
+
+/// An item with its score; orders worst first so the heap top is evicted
+#[derive(Debug, Clone)]
+struct Ranked<T> {
+    score: f64,
+    /// Arrival order; among equal scores the earliest is kept
+    seq: u64,
+    item: T,
+}
+
+impl<T> PartialEq for Ranked<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Ranked<T> {}
+
+impl<T> PartialOrd for Ranked<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Ranked<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.score.total_cmp(&self.score).then(self.seq.cmp(&other.seq))
+    }
+}
+
+/// The `k` highest-scoring items seen
+#[derive(Debug, Clone)]
+pub struct TopK<T> {
+    capacity: usize,
+    heap: BinaryHeap<Ranked<T>>,
+    seen: u64,
+}
+
+impl<T> TopK<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            heap: BinaryHeap::with_capacity(capacity.min(1024)),
+            seen: 0,
+        }
+    }
+
+    /// Offer an item, returning whether it was kept
+    pub fn push(&mut self, score: f64, item: T) -> bool {
+        let ranked = Ranked { score, seq: self.seen, item };
+        self.seen += 1;
+        if self.heap.len() < self.capacity {
+            self.heap.push(ranked);
+            return true;
+        }
+        match self.heap.peek() {
+            Some(worst) if ranked < *worst => {
+                self.heap.pop();
+                self.heap.push(ranked);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Score an item needs to beat to be kept, once the heap is full
+    pub fn threshold(&self) -> Option<f64> {
+        if self.heap.len() < self.capacity {
+            return None;
+        }
+        self.heap.peek().map(|worst| worst.score)
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Items offered so far, kept or not
+    pub fn seen(&self) -> u64 {
+        self.seen
+    }
+
+    /// Kept items, best first
+    pub fn sorted(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.clone().into_sorted()
+    }
+
+    /// Kept items, best first
+    pub fn into_sorted(self) -> Vec<T> {
+        self.heap.into_sorted_vec().into_iter().map(|ranked| ranked.item).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/// Streaming quantile sketch
+///
+/// Values are buffered and periodically merged into centroids whose size
+/// is bounded by `4 n q (1 - q) / compression`, so centroids near the tails
+/// stay small and extreme quantiles stay accurate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TDigest {
+    compression: f64,
+    centroids: Vec<Centroid>,
+    #[serde(skip)]
+    buffer: Vec<f64>,
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Default for TDigest {
+    fn default() -> Self {
+        Self::new(DEFAULT_COMPRESSION)
+    }
+}
+
+impl TDigest {
+    pub fn new(compression: f64) -> Self {
+        Self {
+            compression: compression.max(10.0),
+            centroids: Vec::new(),
+            buffer: Vec::new(),
+            count: 0,
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// Add a value; non-finite values are ignored
+    pub fn add(&mut self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        self.buffer.push(value);
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        if self.buffer.len() >= BUFFER_FACTOR * self.compression as usize {
+            self.compress();
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+
+    /// Merge buffered values into the centroids
+    fn compress(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        let mut all: Vec<Centroid> = self.centroids.drain(..)
+            .chain(self.buffer.drain(..).map(|mean| Centroid { mean, weight: 1.0 }))
+            .collect();
+        all.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+        let total: f64 = all.iter().map(|c| c.weight).sum();
+
+        let mut merged = Vec::new();
+        let mut current = all[0];
+        let mut cumulative = 0.0;
+        for centroid in all.into_iter().skip(1) {
+            let q = (cumulative + (current.weight + centroid.weight) / 2.0) / total;
+            let limit = (4.0 * total * q * (1.0 - q) / self.compression).max(1.0);
+            if current.weight + centroid.weight <= limit {
+                current.weight += centroid.weight;
+                current.mean += (centroid.mean - current.mean) * centroid.weight / current.weight;
+            } else {
+                cumulative += current.weight;
+                merged.push(current);
+                current = centroid;
+            }
+        }
+        merged.push(current);
+        self.centroids = merged;
+    }
+
+    /// Estimated value at quantile `q` in [0, 1], `None` when empty
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        let q = q.clamp(0.0, 1.0);
+        if q == 0.0 {
+            return Some(self.min);
+        }
+        if q == 1.0 {
+            return Some(self.max);
+        }
+        let compressed;
+        let digest = if self.buffer.is_empty() {
+            self
+        } else {
+            let mut copy = self.clone();
+            copy.compress();
+            compressed = copy;
+            &compressed
+        };
+
+        let centroids = &digest.centroids;
+        let total: f64 = centroids.iter().map(|c| c.weight).sum();
+        let target = q * total;
+        let mut cumulative = 0.0;
+        for (i, centroid) in centroids.iter().enumerate() {
+            if cumulative + centroid.weight >= target {
+                // Interpolate between the centres of neighbouring centroids
+                let center = cumulative + centroid.weight / 2.0;
+                let (x0, x1, t0, t1) = if target < center {
+                    match i.checked_sub(1).map(|j| centroids[j]) {
+                        Some(prev) => (prev.mean, centroid.mean, cumulative - prev.weight / 2.0, center),
+                        None => (self.min, centroid.mean, 0.0, center),
+                    }
+                } else {
+                    match centroids.get(i + 1) {
+                        Some(next) => (centroid.mean, next.mean, center, cumulative + centroid.weight + next.weight / 2.0),
+                        None => (centroid.mean, self.max, center, total),
+                    }
+                };
+                if t1 <= t0 {
+                    return Some(x0);
+                }
+                return Some(x0 + (x1 - x0) * (target - t0) / (t1 - t0));
+            }
+            cumulative += centroid.weight;
+        }
+        Some(self.max)
+    }
+
+    /// Count, mean, range and standard percentiles of the values seen
+    pub fn summary(&self) -> ObjectiveDistribution {
+        let quantile = |q| self.quantile(q).unwrap_or(0.0);
+        ObjectiveDistribution {
+            count: self.count,
+            mean: self.mean().unwrap_or(0.0),
+            min: if self.count > 0 { self.min } else { 0.0 },
+            max: if self.count > 0 { self.max } else { 0.0 },
+            p5: quantile(0.05),
+            p25: quantile(0.25),
+            p50: quantile(0.5),
+            p75: quantile(0.75),
+            p95: quantile(0.95),
+        }
+    }
+}
+
+/// Distribution of the objective over every evaluated combination
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ObjectiveDistribution {
+    pub count: u64,
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+    pub p5: f64,
+    pub p25: f64,
+    pub p50: f64,
+    pub p75: f64,
+    pub p95: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_k_and_digest() {
+        let mut top = TopK::new(3);
+        let mut digest = TDigest::default();
+        // 1..=10_000 in a scrambled order
+        for i in 0..10_000u64 {
+            let value = ((i * 7_919) % 10_000 + 1) as f64;
+            top.push(value, i);
+            digest.add(value);
+        }
+        assert_eq!(top.len(), 3);
+        assert_eq!(top.threshold(), Some(9_998.0));
+        assert_eq!(top.seen(), 10_000);
+        let best: Vec<u64> = top.into_sorted();
+        assert_eq!(best.iter().map(|i| (i * 7_919) % 10_000 + 1).collect::<Vec<_>>(), vec![10_000, 9_999, 9_998]);
+
+        let summary = digest.summary();
+        assert_eq!((summary.count, summary.min, summary.max), (10_000, 1.0, 10_000.0));
+        assert!((summary.mean - 5_000.5).abs() < 1e-6);
+        assert!((summary.p50 - 5_000.0).abs() < 50.0);
+        assert!((summary.p95 - 9_500.0).abs() < 50.0);
+        assert!(digest.centroids.len() < 1_000);
+
+        // Equal scores keep the earliest
+        let mut ties = TopK::new(1);
+        ties.push(1.0, "first");
+        assert!(!ties.push(1.0, "second"));
+        assert_eq!(ties.sorted(), vec!["first"]);
+    }
+}
@@ -0,0 +1,348 @@
+//! Two-stage optimization over subsampled sessions
+//!
+//! A first pass backtests every candidate on a slice of the sessions (every
+//! third trading day by default), which is a fraction of the cost of the full
+//! history. Only the best candidates of that pass are re-evaluated on all of
+//! the data. Each reported evaluation records which stage it came from, and
+//! the rank correlation between the stages shows how far the first pass can
+//! be trusted for this strategy.
+
+use crate::backtesting::{BacktestConfig, BacktestEngine, BacktestResult, MetricRegistry};
+use crate::data::TickData;
+use crate::optimization::{ObjectiveFunction, ParameterSet};
+use crate::strategy::Strategy;
+use crate::timestamp::{SessionCalendar, Timestamp};
+use chrono::NaiveDate;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+use tracing::info;
+
+/// Errors raised by two-stage optimization
+#[derive(Debug, thiserror::Error)]
+pub enum TwoStageError {
+    #[error("No parameter sets to evaluate")]
+    NoCandidates,
+    #[error("No ticks fall inside a trading session")]
+    NoSessions,
+    #[error("Session stride must be at least 1")]
+    InvalidStride,
+    #[error("No parameter set reached the minimum trade count on the subsample")]
+    NoSurvivors,
+}
+
+/// Two-stage optimization configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoStageConfig {
+    /// The first pass uses every `session_stride`-th session
+    #[serde(default = "default_session_stride")]
+    pub session_stride: usize,
+    /// Index of the first sampled session
+    #[serde(default)]
+    pub session_offset: usize,
+    /// Share of first-pass candidates re-evaluated on the full data
+    #[serde(default = "default_survivor_fraction")]
+    pub survivor_fraction: f64,
+    /// Re-evaluate at least this many candidates, when there are that many
+    #[serde(default = "default_min_survivors")]
+    pub min_survivors: usize,
+    #[serde(default = "default_objective")]
+    pub objective: ObjectiveFunction,
+    /// Minimum trades on the full data; the first pass requires the same
+    /// share of it as the share of sessions sampled
+    #[serde(default = "default_min_trades")]
+    pub min_trades: u32,
+    #[serde(default)]
+    pub calendar: SessionCalendar,
+}
+
+fn default_session_stride() -> usize {
+    3
+}
+
+fn default_survivor_fraction() -> f64 {
+    0.2
+}
+
+fn default_min_survivors() -> usize {
+    5
+}
+
+fn default_objective() -> ObjectiveFunction {
+    ObjectiveFunction::SharpeRatio
+}
+
+fn default_min_trades() -> u32 {
+    10
+}
+
+impl Default for TwoStageConfig {
+    fn default() -> Self {
+        Self {
+            session_stride: default_session_stride(),
+            session_offset: 0,
+            survivor_fraction: default_survivor_fraction(),
+            min_survivors: default_min_survivors(),
+            objective: default_objective(),
+            min_trades: default_min_trades(),
+            calendar: SessionCalendar::default(),
+        }
+    }
+}
+
+/// Stage an evaluation's reported performance comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvaluationStage {
+    /// Pruned after the first pass; only evaluated on the sampled sessions
+    Subsample,
+    /// Survived the first pass and was re-evaluated on all sessions
+    Full,
+}
+
+/// One candidate's evaluation with its provenance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StagedEvaluation {
+    pub parameters: ParameterSet,
+    pub stage: EvaluationStage,
+    pub subsample_objective: f64,
+    /// 1-based rank in the first pass
+    pub subsample_rank: usize,
+    /// Set for `Full` evaluations
+    pub full_objective: Option<f64>,
+    /// Performance on the data of `stage`
+    pub performance: BacktestResult,
+}
+
+impl StagedEvaluation {
+    /// Objective on the most complete data evaluated
+    pub fn objective(&self) -> f64 {
+        self.full_objective.unwrap_or(self.subsample_objective)
+    }
+}
+
+/// Outcome of a two-stage optimization
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoStageResult {
+    pub total_sessions: usize,
+    pub sampled_sessions: Vec<NaiveDate>,
+    pub candidates: usize,
+    /// Candidates that reached the minimum trade count on the subsample
+    pub first_pass_valid: usize,
+    pub survivors: usize,
+    /// Full-data evaluations by full objective, then pruned candidates by
+    /// first-pass objective
+    pub evaluations: Vec<StagedEvaluation>,
+    /// Spearman correlation of first-pass and full objectives among the
+    /// survivors; low values mean the subsample is a poor guide
+    pub stage_rank_correlation: Option<f64>,
+}
+
+impl TwoStageResult {
+    /// Best candidate evaluated on the full data
+    pub fn best(&self) -> Option<&StagedEvaluation> {
+        self.evaluations.iter().find(|e| e.stage == EvaluationStage::Full)
+    }
+}
+
+/// Runs two-stage optimization over an in-memory tick series
+pub struct TwoStageOptimizer {
+    config: TwoStageConfig,
+    backtest_config: BacktestConfig,
+    metrics: MetricRegistry,
+}
+
+impl TwoStageOptimizer {
+    pub fn new(config: TwoStageConfig, backtest_config: BacktestConfig) -> Self {
+        Self { config, backtest_config, metrics: MetricRegistry::new() }
+    }
+
+    /// Resolve `ObjectiveFunction::Metric` objectives through `metrics`
+    pub fn with_metric_registry(mut self, metrics: MetricRegistry) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Optimize `candidates` over `ticks`, which were read from `data_path`
+    pub fn run<S, F>(
+        &self,
+        strategy_factory: F,
+        candidates: &[ParameterSet],
+        data_path: &Path,
+        ticks: &[TickData],
+    ) -> Result<TwoStageResult, TwoStageError>
+    where
+        S: Strategy,
+        F: Fn(ParameterSet) -> S + Sync,
+    {
+        if candidates.is_empty() {
+            return Err(TwoStageError::NoCandidates);
+        }
+        if self.config.session_stride == 0 {
+            return Err(TwoStageError::InvalidStride);
+        }
+
+        let session_of = |tick: &TickData| self.config.calendar.trading_date(Timestamp::from_nanos(tick.timestamp));
+        let sessions: BTreeSet<NaiveDate> = ticks.iter().filter_map(session_of).collect();
+        let sampled: BTreeSet<NaiveDate> = sessions.iter()
+            .skip(self.config.session_offset)
+            .step_by(self.config.session_stride)
+            .copied()
+            .collect();
+        if sampled.is_empty() {
+            return Err(TwoStageError::NoSessions);
+        }
+        let subsample: Vec<TickData> = ticks.iter()
+            .filter(|&tick| session_of(tick).is_some_and(|date| sampled.contains(&date)))
+            .cloned()
+            .collect();
+
+        let sampled_share = sampled.len() as f64 / sessions.len() as f64;
+        let first_pass_min_trades = (self.config.min_trades as f64 * sampled_share).ceil() as u32;
+        info!("Two-stage optimization: {} candidates on {} of {} sessions ({} ticks)",
+            candidates.len(), sampled.len(), sessions.len(), subsample.len());
+
+        // First pass, best first
+        let mut first_pass: Vec<(usize, f64, BacktestResult)> = self.evaluate(
+            &strategy_factory,
+            candidates.iter().enumerate(),
+            data_path,
+            &subsample,
+            first_pass_min_trades,
+        );
+        if first_pass.is_empty() {
+            return Err(TwoStageError::NoSurvivors);
+        }
+        first_pass.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let survivors = ((first_pass.len() as f64 * self.config.survivor_fraction).ceil() as usize)
+            .max(self.config.min_survivors)
+            .min(first_pass.len());
+        let pruned = first_pass.split_off(survivors);
+
+        let full = self.evaluate(
+            &strategy_factory,
+            first_pass.iter().map(|(index, _, _)| (*index, &candidates[*index])),
+            data_path,
+            ticks,
+            self.config.min_trades,
+        );
+
+        let mut full_evaluations: Vec<StagedEvaluation> = full.into_iter()
+            .filter_map(|(index, objective, performance)| {
+                let position = first_pass.iter().position(|(i, _, _)| *i == index)?;
+                Some(StagedEvaluation {
+                    parameters: candidates[index].clone(),
+                    stage: EvaluationStage::Full,
+                    subsample_objective: first_pass[position].1,
+                    subsample_rank: position + 1,
+                    full_objective: Some(objective),
+                    performance,
+                })
+            })
+            .collect();
+        full_evaluations.sort_by(|a, b| b.objective().total_cmp(&a.objective()));
+
+        let stage_rank_correlation = (full_evaluations.len() > 2).then(|| {
+            let first: Vec<f64> = full_evaluations.iter().map(|e| e.subsample_objective).collect();
+            let second: Vec<f64> = full_evaluations.iter().map(|e| e.objective()).collect();
+            spearman(&first, &second)
+        });
+
+        let first_pass_valid = survivors + pruned.len();
+        let mut evaluations = full_evaluations;
+        evaluations.extend(pruned.into_iter().enumerate().map(|(position, (index, objective, performance))| {
+            StagedEvaluation {
+                parameters: candidates[index].clone(),
+                stage: EvaluationStage::Subsample,
+                subsample_objective: objective,
+                subsample_rank: survivors + position + 1,
+                full_objective: None,
+                performance,
+            }
+        }));
+
+        info!("Two-stage optimization: {} of {} first-pass candidates re-evaluated on full data",
+            survivors, first_pass_valid);
+        Ok(TwoStageResult {
+            total_sessions: sessions.len(),
+            sampled_sessions: sampled.into_iter().collect(),
+            candidates: candidates.len(),
+            first_pass_valid,
+            survivors,
+            evaluations,
+            stage_rank_correlation,
+        })
+    }
+
+    /// Backtest candidates in parallel, keeping those with enough trades
+    fn evaluate<'a, S, F>(
+        &self,
+        strategy_factory: &F,
+        candidates: impl Iterator<Item = (usize, &'a ParameterSet)>,
+        data_path: &Path,
+        ticks: &[TickData],
+        min_trades: u32,
+    ) -> Vec<(usize, f64, BacktestResult)>
+    where
+        S: Strategy,
+        F: Fn(ParameterSet) -> S + Sync,
+    {
+        let candidates: Vec<_> = candidates.collect();
+        candidates.into_par_iter()
+            .filter_map(|(index, parameters)| {
+                let mut strategy = strategy_factory(parameters.clone());
+                let result = BacktestEngine::new(self.backtest_config.clone())
+                    .run_loaded(&mut strategy, data_path, ticks)
+                    .ok()?;
+                (result.total_trades >= min_trades)
+                    .then(|| (index, self.config.objective.calculate_with(&result, &self.metrics), result))
+            })
+            .collect()
+    }
+}
+
+/// Spearman rank correlation, averaging the ranks of ties
+fn spearman(x: &[f64], y: &[f64]) -> f64 {
+    let (rx, ry) = (ranks(x), ranks(y));
+    let n = rx.len() as f64;
+    let mean = (n + 1.0) / 2.0;
+    let covariance: f64 = rx.iter().zip(&ry).map(|(a, b)| (a - mean) * (b - mean)).sum();
+    let spread = |r: &[f64]| r.iter().map(|v| (v - mean).powi(2)).sum::<f64>().sqrt();
+    let denominator = spread(&rx) * spread(&ry);
+    if denominator == 0.0 { 0.0 } else { covariance / denominator }
+}
+
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        let rank = (start + end + 1) as f64 / 2.0;
+        for &i in &order[start..end] {
+            ranks[i] = rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spearman_uses_ranks() {
+        // Monotone but non-linear agreement is perfect rank correlation
+        assert!((spearman(&[1.0, 2.0, 3.0, 4.0], &[1.0, 10.0, 100.0, 1000.0]) - 1.0).abs() < 1e-12);
+        assert!((spearman(&[1.0, 2.0, 3.0], &[3.0, 2.0, 1.0]) + 1.0).abs() < 1e-12);
+        assert_eq!(ranks(&[5.0, 1.0, 5.0, 2.0]), vec![3.5, 1.0, 3.5, 2.0]);
+    }
+}
@@ -0,0 +1,356 @@
+//! Iterative zoom grid search
+//!
+//! A coarse grid finds the neighbourhood of the best parameters cheaply but
+//! can only land on its own grid points. Zooming re-runs the grid search on a
+//! finer grid spanning the coarse points either side of the best one, and
+//! repeats for a set number of rounds. A best point on the edge of a round's
+//! region means the optimum may lie beyond it, so the next round moves the
+//! region over at the same resolution instead of narrowing it. A best point
+//! on the edge of the original ranges is reported, since no round searches
+//! outside them.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use tracing::info;
+
+use crate::backtesting::{BacktestConfig, MetricRegistry};
+use crate::optimization::grid_search::{GridSearchConfig, GridSearchOptimizer, ParameterRange};
+use crate::optimization::{OptimizationResult, ParameterSet};
+use crate::performance::ThreadPools;
+use crate::strategy::Strategy;
+
+/// Errors raised configuring a zoom search
+#[derive(Debug, thiserror::Error)]
+pub enum ZoomError {
+    #[error("At least {MIN_POINTS_PER_AXIS} grid points per parameter are needed, got {0}")]
+    TooFewPoints(usize),
+    #[error("Zoom span must be positive, got {0}")]
+    InvalidSpan(f64),
+    #[error("Parameter {0} has an empty range or a non-positive step")]
+    InvalidRange(String),
+}
+
+/// Fewest points a zoomed axis is searched at; fewer cannot be centered
+pub const MIN_POINTS_PER_AXIS: usize = 3;
+
+/// Zoom search configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoomConfig {
+    /// Finer grids searched after the coarse one
+    #[serde(default = "default_rounds")]
+    pub rounds: usize,
+    /// Grid points per parameter in each finer grid
+    #[serde(default = "default_points_per_axis")]
+    pub points_per_axis: usize,
+    /// Previous-round steps the next region spans either side of the best point
+    #[serde(default = "default_span_steps")]
+    pub span_steps: f64,
+    /// Smallest step, as a fraction of the coarse step; an axis at it stops narrowing
+    #[serde(default = "default_min_step_ratio")]
+    pub min_step_ratio: f64,
+    /// Stop once a round improves the best objective by less than this
+    #[serde(default)]
+    pub min_improvement: Option<f64>,
+}
+
+fn default_rounds() -> usize {
+    3
+}
+
+fn default_points_per_axis() -> usize {
+    5
+}
+
+fn default_span_steps() -> f64 {
+    1.0
+}
+
+fn default_min_step_ratio() -> f64 {
+    0.01
+}
+
+impl Default for ZoomConfig {
+    fn default() -> Self {
+        Self {
+            rounds: default_rounds(),
+            points_per_axis: default_points_per_axis(),
+            span_steps: default_span_steps(),
+            min_step_ratio: default_min_step_ratio(),
+            min_improvement: None,
+        }
+    }
+}
+
+/// How a parameter's region changed going into a round
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AxisMove {
+    /// Narrowed around the best point at a finer step
+    Narrowed,
+    /// The best point sat on an inner edge; moved over without narrowing
+    Shifted,
+    /// The best point sat on the original bound; narrowed against it
+    AtBound,
+    /// Already at the smallest step; only re-centered
+    Held,
+}
+
+/// One round of a zoom search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoomRound {
+    /// 0 for the coarse grid
+    pub round: usize,
+    pub region: BTreeMap<String, ParameterRange>,
+    /// How each parameter's region was derived; empty for the coarse grid
+    pub moves: BTreeMap<String, AxisMove>,
+    pub evaluations: usize,
+    /// Best objective evaluated in this round
+    pub best_objective: Option<f64>,
+    /// Best objective of this round over the best of the rounds before it
+    pub improvement: Option<f64>,
+}
+
+/// Outcome of a zoom search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoomResult {
+    pub rounds: Vec<ZoomRound>,
+    /// Best results over every round, best first, without repeats
+    pub results: Vec<OptimizationResult>,
+    /// Parameters whose best value lies on the edge of its original range;
+    /// widening those ranges may find better values
+    pub boundary_parameters: Vec<String>,
+}
+
+impl ZoomResult {
+    pub fn best(&self) -> Option<&OptimizationResult> {
+        self.results.first()
+    }
+}
+
+/// Runs a coarse grid search, then finer grids around the best point
+pub struct ZoomSearch {
+    grid: GridSearchConfig,
+    config: ZoomConfig,
+    metrics: MetricRegistry,
+    thread_pools: Option<ThreadPools>,
+}
+
+impl ZoomSearch {
+    /// Zoom in from the coarse grid of `grid`
+    pub fn new(grid: GridSearchConfig, config: ZoomConfig) -> Result<Self, ZoomError> {
+        if config.points_per_axis < MIN_POINTS_PER_AXIS {
+            return Err(ZoomError::TooFewPoints(config.points_per_axis));
+        }
+        if config.span_steps <= 0.0 {
+            return Err(ZoomError::InvalidSpan(config.span_steps));
+        }
+        if let Some((name, _)) = grid.parameters.iter().find(|(_, range)| range.step <= 0.0 || range.max < range.min) {
+            return Err(ZoomError::InvalidRange(name.clone()));
+        }
+        Ok(Self { grid, config, metrics: MetricRegistry::new(), thread_pools: None })
+    }
+
+    /// Resolve `ObjectiveFunction::Metric` objectives through `metrics`
+    pub fn with_metric_registry(mut self, metrics: MetricRegistry) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Evaluate every round on the engine's shared evaluation pool
+    pub fn with_thread_pools(mut self, thread_pools: ThreadPools) -> Self {
+        self.thread_pools = Some(thread_pools);
+        self
+    }
+
+    /// Run the coarse grid and up to `rounds` finer grids
+    pub async fn optimize<S, F>(
+        &self,
+        strategy_factory: F,
+        backtest_config: BacktestConfig,
+        data_path: &str,
+    ) -> Result<ZoomResult, Box<dyn std::error::Error>>
+    where
+        S: Strategy + Send + 'static,
+        F: Fn(ParameterSet) -> S + Send + Sync + 'static,
+    {
+        let strategy_factory = Arc::new(strategy_factory);
+        let mut region = self.grid.parameters.clone();
+        let mut moves = BTreeMap::new();
+        let mut rounds = Vec::new();
+        let mut results: Vec<OptimizationResult> = Vec::new();
+
+        for round in 0..=self.config.rounds {
+            let factory = Arc::clone(&strategy_factory);
+            let mut optimizer = GridSearchOptimizer::new(GridSearchConfig {
+                parameters: region.clone(),
+                ..self.grid.clone()
+            })
+            .with_metric_registry(self.metrics.clone());
+            if let Some(thread_pools) = &self.thread_pools {
+                optimizer = optimizer.with_thread_pools(thread_pools.clone());
+            }
+            let round_results = optimizer.optimize(move |p| factory(p), backtest_config.clone(), data_path).await?;
+
+            let best_before = results.first().map(|r| r.objective_value);
+            let best_objective = round_results.first().map(|r| r.objective_value);
+            let improvement = best_objective.zip(best_before).map(|(now, before)| now - before);
+            rounds.push(ZoomRound {
+                round,
+                region: region.iter().map(|(name, range)| (name.clone(), range.clone())).collect(),
+                moves: std::mem::take(&mut moves),
+                evaluations: optimizer.get_progress().evaluations,
+                best_objective,
+                improvement,
+            });
+            results = merge(results, round_results, self.grid.top_k);
+            info!("Zoom round {}: best {:?}, improvement {:?}", round, best_objective, improvement);
+
+            let Some(best) = results.first() else { break };
+            if let (Some(min), Some(improvement)) = (self.config.min_improvement, improvement) {
+                if improvement < min {
+                    break;
+                }
+            }
+            (region, moves) = self.next_region(&region, &best.parameters);
+        }
+
+        let boundary_parameters = results.first()
+            .map(|best| self.boundary_parameters(&best.parameters))
+            .unwrap_or_default();
+        Ok(ZoomResult { rounds, results, boundary_parameters })
+    }
+
+    /// The region of the next round, centered on `best`
+    ///
+    /// Parameters missing from `best` keep their region.
+    pub fn next_region(
+        &self,
+        region: &HashMap<String, ParameterRange>,
+        best: &ParameterSet,
+    ) -> (HashMap<String, ParameterRange>, BTreeMap<String, AxisMove>) {
+        let mut next = HashMap::new();
+        let mut moves = BTreeMap::new();
+        for (name, range) in region {
+            let (Some(bounds), Some(value)) = (self.grid.parameters.get(name), best.get_float(name)) else {
+                next.insert(name.clone(), range.clone());
+                continue;
+            };
+            let (zoomed, axis_move) = self.zoom_axis(range, bounds, value);
+            next.insert(name.clone(), zoomed);
+            moves.insert(name.clone(), axis_move);
+        }
+        (next, moves)
+    }
+
+    fn zoom_axis(&self, range: &ParameterRange, bounds: &ParameterRange, best: f64) -> (ParameterRange, AxisMove) {
+        let tolerance = range.step / 2.0;
+        let on_edge = best - range.min <= tolerance || range.max - best <= tolerance;
+        let on_bound = best - bounds.min <= tolerance || bounds.max - best <= tolerance;
+        let min_step = bounds.step * self.config.min_step_ratio;
+        let intervals = (self.config.points_per_axis - 1) as f64;
+
+        let (step, axis_move) = if on_edge && !on_bound {
+            (range.step, AxisMove::Shifted)
+        } else if range.step <= min_step {
+            (range.step, AxisMove::Held)
+        } else {
+            let step = (2.0 * self.config.span_steps * range.step / intervals).max(min_step);
+            (step, if on_bound { AxisMove::AtBound } else { AxisMove::Narrowed })
+        };
+
+        // Slide rather than truncate at the original bounds, so the round
+        // keeps its full number of points
+        let width = (step * intervals).min(bounds.max - bounds.min);
+        let min = (best - width / 2.0).clamp(bounds.min, bounds.max - width);
+        let zoomed = ParameterRange {
+            min,
+            // Headroom so accumulated float error does not drop the top point
+            max: min + width + step * 1e-9,
+            step,
+        };
+        (zoomed, axis_move)
+    }
+
+    /// Parameters of `best` on the edge of their original ranges
+    fn boundary_parameters(&self, best: &ParameterSet) -> Vec<String> {
+        let mut names: Vec<String> = self.grid.parameters.iter()
+            .filter(|(name, bounds)| {
+                best.get_float(name).is_some_and(|value| {
+                    let tolerance = bounds.step * self.config.min_step_ratio / 2.0;
+                    value - bounds.min <= tolerance || bounds.max - value <= tolerance
+                })
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+/// Best `keep` of two result lists, dropping re-evaluations of the same parameters
+fn merge(previous: Vec<OptimizationResult>, round: Vec<OptimizationResult>, keep: usize) -> Vec<OptimizationResult> {
+    let mut all: Vec<OptimizationResult> = previous.into_iter().chain(round).collect();
+    all.sort_by(|a, b| b.objective_value.total_cmp(&a.objective_value));
+
+    let mut seen = HashSet::new();
+    all.retain(|result| {
+        let mut key: Vec<String> = result.parameters.parameters.iter()
+            .map(|(name, value)| format!("{}={:?}", name, value))
+            .collect();
+        key.sort();
+        seen.insert(key)
+    });
+    all.truncate(keep);
+    all
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::optimization::ObjectiveFunction;
+
+    #[test]
+    fn test_region_narrows_shifts_and_respects_bounds() {
+        let grid = GridSearchConfig {
+            parameters: HashMap::from([
+                ("fast".to_string(), ParameterRange { min: 0.0, max: 100.0, step: 10.0 }),
+                ("slow".to_string(), ParameterRange { min: 0.0, max: 100.0, step: 10.0 }),
+            ]),
+            max_combinations: None,
+            early_stopping: None,
+            num_workers: 1,
+            objective: ObjectiveFunction::SharpeRatio,
+            min_trades: 0,
+            parameter_constraints: Vec::new(),
+            constraint_handling: Default::default(),
+            top_k: 10,
+        };
+        let search = ZoomSearch::new(grid.clone(), ZoomConfig::default()).unwrap();
+        let best = ParameterSet::from_hashmap(HashMap::from([("fast".to_string(), 40.0), ("slow".to_string(), 100.0)]));
+
+        // Interior best: the next grid spans the coarse neighbours 30..50
+        let (region, moves) = search.next_region(&grid.parameters, &best);
+        assert_eq!(moves["fast"], AxisMove::Narrowed);
+        let fast = &region["fast"];
+        assert_eq!((fast.min, fast.step), (30.0, 5.0));
+        assert_eq!(fast.generate_values(), vec![30.0, 35.0, 40.0, 45.0, 50.0]);
+
+        // Best on the original bound: slides inside rather than past it
+        assert_eq!(moves["slow"], AxisMove::AtBound);
+        assert_eq!((region["slow"].min, region["slow"].generate_values().len()), (80.0, 5));
+        assert_eq!(search.boundary_parameters(&best), vec!["slow".to_string()]);
+
+        // Best on an inner edge of the zoomed region: moves over at the same step
+        let edge = ParameterSet::from_hashmap(HashMap::from([("fast".to_string(), 50.0)]));
+        let (shifted, moves) = search.next_region(&region, &edge);
+        assert_eq!(moves["fast"], AxisMove::Shifted);
+        assert_eq!((shifted["fast"].min, shifted["fast"].step), (40.0, 5.0));
+        assert_eq!(shifted["slow"].min, region["slow"].min);
+
+        assert!(matches!(
+            ZoomSearch::new(grid, ZoomConfig { points_per_axis: 2, ..Default::default() }),
+            Err(ZoomError::TooFewPoints(2))
+        ));
+    }
+}
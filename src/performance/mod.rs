@@ -4,5 +4,13 @@
 //! including load testing, memory profiling, and system benchmarking.
 
 pub mod load_tests;
+pub mod reservation;
+pub mod threading;
+pub mod warm_cache;
 
-pub use load_tests::{LoadTestSuite, LoadTestResult};
\ No newline at end of file
+pub use load_tests::{LoadTestSuite, LoadTestResult};
+pub use reservation::{MemorySnapshot, ReservationError, ReservationPolicy, WorkloadClass};
+pub use threading::{
+    ConfigThroughput, PoolKind, PoolThroughput, ThreadPools, ThreadingConfig, ThreadingError, ThreadingReport,
+};
+pub use warm_cache::{BookCheckpoint, CachedDataset, DatasetCache, WarmupReport, WarmupRequest};
@@ -0,0 +1,119 @@
+//! Headroom kept for interactive work
+//!
+//! Week-long optimization sweeps would happily take every core and all the
+//! memory of the machine, leaving a researcher's single backtest or replay
+//! waiting behind them. The reservation policy holds a share of both back
+//! from batch work: the evaluation pool is sized to leave the reserved cores
+//! idle, and new batch jobs are refused while memory use is already into the
+//! reserved share. Interactive requests are never refused by the policy.
+
+use serde::{Deserialize, Serialize};
+use sysinfo::System;
+
+/// What kind of request work is done for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkloadClass {
+    /// Single quick backtests, replays and other requests a user waits on
+    Interactive,
+    /// Optimizations, sweeps and cache warm-ups
+    Batch,
+}
+
+/// Errors raised admitting work
+#[derive(Debug, thiserror::Error)]
+pub enum ReservationError {
+    #[error("Memory use of {used_bytes} bytes is past the {ceiling_bytes} bytes batch work may use")]
+    MemoryReserved { used_bytes: u64, ceiling_bytes: u64 },
+}
+
+/// Shares of the machine held back for interactive work
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReservationPolicy {
+    /// Share of cores the evaluation pool leaves free
+    #[serde(default = "default_share")]
+    pub interactive_cpu_share: f64,
+    /// Share of memory batch jobs may not start into
+    #[serde(default = "default_share")]
+    pub interactive_memory_share: f64,
+}
+
+fn default_share() -> f64 {
+    0.25
+}
+
+impl Default for ReservationPolicy {
+    fn default() -> Self {
+        Self {
+            interactive_cpu_share: default_share(),
+            interactive_memory_share: default_share(),
+        }
+    }
+}
+
+/// Memory in use on the machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemorySnapshot {
+    pub used_bytes: u64,
+    pub total_bytes: u64,
+}
+
+impl MemorySnapshot {
+    pub fn current() -> Self {
+        let mut system = System::new();
+        system.refresh_memory();
+        Self {
+            used_bytes: system.used_memory(),
+            total_bytes: system.total_memory(),
+        }
+    }
+}
+
+impl ReservationPolicy {
+    /// Cores out of `available` kept for interactive work; at least one is
+    /// always left for batch work
+    pub fn reserved_cores(&self, available: usize) -> usize {
+        let share = self.interactive_cpu_share.clamp(0.0, 1.0);
+        ((available as f64 * share).ceil() as usize).min(available.saturating_sub(1))
+    }
+
+    /// Memory batch work may bring the machine up to
+    pub fn batch_memory_ceiling(&self, total_bytes: u64) -> u64 {
+        let share = self.interactive_memory_share.clamp(0.0, 1.0);
+        (total_bytes as f64 * (1.0 - share)) as u64
+    }
+
+    /// Decide whether work of `class` may start given current memory use
+    pub fn admit(&self, class: WorkloadClass, memory: MemorySnapshot) -> Result<(), ReservationError> {
+        if class == WorkloadClass::Interactive {
+            return Ok(());
+        }
+        let ceiling_bytes = self.batch_memory_ceiling(memory.total_bytes);
+        if memory.used_bytes >= ceiling_bytes {
+            return Err(ReservationError::MemoryReserved { used_bytes: memory.used_bytes, ceiling_bytes });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_work_stays_out_of_the_reserved_share() {
+        let policy = ReservationPolicy::default();
+        assert_eq!(policy.reserved_cores(16), 4);
+        assert_eq!(policy.reserved_cores(3), 1);
+        // A single core is never reserved away from batch work
+        assert_eq!(policy.reserved_cores(1), 0);
+
+        let busy = MemorySnapshot { used_bytes: 80, total_bytes: 100 };
+        assert!(matches!(
+            policy.admit(WorkloadClass::Batch, busy),
+            Err(ReservationError::MemoryReserved { ceiling_bytes: 75, .. })
+        ));
+        assert!(policy.admit(WorkloadClass::Interactive, busy).is_ok());
+        assert!(policy.admit(WorkloadClass::Batch, MemorySnapshot { used_bytes: 50, total_bytes: 100 }).is_ok());
+    }
+}
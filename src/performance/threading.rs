@@ -0,0 +1,347 @@
+//! Engine thread pools
+//!
+//! Ingestion and strategy evaluation run on separate rayon pools so a large
+//! file load cannot starve a running optimization, or the other way round.
+//! Each pool can be given its own cores, restricted to one NUMA node and
+//! pinned core by core on Linux. Unless its size or cores are given, the
+//! evaluation pool leaves the cores of the interactive reservation free.
+//! Pools are rebuilt when the configuration changes at runtime, and the
+//! throughput measured under each configuration is kept so the effect of a
+//! change can be compared.
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use super::reservation::ReservationPolicy;
+
+/// Threading layout of the engine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThreadingConfig {
+    /// Evaluation pool size; one per evaluation core, or per available
+    /// core not reserved for interactive work, when absent
+    #[serde(default)]
+    pub evaluation_threads: Option<usize>,
+    #[serde(default = "default_ingestion_threads")]
+    pub ingestion_threads: usize,
+    /// Cores reserved for strategy evaluation
+    #[serde(default)]
+    pub evaluation_cores: Vec<usize>,
+    /// Cores reserved for data ingestion
+    #[serde(default)]
+    pub ingestion_cores: Vec<usize>,
+    /// Take evaluation cores from this NUMA node when none are listed
+    #[serde(default)]
+    pub numa_node: Option<usize>,
+    /// Pin each worker thread to one core of its pool (Linux only)
+    #[serde(default)]
+    pub pin_threads: bool,
+    /// Cores and memory held back from batch work
+    #[serde(default)]
+    pub reservation: ReservationPolicy,
+}
+
+fn default_ingestion_threads() -> usize {
+    2
+}
+
+impl Default for ThreadingConfig {
+    fn default() -> Self {
+        Self {
+            evaluation_threads: None,
+            ingestion_threads: default_ingestion_threads(),
+            evaluation_cores: Vec::new(),
+            ingestion_cores: Vec::new(),
+            numa_node: None,
+            pin_threads: false,
+            reservation: ReservationPolicy::default(),
+        }
+    }
+}
+
+/// Errors raised while building thread pools
+#[derive(Debug, thiserror::Error)]
+pub enum ThreadingError {
+    #[error("Failed to build thread pool: {0}")]
+    Build(#[from] rayon::ThreadPoolBuildError),
+    #[error("NUMA node {0} not found")]
+    UnknownNumaNode(usize),
+    #[error("Core {core} does not exist, the machine has {available}")]
+    UnknownCore { core: usize, available: usize },
+}
+
+/// Which engine pool work ran on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolKind {
+    Ingestion,
+    Evaluation,
+}
+
+/// Parse a Linux cpu list such as `0-3,8,10-11`
+fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
+    let mut cores = Vec::new();
+    for part in list.trim().split(',').filter(|part| !part.is_empty()) {
+        match part.split_once('-') {
+            Some((first, last)) => cores.extend(first.parse::<usize>().ok()?..=last.parse::<usize>().ok()?),
+            None => cores.push(part.parse().ok()?),
+        }
+    }
+    Some(cores)
+}
+
+/// Cores of a NUMA node, from sysfs
+fn numa_node_cores(node: usize) -> Result<Vec<usize>, ThreadingError> {
+    std::fs::read_to_string(format!("/sys/devices/system/node/node{}/cpulist", node))
+        .ok()
+        .and_then(|list| parse_cpu_list(&list))
+        .ok_or(ThreadingError::UnknownNumaNode(node))
+}
+
+#[cfg(target_os = "linux")]
+fn pin_current_thread(core: usize) -> std::io::Result<()> {
+    // SAFETY: the set is a plain bitmask owned by this frame and the call
+    // only changes the affinity of the calling thread
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(core, &mut set);
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin_current_thread(_core: usize) -> std::io::Result<()> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "thread pinning is only supported on Linux"))
+}
+
+fn build_pool(kind: PoolKind, threads: usize, cores: Vec<usize>, pin: bool) -> Result<ThreadPool, ThreadingError> {
+    let prefix = match kind {
+        PoolKind::Ingestion => "ingest",
+        PoolKind::Evaluation => "eval",
+    };
+    let pin = pin && !cores.is_empty();
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(threads.max(1))
+        .thread_name(move |index| format!("{}-{}", prefix, index))
+        .start_handler(move |index| {
+            if pin {
+                let core = cores[index % cores.len()];
+                if let Err(e) = pin_current_thread(core) {
+                    warn!("Could not pin {} worker {} to core {}: {}", prefix, index, core, e);
+                }
+            }
+        })
+        .build()?;
+    Ok(pool)
+}
+
+/// Work measured on one pool under one configuration
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PoolThroughput {
+    pub items: u64,
+    pub busy_secs: f64,
+}
+
+impl PoolThroughput {
+    pub fn items_per_sec(&self) -> Option<f64> {
+        (self.busy_secs > 0.0).then(|| self.items as f64 / self.busy_secs)
+    }
+}
+
+/// Throughput measured while one configuration was active
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigThroughput {
+    /// Increments each time the pools are rebuilt
+    pub generation: u64,
+    pub config: ThreadingConfig,
+    pub evaluation_threads: usize,
+    pub ingestion_threads: usize,
+    /// Cores left free for interactive work
+    pub reserved_cores: usize,
+    pub pools: BTreeMap<PoolKind, PoolThroughput>,
+}
+
+/// Current layout and the throughput of every configuration used so far
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadingReport {
+    pub current: ConfigThroughput,
+    pub history: Vec<ConfigThroughput>,
+    /// Evaluation throughput of the current configuration relative to the
+    /// previous one, in percent
+    pub evaluation_change_pct: Option<f64>,
+}
+
+struct PoolsState {
+    evaluation: Arc<ThreadPool>,
+    ingestion: Arc<ThreadPool>,
+    /// Oldest first; the last entry is the active configuration
+    history: Vec<ConfigThroughput>,
+}
+
+/// Engine thread pools, shared by the optimizers, the API and the monitor
+#[derive(Clone)]
+pub struct ThreadPools {
+    inner: Arc<RwLock<PoolsState>>,
+}
+
+impl ThreadPools {
+    pub fn new(config: ThreadingConfig) -> Result<Self, ThreadingError> {
+        let (evaluation, ingestion, entry) = Self::build(config, 0)?;
+        Ok(Self {
+            inner: Arc::new(RwLock::new(PoolsState {
+                evaluation: Arc::new(evaluation),
+                ingestion: Arc::new(ingestion),
+                history: vec![entry],
+            })),
+        })
+    }
+
+    fn build(config: ThreadingConfig, generation: u64) -> Result<(ThreadPool, ThreadPool, ConfigThroughput), ThreadingError> {
+        let available = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        if let Some(&core) = config.evaluation_cores.iter().chain(&config.ingestion_cores).find(|&&core| core >= available) {
+            return Err(ThreadingError::UnknownCore { core, available });
+        }
+
+        let evaluation_cores = match (config.evaluation_cores.is_empty(), config.numa_node) {
+            (true, Some(node)) => numa_node_cores(node)?
+                .into_iter()
+                .filter(|core| !config.ingestion_cores.contains(core))
+                .collect(),
+            _ => config.evaluation_cores.clone(),
+        };
+        // An explicitly sized or placed pool overrides the reservation
+        let reserved_cores = match (config.evaluation_threads, evaluation_cores.is_empty()) {
+            (None, true) => config.reservation.reserved_cores(available),
+            _ => 0,
+        };
+        let evaluation_threads = config.evaluation_threads.unwrap_or(if evaluation_cores.is_empty() {
+            available - reserved_cores
+        } else {
+            evaluation_cores.len()
+        });
+        let ingestion_threads = config.ingestion_threads.max(1);
+
+        let evaluation = build_pool(PoolKind::Evaluation, evaluation_threads, evaluation_cores, config.pin_threads)?;
+        let ingestion = build_pool(PoolKind::Ingestion, ingestion_threads, config.ingestion_cores.clone(), config.pin_threads)?;
+        info!("Thread pools: {} evaluation, {} ingestion threads{}",
+            evaluation.current_num_threads(),
+            ingestion.current_num_threads(),
+            if config.pin_threads { ", pinned" } else { "" });
+
+        let entry = ConfigThroughput {
+            generation,
+            evaluation_threads: evaluation.current_num_threads(),
+            ingestion_threads: ingestion.current_num_threads(),
+            reserved_cores,
+            config,
+            pools: BTreeMap::new(),
+        };
+        Ok((evaluation, ingestion, entry))
+    }
+
+    /// Rebuild the pools with a new layout
+    ///
+    /// Work already running finishes on the old pools; new work goes to the
+    /// new ones. A failed rebuild leaves the current pools in place.
+    pub fn reconfigure(&self, config: ThreadingConfig) -> Result<(), ThreadingError> {
+        let generation = self.inner.read().unwrap().history.last().map_or(0, |entry| entry.generation + 1);
+        let (evaluation, ingestion, entry) = Self::build(config, generation)?;
+        let mut state = self.inner.write().unwrap();
+        state.evaluation = Arc::new(evaluation);
+        state.ingestion = Arc::new(ingestion);
+        state.history.push(entry);
+        Ok(())
+    }
+
+    pub fn config(&self) -> ThreadingConfig {
+        self.inner.read().unwrap().history.last().map(|entry| entry.config.clone()).unwrap_or_default()
+    }
+
+    pub fn pool(&self, kind: PoolKind) -> Arc<ThreadPool> {
+        let state = self.inner.read().unwrap();
+        match kind {
+            PoolKind::Evaluation => Arc::clone(&state.evaluation),
+            PoolKind::Ingestion => Arc::clone(&state.ingestion),
+        }
+    }
+
+    /// Credit `items` processed in `elapsed` to the active configuration
+    pub fn record(&self, kind: PoolKind, items: u64, elapsed: Duration) {
+        let mut state = self.inner.write().unwrap();
+        if let Some(entry) = state.history.last_mut() {
+            let throughput = entry.pools.entry(kind).or_default();
+            throughput.items += items;
+            throughput.busy_secs += elapsed.as_secs_f64();
+        }
+    }
+
+    /// Run `work` on a pool and record the items it reports processing
+    pub fn run<R, F>(&self, kind: PoolKind, work: F) -> R
+    where
+        F: FnOnce() -> (R, u64) + Send,
+        R: Send,
+    {
+        let pool = self.pool(kind);
+        let started = std::time::Instant::now();
+        let (result, items) = pool.install(work);
+        self.record(kind, items, started.elapsed());
+        result
+    }
+
+    pub fn report(&self) -> ThreadingReport {
+        let state = self.inner.read().unwrap();
+        let current = state.history.last().cloned().expect("pools always have a configuration");
+        let rate = |entry: &ConfigThroughput| {
+            entry.pools.get(&PoolKind::Evaluation).and_then(PoolThroughput::items_per_sec)
+        };
+        let evaluation_change_pct = state.history.iter().rev().nth(1)
+            .and_then(rate)
+            .zip(rate(&current))
+            .filter(|(before, _)| *before > 0.0)
+            .map(|(before, now)| (now - before) / before * 100.0);
+
+        ThreadingReport {
+            current,
+            history: state.history.clone(),
+            evaluation_change_pct,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconfigure_tracks_throughput_per_layout() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), Some(vec![0, 1, 2, 3, 8, 10, 11]));
+        assert_eq!(parse_cpu_list("x"), None);
+
+        let pools = ThreadPools::new(ThreadingConfig {
+            evaluation_threads: Some(1),
+            ingestion_threads: 1,
+            ..Default::default()
+        }).unwrap();
+        pools.record(PoolKind::Evaluation, 100, Duration::from_secs(1));
+
+        pools.reconfigure(ThreadingConfig { evaluation_threads: Some(2), ..Default::default() }).unwrap();
+        assert_eq!(pools.pool(PoolKind::Evaluation).current_num_threads(), 2);
+        let sum = pools.run(PoolKind::Evaluation, || ((0..10).sum::<u32>(), 150));
+        assert_eq!(sum, 45);
+
+        let report = pools.report();
+        assert_eq!(report.current.generation, 1);
+        assert_eq!(report.history.len(), 2);
+        assert_eq!(report.current.pools[&PoolKind::Evaluation].items, 150);
+
+        let huge = ThreadingConfig { evaluation_cores: vec![usize::MAX], ..Default::default() };
+        assert!(matches!(pools.reconfigure(huge), Err(ThreadingError::UnknownCore { .. })));
+        assert_eq!(pools.report().history.len(), 2);
+    }
+}
@@ -0,0 +1,402 @@
+//! Dataset cache warming
+//!
+//! Large optimization runs spend their first stretch parsing the same tick
+//! files over and over. The cache keeps parsed datasets in memory, together
+//! with order book checkpoints taken every few thousand ticks, so a run can
+//! start from warm data and seek into the book without replaying from the
+//! start of the file. Warm-up can be scheduled to finish ahead of a run.
+//!
+//! The cache holds a bounded number of bytes; the least recently used
+//! datasets are dropped to make room, and a dataset larger than the whole
+//! budget is read but not kept.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+use tracing::{info, warn};
+
+use crate::data::{DataIngestionEngine, IngestionConfig, TickData};
+use crate::market::{OrderBookManager, OrderBookState, PriceLevel};
+
+/// Default number of ticks between order book checkpoints
+pub const DEFAULT_CHECKPOINT_INTERVAL: usize = 100_000;
+
+/// Bytes of parsed data the cache holds unless configured otherwise
+pub const DEFAULT_MAX_CACHE_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+
+/// Order book state after a given tick of a dataset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookCheckpoint {
+    /// Index of the last tick applied to the book
+    pub tick_index: usize,
+    /// Timestamp of that tick, nanoseconds since epoch
+    pub timestamp: i64,
+    /// Book of every contract seen by then
+    pub books: Vec<OrderBookState>,
+}
+
+/// A parsed dataset held in memory
+#[derive(Debug, Clone)]
+pub struct CachedDataset {
+    pub ticks: Arc<Vec<TickData>>,
+    /// Checkpoints in tick order
+    pub checkpoints: Arc<Vec<BookCheckpoint>>,
+    pub loaded_at: DateTime<Utc>,
+    pub load_ms: u64,
+}
+
+impl CachedDataset {
+    /// Latest checkpoint at or before `timestamp`
+    pub fn checkpoint_before(&self, timestamp: i64) -> Option<&BookCheckpoint> {
+        let idx = self.checkpoints.partition_point(|c| c.timestamp <= timestamp);
+        idx.checked_sub(1).map(|i| &self.checkpoints[i])
+    }
+
+    /// Approximate memory held by the ticks and checkpoints
+    pub fn approx_bytes(&self) -> u64 {
+        let ticks = self.ticks.len() * std::mem::size_of::<TickData>()
+            + self.ticks.iter().map(|tick| tick.contract_month.capacity()).sum::<usize>();
+        let levels: usize = self.checkpoints.iter()
+            .flat_map(|checkpoint| &checkpoint.books)
+            .map(|book| book.bids.len() + book.asks.len())
+            .sum();
+        (ticks + levels * std::mem::size_of::<PriceLevel>()) as u64
+    }
+}
+
+/// Cache summary entry for one dataset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedDatasetInfo {
+    pub path: String,
+    pub ticks: usize,
+    pub checkpoints: usize,
+    /// Approximate memory held, see `CachedDataset::approx_bytes`
+    pub bytes: u64,
+    pub loaded_at: DateTime<Utc>,
+    pub load_ms: u64,
+}
+
+/// Datasets to preload ahead of a scheduled run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmupRequest {
+    /// Data files to load; the API takes catalog ids and resolves them
+    pub datasets: Vec<String>,
+    /// Ticks between order book checkpoints
+    #[serde(default = "default_checkpoint_interval")]
+    pub checkpoint_interval: usize,
+    /// Start of the run the cache is warmed for; warm-up starts immediately when unset
+    #[serde(default)]
+    pub scheduled_for: Option<DateTime<Utc>>,
+    /// How long before `scheduled_for` warm-up starts
+    #[serde(default = "default_lead_minutes")]
+    pub lead_minutes: i64,
+}
+
+fn default_checkpoint_interval() -> usize {
+    DEFAULT_CHECKPOINT_INTERVAL
+}
+
+fn default_lead_minutes() -> i64 {
+    30
+}
+
+impl WarmupRequest {
+    /// When warm-up should begin
+    pub fn start_time(&self) -> Option<DateTime<Utc>> {
+        self.scheduled_for.map(|at| at - Duration::minutes(self.lead_minutes))
+    }
+}
+
+/// Outcome of warming one dataset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetWarmup {
+    pub path: String,
+    /// Already in the cache, nothing was loaded
+    pub already_cached: bool,
+    pub ticks: usize,
+    pub checkpoints: usize,
+    pub elapsed_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Outcome of a warm-up job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmupReport {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub datasets: Vec<DatasetWarmup>,
+}
+
+impl WarmupReport {
+    pub fn failed(&self) -> usize {
+        self.datasets.iter().filter(|d| d.error.is_some()).count()
+    }
+}
+
+/// A cached dataset and when it was last read
+#[derive(Debug, Clone)]
+struct Slot {
+    dataset: CachedDataset,
+    bytes: u64,
+    last_used: Instant,
+}
+
+/// Process-wide cache of parsed datasets shared by backtests and the API
+#[derive(Debug, Clone)]
+pub struct DatasetCache {
+    inner: Arc<RwLock<HashMap<String, Slot>>>,
+    max_bytes: u64,
+}
+
+impl Default for DatasetCache {
+    fn default() -> Self {
+        Self {
+            inner: Default::default(),
+            max_bytes: DEFAULT_MAX_CACHE_BYTES,
+        }
+    }
+}
+
+impl DatasetCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold at most `max_bytes` of parsed data
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Cached dataset for `path`, counted as used
+    pub fn get(&self, path: &str) -> Option<CachedDataset> {
+        let mut slots = self.inner.write().unwrap();
+        let slot = slots.get_mut(path)?;
+        slot.last_used = Instant::now();
+        Some(slot.dataset.clone())
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.inner.read().unwrap().contains_key(path)
+    }
+
+    /// Keep a dataset, dropping the least recently used ones to make room,
+    /// and return whether it was kept
+    pub fn insert(&self, path: impl Into<String>, dataset: CachedDataset) -> bool {
+        let path = path.into();
+        let bytes = dataset.approx_bytes();
+        if bytes > self.max_bytes {
+            warn!("Not caching {}: {} bytes exceeds the cache size of {}", path, bytes, self.max_bytes);
+            return false;
+        }
+
+        let mut slots = self.inner.write().unwrap();
+        slots.remove(&path);
+        let mut used: u64 = slots.values().map(|slot| slot.bytes).sum();
+        while used + bytes > self.max_bytes {
+            let Some(oldest) = slots.iter().min_by_key(|(_, slot)| slot.last_used).map(|(path, _)| path.clone()) else {
+                break;
+            };
+            if let Some(slot) = slots.remove(&oldest) {
+                info!("Evicted {} from the dataset cache", oldest);
+                used -= slot.bytes;
+            }
+        }
+        slots.insert(path, Slot { dataset, bytes, last_used: Instant::now() });
+        true
+    }
+
+    pub fn evict(&self, path: &str) -> bool {
+        self.inner.write().unwrap().remove(path).is_some()
+    }
+
+    pub fn clear(&self) {
+        self.inner.write().unwrap().clear();
+    }
+
+    /// Approximate memory held by all cached datasets
+    pub fn bytes(&self) -> u64 {
+        self.inner.read().unwrap().values().map(|slot| slot.bytes).sum()
+    }
+
+    pub fn list(&self) -> Vec<CachedDatasetInfo> {
+        let mut datasets: Vec<_> = self.inner.read().unwrap().iter()
+            .map(|(path, slot)| CachedDatasetInfo {
+                path: path.clone(),
+                ticks: slot.dataset.ticks.len(),
+                checkpoints: slot.dataset.checkpoints.len(),
+                bytes: slot.bytes,
+                loaded_at: slot.dataset.loaded_at,
+                load_ms: slot.dataset.load_ms,
+            })
+            .collect();
+        datasets.sort_by(|a, b| a.path.cmp(&b.path));
+        datasets
+    }
+
+    /// Cached ticks for `path`, loading and caching them on a miss
+    pub async fn get_or_load(
+        &self,
+        path: &str,
+        checkpoint_interval: usize,
+    ) -> Result<CachedDataset, Box<dyn std::error::Error>> {
+        if let Some(dataset) = self.get(path) {
+            return Ok(dataset);
+        }
+
+        let dataset = load_dataset(path, checkpoint_interval).await?;
+        self.insert(path, dataset.clone());
+        Ok(dataset)
+    }
+
+    /// Wait until the request's start time, then preload every dataset
+    pub async fn warm(&self, request: &WarmupRequest) -> WarmupReport {
+        if let Some(start) = request.start_time() {
+            if let Ok(wait) = (start - Utc::now()).to_std() {
+                info!("Cache warm-up scheduled in {}s", wait.as_secs());
+                tokio::time::sleep(wait).await;
+            }
+        }
+
+        let started_at = Utc::now();
+        let mut datasets = Vec::with_capacity(request.datasets.len());
+
+        for path in &request.datasets {
+            let start = Instant::now();
+            let already_cached = self.contains(path);
+
+            let warmup = match self.get_or_load(path, request.checkpoint_interval).await {
+                Ok(dataset) => DatasetWarmup {
+                    path: path.clone(),
+                    already_cached,
+                    ticks: dataset.ticks.len(),
+                    checkpoints: dataset.checkpoints.len(),
+                    elapsed_ms: start.elapsed().as_millis() as u64,
+                    error: None,
+                },
+                Err(e) => {
+                    warn!("Failed to warm {}: {}", path, e);
+                    DatasetWarmup {
+                        path: path.clone(),
+                        already_cached: false,
+                        ticks: 0,
+                        checkpoints: 0,
+                        elapsed_ms: start.elapsed().as_millis() as u64,
+                        error: Some(e.to_string()),
+                    }
+                }
+            };
+            datasets.push(warmup);
+        }
+
+        WarmupReport {
+            started_at,
+            finished_at: Utc::now(),
+            datasets,
+        }
+    }
+}
+
+/// Parse a dataset and build its order book checkpoints
+async fn load_dataset(
+    path: &str,
+    checkpoint_interval: usize,
+) -> Result<CachedDataset, Box<dyn std::error::Error>> {
+    let start = Instant::now();
+    let mut engine = DataIngestionEngine::new(IngestionConfig::default());
+    let ticks = engine.ingest_file(path).await?;
+    let checkpoints = build_checkpoints(&ticks, checkpoint_interval);
+
+    info!("Cached {} ticks and {} checkpoints from {}", ticks.len(), checkpoints.len(), path);
+
+    Ok(CachedDataset {
+        ticks: Arc::new(ticks),
+        checkpoints: Arc::new(checkpoints),
+        loaded_at: Utc::now(),
+        load_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+/// Replay ticks through a book per contract, snapshotting them every `interval` ticks
+pub fn build_checkpoints(ticks: &[TickData], interval: usize) -> Vec<BookCheckpoint> {
+    let interval = interval.max(1);
+    let mut books = OrderBookManager::new(false);
+    let mut checkpoints = Vec::with_capacity(ticks.len() / interval + 1);
+
+    for (index, tick) in ticks.iter().enumerate() {
+        books.process_tick(tick);
+        if (index + 1) % interval == 0 {
+            checkpoints.push(BookCheckpoint {
+                tick_index: index,
+                timestamp: tick.timestamp,
+                books: books.states(),
+            });
+        }
+    }
+
+    checkpoints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::types::OrderBookOperation;
+    use crate::data::{DataLevel, MarketDataType};
+    use rust_decimal::Decimal;
+
+    fn bid(contract: &str, timestamp: i64, price: i64) -> TickData {
+        TickData::new(DataLevel::L2, MarketDataType::BidQuote, timestamp, Decimal::new(price, 2), 5, contract.to_string())
+            .with_l2_data(OrderBookOperation::Add, 0)
+    }
+
+    fn dataset(ticks: Vec<TickData>) -> CachedDataset {
+        let checkpoints = build_checkpoints(&ticks, 2);
+        CachedDataset {
+            ticks: Arc::new(ticks),
+            checkpoints: Arc::new(checkpoints),
+            loaded_at: Utc::now(),
+            load_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_checkpoints_keep_a_book_per_contract() {
+        let cached = dataset(vec![
+            bid("0624", 10, 1850000),
+            bid("0924", 20, 1870000),
+            bid("0624", 30, 1850025),
+            bid("0924", 40, 1870025),
+        ]);
+
+        assert_eq!(cached.checkpoints.len(), 2);
+        assert!(cached.checkpoint_before(19).is_none());
+        let checkpoint = cached.checkpoint_before(39).unwrap();
+        assert_eq!(checkpoint.tick_index, 1);
+        let mut contracts: Vec<_> = checkpoint.books.iter().map(|book| book.contract.as_str()).collect();
+        contracts.sort();
+        assert_eq!(contracts, ["0624", "0924"]);
+        assert_eq!(cached.checkpoint_before(i64::MAX).unwrap().tick_index, 3);
+    }
+
+    #[test]
+    fn test_cache_drops_the_least_recently_used_dataset_to_fit() {
+        let one = dataset(vec![bid("0624", 10, 1850000)]);
+        let size = one.approx_bytes();
+        let cache = DatasetCache::new().with_max_bytes(size * 2);
+
+        assert!(cache.insert("a", one.clone()));
+        assert!(cache.insert("b", one.clone()));
+        // Reading `a` makes `b` the one to go
+        assert!(cache.get("a").is_some());
+        assert!(cache.insert("c", one.clone()));
+        assert!(cache.contains("a") && cache.contains("c") && !cache.contains("b"));
+        assert!(cache.bytes() <= size * 2);
+
+        // Too large to keep at all
+        let tiny = DatasetCache::new().with_max_bytes(size - 1);
+        assert!(!tiny.insert("a", one));
+        assert!(tiny.list().is_empty());
+    }
+}
@@ -0,0 +1,213 @@
+//! Arrow output for large analytics tables
+//!
+//! Trade ledgers, equity curves and optimization evaluations can reach
+//! millions of rows. Building Arrow `RecordBatch`es directly (and writing
+//! them as Arrow IPC / Feather v2 files) lets Polars or pandas memory-map
+//! the results instead of parsing JSON.
+
+use arrow::array::{
+    ArrayRef, Decimal128Array, Float64Array, Int32Array, StringArray,
+    TimestampNanosecondArray, UInt32Array,
+};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use arrow::error::ArrowError;
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::backtesting::metrics::TradeRecord;
+use crate::optimization::OptimizationResult;
+
+/// Precision/scale used for price columns, matching the source tick schema
+const PRICE_PRECISION: u8 = 18;
+const PRICE_SCALE: i8 = 2;
+
+fn timestamp_type() -> DataType {
+    DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into()))
+}
+
+fn price_type() -> DataType {
+    DataType::Decimal128(PRICE_PRECISION, PRICE_SCALE)
+}
+
+fn to_nanos(timestamp: &DateTime<Utc>) -> i64 {
+    timestamp.timestamp_nanos_opt().unwrap_or(0)
+}
+
+/// Fixed-point representation of a price at `PRICE_SCALE` decimals
+fn to_decimal128(value: Decimal) -> i128 {
+    let mut value = value.round_dp(PRICE_SCALE as u32);
+    value.rescale(PRICE_SCALE as u32);
+    value.mantissa()
+}
+
+fn decimal_column(values: impl Iterator<Item = Decimal>) -> Result<ArrayRef, ArrowError> {
+    let array = Decimal128Array::from_iter_values(values.map(to_decimal128))
+        .with_precision_and_scale(PRICE_PRECISION, PRICE_SCALE)?;
+    Ok(Arc::new(array))
+}
+
+fn timestamp_column(values: impl Iterator<Item = i64>) -> ArrayRef {
+    Arc::new(TimestampNanosecondArray::from_iter_values(values).with_timezone("UTC"))
+}
+
+/// Schema of the trade ledger table
+pub fn trade_ledger_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("timestamp", timestamp_type(), false),
+        Field::new("side", DataType::Utf8, false),
+        Field::new("quantity", DataType::Int32, false),
+        Field::new("price", price_type(), false),
+        Field::new("commission", price_type(), false),
+        Field::new("slippage", price_type(), false),
+    ])
+}
+
+/// Build a record batch from a trade ledger
+pub fn trades_to_record_batch(trades: &[TradeRecord]) -> Result<RecordBatch, ArrowError> {
+    let columns: Vec<ArrayRef> = vec![
+        timestamp_column(trades.iter().map(|t| to_nanos(&t.timestamp))),
+        Arc::new(StringArray::from_iter_values(trades.iter().map(|t| format!("{:?}", t.side)))),
+        Arc::new(Int32Array::from_iter_values(trades.iter().map(|t| t.quantity))),
+        decimal_column(trades.iter().map(|t| t.price))?,
+        decimal_column(trades.iter().map(|t| t.commission))?,
+        decimal_column(trades.iter().map(|t| t.slippage))?,
+    ];
+
+    RecordBatch::try_new(Arc::new(trade_ledger_schema()), columns)
+}
+
+/// Schema of the equity curve table
+pub fn equity_curve_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("timestamp", timestamp_type(), false),
+        Field::new("equity", price_type(), false),
+    ])
+}
+
+/// Build a record batch from an equity curve
+pub fn equity_curve_to_record_batch(
+    equity_curve: &[(DateTime<Utc>, Decimal)],
+) -> Result<RecordBatch, ArrowError> {
+    let columns: Vec<ArrayRef> = vec![
+        timestamp_column(equity_curve.iter().map(|(ts, _)| to_nanos(ts))),
+        decimal_column(equity_curve.iter().map(|(_, equity)| *equity))?,
+    ];
+
+    RecordBatch::try_new(Arc::new(equity_curve_schema()), columns)
+}
+
+/// Build a record batch of optimization evaluations
+///
+/// Every parameter seen in any evaluation becomes a nullable `param_<name>`
+/// column, followed by the objective value and headline backtest metrics.
+pub fn optimization_results_to_record_batch(
+    results: &[OptimizationResult],
+) -> Result<RecordBatch, ArrowError> {
+    let parameter_names: BTreeSet<&String> = results.iter()
+        .flat_map(|r| r.parameters.parameters.keys())
+        .collect();
+
+    let mut fields = vec![Field::new("timestamp", timestamp_type(), false)];
+    let mut columns: Vec<ArrayRef> = vec![
+        timestamp_column(results.iter().map(|r| to_nanos(&r.timestamp))),
+    ];
+
+    for name in &parameter_names {
+        fields.push(Field::new(format!("param_{}", name), DataType::Float64, true));
+        columns.push(Arc::new(Float64Array::from(
+            results.iter()
+                .map(|r| r.parameters.get_float(name))
+                .collect::<Vec<_>>(),
+        )));
+    }
+
+    fields.extend([
+        Field::new("objective_value", DataType::Float64, false),
+        Field::new("total_pnl", DataType::Float64, false),
+        Field::new("total_trades", DataType::UInt32, false),
+        Field::new("win_rate", DataType::Float64, false),
+        Field::new("sharpe_ratio", DataType::Float64, false),
+        Field::new("max_drawdown", DataType::Float64, false),
+        Field::new("profit_factor", DataType::Float64, false),
+    ]);
+
+    let backtests = || results.iter().map(|r| &r.backtest_result);
+    columns.extend([
+        Arc::new(Float64Array::from_iter_values(results.iter().map(|r| r.objective_value))) as ArrayRef,
+        Arc::new(Float64Array::from_iter_values(backtests().map(|b| b.total_pnl.to_f64().unwrap_or(0.0)))),
+        Arc::new(UInt32Array::from_iter_values(backtests().map(|b| b.total_trades))),
+        Arc::new(Float64Array::from_iter_values(backtests().map(|b| b.win_rate))),
+        Arc::new(Float64Array::from_iter_values(backtests().map(|b| b.sharpe_ratio))),
+        Arc::new(Float64Array::from_iter_values(backtests().map(|b| b.max_drawdown.to_f64().unwrap_or(0.0)))),
+        Arc::new(Float64Array::from_iter_values(backtests().map(|b| b.profit_factor))),
+    ]);
+
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+}
+
+/// Write record batches to an Arrow IPC file (readable as Feather v2)
+pub fn write_ipc_file<P: AsRef<Path>>(path: P, batches: &[RecordBatch]) -> Result<(), ArrowError> {
+    let first = batches.first()
+        .ok_or_else(|| ArrowError::InvalidArgumentError("no record batches to write".to_string()))?;
+
+    let file = File::create(path)?;
+    let mut writer = FileWriter::try_new(file, &first.schema())?;
+    for batch in batches {
+        writer.write(batch)?;
+    }
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::OrderSide;
+    use arrow::array::Array;
+    use arrow::ipc::reader::FileReader;
+    use chrono::{Duration, TimeZone};
+
+    #[test]
+    fn test_ledger_batches_round_trip_through_ipc() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 12, 14, 30, 0).unwrap();
+        let trades: Vec<TradeRecord> = (0..4)
+            .map(|i| TradeRecord {
+                timestamp: start + Duration::seconds(i),
+                side: if i % 2 == 0 { OrderSide::Buy } else { OrderSide::Sell },
+                quantity: 1,
+                price: Decimal::new(1_800_025 + i, 2),
+                commission: Decimal::new(62, 2),
+                slippage: Decimal::ZERO,
+                tag: None,
+            })
+            .collect();
+
+        let batch = trades_to_record_batch(&trades).unwrap();
+        assert_eq!(batch.num_rows(), 4);
+        assert_eq!(batch.schema().as_ref(), &trade_ledger_schema());
+        let prices = batch.column(3).as_any().downcast_ref::<Decimal128Array>().unwrap();
+        assert_eq!(prices.value(1), 1_800_026);
+
+        let curve = vec![(start, Decimal::from(10_000)), (start + Duration::minutes(1), Decimal::new(1_001_250, 2))];
+        let equity = equity_curve_to_record_batch(&curve).unwrap();
+        assert_eq!(equity.num_rows(), 2);
+
+        let path = std::env::temp_dir().join(format!("ledger-{}.arrow", uuid::Uuid::new_v4()));
+        write_ipc_file(&path, &[batch.clone(), batch]).unwrap();
+        let read: Vec<RecordBatch> = FileReader::try_new(File::open(&path).unwrap(), None)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(read.iter().map(RecordBatch::num_rows).sum::<usize>(), 8);
+        assert!(read[0].column(0).null_count() == 0);
+        let _ = std::fs::remove_file(path);
+
+        assert!(write_ipc_file(std::env::temp_dir().join("empty.arrow"), &[]).is_err());
+    }
+}
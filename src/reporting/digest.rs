@@ -0,0 +1,341 @@
+//! Daily digest of an always-on research server
+//!
+//! Summarises one period, normally the last day: how many jobs ran and how
+//! they ended, the best backtest and optimization results found, the
+//! datasets ingested and anything that went wrong. The API compiles it on a
+//! schedule and sends it through the configured notification channels.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+use super::notify::Notification;
+use crate::monitoring::ResourceUsage;
+
+/// Jobs of one kind that ended in the period
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct JobTally {
+    pub kind: String,
+    pub completed: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+    /// Wall-clock time of every job of the kind
+    pub total_secs: f64,
+}
+
+impl JobTally {
+    pub fn total(&self) -> usize {
+        self.completed + self.failed + self.cancelled
+    }
+}
+
+/// A backtest that finished in the period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DigestBacktest {
+    pub id: String,
+    pub strategy_id: String,
+    pub workspace_id: String,
+    pub total_return: f64,
+    pub sharpe_ratio: f64,
+    pub max_drawdown: f64,
+    pub total_trades: u32,
+}
+
+/// An optimization that evaluated parameter sets in the period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DigestOptimization {
+    pub optimization_id: String,
+    pub strategy_id: String,
+    /// Evaluations made in the period
+    pub evaluations: usize,
+    pub best_objective: f64,
+    pub best_parameters: serde_json::Value,
+}
+
+/// A dataset ingested in the period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DigestDataset {
+    pub id: String,
+    pub trade_date: Option<NaiveDate>,
+    pub contracts: Vec<String>,
+    pub ticks: usize,
+    pub ticks_dropped: usize,
+    pub ticks_flagged: usize,
+}
+
+/// How much an incident needs attention
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IncidentSeverity {
+    Warning,
+    Critical,
+}
+
+/// Something in the period that an operator should look at
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Incident {
+    pub severity: IncidentSeverity,
+    /// What raised it, e.g. `job`, `ingestion` or `resources`
+    pub source: String,
+    pub message: String,
+    pub at: Option<DateTime<Utc>>,
+}
+
+impl Incident {
+    pub fn warning(source: impl Into<String>, message: impl Into<String>, at: Option<DateTime<Utc>>) -> Self {
+        Self { severity: IncidentSeverity::Warning, source: source.into(), message: message.into(), at }
+    }
+
+    pub fn critical(source: impl Into<String>, message: impl Into<String>, at: Option<DateTime<Utc>>) -> Self {
+        Self { severity: IncidentSeverity::Critical, source: source.into(), message: message.into(), at }
+    }
+}
+
+/// One period of activity on the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyDigest {
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub generated_at: DateTime<Utc>,
+    /// Workspace the digest covers; every workspace when unset
+    pub workspace_id: Option<String>,
+    pub jobs: Vec<JobTally>,
+    /// Best finished backtests by Sharpe ratio
+    pub best_backtests: Vec<DigestBacktest>,
+    /// Optimizations by best objective value
+    pub optimizations: Vec<DigestOptimization>,
+    pub datasets: Vec<DigestDataset>,
+    /// Most severe first
+    pub incidents: Vec<Incident>,
+    /// Host resources when the digest was compiled
+    pub resources: Option<ResourceUsage>,
+}
+
+impl DailyDigest {
+    pub fn new(period_start: DateTime<Utc>, period_end: DateTime<Utc>) -> Self {
+        Self {
+            period_start,
+            period_end,
+            generated_at: Utc::now(),
+            workspace_id: None,
+            jobs: Vec::new(),
+            best_backtests: Vec::new(),
+            optimizations: Vec::new(),
+            datasets: Vec::new(),
+            incidents: Vec::new(),
+            resources: None,
+        }
+    }
+
+    /// Count a finished job; `outcome` is `completed`, `failed` or `cancelled`
+    pub fn record_job(&mut self, kind: &str, outcome: &str, duration_secs: f64) {
+        let index = match self.jobs.iter().position(|tally| tally.kind == kind) {
+            Some(index) => index,
+            None => {
+                self.jobs.push(JobTally { kind: kind.to_string(), ..Default::default() });
+                self.jobs.len() - 1
+            }
+        };
+        let tally = &mut self.jobs[index];
+        match outcome {
+            "failed" => tally.failed += 1,
+            "cancelled" => tally.cancelled += 1,
+            _ => tally.completed += 1,
+        }
+        tally.total_secs += duration_secs;
+    }
+
+    /// Order each section, keep the `top` best results and add incidents
+    /// for strained host resources
+    pub fn finalize(&mut self, top: usize) {
+        self.jobs.sort_by(|a, b| a.kind.cmp(&b.kind));
+        self.best_backtests.sort_by(|a, b| b.sharpe_ratio.total_cmp(&a.sharpe_ratio));
+        self.best_backtests.truncate(top);
+        self.optimizations.sort_by(|a, b| b.best_objective.total_cmp(&a.best_objective));
+        self.optimizations.truncate(top);
+        self.datasets.sort_by(|a, b| a.trade_date.cmp(&b.trade_date).then_with(|| a.id.cmp(&b.id)));
+
+        if let Some(resources) = &self.resources {
+            if resources.memory_percent >= 90.0 {
+                self.incidents.push(Incident::warning(
+                    "resources",
+                    format!("Memory at {:.0}% ({:.1} GB)", resources.memory_percent, resources.memory_gb),
+                    Some(self.generated_at),
+                ));
+            }
+            if resources.cpu_percent >= 95.0 {
+                self.incidents.push(Incident::warning(
+                    "resources",
+                    format!("CPU at {:.0}%", resources.cpu_percent),
+                    Some(self.generated_at),
+                ));
+            }
+        }
+        self.incidents.sort_by(|a, b| b.severity.cmp(&a.severity).then_with(|| a.at.cmp(&b.at)));
+    }
+
+    pub fn jobs_run(&self) -> usize {
+        self.jobs.iter().map(JobTally::total).sum()
+    }
+
+    pub fn subject(&self) -> String {
+        let mut subject = format!(
+            "Strategy Lab digest {}: {} jobs, {} datasets",
+            self.period_end.format("%Y-%m-%d"),
+            self.jobs_run(),
+            self.datasets.len()
+        );
+        if !self.incidents.is_empty() {
+            let _ = write!(subject, ", {} incidents", self.incidents.len());
+        }
+        subject
+    }
+
+    /// Render the digest as Markdown
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "Period: {} to {} UTC",
+            self.period_start.format("%Y-%m-%d %H:%M"),
+            self.period_end.format("%Y-%m-%d %H:%M")
+        );
+        if let Some(workspace) = &self.workspace_id {
+            let _ = writeln!(out, "Workspace: {}", workspace);
+        }
+
+        let _ = writeln!(out, "\n## Jobs\n");
+        if self.jobs.is_empty() {
+            let _ = writeln!(out, "No jobs finished.");
+        } else {
+            let _ = writeln!(out, "| Kind | Completed | Failed | Cancelled | Hours |");
+            let _ = writeln!(out, "|---|---:|---:|---:|---:|");
+            for tally in &self.jobs {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {} | {} | {:.1} |",
+                    tally.kind, tally.completed, tally.failed, tally.cancelled, tally.total_secs / 3600.0
+                );
+            }
+        }
+
+        let _ = writeln!(out, "\n## Best results\n");
+        if self.best_backtests.is_empty() && self.optimizations.is_empty() {
+            let _ = writeln!(out, "No results.");
+        }
+        for backtest in &self.best_backtests {
+            let _ = writeln!(
+                out,
+                "- Backtest {} of {}: Sharpe {:.2}, return {:.2}%, max drawdown {:.2}%, {} trades",
+                backtest.id,
+                backtest.strategy_id,
+                backtest.sharpe_ratio,
+                backtest.total_return * 100.0,
+                backtest.max_drawdown * 100.0,
+                backtest.total_trades
+            );
+        }
+        for optimization in &self.optimizations {
+            let _ = writeln!(
+                out,
+                "- Optimization {} of {}: best objective {:.4} over {} evaluations, parameters {}",
+                optimization.optimization_id,
+                optimization.strategy_id,
+                optimization.best_objective,
+                optimization.evaluations,
+                optimization.best_parameters
+            );
+        }
+
+        let _ = writeln!(out, "\n## Data ingested\n");
+        if self.datasets.is_empty() {
+            let _ = writeln!(out, "No datasets ingested.");
+        }
+        for dataset in &self.datasets {
+            let trade_date = dataset.trade_date.map(|date| date.to_string()).unwrap_or_else(|| "undated".to_string());
+            let _ = writeln!(
+                out,
+                "- {} ({}, {}): {} ticks, {} dropped, {} flagged",
+                dataset.id,
+                trade_date,
+                dataset.contracts.join(", "),
+                dataset.ticks,
+                dataset.ticks_dropped,
+                dataset.ticks_flagged
+            );
+        }
+
+        let _ = writeln!(out, "\n## System health\n");
+        if let Some(resources) = &self.resources {
+            let _ = writeln!(
+                out,
+                "CPU {:.0}%, memory {:.1} GB ({:.0}%), {} threads",
+                resources.cpu_percent, resources.memory_gb, resources.memory_percent, resources.active_threads
+            );
+        }
+        if self.incidents.is_empty() {
+            let _ = writeln!(out, "No incidents.");
+        }
+        for incident in &self.incidents {
+            let severity = match incident.severity {
+                IncidentSeverity::Critical => "CRITICAL",
+                IncidentSeverity::Warning => "warning",
+            };
+            let at = incident.at.map(|at| format!(" at {}", at.format("%H:%M"))).unwrap_or_default();
+            let _ = writeln!(out, "- [{}] {}{}: {}", severity, incident.source, at, incident.message);
+        }
+        out
+    }
+
+    /// The digest as a notification, with the full digest as its payload
+    pub fn to_notification(&self) -> Notification {
+        let notification = Notification::new(self.subject(), self.to_markdown());
+        match serde_json::to_value(self) {
+            Ok(payload) => notification.with_payload(payload),
+            Err(_) => notification,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_digest_orders_and_renders() {
+        let end = Utc::now();
+        let mut digest = DailyDigest::new(end - Duration::days(1), end);
+        digest.record_job("optimization", "completed", 3600.0);
+        digest.record_job("backtest", "completed", 30.0);
+        digest.record_job("backtest", "failed", 5.0);
+        for (id, sharpe) in [("a", 0.8), ("b", 2.1), ("c", 1.4)] {
+            digest.best_backtests.push(DigestBacktest {
+                id: id.to_string(),
+                strategy_id: "mean_reversion".to_string(),
+                workspace_id: "default".to_string(),
+                total_return: 0.05,
+                sharpe_ratio: sharpe,
+                max_drawdown: 0.02,
+                total_trades: 40,
+            });
+        }
+        digest.incidents.push(Incident::warning("job", "Backtest a failed", Some(end)));
+        digest.incidents.push(Incident::critical("ingestion", "mnq_20240612 dropped every tick", None));
+        digest.finalize(2);
+
+        assert_eq!(digest.jobs_run(), 3);
+        assert_eq!(digest.jobs[0].kind, "backtest");
+        assert_eq!((digest.jobs[0].completed, digest.jobs[0].failed), (1, 1));
+        let ids: Vec<&str> = digest.best_backtests.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ids, ["b", "c"]);
+        assert_eq!(digest.incidents[0].severity, IncidentSeverity::Critical);
+        assert!(digest.subject().ends_with("3 jobs, 0 datasets, 2 incidents"));
+
+        let markdown = digest.to_markdown();
+        assert!(markdown.contains("| backtest | 1 | 1 | 0 | 0.0 |"));
+        assert!(markdown.contains("Backtest b of mean_reversion: Sharpe 2.10"));
+        assert!(markdown.contains("No datasets ingested."));
+        assert!(digest.to_notification().payload.is_some());
+    }
+}
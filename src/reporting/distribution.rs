@@ -0,0 +1,259 @@
+//! Trade-level distribution statistics
+//!
+//! Averages hide how a strategy makes its money. These statistics describe
+//! the shape of the per-trade P&L: expectancy and how precisely it is known,
+//! skew and fat tails, percentiles, and the same distribution in R-multiples
+//! (P&L divided by the risk taken on the trade).
+
+use serde::{Deserialize, Serialize};
+
+/// Percentiles reported in the P&L table
+pub const TRADE_PERCENTILES: [f64; 9] = [0.01, 0.05, 0.10, 0.25, 0.50, 0.75, 0.90, 0.95, 0.99];
+
+/// Width of an R-multiple histogram bucket
+const R_BUCKET_WIDTH: f64 = 0.5;
+
+/// Largest R-multiple given its own histogram bucket
+const R_HISTOGRAM_LIMIT: f64 = 10.0;
+
+/// One closed trade
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TradeOutcome {
+    pub pnl: f64,
+    /// Amount at risk when the trade was opened, e.g. the distance to its stop
+    #[serde(default)]
+    pub initial_risk: Option<f64>,
+}
+
+/// What one R, the unit of risk, is taken to be
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum InitialRisk {
+    /// The same amount for every trade, e.g. a fixed stop times the tick value
+    Fixed(f64),
+    /// Each trade's own `initial_risk`; trades without one are left out
+    PerTrade,
+    /// The average losing trade, for strategies without hard stops
+    #[default]
+    AverageLoss,
+}
+
+/// A row of the percentile table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PercentileRow {
+    pub percentile: f64,
+    pub pnl: f64,
+    /// `None` when no R-multiples could be computed
+    pub r_multiple: Option<f64>,
+}
+
+/// Count of trades with an R-multiple in `[lower, upper)`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RBucket {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+}
+
+/// Trade P&L expressed in units of initial risk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RMultipleStats {
+    pub risk_definition: InitialRisk,
+    /// Trades with a usable risk amount
+    pub trades: usize,
+    /// Expectancy in R
+    pub expectancy: f64,
+    pub std_dev: f64,
+    pub best: f64,
+    pub worst: f64,
+    pub histogram: Vec<RBucket>,
+}
+
+/// Distribution of per-trade P&L
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TradeDistribution {
+    pub trades: usize,
+    /// Mean P&L per trade
+    pub expectancy: f64,
+    /// Standard error of the expectancy
+    pub expectancy_std_error: f64,
+    /// Expectancy divided by its standard error
+    pub expectancy_t_stat: f64,
+    pub std_dev: f64,
+    pub skewness: f64,
+    /// Kurtosis minus 3, so a normal distribution scores 0
+    pub excess_kurtosis: f64,
+    pub percentiles: Vec<PercentileRow>,
+    pub r_multiples: Option<RMultipleStats>,
+}
+
+impl TradeDistribution {
+    /// Compute the distribution of `trades`, measuring R with `risk`
+    pub fn from_trades(trades: &[TradeOutcome], risk: InitialRisk) -> Self {
+        let pnls: Vec<f64> = trades.iter().map(|t| t.pnl).collect();
+        let Some(moments) = Moments::of(&pnls) else {
+            return Self::default();
+        };
+
+        let n = pnls.len() as f64;
+        let expectancy_std_error = if pnls.len() > 1 { moments.sample_std_dev / n.sqrt() } else { 0.0 };
+        let expectancy_t_stat = if expectancy_std_error > 0.0 { moments.mean / expectancy_std_error } else { 0.0 };
+
+        let r_values = r_multiples(trades, risk);
+        let r_moments = Moments::of(&r_values);
+        let percentiles = TRADE_PERCENTILES.iter()
+            .map(|&q| PercentileRow {
+                percentile: q * 100.0,
+                pnl: percentile(&moments.sorted, q),
+                r_multiple: r_moments.as_ref().map(|r| percentile(&r.sorted, q)),
+            })
+            .collect();
+        let r_multiples = r_moments.map(|r| RMultipleStats {
+            risk_definition: risk,
+            trades: r_values.len(),
+            expectancy: r.mean,
+            std_dev: r.sample_std_dev,
+            best: r.sorted[r.sorted.len() - 1],
+            worst: r.sorted[0],
+            histogram: histogram(&r.sorted),
+        });
+
+        Self {
+            trades: pnls.len(),
+            expectancy: moments.mean,
+            expectancy_std_error,
+            expectancy_t_stat,
+            std_dev: moments.sample_std_dev,
+            skewness: moments.skewness,
+            excess_kurtosis: moments.excess_kurtosis,
+            percentiles,
+            r_multiples,
+        }
+    }
+}
+
+struct Moments {
+    mean: f64,
+    sample_std_dev: f64,
+    skewness: f64,
+    excess_kurtosis: f64,
+    sorted: Vec<f64>,
+}
+
+impl Moments {
+    fn of(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let m2 = values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+        let m3 = values.iter().map(|x| (x - mean).powi(3)).sum::<f64>() / n;
+        let m4 = values.iter().map(|x| (x - mean).powi(4)).sum::<f64>() / n;
+
+        // Shape is undefined when every trade made the same amount
+        let (skewness, excess_kurtosis) = if m2 > 0.0 {
+            (m3 / m2.powf(1.5), m4 / (m2 * m2) - 3.0)
+        } else {
+            (0.0, 0.0)
+        };
+        let sample_std_dev = if values.len() > 1 { (m2 * n / (n - 1.0)).sqrt() } else { 0.0 };
+
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        Some(Self { mean, sample_std_dev, skewness, excess_kurtosis, sorted })
+    }
+}
+
+fn r_multiples(trades: &[TradeOutcome], risk: InitialRisk) -> Vec<f64> {
+    match risk {
+        InitialRisk::Fixed(amount) if amount > 0.0 => {
+            trades.iter().map(|t| t.pnl / amount).collect()
+        }
+        InitialRisk::Fixed(_) => Vec::new(),
+        InitialRisk::PerTrade => trades.iter()
+            .filter_map(|t| t.initial_risk.filter(|r| *r > 0.0).map(|r| t.pnl / r))
+            .collect(),
+        InitialRisk::AverageLoss => {
+            let losses: Vec<f64> = trades.iter().filter(|t| t.pnl < 0.0).map(|t| -t.pnl).collect();
+            if losses.is_empty() {
+                return Vec::new();
+            }
+            let average_loss = losses.iter().sum::<f64>() / losses.len() as f64;
+            trades.iter().map(|t| t.pnl / average_loss).collect()
+        }
+    }
+}
+
+/// Half-R buckets spanning the sorted values
+///
+/// R-multiples beyond ±`R_HISTOGRAM_LIMIT` are counted in the outermost buckets.
+fn histogram(sorted: &[f64]) -> Vec<RBucket> {
+    let (Some(&first), Some(&last)) = (sorted.first(), sorted.last()) else {
+        return Vec::new();
+    };
+    let max_index = (R_HISTOGRAM_LIMIT / R_BUCKET_WIDTH) as i64;
+    let index = |r: f64| ((r / R_BUCKET_WIDTH).floor() as i64).clamp(-max_index, max_index - 1);
+    let first_index = index(first);
+
+    let mut buckets: Vec<RBucket> = (first_index..=index(last))
+        .map(|i| RBucket {
+            lower: i as f64 * R_BUCKET_WIDTH,
+            upper: (i + 1) as f64 * R_BUCKET_WIDTH,
+            count: 0,
+        })
+        .collect();
+    for &r in sorted {
+        buckets[(index(r) - first_index) as usize].count += 1;
+    }
+    buckets
+}
+
+/// Linear-interpolated percentile of sorted values
+fn percentile(sorted: &[f64], q: f64) -> f64 {
+    let rank = q * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(pnl: f64, initial_risk: Option<f64>) -> TradeOutcome {
+        TradeOutcome { pnl, initial_risk }
+    }
+
+    #[test]
+    fn test_trade_distribution() {
+        let trades = [
+            trade(-50.0, Some(50.0)),
+            trade(-50.0, Some(50.0)),
+            trade(100.0, Some(50.0)),
+            trade(150.0, None),
+        ];
+
+        let distribution = TradeDistribution::from_trades(&trades, InitialRisk::Fixed(50.0));
+        assert_eq!(distribution.trades, 4);
+        assert!((distribution.expectancy - 37.5).abs() < 1e-9);
+        // Sample std dev of [-50, -50, 100, 150] is 103.08
+        assert!((distribution.expectancy_std_error - 103.077640640 / 2.0).abs() < 1e-6);
+        assert!(distribution.skewness > 0.0);
+
+        let r = distribution.r_multiples.as_ref().unwrap();
+        assert!((r.expectancy - 0.75).abs() < 1e-9);
+        assert_eq!((r.worst, r.best), (-1.0, 3.0));
+        assert_eq!(r.histogram.iter().map(|b| b.count).sum::<usize>(), 4);
+
+        let median = distribution.percentiles.iter().find(|p| p.percentile == 50.0).unwrap();
+        assert!((median.pnl - 25.0).abs() < 1e-9);
+        assert_eq!(median.r_multiple, Some(0.5));
+
+        // Trades without a recorded risk drop out of the R statistics
+        let per_trade = TradeDistribution::from_trades(&trades, InitialRisk::PerTrade);
+        assert_eq!(per_trade.r_multiples.unwrap().trades, 3);
+    }
+}
@@ -79,6 +79,21 @@ impl ExportManager {
         Ok(())
     }
     
+    /// Export a report through a user-defined template
+    pub fn export_with_template(
+        &self,
+        report: &Report,
+        template: &super::templates::ReportTemplate,
+    ) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let filename = self.generate_filename(
+            &format!("{}_{}", report.metadata.strategy_name, template.name),
+            template.format,
+        );
+        let path = self.output_dir.join(filename);
+        fs::write(&path, template.render(report)?)?;
+        Ok(path)
+    }
+
     /// Generate filename based on strategy and format
     fn generate_filename(&self, strategy_name: &str, format: ReportFormat) -> String {
         let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
@@ -125,6 +140,18 @@ impl ExportManager {
             csv.push_str(&format!("Largest Win,{:.2}\n", backtest.trade_analysis.largest_win));
             csv.push_str(&format!("Largest Loss,{:.2}\n", backtest.trade_analysis.largest_loss));
             csv.push_str(&format!("Avg Duration (min),{:.1}\n", backtest.trade_analysis.avg_duration_minutes));
+            
+            let distribution = &backtest.trade_analysis.distribution;
+            csv.push_str(&format!("Expectancy,{:.2}\n", distribution.expectancy));
+            csv.push_str(&format!("Expectancy Std Error,{:.2}\n", distribution.expectancy_std_error));
+            csv.push_str(&format!("Trade P&L Skew,{:.3}\n", distribution.skewness));
+            csv.push_str(&format!("Trade P&L Excess Kurtosis,{:.3}\n", distribution.excess_kurtosis));
+            if let Some(r) = &distribution.r_multiples {
+                csv.push_str(&format!("Expectancy (R),{:.3}\n", r.expectancy));
+            }
+            for row in &distribution.percentiles {
+                csv.push_str(&format!("Trade P&L P{},{:.2}\n", row.percentile, row.pnl));
+            }
         }
         
         Ok(csv)
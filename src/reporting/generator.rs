@@ -5,6 +5,9 @@ use crate::backtesting::{BacktestResult, PerformanceMetrics};
 use crate::optimization::OptimizationReport;
 use super::*;
 
+/// Risk of ruin above which a sizing rule is flagged
+const RUIN_WARNING_THRESHOLD: f64 = 0.01;
+
 /// Report generator
 pub struct ReportGenerator {
     strategy_name: String,
@@ -79,7 +82,49 @@ impl ReportGenerator {
             recovery_factor: backtest.total_return / backtest.max_drawdown.abs(),
             downside_deviation: self.calculate_downside_deviation(backtest),
             tail_ratio: self.calculate_tail_ratio(backtest),
+            risk_of_ruin: None,
+        }
+    }
+    
+    /// Recommendations from a risk-of-ruin estimate: sizing rules likely to
+    /// ruin the account, or trades with no edge to size at all
+    pub fn generate_sizing_recommendations(&self, ruin: &RiskOfRuinReport) -> Vec<Recommendation> {
+        let mut recommendations = Vec::new();
+        
+        if ruin.distribution.trades > 0 && ruin.kelly_fraction <= 0.0 {
+            recommendations.push(Recommendation {
+                category: RecommendationCategory::RiskManagement,
+                priority: Priority::Critical,
+                title: "No Edge to Size".to_string(),
+                description: "The Kelly fraction of the trade distribution is not positive; any size loses in expectation".to_string(),
+                impact: "Avoid trading the strategy live until the edge is positive".to_string(),
+            });
         }
+        
+        if let Some(riskiest) = ruin.riskiest().filter(|estimate| estimate.risk_of_ruin > RUIN_WARNING_THRESHOLD) {
+            let suggestion = ruin.kelly.iter()
+                .filter(|k| k.estimate.risk_of_ruin <= RUIN_WARNING_THRESHOLD && k.estimate.initial_contracts > 0)
+                .max_by(|a, b| a.fraction.total_cmp(&b.fraction))
+                .map(|k| format!("; {:.2}x Kelly ({} contracts) keeps it at {:.1}%", k.multiplier, k.estimate.initial_contracts, k.estimate.risk_of_ruin * 100.0))
+                .unwrap_or_default();
+            recommendations.push(Recommendation {
+                category: RecommendationCategory::RiskManagement,
+                priority: Priority::High,
+                title: "Reduce Position Size".to_string(),
+                description: format!(
+                    "Trading {} has a {:.1}% chance of losing {:.0}% of ${:.0} within {} trades{}",
+                    riskiest.sizing.describe(),
+                    riskiest.risk_of_ruin * 100.0,
+                    (1.0 - ruin.ruin_level / ruin.capital) * 100.0,
+                    ruin.capital,
+                    ruin.horizon_trades,
+                    suggestion
+                ),
+                impact: "Keeps the account alive through ordinary losing streaks".to_string(),
+            });
+        }
+        
+        recommendations
     }
     
     /// Calculate Value at Risk
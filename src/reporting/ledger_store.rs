@@ -0,0 +1,245 @@
+//! Persisted fill ledgers, indexed for paging
+//!
+//! A ledger is written to `<dir>/<result id>.ledger` as one JSON fill per
+//! line. Beside it, `<result id>.ledger.idx` lists seek points: every
+//! `SEEK_STRIDE` trades, the first trade opened from flat after that many,
+//! with the fill it opens on and that fill's byte offset. A page starting
+//! at trade `cursor` is read from the last seek point at or before it, so
+//! paging deep into a long run reads the index and one stride of fills
+//! rather than the ledger from the start.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use super::trade_table::{TradeFilter, TradePage};
+use crate::backtesting::metrics::TradeRecord;
+use crate::strategy::OrderSide;
+use rust_decimal::Decimal;
+
+/// File extension of stored ledgers
+pub const LEDGER_EXTENSION: &str = "ledger";
+
+/// Trades between seek points
+const SEEK_STRIDE: usize = 256;
+
+/// Errors raised storing or reading ledgers
+#[derive(Debug, thiserror::Error)]
+pub enum LedgerStoreError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("Invalid result id: {0}")]
+    InvalidId(String),
+}
+
+/// Where reading can start without the fills before it
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct SeekPoint {
+    /// Index of the trade opened by the fill
+    trade: usize,
+    /// Byte offset of the fill's line
+    offset: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct LedgerIndex {
+    fills: usize,
+    seeks: Vec<SeekPoint>,
+}
+
+impl LedgerIndex {
+    /// The last seek point at or before trade `cursor`
+    fn seek(&self, cursor: usize) -> SeekPoint {
+        let at = self.seeks.partition_point(|seek| seek.trade <= cursor);
+        at.checked_sub(1)
+            .map(|at| self.seeks[at])
+            .unwrap_or(SeekPoint { trade: 0, offset: 0 })
+    }
+}
+
+/// Fill ledgers of finished backtests, one file per result
+#[derive(Debug, Clone)]
+pub struct TradeLedgerStore {
+    dir: PathBuf,
+}
+
+impl TradeLedgerStore {
+    /// Open (creating if needed) a store rooted at `dir`
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, LedgerStoreError> {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(Self { dir: dir.as_ref().to_path_buf() })
+    }
+
+    fn path(&self, result_id: &str) -> Result<PathBuf, LedgerStoreError> {
+        let valid = !result_id.is_empty()
+            && result_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(LedgerStoreError::InvalidId(result_id.to_string()));
+        }
+        Ok(self.dir.join(format!("{}.{}", result_id, LEDGER_EXTENSION)))
+    }
+
+    fn index_path(path: &Path) -> PathBuf {
+        path.with_extension(format!("{}.idx", LEDGER_EXTENSION))
+    }
+
+    /// Store the ledger of a result, replacing any earlier one
+    pub fn save(&self, result_id: &str, ledger: &[TradeRecord]) -> Result<(), LedgerStoreError> {
+        let path = self.path(result_id)?;
+        // Write beside the files and rename, so readers never see half a ledger
+        let partial = path.with_extension(format!("{}.partial", LEDGER_EXTENSION));
+        let mut out = BufWriter::new(File::create(&partial)?);
+        let mut index = LedgerIndex { fills: ledger.len(), seeks: Vec::new() };
+        let mut offset = 0u64;
+        let mut position = 0i32;
+        let mut trades = 0usize;
+        for fill in ledger {
+            if fill.quantity > 0 {
+                let signed = match fill.side {
+                    OrderSide::Buy => fill.quantity,
+                    OrderSide::Sell => -fill.quantity,
+                };
+                if position == 0 {
+                    let due = index.seeks.last().map_or(SEEK_STRIDE, |seek| seek.trade + SEEK_STRIDE);
+                    if trades >= due {
+                        index.seeks.push(SeekPoint { trade: trades, offset });
+                    }
+                    trades += 1;
+                } else if position.signum() != signed.signum() && signed.abs() > position.abs() {
+                    trades += 1;
+                }
+                position += signed;
+            }
+            let mut line = serde_json::to_vec(fill)?;
+            line.push(b'\n');
+            out.write_all(&line)?;
+            offset += line.len() as u64;
+        }
+        out.flush()?;
+        drop(out);
+
+        let partial_index = Self::index_path(&partial);
+        fs::write(&partial_index, serde_json::to_vec(&index)?)?;
+        fs::rename(partial_index, Self::index_path(&path))?;
+        fs::rename(partial, path)?;
+        Ok(())
+    }
+
+    /// Every fill of a result's ledger, or `None` if it has none
+    pub fn load(&self, result_id: &str) -> Result<Option<Vec<TradeRecord>>, LedgerStoreError> {
+        let file = match File::open(self.path(result_id)?) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut fills = Vec::new();
+        for line in BufReader::new(file).lines() {
+            fills.push(serde_json::from_str(&line?)?);
+        }
+        Ok(Some(fills))
+    }
+
+    /// A page of a result's trades, read from the seek point before `cursor`
+    ///
+    /// Pages match `TradePage::from_ledger` over the whole ledger. `None`
+    /// if the result has no stored ledger.
+    pub fn page(
+        &self,
+        result_id: &str,
+        filter: &TradeFilter,
+        cursor: usize,
+        limit: usize,
+        point_value: Decimal,
+    ) -> Result<Option<TradePage>, LedgerStoreError> {
+        let path = self.path(result_id)?;
+        let mut file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        // A ledger stored without its index is read from the start
+        let index: LedgerIndex = match fs::read(Self::index_path(&path)) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => LedgerIndex::default(),
+            Err(e) => return Err(e.into()),
+        };
+        let seek = index.seek(cursor);
+        file.seek(SeekFrom::Start(seek.offset))?;
+
+        let mut error = None;
+        let fills = BufReader::new(file).lines().map_while(|line| {
+            let fill = line.map_err(LedgerStoreError::from)
+                .and_then(|line| serde_json::from_str::<TradeRecord>(&line).map_err(LedgerStoreError::from));
+            fill.map_err(|e| error = Some(e)).ok()
+        });
+        let page = TradePage::from_fills(fills, seek.trade, filter, cursor, limit, point_value);
+        match error {
+            Some(e) => Err(e),
+            None => Ok(Some(page)),
+        }
+    }
+
+    /// Delete the stored ledger of a result, returning whether there was one
+    pub fn remove(&self, result_id: &str) -> Result<bool, LedgerStoreError> {
+        let path = self.path(result_id)?;
+        let _ = fs::remove_file(Self::index_path(&path));
+        match fs::remove_file(path) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    fn test_pages_seek_and_match_the_whole_ledger() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 12, 14, 30, 0).unwrap();
+        let fill = |i: usize, side, quantity| TradeRecord {
+            timestamp: start + Duration::seconds(i as i64),
+            side,
+            quantity,
+            price: Decimal::from(18_000 + (i % 7) as i64),
+            commission: Decimal::ONE,
+            slippage: Decimal::ZERO,
+            tag: None,
+        };
+        // Round trips, with a reversal every tenth trade
+        let mut ledger = Vec::new();
+        for i in 0..1_000 {
+            if i % 10 == 0 {
+                ledger.extend([fill(3 * i, OrderSide::Buy, 1), fill(3 * i + 1, OrderSide::Sell, 2), fill(3 * i + 2, OrderSide::Buy, 1)]);
+            } else {
+                ledger.extend([fill(3 * i, OrderSide::Sell, 1), fill(3 * i + 1, OrderSide::Buy, 1)]);
+            }
+        }
+        let dir = std::env::temp_dir().join(format!("ledgers-{}", uuid::Uuid::new_v4()));
+        let store = TradeLedgerStore::open(&dir).unwrap();
+        store.save("run-1", &ledger).unwrap();
+        let index: LedgerIndex = serde_json::from_slice(
+            &fs::read(TradeLedgerStore::index_path(&store.path("run-1").unwrap())).unwrap()
+        ).unwrap();
+        let seek = index.seek(700);
+        assert!(seek.trade > 0 && seek.trade <= 700);
+
+        let point_value = Decimal::from(2);
+        let filter = TradeFilter::default();
+        for cursor in [0, 255, 256, 700, 1_095] {
+            let expected = TradePage::from_ledger(&ledger, &filter, cursor, 20, point_value);
+            let page = store.page("run-1", &filter, cursor, 20, point_value).unwrap().unwrap();
+            assert_eq!(page.trades, expected.trades);
+            assert_eq!(page.next_cursor, expected.next_cursor);
+        }
+        assert_eq!(store.load("run-1").unwrap().map(|fills| fills.len()), Some(ledger.len()));
+        assert!(store.page("run-2", &filter, 0, 20, point_value).unwrap().is_none());
+        assert!(store.remove("run-1").unwrap());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -3,14 +3,30 @@
 pub mod generator;
 pub mod templates;
 pub mod export;
+pub mod arrow_export;
+pub mod distribution;
+pub mod digest;
+pub mod ledger_store;
+pub mod notify;
+pub mod replay;
+pub mod trade_table;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+use crate::analysis::RiskOfRuinReport;
 use crate::backtesting::BacktestResult;
 use crate::optimization::OptimizationReport;
 
+pub use digest::{DailyDigest, DigestBacktest, DigestDataset, DigestOptimization, Incident, IncidentSeverity, JobTally};
+pub use distribution::{InitialRisk, TradeDistribution, TradeOutcome};
+pub use ledger_store::{LedgerStoreError, TradeLedgerStore};
+pub use notify::{Notification, NotificationChannel, NotifyError};
+pub use replay::{trade_windows, Print, ReplayConfig, ReplayFrame, TradePhase, TradeReplay, TradeWindow};
+pub use templates::{Branding, ReportSection, ReportTemplate, TemplateError};
+pub use trade_table::{PnlSign, TradeFilter, TradePage, TradeRow, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+
 /// Report format options
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum ReportFormat {
@@ -74,6 +90,9 @@ pub struct TradeAnalysis {
     pub largest_loss: f64,
     pub avg_duration_minutes: f64,
     pub trades_per_day: f64,
+    /// Shape of the per-trade P&L
+    #[serde(default)]
+    pub distribution: TradeDistribution,
 }
 
 /// Period returns
@@ -94,6 +113,9 @@ pub struct RiskAnalysis {
     pub recovery_factor: f64,
     pub downside_deviation: f64,
     pub tail_ratio: f64,
+    /// Ruin probabilities and Kelly sizing, when the trade ledger was available
+    #[serde(default)]
+    pub risk_of_ruin: Option<RiskOfRuinReport>,
 }
 
 /// Recommendations
@@ -0,0 +1,196 @@
+//! Delivery of reports to the people running the lab
+//!
+//! A research server left running overnight has nobody watching its logs.
+//! Reports that should reach someone are wrapped in a `Notification` and
+//! handed to each configured `NotificationChannel`: the log, a directory an
+//! operator or another tool picks files up from, or email through the
+//! host's `sendmail`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use tracing::info;
+
+/// Mail transfer program used unless a channel names another
+pub const DEFAULT_SENDMAIL: &str = "/usr/sbin/sendmail";
+
+/// Errors raised delivering a notification
+#[derive(Debug, thiserror::Error)]
+pub enum NotifyError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{program} exited with {status}")]
+    CommandFailed { program: String, status: String },
+    #[error("Invalid notification channel '{0}'")]
+    InvalidChannel(String),
+}
+
+/// A message for the lab's operators
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub subject: String,
+    /// Markdown body
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    /// Machine-readable form of the body, written alongside it where the
+    /// channel keeps files
+    #[serde(default)]
+    pub payload: Option<serde_json::Value>,
+}
+
+impl Notification {
+    pub fn new(subject: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            body: body.into(),
+            created_at: Utc::now(),
+            payload: None,
+        }
+    }
+
+    pub fn with_payload(mut self, payload: serde_json::Value) -> Self {
+        self.payload = Some(payload);
+        self
+    }
+}
+
+/// Where notifications are sent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationChannel {
+    /// Write the subject and body to the server log
+    Log,
+    /// Write `<timestamp>-<slug>.md`, and `.json` when there is a payload
+    Directory { path: PathBuf },
+    /// Mail the body through a sendmail-compatible program
+    Email {
+        to: Vec<String>,
+        #[serde(default)]
+        from: Option<String>,
+        #[serde(default = "default_sendmail")]
+        sendmail: String,
+    },
+}
+
+fn default_sendmail() -> String {
+    DEFAULT_SENDMAIL.to_string()
+}
+
+impl NotificationChannel {
+    /// Deliver one notification
+    pub fn deliver(&self, notification: &Notification) -> Result<(), NotifyError> {
+        match self {
+            Self::Log => {
+                info!("{}\n{}", notification.subject, notification.body);
+                Ok(())
+            }
+            Self::Directory { path } => {
+                std::fs::create_dir_all(path)?;
+                let stem = format!(
+                    "{}-{}",
+                    notification.created_at.format("%Y%m%dT%H%M%SZ"),
+                    slug(&notification.subject)
+                );
+                let body = format!("# {}\n\n{}", notification.subject, notification.body);
+                std::fs::write(path.join(format!("{}.md", stem)), body)?;
+                if let Some(payload) = &notification.payload {
+                    let json = serde_json::to_vec_pretty(payload)
+                        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+                    std::fs::write(path.join(format!("{}.json", stem)), json)?;
+                }
+                Ok(())
+            }
+            Self::Email { to, from, sendmail } => {
+                let mut child = Command::new(sendmail)
+                    .args(["-t", "-i"])
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .spawn()?;
+                let message = email_message(notification, to, from.as_deref());
+                // Dropping stdin closes it, so sendmail sees the end of the message
+                child.stdin.take().expect("stdin is piped").write_all(message.as_bytes())?;
+                let status = child.wait()?;
+                if !status.success() {
+                    return Err(NotifyError::CommandFailed { program: sendmail.clone(), status: status.to_string() });
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Parses the compact form used in environment variables: `log`,
+/// `dir:<path>` or `email:<to>[;<to>...]`
+impl FromStr for NotificationChannel {
+    type Err = NotifyError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let spec = spec.trim();
+        match spec.split_once(':') {
+            None if spec == "log" => Ok(Self::Log),
+            Some(("dir", path)) if !path.is_empty() => Ok(Self::Directory { path: PathBuf::from(path) }),
+            Some(("email", to)) => {
+                let to: Vec<String> = to.split(';').map(str::trim).filter(|to| !to.is_empty()).map(String::from).collect();
+                if to.is_empty() {
+                    return Err(NotifyError::InvalidChannel(spec.to_string()));
+                }
+                Ok(Self::Email { to, from: None, sendmail: default_sendmail() })
+            }
+            _ => Err(NotifyError::InvalidChannel(spec.to_string())),
+        }
+    }
+}
+
+fn email_message(notification: &Notification, to: &[String], from: Option<&str>) -> String {
+    let mut message = format!("To: {}\n", to.join(", "));
+    if let Some(from) = from {
+        message.push_str(&format!("From: {}\n", from));
+    }
+    message.push_str(&format!(
+        "Subject: {}\nDate: {}\nMIME-Version: 1.0\nContent-Type: text/plain; charset=utf-8\n\n{}\n",
+        notification.subject.replace(['\r', '\n'], " "),
+        notification.created_at.to_rfc2822(),
+        notification.body
+    ));
+    message
+}
+
+fn slug(text: &str) -> String {
+    let slug: String = text.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    slug.split('-').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_specs_and_directory_delivery() {
+        assert_eq!("log".parse::<NotificationChannel>().unwrap(), NotificationChannel::Log);
+        assert!(matches!(
+            "email:ops@example.com; me@example.com".parse::<NotificationChannel>().unwrap(),
+            NotificationChannel::Email { to, .. } if to.len() == 2
+        ));
+        assert!("email:".parse::<NotificationChannel>().is_err());
+        assert!("webhook:https://example.com".parse::<NotificationChannel>().is_err());
+
+        let dir = std::env::temp_dir().join(format!("strategy_lab_notify_{}", uuid::Uuid::new_v4()));
+        let channel: NotificationChannel = format!("dir:{}", dir.display()).parse().unwrap();
+        let notification = Notification::new("Daily digest: 2024-06-12", "3 jobs ran")
+            .with_payload(serde_json::json!({ "jobs": 3 }));
+        channel.deliver(&notification).unwrap();
+
+        let mut files: Vec<String> = std::fs::read_dir(&dir).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(files.len(), 2);
+        assert!(files[1].ends_with("-daily-digest-2024-06-12.md"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,541 @@
+//! Frame-by-frame replay of a single trade
+//!
+//! The market around one trade of a backtest is sampled at a fixed interval,
+//! from shortly before the fill that opened the position until shortly after
+//! the one that closed it. Each frame holds the top of the book ladder, the
+//! prints and our fills since the previous frame, and the position held, so
+//! the frontend can animate exactly what the strategy saw on entry and exit.
+//! Frames serialize as JSON or render to standalone SVG images on a price
+//! ladder shared by the whole replay.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use crate::backtesting::metrics::TradeRecord;
+use crate::data::{MarketDataType, TickData};
+use crate::market::book_diff::{updates_book, BookSnapshot};
+use crate::market::order_book::OrderBook;
+use crate::strategy::OrderSide;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayConfig {
+    /// Time shown before the opening fill
+    #[serde(default = "default_lead_ms")]
+    pub lead_ms: u64,
+    /// Time shown after the closing fill
+    #[serde(default = "default_trail_ms")]
+    pub trail_ms: u64,
+    #[serde(default = "default_frame_interval_ms")]
+    pub frame_interval_ms: u64,
+    /// Book levels per side in each frame
+    #[serde(default = "default_depth")]
+    pub depth: usize,
+    /// Frames are spaced further apart when the window would need more
+    #[serde(default = "default_max_frames")]
+    pub max_frames: usize,
+}
+
+fn default_lead_ms() -> u64 {
+    30_000
+}
+
+fn default_trail_ms() -> u64 {
+    30_000
+}
+
+fn default_frame_interval_ms() -> u64 {
+    250
+}
+
+fn default_depth() -> usize {
+    10
+}
+
+fn default_max_frames() -> usize {
+    2_400
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            lead_ms: default_lead_ms(),
+            trail_ms: default_trail_ms(),
+            frame_interval_ms: default_frame_interval_ms(),
+            depth: default_depth(),
+            max_frames: default_max_frames(),
+        }
+    }
+}
+
+/// One trade of a ledger, from flat to flat
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeWindow {
+    /// Position in the ledger's sequence of trades
+    pub index: usize,
+    /// Side of the opening fill
+    pub side: OrderSide,
+    pub opened_at: DateTime<Utc>,
+    /// `None` when the position was still open at the end of the ledger
+    pub closed_at: Option<DateTime<Utc>>,
+    /// Largest absolute position held
+    pub max_position: i32,
+    /// Fills of the trade; a fill reversing the position is split between
+    /// the trade it closes and the one it opens
+    pub fills: Vec<TradeRecord>,
+}
+
+/// Split a ledger into trades, each running from flat to flat
+pub fn trade_windows(ledger: &[TradeRecord]) -> Vec<TradeWindow> {
+    let mut windows: Vec<TradeWindow> = Vec::new();
+    let mut position = 0i32;
+
+    for fill in ledger.iter().filter(|fill| fill.quantity > 0) {
+        let signed = signed_quantity(fill);
+        if position == 0 {
+            windows.push(TradeWindow {
+                index: windows.len(),
+                side: fill.side,
+                opened_at: fill.timestamp,
+                closed_at: None,
+                max_position: 0,
+                fills: Vec::new(),
+            });
+        }
+
+        let reverses = position != 0 && position.signum() != signed.signum() && signed.abs() > position.abs();
+        if reverses {
+            let closing = position.abs();
+            let (close, open) = (split_fill(fill, closing), split_fill(fill, fill.quantity - closing));
+            if let Some(window) = windows.last_mut() {
+                window.fills.push(close);
+                window.closed_at = Some(fill.timestamp);
+            }
+            position += signed;
+            windows.push(TradeWindow {
+                index: windows.len(),
+                side: fill.side,
+                opened_at: fill.timestamp,
+                closed_at: None,
+                max_position: position.abs(),
+                fills: vec![open],
+            });
+            continue;
+        }
+
+        position += signed;
+        if let Some(window) = windows.last_mut() {
+            window.fills.push(fill.clone());
+            window.max_position = window.max_position.max(position.abs());
+            if position == 0 {
+                window.closed_at = Some(fill.timestamp);
+            }
+        }
+    }
+    windows
+}
+
+fn signed_quantity(fill: &TradeRecord) -> i32 {
+    match fill.side {
+        OrderSide::Buy => fill.quantity,
+        OrderSide::Sell => -fill.quantity,
+    }
+}
+
+/// `quantity` contracts of `fill`, with its costs in proportion
+fn split_fill(fill: &TradeRecord, quantity: i32) -> TradeRecord {
+    let share = Decimal::from(quantity) / Decimal::from(fill.quantity);
+    TradeRecord {
+        quantity,
+        commission: fill.commission * share,
+        slippage: fill.slippage * share,
+        ..fill.clone()
+    }
+}
+
+/// Where a frame falls relative to the trade
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradePhase {
+    Before,
+    Open,
+    After,
+}
+
+/// A trade printed on the exchange
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Print {
+    pub timestamp: i64,
+    pub price: Decimal,
+    pub volume: i32,
+}
+
+/// The market and our position at one instant of the replay
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayFrame {
+    pub index: usize,
+    /// Nanoseconds since epoch
+    pub timestamp: i64,
+    pub phase: TradePhase,
+    /// Top `depth` levels per side, best first
+    pub book: BookSnapshot,
+    /// Exchange prints since the previous frame
+    pub prints: Vec<Print>,
+    /// Our fills since the previous frame
+    pub fills: Vec<TradeRecord>,
+    /// Signed position, long positive
+    pub position: i32,
+    /// Average price of the open position
+    pub average_price: Option<Decimal>,
+    pub last_price: Option<Decimal>,
+}
+
+/// Frames covering one trade
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeReplay {
+    pub contract: String,
+    pub trade: TradeWindow,
+    pub config: ReplayConfig,
+    /// Actual spacing of the frames, which `max_frames` may have widened
+    pub frame_interval_ms: u64,
+    pub frames: Vec<ReplayFrame>,
+    /// License notice the data requires on every rendered frame
+    #[serde(default)]
+    pub watermark: Option<String>,
+}
+
+impl TradeReplay {
+    /// Sample `ticks` around `trade`
+    ///
+    /// The contract defaults to that of the last tick at or before the
+    /// opening fill. Returns `None` when there is no such tick.
+    pub fn build(ticks: &[TickData], trade: &TradeWindow, contract: Option<&str>, config: &ReplayConfig) -> Option<Self> {
+        let opened_ns = nanos(trade.opened_at);
+        let closed_ns = trade.closed_at.map_or(opened_ns, nanos);
+        let contract = match contract {
+            Some(contract) => contract.to_string(),
+            None => ticks.iter()
+                .filter(|tick| tick.timestamp <= opened_ns)
+                .max_by_key(|tick| tick.timestamp)?
+                .contract_month
+                .clone(),
+        };
+
+        let start_ns = opened_ns.saturating_sub(config.lead_ms as i64 * 1_000_000);
+        let end_ns = closed_ns.saturating_add(config.trail_ms as i64 * 1_000_000);
+        let span_ns = (end_ns - start_ns).max(0) as u64;
+        let interval_ns = (config.frame_interval_ms.max(1) * 1_000_000).max(span_ns.div_ceil(config.max_frames.max(1) as u64));
+
+        let mut contract_ticks: Vec<&TickData> = ticks.iter()
+            .filter(|tick| tick.contract_month == contract && tick.timestamp <= end_ns)
+            .collect();
+        contract_ticks.sort_by_key(|tick| tick.timestamp);
+        let mut fills: Vec<&TradeRecord> = trade.fills.iter().collect();
+        fills.sort_by_key(|fill| fill.timestamp);
+
+        let mut book = OrderBook::new(contract.clone(), false);
+        let (mut next_tick, mut next_fill) = (0, 0);
+        let mut position = 0i32;
+        let mut average_price: Option<Decimal> = None;
+        let mut last_price = None;
+        let mut frames = Vec::new();
+        let mut at_ns = start_ns;
+
+        loop {
+            let mut prints = Vec::new();
+            while let Some(tick) = contract_ticks.get(next_tick).filter(|tick| tick.timestamp <= at_ns) {
+                if updates_book(tick) {
+                    book.process_tick(tick);
+                } else if matches!(tick.mdt, MarketDataType::Trade) {
+                    last_price = Some(tick.price);
+                    // Prints before the window only set the last price
+                    if tick.timestamp >= start_ns {
+                        prints.push(Print { timestamp: tick.timestamp, price: tick.price, volume: tick.volume });
+                    }
+                }
+                next_tick += 1;
+            }
+
+            let mut frame_fills = Vec::new();
+            while let Some(fill) = fills.get(next_fill).filter(|fill| nanos(fill.timestamp) <= at_ns) {
+                let signed = signed_quantity(fill);
+                average_price = if position + signed == 0 {
+                    None
+                } else if position == 0 || position.signum() == signed.signum() {
+                    let held = average_price.unwrap_or(fill.price) * Decimal::from(position.abs());
+                    Some((held + fill.price * Decimal::from(fill.quantity)) / Decimal::from(position.abs() + fill.quantity))
+                } else {
+                    average_price
+                };
+                position += signed;
+                frame_fills.push((*fill).clone());
+                next_fill += 1;
+            }
+
+            let phase = if at_ns < opened_ns {
+                TradePhase::Before
+            } else if trade.closed_at.is_some() && at_ns >= closed_ns {
+                TradePhase::After
+            } else {
+                TradePhase::Open
+            };
+            let mut snapshot = BookSnapshot::from_state(book.get_state());
+            snapshot.bids.truncate(config.depth);
+            snapshot.asks.truncate(config.depth);
+
+            frames.push(ReplayFrame {
+                index: frames.len(),
+                timestamp: at_ns,
+                phase,
+                book: snapshot,
+                prints,
+                fills: frame_fills,
+                position,
+                average_price,
+                last_price,
+            });
+
+            if at_ns >= end_ns {
+                break;
+            }
+            at_ns = at_ns.saturating_add(interval_ns as i64).min(end_ns);
+        }
+
+        Some(Self {
+            contract,
+            trade: trade.clone(),
+            config: config.clone(),
+            frame_interval_ms: interval_ns / 1_000_000,
+            frames,
+            watermark: None,
+        })
+    }
+
+    /// Every price shown in any frame, highest first, so the ladder does not
+    /// shift between frames
+    pub fn ladder_prices(&self) -> Vec<Decimal> {
+        let mut prices = BTreeSet::new();
+        for frame in &self.frames {
+            prices.extend(frame.book.bids.iter().chain(&frame.book.asks).map(|level| level.price));
+            prices.extend(frame.prints.iter().map(|print| print.price));
+            prices.extend(frame.fills.iter().map(|fill| fill.price));
+            prices.extend(frame.average_price);
+        }
+        prices.into_iter().rev().collect()
+    }
+
+    /// Stamp `notice` on every rendered frame
+    pub fn with_watermark(mut self, notice: String) -> Self {
+        self.watermark = Some(notice);
+        self
+    }
+
+    /// Each frame as a standalone SVG image
+    pub fn to_svg_frames(&self) -> Vec<String> {
+        let prices = self.ladder_prices();
+        let max_volume = self.frames.iter()
+            .flat_map(|frame| frame.book.bids.iter().chain(&frame.book.asks))
+            .map(|level| level.volume)
+            .max()
+            .unwrap_or(1)
+            .max(1);
+        self.frames.iter()
+            .map(|frame| render_frame(&self.contract, frame, &prices, max_volume, self.watermark.as_deref()))
+            .collect()
+    }
+}
+
+fn nanos(at: DateTime<Utc>) -> i64 {
+    at.timestamp_nanos_opt().unwrap_or_default()
+}
+
+const SVG_WIDTH: usize = 360;
+const SVG_HEADER: usize = 36;
+const SVG_ROW: usize = 14;
+/// Bids grow left from here, asks right from `PRICE_RIGHT`
+const PRICE_LEFT: usize = 140;
+const PRICE_RIGHT: usize = 220;
+const BAR_WIDTH: usize = 140;
+
+/// Book ladder with bids left and asks right, prints and fills marked in
+/// the price column and the position drawn at its average price
+fn render_frame(contract: &str, frame: &ReplayFrame, prices: &[Decimal], max_volume: i32, watermark: Option<&str>) -> String {
+    let ladder_height = SVG_HEADER + prices.len().max(1) * SVG_ROW;
+    let height = ladder_height + if watermark.is_some() { SVG_ROW } else { 0 };
+    let row_y = |price: Decimal| prices.iter().position(|p| *p == price).map(|row| SVG_HEADER + row * SVG_ROW);
+    let bar = |volume: i32| volume.max(0) as usize * BAR_WIDTH / max_volume.max(1) as usize;
+    let time = DateTime::<Utc>::from_timestamp_nanos(frame.timestamp).format("%H:%M:%S%.3f");
+    let position = match frame.position {
+        0 => "flat".to_string(),
+        size if size > 0 => format!("long {}", size),
+        size => format!("short {}", -size),
+    };
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="monospace" font-size="10">"##,
+        w = SVG_WIDTH,
+        h = height
+    );
+    let _ = write!(svg, r##"<rect width="{}" height="{}" fill="#ffffff"/>"##, SVG_WIDTH, height);
+    let _ = write!(
+        svg,
+        r##"<text x="4" y="14">{} {} {:?}</text><text x="4" y="28">{}</text>"##,
+        escape(contract),
+        time,
+        frame.phase,
+        position
+    );
+
+    for &price in prices {
+        let Some(y) = row_y(price) else { continue };
+        let _ = write!(
+            svg,
+            r##"<text x="{}" y="{}" text-anchor="middle">{}</text>"##,
+            (PRICE_LEFT + PRICE_RIGHT) / 2,
+            y + SVG_ROW - 3,
+            price
+        );
+    }
+    for level in &frame.book.bids {
+        if let Some(y) = row_y(level.price) {
+            let width = bar(level.volume);
+            let _ = write!(
+                svg,
+                r##"<rect x="{}" y="{}" width="{}" height="{}" fill="#2e7d32"/><text x="{}" y="{}" text-anchor="end">{}</text>"##,
+                PRICE_LEFT - width,
+                y + 1,
+                width,
+                SVG_ROW - 2,
+                PRICE_LEFT - 2,
+                y + SVG_ROW - 3,
+                level.volume
+            );
+        }
+    }
+    for level in &frame.book.asks {
+        if let Some(y) = row_y(level.price) {
+            let _ = write!(
+                svg,
+                r##"<rect x="{}" y="{}" width="{}" height="{}" fill="#c62828"/><text x="{}" y="{}">{}</text>"##,
+                PRICE_RIGHT,
+                y + 1,
+                bar(level.volume),
+                SVG_ROW - 2,
+                PRICE_RIGHT + 2,
+                y + SVG_ROW - 3,
+                level.volume
+            );
+        }
+    }
+    for print in &frame.prints {
+        if let Some(y) = row_y(print.price) {
+            let _ = write!(
+                svg,
+                r##"<circle cx="{}" cy="{}" r="3" fill="#ef6c00"/>"##,
+                PRICE_RIGHT - 6,
+                y + SVG_ROW / 2
+            );
+        }
+    }
+    for fill in &frame.fills {
+        if let Some(y) = row_y(fill.price) {
+            let colour = match fill.side {
+                OrderSide::Buy => "#1565c0",
+                OrderSide::Sell => "#6a1b9a",
+            };
+            let _ = write!(
+                svg,
+                r##"<rect x="{}" y="{}" width="8" height="8" fill="{}"/>"##,
+                PRICE_LEFT + 2,
+                y + SVG_ROW / 2 - 4,
+                colour
+            );
+        }
+    }
+    if let Some(y) = frame.average_price.and_then(row_y) {
+        let colour = if frame.position > 0 { "#1565c0" } else { "#6a1b9a" };
+        let _ = write!(
+            svg,
+            r##"<line x1="0" y1="{y}" x2="{}" y2="{y}" stroke="{}" stroke-dasharray="4 2"/>"##,
+            SVG_WIDTH,
+            colour,
+            y = y + SVG_ROW / 2
+        );
+    }
+    if let Some(watermark) = watermark {
+        let _ = write!(
+            svg,
+            r##"<text x="4" y="{}" font-size="8" fill="#616161">{}</text>"##,
+            ladder_height + SVG_ROW - 4,
+            escape(watermark)
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::DataLevel;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_frames_follow_the_trade() {
+        let opened = Utc.with_ymd_and_hms(2024, 6, 12, 14, 30, 0).unwrap();
+        let fill = |side, quantity, price: i64, secs| TradeRecord {
+            timestamp: opened + chrono::Duration::seconds(secs),
+            side,
+            quantity,
+            price: Decimal::from(price),
+            commission: Decimal::from(quantity),
+            slippage: Decimal::ZERO,
+            tag: None,
+        };
+        // Long one, reversed to short one, then flat
+        let ledger = vec![
+            fill(OrderSide::Buy, 1, 18_000, 0),
+            fill(OrderSide::Sell, 2, 18_004, 2),
+            fill(OrderSide::Buy, 1, 18_001, 5),
+        ];
+        let windows = trade_windows(&ledger);
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].closed_at, Some(ledger[1].timestamp));
+        assert_eq!(windows[1].fills[0].quantity, 1);
+        assert_eq!(windows[1].fills[0].commission, Decimal::ONE);
+
+        let ticks: Vec<TickData> = (-2..5)
+            .map(|secs| TickData::new(
+                DataLevel::L1,
+                MarketDataType::Trade,
+                nanos(opened) + secs * 1_000_000_000,
+                Decimal::from(18_000 + secs),
+                1,
+                "0624".to_string(),
+            ))
+            .collect();
+        let config = ReplayConfig { lead_ms: 1_000, trail_ms: 1_000, frame_interval_ms: 1_000, ..Default::default() };
+        let replay = TradeReplay::build(&ticks, &windows[0], None, &config).unwrap();
+
+        // One second either side of a two second trade
+        assert_eq!(replay.frames.len(), 5);
+        let phases: Vec<TradePhase> = replay.frames.iter().map(|frame| frame.phase).collect();
+        assert_eq!(phases, vec![TradePhase::Before, TradePhase::Open, TradePhase::Open, TradePhase::After, TradePhase::After]);
+        assert_eq!(replay.frames[1].position, 1);
+        assert_eq!(replay.frames[1].average_price, Some(Decimal::from(18_000)));
+        assert_eq!(replay.frames[3].position, 0);
+        assert_eq!(replay.frames[2].prints.len(), 1);
+
+        let svg = replay.to_svg_frames();
+        assert_eq!(svg.len(), 5);
+        assert!(svg[1].starts_with("<svg") && svg[1].contains("long 1"));
+    }
+}
@@ -111,8 +111,11 @@ pub fn html_template(report: &Report) -> String {
             <tr><td>Max Consecutive Losses</td><td><strong>{}</strong></td></tr>
             <tr><td>Recovery Factor</td><td><strong>{:.2}</strong></td></tr>
         </table>
+        {}
     </div>
     
+    {}
+    
     <div class="chart-container">
         <h2>Recommendations</h2>
         {}
@@ -136,6 +139,8 @@ pub fn html_template(report: &Report) -> String {
         report.risk_analysis.conditional_var_95,
         report.risk_analysis.max_consecutive_losses,
         report.risk_analysis.recovery_factor,
+        format_risk_of_ruin(&report.risk_analysis),
+        format_trade_distribution(report),
         format_recommendations(&report.recommendations)
     )
 }
@@ -171,7 +176,7 @@ pub fn markdown_template(report: &Report) -> String {
 - **Recovery Factor:** {:.2}
 - **Downside Deviation:** {:.2}%
 - **Tail Ratio:** {:.2}
-
+{}{}
 ## Recommendations
 
 {}
@@ -199,11 +204,157 @@ pub fn markdown_template(report: &Report) -> String {
         report.risk_analysis.recovery_factor,
         report.risk_analysis.downside_deviation,
         report.risk_analysis.tail_ratio,
+        format_risk_of_ruin_markdown(&report.risk_analysis),
+        format_trade_distribution_markdown(report),
         format_recommendations_markdown(&report.recommendations),
         report.metadata.version
     )
 }
 
+/// Format the trade distribution section for HTML, empty without trades
+fn format_trade_distribution(report: &Report) -> String {
+    let Some(distribution) = report.backtest_results.as_ref()
+        .map(|backtest| &backtest.trade_analysis.distribution)
+        .filter(|distribution| distribution.trades > 0) else {
+        return String::new();
+    };
+    
+    let r_expectancy = distribution.r_multiples.as_ref()
+        .map(|r| format!("<tr><td>Expectancy (R)</td><td><strong>{:.3}R</strong></td></tr>", r.expectancy))
+        .unwrap_or_default();
+    let percentiles = distribution.percentiles.iter()
+        .map(|row| format!(
+            "<tr><td>P{}</td><td>${:.2}</td><td>{}</td></tr>",
+            row.percentile,
+            row.pnl,
+            row.r_multiple.map(|r| format!("{:.2}R", r)).unwrap_or_else(|| "-".to_string())
+        ))
+        .collect::<Vec<_>>()
+        .join("\n");
+    
+    format!(
+        r#"<div class="chart-container">
+        <h2>Trade Distribution</h2>
+        <table style="width: 100%;">
+            <tr><td>Expectancy</td><td><strong>${:.2} &plusmn; {:.2}</strong></td></tr>
+            {}
+            <tr><td>Skew</td><td><strong>{:.3}</strong></td></tr>
+            <tr><td>Excess Kurtosis</td><td><strong>{:.3}</strong></td></tr>
+        </table>
+        <table style="width: 100%;">
+            <tr><th>Percentile</th><th>P&amp;L</th><th>R-Multiple</th></tr>
+            {}
+        </table>
+    </div>"#,
+        distribution.expectancy,
+        distribution.expectancy_std_error,
+        r_expectancy,
+        distribution.skewness,
+        distribution.excess_kurtosis,
+        percentiles
+    )
+}
+
+/// Format the trade distribution section for Markdown, empty without trades
+fn format_trade_distribution_markdown(report: &Report) -> String {
+    let Some(distribution) = report.backtest_results.as_ref()
+        .map(|backtest| &backtest.trade_analysis.distribution)
+        .filter(|distribution| distribution.trades > 0) else {
+        return String::new();
+    };
+    
+    let mut section = format!(
+        "\n## Trade Distribution\n\n\
+         - **Expectancy:** ${:.2} ± {:.2} (t = {:.2})\n\
+         - **Skew:** {:.3}\n\
+         - **Excess Kurtosis:** {:.3}\n",
+        distribution.expectancy,
+        distribution.expectancy_std_error,
+        distribution.expectancy_t_stat,
+        distribution.skewness,
+        distribution.excess_kurtosis
+    );
+    if let Some(r) = &distribution.r_multiples {
+        section.push_str(&format!("- **Expectancy (R):** {:.3}R over {} trades\n", r.expectancy, r.trades));
+    }
+    
+    section.push_str("\n| Percentile | P&L | R-Multiple |\n|------------|-----|------------|\n");
+    for row in &distribution.percentiles {
+        let r = row.r_multiple.map(|r| format!("{:.2}R", r)).unwrap_or_else(|| "-".to_string());
+        section.push_str(&format!("| P{} | ${:.2} | {} |\n", row.percentile, row.pnl, r));
+    }
+    section
+}
+
+/// Format the risk-of-ruin and Kelly sizing tables for HTML, empty without an estimate
+fn format_risk_of_ruin(risk: &RiskAnalysis) -> String {
+    let Some(ruin) = &risk.risk_of_ruin else {
+        return String::new();
+    };
+    
+    let rows = ruin.sizing.iter()
+        .map(|estimate| (estimate.sizing.describe(), estimate))
+        .chain(ruin.kelly.iter().map(|k| (format!("{:.2}x Kelly ({:.1}%)", k.multiplier, k.fraction * 100.0), &k.estimate)))
+        .map(|(label, estimate)| format!(
+            "<tr><td>{}</td><td>{}</td><td>{:.1}%</td><td>${:.0}</td><td>{:.1}%</td></tr>",
+            label,
+            estimate.initial_contracts,
+            estimate.risk_of_ruin * 100.0,
+            estimate.median_final_equity,
+            estimate.median_max_drawdown * 100.0
+        ))
+        .collect::<Vec<_>>()
+        .join("\n");
+    
+    format!(
+        r#"<h3>Risk of Ruin</h3>
+        <p>Ruin at ${:.0} from ${:.0} within {} trades over {} simulations; full Kelly risks {:.1}% per trade.</p>
+        <table style="width: 100%;">
+            <tr><th>Sizing</th><th>Contracts</th><th>Risk of Ruin</th><th>Median Equity</th><th>Median Max DD</th></tr>
+            {}
+        </table>"#,
+        ruin.ruin_level,
+        ruin.capital,
+        ruin.horizon_trades,
+        ruin.simulations,
+        ruin.kelly_fraction * 100.0,
+        rows
+    )
+}
+
+/// Format the risk-of-ruin and Kelly sizing tables for Markdown, empty without an estimate
+fn format_risk_of_ruin_markdown(risk: &RiskAnalysis) -> String {
+    let Some(ruin) = &risk.risk_of_ruin else {
+        return String::new();
+    };
+    
+    let mut section = format!(
+        "\n### Risk of Ruin\n\n\
+         Ruin at ${:.0} from ${:.0} within {} trades over {} simulations; full Kelly risks {:.1}% per trade.\n\n\
+         | Sizing | Contracts | Risk of Ruin | Median Equity | Median Max DD |\n\
+         |--------|-----------|--------------|---------------|---------------|\n",
+        ruin.ruin_level,
+        ruin.capital,
+        ruin.horizon_trades,
+        ruin.simulations,
+        ruin.kelly_fraction * 100.0
+    );
+    let rows = ruin.sizing.iter()
+        .map(|estimate| (estimate.sizing.describe(), estimate))
+        .chain(ruin.kelly.iter().map(|k| (format!("{:.2}x Kelly ({:.1}%)", k.multiplier, k.fraction * 100.0), &k.estimate)));
+    for (label, estimate) in rows {
+        section.push_str(&format!(
+            "| {} | {} | {:.1}% | ${:.0} | {:.1}% |\n",
+            label,
+            estimate.initial_contracts,
+            estimate.risk_of_ruin * 100.0,
+            estimate.median_final_equity,
+            estimate.median_max_drawdown * 100.0
+        ));
+    }
+    section
+}
+
 /// Format recommendations for HTML
 fn format_recommendations(recommendations: &[Recommendation]) -> String {
     recommendations.iter()
@@ -238,4 +389,472 @@ fn format_recommendations_markdown(recommendations: &[Recommendation]) -> String
         })
         .collect::<Vec<_>>()
         .join("\n")
-}
\ No newline at end of file
+}
+
+/// A report section a template can include
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportSection {
+    Summary,
+    KeyFindings,
+    RiskAnalysis,
+    TradeDistribution,
+    PeriodReturns,
+    Recommendations,
+}
+
+impl ReportSection {
+    pub fn title(self) -> &'static str {
+        match self {
+            ReportSection::Summary => "Executive Summary",
+            ReportSection::KeyFindings => "Key Findings",
+            ReportSection::RiskAnalysis => "Risk Analysis",
+            ReportSection::TradeDistribution => "Trade Distribution",
+            ReportSection::PeriodReturns => "Period Returns",
+            ReportSection::Recommendations => "Recommendations",
+        }
+    }
+
+    /// Every section, in the order of the built-in reports
+    pub fn all() -> Vec<Self> {
+        vec![
+            ReportSection::Summary,
+            ReportSection::KeyFindings,
+            ReportSection::RiskAnalysis,
+            ReportSection::TradeDistribution,
+            ReportSection::PeriodReturns,
+            ReportSection::Recommendations,
+        ]
+    }
+}
+
+/// Look of a templated report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Branding {
+    /// Replaces the strategy name as the report title
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub organization: Option<String>,
+    #[serde(default)]
+    pub logo_url: Option<String>,
+    /// CSS color of the header and metric values
+    #[serde(default = "default_primary_color")]
+    pub primary_color: String,
+    /// Closing line such as a disclaimer
+    #[serde(default)]
+    pub footer: Option<String>,
+}
+
+fn default_primary_color() -> String {
+    "#667eea".to_string()
+}
+
+impl Default for Branding {
+    fn default() -> Self {
+        Self {
+            title: None,
+            organization: None,
+            logo_url: None,
+            primary_color: default_primary_color(),
+            footer: None,
+        }
+    }
+}
+
+/// Errors raised while loading or rendering a template
+#[derive(Debug, thiserror::Error)]
+pub enum TemplateError {
+    #[error("Templates render HTML or Markdown, not {0:?}")]
+    UnsupportedFormat(ReportFormat),
+    #[error("Invalid template: {0}")]
+    Parse(#[from] Box<handlebars::TemplateError>),
+    #[error("Render error: {0}")]
+    Render(#[from] handlebars::RenderError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid template definition: {0}")]
+    Definition(#[from] serde_json::Error),
+}
+
+/// A user-defined report layout
+///
+/// `sections` picks which sections appear and in what order. `body` is an
+/// optional Handlebars template; it sees the full `report`, the `branding`,
+/// a `title` and the rendered `sections` (each with `id`, `title` and
+/// `content`). Without a body the sections are laid out one after another
+/// under a branded header.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportTemplate {
+    pub name: String,
+    pub format: ReportFormat,
+    #[serde(default = "ReportSection::all")]
+    pub sections: Vec<ReportSection>,
+    #[serde(default)]
+    pub branding: Branding,
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+impl ReportTemplate {
+    pub fn new(name: impl Into<String>, format: ReportFormat) -> Self {
+        Self {
+            name: name.into(),
+            format,
+            sections: ReportSection::all(),
+            branding: Branding::default(),
+            body: None,
+        }
+    }
+
+    /// Everything, for reviewing one's own research
+    pub fn personal_review(format: ReportFormat) -> Self {
+        Self::new("personal_review", format)
+    }
+
+    /// Headline performance and risk, without research notes
+    pub fn investor_summary(format: ReportFormat) -> Self {
+        Self::new("investor_summary", format)
+            .with_sections(vec![ReportSection::Summary, ReportSection::RiskAnalysis, ReportSection::PeriodReturns])
+    }
+
+    pub fn with_sections(mut self, sections: Vec<ReportSection>) -> Self {
+        self.sections = sections;
+        self
+    }
+
+    pub fn with_branding(mut self, branding: Branding) -> Self {
+        self.branding = branding;
+        self
+    }
+
+    pub fn with_body(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Read a template definition saved as JSON
+    pub fn load(path: &std::path::Path) -> Result<Self, TemplateError> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// Render `report` through this template
+    pub fn render(&self, report: &Report) -> Result<String, TemplateError> {
+        let markdown = match self.format {
+            ReportFormat::Html => false,
+            ReportFormat::Markdown => true,
+            other => return Err(TemplateError::UnsupportedFormat(other)),
+        };
+
+        let sections: Vec<serde_json::Value> = self.sections.iter()
+            .filter_map(|&section| {
+                let content = if markdown {
+                    markdown_section(report, section)
+                } else {
+                    html_section(report, section)
+                };
+                (!content.is_empty()).then(|| serde_json::json!({
+                    "id": section,
+                    "title": section.title(),
+                    "content": content,
+                }))
+            })
+            .collect();
+
+        let context = serde_json::json!({
+            "report": report,
+            "branding": self.branding,
+            "title": self.branding.title.clone().unwrap_or_else(|| report.metadata.strategy_name.clone()),
+            "generated_at": report.metadata.generated_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            "style": html_style(&self.branding.primary_color),
+            "sections": sections,
+        });
+
+        let mut handlebars = handlebars::Handlebars::new();
+        if markdown {
+            handlebars.register_escape_fn(handlebars::no_escape);
+        }
+        let body = match &self.body {
+            Some(body) => body.as_str(),
+            None if markdown => DEFAULT_MARKDOWN_BODY,
+            None => DEFAULT_HTML_BODY,
+        };
+        handlebars.register_template_string(&self.name, body).map_err(Box::new)?;
+        Ok(handlebars.render(&self.name, &context)?)
+    }
+}
+
+const DEFAULT_HTML_BODY: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{{title}}</title>
+    <style>{{{style}}}</style>
+</head>
+<body>
+    <div class="header">
+        {{#if branding.logo_url}}<img src="{{branding.logo_url}}" alt="{{branding.organization}}" class="logo">{{/if}}
+        <h1>{{title}}</h1>
+        <p>{{#if branding.organization}}{{branding.organization}} | {{/if}}Generated: {{generated_at}} | Author: {{report.metadata.author}}</p>
+    </div>
+    {{#each sections}}
+    {{{content}}}
+    {{/each}}
+    {{#if branding.footer}}<p class="footer">{{branding.footer}}</p>{{/if}}
+</body>
+</html>
+"#;
+
+const DEFAULT_MARKDOWN_BODY: &str = r#"# {{title}}
+
+{{#if branding.organization}}**{{branding.organization}}**  
+{{/if}}**Generated:** {{generated_at}}  
+**Author:** {{report.metadata.author}}
+
+{{#each sections}}
+{{content}}
+{{/each}}
+{{#if branding.footer}}---
+*{{branding.footer}}*
+{{/if}}"#;
+
+/// Stylesheet of templated HTML reports
+fn html_style(primary_color: &str) -> String {
+    format!(r#"
+        body {{ font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif; line-height: 1.6; color: #333; max-width: 1200px; margin: 0 auto; padding: 20px; background: #f5f5f5; }}
+        .header {{ background: {0}; color: white; padding: 30px; border-radius: 10px; margin-bottom: 30px; }}
+        .header .logo {{ max-height: 48px; float: right; }}
+        .metric-grid {{ display: grid; grid-template-columns: repeat(auto-fit, minmax(250px, 1fr)); gap: 20px; margin-bottom: 30px; }}
+        .metric-card, .chart-container {{ background: white; padding: 20px; border-radius: 8px; box-shadow: 0 2px 4px rgba(0,0,0,0.1); margin-bottom: 20px; }}
+        .metric-value {{ font-size: 2em; font-weight: bold; color: {0}; }}
+        .metric-label {{ color: #666; font-size: 0.9em; text-transform: uppercase; letter-spacing: 1px; }}
+        .recommendation {{ background: white; padding: 15px; border-left: 4px solid {0}; margin-bottom: 15px; border-radius: 4px; }}
+        .priority-critical {{ border-left-color: #e53e3e; }}
+        .priority-high {{ border-left-color: #ed8936; }}
+        .priority-medium {{ border-left-color: #ecc94b; }}
+        .priority-low {{ border-left-color: #48bb78; }}
+        .footer {{ color: #666; font-size: 0.8em; text-align: center; }}
+    "#, primary_color)
+}
+
+/// One section as HTML, empty when the report has nothing for it
+fn html_section(report: &Report, section: ReportSection) -> String {
+    let summary = &report.summary;
+    let risk = &report.risk_analysis;
+    match section {
+        ReportSection::Summary => {
+            let card = |label: &str, value: String| format!(
+                r#"<div class="metric-card"><div class="metric-label">{}</div><div class="metric-value">{}</div></div>"#,
+                label, value
+            );
+            format!(
+                r#"<div class="metric-grid">{}{}{}{}{}</div>"#,
+                card("Total Return", format!("{:.2}%", summary.total_return)),
+                card("Sharpe Ratio", format!("{:.2}", summary.sharpe_ratio)),
+                card("Max Drawdown", format!("{:.2}%", summary.max_drawdown)),
+                card("Win Rate", format!("{:.1}%", summary.win_rate * 100.0)),
+                card("Profit Factor", format!("{:.2}", summary.profit_factor)),
+            )
+        }
+        ReportSection::KeyFindings if summary.key_findings.is_empty() => String::new(),
+        ReportSection::KeyFindings => format!(
+            r#"<div class="chart-container"><h2>Key Findings</h2><ul>{}</ul></div>"#,
+            summary.key_findings.iter()
+                .map(|f| format!("<li>{}</li>", f))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ),
+        ReportSection::RiskAnalysis => format!(
+            r#"<div class="chart-container">
+        <h2>Risk Analysis</h2>
+        <table style="width: 100%;">
+            <tr><td>Value at Risk (95%)</td><td><strong>${:.2}</strong></td></tr>
+            <tr><td>Conditional VaR (95%)</td><td><strong>${:.2}</strong></td></tr>
+            <tr><td>Max Consecutive Losses</td><td><strong>{}</strong></td></tr>
+            <tr><td>Recovery Factor</td><td><strong>{:.2}</strong></td></tr>
+            <tr><td>Downside Deviation</td><td><strong>{:.2}%</strong></td></tr>
+            <tr><td>Tail Ratio</td><td><strong>{:.2}</strong></td></tr>
+        </table>
+        {}
+    </div>"#,
+            risk.value_at_risk_95,
+            risk.conditional_var_95,
+            risk.max_consecutive_losses,
+            risk.recovery_factor,
+            risk.downside_deviation,
+            risk.tail_ratio,
+            format_risk_of_ruin(risk)
+        ),
+        ReportSection::TradeDistribution => format_trade_distribution(report),
+        ReportSection::PeriodReturns => {
+            let Some(backtest) = report.backtest_results.as_ref()
+                .filter(|backtest| !backtest.period_returns.is_empty()) else {
+                return String::new();
+            };
+            let rows = backtest.period_returns.iter()
+                .map(|p| format!(
+                    "<tr><td>{}</td><td>{:.2}%</td><td>{}</td><td>{:.2}</td></tr>",
+                    p.period, p.return_pct, p.trades, p.sharpe
+                ))
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!(
+                r#"<div class="chart-container">
+        <h2>Period Returns</h2>
+        <table style="width: 100%;">
+            <tr><th>Period</th><th>Return</th><th>Trades</th><th>Sharpe</th></tr>
+            {}
+        </table>
+    </div>"#,
+                rows
+            )
+        }
+        ReportSection::Recommendations if report.recommendations.is_empty() => String::new(),
+        ReportSection::Recommendations => format!(
+            r#"<div class="chart-container"><h2>Recommendations</h2>{}</div>"#,
+            format_recommendations(&report.recommendations)
+        ),
+    }
+}
+
+/// One section as Markdown, empty when the report has nothing for it
+fn markdown_section(report: &Report, section: ReportSection) -> String {
+    let summary = &report.summary;
+    let risk = &report.risk_analysis;
+    match section {
+        ReportSection::Summary => format!(
+            "## Executive Summary\n\n\
+             | Metric | Value |\n|--------|-------|\n\
+             | Total Return | {:.2}% |\n\
+             | Sharpe Ratio | {:.2} |\n\
+             | Max Drawdown | {:.2}% |\n\
+             | Win Rate | {:.1}% |\n\
+             | Profit Factor | {:.2} |\n",
+            summary.total_return,
+            summary.sharpe_ratio,
+            summary.max_drawdown,
+            summary.win_rate * 100.0,
+            summary.profit_factor
+        ),
+        ReportSection::KeyFindings if summary.key_findings.is_empty() => String::new(),
+        ReportSection::KeyFindings => format!(
+            "## Key Findings\n\n{}\n",
+            summary.key_findings.iter()
+                .map(|f| format!("- {}", f))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ),
+        ReportSection::RiskAnalysis => format!(
+            "## Risk Analysis\n\n\
+             - **Value at Risk (95%):** ${:.2}\n\
+             - **Conditional VaR (95%):** ${:.2}\n\
+             - **Max Consecutive Losses:** {}\n\
+             - **Recovery Factor:** {:.2}\n\
+             - **Downside Deviation:** {:.2}%\n\
+             - **Tail Ratio:** {:.2}\n{}",
+            risk.value_at_risk_95,
+            risk.conditional_var_95,
+            risk.max_consecutive_losses,
+            risk.recovery_factor,
+            risk.downside_deviation,
+            risk.tail_ratio,
+            format_risk_of_ruin_markdown(risk)
+        ),
+        ReportSection::TradeDistribution => format_trade_distribution_markdown(report)
+            .trim_start()
+            .to_string(),
+        ReportSection::PeriodReturns => {
+            let Some(backtest) = report.backtest_results.as_ref()
+                .filter(|backtest| !backtest.period_returns.is_empty()) else {
+                return String::new();
+            };
+            let mut section = "## Period Returns\n\n| Period | Return | Trades | Sharpe |\n|--------|--------|--------|--------|\n".to_string();
+            for p in &backtest.period_returns {
+                section.push_str(&format!("| {} | {:.2}% | {} | {:.2} |\n", p.period, p.return_pct, p.trades, p.sharpe));
+            }
+            section
+        }
+        ReportSection::Recommendations if report.recommendations.is_empty() => String::new(),
+        ReportSection::Recommendations => format!(
+            "## Recommendations\n\n{}",
+            format_recommendations_markdown(&report.recommendations)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> Report {
+        let now = Utc::now();
+        Report {
+            metadata: ReportMetadata {
+                generated_at: now,
+                strategy_name: "Order flow scalper".to_string(),
+                version: "0.1.0".to_string(),
+                author: "research".to_string(),
+                data_period: DataPeriod { start: now, end: now, total_days: 30, trading_days: 21 },
+            },
+            summary: ExecutiveSummary {
+                total_return: 12.5,
+                sharpe_ratio: 1.8,
+                max_drawdown: -4.2,
+                win_rate: 0.55,
+                profit_factor: 1.6,
+                key_findings: vec!["Well-controlled drawdown under 10%".to_string()],
+            },
+            backtest_results: None,
+            optimization_results: None,
+            risk_analysis: RiskAnalysis {
+                value_at_risk_95: 120.0,
+                conditional_var_95: 180.0,
+                max_consecutive_losses: 5,
+                recovery_factor: 3.0,
+                downside_deviation: 0.8,
+                tail_ratio: 1.1,
+                risk_of_ruin: None,
+            },
+            recommendations: vec![Recommendation {
+                category: RecommendationCategory::RiskManagement,
+                priority: Priority::High,
+                title: "Tighten stops".to_string(),
+                description: "Losses cluster around the open".to_string(),
+                impact: "Lower drawdown".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_templates_choose_sections_and_branding() {
+        let report = report();
+        let branding = Branding {
+            organization: Some("Acme Capital".to_string()),
+            primary_color: "#123456".to_string(),
+            ..Default::default()
+        };
+
+        let investor = ReportTemplate::investor_summary(ReportFormat::Html)
+            .with_branding(branding)
+            .render(&report)
+            .unwrap();
+        assert!(investor.contains("Acme Capital"));
+        assert!(investor.contains("#123456"));
+        assert!(investor.contains("Risk Analysis"));
+        assert!(!investor.contains("Tighten stops"));
+
+        let custom = ReportTemplate::new("titles", ReportFormat::Markdown)
+            .with_sections(vec![ReportSection::Recommendations, ReportSection::Summary])
+            .with_body("{{#each sections}}{{title}};{{/each}} {{report.metadata.author}}")
+            .render(&report)
+            .unwrap();
+        assert_eq!(custom, "Recommendations;Executive Summary; research");
+
+        assert!(matches!(
+            ReportTemplate::new("csv", ReportFormat::Csv).render(&report),
+            Err(TemplateError::UnsupportedFormat(_))
+        ));
+    }
+}
@@ -0,0 +1,331 @@
+//! Paged, filtered trade tables
+//!
+//! A run with hundreds of thousands of trades cannot be sent to the browser
+//! whole. Pages are cut from the fill ledger on the server instead: fills
+//! are grouped into trades from flat to flat, as `trade_windows` does for
+//! replays, filtered, and returned a page at a time with a cursor to resume
+//! from. Rows are built in one pass over the ledger without copying it, and
+//! the pass stops once the page is full. Trades keep their index in the
+//! ledger's sequence of trades, so a row links straight to its replay.
+//! Stored ledgers are paged from a seek point near the cursor, see
+//! `TradeLedgerStore`.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+
+use crate::backtesting::metrics::TradeRecord;
+use crate::strategy::OrderSide;
+
+/// Rows per page unless asked otherwise
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Most rows a page may hold
+pub const MAX_PAGE_SIZE: usize = 1_000;
+
+/// Which side of zero a trade's net P&L falls on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PnlSign {
+    Profit,
+    Loss,
+    Breakeven,
+}
+
+/// Trades to include in a page; unset fields do not filter
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TradeFilter {
+    /// Earliest opening time, inclusive
+    #[serde(default)]
+    pub from: Option<DateTime<Utc>>,
+    /// Latest opening time, exclusive
+    #[serde(default)]
+    pub to: Option<DateTime<Utc>>,
+    /// Side of the opening fill
+    #[serde(default)]
+    pub side: Option<OrderSide>,
+    /// Open trades have no P&L and never match
+    #[serde(default)]
+    pub pnl: Option<PnlSign>,
+    /// Tag of the order that opened the trade
+    #[serde(default)]
+    pub tag: Option<String>,
+}
+
+impl TradeFilter {
+    pub fn matches(&self, row: &TradeRow) -> bool {
+        self.from.is_none_or(|from| row.opened_at >= from)
+            && self.to.is_none_or(|to| row.opened_at < to)
+            && self.side.is_none_or(|side| row.side == side)
+            && self.pnl.is_none_or(|sign| row.pnl.is_some_and(|pnl| match sign {
+                PnlSign::Profit => pnl > Decimal::ZERO,
+                PnlSign::Loss => pnl < Decimal::ZERO,
+                PnlSign::Breakeven => pnl.is_zero(),
+            }))
+            && self.tag.as_ref().is_none_or(|tag| row.tag.as_ref() == Some(tag))
+    }
+}
+
+/// One trade of a ledger, from flat to flat
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradeRow {
+    /// Position in the ledger's sequence of trades, as used by replays
+    pub index: usize,
+    /// Side of the opening fill
+    pub side: OrderSide,
+    pub opened_at: DateTime<Utc>,
+    /// `None` when the position was still open at the end of the ledger
+    pub closed_at: Option<DateTime<Utc>>,
+    /// Largest absolute position held
+    pub quantity: i32,
+    /// Average price of the fills adding to the position
+    pub entry_price: Decimal,
+    /// Average price of the fills reducing it
+    pub exit_price: Option<Decimal>,
+    /// Net of commission; `None` while the trade is open
+    pub pnl: Option<Decimal>,
+    pub commission: Decimal,
+    pub fills: usize,
+    #[serde(default)]
+    pub tag: Option<String>,
+}
+
+/// A page of a trade table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradePage {
+    pub trades: Vec<TradeRow>,
+    /// Cursor of the next page; `None` on the last
+    pub next_cursor: Option<usize>,
+}
+
+impl TradePage {
+    /// Up to `limit` trades matching `filter`, starting at trade index `cursor`
+    pub fn from_ledger(
+        ledger: &[TradeRecord],
+        filter: &TradeFilter,
+        cursor: usize,
+        limit: usize,
+        point_value: Decimal,
+    ) -> Self {
+        Self::from_fills(ledger.iter(), 0, filter, cursor, limit, point_value)
+    }
+
+    /// A page from fills starting at a trade boundary, the opening fill of
+    /// trade `first_index`
+    ///
+    /// Used to page a stored ledger from a seek point rather than its start.
+    pub fn from_fills<I>(
+        fills: I,
+        first_index: usize,
+        filter: &TradeFilter,
+        cursor: usize,
+        limit: usize,
+        point_value: Decimal,
+    ) -> Self
+    where
+        I: Iterator,
+        I::Item: Borrow<TradeRecord>,
+    {
+        let limit = limit.clamp(1, MAX_PAGE_SIZE);
+        let mut trades: Vec<TradeRow> = TradeRows::new(fills, first_index, point_value)
+            .filter(|row| row.index >= cursor && filter.matches(row))
+            .take(limit + 1)
+            .collect();
+
+        let next_cursor = (trades.len() > limit).then(|| {
+            trades.truncate(limit);
+            trades[limit - 1].index + 1
+        });
+        Self { trades, next_cursor }
+    }
+}
+
+/// A trade being built up fill by fill
+struct OpenTrade {
+    index: usize,
+    side: OrderSide,
+    opened_at: DateTime<Utc>,
+    tag: Option<String>,
+    max_position: i32,
+    fills: usize,
+    commission: Decimal,
+    /// Sale proceeds less purchase costs, in points
+    cash: Decimal,
+    entry: (i32, Decimal),
+    exit: (i32, Decimal),
+}
+
+impl OpenTrade {
+    fn new(index: usize, fill: &TradeRecord) -> Self {
+        Self {
+            index,
+            side: fill.side,
+            opened_at: fill.timestamp,
+            tag: fill.tag.clone(),
+            max_position: 0,
+            fills: 0,
+            commission: Decimal::ZERO,
+            cash: Decimal::ZERO,
+            entry: (0, Decimal::ZERO),
+            exit: (0, Decimal::ZERO),
+        }
+    }
+
+    /// Add `quantity` contracts of `fill`, with its commission in proportion
+    fn add(&mut self, fill: &TradeRecord, quantity: i32) {
+        let contracts = Decimal::from(quantity);
+        let notional = fill.price * contracts;
+        self.fills += 1;
+        self.commission += fill.commission * contracts / Decimal::from(fill.quantity);
+        self.cash += match fill.side {
+            OrderSide::Buy => -notional,
+            OrderSide::Sell => notional,
+        };
+        let leg = if fill.side == self.side { &mut self.entry } else { &mut self.exit };
+        leg.0 += quantity;
+        leg.1 += notional;
+    }
+
+    fn finish(self, closed_at: Option<DateTime<Utc>>, point_value: Decimal) -> TradeRow {
+        let average = |(quantity, notional): (i32, Decimal)| (quantity > 0).then(|| notional / Decimal::from(quantity));
+        TradeRow {
+            index: self.index,
+            side: self.side,
+            opened_at: self.opened_at,
+            closed_at,
+            quantity: self.max_position,
+            entry_price: average(self.entry).unwrap_or_default(),
+            exit_price: average(self.exit),
+            pnl: closed_at.map(|_| self.cash * point_value - self.commission),
+            commission: self.commission,
+            fills: self.fills,
+            tag: self.tag,
+        }
+    }
+}
+
+/// Trades of a ledger in order, built as the ledger is read
+struct TradeRows<I> {
+    fills: I,
+    point_value: Decimal,
+    position: i32,
+    next_index: usize,
+    open: Option<OpenTrade>,
+}
+
+impl<I> TradeRows<I> {
+    fn new(fills: I, first_index: usize, point_value: Decimal) -> Self {
+        Self { fills, point_value, position: 0, next_index: first_index, open: None }
+    }
+
+    fn start(&mut self, fill: &TradeRecord) -> OpenTrade {
+        self.next_index += 1;
+        OpenTrade::new(self.next_index - 1, fill)
+    }
+}
+
+impl<I> Iterator for TradeRows<I>
+where
+    I: Iterator,
+    I::Item: Borrow<TradeRecord>,
+{
+    type Item = TradeRow;
+
+    fn next(&mut self) -> Option<TradeRow> {
+        while let Some(fill) = self.fills.next() {
+            let fill = fill.borrow();
+            if fill.quantity <= 0 {
+                continue;
+            }
+            let signed = match fill.side {
+                OrderSide::Buy => fill.quantity,
+                OrderSide::Sell => -fill.quantity,
+            };
+            if self.position == 0 {
+                self.open = Some(self.start(fill));
+            }
+
+            // A fill reversing the position closes this trade and opens the next
+            let reverses = self.position != 0 && self.position.signum() != signed.signum()
+                && signed.abs() > self.position.abs();
+            let closing = if reverses { self.position.abs() } else { fill.quantity };
+            self.position += signed;
+            let mut trade = self.open.take()?;
+            trade.add(fill, closing);
+
+            if reverses {
+                let mut opened = self.start(fill);
+                opened.add(fill, fill.quantity - closing);
+                opened.max_position = fill.quantity - closing;
+                self.open = Some(opened);
+                return Some(trade.finish(Some(fill.timestamp), self.point_value));
+            }
+            trade.max_position = trade.max_position.max(self.position.abs());
+            if self.position == 0 {
+                return Some(trade.finish(Some(fill.timestamp), self.point_value));
+            }
+            self.open = Some(trade);
+        }
+        self.open.take().map(|trade| trade.finish(None, self.point_value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reporting::replay::trade_windows;
+    use chrono::TimeZone;
+
+    fn fill(side: OrderSide, quantity: i32, price: i64, minute: u32, tag: Option<&str>) -> TradeRecord {
+        TradeRecord {
+            timestamp: Utc.with_ymd_and_hms(2024, 3, 12, 14, minute, 0).unwrap(),
+            side,
+            quantity,
+            price: Decimal::from(price),
+            commission: Decimal::from(quantity),
+            slippage: Decimal::ZERO,
+            tag: tag.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_pages_filter_and_match_replay_numbering() {
+        use OrderSide::{Buy, Sell};
+        let ledger = vec![
+            // Long one for +10 points
+            fill(Buy, 1, 18_000, 0, Some("breakout")),
+            fill(Sell, 1, 18_010, 1, None),
+            // Long two, reversed to short one, then flat at a loss
+            fill(Buy, 2, 18_020, 2, Some("fade")),
+            fill(Sell, 3, 18_015, 3, None),
+            fill(Buy, 1, 18_025, 4, None),
+            // Still open
+            fill(Sell, 1, 18_030, 5, Some("breakout")),
+        ];
+        let point_value = Decimal::from(2);
+        let all = TradePage::from_ledger(&ledger, &TradeFilter::default(), 0, 10, point_value);
+        assert_eq!(all.next_cursor, None);
+        assert_eq!(all.trades.len(), trade_windows(&ledger).len());
+        let [first, second, third, fourth] = &all.trades[..] else { panic!("four trades") };
+
+        // 10 points at 2 a point, less 1 commission a contract each way
+        assert_eq!(first.pnl, Some(Decimal::from(18)));
+        assert_eq!(first.tag.as_deref(), Some("breakout"));
+        assert_eq!((second.quantity, second.exit_price), (2, Some(Decimal::from(18_015))));
+        assert_eq!(second.pnl, Some(Decimal::from(-24)));
+        assert_eq!((third.side, third.quantity, third.opened_at), (Sell, 1, second.closed_at.unwrap()));
+        assert_eq!(third.pnl, Some(Decimal::from(-22)));
+        assert_eq!((fourth.closed_at, fourth.pnl), (None, None));
+
+        let losers = TradeFilter { pnl: Some(PnlSign::Loss), ..Default::default() };
+        let page = TradePage::from_ledger(&ledger, &losers, 0, 1, point_value);
+        assert_eq!((page.trades[0].index, page.next_cursor), (1, Some(2)));
+        let page = TradePage::from_ledger(&ledger, &losers, 2, 1, point_value);
+        assert_eq!((page.trades[0].index, page.next_cursor), (2, None));
+
+        let tagged = TradeFilter { tag: Some("breakout".to_string()), from: Some(first.closed_at.unwrap()), ..Default::default() };
+        let page = TradePage::from_ledger(&ledger, &tagged, 0, 10, point_value);
+        assert_eq!(page.trades.iter().map(|t| t.index).collect::<Vec<_>>(), vec![3]);
+    }
+}
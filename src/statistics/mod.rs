@@ -89,6 +89,42 @@ impl StatisticalAnalyzer {
         }
     }
     
+    /// Confidence interval for the mean of k-fold cross-validation scores
+    ///
+    /// Training sets of different folds overlap, so fold scores are correlated
+    /// and the plain standard error is too small. The Nadeau-Bengio correction
+    /// inflates the variance by `test_size / train_size` on top of `1 / k`.
+    pub fn cross_validated_interval(
+        fold_scores: &[f64],
+        test_train_ratio: f64,
+        confidence_level: f64,
+    ) -> ConfidenceInterval {
+        let k = fold_scores.len() as f64;
+        let mean = fold_scores.iter().sum::<f64>() / k;
+        
+        if fold_scores.len() < 2 {
+            return ConfidenceInterval {
+                lower_bound: f64::NEG_INFINITY,
+                upper_bound: f64::INFINITY,
+                confidence_level,
+                point_estimate: mean,
+            };
+        }
+        
+        let variance = fold_scores.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (k - 1.0);
+        let std_error = ((1.0 / k + test_train_ratio.max(0.0)) * variance).sqrt();
+        
+        let t_dist = StudentsT::new(0.0, 1.0, k - 1.0).unwrap();
+        let margin_of_error = t_dist.inverse_cdf((1.0 + confidence_level) / 2.0) * std_error;
+        
+        ConfidenceInterval {
+            lower_bound: mean - margin_of_error,
+            upper_bound: mean + margin_of_error,
+            confidence_level,
+            point_estimate: mean,
+        }
+    }
+    
     /// Bootstrap confidence interval for any statistic
     pub fn bootstrap_confidence_interval<F>(
         data: &[f64],
@@ -372,9 +408,306 @@ impl StatisticalAnalyzer {
             
             simulations.push(path);
         }
-        
+
         simulations
     }
+
+    /// Kolmogorov-Smirnov two-sample test for comparing return distributions
+    pub fn ks_test(sample1: &[f64], sample2: &[f64], confidence_level: f64) -> StatisticalTest {
+        let mut sorted1 = sample1.to_vec();
+        let mut sorted2 = sample2.to_vec();
+        sorted1.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        sorted2.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let n1 = sorted1.len();
+        let n2 = sorted2.len();
+
+        // Walk both empirical CDFs and track the largest vertical gap
+        let mut i = 0;
+        let mut j = 0;
+        let mut d_stat: f64 = 0.0;
+        while i < n1 && j < n2 {
+            let x = sorted1[i].min(sorted2[j]);
+            while i < n1 && sorted1[i] <= x {
+                i += 1;
+            }
+            while j < n2 && sorted2[j] <= x {
+                j += 1;
+            }
+            let cdf1 = i as f64 / n1 as f64;
+            let cdf2 = j as f64 / n2 as f64;
+            d_stat = d_stat.max((cdf1 - cdf2).abs());
+        }
+
+        // Asymptotic Kolmogorov distribution with Stephens' small-sample correction
+        let effective_n = (n1 * n2) as f64 / (n1 + n2) as f64;
+        let sqrt_n = effective_n.sqrt();
+        let lambda = (sqrt_n + 0.12 + 0.11 / sqrt_n) * d_stat;
+        let p_value = Self::kolmogorov_survival(lambda);
+
+        let is_significant = p_value < (1.0 - confidence_level);
+
+        let interpretation = if is_significant {
+            format!("Samples come from different distributions (D={:.4}, p={:.4})", d_stat, p_value)
+        } else {
+            format!("No evidence samples come from different distributions (D={:.4}, p={:.4})", d_stat, p_value)
+        };
+
+        StatisticalTest {
+            test_name: "Kolmogorov-Smirnov Two-Sample Test".to_string(),
+            statistic: d_stat,
+            p_value,
+            confidence_level,
+            is_significant,
+            interpretation,
+        }
+    }
+
+    /// Survival function of the Kolmogorov distribution
+    fn kolmogorov_survival(lambda: f64) -> f64 {
+        if lambda < 1e-3 {
+            return 1.0;
+        }
+
+        let mut sum = 0.0;
+        let mut sign = 1.0;
+        for j in 1..=100 {
+            let term = sign * (-2.0 * (j * j) as f64 * lambda * lambda).exp();
+            sum += term;
+            if term.abs() < 1e-12 {
+                break;
+            }
+            sign = -sign;
+        }
+
+        (2.0 * sum).clamp(0.0, 1.0)
+    }
+
+    /// Augmented Dickey-Fuller test for stationarity of a return or price series
+    ///
+    /// Regresses the first difference on a constant, the lagged level and
+    /// `max_lag` lagged differences. A significant result rejects the unit root,
+    /// i.e. the series is stationary.
+    pub fn adf_test(series: &[f64], max_lag: usize) -> StatisticalTest {
+        let diffs: Vec<f64> = series.windows(2).map(|w| w[1] - w[0]).collect();
+        let n_obs = diffs.len().saturating_sub(max_lag);
+        let n_regressors = 2 + max_lag;
+
+        if n_obs <= n_regressors {
+            return StatisticalTest {
+                test_name: "Augmented Dickey-Fuller Test".to_string(),
+                statistic: f64::NAN,
+                p_value: 1.0,
+                confidence_level: 0.95,
+                is_significant: false,
+                interpretation: "Insufficient observations for ADF test".to_string(),
+            };
+        }
+
+        // Design matrix rows: [1, y_{t-1}, dy_{t-1}, ..., dy_{t-p}]
+        let mut x_rows = Vec::with_capacity(n_obs);
+        let mut y = Vec::with_capacity(n_obs);
+        for t in max_lag..diffs.len() {
+            let mut row = Vec::with_capacity(n_regressors);
+            row.push(1.0);
+            row.push(series[t]);
+            for lag in 1..=max_lag {
+                row.push(diffs[t - lag]);
+            }
+            x_rows.push(row);
+            y.push(diffs[t]);
+        }
+
+        let (coefficients, std_errors) = match Self::ols(&x_rows, &y) {
+            Some(fit) => fit,
+            None => {
+                return StatisticalTest {
+                    test_name: "Augmented Dickey-Fuller Test".to_string(),
+                    statistic: f64::NAN,
+                    p_value: 1.0,
+                    confidence_level: 0.95,
+                    is_significant: false,
+                    interpretation: "ADF regression is singular (constant series?)".to_string(),
+                };
+            }
+        };
+
+        let adf_stat = coefficients[1] / std_errors[1];
+        let p_value = Self::mackinnon_p_value(adf_stat);
+
+        let is_significant = p_value < 0.05;
+
+        let interpretation = if is_significant {
+            format!("Series is stationary, unit root rejected (ADF={:.3})", adf_stat)
+        } else {
+            format!("Series appears non-stationary, unit root not rejected (ADF={:.3})", adf_stat)
+        };
+
+        StatisticalTest {
+            test_name: "Augmented Dickey-Fuller Test".to_string(),
+            statistic: adf_stat,
+            p_value,
+            confidence_level: 0.95,
+            is_significant,
+            interpretation,
+        }
+    }
+
+    /// MacKinnon (1994) approximate p-value for the constant-only ADF regression
+    fn mackinnon_p_value(tau: f64) -> f64 {
+        const TAU_MAX: f64 = 2.74;
+        const TAU_MIN: f64 = -18.83;
+        const TAU_STAR: f64 = -1.61;
+
+        if tau > TAU_MAX {
+            return 1.0;
+        }
+        if tau < TAU_MIN {
+            return 0.0;
+        }
+
+        let z = if tau <= TAU_STAR {
+            2.1659 + 1.4412 * tau + 0.038269 * tau.powi(2)
+        } else {
+            1.7339 + 0.93202 * tau - 0.12745 * tau.powi(2) - 0.010368 * tau.powi(3)
+        };
+
+        Normal::new(0.0, 1.0).unwrap().cdf(z)
+    }
+
+    /// Ordinary least squares returning coefficients and their standard errors
+    fn ols(x_rows: &[Vec<f64>], y: &[f64]) -> Option<(Vec<f64>, Vec<f64>)> {
+        let n = y.len();
+        let k = x_rows.first()?.len();
+
+        // Build X'X augmented with the identity so one elimination yields the inverse
+        let mut augmented = vec![vec![0.0; 2 * k]; k];
+        for row in x_rows {
+            for a in 0..k {
+                for b in 0..k {
+                    augmented[a][b] += row[a] * row[b];
+                }
+            }
+        }
+        for (a, aug_row) in augmented.iter_mut().enumerate() {
+            aug_row[k + a] = 1.0;
+        }
+
+        // Gauss-Jordan elimination with partial pivoting
+        for col in 0..k {
+            let pivot = (col..k).max_by(|&a, &b| {
+                augmented[a][col].abs().partial_cmp(&augmented[b][col].abs()).unwrap()
+            })?;
+            if augmented[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            augmented.swap(col, pivot);
+
+            let pivot_value = augmented[col][col];
+            for value in augmented[col].iter_mut() {
+                *value /= pivot_value;
+            }
+            for r in 0..k {
+                if r != col {
+                    let factor = augmented[r][col];
+                    if factor != 0.0 {
+                        for c in 0..2 * k {
+                            augmented[r][c] -= factor * augmented[col][c];
+                        }
+                    }
+                }
+            }
+        }
+
+        let xtx_inv: Vec<Vec<f64>> = augmented.iter().map(|row| row[k..].to_vec()).collect();
+
+        let mut xty = vec![0.0; k];
+        for (row, &target) in x_rows.iter().zip(y) {
+            for a in 0..k {
+                xty[a] += row[a] * target;
+            }
+        }
+
+        let coefficients: Vec<f64> = xtx_inv.iter()
+            .map(|inv_row| inv_row.iter().zip(&xty).map(|(a, b)| a * b).sum())
+            .collect();
+
+        let rss: f64 = x_rows.iter()
+            .zip(y)
+            .map(|(row, &target)| {
+                let fitted: f64 = row.iter().zip(&coefficients).map(|(a, b)| a * b).sum();
+                (target - fitted).powi(2)
+            })
+            .sum();
+        let sigma2 = rss / (n - k) as f64;
+
+        let std_errors = (0..k).map(|a| (sigma2 * xtx_inv[a][a]).sqrt()).collect();
+
+        Some((coefficients, std_errors))
+    }
+
+    /// Wald-Wolfowitz runs test for randomness of a return sequence around its median
+    pub fn runs_test(data: &[f64]) -> StatisticalTest {
+        let mut sorted = data.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let median = if sorted.is_empty() {
+            0.0
+        } else if sorted.len() % 2 == 0 {
+            (sorted[sorted.len() / 2 - 1] + sorted[sorted.len() / 2]) / 2.0
+        } else {
+            sorted[sorted.len() / 2]
+        };
+
+        // Values equal to the median carry no sign and are dropped
+        let signs: Vec<bool> = data.iter()
+            .filter(|&&x| x != median)
+            .map(|&x| x > median)
+            .collect();
+
+        let n_above = signs.iter().filter(|&&s| s).count() as f64;
+        let n_below = signs.len() as f64 - n_above;
+        let n = n_above + n_below;
+
+        if n_above == 0.0 || n_below == 0.0 {
+            return StatisticalTest {
+                test_name: "Wald-Wolfowitz Runs Test".to_string(),
+                statistic: f64::NAN,
+                p_value: 1.0,
+                confidence_level: 0.95,
+                is_significant: false,
+                interpretation: "Insufficient variation for runs test".to_string(),
+            };
+        }
+
+        let runs = 1 + signs.windows(2).filter(|w| w[0] != w[1]).count();
+
+        let expected_runs = 2.0 * n_above * n_below / n + 1.0;
+        let variance_runs = 2.0 * n_above * n_below * (2.0 * n_above * n_below - n)
+            / (n * n * (n - 1.0));
+        let z = (runs as f64 - expected_runs) / variance_runs.sqrt();
+
+        let normal = Normal::new(0.0, 1.0).unwrap();
+        let p_value = 2.0 * (1.0 - normal.cdf(z.abs()));
+
+        let is_significant = p_value < 0.05;
+
+        let interpretation = if !is_significant {
+            "Sequence is consistent with randomness".to_string()
+        } else if z < 0.0 {
+            format!("Fewer runs than expected, returns cluster/trend ({} runs)", runs)
+        } else {
+            format!("More runs than expected, returns mean-revert ({} runs)", runs)
+        };
+
+        StatisticalTest {
+            test_name: "Wald-Wolfowitz Runs Test".to_string(),
+            statistic: z,
+            p_value,
+            confidence_level: 0.95,
+            is_significant,
+            interpretation,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -591,4 +924,64 @@ mod tests {
         
         assert!(sortino_positive.is_infinite() || sortino_positive.is_nan());
     }
+
+    #[test]
+    fn test_ks_test() {
+        let sample1 = vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.0];
+        let ks = StatisticalAnalyzer::ks_test(&sample1, &sample1, 0.95);
+        assert!(approx_equal(ks.statistic, 0.0, 1e-12));
+        assert!(!ks.is_significant);
+
+        let sample2: Vec<f64> = sample1.iter().map(|x| x + 5.0).collect();
+        let ks = StatisticalAnalyzer::ks_test(&sample1, &sample2, 0.95);
+        assert!(approx_equal(ks.statistic, 1.0, 1e-12));
+        assert!(ks.is_significant);
+    }
+
+    #[test]
+    fn test_adf_test() {
+        // Alternating series is strongly mean-reverting
+        let stationary: Vec<f64> = (0..200).map(|i| if i % 2 == 0 { 1.0 } else { -1.0 } + (i % 7) as f64 * 0.01).collect();
+        let adf = StatisticalAnalyzer::adf_test(&stationary, 1);
+        assert!(adf.statistic < -2.86);
+        assert!(adf.is_significant);
+
+        // Deterministic random-walk-like drift does not reject the unit root
+        let mut level = 0.0;
+        let trending: Vec<f64> = (0..200).map(|i| { level += ((i * 37) % 11) as f64 - 4.0; level }).collect();
+        let adf = StatisticalAnalyzer::adf_test(&trending, 1);
+        assert!(!adf.is_significant);
+
+        let adf = StatisticalAnalyzer::adf_test(&[1.0, 2.0, 3.0], 2);
+        assert!(adf.statistic.is_nan());
+    }
+
+    #[test]
+    fn test_cross_validated_interval() {
+        let scores = vec![0.8, 1.1, 0.9, 1.3, 0.9];
+        let naive = StatisticalAnalyzer::confidence_interval(&scores, 0.95);
+        let corrected = StatisticalAnalyzer::cross_validated_interval(&scores, 0.25, 0.95);
+        
+        assert!(approx_equal(corrected.point_estimate, 1.0, 1e-12));
+        // Overlapping training sets widen the interval
+        assert!(corrected.upper_bound - corrected.lower_bound > naive.upper_bound - naive.lower_bound);
+        
+        let single = StatisticalAnalyzer::cross_validated_interval(&[0.5], 0.25, 0.95);
+        assert!(single.lower_bound.is_infinite());
+    }
+
+    #[test]
+    fn test_runs_test() {
+        // Perfect alternation has the maximum number of runs
+        let alternating: Vec<f64> = (0..40).map(|i| if i % 2 == 0 { 1.0 } else { -1.0 }).collect();
+        let runs = StatisticalAnalyzer::runs_test(&alternating);
+        assert!(runs.statistic > 0.0);
+        assert!(runs.is_significant);
+
+        // Two long blocks have the minimum number of runs
+        let clustered: Vec<f64> = (0..40).map(|i| if i < 20 { -1.0 } else { 1.0 }).collect();
+        let runs = StatisticalAnalyzer::runs_test(&clustered);
+        assert!(runs.statistic < 0.0);
+        assert!(runs.is_significant);
+    }
 }
\ No newline at end of file
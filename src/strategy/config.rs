@@ -208,6 +208,91 @@ impl StrategyConfig {
         
         config
     }
+    
+    /// Create configuration for VWAP reversion strategy
+    pub fn vwap_reversion() -> Self {
+        let mut config = Self::default();
+        config.name = "VwapReversion".to_string();
+        
+        config.parameters.custom.insert(
+            "entry_std_devs".to_string(),
+            ParameterValue::Float(2.0),
+        );
+        config.parameters.custom.insert(
+            "exit_std_devs".to_string(),
+            ParameterValue::Float(0.25),
+        );
+        config.parameters.custom.insert(
+            "min_trades".to_string(),
+            ParameterValue::Integer(200),
+        );
+        
+        config
+    }
+    
+    /// Create configuration for opening range breakout strategy
+    pub fn opening_range_breakout() -> Self {
+        let mut config = Self::default();
+        config.name = "OpeningRangeBreakout".to_string();
+        config.parameters.max_holding_time = Some(3600);
+        
+        config.parameters.custom.insert(
+            "range_minutes".to_string(),
+            ParameterValue::Integer(15),
+        );
+        config.parameters.custom.insert(
+            "breakout_buffer".to_string(),
+            ParameterValue::Decimal(Decimal::from_str_exact("0.5").unwrap()),
+        );
+        config.parameters.custom.insert(
+            "target_multiple".to_string(),
+            ParameterValue::Decimal(Decimal::ONE),
+        );
+        
+        config
+    }
+    
+    /// Create configuration for absorption detection strategy
+    pub fn absorption() -> Self {
+        let mut config = Self::default();
+        config.name = "Absorption".to_string();
+        
+        config.parameters.custom.insert(
+            "absorption_volume".to_string(),
+            ParameterValue::Integer(300),
+        );
+        config.parameters.custom.insert(
+            "window_ms".to_string(),
+            ParameterValue::Integer(5000),
+        );
+        config.parameters.custom.insert(
+            "target_points".to_string(),
+            ParameterValue::Decimal(Decimal::from(2)),
+        );
+        
+        config
+    }
+    
+    /// Create configuration for delta divergence strategy
+    pub fn delta_divergence() -> Self {
+        let mut config = Self::default();
+        config.name = "DeltaDivergence".to_string();
+        
+        config.parameters.custom.insert(
+            "lookback_trades".to_string(),
+            ParameterValue::Integer(500),
+        );
+        config.parameters.custom.insert(
+            "min_delta_divergence".to_string(),
+            ParameterValue::Integer(50),
+        );
+        config.parameters.custom.insert(
+            "target_points".to_string(),
+            ParameterValue::Decimal(Decimal::from(3)),
+        );
+        
+        config
+    }
 }
 
 /// Load configuration from YAML file
@@ -0,0 +1,261 @@
+//! Absorption Detection Strategy
+//!
+//! This strategy looks for large passive orders that soak up aggressive
+//! flow without letting price through. When heavy selling hits the bid
+//! and the bid holds, a buyer is absorbing supply and price tends to
+//! turn up (and vice versa at the ask).
+
+use crate::data::{MarketDataType, TickData};
+use crate::market::OrderBookState;
+use crate::strategy::{
+    Order, OrderSide, Position, Signal, SignalType,
+    Strategy, StrategyConfig, StrategyContext, StrategyMetrics,
+};
+use crate::strategy::traits::OrderFill;
+use rust_decimal::Decimal;
+use tracing::{debug, info};
+
+crate::strategy_params! {
+    /// Parameters for the absorption strategy
+    pub struct AbsorptionConfig {
+        /// Aggressive volume that must trade into a single price without breaking it
+        absorption_volume: i64 = 300, min = 1;
+
+        /// Window in milliseconds in which the volume must accumulate
+        window_ms: i64 = 5_000, min = 1;
+
+        /// Profit target in points from entry
+        target_points: Decimal = Decimal::from(2), min = Decimal::ZERO;
+
+        /// Distance beyond the absorption price that invalidates the setup
+        invalidation_points: Decimal = Decimal::from_str_exact("0.5").unwrap(), min = Decimal::ZERO;
+    }
+}
+
+/// Which side of the book is absorbing aggressive flow
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AbsorbingSide {
+    /// Passive buyers on the bid absorbing market sells
+    Bid,
+    /// Passive sellers on the ask absorbing market buys
+    Ask,
+}
+
+/// Aggressive volume accumulating at a single price
+#[derive(Debug, Clone)]
+struct AbsorptionZone {
+    price: Decimal,
+    side: AbsorbingSide,
+    volume: i64,
+    started_at: i64,
+}
+
+/// Absorption detection scalping strategy
+///
+/// # Strategy Logic
+/// - Classifies each trade as hitting the bid or lifting the ask
+/// - Accumulates aggressive volume trading into one price within `window_ms`
+/// - Enters long when `absorption_volume` sells hit a bid that holds
+/// - Enters short when `absorption_volume` buys lift an ask that holds
+/// - Exits at `target_points` or when price trades through the absorption price
+pub struct AbsorptionStrategy {
+    /// Strategy configuration
+    config: StrategyConfig,
+
+    /// Strategy-specific parameters
+    params: AbsorptionConfig,
+
+    /// Current position
+    position: Position,
+
+    /// Performance metrics
+    metrics: StrategyMetrics,
+
+    /// Zone currently accumulating volume
+    zone: Option<AbsorptionZone>,
+
+    /// Price that was absorbed for the open trade
+    entry_zone_price: Option<Decimal>,
+}
+
+impl AbsorptionStrategy {
+    /// Create a new absorption strategy
+    pub fn new(config: StrategyConfig) -> Self {
+        let params = AbsorptionConfig::from_strategy_config(&config);
+
+        Self {
+            config,
+            params,
+            position: Position::new(),
+            metrics: StrategyMetrics::default(),
+            zone: None,
+            entry_zone_price: None,
+        }
+    }
+
+    /// Which passive side a trade executed against
+    fn classify(tick: &TickData, book: &OrderBookState) -> Option<AbsorbingSide> {
+        match (book.best_bid, book.best_ask) {
+            (Some(bid), _) if tick.price <= bid => Some(AbsorbingSide::Bid),
+            (_, Some(ask)) if tick.price >= ask => Some(AbsorbingSide::Ask),
+            _ => None,
+        }
+    }
+
+    /// Update the absorption zone with a trade and return a signal once it fires
+    fn track_absorption(&mut self, tick: &TickData, book: &OrderBookState) -> Option<Signal> {
+        let window_ns = self.params.window_ms * 1_000_000;
+
+        // Price trading through the zone means the passive side gave way
+        if let Some(zone) = &self.zone {
+            let broken = match zone.side {
+                AbsorbingSide::Bid => tick.price < zone.price,
+                AbsorbingSide::Ask => tick.price > zone.price,
+            };
+            if broken || tick.timestamp - zone.started_at > window_ns {
+                self.zone = None;
+            }
+        }
+
+        let side = Self::classify(tick, book)?;
+
+        match &mut self.zone {
+            Some(zone) if zone.side == side && zone.price == tick.price => {
+                zone.volume += tick.volume as i64;
+            }
+            _ => {
+                self.zone = Some(AbsorptionZone {
+                    price: tick.price,
+                    side,
+                    volume: tick.volume as i64,
+                    started_at: tick.timestamp,
+                });
+            }
+        }
+
+        let zone = self.zone.as_ref()?;
+        if zone.volume < self.params.absorption_volume {
+            return None;
+        }
+
+        debug!("Absorption of {} contracts at {} ({:?})", zone.volume, zone.price, zone.side);
+        let reason = format!("{} contracts absorbed at {}", zone.volume, zone.price);
+        let signal = match zone.side {
+            AbsorbingSide::Bid => Signal::long(0.75, zone.price, reason),
+            AbsorbingSide::Ask => Signal::short(0.75, zone.price, reason),
+        };
+        self.zone = None;
+        Some(signal)
+    }
+
+    /// Check target and invalidation for an open trade
+    fn check_exit(&self, current_price: Decimal) -> Option<Signal> {
+        if self.position.is_flat() {
+            return None;
+        }
+
+        let entry = self.position.avg_entry_price;
+        let zone_price = self.entry_zone_price.unwrap_or(entry);
+
+        if self.position.is_long() {
+            if current_price >= entry + self.params.target_points {
+                return Some(Signal::exit(current_price, "Absorption target reached".to_string()));
+            }
+            if current_price < zone_price - self.params.invalidation_points {
+                return Some(Signal::exit(current_price, "Bid gave way".to_string()));
+            }
+        } else {
+            if current_price <= entry - self.params.target_points {
+                return Some(Signal::exit(current_price, "Absorption target reached".to_string()));
+            }
+            if current_price > zone_price + self.params.invalidation_points {
+                return Some(Signal::exit(current_price, "Ask gave way".to_string()));
+            }
+        }
+
+        None
+    }
+}
+
+impl Strategy for AbsorptionStrategy {
+    fn on_tick(&mut self, tick: &TickData, context: &StrategyContext) -> Option<Order> {
+        if let Some(mid_price) = context.mid_price() {
+            self.position.update_unrealized_pnl(mid_price);
+        }
+
+        if tick.mdt != MarketDataType::Trade {
+            return None;
+        }
+
+        if let Some(signal) = self.check_exit(tick.price) {
+            if signal.signal_type == SignalType::Exit {
+                let side = if self.position.is_long() {
+                    OrderSide::Sell
+                } else {
+                    OrderSide::Buy
+                };
+                return Some(Order::market(side, self.position.size.abs()));
+            }
+        }
+
+        let signal = self.track_absorption(tick, &context.order_book)?;
+
+        if !self.position.is_flat() {
+            return None;
+        }
+
+        self.entry_zone_price = Some(signal.price);
+
+        match signal.signal_type {
+            // Join the absorbing side passively
+            SignalType::Long => Some(Order::limit(
+                OrderSide::Buy,
+                self.config.parameters.position_size,
+                signal.price,
+            )),
+            SignalType::Short => Some(Order::limit(
+                OrderSide::Sell,
+                self.config.parameters.position_size,
+                signal.price,
+            )),
+            _ => None,
+        }
+    }
+
+    fn on_order_fill(&mut self, fill: &OrderFill) {
+        let was_flat = self.position.is_flat();
+        self.position.apply_fill(fill);
+
+        if !was_flat && self.position.is_flat() {
+            let pnl = self.position.realized_pnl;
+            let duration = fill.timestamp.signed_duration_since(
+                self.position.open_time.unwrap_or(fill.timestamp)
+            ).num_seconds() as f64;
+
+            self.metrics.update_trade(pnl, duration);
+            self.entry_zone_price = None;
+        }
+
+        info!("Fill: {:?} {} @ {}, Position: {}",
+            fill.side, fill.quantity, fill.price, self.position.size);
+    }
+
+    fn get_parameters(&self) -> &StrategyConfig {
+        &self.config
+    }
+
+    fn reset(&mut self) {
+        self.position.reset();
+        self.metrics = StrategyMetrics::default();
+        self.zone = None;
+        self.entry_zone_price = None;
+    }
+
+    fn get_position(&self) -> &Position {
+        &self.position
+    }
+
+    fn get_metrics(&self) -> StrategyMetrics {
+        self.metrics.clone()
+    }
+}
@@ -5,7 +5,7 @@
 //! it to bounce back to the other side.
 
 use crate::data::TickData;
-use crate::market::OrderBookState;
+use crate::market::{BookDepth, OrderBookState};
 use crate::strategy::{
     Order, OrderSide, Position, Signal, SignalType,
     Strategy, StrategyConfig, StrategyContext, StrategyMetrics,
@@ -14,6 +14,20 @@ use crate::strategy::traits::OrderFill;
 use rust_decimal::Decimal;
 use tracing::{debug, info};
 
+crate::strategy_params! {
+    /// Parameters for the bid-ask bounce strategy
+    pub struct BidAskBounceConfig {
+        /// Minimum bounce expected (in ticks)
+        bounce_threshold: Decimal = Decimal::from_str_exact("0.5").unwrap(), min = Decimal::ZERO;
+
+        /// Minimum volume required at touch
+        min_volume: i32 = 100, min = 0;
+
+        /// Offset from bid/ask for entry
+        entry_offset: Decimal = Decimal::from_str_exact("0.1").unwrap(), min = Decimal::ZERO;
+    }
+}
+
 /// Bid-ask bounce scalping strategy
 /// 
 /// # Strategy Logic
@@ -60,30 +74,15 @@ enum TouchSide {
 impl BidAskBounceStrategy {
     /// Create a new bid-ask bounce strategy
     pub fn new(config: StrategyConfig) -> Self {
-        // Extract custom parameters
-        let bounce_threshold = config.parameters.custom
-            .get("bounce_threshold")
-            .and_then(|v| v.as_decimal())
-            .unwrap_or(Decimal::from_str_exact("0.5").unwrap());
-        
-        let min_volume = config.parameters.custom
-            .get("min_volume")
-            .and_then(|v| v.as_f64())
-            .map(|v| v as i32)
-            .unwrap_or(100);
-        
-        let entry_offset = config.parameters.custom
-            .get("entry_offset")
-            .and_then(|v| v.as_decimal())
-            .unwrap_or(Decimal::from_str_exact("0.1").unwrap());
+        let params = BidAskBounceConfig::from_strategy_config(&config);
         
         Self {
             config,
             position: Position::new(),
             metrics: StrategyMetrics::default(),
-            bounce_threshold,
-            min_volume,
-            entry_offset,
+            bounce_threshold: params.bounce_threshold,
+            min_volume: params.min_volume,
+            entry_offset: params.entry_offset,
             last_touch_side: None,
             touch_count: 0,
             required_touches: 2,
@@ -293,4 +292,9 @@ impl Strategy for BidAskBounceStrategy {
     fn get_metrics(&self) -> StrategyMetrics {
         self.metrics.clone()
     }
+    
+    // Touches are read off the best bid and ask
+    fn book_depth(&self) -> BookDepth {
+        BookDepth::TopOfBook
+    }
 }
\ No newline at end of file
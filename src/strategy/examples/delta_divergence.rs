@@ -0,0 +1,248 @@
+//! Delta Divergence Strategy
+//!
+//! This strategy compares price with cumulative delta (aggressive buy
+//! volume minus aggressive sell volume). When price makes a new high but
+//! delta does not, buyers are exhausted and the move is likely to fail
+//! (and vice versa at lows).
+
+use crate::data::{MarketDataType, TickData};
+use crate::market::OrderBookState;
+use crate::strategy::{
+    Order, OrderSide, Position, Signal, SignalType,
+    Strategy, StrategyConfig, StrategyContext, StrategyMetrics,
+};
+use crate::strategy::traits::OrderFill;
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+use tracing::{debug, info};
+
+crate::strategy_params! {
+    /// Parameters for the delta divergence strategy
+    pub struct DeltaDivergenceConfig {
+        /// Number of trades in the swing lookback window
+        lookback_trades: usize = 500, min = 2;
+
+        /// How far delta must lag the prior swing extreme to count as divergence
+        min_delta_divergence: i64 = 50, min = 0;
+
+        /// Profit target in points from entry
+        target_points: Decimal = Decimal::from(3), min = Decimal::ZERO;
+    }
+}
+
+/// Price and cumulative delta at a trade
+#[derive(Debug, Clone, Copy)]
+struct DeltaPoint {
+    price: Decimal,
+    cumulative_delta: i64,
+}
+
+/// Cumulative delta divergence strategy
+///
+/// # Strategy Logic
+/// - Classifies trades as buyer or seller initiated against the order book
+/// - Maintains cumulative delta and a window of the last `lookback_trades` trades
+/// - Enters short when price exceeds the window high while delta is
+///   `min_delta_divergence` below its value at that high
+/// - Enters long on the mirror image at the window low
+/// - Exits at `target_points` or on the configured stop loss
+pub struct DeltaDivergenceStrategy {
+    /// Strategy configuration
+    config: StrategyConfig,
+
+    /// Strategy-specific parameters
+    params: DeltaDivergenceConfig,
+
+    /// Current position
+    position: Position,
+
+    /// Performance metrics
+    metrics: StrategyMetrics,
+
+    /// Running aggressive buy minus sell volume
+    cumulative_delta: i64,
+
+    /// Recent trades for swing detection
+    window: VecDeque<DeltaPoint>,
+}
+
+impl DeltaDivergenceStrategy {
+    /// Create a new delta divergence strategy
+    pub fn new(config: StrategyConfig) -> Self {
+        let params = DeltaDivergenceConfig::from_strategy_config(&config);
+
+        Self {
+            config,
+            window: VecDeque::with_capacity(params.lookback_trades),
+            params,
+            position: Position::new(),
+            metrics: StrategyMetrics::default(),
+            cumulative_delta: 0,
+        }
+    }
+
+    /// Current cumulative delta
+    pub fn cumulative_delta(&self) -> i64 {
+        self.cumulative_delta
+    }
+
+    /// Signed volume of a trade: positive when buyers lifted the ask
+    fn signed_volume(tick: &TickData, book: &OrderBookState) -> i64 {
+        let volume = tick.volume as i64;
+        match (book.best_bid, book.best_ask) {
+            (_, Some(ask)) if tick.price >= ask => volume,
+            (Some(bid), _) if tick.price <= bid => -volume,
+            // Inside the spread: classify against the mid
+            _ => match book.mid_price() {
+                Some(mid) if tick.price > mid => volume,
+                Some(mid) if tick.price < mid => -volume,
+                _ => 0,
+            },
+        }
+    }
+
+    /// Look for a divergence between the new trade and the window's swing extremes
+    fn detect_divergence(&self, price: Decimal) -> Option<Signal> {
+        if self.window.len() < self.params.lookback_trades {
+            return None;
+        }
+
+        let swing_high = self.window.iter()
+            .max_by(|a, b| a.price.cmp(&b.price).then(a.cumulative_delta.cmp(&b.cumulative_delta)))?;
+        let swing_low = self.window.iter()
+            .min_by(|a, b| a.price.cmp(&b.price).then(b.cumulative_delta.cmp(&a.cumulative_delta)))?;
+
+        if price > swing_high.price
+            && swing_high.cumulative_delta - self.cumulative_delta >= self.params.min_delta_divergence
+        {
+            return Some(Signal::short(0.7, price, format!(
+                "New high {} with delta {} below swing delta {}",
+                price, self.cumulative_delta, swing_high.cumulative_delta
+            )));
+        }
+
+        if price < swing_low.price
+            && self.cumulative_delta - swing_low.cumulative_delta >= self.params.min_delta_divergence
+        {
+            return Some(Signal::long(0.7, price, format!(
+                "New low {} with delta {} above swing delta {}",
+                price, self.cumulative_delta, swing_low.cumulative_delta
+            )));
+        }
+
+        None
+    }
+
+    /// Check target and stop for an open trade
+    fn check_exit(&self, current_price: Decimal) -> Option<Signal> {
+        if self.position.is_flat() {
+            return None;
+        }
+
+        let entry = self.position.avg_entry_price;
+        let pnl = if self.position.is_long() {
+            current_price - entry
+        } else {
+            entry - current_price
+        };
+
+        if pnl >= self.params.target_points {
+            return Some(Signal::exit(current_price, format!("Divergence target reached: PnL={}", pnl)));
+        }
+
+        if pnl < -self.config.parameters.stop_loss {
+            return Some(Signal::exit(current_price, format!("Stop loss: PnL={}", pnl)));
+        }
+
+        None
+    }
+}
+
+impl Strategy for DeltaDivergenceStrategy {
+    fn on_tick(&mut self, tick: &TickData, context: &StrategyContext) -> Option<Order> {
+        if let Some(mid_price) = context.mid_price() {
+            self.position.update_unrealized_pnl(mid_price);
+        }
+
+        if tick.mdt != MarketDataType::Trade {
+            return None;
+        }
+
+        self.cumulative_delta += Self::signed_volume(tick, &context.order_book);
+
+        // Compare against the window before this trade joins it
+        let divergence = self.detect_divergence(tick.price);
+
+        self.window.push_back(DeltaPoint {
+            price: tick.price,
+            cumulative_delta: self.cumulative_delta,
+        });
+        if self.window.len() > self.params.lookback_trades {
+            self.window.pop_front();
+        }
+
+        if let Some(signal) = self.check_exit(tick.price) {
+            if signal.signal_type == SignalType::Exit {
+                let side = if self.position.is_long() {
+                    OrderSide::Sell
+                } else {
+                    OrderSide::Buy
+                };
+                return Some(Order::market(side, self.position.size.abs()));
+            }
+        }
+
+        if !self.position.is_flat() {
+            return None;
+        }
+
+        let signal = divergence?;
+        debug!("{}", signal.reason);
+
+        match signal.signal_type {
+            SignalType::Long => Some(Order::market(OrderSide::Buy, self.config.parameters.position_size)),
+            SignalType::Short => Some(Order::market(OrderSide::Sell, self.config.parameters.position_size)),
+            _ => None,
+        }
+    }
+
+    fn on_order_fill(&mut self, fill: &OrderFill) {
+        let was_flat = self.position.is_flat();
+        self.position.apply_fill(fill);
+
+        if !was_flat && self.position.is_flat() {
+            let pnl = self.position.realized_pnl;
+            let duration = fill.timestamp.signed_duration_since(
+                self.position.open_time.unwrap_or(fill.timestamp)
+            ).num_seconds() as f64;
+
+            self.metrics.update_trade(pnl, duration);
+        }
+
+        info!("Fill: {:?} {} @ {}, Position: {}",
+            fill.side, fill.quantity, fill.price, self.position.size);
+    }
+
+    fn get_parameters(&self) -> &StrategyConfig {
+        &self.config
+    }
+
+    fn reset(&mut self) {
+        self.position.reset();
+        self.metrics = StrategyMetrics::default();
+        self.on_session_end();
+    }
+
+    fn get_position(&self) -> &Position {
+        &self.position
+    }
+
+    fn get_metrics(&self) -> StrategyMetrics {
+        self.metrics.clone()
+    }
+
+    fn on_session_end(&mut self) {
+        self.cumulative_delta = 0;
+        self.window.clear();
+    }
+}
@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use crate::data::test_support::trade;
+    use crate::market::OrderBookState;
+    use crate::strategy::config::ParameterValue;
+    use crate::strategy::{OrderSide, OrderType, Strategy, StrategyConfig, StrategyContext};
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    const MINUTE_NS: i64 = 60 * 1_000_000_000;
+
+    fn context(bid: &str, ask: &str) -> StrategyContext {
+        let mut order_book = OrderBookState::new("MNQZ24".to_string());
+        order_book.best_bid = Some(Decimal::from_str(bid).unwrap());
+        order_book.best_ask = Some(Decimal::from_str(ask).unwrap());
+
+        StrategyContext {
+            order_book,
+            timestamp: Utc::now(),
+            session_high: None,
+            session_low: None,
+            session_volume: 0,
+            contract: "0624".to_string(),
+            market_open: true,
+            lookahead: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_vwap_reversion_enters_below_vwap() {
+        let mut strategy = VwapReversionStrategy::new(StrategyConfig::vwap_reversion());
+        let ctx = context("99.75", "100.25");
+
+        for i in 0..200 {
+            let price = if i % 2 == 0 { "99.75" } else { "100.25" };
+            assert!(strategy.on_tick(&trade(price, 1, i), &ctx).is_none());
+        }
+        assert!((strategy.vwap().unwrap() - 100.0).abs() < 1e-9);
+
+        let order = strategy.on_tick(&trade("99.00", 1, 200), &ctx).unwrap();
+        assert_eq!(order.side, OrderSide::Buy);
+        assert_eq!(order.order_type, OrderType::Limit);
+    }
+
+    #[test]
+    fn test_opening_range_breakout() {
+        let mut strategy = OpeningRangeBreakoutStrategy::new(StrategyConfig::opening_range_breakout());
+        let ctx = context("110.75", "111.00");
+
+        // Build a 10 point range during the first 15 minutes
+        assert!(strategy.on_tick(&trade("100.00", 1, 0), &ctx).is_none());
+        assert!(strategy.on_tick(&trade("110.00", 1, 5 * MINUTE_NS), &ctx).is_none());
+        assert!(strategy.opening_range().is_none());
+
+        // Inside the range after the window closes: no trade
+        assert!(strategy.on_tick(&trade("105.00", 1, 16 * MINUTE_NS), &ctx).is_none());
+        assert_eq!(strategy.opening_range().unwrap().width(), Decimal::from(10));
+
+        let order = strategy.on_tick(&trade("111.00", 1, 17 * MINUTE_NS), &ctx).unwrap();
+        assert_eq!(order.side, OrderSide::Buy);
+
+        strategy.on_session_end();
+        assert!(strategy.opening_range().is_none());
+    }
+
+    #[test]
+    fn test_absorption_at_bid() {
+        let mut strategy = AbsorptionStrategy::new(StrategyConfig::absorption());
+        let ctx = context("100.00", "100.25");
+
+        assert!(strategy.on_tick(&trade("100.00", 100, 0), &ctx).is_none());
+        assert!(strategy.on_tick(&trade("100.00", 100, 1_000_000), &ctx).is_none());
+
+        let order = strategy.on_tick(&trade("100.00", 100, 2_000_000), &ctx).unwrap();
+        assert_eq!(order.side, OrderSide::Buy);
+        assert_eq!(order.limit_price, Some(Decimal::from(100)));
+    }
+
+    #[test]
+    fn test_absorption_resets_when_bid_breaks() {
+        let mut strategy = AbsorptionStrategy::new(StrategyConfig::absorption());
+        let ctx = context("100.00", "100.25");
+
+        strategy.on_tick(&trade("100.00", 200, 0), &ctx);
+        // Price trades through the bid: the passive buyer gave way
+        strategy.on_tick(&trade("99.75", 1, 1_000_000), &ctx);
+        assert!(strategy.on_tick(&trade("100.00", 150, 2_000_000), &ctx).is_none());
+    }
+
+    #[test]
+    fn test_delta_divergence_at_new_high() {
+        let mut config = StrategyConfig::delta_divergence();
+        config.parameters.custom.insert("lookback_trades".to_string(), ParameterValue::Integer(5));
+        config.parameters.custom.insert("min_delta_divergence".to_string(), ParameterValue::Integer(10));
+        let mut strategy = DeltaDivergenceStrategy::new(config);
+        let ctx = context("99.75", "100.00");
+
+        // Buyers push to a swing high with strong delta
+        strategy.on_tick(&trade("100.00", 50, 0), &ctx);
+        strategy.on_tick(&trade("100.50", 50, 1), &ctx);
+        // Sellers take delta negative
+        for i in 2..5 {
+            strategy.on_tick(&trade("99.50", 40, i), &ctx);
+        }
+        assert_eq!(strategy.cumulative_delta(), -20);
+
+        // New price high on weak delta
+        let order = strategy.on_tick(&trade("101.00", 1, 5), &ctx).unwrap();
+        assert_eq!(order.side, OrderSide::Sell);
+    }
+
+    #[test]
+    fn test_every_template_builds_with_request_parameters() {
+        use crate::strategy::templates::StrategyTemplate;
+
+        for template in StrategyTemplate::all() {
+            assert!(from_template(&template.name, &serde_json::Value::Null).is_some(), "{}", template.name);
+        }
+        assert!(from_template("Unknown", &serde_json::Value::Null).is_none());
+
+        let strategy = from_template("VWAP Reversion", &serde_json::json!({ "entry_std_devs": 3.5, "min_trades": 10 })).unwrap();
+        let custom = &strategy.get_parameters().parameters.custom;
+        assert!(matches!(custom.get("entry_std_devs"), Some(ParameterValue::Float(v)) if *v == 3.5));
+        assert!(custom.contains_key("exit_std_devs"));
+    }
+}
@@ -6,6 +6,69 @@
 
 pub mod order_book_imbalance;
 pub mod bid_ask_bounce;
+pub mod vwap_reversion;
+pub mod opening_range_breakout;
+pub mod absorption;
+pub mod delta_divergence;
 
-pub use order_book_imbalance::OrderBookImbalanceStrategy;
-pub use bid_ask_bounce::BidAskBounceStrategy;
\ No newline at end of file
+pub use order_book_imbalance::{OrderBookImbalanceStrategy, OrderBookImbalanceConfig};
+pub use bid_ask_bounce::{BidAskBounceStrategy, BidAskBounceConfig};
+pub use vwap_reversion::{VwapReversionStrategy, VwapReversionConfig};
+pub use opening_range_breakout::{OpeningRangeBreakoutStrategy, OpeningRangeBreakoutConfig};
+pub use absorption::{AbsorptionStrategy, AbsorptionConfig};
+pub use delta_divergence::{DeltaDivergenceStrategy, DeltaDivergenceConfig};
+
+use crate::strategy::config::ParameterValue;
+use crate::strategy::{Strategy, StrategyConfig};
+
+/// A bundled template resolved by name
+///
+/// Building from a resolved template cannot fail, so callers that build
+/// many strategies, such as optimizer factories, check the name once.
+#[derive(Debug, Clone)]
+pub struct TemplateBuilder {
+    defaults: StrategyConfig,
+    build: fn(StrategyConfig) -> Box<dyn Strategy>,
+}
+
+impl TemplateBuilder {
+    /// The example implementing the bundled template `name`
+    pub fn named(name: &str) -> Option<Self> {
+        type Build = fn(StrategyConfig) -> Box<dyn Strategy>;
+        let (defaults, build): (StrategyConfig, Build) = match name {
+            "Order Book Imbalance" => (StrategyConfig::order_book_imbalance(), |c| Box::new(OrderBookImbalanceStrategy::new(c))),
+            "Bid Ask Bounce" => (StrategyConfig::bid_ask_bounce(), |c| Box::new(BidAskBounceStrategy::new(c))),
+            "VWAP Reversion" => (StrategyConfig::vwap_reversion(), |c| Box::new(VwapReversionStrategy::new(c))),
+            "Opening Range Breakout" => (StrategyConfig::opening_range_breakout(), |c| Box::new(OpeningRangeBreakoutStrategy::new(c))),
+            "Absorption" => (StrategyConfig::absorption(), |c| Box::new(AbsorptionStrategy::new(c))),
+            "Delta Divergence" => (StrategyConfig::delta_divergence(), |c| Box::new(DeltaDivergenceStrategy::new(c))),
+            _ => return None,
+        };
+        Some(Self { defaults, build })
+    }
+
+    /// The example configured with `parameters` over the template's defaults
+    ///
+    /// Values that do not parse as parameters are skipped; values the strategy
+    /// rejects fall back to its defaults, see `strategy_params!`.
+    pub fn build(&self, parameters: &serde_json::Value) -> Box<dyn Strategy> {
+        let mut config = self.defaults.clone();
+        if let Some(values) = parameters.as_object() {
+            for (key, value) in values {
+                if let Ok(value) = serde_json::from_value::<ParameterValue>(value.clone()) {
+                    config.parameters.custom.insert(key.clone(), value);
+                }
+            }
+        }
+        (self.build)(config)
+    }
+}
+
+/// The example implementing the bundled template `name`, configured with
+/// `parameters` over the template's defaults; see `TemplateBuilder::build`
+pub fn from_template(name: &str, parameters: &serde_json::Value) -> Option<Box<dyn Strategy>> {
+    TemplateBuilder::named(name).map(|template| template.build(parameters))
+}
+
+#[cfg(test)]
+mod example_tests;
\ No newline at end of file
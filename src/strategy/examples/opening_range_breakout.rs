@@ -0,0 +1,263 @@
+//! Opening Range Breakout Strategy
+//!
+//! This strategy records the high and low of the first minutes of the
+//! session and trades the first decisive break out of that range,
+//! targeting a multiple of the range width.
+
+use crate::data::{MarketDataType, TickData};
+use crate::market::BookDepth;
+use crate::strategy::{
+    Order, OrderSide, Position, Signal, SignalType,
+    Strategy, StrategyConfig, StrategyContext, StrategyMetrics,
+};
+use crate::strategy::traits::OrderFill;
+use rust_decimal::Decimal;
+use tracing::{debug, info};
+
+crate::strategy_params! {
+    /// Parameters for the opening range breakout strategy
+    pub struct OpeningRangeBreakoutConfig {
+        /// Length of the opening range in minutes
+        range_minutes: u32 = 15, min = 1;
+
+        /// Extra distance beyond the range required to confirm a breakout
+        breakout_buffer: Decimal = Decimal::from_str_exact("0.5").unwrap(), min = Decimal::ZERO;
+
+        /// Profit target as a multiple of the opening range width
+        target_multiple: Decimal = Decimal::ONE, min = Decimal::ZERO;
+
+        /// Ranges narrower than this are ignored (no clear level to break)
+        min_range_width: Decimal = Decimal::from(5), min = Decimal::ZERO;
+
+        /// Maximum breakout trades per session
+        max_trades_per_session: u32 = 1, min = 1;
+    }
+}
+
+/// Opening range established at the start of the session
+#[derive(Debug, Clone, Copy)]
+pub struct OpeningRange {
+    pub high: Decimal,
+    pub low: Decimal,
+}
+
+impl OpeningRange {
+    pub fn width(&self) -> Decimal {
+        self.high - self.low
+    }
+}
+
+/// Opening range breakout strategy
+///
+/// # Strategy Logic
+/// - Tracks the high/low of trades during the first `range_minutes` of the session
+/// - Enters long when price trades above the range high plus buffer
+/// - Enters short when price trades below the range low minus buffer
+/// - Targets `target_multiple` x range width, stops at the middle of the range
+pub struct OpeningRangeBreakoutStrategy {
+    /// Strategy configuration
+    config: StrategyConfig,
+
+    /// Strategy-specific parameters
+    params: OpeningRangeBreakoutConfig,
+
+    /// Current position
+    position: Position,
+
+    /// Performance metrics
+    metrics: StrategyMetrics,
+
+    /// Timestamp (ns) of the first trade of the session
+    session_start: Option<i64>,
+
+    /// Range being built during the opening window
+    building_range: Option<OpeningRange>,
+
+    /// Range once the opening window has closed
+    opening_range: Option<OpeningRange>,
+
+    /// Breakout trades taken this session
+    trades_this_session: u32,
+}
+
+impl OpeningRangeBreakoutStrategy {
+    /// Create a new opening range breakout strategy
+    pub fn new(config: StrategyConfig) -> Self {
+        let params = OpeningRangeBreakoutConfig::from_strategy_config(&config);
+
+        Self {
+            config,
+            params,
+            position: Position::new(),
+            metrics: StrategyMetrics::default(),
+            session_start: None,
+            building_range: None,
+            opening_range: None,
+            trades_this_session: 0,
+        }
+    }
+
+    /// The completed opening range, if the opening window has closed
+    pub fn opening_range(&self) -> Option<OpeningRange> {
+        self.opening_range
+    }
+
+    /// Feed a trade into the opening range; returns true once the range is complete
+    fn update_range(&mut self, tick: &TickData) -> bool {
+        if self.opening_range.is_some() {
+            return true;
+        }
+
+        let start = *self.session_start.get_or_insert(tick.timestamp);
+        let window_ns = self.params.range_minutes as i64 * 60 * 1_000_000_000;
+
+        if tick.timestamp - start < window_ns {
+            let range = self.building_range.get_or_insert(OpeningRange {
+                high: tick.price,
+                low: tick.price,
+            });
+            range.high = range.high.max(tick.price);
+            range.low = range.low.min(tick.price);
+            return false;
+        }
+
+        self.opening_range = self.building_range.take();
+        if let Some(range) = self.opening_range {
+            debug!("Opening range set: {} - {}", range.low, range.high);
+        }
+        true
+    }
+
+    /// Generate a breakout signal
+    fn generate_signal(&self, price: Decimal, range: OpeningRange) -> Option<Signal> {
+        if self.trades_this_session >= self.params.max_trades_per_session
+            || range.width() < self.params.min_range_width
+        {
+            return None;
+        }
+
+        if price > range.high + self.params.breakout_buffer {
+            Some(Signal::long(0.8, price, format!("Breakout above opening range high {}", range.high)))
+        } else if price < range.low - self.params.breakout_buffer {
+            Some(Signal::short(0.8, price, format!("Breakout below opening range low {}", range.low)))
+        } else {
+            None
+        }
+    }
+
+    /// Check target and stop for an open breakout trade
+    fn check_exit(&self, current_price: Decimal, range: OpeningRange) -> Option<Signal> {
+        if self.position.is_flat() {
+            return None;
+        }
+
+        let target_distance = range.width() * self.params.target_multiple;
+        let range_mid = (range.high + range.low) / Decimal::from(2);
+
+        if self.position.is_long() {
+            if current_price >= range.high + target_distance {
+                return Some(Signal::exit(current_price, "Breakout target reached".to_string()));
+            }
+            if current_price <= range_mid {
+                return Some(Signal::exit(current_price, "Failed breakout, back inside range".to_string()));
+            }
+        } else {
+            if current_price <= range.low - target_distance {
+                return Some(Signal::exit(current_price, "Breakout target reached".to_string()));
+            }
+            if current_price >= range_mid {
+                return Some(Signal::exit(current_price, "Failed breakout, back inside range".to_string()));
+            }
+        }
+
+        None
+    }
+}
+
+impl Strategy for OpeningRangeBreakoutStrategy {
+    fn on_tick(&mut self, tick: &TickData, context: &StrategyContext) -> Option<Order> {
+        if let Some(mid_price) = context.mid_price() {
+            self.position.update_unrealized_pnl(mid_price);
+        }
+
+        if tick.mdt != MarketDataType::Trade || !self.update_range(tick) {
+            return None;
+        }
+
+        let range = self.opening_range?;
+
+        if let Some(signal) = self.check_exit(tick.price, range) {
+            if signal.signal_type == SignalType::Exit {
+                let side = if self.position.is_long() {
+                    OrderSide::Sell
+                } else {
+                    OrderSide::Buy
+                };
+                return Some(Order::market(side, self.position.size.abs()));
+            }
+        }
+
+        if !self.position.is_flat() {
+            return None;
+        }
+
+        let signal = self.generate_signal(tick.price, range)?;
+
+        match signal.signal_type {
+            SignalType::Long => Some(Order::market(OrderSide::Buy, self.config.parameters.position_size)),
+            SignalType::Short => Some(Order::market(OrderSide::Sell, self.config.parameters.position_size)),
+            _ => None,
+        }
+    }
+
+    fn on_order_fill(&mut self, fill: &OrderFill) {
+        let was_flat = self.position.is_flat();
+        self.position.apply_fill(fill);
+
+        if was_flat && !self.position.is_flat() {
+            self.trades_this_session += 1;
+        }
+
+        if !was_flat && self.position.is_flat() {
+            let pnl = self.position.realized_pnl;
+            let duration = fill.timestamp.signed_duration_since(
+                self.position.open_time.unwrap_or(fill.timestamp)
+            ).num_seconds() as f64;
+
+            self.metrics.update_trade(pnl, duration);
+        }
+
+        info!("Fill: {:?} {} @ {}, Position: {}",
+            fill.side, fill.quantity, fill.price, self.position.size);
+    }
+
+    fn get_parameters(&self) -> &StrategyConfig {
+        &self.config
+    }
+
+    fn reset(&mut self) {
+        self.position.reset();
+        self.metrics = StrategyMetrics::default();
+        self.on_session_end();
+    }
+
+    fn get_position(&self) -> &Position {
+        &self.position
+    }
+
+    fn get_metrics(&self) -> StrategyMetrics {
+        self.metrics.clone()
+    }
+
+    // Only the mid is read off the book
+    fn book_depth(&self) -> BookDepth {
+        BookDepth::TopOfBook
+    }
+
+    fn on_session_end(&mut self) {
+        self.session_start = None;
+        self.building_range = None;
+        self.opening_range = None;
+        self.trades_this_session = 0;
+    }
+}
@@ -14,6 +14,20 @@ use crate::strategy::traits::OrderFill;
 use rust_decimal::Decimal;
 use tracing::{debug, info};
 
+crate::strategy_params! {
+    /// Parameters for the order book imbalance strategy
+    pub struct OrderBookImbalanceConfig {
+        /// Imbalance threshold (0.5 to 1.0, higher = stronger signal required)
+        imbalance_threshold: f64 = 0.6, min = 0.5, max = 1.0;
+
+        /// Minimum spread required to trade
+        min_spread: Decimal = Decimal::from_str_exact("0.25").unwrap(), min = Decimal::ZERO;
+
+        /// Number of order book levels to analyze
+        depth_levels: usize = 3, min = 1;
+    }
+}
+
 /// Order book imbalance scalping strategy
 /// 
 /// # Strategy Logic
@@ -51,30 +65,15 @@ pub struct OrderBookImbalanceStrategy {
 impl OrderBookImbalanceStrategy {
     /// Create a new order book imbalance strategy
     pub fn new(config: StrategyConfig) -> Self {
-        // Extract custom parameters from config
-        let imbalance_threshold = config.parameters.custom
-            .get("imbalance_threshold")
-            .and_then(|v| v.as_f64())
-            .unwrap_or(0.6);
-        
-        let min_spread = config.parameters.custom
-            .get("min_spread")
-            .and_then(|v| v.as_decimal())
-            .unwrap_or(Decimal::from_str_exact("0.25").unwrap());
-        
-        let depth_levels = config.parameters.custom
-            .get("depth_levels")
-            .and_then(|v| v.as_f64())
-            .map(|v| v as usize)
-            .unwrap_or(3);
+        let params = OrderBookImbalanceConfig::from_strategy_config(&config);
         
         Self {
             config,
             position: Position::new(),
             metrics: StrategyMetrics::default(),
-            imbalance_threshold,
-            min_spread,
-            depth_levels,
+            imbalance_threshold: params.imbalance_threshold,
+            min_spread: params.min_spread,
+            depth_levels: params.depth_levels,
             last_signal: None,
             entry_price: None,
         }
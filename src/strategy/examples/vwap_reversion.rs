@@ -0,0 +1,273 @@
+//! VWAP Reversion Strategy
+//!
+//! This strategy fades stretched moves away from the session VWAP
+//! (volume-weighted average price), expecting price to revert back
+//! toward the average where most volume has traded.
+
+use crate::data::{MarketDataType, TickData};
+use crate::market::BookDepth;
+use crate::strategy::{
+    Order, OrderSide, Position, Signal, SignalType,
+    Strategy, StrategyConfig, StrategyContext, StrategyMetrics,
+};
+use crate::strategy::traits::OrderFill;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use tracing::info;
+
+crate::strategy_params! {
+    /// Parameters for the VWAP reversion strategy
+    pub struct VwapReversionConfig {
+        /// Distance from VWAP, in standard deviations, required to enter
+        entry_std_devs: f64 = 2.0, min = 0.0;
+
+        /// Distance from VWAP, in standard deviations, at which to take profit
+        /// (0.0 = exit exactly at VWAP)
+        exit_std_devs: f64 = 0.25, min = 0.0;
+
+        /// Number of trades required before VWAP is considered reliable
+        min_trades: u32 = 200;
+
+        /// Minimum size of the triggering trade
+        min_volume: i32 = 1, min = 0;
+    }
+}
+
+/// VWAP mean-reversion scalping strategy
+///
+/// # Strategy Logic
+/// - Builds a session VWAP and volume-weighted price deviation from trade ticks
+/// - Enters long when price trades `entry_std_devs` below VWAP
+/// - Enters short when price trades `entry_std_devs` above VWAP
+/// - Exits when price comes back within `exit_std_devs` of VWAP or on stop loss
+pub struct VwapReversionStrategy {
+    /// Strategy configuration
+    config: StrategyConfig,
+
+    /// Strategy-specific parameters
+    params: VwapReversionConfig,
+
+    /// Current position
+    position: Position,
+
+    /// Performance metrics
+    metrics: StrategyMetrics,
+
+    /// Sum of price * volume for the session
+    cum_price_volume: f64,
+
+    /// Sum of price^2 * volume for the session (for deviation)
+    cum_price_sq_volume: f64,
+
+    /// Session volume
+    cum_volume: f64,
+
+    /// Trades seen this session
+    trade_count: u32,
+}
+
+impl VwapReversionStrategy {
+    /// Create a new VWAP reversion strategy
+    pub fn new(config: StrategyConfig) -> Self {
+        let params = VwapReversionConfig::from_strategy_config(&config);
+
+        Self {
+            config,
+            params,
+            position: Position::new(),
+            metrics: StrategyMetrics::default(),
+            cum_price_volume: 0.0,
+            cum_price_sq_volume: 0.0,
+            cum_volume: 0.0,
+            trade_count: 0,
+        }
+    }
+
+    /// Current session VWAP
+    pub fn vwap(&self) -> Option<f64> {
+        if self.cum_volume > 0.0 {
+            Some(self.cum_price_volume / self.cum_volume)
+        } else {
+            None
+        }
+    }
+
+    /// Volume-weighted standard deviation of price around VWAP
+    pub fn vwap_std_dev(&self) -> Option<f64> {
+        let vwap = self.vwap()?;
+        let variance = self.cum_price_sq_volume / self.cum_volume - vwap * vwap;
+        Some(variance.max(0.0).sqrt())
+    }
+
+    /// Add a trade to the running VWAP
+    fn update_vwap(&mut self, price: f64, volume: i32) {
+        let volume = volume as f64;
+        self.cum_price_volume += price * volume;
+        self.cum_price_sq_volume += price * price * volume;
+        self.cum_volume += volume;
+        self.trade_count += 1;
+    }
+
+    /// Distance of price from VWAP in standard deviations
+    fn z_score(&self, price: f64) -> Option<f64> {
+        let vwap = self.vwap()?;
+        let std_dev = self.vwap_std_dev()?;
+        if std_dev <= f64::EPSILON {
+            return None;
+        }
+        Some((price - vwap) / std_dev)
+    }
+
+    /// Generate an entry signal from the current stretch away from VWAP
+    fn generate_signal(&self, tick: &TickData, z_score: f64) -> Option<Signal> {
+        if self.trade_count < self.params.min_trades || tick.volume < self.params.min_volume {
+            return None;
+        }
+
+        if z_score <= -self.params.entry_std_devs {
+            Some(Signal::long(
+                (z_score.abs() / (self.params.entry_std_devs * 2.0)).min(1.0),
+                tick.price,
+                format!("Price {:.2} std devs below VWAP", z_score.abs()),
+            ))
+        } else if z_score >= self.params.entry_std_devs {
+            Some(Signal::short(
+                (z_score / (self.params.entry_std_devs * 2.0)).min(1.0),
+                tick.price,
+                format!("Price {:.2} std devs above VWAP", z_score),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Check exit conditions for an open reversion trade
+    fn check_exit(&self, current_price: Decimal, z_score: Option<f64>) -> Option<Signal> {
+        if self.position.is_flat() {
+            return None;
+        }
+
+        let entry = self.position.avg_entry_price;
+        let pnl = if self.position.is_long() {
+            current_price - entry
+        } else {
+            entry - current_price
+        };
+
+        // Stop loss
+        if pnl < -self.config.parameters.stop_loss {
+            return Some(Signal::exit(current_price, format!("Stop loss: PnL={}", pnl)));
+        }
+
+        // Reverted back to VWAP
+        if let Some(z) = z_score {
+            let reverted = if self.position.is_long() {
+                z >= -self.params.exit_std_devs
+            } else {
+                z <= self.params.exit_std_devs
+            };
+            if reverted {
+                return Some(Signal::exit(current_price, format!("Reverted to VWAP (z={:.2})", z)));
+            }
+        }
+
+        None
+    }
+}
+
+impl Strategy for VwapReversionStrategy {
+    fn on_tick(&mut self, tick: &TickData, context: &StrategyContext) -> Option<Order> {
+        if let Some(mid_price) = context.mid_price() {
+            self.position.update_unrealized_pnl(mid_price);
+        }
+
+        // Only trade prints contribute to VWAP
+        if tick.mdt != MarketDataType::Trade {
+            return None;
+        }
+
+        let price = tick.price.to_f64()?;
+        // Measure the stretch against VWAP before this trade is folded in
+        let z_score = self.z_score(price);
+        self.update_vwap(price, tick.volume);
+
+        if let Some(signal) = self.check_exit(tick.price, z_score) {
+            if signal.signal_type == SignalType::Exit {
+                let side = if self.position.is_long() {
+                    OrderSide::Sell
+                } else {
+                    OrderSide::Buy
+                };
+                return Some(Order::market(side, self.position.size.abs()));
+            }
+        }
+
+        if !self.position.is_flat() {
+            return None;
+        }
+
+        let signal = self.generate_signal(tick, z_score?)?;
+
+        match signal.signal_type {
+            SignalType::Long => Some(Order::limit(
+                OrderSide::Buy,
+                self.config.parameters.position_size,
+                tick.price,
+            )),
+            SignalType::Short => Some(Order::limit(
+                OrderSide::Sell,
+                self.config.parameters.position_size,
+                tick.price,
+            )),
+            _ => None,
+        }
+    }
+
+    fn on_order_fill(&mut self, fill: &OrderFill) {
+        let was_flat = self.position.is_flat();
+        self.position.apply_fill(fill);
+
+        if !was_flat && self.position.is_flat() {
+            let pnl = self.position.realized_pnl;
+            let duration = fill.timestamp.signed_duration_since(
+                self.position.open_time.unwrap_or(fill.timestamp)
+            ).num_seconds() as f64;
+
+            self.metrics.update_trade(pnl, duration);
+        }
+
+        info!("Fill: {:?} {} @ {}, Position: {}",
+            fill.side, fill.quantity, fill.price, self.position.size);
+    }
+
+    fn get_parameters(&self) -> &StrategyConfig {
+        &self.config
+    }
+
+    fn reset(&mut self) {
+        self.position.reset();
+        self.metrics = StrategyMetrics::default();
+        self.on_session_end();
+    }
+
+    fn get_position(&self) -> &Position {
+        &self.position
+    }
+
+    fn get_metrics(&self) -> StrategyMetrics {
+        self.metrics.clone()
+    }
+
+    // Only the mid is read off the book
+    fn book_depth(&self) -> BookDepth {
+        BookDepth::TopOfBook
+    }
+
+    fn on_session_end(&mut self) {
+        // VWAP is a session statistic
+        self.cum_price_volume = 0.0;
+        self.cum_price_sq_volume = 0.0;
+        self.cum_volume = 0.0;
+        self.trade_count = 0;
+    }
+}
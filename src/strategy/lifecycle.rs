@@ -0,0 +1,138 @@
+//! Strategy lifecycle
+//!
+//! A strategy moves Draft → Backtested → Validated → Paper → Live and is
+//! eventually Retired. Each promotion names the evidence it rests on: a
+//! backtest of the strategy, passing promotion gates, an out-of-sample run.
+//! Strategies can be sent back to Draft for rework, pulled from Live to
+//! Paper, or retired from any state; retirement is final.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Where a strategy is in its lifecycle
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleState {
+    #[default]
+    Draft,
+    Backtested,
+    Validated,
+    Paper,
+    /// Trading real money; `active` in records from before the lifecycle
+    #[serde(alias = "active")]
+    Live,
+    Retired,
+}
+
+impl fmt::Display for LifecycleState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LifecycleState::Draft => "draft",
+            LifecycleState::Backtested => "backtested",
+            LifecycleState::Validated => "validated",
+            LifecycleState::Paper => "paper",
+            LifecycleState::Live => "live",
+            LifecycleState::Retired => "retired",
+        };
+        f.write_str(name)
+    }
+}
+
+/// What a promotion has to point at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvidenceKind {
+    /// A backtest result of the strategy
+    BacktestResult,
+    /// The strategy passing the promotion gates
+    PassingValidation,
+    /// An out-of-sample result of the strategy, e.g. its paper run
+    OutOfSampleResult,
+}
+
+impl fmt::Display for EvidenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EvidenceKind::BacktestResult => "a backtest result",
+            EvidenceKind::PassingValidation => "passing promotion gates",
+            EvidenceKind::OutOfSampleResult => "an out-of-sample result",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Errors raised changing a strategy's lifecycle state
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum LifecycleError {
+    #[error("Strategy is already {0}")]
+    Unchanged(LifecycleState),
+    #[error("Cannot move a strategy from {from} to {to}")]
+    NotAllowed { from: LifecycleState, to: LifecycleState },
+    #[error("Moving to {to} needs {needs}")]
+    MissingEvidence { to: LifecycleState, needs: EvidenceKind },
+}
+
+impl LifecycleState {
+    /// Evidence a move to `to` needs, or why the move is not allowed
+    pub fn check_transition(self, to: LifecycleState) -> Result<Option<EvidenceKind>, LifecycleError> {
+        use LifecycleState::*;
+        match (self, to) {
+            (from, to) if from == to => Err(LifecycleError::Unchanged(from)),
+            (Draft, Backtested) => Ok(Some(EvidenceKind::BacktestResult)),
+            (Backtested, Validated) => Ok(Some(EvidenceKind::PassingValidation)),
+            (Validated, Paper) => Ok(None),
+            (Paper, Live) => Ok(Some(EvidenceKind::OutOfSampleResult)),
+            (Live, Paper) => Ok(None),
+            (from, Draft | Retired) if from != Retired => Ok(None),
+            (from, to) => Err(LifecycleError::NotAllowed { from, to }),
+        }
+    }
+
+    /// States reachable from this one
+    pub fn next_states(self) -> Vec<LifecycleState> {
+        use LifecycleState::*;
+        [Draft, Backtested, Validated, Paper, Live, Retired].into_iter()
+            .filter(|to| self.check_transition(*to).is_ok())
+            .collect()
+    }
+}
+
+/// A recorded change of lifecycle state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LifecycleTransition {
+    pub from: LifecycleState,
+    pub to: LifecycleState,
+    pub at: DateTime<Utc>,
+    pub actor: Option<String>,
+    /// Result the promotion rests on
+    #[serde(default)]
+    pub result_id: Option<String>,
+    /// Whether failed promotion gates were overridden
+    #[serde(default)]
+    pub overridden: bool,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use LifecycleState::*;
+
+    #[test]
+    fn test_transitions_and_evidence() {
+        assert_eq!(Draft.check_transition(Backtested), Ok(Some(EvidenceKind::BacktestResult)));
+        assert_eq!(Backtested.check_transition(Validated), Ok(Some(EvidenceKind::PassingValidation)));
+        assert_eq!(Paper.check_transition(Live), Ok(Some(EvidenceKind::OutOfSampleResult)));
+        assert_eq!(Draft.check_transition(Live), Err(LifecycleError::NotAllowed { from: Draft, to: Live }));
+        assert_eq!(Live.check_transition(Live), Err(LifecycleError::Unchanged(Live)));
+        assert!(Retired.next_states().is_empty());
+        assert_eq!(Live.next_states(), vec![Draft, Paper, Retired]);
+
+        // Records written before the lifecycle used free-form strings
+        let legacy: LifecycleState = serde_json::from_str("\"active\"").unwrap();
+        assert_eq!(legacy, Live);
+        assert_eq!(serde_json::to_string(&Validated).unwrap(), "\"validated\"");
+    }
+}
@@ -0,0 +1,171 @@
+//! Lookahead protection for strategy data access
+//!
+//! Strategies often precompute bars or signals over a whole dataset. Reading
+//! those series through the `StrategyContext` accessors only ever exposes
+//! entries stamped at or before the current event; any attempt to reach past
+//! it is refused and flagged. In strict mode a flagged access fails the
+//! backtest instead of silently producing too-good-to-be-true results.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// Maximum number of violations kept for reporting
+const MAX_RECORDED_VIOLATIONS: usize = 100;
+
+/// How lookahead attempts are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LookaheadMode {
+    /// Refuse the access and log a warning
+    #[default]
+    Warn,
+    /// Refuse the access and fail the backtest
+    Strict,
+}
+
+/// Data with an event time
+pub trait Timestamped {
+    fn event_time(&self) -> DateTime<Utc>;
+}
+
+impl<T> Timestamped for (DateTime<Utc>, T) {
+    fn event_time(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// A refused attempt to read data from the future
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LookaheadViolation {
+    /// Time of the event being processed
+    pub event_time: DateTime<Utc>,
+    /// Timestamp of the data the strategy tried to read
+    pub requested_time: DateTime<Utc>,
+    /// Label of the series that was accessed
+    pub source: String,
+}
+
+/// Error returned when strict mode fails a backtest
+#[derive(Debug, thiserror::Error)]
+#[error("Lookahead detected: {source_name} read data at {requested_time} while processing {event_time}")]
+pub struct LookaheadError {
+    pub source_name: String,
+    pub event_time: DateTime<Utc>,
+    pub requested_time: DateTime<Utc>,
+}
+
+#[derive(Debug, Default)]
+struct GuardState {
+    mode: LookaheadMode,
+    count: AtomicUsize,
+    violations: Mutex<Vec<LookaheadViolation>>,
+}
+
+/// Shared record of lookahead attempts during a run
+#[derive(Debug, Clone, Default)]
+pub struct LookaheadGuard {
+    state: Arc<GuardState>,
+}
+
+impl LookaheadGuard {
+    pub fn new(mode: LookaheadMode) -> Self {
+        Self {
+            state: Arc::new(GuardState {
+                mode,
+                ..Default::default()
+            }),
+        }
+    }
+
+    pub fn mode(&self) -> LookaheadMode {
+        self.state.mode
+    }
+
+    /// Check an access, flagging it if `requested_time` is after `event_time`
+    ///
+    /// Returns whether the access is allowed.
+    pub fn check(&self, event_time: DateTime<Utc>, requested_time: DateTime<Utc>, source: &str) -> bool {
+        if requested_time <= event_time {
+            return true;
+        }
+
+        let count = self.state.count.fetch_add(1, Ordering::Relaxed) + 1;
+        if count <= MAX_RECORDED_VIOLATIONS {
+            warn!("Lookahead refused: {} requested {} while processing {}",
+                source, requested_time, event_time);
+            self.state.violations.lock().unwrap().push(LookaheadViolation {
+                event_time,
+                requested_time,
+                source: source.to_string(),
+            });
+        }
+
+        false
+    }
+
+    /// Total number of refused accesses
+    pub fn violation_count(&self) -> usize {
+        self.state.count.load(Ordering::Relaxed)
+    }
+
+    /// The first refused accesses, up to a fixed limit
+    pub fn violations(&self) -> Vec<LookaheadViolation> {
+        self.state.violations.lock().unwrap().clone()
+    }
+
+    /// Fail if strict mode is on and any lookahead was attempted
+    pub fn enforce(&self) -> Result<(), LookaheadError> {
+        if self.state.mode != LookaheadMode::Strict || self.violation_count() == 0 {
+            return Ok(());
+        }
+
+        let first = self.state.violations.lock().unwrap().first().cloned();
+        match first {
+            Some(violation) => Err(LookaheadError {
+                source_name: violation.source,
+                event_time: violation.event_time,
+                requested_time: violation.requested_time,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn reset(&self) {
+        self.state.count.store(0, Ordering::Relaxed);
+        self.state.violations.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_future_access_is_flagged() {
+        let guard = LookaheadGuard::new(LookaheadMode::Warn);
+        let now = Utc::now();
+
+        assert!(guard.check(now, now, "bars"));
+        assert!(!guard.check(now, now + Duration::seconds(1), "bars"));
+        assert_eq!(guard.violation_count(), 1);
+        assert_eq!(guard.violations()[0].source, "bars");
+        assert!(guard.enforce().is_ok());
+    }
+
+    #[test]
+    fn test_strict_mode_fails() {
+        let guard = LookaheadGuard::new(LookaheadMode::Strict);
+        let now = Utc::now();
+
+        assert!(guard.enforce().is_ok());
+        guard.check(now, now + Duration::minutes(5), "signals");
+        assert!(guard.enforce().is_err());
+
+        guard.reset();
+        assert!(guard.enforce().is_ok());
+    }
+}
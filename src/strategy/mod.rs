@@ -11,15 +11,32 @@ pub mod signals;
 pub mod orders;
 pub mod position;
 pub mod examples;
+pub mod lookahead;
+pub mod state;
+pub mod params;
+pub mod lifecycle;
+pub mod sandbox;
+pub mod source;
 
 pub use traits::{Strategy, StrategyContext, StrategyMetrics};
 pub use config::{StrategyConfig, StrategyParameters};
-pub use orders::{Order, OrderType, OrderSide, OrderFill};
-pub use position::{Position, PositionManager};
+pub use orders::{Order, OrderType, OrderSide, OrderFill, TimeInForce};
+pub use position::{
+    CloseOut, HedgedPosition, Lot, LotClose, Position, PositionAccounting, PositionEffect, PositionManager,
+};
 pub use signals::{Signal, SignalType};
+pub use lookahead::{LookaheadGuard, LookaheadMode, LookaheadViolation, Timestamped};
+pub use params::{ParamType, ParameterError};
+pub use lifecycle::{EvidenceKind, LifecycleError, LifecycleState, LifecycleTransition};
+pub use sandbox::{SandboxConfig, SandboxError, SandboxLimits, SandboxedStrategy};
+pub use source::{CodeArchive, CodeSnapshot, SourceError, StrategySource};
+pub use state::{StateEnvelope, StateKey, StateResume, StrategyStateError, StrategyStateStore};
 
 // Re-export example strategies
-pub use examples::{OrderBookImbalanceStrategy, BidAskBounceStrategy};
+pub use examples::{
+    OrderBookImbalanceStrategy, BidAskBounceStrategy, VwapReversionStrategy,
+    OpeningRangeBreakoutStrategy, AbsorptionStrategy, DeltaDivergenceStrategy,
+};
 
 #[cfg(test)]
 mod strategy_tests;
\ No newline at end of file
@@ -5,6 +5,8 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::strategy::position::PositionEffect;
+
 /// Order to be submitted to the market
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Order {
@@ -40,6 +42,10 @@ pub struct Order {
     
     /// Optional tag for strategy tracking
     pub tag: Option<String>,
+    
+    /// Whether the order closes opposite lots under hedged accounting
+    #[serde(default)]
+    pub position_effect: PositionEffect,
 }
 
 impl Order {
@@ -57,6 +63,7 @@ impl Order {
             time_in_force: TimeInForce::IOC,
             timestamp: Utc::now(),
             tag: None,
+            position_effect: PositionEffect::Close,
         }
     }
     
@@ -74,6 +81,7 @@ impl Order {
             time_in_force: TimeInForce::GTC,
             timestamp: Utc::now(),
             tag: None,
+            position_effect: PositionEffect::Close,
         }
     }
     
@@ -91,6 +99,7 @@ impl Order {
             time_in_force: TimeInForce::GTC,
             timestamp: Utc::now(),
             tag: None,
+            position_effect: PositionEffect::Close,
         }
     }
     
@@ -108,6 +117,7 @@ impl Order {
             time_in_force: TimeInForce::GTC,
             timestamp: Utc::now(),
             tag: None,
+            position_effect: PositionEffect::Close,
         }
     }
     
@@ -117,6 +127,12 @@ impl Order {
         self
     }
     
+    /// Open a new lot even against opposite lots, under hedged accounting
+    pub fn opening(mut self) -> Self {
+        self.position_effect = PositionEffect::Open;
+        self
+    }
+    
     /// Set time in force
     pub fn with_time_in_force(mut self, tif: TimeInForce) -> Self {
         self.time_in_force = tif;
@@ -173,6 +189,16 @@ pub enum TimeInForce {
     
     /// Day order
     Day,
+    
+    /// Rests like GTC but is rejected if it would take liquidity on arrival
+    PostOnly,
+}
+
+impl TimeInForce {
+    /// Whether an unfilled remainder is cancelled rather than left resting
+    pub fn is_immediate(&self) -> bool {
+        matches!(self, TimeInForce::IOC | TimeInForce::FOK)
+    }
 }
 
 /// Order status
@@ -0,0 +1,345 @@
+//! Typed strategy parameters
+//!
+//! `strategy_params!` declares a parameter struct and generates its parsing
+//! from the custom section of `StrategyParameters`: each field is coerced to
+//! its declared type, checked against optional bounds and defaulted when
+//! absent, so strategies never index the parameter map by hand. Optimizers
+//! search integer parameters as floats, so integer fields truncate
+//! fractional numbers the way an `as` cast does.
+//!
+//! ```ignore
+//! strategy_params! {
+//!     /// Parameters for my strategy
+//!     pub struct MyConfig {
+//!         /// Bars in the lookback window
+//!         lookback: usize = 20, min = 1, max = 500;
+//!         /// Profit target in points
+//!         target_points: Decimal = Decimal::from(2), min = Decimal::ZERO;
+//!     }
+//! }
+//!
+//! let params = MyConfig::try_from_strategy_config(&config)?;
+//! ```
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::config::{ParameterValue, StrategyParameters};
+
+/// Errors raised while reading typed parameters
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ParameterError {
+    #[error("Parameter {name} should be {expected}, got {value}")]
+    InvalidType { name: String, expected: &'static str, value: String },
+    #[error("Parameter {name} is {value}, below the minimum {min}")]
+    BelowMinimum { name: String, value: String, min: String },
+    #[error("Parameter {name} is {value}, above the maximum {max}")]
+    AboveMaximum { name: String, value: String, max: String },
+}
+
+/// A type a parameter value can be coerced to
+pub trait ParamType: Sized {
+    /// Description used in type errors
+    const EXPECTED: &'static str;
+
+    fn coerce(value: &ParameterValue) -> Option<Self>;
+}
+
+/// Whole number held by any numeric or string value, truncating fractions
+fn as_integer(value: &ParameterValue) -> Option<i64> {
+    match value {
+        ParameterValue::Integer(v) => Some(*v),
+        ParameterValue::Float(v) if v.is_finite() => Some(v.trunc() as i64),
+        ParameterValue::Decimal(v) => v.trunc().to_i64(),
+        ParameterValue::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+macro_rules! integer_param {
+    ($($ty:ty),*) => {
+        $(
+            impl ParamType for $ty {
+                const EXPECTED: &'static str = concat!("an integer fitting ", stringify!($ty));
+
+                fn coerce(value: &ParameterValue) -> Option<Self> {
+                    as_integer(value).and_then(|v| <$ty>::try_from(v).ok())
+                }
+            }
+        )*
+    };
+}
+
+integer_param!(i32, i64, u32, u64, usize);
+
+impl ParamType for f64 {
+    const EXPECTED: &'static str = "a number";
+
+    fn coerce(value: &ParameterValue) -> Option<Self> {
+        match value {
+            ParameterValue::String(s) => s.trim().parse().ok(),
+            other => other.as_f64(),
+        }
+    }
+}
+
+impl ParamType for Decimal {
+    const EXPECTED: &'static str = "a decimal number";
+
+    fn coerce(value: &ParameterValue) -> Option<Self> {
+        match value {
+            ParameterValue::String(s) => Decimal::from_str(s.trim()).ok(),
+            other => other.as_decimal(),
+        }
+    }
+}
+
+impl ParamType for bool {
+    const EXPECTED: &'static str = "true or false";
+
+    fn coerce(value: &ParameterValue) -> Option<Self> {
+        match value {
+            ParameterValue::Boolean(v) => Some(*v),
+            ParameterValue::Integer(0) => Some(false),
+            ParameterValue::Integer(1) => Some(true),
+            ParameterValue::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl ParamType for String {
+    const EXPECTED: &'static str = "a string";
+
+    fn coerce(value: &ParameterValue) -> Option<Self> {
+        match value {
+            ParameterValue::String(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+}
+
+fn describe(value: &ParameterValue) -> String {
+    match value {
+        ParameterValue::Float(v) => v.to_string(),
+        ParameterValue::Integer(v) => v.to_string(),
+        ParameterValue::Decimal(v) => v.to_string(),
+        ParameterValue::Boolean(v) => v.to_string(),
+        ParameterValue::String(s) => format!("{:?}", s),
+    }
+}
+
+/// Read `name` from the custom parameters, or `default` when absent
+pub fn read<T: ParamType>(
+    custom: &HashMap<String, ParameterValue>,
+    name: &str,
+    default: impl FnOnce() -> T,
+) -> Result<T, ParameterError> {
+    match custom.get(name) {
+        None => Ok(default()),
+        Some(value) => T::coerce(value).ok_or_else(|| ParameterError::InvalidType {
+            name: name.to_string(),
+            expected: T::EXPECTED,
+            value: describe(value),
+        }),
+    }
+}
+
+pub fn check_min<T: PartialOrd + Display>(name: &str, value: &T, min: &T) -> Result<(), ParameterError> {
+    if value < min {
+        return Err(ParameterError::BelowMinimum {
+            name: name.to_string(),
+            value: value.to_string(),
+            min: min.to_string(),
+        });
+    }
+    Ok(())
+}
+
+pub fn check_max<T: PartialOrd + Display>(name: &str, value: &T, max: &T) -> Result<(), ParameterError> {
+    if value > max {
+        return Err(ParameterError::AboveMaximum {
+            name: name.to_string(),
+            value: value.to_string(),
+            max: max.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Custom parameters not declared in `keys`, usually misspellings
+pub fn unknown_keys(parameters: &StrategyParameters, keys: &[&str]) -> Vec<String> {
+    let mut unknown: Vec<String> = parameters.custom.keys()
+        .filter(|key| !keys.contains(&key.as_str()))
+        .cloned()
+        .collect();
+    unknown.sort();
+    unknown
+}
+
+/// Declare a typed, validated strategy parameter struct
+///
+/// Each field is written `name: Type = default` with optional `, min = ..`
+/// and `, max = ..` bounds. See the module documentation for an example.
+#[macro_export]
+macro_rules! strategy_params {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field:ident : $ty:ty = $default:expr
+                $(, min = $min:expr)?
+                $(, max = $max:expr)?
+                ;
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, ::serde::Serialize, ::serde::Deserialize)]
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                pub $field: $ty,
+            )*
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self {
+                    $($field: $default,)*
+                }
+            }
+        }
+
+        impl $name {
+            /// Custom parameter names this struct reads
+            pub const KEYS: &'static [&'static str] = &[$(stringify!($field)),*];
+
+            /// Each field parsed and validated on its own
+            fn parse_fields(
+                parameters: &$crate::strategy::StrategyParameters,
+            ) -> ($(Result<$ty, $crate::strategy::params::ParameterError>,)*) {
+                let custom = &parameters.custom;
+                ($(
+                    $crate::strategy::params::read(custom, stringify!($field), || $default).and_then(|value: $ty| {
+                        $($crate::strategy::params::check_min(stringify!($field), &value, &$min)?;)?
+                        $($crate::strategy::params::check_max(stringify!($field), &value, &$max)?;)?
+                        Ok(value)
+                    }),
+                )*)
+            }
+
+            /// Parse and validate; absent parameters take their defaults
+            pub fn from_parameters(
+                parameters: &$crate::strategy::StrategyParameters,
+            ) -> Result<Self, $crate::strategy::params::ParameterError> {
+                let ($($field,)*) = Self::parse_fields(parameters);
+                Ok(Self {
+                    $($field: $field?,)*
+                })
+            }
+
+            /// Read parameters from the custom section of a strategy config,
+            /// warning about keys this struct does not declare
+            pub fn try_from_strategy_config(
+                config: &$crate::strategy::StrategyConfig,
+            ) -> Result<Self, $crate::strategy::params::ParameterError> {
+                for key in $crate::strategy::params::unknown_keys(&config.parameters, Self::KEYS) {
+                    ::tracing::warn!("{}: unknown parameter {:?} ignored", stringify!($name), key);
+                }
+                Self::from_parameters(&config.parameters)
+            }
+
+            /// Like `try_from_strategy_config`, falling back to the default
+            /// of each parameter that is invalid while keeping the rest
+            pub fn from_strategy_config(config: &$crate::strategy::StrategyConfig) -> Self {
+                for key in $crate::strategy::params::unknown_keys(&config.parameters, Self::KEYS) {
+                    ::tracing::warn!("{}: unknown parameter {:?} ignored", stringify!($name), key);
+                }
+                let ($($field,)*) = Self::parse_fields(&config.parameters);
+                Self {
+                    $(
+                        $field: $field.unwrap_or_else(|error| {
+                            ::tracing::warn!("{}: {}, using the default", stringify!($name), error);
+                            $default
+                        }),
+                    )*
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::StrategyConfig;
+
+    crate::strategy_params! {
+        /// Parameters used by the tests
+        pub struct TestParams {
+            /// Bars in the lookback window
+            lookback: usize = 20, min = 1, max = 500;
+            target_points: Decimal = Decimal::from(2), min = Decimal::ZERO;
+            use_filter: bool = false;
+        }
+    }
+
+    #[test]
+    fn test_params_coerce_and_validate() {
+        let mut config = StrategyConfig::default();
+        assert_eq!(TestParams::from_parameters(&config.parameters).unwrap().lookback, 20);
+
+        let custom = &mut config.parameters.custom;
+        custom.insert("lookback".to_string(), ParameterValue::Float(50.0));
+        custom.insert("target_points".to_string(), ParameterValue::String("1.25".to_string()));
+        custom.insert("use_filter".to_string(), ParameterValue::Integer(1));
+        let params = TestParams::try_from_strategy_config(&config).unwrap();
+        assert_eq!(params.lookback, 50);
+        assert_eq!(params.target_points, Decimal::new(125, 2));
+        assert!(params.use_filter);
+
+        config.parameters.custom.insert("lookback".to_string(), ParameterValue::String("long".to_string()));
+        assert!(matches!(
+            TestParams::from_parameters(&config.parameters),
+            Err(ParameterError::InvalidType { .. })
+        ));
+
+        config.parameters.custom.insert("lookback".to_string(), ParameterValue::Integer(0));
+        assert!(matches!(
+            TestParams::from_parameters(&config.parameters),
+            Err(ParameterError::BelowMinimum { .. })
+        ));
+        // Only the invalid field falls back
+        let params = TestParams::from_strategy_config(&config);
+        assert_eq!((params.lookback, params.target_points), (20, Decimal::new(125, 2)));
+
+        config.parameters.custom.insert("lookbak".to_string(), ParameterValue::Integer(5));
+        assert_eq!(unknown_keys(&config.parameters, TestParams::KEYS), vec!["lookbak".to_string()]);
+    }
+
+    #[test]
+    fn test_optimizer_floats_truncate_for_integer_fields() {
+        // The genetic optimizer draws every parameter as a float
+        let mut config = StrategyConfig::default();
+        let custom = &mut config.parameters.custom;
+        custom.insert("lookback".to_string(), ParameterValue::Float(37.8));
+        custom.insert("target_points".to_string(), ParameterValue::Float(1.5));
+        custom.insert("use_filter".to_string(), ParameterValue::Boolean(true));
+        let params = TestParams::from_strategy_config(&config);
+        assert_eq!(params.lookback, 37);
+        assert_eq!(params.target_points, Decimal::new(15, 1));
+        assert!(params.use_filter);
+
+        // Below the minimum once truncated: that field alone takes its default
+        config.parameters.custom.insert("lookback".to_string(), ParameterValue::Float(0.6));
+        let params = TestParams::from_strategy_config(&config);
+        assert_eq!(params.lookback, 20);
+        assert_eq!(params.target_points, Decimal::new(15, 1));
+        assert!(params.use_filter);
+    }
+}
@@ -1,10 +1,17 @@
 //! Position tracking and management
+//!
+//! Positions net by default: a sell against a long reduces it. Hedged
+//! accounting instead keeps long and short lots side by side and closes
+//! them lot by lot, first-in-first-out or last-in-first-out, so a strategy
+//! that scales in and out with mixed lots gets P&L per lot rather than
+//! against a blended average price.
 
 use crate::strategy::{OrderSide};
 use crate::strategy::traits::OrderFill;
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 
 /// Current position state
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -154,20 +161,256 @@ impl Position {
     }
 }
 
+/// How fills are booked against existing positions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PositionAccounting {
+    /// One position per symbol; opposite fills reduce it
+    #[default]
+    Netting,
+    /// Long and short lots held side by side and closed lot by lot
+    Hedged,
+}
+
+/// Which lot a closing fill takes first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseOut {
+    /// Oldest lot first
+    #[default]
+    Fifo,
+    /// Newest lot first
+    Lifo,
+}
+
+/// What a fill does to opposite lots under hedged accounting
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PositionEffect {
+    /// Close opposite lots first and open a lot with any remainder
+    #[default]
+    Close,
+    /// Open a new lot, leaving opposite lots in place
+    Open,
+}
+
+/// Contracts opened by one fill and not yet closed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lot {
+    pub id: u64,
+    pub side: OrderSide,
+    /// Contracts still open
+    pub quantity: i32,
+    pub opened_quantity: i32,
+    pub entry_price: Decimal,
+    pub opened_at: DateTime<Utc>,
+    /// Entry commission on the contracts still open
+    pub commission: Decimal,
+    pub tag: Option<String>,
+}
+
+impl Lot {
+    /// P&L of the open contracts at `price`, before commission
+    pub fn unrealized_pnl(&self, price: Decimal) -> Decimal {
+        points(self.side, self.entry_price, price) * Decimal::from(self.quantity)
+    }
+}
+
+/// Part or all of a lot closed by a fill
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LotClose {
+    pub lot_id: u64,
+    /// Side of the lot, not of the closing fill
+    pub side: OrderSide,
+    pub quantity: i32,
+    pub entry_price: Decimal,
+    pub exit_price: Decimal,
+    pub opened_at: DateTime<Utc>,
+    pub closed_at: DateTime<Utc>,
+    /// Before commission, in price points times contracts like `Position`
+    pub pnl: Decimal,
+    /// Entry and exit commission on the closed contracts
+    pub commission: Decimal,
+    /// Tag of the order that opened the lot
+    pub tag: Option<String>,
+}
+
+impl LotClose {
+    pub fn net_pnl(&self) -> Decimal {
+        self.pnl - self.commission
+    }
+}
+
+/// Price move in favour of a position on `side`
+fn points(side: OrderSide, entry: Decimal, exit: Decimal) -> Decimal {
+    match side {
+        OrderSide::Buy => exit - entry,
+        OrderSide::Sell => entry - exit,
+    }
+}
+
+/// Long and short lots of one symbol
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HedgedPosition {
+    pub close_out: CloseOut,
+    long: VecDeque<Lot>,
+    short: VecDeque<Lot>,
+    closed: Vec<LotClose>,
+    next_lot_id: u64,
+    pub realized_pnl: Decimal,
+    pub unrealized_pnl: Decimal,
+    pub total_commission: Decimal,
+}
+
+impl HedgedPosition {
+    pub fn new(close_out: CloseOut) -> Self {
+        Self { close_out, ..Self::default() }
+    }
+
+    /// Book a fill, returning the lots it closed
+    pub fn apply_fill(&mut self, fill: &OrderFill, effect: PositionEffect) -> Vec<LotClose> {
+        if fill.quantity <= 0 {
+            return Vec::new();
+        }
+        let per_contract = fill.commission / Decimal::from(fill.quantity);
+        self.total_commission += fill.commission;
+
+        let mut remaining = fill.quantity;
+        let mut closes = Vec::new();
+        if effect == PositionEffect::Close {
+            let close_out = self.close_out;
+            let opposite = match fill.side {
+                OrderSide::Buy => &mut self.short,
+                OrderSide::Sell => &mut self.long,
+            };
+            while remaining > 0 {
+                let lot = match close_out {
+                    CloseOut::Fifo => opposite.front_mut(),
+                    CloseOut::Lifo => opposite.back_mut(),
+                };
+                let Some(lot) = lot else { break };
+
+                let quantity = remaining.min(lot.quantity);
+                let entry_commission = lot.commission * Decimal::from(quantity) / Decimal::from(lot.quantity);
+                lot.commission -= entry_commission;
+                lot.quantity -= quantity;
+                remaining -= quantity;
+                closes.push(LotClose {
+                    lot_id: lot.id,
+                    side: lot.side,
+                    quantity,
+                    entry_price: lot.entry_price,
+                    exit_price: fill.price,
+                    opened_at: lot.opened_at,
+                    closed_at: fill.timestamp,
+                    pnl: points(lot.side, lot.entry_price, fill.price) * Decimal::from(quantity),
+                    commission: entry_commission + per_contract * Decimal::from(quantity),
+                    tag: lot.tag.clone(),
+                });
+
+                if lot.quantity == 0 {
+                    match close_out {
+                        CloseOut::Fifo => opposite.pop_front(),
+                        CloseOut::Lifo => opposite.pop_back(),
+                    };
+                }
+            }
+        }
+
+        if remaining > 0 {
+            let lot = Lot {
+                id: self.next_lot_id,
+                side: fill.side,
+                quantity: remaining,
+                opened_quantity: remaining,
+                entry_price: fill.price,
+                opened_at: fill.timestamp,
+                commission: per_contract * Decimal::from(remaining),
+                tag: fill.tag.clone(),
+            };
+            self.next_lot_id += 1;
+            match fill.side {
+                OrderSide::Buy => self.long.push_back(lot),
+                OrderSide::Sell => self.short.push_back(lot),
+            }
+        }
+
+        self.realized_pnl += closes.iter().map(|close| close.pnl).sum::<Decimal>();
+        self.closed.extend(closes.iter().cloned());
+        closes
+    }
+
+    pub fn long_size(&self) -> i32 {
+        self.long.iter().map(|lot| lot.quantity).sum()
+    }
+
+    pub fn short_size(&self) -> i32 {
+        self.short.iter().map(|lot| lot.quantity).sum()
+    }
+
+    /// Net exposure, positive when long
+    pub fn net_size(&self) -> i32 {
+        self.long_size() - self.short_size()
+    }
+
+    pub fn is_flat(&self) -> bool {
+        self.long.is_empty() && self.short.is_empty()
+    }
+
+    /// Open lots, longs then shorts, each oldest first
+    pub fn lots(&self) -> impl Iterator<Item = &Lot> {
+        self.long.iter().chain(self.short.iter())
+    }
+
+    /// Every lot closed so far, in the order closed
+    pub fn closed_lots(&self) -> &[LotClose] {
+        &self.closed
+    }
+
+    pub fn update_unrealized_pnl(&mut self, current_price: Decimal) {
+        self.unrealized_pnl = self.lots().map(|lot| lot.unrealized_pnl(current_price)).sum();
+    }
+
+    /// Same convention as `Position::total_pnl`
+    pub fn total_pnl(&self) -> Decimal {
+        self.realized_pnl + self.unrealized_pnl - self.total_commission
+    }
+}
+
 /// Position manager for tracking multiple positions
+///
+/// The netted `Position` of each symbol is kept under either accounting;
+/// hedged accounting adds the lots behind it.
 pub struct PositionManager {
-    positions: std::collections::HashMap<String, Position>,
+    positions: HashMap<String, Position>,
     default_position: Position,
+    accounting: PositionAccounting,
+    close_out: CloseOut,
+    hedged: HashMap<String, HedgedPosition>,
 }
 
 impl PositionManager {
     pub fn new() -> Self {
         Self {
-            positions: std::collections::HashMap::new(),
+            positions: HashMap::new(),
             default_position: Position::new(),
+            accounting: PositionAccounting::default(),
+            close_out: CloseOut::default(),
+            hedged: HashMap::new(),
         }
     }
     
+    /// Book fills as hedged lots, closed out in the given order
+    pub fn with_accounting(mut self, accounting: PositionAccounting, close_out: CloseOut) -> Self {
+        self.accounting = accounting;
+        self.close_out = close_out;
+        self
+    }
+    
+    pub fn accounting(&self) -> PositionAccounting {
+        self.accounting
+    }
+    
     /// Get position for a symbol
     pub fn get_position(&self, symbol: &str) -> &Position {
         self.positions.get(symbol).unwrap_or(&self.default_position)
@@ -179,28 +422,63 @@ impl PositionManager {
             .or_insert_with(Position::new)
     }
     
+    /// Lots of a symbol under hedged accounting
+    pub fn get_hedged(&self, symbol: &str) -> Option<&HedgedPosition> {
+        self.hedged.get(symbol)
+    }
+    
     /// Apply fill to position
     pub fn apply_fill(&mut self, symbol: &str, fill: &OrderFill) {
-        let position = self.get_position_mut(symbol);
-        position.apply_fill(fill);
+        self.apply_fill_with_effect(symbol, fill, PositionEffect::Close);
+    }
+    
+    /// Apply fill to position, returning the lots it closed
+    ///
+    /// Netting accounting ignores `effect` and closes no lots.
+    pub fn apply_fill_with_effect(&mut self, symbol: &str, fill: &OrderFill, effect: PositionEffect) -> Vec<LotClose> {
+        self.get_position_mut(symbol).apply_fill(fill);
+        match self.accounting {
+            PositionAccounting::Netting => Vec::new(),
+            PositionAccounting::Hedged => {
+                let close_out = self.close_out;
+                self.hedged.entry(symbol.to_string())
+                    .or_insert_with(|| HedgedPosition::new(close_out))
+                    .apply_fill(fill, effect)
+            }
+        }
     }
     
     /// Update all positions with current prices
-    pub fn update_prices(&mut self, prices: &std::collections::HashMap<String, Decimal>) {
+    pub fn update_prices(&mut self, prices: &HashMap<String, Decimal>) {
         for (symbol, position) in &mut self.positions {
             if let Some(&price) = prices.get(symbol) {
                 position.update_unrealized_pnl(price);
             }
         }
+        for (symbol, position) in &mut self.hedged {
+            if let Some(&price) = prices.get(symbol) {
+                position.update_unrealized_pnl(price);
+            }
+        }
     }
     
     /// Get total P&L across all positions
+    ///
+    /// Netting and lot accounting split realized and unrealized P&L
+    /// differently but agree on the total.
     pub fn total_pnl(&self) -> Decimal {
         self.positions.values()
             .map(|p| p.total_pnl())
             .sum()
     }
     
+    /// Every lot closed so far across all symbols
+    pub fn closed_lots(&self) -> Vec<&LotClose> {
+        self.hedged.values()
+            .flat_map(|position| position.closed_lots())
+            .collect()
+    }
+    
     /// Get all open positions
     pub fn open_positions(&self) -> Vec<(&String, &Position)> {
         self.positions.iter()
@@ -211,5 +489,72 @@ impl PositionManager {
     /// Reset all positions
     pub fn reset(&mut self) {
         self.positions.clear();
+        self.hedged.clear();
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn fill(side: OrderSide, price: i64, minute: u32, tag: &str) -> OrderFill {
+        OrderFill {
+            order_id: format!("{minute}"),
+            timestamp: Utc.with_ymd_and_hms(2024, 3, 12, 14, minute, 0).unwrap(),
+            price: Decimal::from(price),
+            quantity: 1,
+            side,
+            commission: Decimal::ONE,
+            slippage: Decimal::ZERO,
+            tag: Some(tag.to_string()),
+            position_effect: PositionEffect::Close,
+        }
+    }
+
+    #[test]
+    fn test_hedged_lots_attribute_pnl_by_close_out_order() {
+        use OrderSide::{Buy, Sell};
+        let run = |close_out| {
+            let mut manager = PositionManager::new().with_accounting(PositionAccounting::Hedged, close_out);
+            manager.apply_fill("MNQ", &fill(Buy, 100, 0, "first"));
+            manager.apply_fill("MNQ", &fill(Buy, 110, 1, "second"));
+            let closes = manager.apply_fill_with_effect("MNQ", &fill(Sell, 120, 2, "exit"), PositionEffect::Close);
+            manager.update_prices(&HashMap::from([("MNQ".to_string(), Decimal::from(120))]));
+            (manager, closes)
+        };
+
+        let (fifo, fifo_closes) = run(CloseOut::Fifo);
+        let [close] = &fifo_closes[..] else { panic!("one lot closed") };
+        assert_eq!((close.tag.as_deref(), close.pnl), (Some("first"), Decimal::from(20)));
+        // Entry and exit commission of the one contract
+        assert_eq!(close.net_pnl(), Decimal::from(18));
+
+        let (lifo, lifo_closes) = run(CloseOut::Lifo);
+        assert_eq!((lifo_closes[0].tag.as_deref(), lifo_closes[0].pnl), (Some("second"), Decimal::from(10)));
+        let lots: Vec<_> = lifo.get_hedged("MNQ").unwrap().lots().collect();
+        assert_eq!((lots.len(), lots[0].entry_price), (1, Decimal::from(100)));
+
+        // The netted position realizes against the average of 105, but every
+        // view agrees on the total
+        let hedged_total = |manager: &PositionManager| manager.get_hedged("MNQ").unwrap().total_pnl();
+        assert_eq!(fifo.get_position("MNQ").realized_pnl, Decimal::from(15));
+        assert_eq!(hedged_total(&fifo), Decimal::from(27));
+        assert_eq!(hedged_total(&lifo), Decimal::from(27));
+        assert_eq!(fifo.total_pnl(), Decimal::from(27));
+
+        // An opening sell sits beside the long instead of closing it
+        let mut manager = fifo;
+        let mut hedge = fill(Sell, 115, 3, "hedge");
+        hedge.position_effect = PositionEffect::Open;
+        assert!(manager.apply_fill_with_effect("MNQ", &hedge, hedge.position_effect).is_empty());
+        let position = manager.get_hedged("MNQ").unwrap();
+        assert_eq!((position.long_size(), position.short_size(), position.net_size()), (1, 1, 0));
+        assert!(manager.get_position("MNQ").is_flat());
+
+        let closes = manager.apply_fill_with_effect("MNQ", &fill(Buy, 105, 4, "cover"), PositionEffect::Close);
+        assert_eq!((closes[0].side, closes[0].pnl), (Sell, Decimal::from(10)));
+        assert_eq!(manager.get_hedged("MNQ").unwrap().long_size(), 1);
+        assert_eq!(manager.closed_lots().len(), 2);
+    }
+}
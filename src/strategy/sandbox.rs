@@ -0,0 +1,558 @@
+//! Out-of-process strategies under resource limits
+//!
+//! Plugin and Python strategies are code the engine cannot vouch for: an
+//! infinite loop or a leak inside the backtest process takes every other
+//! run on the host down with it. A `SandboxedStrategy` runs such a strategy
+//! in a worker process instead, started with ceilings on CPU time and
+//! address space, and talks to it over the worker's stdin and stdout.
+//!
+//! The protocol is one JSON object per line. Each request carries a `type`
+//! (`init`, `tick`, `fill`, `reset`, `session_end`, `save_state`,
+//! `load_state` or `metrics`) and is answered by exactly one reply object,
+//! where `{}` is a plain acknowledgement. A reply to `tick` may carry an
+//! `order`, to `save_state` a `state`, to `metrics` the `metrics`, to
+//! `init` the `state_version`, and any reply an `error` message. Anything
+//! the worker writes to stderr is logged.
+//!
+//! A worker that misses a reply deadline, exceeds its limits or exits is
+//! killed and the strategy goes quiet for the rest of the run; `failure`
+//! says why. The next `reset` starts a fresh worker.
+
+use crate::data::TickData;
+use crate::market::MarketDepth;
+use crate::strategy::state::StrategyStateError;
+use crate::strategy::traits::{OrderFill, StrategyMetrics};
+use crate::strategy::{Order, Position, Strategy, StrategyConfig, StrategyContext};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// Book levels per side sent with each tick
+pub const CONTEXT_DEPTH: usize = 10;
+
+/// How long a worker that stopped answering gets to exit before it is killed
+const EXIT_GRACE: Duration = Duration::from_millis(500);
+
+/// How often an exiting worker is checked on
+const EXIT_POLL: Duration = Duration::from_millis(10);
+
+/// Errors raised running a sandboxed strategy
+#[derive(Debug, thiserror::Error)]
+pub enum SandboxError {
+    #[error("Failed to start strategy worker {program}: {source}")]
+    Spawn { program: String, source: std::io::Error },
+    #[error("IO error talking to strategy worker: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Strategy worker sent an invalid reply: {0}")]
+    Protocol(String),
+    #[error("Strategy worker did not reply within {0}ms")]
+    Timeout(u64),
+    #[error("Strategy worker exceeded its CPU time limit of {0}s")]
+    CpuLimit(u64),
+    #[error("Strategy worker exited: {0}")]
+    Exited(String),
+    #[error("Strategy worker closed its pipes without exiting and was killed")]
+    Unresponsive,
+    #[error("Restarted strategy worker has state version {found}, expected {expected}")]
+    StateVersion { expected: u32, found: u32 },
+    #[error("Strategy reported an error: {0}")]
+    Strategy(String),
+}
+
+/// Ceilings the worker process runs under
+///
+/// CPU time and memory are enforced by the kernel on Linux; elsewhere only
+/// the reply deadline applies. A worker that runs out of memory fails its
+/// allocations and usually exits, which is reported as such.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxLimits {
+    /// CPU seconds the worker may use over its whole life
+    #[serde(default = "default_cpu_time_secs")]
+    pub cpu_time_secs: u64,
+    /// Address space the worker may map
+    #[serde(default = "default_memory_bytes")]
+    pub memory_bytes: u64,
+    /// Longest the engine waits for the reply to one request
+    #[serde(default = "default_reply_timeout_ms")]
+    pub reply_timeout_ms: u64,
+}
+
+fn default_cpu_time_secs() -> u64 {
+    600
+}
+
+fn default_memory_bytes() -> u64 {
+    2 << 30
+}
+
+fn default_reply_timeout_ms() -> u64 {
+    5_000
+}
+
+impl Default for SandboxLimits {
+    fn default() -> Self {
+        Self {
+            cpu_time_secs: default_cpu_time_secs(),
+            memory_bytes: default_memory_bytes(),
+            reply_timeout_ms: default_reply_timeout_ms(),
+        }
+    }
+}
+
+/// Worker command and its limits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxConfig {
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub working_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub limits: SandboxLimits,
+}
+
+impl SandboxConfig {
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            working_dir: None,
+            limits: SandboxLimits::default(),
+        }
+    }
+
+    /// A Python script speaking the protocol, run unbuffered
+    pub fn python(script: impl Into<String>) -> Self {
+        Self::new("python3").with_args(["-u".to_string(), script.into()])
+    }
+
+    pub fn with_args<I: IntoIterator<Item = String>>(mut self, args: I) -> Self {
+        self.args = args.into_iter().collect();
+        self
+    }
+
+    pub fn with_working_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.working_dir = Some(dir.into());
+        self
+    }
+
+    pub fn with_limits(mut self, limits: SandboxLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+/// A tick as the worker sees it
+#[derive(Debug, Serialize)]
+struct TickMessage<'a> {
+    timestamp_ns: i64,
+    price: Decimal,
+    volume: i32,
+    level: String,
+    kind: String,
+    contract: &'a str,
+}
+
+/// The parts of `StrategyContext` the worker sees
+#[derive(Debug, Serialize)]
+struct ContextMessage<'a> {
+    timestamp: DateTime<Utc>,
+    contract: &'a str,
+    market_open: bool,
+    session_high: Option<Decimal>,
+    session_low: Option<Decimal>,
+    session_volume: i64,
+    best_bid: Option<Decimal>,
+    best_ask: Option<Decimal>,
+    /// (price, volume), best first
+    bids: Vec<(Decimal, i32)>,
+    asks: Vec<(Decimal, i32)>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Request<'a> {
+    Init { config: &'a StrategyConfig },
+    Tick { tick: &'a TickMessage<'a>, context: &'a ContextMessage<'a> },
+    Fill { fill: &'a OrderFill },
+    Reset,
+    SessionEnd,
+    SaveState,
+    LoadState { state: serde_json::Value, version: u32 },
+    Metrics,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Reply {
+    #[serde(default)]
+    order: Option<Order>,
+    #[serde(default)]
+    state: Option<serde_json::Value>,
+    #[serde(default)]
+    metrics: Option<StrategyMetrics>,
+    #[serde(default)]
+    state_version: Option<u32>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// A running worker process
+struct Worker {
+    child: Child,
+    stdin: ChildStdin,
+    replies: Receiver<std::io::Result<String>>,
+    limits: SandboxLimits,
+}
+
+impl Worker {
+    fn spawn(name: &str, config: &SandboxConfig) -> Result<Self, SandboxError> {
+        let mut command = Command::new(&config.program);
+        command.args(&config.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(dir) = &config.working_dir {
+            command.current_dir(dir);
+        }
+        apply_limits(&mut command, &config.limits);
+
+        let mut child = command.spawn()
+            .map_err(|source| SandboxError::Spawn { program: config.program.clone(), source })?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+
+        // Replies are read on their own thread so a silent worker can be timed out
+        let (sender, replies) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        let name = name.to_string();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                info!("[{}] {}", name, line);
+            }
+        });
+
+        Ok(Self { child, stdin, replies, limits: config.limits.clone() })
+    }
+
+    fn call(&mut self, request: &Request) -> Result<Reply, SandboxError> {
+        let mut line = serde_json::to_vec(request).map_err(|e| SandboxError::Protocol(e.to_string()))?;
+        line.push(b'\n');
+        if self.stdin.write_all(&line).and_then(|_| self.stdin.flush()).is_err() {
+            return Err(self.exit_error());
+        }
+
+        let timeout = Duration::from_millis(self.limits.reply_timeout_ms);
+        let line = match self.replies.recv_timeout(timeout) {
+            Ok(line) => line?,
+            Err(RecvTimeoutError::Timeout) => {
+                // Spinning until the kernel steps in looks the same as a slow reply
+                return Err(match self.child.try_wait() {
+                    Ok(Some(_)) => self.exit_error(),
+                    _ => SandboxError::Timeout(self.limits.reply_timeout_ms),
+                });
+            }
+            Err(RecvTimeoutError::Disconnected) => return Err(self.exit_error()),
+        };
+        let reply: Reply = serde_json::from_str(&line)
+            .map_err(|e| SandboxError::Protocol(format!("{}: {}", e, line)))?;
+        match reply.error {
+            Some(message) => Err(SandboxError::Strategy(message)),
+            None => Ok(reply),
+        }
+    }
+
+    /// Why the worker stopped answering
+    ///
+    /// A worker that closed its pipes but is still running after
+    /// `EXIT_GRACE` is killed rather than waited on.
+    fn exit_error(&mut self) -> SandboxError {
+        let deadline = Instant::now() + EXIT_GRACE;
+        let status = loop {
+            match self.child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if Instant::now() < deadline => std::thread::sleep(EXIT_POLL),
+                Ok(None) => {
+                    let _ = self.child.kill();
+                    let _ = self.child.wait();
+                    return SandboxError::Unresponsive;
+                }
+                Err(e) => return SandboxError::Io(e),
+            }
+        };
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            if status.signal() == Some(libc::SIGXCPU) {
+                return SandboxError::CpuLimit(self.limits.cpu_time_secs);
+            }
+        }
+        SandboxError::Exited(status.to_string())
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn apply_limits(command: &mut Command, limits: &SandboxLimits) {
+    use std::os::unix::process::CommandExt;
+
+    let cpu = libc::rlimit {
+        rlim_cur: limits.cpu_time_secs as libc::rlim_t,
+        rlim_max: limits.cpu_time_secs.saturating_add(1) as libc::rlim_t,
+    };
+    let memory = libc::rlimit {
+        rlim_cur: limits.memory_bytes as libc::rlim_t,
+        rlim_max: limits.memory_bytes as libc::rlim_t,
+    };
+    // SAFETY: runs in the forked child before exec and only calls
+    // setrlimit, which is async-signal-safe, on values owned by the closure
+    unsafe {
+        command.pre_exec(move || {
+            if libc::setrlimit(libc::RLIMIT_CPU, &cpu) != 0 || libc::setrlimit(libc::RLIMIT_AS, &memory) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn apply_limits(_command: &mut Command, _limits: &SandboxLimits) {
+    warn!("CPU and memory limits of sandboxed strategies are only enforced on Linux");
+}
+
+/// A strategy running in a resource-limited worker process
+pub struct SandboxedStrategy {
+    config: StrategyConfig,
+    sandbox: SandboxConfig,
+    worker: Mutex<Option<Worker>>,
+    failure: Mutex<Option<String>>,
+    /// Tracked from fills, so it stays right if the worker dies
+    position: Position,
+    state_version: u32,
+}
+
+impl SandboxedStrategy {
+    /// Start the worker and send it the strategy configuration
+    pub fn spawn(config: StrategyConfig, sandbox: SandboxConfig) -> Result<Self, SandboxError> {
+        let mut worker = Worker::spawn(&config.name, &sandbox)?;
+        let reply = worker.call(&Request::Init { config: &config })?;
+        Ok(Self {
+            state_version: reply.state_version.unwrap_or(1),
+            config,
+            sandbox,
+            worker: Mutex::new(Some(worker)),
+            failure: Mutex::new(None),
+            position: Position::new(),
+        })
+    }
+
+    /// Why the worker was stopped, if it was
+    pub fn failure(&self) -> Option<String> {
+        self.failure.lock().unwrap().clone()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.worker.lock().unwrap().is_some()
+    }
+
+    /// Send a request, stopping the worker if it misbehaves
+    fn call(&self, request: &Request) -> Option<Reply> {
+        let mut worker = self.worker.lock().unwrap();
+        match worker.as_mut()?.call(request) {
+            Ok(reply) => Some(reply),
+            Err(SandboxError::Strategy(message)) => {
+                warn!("Strategy {} reported an error: {}", self.config.name, message);
+                None
+            }
+            Err(e) => {
+                error!("Stopping sandboxed strategy {}: {}", self.config.name, e);
+                *self.failure.lock().unwrap() = Some(e.to_string());
+                *worker = None;
+                None
+            }
+        }
+    }
+}
+
+impl Strategy for SandboxedStrategy {
+    fn on_tick(&mut self, tick: &TickData, context: &StrategyContext) -> Option<Order> {
+        let depth = MarketDepth::from_order_book(&context.order_book, CONTEXT_DEPTH);
+        let tick = TickMessage {
+            timestamp_ns: tick.timestamp,
+            price: tick.price,
+            volume: tick.volume,
+            level: format!("{:?}", tick.level),
+            kind: format!("{:?}", tick.mdt),
+            contract: &tick.contract_month,
+        };
+        let context = ContextMessage {
+            timestamp: context.timestamp,
+            contract: &context.contract,
+            market_open: context.market_open,
+            session_high: context.session_high,
+            session_low: context.session_low,
+            session_volume: context.session_volume,
+            best_bid: context.order_book.best_bid,
+            best_ask: context.order_book.best_ask,
+            bids: depth.bids,
+            asks: depth.asks,
+        };
+        self.call(&Request::Tick { tick: &tick, context: &context })?.order
+    }
+
+    fn on_order_fill(&mut self, fill: &OrderFill) {
+        self.position.apply_fill(fill);
+        self.call(&Request::Fill { fill });
+    }
+
+    fn get_parameters(&self) -> &StrategyConfig {
+        &self.config
+    }
+
+    /// Also replaces a worker stopped during the previous run
+    fn reset(&mut self) {
+        self.position.reset();
+        let mut worker = self.worker.lock().unwrap();
+        if worker.is_none() {
+            let expected = self.state_version;
+            let started = Worker::spawn(&self.config.name, &self.sandbox).and_then(|mut started| {
+                // Saved state is exchanged in the version the strategy started with
+                let found = started.call(&Request::Init { config: &self.config })?.state_version.unwrap_or(1);
+                match found == expected {
+                    true => Ok(started),
+                    false => Err(SandboxError::StateVersion { expected, found }),
+                }
+            });
+            match started {
+                Ok(started) => {
+                    *worker = Some(started);
+                    *self.failure.lock().unwrap() = None;
+                }
+                Err(e) => {
+                    error!("Failed to restart sandboxed strategy {}: {}", self.config.name, e);
+                    *self.failure.lock().unwrap() = Some(e.to_string());
+                    return;
+                }
+            }
+        }
+        drop(worker);
+        self.call(&Request::Reset);
+    }
+
+    fn get_position(&self) -> &Position {
+        &self.position
+    }
+
+    fn get_metrics(&self) -> StrategyMetrics {
+        self.call(&Request::Metrics)
+            .and_then(|reply| reply.metrics)
+            .unwrap_or_else(|| StrategyMetrics {
+                total_pnl: self.position.total_pnl(),
+                ..Default::default()
+            })
+    }
+
+    fn on_session_end(&mut self) {
+        self.call(&Request::SessionEnd);
+    }
+
+    fn state_version(&self) -> u32 {
+        self.state_version
+    }
+
+    fn save_state(&self) -> Option<serde_json::Value> {
+        self.call(&Request::SaveState)?.state
+    }
+
+    fn load_state(&mut self, state: serde_json::Value, version: u32) -> Result<(), StrategyStateError> {
+        let mut worker = self.worker.lock().unwrap();
+        let Some(running) = worker.as_mut() else {
+            return Err(StrategyStateError::Invalid("strategy worker is not running".to_string()));
+        };
+        running.call(&Request::LoadState { state, version })
+            .map(|_| ())
+            .map_err(|e| StrategyStateError::Invalid(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_runaway_worker_is_stopped() {
+        // Acknowledges init and the first request, then spins
+        let script = "read l; echo '{\"state_version\": 3}'; read l; echo '{}'; read l; while :; do :; done";
+        let sandbox = SandboxConfig::new("sh")
+            .with_args(["-c".to_string(), script.to_string()])
+            .with_limits(SandboxLimits { cpu_time_secs: 1, reply_timeout_ms: 5_000, ..Default::default() });
+
+        let mut strategy = SandboxedStrategy::spawn(StrategyConfig::default(), sandbox).unwrap();
+        assert_eq!(strategy.state_version(), 3);
+        strategy.on_session_end();
+        assert!(strategy.is_running());
+
+        // The kernel stops the loop after a second of CPU, well before the deadline
+        strategy.on_session_end();
+        assert!(!strategy.is_running());
+        assert!(strategy.failure().unwrap().contains("CPU time limit"));
+        assert!(strategy.save_state().is_none());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_worker_closing_its_pipes_is_killed() {
+        let script = "read l; echo '{}'; exec sleep 30 >&-";
+        let sandbox = SandboxConfig::new("sh").with_args(["-c".to_string(), script.to_string()]);
+
+        let mut strategy = SandboxedStrategy::spawn(StrategyConfig::default(), sandbox).unwrap();
+        let started = Instant::now();
+        strategy.on_session_end();
+        assert!(!strategy.is_running());
+        assert!(strategy.failure().unwrap().contains("without exiting"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_restarted_worker_must_keep_the_state_version() {
+        // Each run reports a state version one higher than the last
+        let counter = std::env::temp_dir().join(format!("sandbox-version-{}", uuid::Uuid::new_v4()));
+        let script = format!(
+            "v=$(( $(cat {0} 2>/dev/null || echo 0) + 1 )); echo $v > {0}; read l; echo \"{{\\\"state_version\\\": $v}}\"; exit 1",
+            counter.display()
+        );
+        let sandbox = SandboxConfig::new("sh").with_args(["-c".to_string(), script]);
+
+        let mut strategy = SandboxedStrategy::spawn(StrategyConfig::default(), sandbox).unwrap();
+        assert_eq!(strategy.state_version(), 1);
+        strategy.on_session_end();
+        assert!(!strategy.is_running());
+
+        strategy.reset();
+        assert!(!strategy.is_running());
+        assert!(strategy.failure().unwrap().contains("state version 2, expected 1"));
+        let _ = std::fs::remove_file(counter);
+    }
+}
@@ -0,0 +1,134 @@
+//! Where a strategy's code came from
+//!
+//! Each backtest captures a `CodeSnapshot` of its strategy's code so a
+//! result can be traced back to the exact code that produced it: the commit
+//! of a strategy kept in git, the digest of a plugin executable, or the full
+//! source of a bundled template. Template sources are kept once per digest
+//! in a `CodeArchive` rather than copied into every result.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use super::templates::StrategyTemplate;
+
+/// Commit the engine was built from, when the build provides it
+pub const ENGINE_COMMIT: Option<&str> = option_env!("STRATEGY_LAB_GIT_COMMIT");
+
+/// Errors raised capturing a strategy's code
+#[derive(Debug, thiserror::Error)]
+pub enum SourceError {
+    #[error("No strategy template named {0}")]
+    UnknownTemplate(String),
+    #[error("{0} is not a git commit hash")]
+    InvalidCommit(String),
+    #[error("Failed to read plugin {path}: {source}")]
+    Plugin { path: PathBuf, source: std::io::Error },
+}
+
+/// How a strategy's code is identified
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StrategySource {
+    /// Compiled into the engine; identified by the engine's own commit
+    #[default]
+    Builtin,
+    /// Kept in a git repository and built at `commit`
+    Git { repository: String, commit: String },
+    /// Plugin or script executable run in the sandbox
+    Plugin { path: PathBuf },
+    /// One of the bundled strategy templates, by name
+    Template { name: String },
+}
+
+/// The code a run used
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodeSnapshot {
+    pub source: StrategySource,
+    /// Hex-encoded SHA-256 of the plugin file or template source
+    pub sha256: Option<String>,
+    pub engine_version: String,
+    pub engine_commit: Option<String>,
+    pub captured_at: DateTime<Utc>,
+}
+
+/// Template sources captured by runs, keyed by digest
+#[derive(Debug, Clone, Default)]
+pub struct CodeArchive {
+    inner: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl CodeArchive {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Snapshot the code `source` identifies as it is now, archiving any
+    /// source text
+    pub fn capture(&self, source: &StrategySource) -> Result<CodeSnapshot, SourceError> {
+        let sha256 = match source {
+            StrategySource::Builtin => None,
+            StrategySource::Git { commit, .. } => {
+                if !is_commit_hash(commit) {
+                    return Err(SourceError::InvalidCommit(commit.clone()));
+                }
+                None
+            }
+            StrategySource::Plugin { path } => {
+                let bytes = std::fs::read(path)
+                    .map_err(|source| SourceError::Plugin { path: path.clone(), source })?;
+                Some(format!("{:x}", Sha256::digest(&bytes)))
+            }
+            StrategySource::Template { name } => {
+                let template = StrategyTemplate::find(name)
+                    .ok_or_else(|| SourceError::UnknownTemplate(name.clone()))?;
+                let sha256 = format!("{:x}", Sha256::digest(template.code_template.as_bytes()));
+                self.inner.write().unwrap()
+                    .entry(sha256.clone())
+                    .or_insert(template.code_template);
+                Some(sha256)
+            }
+        };
+
+        Ok(CodeSnapshot {
+            source: source.clone(),
+            sha256,
+            engine_version: env!("CARGO_PKG_VERSION").to_string(),
+            engine_commit: ENGINE_COMMIT.map(str::to_string),
+            captured_at: Utc::now(),
+        })
+    }
+
+    /// Archived source text with this digest
+    pub fn get(&self, sha256: &str) -> Option<String> {
+        self.inner.read().unwrap().get(sha256).cloned()
+    }
+}
+
+/// Abbreviated or full hex commit hash
+fn is_commit_hash(commit: &str) -> bool {
+    (7..=64).contains(&commit.len()) && commit.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_source_is_archived_by_digest() {
+        let archive = CodeArchive::new();
+        let source = StrategySource::Template { name: "Bid Ask Bounce".to_string() };
+        let snapshot = archive.capture(&source).unwrap();
+
+        let sha256 = snapshot.sha256.unwrap();
+        let code = archive.get(&sha256).unwrap();
+        assert_eq!(code, StrategyTemplate::bid_ask_bounce().code_template);
+        assert_eq!(archive.capture(&source).unwrap().sha256.as_deref(), Some(sha256.as_str()));
+
+        let git = StrategySource::Git { repository: "strategies".to_string(), commit: "main".to_string() };
+        assert!(matches!(archive.capture(&git), Err(SourceError::InvalidCommit(_))));
+    }
+}
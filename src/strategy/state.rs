@@ -0,0 +1,206 @@
+//! Strategy state persisted across sessions
+//!
+//! Strategies that accumulate state over several days (volume profiles,
+//! rolling statistics) export it through `Strategy::save_state`. The engine
+//! wraps it in a versioned envelope and stores it keyed by strategy name,
+//! strategy version and the dataset the state was built on, so the next
+//! day's run can pick up where the previous one stopped.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Envelope layout written by this version of the engine
+pub const STATE_FORMAT_VERSION: u32 = 1;
+
+/// Errors raised saving or restoring strategy state
+#[derive(Debug, thiserror::Error)]
+pub enum StrategyStateError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("State envelope format {0} is newer than this engine supports")]
+    UnsupportedFormat(u32),
+    #[error("State version {found} cannot be loaded, supported up to {supported}")]
+    UnsupportedVersion { found: u32, supported: u32 },
+    #[error("Invalid strategy state: {0}")]
+    Invalid(String),
+}
+
+/// Which saved state a run starts from
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StateResume {
+    /// State saved for the closest dataset id sorting before this run's
+    ///
+    /// Daily files named by date, such as `mnq_20240612.parquet`, resume
+    /// from the previous trading day.
+    #[default]
+    Previous,
+    /// State saved for a specific dataset id
+    From(String),
+    /// Start from a clean strategy
+    Fresh,
+}
+
+/// Identifies one saved state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateKey {
+    pub strategy: String,
+    pub strategy_version: String,
+    /// Dataset the state was built on, usually its file name
+    pub dataset: String,
+}
+
+impl StateKey {
+    pub fn new(strategy: impl Into<String>, strategy_version: impl Into<String>, dataset: impl Into<String>) -> Self {
+        Self {
+            strategy: strategy.into(),
+            strategy_version: strategy_version.into(),
+            dataset: dataset.into(),
+        }
+    }
+
+    /// Dataset id of a data file
+    pub fn dataset_id(data_path: &Path) -> String {
+        data_path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| data_path.to_string_lossy().to_string())
+    }
+}
+
+/// A saved strategy state with its version information
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateEnvelope {
+    /// Envelope layout, see `STATE_FORMAT_VERSION`
+    pub format: u32,
+    pub key: StateKey,
+    /// Version of the strategy's own state layout, from `Strategy::state_version`
+    pub state_version: u32,
+    pub saved_at: DateTime<Utc>,
+    pub state: serde_json::Value,
+}
+
+/// Strategy states stored as JSON files under a root directory
+///
+/// Files are laid out as `<root>/<strategy>/<strategy_version>/<dataset>.json`.
+#[derive(Debug, Clone)]
+pub struct StrategyStateStore {
+    root: PathBuf,
+}
+
+impl StrategyStateStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn strategy_dir(&self, strategy: &str, strategy_version: &str) -> PathBuf {
+        self.root.join(sanitize(strategy)).join(sanitize(strategy_version))
+    }
+
+    fn path(&self, key: &StateKey) -> PathBuf {
+        self.strategy_dir(&key.strategy, &key.strategy_version)
+            .join(format!("{}.json", sanitize(&key.dataset)))
+    }
+
+    pub fn save(&self, key: StateKey, state_version: u32, state: serde_json::Value) -> Result<(), StrategyStateError> {
+        let path = self.path(&key);
+        let envelope = StateEnvelope {
+            format: STATE_FORMAT_VERSION,
+            key,
+            state_version,
+            saved_at: Utc::now(),
+            state,
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Write-then-rename so an interrupted save keeps the previous state
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(&envelope)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    pub fn load(&self, key: &StateKey) -> Result<Option<StateEnvelope>, StrategyStateError> {
+        read_envelope(&self.path(key))
+    }
+
+    /// State saved for the closest dataset sorting before `key.dataset`
+    pub fn load_previous(&self, key: &StateKey) -> Result<Option<StateEnvelope>, StrategyStateError> {
+        let dir = self.strategy_dir(&key.strategy, &key.strategy_version);
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let current = sanitize(&key.dataset);
+        let mut previous = None;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let stem = path.file_stem().map(|stem| stem.to_string_lossy().to_string());
+            if stem.as_ref().is_some_and(|stem| *stem < current) {
+                previous = previous.max(stem);
+            }
+        }
+
+        match previous {
+            Some(stem) => read_envelope(&dir.join(format!("{}.json", stem))),
+            None => Ok(None),
+        }
+    }
+}
+
+fn read_envelope(path: &Path) -> Result<Option<StateEnvelope>, StrategyStateError> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let envelope: StateEnvelope = serde_json::from_slice(&bytes)?;
+    if envelope.format > STATE_FORMAT_VERSION {
+        return Err(StrategyStateError::UnsupportedFormat(envelope.format));
+    }
+    Ok(Some(envelope))
+}
+
+/// Make a key component safe to use as a file name
+fn sanitize(component: &str) -> String {
+    component.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_previous_dataset_state() {
+        let root = std::env::temp_dir().join(format!("strategy_state_{}", uuid::Uuid::new_v4()));
+        let store = StrategyStateStore::new(&root);
+        let key = |dataset: &str| StateKey::new("Volume Profile", "1.0.0", dataset);
+
+        store.save(key("mnq_20240610.parquet"), 1, serde_json::json!({"days": 1})).unwrap();
+        store.save(key("mnq_20240611.parquet"), 1, serde_json::json!({"days": 2})).unwrap();
+        store.save(key("mnq_20240613.parquet"), 1, serde_json::json!({"days": 4})).unwrap();
+
+        let previous = store.load_previous(&key("mnq_20240612.parquet")).unwrap().unwrap();
+        assert_eq!(previous.key.dataset, "mnq_20240611.parquet");
+        assert_eq!(previous.state["days"], 2);
+
+        assert!(store.load_previous(&key("mnq_20240610.parquet")).unwrap().is_none());
+        assert!(store.load(&StateKey::new("Volume Profile", "2.0.0", "mnq_20240611.parquet")).unwrap().is_none());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -165,6 +165,8 @@ use super::super::traits::OrderFill;
             quantity: 1,
             side: OrderSide::Buy,
             commission: Decimal::from_str("1.00").unwrap(),
+            tag: None,
+            position_effect: Default::default(),
         };
 
         strategy.on_order_fill(&fill);
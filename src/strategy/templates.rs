@@ -21,6 +21,23 @@ pub struct ParameterTemplate {
 }
 
 impl StrategyTemplate {
+    /// Every bundled template
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::order_book_imbalance(),
+            Self::bid_ask_bounce(),
+            Self::vwap_reversion(),
+            Self::opening_range_breakout(),
+            Self::absorption(),
+            Self::delta_divergence(),
+        ]
+    }
+
+    /// The bundled template with this name
+    pub fn find(name: &str) -> Option<Self> {
+        Self::all().into_iter().find(|template| template.name == name)
+    }
+
     pub fn order_book_imbalance() -> Self {
         let mut parameters = HashMap::new();
         
@@ -71,4 +88,133 @@ impl StrategyTemplate {
             code_template: include_str!("examples/bid_ask_bounce.rs").to_string(),
         }
     }
+
+    pub fn vwap_reversion() -> Self {
+        let mut parameters = HashMap::new();
+        
+        parameters.insert("entry_std_devs".to_string(), ParameterTemplate {
+            name: "Entry Std Devs".to_string(),
+            param_type: "float".to_string(),
+            default_value: serde_json::Value::Number(serde_json::Number::from_f64(2.0).unwrap()),
+            min_value: Some(1.0),
+            max_value: Some(4.0),
+            description: "Distance from VWAP in standard deviations required to enter".to_string(),
+        });
+
+        parameters.insert("exit_std_devs".to_string(), ParameterTemplate {
+            name: "Exit Std Devs".to_string(),
+            param_type: "float".to_string(),
+            default_value: serde_json::Value::Number(serde_json::Number::from_f64(0.25).unwrap()),
+            min_value: Some(0.0),
+            max_value: Some(1.0),
+            description: "Distance from VWAP in standard deviations at which to take profit".to_string(),
+        });
+
+        Self {
+            name: "VWAP Reversion".to_string(),
+            description: "Strategy that fades stretched moves away from the session VWAP".to_string(),
+            version: "1.0".to_string(),
+            parameters,
+            code_template: include_str!("examples/vwap_reversion.rs").to_string(),
+        }
+    }
+
+    pub fn opening_range_breakout() -> Self {
+        let mut parameters = HashMap::new();
+        
+        parameters.insert("range_minutes".to_string(), ParameterTemplate {
+            name: "Range Minutes".to_string(),
+            param_type: "float".to_string(),
+            default_value: serde_json::Value::Number(serde_json::Number::from_f64(15.0).unwrap()),
+            min_value: Some(5.0),
+            max_value: Some(60.0),
+            description: "Length of the opening range in minutes".to_string(),
+        });
+
+        parameters.insert("breakout_buffer".to_string(), ParameterTemplate {
+            name: "Breakout Buffer".to_string(),
+            param_type: "float".to_string(),
+            default_value: serde_json::Value::Number(serde_json::Number::from_f64(0.5).unwrap()),
+            min_value: Some(0.0),
+            max_value: Some(2.0),
+            description: "Extra distance beyond the range required to confirm a breakout".to_string(),
+        });
+
+        parameters.insert("target_multiple".to_string(), ParameterTemplate {
+            name: "Target Multiple".to_string(),
+            param_type: "float".to_string(),
+            default_value: serde_json::Value::Number(serde_json::Number::from_f64(1.0).unwrap()),
+            min_value: Some(0.5),
+            max_value: Some(3.0),
+            description: "Profit target as a multiple of the opening range width".to_string(),
+        });
+
+        Self {
+            name: "Opening Range Breakout".to_string(),
+            description: "Strategy that trades breakouts of the session opening range".to_string(),
+            version: "1.0".to_string(),
+            parameters,
+            code_template: include_str!("examples/opening_range_breakout.rs").to_string(),
+        }
+    }
+
+    pub fn absorption() -> Self {
+        let mut parameters = HashMap::new();
+        
+        parameters.insert("absorption_volume".to_string(), ParameterTemplate {
+            name: "Absorption Volume".to_string(),
+            param_type: "float".to_string(),
+            default_value: serde_json::Value::Number(serde_json::Number::from_f64(300.0).unwrap()),
+            min_value: Some(50.0),
+            max_value: Some(2000.0),
+            description: "Aggressive volume that must trade into one price without breaking it".to_string(),
+        });
+
+        parameters.insert("window_ms".to_string(), ParameterTemplate {
+            name: "Window (ms)".to_string(),
+            param_type: "float".to_string(),
+            default_value: serde_json::Value::Number(serde_json::Number::from_f64(5000.0).unwrap()),
+            min_value: Some(500.0),
+            max_value: Some(30000.0),
+            description: "Window in which the absorbed volume must accumulate".to_string(),
+        });
+
+        Self {
+            name: "Absorption".to_string(),
+            description: "Strategy that joins passive orders absorbing aggressive flow".to_string(),
+            version: "1.0".to_string(),
+            parameters,
+            code_template: include_str!("examples/absorption.rs").to_string(),
+        }
+    }
+
+    pub fn delta_divergence() -> Self {
+        let mut parameters = HashMap::new();
+        
+        parameters.insert("lookback_trades".to_string(), ParameterTemplate {
+            name: "Lookback Trades".to_string(),
+            param_type: "float".to_string(),
+            default_value: serde_json::Value::Number(serde_json::Number::from_f64(500.0).unwrap()),
+            min_value: Some(100.0),
+            max_value: Some(5000.0),
+            description: "Number of trades in the swing lookback window".to_string(),
+        });
+
+        parameters.insert("min_delta_divergence".to_string(), ParameterTemplate {
+            name: "Minimum Delta Divergence".to_string(),
+            param_type: "float".to_string(),
+            default_value: serde_json::Value::Number(serde_json::Number::from_f64(50.0).unwrap()),
+            min_value: Some(10.0),
+            max_value: Some(500.0),
+            description: "How far delta must lag the prior swing extreme".to_string(),
+        });
+
+        Self {
+            name: "Delta Divergence".to_string(),
+            description: "Strategy that fades new highs/lows not confirmed by cumulative delta".to_string(),
+            version: "1.0".to_string(),
+            parameters,
+            code_template: include_str!("examples/delta_divergence.rs").to_string(),
+        }
+    }
 }
\ No newline at end of file
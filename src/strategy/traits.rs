@@ -1,8 +1,10 @@
 //! Core strategy trait interface defining required methods
 
 use crate::data::TickData;
-use crate::market::OrderBookState;
+use crate::market::{BookDepth, OrderBookState};
 use crate::strategy::{Order, Position, Signal, StrategyConfig};
+use crate::strategy::lookahead::{LookaheadGuard, Timestamped};
+use crate::strategy::state::StrategyStateError;
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
@@ -63,12 +65,96 @@ pub trait Strategy: Send + Sync {
         None
     }
     
+    /// Optional: How much of the book the strategy reads
+    /// 
+    /// Strategies that only use the best bid and ask can return
+    /// `BookDepth::TopOfBook`, and the engine skips reconstructing depth.
+    fn book_depth(&self) -> BookDepth {
+        BookDepth::Full
+    }
+    
     /// Optional: Called at the end of each trading session
     /// 
     /// Use this to close positions, calculate daily metrics, etc.
     fn on_session_end(&mut self) {
         // Default: do nothing
     }
+    
+    /// Optional: Version of the layout returned by `save_state`
+    /// 
+    /// Bump this when the saved state changes shape, and handle older
+    /// versions in `load_state`.
+    fn state_version(&self) -> u32 {
+        1
+    }
+    
+    /// Optional: State to carry over into the next session's run
+    /// 
+    /// Called by the engine at the end of a run when a state store is
+    /// attached. Return `None` for strategies that start fresh every day.
+    fn save_state(&self) -> Option<serde_json::Value> {
+        None
+    }
+    
+    /// Optional: Restore state saved by a previous run
+    /// 
+    /// Called after `reset` with the state and the `state_version` it was
+    /// saved under. Return an error to start from a clean state instead.
+    fn load_state(&mut self, _state: serde_json::Value, _version: u32) -> Result<(), StrategyStateError> {
+        Ok(())
+    }
+}
+
+/// Strategies chosen at runtime, e.g. a template picked by name, run
+/// through the engine like any other
+impl Strategy for Box<dyn Strategy> {
+    fn on_tick(&mut self, tick: &TickData, context: &StrategyContext) -> Option<Order> {
+        (**self).on_tick(tick, context)
+    }
+    
+    fn on_order_fill(&mut self, fill: &OrderFill) {
+        (**self).on_order_fill(fill)
+    }
+    
+    fn get_parameters(&self) -> &StrategyConfig {
+        (**self).get_parameters()
+    }
+    
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+    
+    fn get_position(&self) -> &Position {
+        (**self).get_position()
+    }
+    
+    fn get_metrics(&self) -> StrategyMetrics {
+        (**self).get_metrics()
+    }
+    
+    fn on_order_book_update(&mut self, book: &OrderBookState) -> Option<Signal> {
+        (**self).on_order_book_update(book)
+    }
+    
+    fn book_depth(&self) -> BookDepth {
+        (**self).book_depth()
+    }
+    
+    fn on_session_end(&mut self) {
+        (**self).on_session_end()
+    }
+    
+    fn state_version(&self) -> u32 {
+        (**self).state_version()
+    }
+    
+    fn save_state(&self) -> Option<serde_json::Value> {
+        (**self).save_state()
+    }
+    
+    fn load_state(&mut self, state: serde_json::Value, version: u32) -> Result<(), StrategyStateError> {
+        (**self).load_state(state, version)
+    }
 }
 
 /// Context provided to strategies containing market state and utilities
@@ -94,6 +180,9 @@ pub struct StrategyContext {
     
     /// Is market open for trading
     pub market_open: bool,
+    
+    /// Flags attempts to read data stamped after `timestamp`
+    pub lookahead: LookaheadGuard,
 }
 
 impl StrategyContext {
@@ -112,6 +201,47 @@ impl StrategyContext {
         self.order_book.imbalance()
     }
     
+    /// Entries of a time-ordered series up to the current event
+    pub fn visible<'a, T: Timestamped>(&self, series: &'a [T]) -> &'a [T] {
+        let end = series.partition_point(|item| item.event_time() <= self.timestamp);
+        &series[..end]
+    }
+    
+    /// Latest entry of a time-ordered series at or before the current event
+    pub fn latest<'a, T: Timestamped>(&self, series: &'a [T]) -> Option<&'a T> {
+        self.visible(series).last()
+    }
+    
+    /// Latest entry at or before `time`
+    /// 
+    /// Returns `None` and records a lookahead violation if `time` is after
+    /// the current event.
+    pub fn value_at<'a, T: Timestamped>(
+        &self,
+        series: &'a [T],
+        time: DateTime<Utc>,
+        source: &str,
+    ) -> Option<&'a T> {
+        if !self.lookahead.check(self.timestamp, time, source) {
+            return None;
+        }
+        let end = series.partition_point(|item| item.event_time() <= time);
+        series[..end].last()
+    }
+    
+    /// Entry of a series by index
+    /// 
+    /// Returns `None` and records a lookahead violation if the entry is
+    /// stamped after the current event.
+    pub fn at_index<'a, T: Timestamped>(&self, series: &'a [T], index: usize, source: &str) -> Option<&'a T> {
+        let item = series.get(index)?;
+        if self.lookahead.check(self.timestamp, item.event_time(), source) {
+            Some(item)
+        } else {
+            None
+        }
+    }
+    
     /// Check if we're near session high
     pub fn near_session_high(&self, threshold: Decimal) -> bool {
         if let (Some(high), Some(mid)) = (self.session_high, self.mid_price()) {
@@ -154,6 +284,14 @@ pub struct OrderFill {
     
     /// Slippage from intended price
     pub slippage: Decimal,
+    
+    /// Tag of the order filled
+    #[serde(default)]
+    pub tag: Option<String>,
+    
+    /// Position effect of the order filled
+    #[serde(default)]
+    pub position_effect: crate::strategy::PositionEffect,
 }
 
 /// Strategy performance metrics
@@ -0,0 +1,279 @@
+//! Catalog of ingested datasets
+//!
+//! One entry per daily file that passed validation, keyed by file name. The
+//! catalog is kept as a single JSON document so it can be inspected and
+//! backed up alongside the data it describes.
+//!
+//! Each dataset belongs to a workspace. Files delivered to the drop
+//! directory belong to the default workspace and are shared with every
+//! workspace; samples belong to the workspace that cut them. When quotas are
+//! attached, adding a dataset charges its file size to the owning workspace.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use crate::data::validation::IngestionSummary;
+use crate::market::{LiquiditySummary, SessionSpread};
+use crate::subscription::license::{DataLicense, LicenseError};
+use crate::subscription::sampling::Derivation;
+use crate::timestamp::SessionCalendar;
+use crate::workspace::{WorkspaceError, WorkspaceRegistry, DEFAULT_WORKSPACE};
+
+/// Errors raised by the dataset catalog
+#[derive(Debug, thiserror::Error)]
+pub enum CatalogError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error(transparent)]
+    Quota(#[from] WorkspaceError),
+}
+
+/// An ingested dataset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetEntry {
+    /// File name the dataset was delivered as
+    pub id: String,
+    /// Where the file lives after ingestion
+    pub path: String,
+    /// Exchange trade date of the last tick
+    pub trade_date: Option<NaiveDate>,
+    pub contracts: Vec<String>,
+    pub ticks: usize,
+    /// Nanoseconds since epoch
+    pub first_timestamp: Option<i64>,
+    pub last_timestamp: Option<i64>,
+    /// Size and modification time of the delivered file
+    pub fingerprint: String,
+    pub ingested_at: DateTime<Utc>,
+    pub validation: IngestionSummary,
+    /// Lineage graph node recorded for the dataset
+    #[serde(default)]
+    pub lineage_id: Option<String>,
+    /// Book liquidity over the session; the full time series is stored
+    /// next to the data file, see `LiquidityProfile::load`
+    #[serde(default)]
+    pub liquidity: Option<LiquiditySummary>,
+    /// Bid-ask spread statistics of each session in the data
+    #[serde(default)]
+    pub spread: Vec<SessionSpread>,
+    /// How the dataset was cut from another one, for derived datasets
+    #[serde(default)]
+    pub derivation: Option<Derivation>,
+    /// Trading hours confirmed for the dataset, see `detect_sessions`
+    #[serde(default)]
+    pub session_calendar: Option<SessionCalendar>,
+    /// Terms the data was licensed under; unrestricted when absent
+    #[serde(default)]
+    pub license: Option<DataLicense>,
+    /// Synthetic data generated by demo mode rather than market data
+    #[serde(default)]
+    pub demo: bool,
+    /// Workspace the dataset belongs to
+    #[serde(default = "default_workspace")]
+    pub workspace_id: String,
+}
+
+fn default_workspace() -> String {
+    DEFAULT_WORKSPACE.to_string()
+}
+
+impl DatasetEntry {
+    /// Whether the dataset's raw ticks may be exported on `on`, and the
+    /// watermark the export must carry if so
+    pub fn check_raw_export(&self, on: NaiveDate) -> Result<Option<String>, LicenseError> {
+        match &self.license {
+            Some(license) => license.check_raw_export(&self.id, on),
+            None => Ok(None),
+        }
+    }
+
+    /// Size of the dataset file, as recorded in its fingerprint
+    pub fn stored_bytes(&self) -> u64 {
+        self.fingerprint.split('-').next()
+            .and_then(|len| len.parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// Whether `workspace` may read the dataset: its own, or one shared
+    /// through the default workspace
+    pub fn visible_to(&self, workspace: &str) -> bool {
+        self.workspace_id == workspace || self.workspace_id == DEFAULT_WORKSPACE
+    }
+}
+
+/// Ingested datasets, optionally persisted to a JSON file
+#[derive(Debug, Clone, Default)]
+pub struct DatasetCatalog {
+    path: Option<PathBuf>,
+    inner: Arc<RwLock<BTreeMap<String, DatasetEntry>>>,
+    quotas: Option<WorkspaceRegistry>,
+}
+
+impl DatasetCatalog {
+    /// A catalog that lives only in memory
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open (creating on first write) a catalog persisted at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CatalogError> {
+        let entries = match fs::read(path.as_ref()) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            path: Some(path.as_ref().to_path_buf()),
+            inner: Arc::new(RwLock::new(entries)),
+            quotas: None,
+        })
+    }
+
+    /// Charge dataset files to their workspace's storage quota
+    ///
+    /// Datasets already in the catalog are charged at once, without
+    /// enforcing the limit, so usage reflects what is stored.
+    pub fn with_quotas(mut self, quotas: WorkspaceRegistry) -> Self {
+        for entry in self.inner.read().unwrap().values() {
+            if let Err(e) = quotas.charge_storage(&entry.workspace_id, entry.stored_bytes() as i64) {
+                tracing::warn!("Failed to charge storage of dataset {}: {}", entry.id, e);
+            }
+        }
+        self.quotas = Some(quotas);
+        self
+    }
+
+    pub fn get(&self, id: &str) -> Option<DatasetEntry> {
+        self.inner.read().unwrap().get(id).cloned()
+    }
+
+    /// Entries ordered by trade date, then id
+    pub fn list(&self) -> Vec<DatasetEntry> {
+        let mut entries: Vec<_> = self.inner.read().unwrap().values().cloned().collect();
+        entries.sort_by(|a, b| a.trade_date.cmp(&b.trade_date).then_with(|| a.id.cmp(&b.id)));
+        entries
+    }
+
+    /// Add or replace an entry, returning the one it replaced
+    ///
+    /// A new or replaced file is charged to the entry's workspace first, and
+    /// the entry is refused if that exceeds the workspace's storage quota.
+    pub fn upsert(&self, entry: DatasetEntry) -> Result<Option<DatasetEntry>, CatalogError> {
+        let mut entries = self.inner.write().unwrap();
+        if let Some(quotas) = &self.quotas {
+            let previous = entries.get(&entry.id);
+            let charged = previous.is_some_and(|previous| {
+                previous.fingerprint == entry.fingerprint && previous.workspace_id == entry.workspace_id
+            });
+            if !charged {
+                // The replaced file no longer counts against the limit
+                if let Some(previous) = previous {
+                    let _ = quotas.charge_storage(&previous.workspace_id, -(previous.stored_bytes() as i64));
+                }
+                if let Err(e) = quotas.charge_storage(&entry.workspace_id, entry.stored_bytes() as i64) {
+                    if let Some(previous) = previous {
+                        let _ = quotas.charge_storage(&previous.workspace_id, previous.stored_bytes() as i64);
+                    }
+                    return Err(e.into());
+                }
+            }
+        }
+        let previous = entries.insert(entry.id.clone(), entry);
+
+        if let Some(path) = &self.path {
+            // Write-then-rename so a crash never leaves a truncated catalog
+            let tmp = path.with_extension("json.tmp");
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&tmp, serde_json::to_vec_pretty(&*entries)?)?;
+            fs::rename(&tmp, path)?;
+        }
+
+        Ok(previous)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::validation::TickValidator;
+    use crate::data::ValidationLevel;
+    use crate::workspace::ResourceQuota;
+
+    fn entry(id: &str, trade_date: &str) -> DatasetEntry {
+        let (_, validation) = TickValidator::new(ValidationLevel::Standard).validate(Vec::new()).unwrap();
+        DatasetEntry {
+            id: id.to_string(),
+            path: format!("data/{}", id),
+            trade_date: Some(trade_date.parse().unwrap()),
+            contracts: vec!["0624".to_string()],
+            ticks: 0,
+            first_timestamp: None,
+            last_timestamp: None,
+            fingerprint: "0-0".to_string(),
+            ingested_at: Utc::now(),
+            validation,
+            lineage_id: None,
+            liquidity: None,
+            spread: Vec::new(),
+            derivation: None,
+            session_calendar: None,
+            license: None,
+            demo: false,
+            workspace_id: DEFAULT_WORKSPACE.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_catalog_persists_entries() {
+        let path = std::env::temp_dir().join(format!("catalog_{}.json", uuid::Uuid::new_v4()));
+        let catalog = DatasetCatalog::open(&path).unwrap();
+
+        catalog.upsert(entry("mnq_20240612.parquet", "2024-06-12")).unwrap();
+        catalog.upsert(entry("mnq_20240611.parquet", "2024-06-11")).unwrap();
+        let previous = catalog.upsert(entry("mnq_20240612.parquet", "2024-06-12")).unwrap();
+        assert!(previous.is_some());
+
+        let reopened = DatasetCatalog::open(&path).unwrap();
+        let ids: Vec<_> = reopened.list().into_iter().map(|e| e.id).collect();
+        assert_eq!(ids, ["mnq_20240611.parquet", "mnq_20240612.parquet"]);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_datasets_are_charged_to_their_workspace() {
+        let quotas = WorkspaceRegistry::new();
+        let team = quotas.create("Team", "alice", ResourceQuota { max_storage_bytes: Some(1_000), ..Default::default() });
+        let catalog = DatasetCatalog::new().with_quotas(quotas.clone());
+        let usage = || quotas.get(&team.id).unwrap().usage.storage_bytes;
+
+        let sample = |id: &str, bytes: u64| DatasetEntry {
+            fingerprint: format!("{}-0", bytes),
+            workspace_id: team.id.clone(),
+            ..entry(id, "2024-06-12")
+        };
+        catalog.upsert(sample("a.parquet", 600)).unwrap();
+        assert_eq!(usage(), 600);
+        // Metadata changes are not charged again
+        catalog.upsert(sample("a.parquet", 600)).unwrap();
+        assert_eq!(usage(), 600);
+        assert!(matches!(catalog.upsert(sample("b.parquet", 600)), Err(CatalogError::Quota(_))));
+        assert!(catalog.get("b.parquet").is_none());
+        catalog.upsert(sample("a.parquet", 900)).unwrap();
+        assert_eq!(usage(), 900);
+
+        assert!(catalog.get("a.parquet").unwrap().visible_to(&team.id));
+        assert!(!catalog.get("a.parquet").unwrap().visible_to(DEFAULT_WORKSPACE));
+        assert!(entry("shared.parquet", "2024-06-12").visible_to(&team.id));
+    }
+}
@@ -0,0 +1,130 @@
+//! Data license and redistribution terms
+//!
+//! Exchange data agreements typically allow raw ticks to be used inside the
+//! firm but not passed on, or only passed on with attribution. A catalog
+//! entry can carry the terms its data was licensed under, and every path
+//! that hands raw ticks or book states to someone outside the lab checks
+//! them first: restricted data is refused, watermarked data leaves stamped
+//! with its notice. Metrics and results derived from the data are not
+//! affected, and datasets without terms are treated as unrestricted.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// What may be done with a dataset's raw ticks outside the lab
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Redistribution {
+    #[default]
+    Unrestricted,
+    /// Shared only stamped with the license notice
+    Watermarked,
+    /// Internal use only
+    Restricted,
+}
+
+/// Terms a dataset was licensed under
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataLicense {
+    /// Exchange or vendor the data is licensed from
+    pub licensor: String,
+    /// Agreement the data falls under, e.g. a market data license id
+    #[serde(default)]
+    pub agreement: Option<String>,
+    #[serde(default)]
+    pub redistribution: Redistribution,
+    /// Last day raw data may be exported; always refused afterwards
+    #[serde(default)]
+    pub expires_on: Option<NaiveDate>,
+    /// Attribution to stamp on exports instead of the default notice
+    #[serde(default)]
+    pub notice: Option<String>,
+}
+
+/// Why raw data may not be exported
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum LicenseError {
+    #[error("Dataset {dataset} is licensed from {licensor} for internal use only")]
+    Restricted { dataset: String, licensor: String },
+    #[error("License of dataset {dataset} from {licensor} expired on {expired_on}")]
+    Expired {
+        dataset: String,
+        licensor: String,
+        expired_on: NaiveDate,
+    },
+}
+
+impl DataLicense {
+    pub fn new(licensor: &str, redistribution: Redistribution) -> Self {
+        Self {
+            licensor: licensor.to_string(),
+            agreement: None,
+            redistribution,
+            expires_on: None,
+            notice: None,
+        }
+    }
+
+    pub fn with_agreement(mut self, agreement: &str) -> Self {
+        self.agreement = Some(agreement.to_string());
+        self
+    }
+
+    pub fn with_expiry(mut self, expires_on: NaiveDate) -> Self {
+        self.expires_on = Some(expires_on);
+        self
+    }
+
+    /// Attribution carried by exports and bundles
+    pub fn notice(&self) -> String {
+        if let Some(notice) = &self.notice {
+            return notice.clone();
+        }
+        match &self.agreement {
+            Some(agreement) => format!("Market data licensed from {} under {}. Not for redistribution.", self.licensor, agreement),
+            None => format!("Market data licensed from {}. Not for redistribution.", self.licensor),
+        }
+    }
+
+    /// Whether raw data of `dataset` may be exported on `on`, and the
+    /// watermark the export must carry if so
+    pub fn check_raw_export(&self, dataset: &str, on: NaiveDate) -> Result<Option<String>, LicenseError> {
+        if let Some(expired_on) = self.expires_on.filter(|expires_on| on > *expires_on) {
+            return Err(LicenseError::Expired {
+                dataset: dataset.to_string(),
+                licensor: self.licensor.clone(),
+                expired_on,
+            });
+        }
+        match self.redistribution {
+            Redistribution::Unrestricted => Ok(None),
+            Redistribution::Watermarked => Ok(Some(self.notice())),
+            Redistribution::Restricted => Err(LicenseError::Restricted {
+                dataset: dataset.to_string(),
+                licensor: self.licensor.clone(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_exports_follow_the_terms() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 12).unwrap();
+        let license = DataLicense::new("CME Group", Redistribution::Watermarked).with_agreement("ILA-1234");
+        let watermark = license.check_raw_export("mnq_20240612.parquet", today).unwrap();
+        assert_eq!(watermark.as_deref(), Some("Market data licensed from CME Group under ILA-1234. Not for redistribution."));
+
+        let restricted = DataLicense { redistribution: Redistribution::Restricted, ..license.clone() };
+        assert!(matches!(restricted.check_raw_export("mnq_20240612.parquet", today), Err(LicenseError::Restricted { .. })));
+
+        // Past its expiry even unrestricted data stays in
+        let lapsed = DataLicense::new("Vendor", Redistribution::Unrestricted)
+            .with_expiry(NaiveDate::from_ymd_opt(2024, 6, 11).unwrap());
+        assert!(matches!(lapsed.check_raw_export("mnq_20240612.parquet", today), Err(LicenseError::Expired { .. })));
+        assert_eq!(lapsed.check_raw_export("mnq_20240612.parquet", today.pred_opt().unwrap()), Ok(None));
+    }
+}
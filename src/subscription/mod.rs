@@ -0,0 +1,20 @@
+//! Data subscriptions
+//!
+//! Hands-off daily ingestion: a watcher picks up tick files delivered to a
+//! drop directory, validates them and records them in the dataset catalog.
+//! In live mode a recorder writes the incoming tick stream in the same
+//! format, so every live session feeds the drop directory too.
+
+pub mod catalog;
+pub mod demo;
+pub mod license;
+pub mod recorder;
+pub mod sampling;
+pub mod watcher;
+
+pub use catalog::{CatalogError, DatasetCatalog, DatasetEntry};
+pub use demo::{generate_demo_datasets, DemoDataConfig, DemoError};
+pub use license::{DataLicense, LicenseError, Redistribution};
+pub use recorder::{RecordedSession, RecorderConfig, RecorderError, TickFanout, TickRecorder};
+pub use sampling::{derive_dataset, sample_ticks, Derivation, SampleMethod, SamplingError};
+pub use watcher::{IngestOutcome, PollReport, ReoptimizationTrigger, SubscriptionConfig, SubscriptionWatcher};
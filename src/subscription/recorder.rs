@@ -0,0 +1,451 @@
+//! Live tick recorder
+//!
+//! In live and paper mode the incoming tick stream is fanned out to
+//! strategies and to a recorder, which writes every tick to parquet in the
+//! same layout and schema as the historical files (`<root>/<MM-YY>/<YYYYMMDD>.parquet`).
+//! A session's file is written under a `.partial` name and renamed when the
+//! session ends, so pointing the recorder at the subscription drop directory
+//! turns each live session into cataloged backtest data.
+
+use arrow::array::{
+    ArrayRef, Decimal128Array, Int32Array, Int8Array, StringArray, TimestampNanosecondArray,
+};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use chrono::NaiveDate;
+use parquet::arrow::ArrowWriter;
+use parquet::errors::ParquetError;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+use crate::data::{DataLevel, MarketDataType, OrderBookOperation, TickData};
+use crate::timestamp::{SessionCalendar, Timestamp};
+
+/// Decimal precision and scale of the historical price column
+const PRICE_PRECISION: u8 = 13;
+const PRICE_SCALE: u32 = 2;
+
+/// Errors raised while recording ticks
+#[derive(Debug, thiserror::Error)]
+pub enum RecorderError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] ArrowError),
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] ParquetError),
+    #[error("Contract month {0:?} is not in MMYY form")]
+    InvalidContract(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecorderConfig {
+    /// Root the `<MM-YY>/<YYYYMMDD>.parquet` tree is written under
+    pub output_dir: PathBuf,
+    /// Calendar deciding which session, and so which file, a tick belongs to
+    #[serde(default)]
+    pub calendar: SessionCalendar,
+    /// Ticks buffered before a row group is written
+    #[serde(default = "default_flush_rows")]
+    pub flush_rows: usize,
+}
+
+fn default_flush_rows() -> usize {
+    50_000
+}
+
+impl RecorderConfig {
+    pub fn new(output_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            output_dir: output_dir.into(),
+            calendar: SessionCalendar::default(),
+            flush_rows: default_flush_rows(),
+        }
+    }
+}
+
+/// A finished session file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedSession {
+    pub contract: String,
+    pub trade_date: NaiveDate,
+    pub path: PathBuf,
+    pub rows: u64,
+    pub first_timestamp: i64,
+    pub last_timestamp: i64,
+}
+
+/// Schema of the historical tick files
+pub fn tick_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("level", DataType::Utf8, false),
+        Field::new("mdt", DataType::Int8, false),
+        Field::new("timestamp", DataType::Timestamp(TimeUnit::Nanosecond, None), false),
+        Field::new("operation", DataType::Int8, true),
+        Field::new("depth", DataType::Int8, true),
+        Field::new("market_maker", DataType::Utf8, true),
+        Field::new("price", DataType::Decimal128(PRICE_PRECISION, PRICE_SCALE as i8), false),
+        Field::new("volume", DataType::Int32, false),
+    ])
+}
+
+/// Market data type code used by the historical files
+fn mdt_code(mdt: &MarketDataType) -> i8 {
+    match mdt {
+        MarketDataType::AskQuote => 0,
+        MarketDataType::BidQuote => 1,
+        MarketDataType::Trade => 2,
+        MarketDataType::SessionHigh => 3,
+        MarketDataType::SessionLow => 4,
+        MarketDataType::Volume => 5,
+        MarketDataType::OpenInterest => 8,
+        MarketDataType::Settlement => 9,
+        _ => 10,
+    }
+}
+
+fn operation_code(operation: &OrderBookOperation) -> i8 {
+    match operation {
+        OrderBookOperation::Add => 0,
+        OrderBookOperation::Update => 1,
+        OrderBookOperation::Remove => 2,
+    }
+}
+
+fn price_mantissa(price: Decimal) -> i128 {
+    let mut price = price;
+    price.rescale(PRICE_SCALE);
+    price.mantissa()
+}
+
+/// `0624` becomes the `06-24` directory of the historical layout
+pub(crate) fn contract_dir(contract: &str) -> Result<String, RecorderError> {
+    if contract.len() != 4 || !contract.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(RecorderError::InvalidContract(contract.to_string()));
+    }
+    Ok(format!("{}-{}", &contract[..2], &contract[2..]))
+}
+
+/// Column buffers of ticks not yet written
+#[derive(Default)]
+struct TickColumns {
+    level: Vec<&'static str>,
+    mdt: Vec<i8>,
+    timestamp: Vec<i64>,
+    operation: Vec<Option<i8>>,
+    depth: Vec<Option<i8>>,
+    price: Vec<i128>,
+    volume: Vec<i32>,
+}
+
+impl TickColumns {
+    fn push(&mut self, tick: &TickData) {
+        self.level.push(match tick.level {
+            DataLevel::L1 => "L1",
+            DataLevel::L2 => "L2",
+        });
+        self.mdt.push(mdt_code(&tick.mdt));
+        self.timestamp.push(tick.timestamp);
+        self.operation.push(tick.operation.as_ref().map(operation_code));
+        self.depth.push(tick.depth.map(|d| d as i8));
+        self.price.push(price_mantissa(tick.price));
+        self.volume.push(tick.volume);
+    }
+
+    fn len(&self) -> usize {
+        self.timestamp.len()
+    }
+
+    fn take_batch(&mut self, schema: &SchemaRef) -> Result<RecordBatch, ArrowError> {
+        let columns = std::mem::take(self);
+        let rows = columns.len();
+        let arrays: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(columns.level)),
+            Arc::new(Int8Array::from(columns.mdt)),
+            Arc::new(TimestampNanosecondArray::from(columns.timestamp)),
+            Arc::new(Int8Array::from(columns.operation)),
+            Arc::new(Int8Array::from(columns.depth)),
+            Arc::new(StringArray::from(vec![None::<&str>; rows])),
+            Arc::new(
+                Decimal128Array::from(columns.price)
+                    .with_precision_and_scale(PRICE_PRECISION, PRICE_SCALE as i8)?,
+            ),
+            Arc::new(Int32Array::from(columns.volume)),
+        ];
+        RecordBatch::try_new(schema.clone(), arrays)
+    }
+}
+
+/// Write ticks to one parquet file in the historical schema
+pub fn write_tick_file(path: &Path, ticks: &[TickData]) -> Result<(), RecorderError> {
+    let schema: SchemaRef = Arc::new(tick_schema());
+    let mut writer = ArrowWriter::try_new(File::create(path)?, schema.clone(), None)?;
+    for chunk in ticks.chunks(default_flush_rows()) {
+        let mut columns = TickColumns::default();
+        for tick in chunk {
+            columns.push(tick);
+        }
+        writer.write(&columns.take_batch(&schema)?)?;
+    }
+    writer.close()?;
+    Ok(())
+}
+
+/// The open file of one contract's session
+struct SessionFile {
+    trade_date: NaiveDate,
+    partial_path: PathBuf,
+    final_path: PathBuf,
+    writer: ArrowWriter<File>,
+    buffer: TickColumns,
+    rows: u64,
+    first_timestamp: i64,
+    last_timestamp: i64,
+}
+
+impl SessionFile {
+    fn flush(&mut self, schema: &SchemaRef) -> Result<(), RecorderError> {
+        if !self.buffer.timestamp.is_empty() {
+            let batch = self.buffer.take_batch(schema)?;
+            self.writer.write(&batch)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a live tick stream to session files
+pub struct TickRecorder {
+    config: RecorderConfig,
+    schema: SchemaRef,
+    sessions: HashMap<String, SessionFile>,
+    finished: Vec<RecordedSession>,
+    dropped: u64,
+}
+
+impl TickRecorder {
+    pub fn new(config: RecorderConfig) -> Self {
+        Self {
+            config,
+            schema: Arc::new(tick_schema()),
+            sessions: HashMap::new(),
+            finished: Vec::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Ticks lost because the recorder fell behind the fan-out
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Sessions closed so far
+    pub fn finished(&self) -> &[RecordedSession] {
+        &self.finished
+    }
+
+    /// Append a tick, closing its contract's file when a new session starts
+    ///
+    /// Ticks outside trading hours, such as settlements published after the
+    /// close, go to the session already open for the contract.
+    pub fn record(&mut self, tick: &TickData) -> Result<(), RecorderError> {
+        let trade_date = self.config.calendar.trading_date(Timestamp::from_nanos(tick.timestamp));
+        let open_date = self.sessions.get(&tick.contract_month).map(|s| s.trade_date);
+
+        let trade_date = match (trade_date, open_date) {
+            (Some(date), Some(open)) if date != open => {
+                self.close(&tick.contract_month)?;
+                date
+            }
+            (Some(date), _) => date,
+            (None, Some(open)) => open,
+            (None, None) => Timestamp::from_nanos(tick.timestamp).exchange_date(),
+        };
+
+        if !self.sessions.contains_key(&tick.contract_month) {
+            let session = self.open(&tick.contract_month, trade_date, tick.timestamp)?;
+            self.sessions.insert(tick.contract_month.clone(), session);
+        }
+
+        let flush_rows = self.config.flush_rows.max(1);
+        let session = self.sessions.get_mut(&tick.contract_month)
+            .expect("session opened above");
+        session.buffer.push(tick);
+        session.rows += 1;
+        session.last_timestamp = tick.timestamp;
+        if session.buffer.len() >= flush_rows {
+            session.flush(&self.schema)?;
+        }
+        Ok(())
+    }
+
+    fn open(&self, contract: &str, trade_date: NaiveDate, timestamp: i64) -> Result<SessionFile, RecorderError> {
+        let dir = self.config.output_dir.join(contract_dir(contract)?);
+        fs::create_dir_all(&dir)?;
+
+        // A restarted session keeps what was recorded before the restart
+        let stem = trade_date.format("%Y%m%d").to_string();
+        let mut final_path = dir.join(format!("{}.parquet", stem));
+        let mut part = 1;
+        while final_path.exists() {
+            part += 1;
+            final_path = dir.join(format!("{}_{}.parquet", stem, part));
+        }
+        let partial_path = final_path.with_extension("parquet.partial");
+
+        let writer = ArrowWriter::try_new(File::create(&partial_path)?, self.schema.clone(), None)?;
+        Ok(SessionFile {
+            trade_date,
+            partial_path,
+            final_path,
+            writer,
+            buffer: TickColumns::default(),
+            rows: 0,
+            first_timestamp: timestamp,
+            last_timestamp: timestamp,
+        })
+    }
+
+    /// Finish a contract's open file and move it to its final name
+    fn close(&mut self, contract: &str) -> Result<(), RecorderError> {
+        let Some(mut session) = self.sessions.remove(contract) else {
+            return Ok(());
+        };
+        session.flush(&self.schema)?;
+        session.writer.close()?;
+        fs::rename(&session.partial_path, &session.final_path)?;
+
+        info!(
+            "Recorded {} ticks of {} for {} to {}",
+            session.rows, contract, session.trade_date, session.final_path.display()
+        );
+        self.finished.push(RecordedSession {
+            contract: contract.to_string(),
+            trade_date: session.trade_date,
+            path: session.final_path,
+            rows: session.rows,
+            first_timestamp: session.first_timestamp,
+            last_timestamp: session.last_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Close every open file and return all sessions recorded
+    pub fn finish(mut self) -> Result<Vec<RecordedSession>, RecorderError> {
+        let contracts: Vec<String> = self.sessions.keys().cloned().collect();
+        for contract in contracts {
+            self.close(&contract)?;
+        }
+        Ok(self.finished)
+    }
+
+    /// Record from a fan-out subscription until the live feed closes
+    pub async fn run(mut self, mut ticks: broadcast::Receiver<Arc<TickData>>) -> Result<Vec<RecordedSession>, RecorderError> {
+        loop {
+            match ticks.recv().await {
+                Ok(tick) => self.record(&tick)?,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!("Tick recorder fell behind, {} ticks not recorded", missed);
+                    self.dropped += missed;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+        self.finish()
+    }
+}
+
+/// Fans the live tick stream out to strategies and the recorder
+#[derive(Debug, Clone)]
+pub struct TickFanout {
+    sender: broadcast::Sender<Arc<TickData>>,
+}
+
+impl TickFanout {
+    /// `capacity` ticks may be buffered for the slowest subscriber
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Deliver a tick to every subscriber; returns how many received it
+    pub fn publish(&self, tick: TickData) -> usize {
+        self.sender.send(Arc::new(tick)).unwrap_or(0)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<TickData>> {
+        self.sender.subscribe()
+    }
+
+    /// Start recording every tick published from now on
+    ///
+    /// The task ends, closing all session files, once every `TickFanout`
+    /// handle has been dropped.
+    pub fn spawn_recorder(&self, config: RecorderConfig) -> tokio::task::JoinHandle<Result<Vec<RecordedSession>, RecorderError>> {
+        tokio::spawn(TickRecorder::new(config).run(self.subscribe()))
+    }
+}
+
+/// Remove `.partial` files left behind by a recorder that did not shut down
+/// cleanly; returns the paths removed
+pub fn clear_partial_files(root: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+    if !root.exists() {
+        return Ok(removed);
+    }
+    for dir in fs::read_dir(root)? {
+        let dir = dir?.path();
+        if !dir.is_dir() {
+            continue;
+        }
+        for file in fs::read_dir(&dir)? {
+            let path = file?.path();
+            if path.extension().map_or(false, |ext| ext == "partial") {
+                fs::remove_file(&path)?;
+                removed.push(path);
+            }
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    #[test]
+    fn test_sessions_roll_into_separate_files() {
+        let root = std::env::temp_dir().join(format!("tick_recorder_{}", uuid::Uuid::new_v4()));
+        let mut recorder = TickRecorder::new(RecorderConfig { flush_rows: 2, ..RecorderConfig::new(&root) });
+
+        // 2024-03-12 14:30 UTC is Tuesday's session; 23:30 UTC opens Wednesday's
+        let tuesday = 1_710_253_800_000_000_000i64;
+        let wednesday = tuesday + 9 * 3_600_000_000_000;
+        let trade = |ts: i64| TickData::new(
+            DataLevel::L1, MarketDataType::Trade, ts, Decimal::new(1_805_025, 2), 2, "0624".to_string(),
+        );
+        for i in 0..3 {
+            recorder.record(&trade(tuesday + i)).unwrap();
+        }
+        recorder.record(&trade(wednesday)).unwrap();
+        let sessions = recorder.finish().unwrap();
+
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].path, root.join("06-24").join("20240312.parquet"));
+        assert_eq!(sessions[0].rows, 3);
+        assert_eq!(sessions[1].trade_date, NaiveDate::from_ymd_opt(2024, 3, 13).unwrap());
+
+        let reader = SerializedFileReader::new(File::open(&sessions[0].path).unwrap()).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 3);
+        assert!(clear_partial_files(&root).unwrap().is_empty());
+
+        fs::remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,305 @@
+//! Derived datasets cut from full history
+//!
+//! Iterating on a strategy against months of ticks is slow. A sample keeps
+//! a labeled slice of a cataloged dataset: a time window, every Nth tick,
+//! or whole time buckets drawn evenly from each volatility stratum so the
+//! slice still covers quiet and busy markets. The slice is written as a new
+//! parquet file in the historical schema, added to the catalog, and linked
+//! to its source in the lineage graph.
+
+use chrono::{DateTime, Utc};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use crate::data::validation::TickValidator;
+use crate::data::{MarketDataType, TickData};
+use crate::lineage::{ArtifactKind, LineageTracker};
+use crate::subscription::recorder::{contract_dir, write_tick_file};
+use crate::subscription::watcher::fingerprint;
+use crate::subscription::{CatalogError, DatasetCatalog, DatasetEntry, RecorderError};
+use crate::timestamp::Timestamp;
+
+const NANOS_PER_SEC: i64 = 1_000_000_000;
+
+/// Errors raised deriving a dataset
+#[derive(Debug, thiserror::Error)]
+pub enum SamplingError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to write sample: {0}")]
+    Write(#[from] RecorderError),
+    #[error("Catalog error: {0}")]
+    Catalog(#[from] CatalogError),
+    #[error("Validation failed: {0}")]
+    Validation(String),
+    #[error("Label {0:?} must be non-empty and use only letters, digits, '-' and '_'")]
+    InvalidLabel(String),
+    #[error("Dataset {0} already exists")]
+    Exists(String),
+    #[error("Sample selected no ticks")]
+    Empty,
+}
+
+/// Which ticks of the source a sample keeps
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum SampleMethod {
+    /// Ticks with `start <= timestamp < end`
+    TimeWindow { start: DateTime<Utc>, end: DateTime<Utc> },
+    /// Every `n`th tick starting from `offset`
+    EveryNth {
+        n: usize,
+        #[serde(default)]
+        offset: usize,
+    },
+    /// Whole `bucket_secs` buckets, `buckets_per_stratum` drawn from each
+    /// of `strata` equal-sized groups of buckets ranked by volatility
+    VolatilityStratified {
+        #[serde(default = "default_bucket_secs")]
+        bucket_secs: u64,
+        #[serde(default = "default_strata")]
+        strata: usize,
+        buckets_per_stratum: usize,
+        #[serde(default)]
+        seed: u64,
+    },
+}
+
+fn default_bucket_secs() -> u64 {
+    300
+}
+
+fn default_strata() -> usize {
+    3
+}
+
+/// How a derived dataset was made
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Derivation {
+    /// Catalog id of the dataset sampled
+    pub source: String,
+    pub label: String,
+    pub method: SampleMethod,
+    /// Ticks in the source when it was sampled
+    pub source_ticks: usize,
+}
+
+/// Ticks `method` keeps, in their original order
+pub fn sample_ticks(ticks: &[TickData], method: &SampleMethod) -> Vec<TickData> {
+    match method {
+        SampleMethod::TimeWindow { start, end } => {
+            let (start, end) = (Timestamp::from(*start).nanos(), Timestamp::from(*end).nanos());
+            ticks.iter().filter(|t| t.timestamp >= start && t.timestamp < end).cloned().collect()
+        }
+        SampleMethod::EveryNth { n, offset } => {
+            ticks.iter().skip(*offset).step_by((*n).max(1)).cloned().collect()
+        }
+        SampleMethod::VolatilityStratified { bucket_secs, strata, buckets_per_stratum, seed } => {
+            let Some(first) = ticks.iter().map(|t| t.timestamp).min() else {
+                return Vec::new();
+            };
+            let width = (*bucket_secs).max(1) as i64 * NANOS_PER_SEC;
+            let bucket_of = |tick: &TickData| (tick.timestamp - first) / width;
+
+            let mut buckets: Vec<(i64, f64)> = bucket_volatility(ticks, bucket_of);
+            buckets.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+
+            let strata = (*strata).max(1);
+            let per_stratum = buckets.len().div_ceil(strata).max(1);
+            let mut rng = StdRng::seed_from_u64(*seed);
+            let mut chosen = BTreeSet::new();
+            for stratum in buckets.chunks(per_stratum) {
+                let picked = stratum.choose_multiple(&mut rng, *buckets_per_stratum);
+                chosen.extend(picked.map(|(bucket, _)| *bucket));
+            }
+
+            ticks.iter().filter(|t| chosen.contains(&bucket_of(t))).cloned().collect()
+        }
+    }
+}
+
+/// Standard deviation of trade-to-trade log returns in each bucket; buckets
+/// with fewer than two trades count as flat
+fn bucket_volatility(ticks: &[TickData], bucket_of: impl Fn(&TickData) -> i64) -> Vec<(i64, f64)> {
+    let mut returns: std::collections::BTreeMap<i64, (Option<f64>, Vec<f64>)> = Default::default();
+    for tick in ticks {
+        let (last, bucket_returns) = returns.entry(bucket_of(tick)).or_default();
+        if !matches!(tick.mdt, MarketDataType::Trade) {
+            continue;
+        }
+        let Some(price) = tick.price.to_f64().filter(|p| *p > 0.0) else {
+            continue;
+        };
+        if let Some(last) = last {
+            bucket_returns.push((price / *last).ln());
+        }
+        *last = Some(price);
+    }
+
+    returns.into_iter()
+        .map(|(bucket, (_, r))| {
+            if r.len() < 2 {
+                return (bucket, 0.0);
+            }
+            let mean = r.iter().sum::<f64>() / r.len() as f64;
+            let variance = r.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (r.len() - 1) as f64;
+            (bucket, variance.sqrt())
+        })
+        .collect()
+}
+
+/// Sample `ticks` of the cataloged `source` into `<output_dir>/<label>.parquet`,
+/// under the contract's `<MM-YY>` directory when it holds one contract
+///
+/// The new dataset belongs to `workspace`, is validated at the source's
+/// level, added to `catalog` and, when `lineage` is given, recorded as
+/// derived from the source. The file is removed again if the catalog
+/// refuses it, e.g. over the workspace's storage quota.
+#[allow(clippy::too_many_arguments)]
+pub fn derive_dataset(
+    source: &DatasetEntry,
+    ticks: &[TickData],
+    label: &str,
+    method: SampleMethod,
+    output_dir: &Path,
+    workspace: &str,
+    catalog: &DatasetCatalog,
+    lineage: Option<&LineageTracker>,
+) -> Result<DatasetEntry, SamplingError> {
+    if label.is_empty() || !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(SamplingError::InvalidLabel(label.to_string()));
+    }
+    let id = format!("{}.parquet", label);
+    if catalog.get(&id).is_some() {
+        return Err(SamplingError::Exists(id));
+    }
+
+    let sampled = sample_ticks(ticks, &method);
+    if sampled.is_empty() {
+        return Err(SamplingError::Empty);
+    }
+    let (sampled, validation) = TickValidator::new(source.validation.level)
+        .validate(sampled)
+        .map_err(|e| SamplingError::Validation(e.to_string()))?;
+
+    let contracts: BTreeSet<String> = sampled.iter().map(|t| t.contract_month.clone()).collect();
+    let dir = match contracts.iter().next() {
+        Some(contract) if contracts.len() == 1 => output_dir.join(contract_dir(contract)?),
+        _ => output_dir.to_path_buf(),
+    };
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(&id);
+    write_tick_file(&path, &sampled)?;
+    let metadata = fs::metadata(&path)?;
+
+    let derivation = Derivation {
+        source: source.id.clone(),
+        label: label.to_string(),
+        method,
+        source_ticks: ticks.len(),
+    };
+    let mut entry = DatasetEntry {
+        id: id.clone(),
+        path: path.to_string_lossy().to_string(),
+        trade_date: source.trade_date,
+        contracts: contracts.into_iter().collect(),
+        ticks: sampled.len(),
+        first_timestamp: sampled.iter().map(|t| t.timestamp).min(),
+        last_timestamp: sampled.iter().map(|t| t.timestamp).max(),
+        fingerprint: fingerprint(metadata.len(), metadata.modified()?),
+        ingested_at: Utc::now(),
+        validation,
+        lineage_id: None,
+        liquidity: None,
+        spread: Vec::new(),
+        derivation: None,
+        session_calendar: source.session_calendar.clone(),
+        // A sample is still the licensor's raw data
+        license: source.license.clone(),
+        demo: source.demo,
+        workspace_id: workspace.to_string(),
+    };
+
+    if let Some(lineage) = lineage {
+        let inputs: Vec<String> = source.lineage_id.iter().cloned().collect();
+        entry.lineage_id = Some(lineage.record_in(
+            workspace,
+            ArtifactKind::Dataset,
+            id,
+            &inputs,
+            serde_json::json!({
+                "derived_from": source.id,
+                "label": label,
+                "method": derivation.method,
+                "ticks": entry.ticks,
+                "source_ticks": derivation.source_ticks,
+            }),
+        ));
+    }
+    entry.derivation = Some(derivation);
+
+    if let Err(e) = catalog.upsert(entry.clone()) {
+        let _ = fs::remove_file(&path);
+        return Err(e.into());
+    }
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::DataLevel;
+    use rust_decimal::Decimal;
+
+    fn trade(second: i64, price: i64) -> TickData {
+        TickData::new(
+            DataLevel::L1, MarketDataType::Trade, second * NANOS_PER_SEC,
+            Decimal::new(price, 2), 1, "0624".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_sampling_methods() {
+        // Four one-minute buckets: flat, flat, choppy, very choppy
+        let mut ticks = Vec::new();
+        for bucket in 0..4i64 {
+            for i in 0..6i64 {
+                let swing = match bucket {
+                    2 => 25 * (i % 2),
+                    3 => 200 * (i % 2),
+                    _ => 0,
+                };
+                ticks.push(trade(bucket * 60 + i, 1_805_000 + swing));
+            }
+        }
+
+        let every_third = sample_ticks(&ticks, &SampleMethod::EveryNth { n: 3, offset: 1 });
+        assert_eq!(every_third.len(), 8);
+        assert_eq!(every_third[0].timestamp, ticks[1].timestamp);
+
+        let window = SampleMethod::TimeWindow {
+            start: Timestamp::from_nanos(60 * NANOS_PER_SEC).to_utc(),
+            end: Timestamp::from_nanos(120 * NANOS_PER_SEC).to_utc(),
+        };
+        assert_eq!(sample_ticks(&ticks, &window).len(), 6);
+
+        // One bucket from the calm half and one from the volatile half
+        let stratified = SampleMethod::VolatilityStratified {
+            bucket_secs: 60,
+            strata: 2,
+            buckets_per_stratum: 1,
+            seed: 3,
+        };
+        let sample = sample_ticks(&ticks, &stratified);
+        let buckets: BTreeSet<i64> = sample.iter().map(|t| t.timestamp / (60 * NANOS_PER_SEC)).collect();
+        assert_eq!(buckets.len(), 2);
+        assert!(buckets.iter().any(|b| *b < 2) && buckets.iter().any(|b| *b >= 2));
+        assert_eq!(sample_ticks(&ticks, &stratified).len(), sample.len());
+    }
+}
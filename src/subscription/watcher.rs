@@ -0,0 +1,397 @@
+//! Drop directory watcher
+//!
+//! Polls a directory that a data vendor delivers daily tick files into.
+//! A file is picked up once it has stopped changing for the settle period,
+//! so half-written uploads are left alone. Each new file is ingested and
+//! validated, recorded in the dataset catalog and lineage graph, and can
+//! trigger re-optimizations on the job queue.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
+
+use crate::data::validation::ingest_validated;
+use crate::data::{IngestionConfig, ValidationLevel};
+#[cfg(feature = "jobs")]
+use crate::jobs::{Job, JobQueue, JobType};
+use crate::lineage::{ArtifactKind, LineageTracker};
+use crate::market::{LiquidityConfig, LiquidityProfile, SessionSpread, SpreadConfig};
+use crate::performance::ThreadPools;
+use crate::subscription::{DataLicense, DatasetCatalog, DatasetEntry};
+use crate::timestamp::{SessionCalendar, Timestamp};
+use crate::workspace::DEFAULT_WORKSPACE;
+
+/// Payload field listing the newly ingested files of a re-optimization job
+pub const NEW_DATASETS_PAYLOAD_KEY: &str = "data_paths";
+
+/// Drop directory watcher configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriptionConfig {
+    /// Directory new daily files are delivered to
+    pub drop_dir: PathBuf,
+    /// Ingested files are moved here; they stay in the drop directory when unset
+    #[serde(default)]
+    pub archive_dir: Option<PathBuf>,
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    /// Seconds a file must go unmodified before it is ingested
+    #[serde(default = "default_settle_secs")]
+    pub settle_secs: u64,
+    /// File extensions picked up, without the dot
+    #[serde(default = "default_extensions")]
+    pub extensions: Vec<String>,
+    /// Validation applied on ingest; the ingestion default when unset
+    #[serde(default)]
+    pub validation_level: Option<ValidationLevel>,
+    /// Re-optimization to queue after new data arrives
+    #[serde(default)]
+    pub reoptimize: Option<ReoptimizationTrigger>,
+    /// Book liquidity metrics computed for each ingested file
+    #[serde(default)]
+    pub liquidity: LiquidityConfig,
+    /// Bid-ask spread statistics computed per session of each ingested file
+    #[serde(default)]
+    pub spread: SpreadConfig,
+    /// Terms recorded on every dataset delivered to the drop directory
+    #[serde(default)]
+    pub license: Option<DataLicense>,
+}
+
+fn default_poll_interval_secs() -> u64 {
+    60
+}
+
+fn default_settle_secs() -> u64 {
+    30
+}
+
+fn default_extensions() -> Vec<String> {
+    vec!["parquet".to_string()]
+}
+
+impl SubscriptionConfig {
+    pub fn new(drop_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            drop_dir: drop_dir.into(),
+            archive_dir: None,
+            poll_interval_secs: default_poll_interval_secs(),
+            settle_secs: default_settle_secs(),
+            extensions: default_extensions(),
+            validation_level: None,
+            reoptimize: None,
+            liquidity: LiquidityConfig::default(),
+            spread: SpreadConfig::default(),
+            license: None,
+        }
+    }
+}
+
+/// Optimization job queued once per poll that ingested new data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReoptimizationTrigger {
+    /// Job payload; the new files are added under `data_paths`
+    pub payload: serde_json::Value,
+    #[serde(default = "default_priority")]
+    pub priority: i32,
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+}
+
+fn default_priority() -> i32 {
+    50
+}
+
+/// What happened to one delivered file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum IngestOutcome {
+    Ingested(DatasetEntry),
+    Rejected { file: String, error: String },
+}
+
+/// Result of one pass over the drop directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PollReport {
+    pub outcomes: Vec<IngestOutcome>,
+    /// Re-optimization job queued for the new data
+    pub reoptimization_job: Option<String>,
+}
+
+/// Watches a drop directory and ingests new daily files
+pub struct SubscriptionWatcher {
+    config: SubscriptionConfig,
+    catalog: DatasetCatalog,
+    calendar: SessionCalendar,
+    lineage: Option<LineageTracker>,
+    #[cfg(feature = "jobs")]
+    jobs: Option<JobQueue>,
+    thread_pools: Option<ThreadPools>,
+    /// Fingerprints of files that failed, retried only once they change
+    rejected: HashMap<PathBuf, String>,
+}
+
+impl SubscriptionWatcher {
+    pub fn new(config: SubscriptionConfig, catalog: DatasetCatalog) -> Self {
+        Self {
+            config,
+            catalog,
+            calendar: SessionCalendar::default(),
+            lineage: None,
+            #[cfg(feature = "jobs")]
+            jobs: None,
+            thread_pools: None,
+            rejected: HashMap::new(),
+        }
+    }
+
+    /// Assign trade dates with a calendar other than the CME default
+    pub fn with_calendar(mut self, calendar: SessionCalendar) -> Self {
+        self.calendar = calendar;
+        self
+    }
+
+    /// Record ingested files and datasets in a lineage graph
+    pub fn with_lineage(mut self, lineage: LineageTracker) -> Self {
+        self.lineage = Some(lineage);
+        self
+    }
+
+    /// Validate delivered files on the engine's ingestion pool
+    pub fn with_thread_pools(mut self, thread_pools: ThreadPools) -> Self {
+        self.thread_pools = Some(thread_pools);
+        self
+    }
+
+    /// Queue re-optimizations on this job queue
+    #[cfg(feature = "jobs")]
+    pub fn with_job_queue(mut self, jobs: JobQueue) -> Self {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    /// Poll the drop directory forever
+    pub async fn run(mut self) {
+        info!("Watching {} for new data every {}s",
+            self.config.drop_dir.display(), self.config.poll_interval_secs);
+
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.poll_interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.poll().await {
+                warn!("Failed to scan {}: {}", self.config.drop_dir.display(), e);
+            }
+        }
+    }
+
+    /// Ingest every settled file not yet in the catalog
+    pub async fn poll(&mut self) -> std::io::Result<PollReport> {
+        let mut report = PollReport::default();
+        let mut new_paths = Vec::new();
+
+        for (path, fingerprint) in self.ready_files()? {
+            let outcome = self.ingest(&path, fingerprint).await;
+            if let IngestOutcome::Ingested(entry) = &outcome {
+                new_paths.push(entry.path.clone());
+            }
+            report.outcomes.push(outcome);
+        }
+
+        if !new_paths.is_empty() {
+            report.reoptimization_job = self.queue_reoptimization(new_paths).await;
+        }
+        Ok(report)
+    }
+
+    /// Candidate files that have settled and are new or changed, oldest first
+    fn ready_files(&self) -> std::io::Result<Vec<(PathBuf, String)>> {
+        let settle = Duration::from_secs(self.config.settle_secs);
+        let now = SystemTime::now();
+        let mut ready = Vec::new();
+
+        for dir_entry in fs::read_dir(&self.config.drop_dir)? {
+            let path = dir_entry?.path();
+            let wanted = path.extension()
+                .is_some_and(|ext| self.config.extensions.iter().any(|e| ext.eq_ignore_ascii_case(e.as_str())));
+            if !wanted || !path.is_file() {
+                continue;
+            }
+
+            let metadata = fs::metadata(&path)?;
+            let modified = metadata.modified()?;
+            if now.duration_since(modified).unwrap_or_default() < settle {
+                continue;
+            }
+
+            let fingerprint = fingerprint(metadata.len(), modified);
+            let known = self.catalog.get(&file_name(&path))
+                .is_some_and(|entry| entry.fingerprint == fingerprint);
+            if known || self.rejected.get(&path) == Some(&fingerprint) {
+                continue;
+            }
+            ready.push((modified, path, fingerprint));
+        }
+
+        ready.sort();
+        Ok(ready.into_iter().map(|(_, path, fingerprint)| (path, fingerprint)).collect())
+    }
+
+    async fn ingest(&mut self, path: &Path, fingerprint: String) -> IngestOutcome {
+        let id = file_name(path);
+        let mut config = IngestionConfig::default();
+        if let Some(level) = self.config.validation_level {
+            config.validation_level = level;
+        }
+
+        let (ticks, validation) = match ingest_validated(path, config, self.thread_pools.as_ref()).await.map_err(|e| e.to_string()) {
+            Ok(ingested) => ingested,
+            Err(error) => {
+                warn!("Rejected {}: {}", path.display(), error);
+                self.rejected.insert(path.to_path_buf(), fingerprint);
+                return IngestOutcome::Rejected { file: id, error };
+            }
+        };
+        self.rejected.remove(path);
+
+        let stored = match self.archive(path) {
+            Ok(stored) => stored,
+            Err(e) => {
+                warn!("Ingested {} but could not archive it: {}", path.display(), e);
+                path.to_path_buf()
+            }
+        };
+
+        let first_timestamp = ticks.iter().map(|t| t.timestamp).min();
+        let last_timestamp = ticks.iter().map(|t| t.timestamp).max();
+        let trade_date = last_timestamp.map(|nanos| {
+            let timestamp = Timestamp::from_nanos(nanos);
+            self.calendar.trading_date(timestamp).unwrap_or_else(|| timestamp.exchange_date())
+        });
+        let contracts: BTreeSet<String> = ticks.iter().map(|t| t.contract_month.clone()).collect();
+
+        let liquidity = LiquidityProfile::compute(&ticks, &self.config.liquidity);
+        if let Err(e) = liquidity.save(&stored) {
+            warn!("Failed to store liquidity profile of {}: {}", id, e);
+        }
+
+        let mut entry = DatasetEntry {
+            id: id.clone(),
+            path: stored.to_string_lossy().to_string(),
+            trade_date,
+            contracts: contracts.into_iter().collect(),
+            ticks: ticks.len(),
+            first_timestamp,
+            last_timestamp,
+            fingerprint,
+            ingested_at: Utc::now(),
+            validation,
+            lineage_id: None,
+            liquidity: Some(liquidity.summary),
+            spread: SessionSpread::compute(&ticks, &self.config.spread, &self.calendar),
+            derivation: None,
+            session_calendar: None,
+            license: self.config.license.clone(),
+            demo: false,
+            workspace_id: DEFAULT_WORKSPACE.to_string(),
+        };
+
+        if let Some(lineage) = &self.lineage {
+            let raw = lineage.record_raw_file(&stored);
+            entry.lineage_id = Some(lineage.record(
+                ArtifactKind::Dataset,
+                id.clone(),
+                &[raw],
+                serde_json::json!({
+                    "trade_date": entry.trade_date,
+                    "ticks": entry.ticks,
+                    "validation_level": entry.validation.level,
+                }),
+            ));
+        }
+
+        match self.catalog.upsert(entry.clone()) {
+            Ok(Some(previous)) => {
+                // A corrected redelivery makes results built on the old file stale
+                if let (Some(lineage), Some(old_id)) = (&self.lineage, &previous.lineage_id) {
+                    if let Err(e) = lineage.invalidate(old_id, "dataset redelivered") {
+                        warn!("Failed to invalidate lineage of {}: {}", previous.id, e);
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to persist catalog entry for {}: {}", id, e),
+        }
+
+        info!("Ingested {} ({} ticks, trade date {:?})", id, entry.ticks, entry.trade_date);
+        IngestOutcome::Ingested(entry)
+    }
+
+    /// Move an ingested file to the archive directory, if one is configured
+    fn archive(&self, path: &Path) -> std::io::Result<PathBuf> {
+        let Some(dir) = &self.config.archive_dir else {
+            return Ok(path.to_path_buf());
+        };
+
+        fs::create_dir_all(dir)?;
+        let target = dir.join(file_name(path));
+        fs::rename(path, &target)?;
+        Ok(target)
+    }
+
+    #[cfg(not(feature = "jobs"))]
+    async fn queue_reoptimization(&mut self, _new_paths: Vec<String>) -> Option<String> {
+        if self.config.reoptimize.is_some() {
+            warn!("Re-optimization configured but built without the jobs feature");
+        }
+        None
+    }
+
+    #[cfg(feature = "jobs")]
+    async fn queue_reoptimization(&mut self, new_paths: Vec<String>) -> Option<String> {
+        let trigger = self.config.reoptimize.as_ref()?;
+        let Some(jobs) = self.jobs.as_mut() else {
+            warn!("Re-optimization configured but no job queue attached");
+            return None;
+        };
+
+        let mut payload = trigger.payload.clone();
+        if let Some(fields) = payload.as_object_mut() {
+            fields.insert(NEW_DATASETS_PAYLOAD_KEY.to_string(), serde_json::json!(new_paths));
+        }
+
+        let job = Job {
+            job_type: JobType::Optimization,
+            payload,
+            priority: trigger.priority,
+            workspace_id: trigger.workspace_id.clone(),
+            ..Default::default()
+        };
+
+        match jobs.enqueue(job).await {
+            Ok(job_id) => {
+                info!("Queued re-optimization {} for {} new datasets", job_id, new_paths.len());
+                Some(job_id)
+            }
+            Err(e) => {
+                warn!("Failed to queue re-optimization: {}", e);
+                None
+            }
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
+}
+
+pub(crate) fn fingerprint(len: u64, modified: SystemTime) -> String {
+    let modified = modified.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("{}-{}", len, modified)
+}
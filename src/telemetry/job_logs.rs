@@ -0,0 +1,427 @@
+//! Per-job log capture
+//!
+//! Events logged inside a span carrying a `job_id` field, such as the
+//! backtest, cache warm-up and worker job spans, are also appended to a log
+//! of that job on disk, so a failed run can be debugged through the API
+//! without shell access to the server. A job's log is written in segments;
+//! once it outgrows its cap the oldest segment is dropped, keeping the end
+//! of the run where failures are reported.
+
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Directory job logs are written to unless overridden
+pub const DEFAULT_JOB_LOG_DIR: &str = "data/job-logs";
+
+/// Size a job's log is kept under unless overridden
+pub const DEFAULT_MAX_JOB_LOG_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Segments a job's log is split into; one is dropped at a time
+const SEGMENTS: u64 = 4;
+
+/// Span field naming the job its events belong to
+const JOB_ID_FIELD: &str = "job_id";
+
+/// Errors raised reading job logs
+#[derive(Debug, thiserror::Error)]
+pub enum JobLogError {
+    #[error("No log for job {0}")]
+    NotFound(String),
+    #[error("Invalid job id {0}")]
+    InvalidJobId(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone)]
+pub struct JobLogConfig {
+    pub dir: PathBuf,
+    /// Most bytes kept of one job's log
+    pub max_bytes: u64,
+}
+
+impl Default for JobLogConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from(DEFAULT_JOB_LOG_DIR),
+            max_bytes: DEFAULT_MAX_JOB_LOG_BYTES,
+        }
+    }
+}
+
+impl JobLogConfig {
+    /// Defaults overridden by `STRATEGY_LAB_JOB_LOG_DIR` and
+    /// `STRATEGY_LAB_JOB_LOG_MAX_BYTES`
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            dir: std::env::var("STRATEGY_LAB_JOB_LOG_DIR").map(PathBuf::from).unwrap_or(defaults.dir),
+            max_bytes: std::env::var("STRATEGY_LAB_JOB_LOG_MAX_BYTES").ok()
+                .and_then(|bytes| bytes.parse().ok())
+                .unwrap_or(defaults.max_bytes),
+        }
+    }
+
+    fn segment_bytes(&self) -> u64 {
+        (self.max_bytes / SEGMENTS).max(1)
+    }
+}
+
+/// Position in a job's log, written `segment:offset`; the default is the
+/// start of the log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct JobLogCursor {
+    pub segment: u64,
+    pub offset: u64,
+}
+
+impl Default for JobLogCursor {
+    fn default() -> Self {
+        Self { segment: 1, offset: 0 }
+    }
+}
+
+impl fmt::Display for JobLogCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.segment, self.offset)
+    }
+}
+
+impl FromStr for JobLogCursor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (segment, offset) = s.split_once(':').ok_or_else(|| format!("Invalid log cursor {}", s))?;
+        Ok(Self {
+            segment: segment.parse().map_err(|_| format!("Invalid log cursor {}", s))?,
+            offset: offset.parse().map_err(|_| format!("Invalid log cursor {}", s))?,
+        })
+    }
+}
+
+impl TryFrom<String> for JobLogCursor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<JobLogCursor> for String {
+    fn from(cursor: JobLogCursor) -> Self {
+        cursor.to_string()
+    }
+}
+
+/// Lines read from a job's log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobLogChunk {
+    pub job_id: String,
+    pub lines: Vec<String>,
+    /// Where to continue reading for lines logged since
+    pub next: JobLogCursor,
+    /// Lines were dropped by the size cap before the ones returned
+    pub truncated: bool,
+}
+
+/// Segment file being appended to
+#[derive(Debug)]
+struct OpenSegment {
+    segment: u64,
+    file: File,
+    len: u64,
+}
+
+/// Job logs on disk
+///
+/// The same directory can be opened by the process writing the logs and by
+/// the API reading them.
+#[derive(Debug, Clone)]
+pub struct JobLogs {
+    config: JobLogConfig,
+    open: Arc<Mutex<HashMap<String, OpenSegment>>>,
+}
+
+impl JobLogs {
+    pub fn new(config: JobLogConfig) -> Self {
+        Self { config, open: Default::default() }
+    }
+
+    /// Ids become directory names, so they are limited to letters, digits,
+    /// `-` and `_`
+    fn job_dir(&self, job_id: &str) -> Result<PathBuf, JobLogError> {
+        let valid = !job_id.is_empty()
+            && job_id.len() <= 128
+            && job_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(JobLogError::InvalidJobId(job_id.to_string()));
+        }
+        Ok(self.config.dir.join(job_id))
+    }
+
+    fn segment_path(dir: &Path, segment: u64) -> PathBuf {
+        dir.join(format!("{:06}.log", segment))
+    }
+
+    /// Segments on disk, oldest first
+    fn segments(dir: &Path) -> Result<Vec<u64>, JobLogError> {
+        let mut segments: Vec<u64> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.strip_suffix(".log")?.parse().ok())
+            .collect();
+        segments.sort_unstable();
+        Ok(segments)
+    }
+
+    /// Append a line to a job's log, dropping its oldest segment once the
+    /// log outgrows the cap
+    pub fn append(&self, job_id: &str, line: &str) -> Result<(), JobLogError> {
+        let dir = self.job_dir(job_id)?;
+        let mut open = self.open.lock().unwrap();
+        if !open.contains_key(job_id) {
+            fs::create_dir_all(&dir)?;
+            let segment = Self::segments(&dir)?.last().copied().unwrap_or(1);
+            let file = OpenOptions::new().create(true).append(true).open(Self::segment_path(&dir, segment))?;
+            let len = file.metadata()?.len();
+            open.insert(job_id.to_string(), OpenSegment { segment, file, len });
+        }
+        let current = open.get_mut(job_id).unwrap();
+
+        // A line longer than a segment is cut to fit one
+        let mut end = line.len().min(self.config.segment_bytes().saturating_sub(1) as usize);
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        let line = &line[..end];
+        let bytes = line.len() as u64 + 1;
+        if current.len > 0 && current.len + bytes > self.config.segment_bytes() {
+            let segment = current.segment + 1;
+            *current = OpenSegment {
+                segment,
+                file: OpenOptions::new().create(true).append(true).open(Self::segment_path(&dir, segment))?,
+                len: 0,
+            };
+            for old in Self::segments(&dir)?.into_iter().filter(|&old| old + SEGMENTS <= segment) {
+                fs::remove_file(Self::segment_path(&dir, old))?;
+            }
+        }
+        // One write per line, so readers never see half of it once it ends
+        // in a newline
+        current.file.write_all(format!("{}\n", line).as_bytes())?;
+        current.len += bytes;
+        Ok(())
+    }
+
+    /// Release the file a job's log is appended to; later lines reopen it
+    pub fn close(&self, job_id: &str) {
+        self.open.lock().unwrap().remove(job_id);
+    }
+
+    /// Lines logged after `cursor`
+    ///
+    /// A cursor into a segment that was since dropped continues from the
+    /// oldest segment kept, and the chunk is marked truncated.
+    pub fn read_after(&self, job_id: &str, cursor: JobLogCursor) -> Result<JobLogChunk, JobLogError> {
+        let dir = self.job_dir(job_id)?;
+        if !dir.is_dir() {
+            return Err(JobLogError::NotFound(job_id.to_string()));
+        }
+        let segments = Self::segments(&dir)?;
+        let first = segments.first().copied().unwrap_or(1);
+        let truncated = cursor.segment < first;
+        let mut next = if truncated { JobLogCursor { segment: first, offset: 0 } } else { cursor };
+
+        let start = next.segment;
+        let mut text = Vec::new();
+        for &segment in segments.iter().filter(|&&segment| segment >= start) {
+            let mut file = File::open(Self::segment_path(&dir, segment))?;
+            let offset = if segment == next.segment { next.offset } else { 0 };
+            file.seek(SeekFrom::Start(offset))?;
+            let mut read = Vec::new();
+            file.read_to_end(&mut read)?;
+
+            // Stop at a line still being written
+            let complete = read.iter().rposition(|&b| b == b'\n').map_or(0, |end| end + 1);
+            text.extend_from_slice(&read[..complete]);
+            next = JobLogCursor { segment, offset: offset + complete as u64 };
+            if complete < read.len() {
+                break;
+            }
+        }
+
+        Ok(JobLogChunk {
+            job_id: job_id.to_string(),
+            lines: String::from_utf8_lossy(&text).lines().map(str::to_string).collect(),
+            next,
+            truncated,
+        })
+    }
+
+    /// The last `lines` lines of a job's log
+    pub fn tail(&self, job_id: &str, lines: usize) -> Result<JobLogChunk, JobLogError> {
+        let mut chunk = self.read_after(job_id, JobLogCursor::default())?;
+        let dropped = chunk.lines.len().saturating_sub(lines);
+        chunk.lines.drain(..dropped);
+        chunk.truncated &= dropped == 0;
+        Ok(chunk)
+    }
+
+    /// Layer capturing events of job spans into these logs
+    pub fn layer(&self) -> JobLogLayer {
+        JobLogLayer { logs: self.clone() }
+    }
+}
+
+/// Job a span belongs to, kept in its extensions
+struct JobId(String);
+
+#[derive(Default)]
+struct JobIdVisitor(Option<String>);
+
+impl Visit for JobIdVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == JOB_ID_FIELD {
+            self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == JOB_ID_FIELD {
+            self.0 = Some(format!("{:?}", value));
+        }
+    }
+}
+
+/// Message and fields of an event as one line
+#[derive(Default)]
+struct LineVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for LineVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            self.fields.push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields.push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+}
+
+/// Tracing layer appending the events of job spans to their job's log
+///
+/// An event belongs to the innermost enclosing span with a `job_id` field.
+pub struct JobLogLayer {
+    logs: JobLogs,
+}
+
+impl<S> Layer<S> for JobLogLayer
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = JobIdVisitor::default();
+        attrs.record(&mut visitor);
+        if let (Some(job_id), Some(span)) = (visitor.0, ctx.span(id)) {
+            span.extensions_mut().insert(JobId(job_id));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(scope) = ctx.event_scope(event) else {
+            return;
+        };
+        let Some(job_id) = scope
+            .into_iter()
+            .find_map(|span| span.extensions().get::<JobId>().map(|job| job.0.clone()))
+        else {
+            return;
+        };
+
+        let mut visitor = LineVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        let line = format!(
+            "{} {:>5} {}: {}{}",
+            Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            metadata.level(),
+            metadata.target(),
+            visitor.message,
+            visitor.fields,
+        );
+        // Logging the failure would be captured again
+        if let Err(e) = self.logs.append(&job_id, &line) {
+            eprintln!("Failed to write the log of job {}: {}", job_id, e);
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(&id) {
+            if let Some(job) = span.extensions().get::<JobId>() {
+                self.logs.close(&job.0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_job_events_are_captured_and_capped() {
+        let dir = std::env::temp_dir().join(format!("job_logs_{}", uuid::Uuid::new_v4()));
+        let logs = JobLogs::new(JobLogConfig { dir: dir.clone(), max_bytes: 4 * 200 });
+        let subscriber = tracing_subscriber::registry().with(logs.layer());
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("outside any job");
+            let span = tracing::info_span!("backtest_job", job_id = %"bt-1");
+            let _entered = span.enter();
+            tracing::warn!(trades = 3, "fill rejected");
+        });
+        let chunk = logs.tail("bt-1", 10).unwrap();
+        assert_eq!(chunk.lines.len(), 1);
+        assert!(chunk.lines[0].ends_with("WARN strategy_lab::telemetry::job_logs::tests: fill rejected trades=3"));
+
+        // Following from the cursor sees only new lines
+        logs.append("bt-1", "next").unwrap();
+        let newer = logs.read_after("bt-1", chunk.next).unwrap();
+        assert_eq!(newer.lines, vec!["next".to_string()]);
+
+        for i in 0..100 {
+            logs.append("bt-1", &format!("line {:03} {}", i, "x".repeat(40))).unwrap();
+        }
+        let capped = logs.read_after("bt-1", JobLogCursor::default()).unwrap();
+        assert!(capped.truncated);
+        assert!(capped.lines.last().unwrap().starts_with("line 099"));
+        let kept: usize = capped.lines.iter().map(|line| line.len() + 1).sum();
+        assert!(kept <= 4 * 200);
+
+        assert!(matches!(logs.tail("../etc", 10), Err(JobLogError::InvalidJobId(_))));
+        assert!(matches!(logs.tail("bt-2", 10), Err(JobLogError::NotFound(_))));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
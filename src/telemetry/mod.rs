@@ -0,0 +1,245 @@
+//! Request tracing and trace export
+//!
+//! Every API request runs in a span carrying a W3C trace id, taken from the
+//! caller's `traceparent` header or started fresh. Jobs queued from a
+//! request carry the id in their `TraceContext`, so the worker that picks
+//! them up and the backtest engine it runs log under the same trace, and a
+//! slow run can be followed from the request that started it.
+//!
+//! Spans always reach the log. With the `telemetry` feature and an OTLP
+//! endpoint configured they are also exported over OTLP/gRPC, joined to the
+//! caller's trace when the request carried one. Events of job spans are
+//! also kept in a log per job, readable through the API.
+
+pub mod job_logs;
+#[cfg(feature = "telemetry")]
+mod otlp;
+
+pub use job_logs::{JobLogChunk, JobLogConfig, JobLogCursor, JobLogError, JobLogLayer, JobLogs};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+/// Header carrying the W3C trace context of a request
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Errors raised setting up tracing
+#[derive(Debug, thiserror::Error)]
+pub enum TelemetryError {
+    #[error("Failed to build the OTLP exporter: {0}")]
+    Exporter(String),
+    #[error("Failed to install the tracing subscriber: {0}")]
+    Subscriber(String),
+}
+
+/// Position in a distributed trace, as carried by `traceparent`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceContext {
+    /// 32 lowercase hex digits
+    pub trace_id: String,
+    /// Span the holder continues from, 16 lowercase hex digits
+    pub parent_span_id: String,
+    #[serde(default = "default_sampled")]
+    pub sampled: bool,
+}
+
+fn default_sampled() -> bool {
+    true
+}
+
+impl TraceContext {
+    /// Start a new trace
+    pub fn new_root() -> Self {
+        let mut rng = rand::thread_rng();
+        Self {
+            trace_id: format!("{:032x}", rng.gen_range(1..=u128::MAX)),
+            parent_span_id: format!("{:016x}", rng.gen_range(1..=u64::MAX)),
+            sampled: true,
+        }
+    }
+
+    /// Read a `traceparent` header value; `None` when it is malformed
+    pub fn parse(traceparent: &str) -> Option<Self> {
+        let mut parts = traceparent.trim().split('-');
+        let (version, trace_id, parent_span_id, flags) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        let hex = |value: &str, len: usize| {
+            value.len() == len
+                && value.chars().all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+                && value.chars().any(|c| c != '0')
+        };
+        // Later versions may append fields; version 00 has exactly four
+        if version.len() != 2 || version == "ff" || (version == "00" && parts.next().is_some()) {
+            return None;
+        }
+        if !hex(trace_id, 32) || !hex(parent_span_id, 16) || flags.len() != 2 {
+            return None;
+        }
+        let flags = u8::from_str_radix(flags, 16).ok()?;
+        Some(Self {
+            trace_id: trace_id.to_string(),
+            parent_span_id: parent_span_id.to_string(),
+            sampled: flags & 1 == 1,
+        })
+    }
+
+    /// Continue the trace from a new span
+    pub fn child(&self) -> Self {
+        Self {
+            trace_id: self.trace_id.clone(),
+            parent_span_id: format!("{:016x}", rand::thread_rng().gen_range(1..=u64::MAX)),
+            sampled: self.sampled,
+        }
+    }
+
+    pub fn to_traceparent(&self) -> String {
+        format!("00-{}-{}-{:02x}", self.trace_id, self.parent_span_id, u8::from(self.sampled))
+    }
+
+    /// Context of the current span when spans are exported, so work handed
+    /// to another process joins the same trace
+    pub fn current() -> Option<Self> {
+        #[cfg(feature = "telemetry")]
+        {
+            otlp::current_context()
+        }
+        #[cfg(not(feature = "telemetry"))]
+        {
+            None
+        }
+    }
+}
+
+/// Make `span` part of the trace `context` belongs to
+///
+/// Without span export the trace id is only known from the span's fields,
+/// so callers should record it there as well.
+pub fn attach(span: &tracing::Span, context: &TraceContext) {
+    #[cfg(feature = "telemetry")]
+    otlp::set_parent(span, context);
+    #[cfg(not(feature = "telemetry"))]
+    let _ = (span, context);
+}
+
+/// How spans are logged and exported
+#[derive(Debug, Clone)]
+pub struct TelemetryConfig {
+    pub service_name: String,
+    /// OTLP/gRPC collector, e.g. `http://localhost:4317`; spans are only
+    /// logged when unset
+    pub otlp_endpoint: Option<String>,
+    /// `RUST_LOG`-style filter
+    pub log_filter: String,
+    /// Where events of job spans are captured
+    pub job_logs: JobLogConfig,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            service_name: "strategy-lab".to_string(),
+            otlp_endpoint: None,
+            log_filter: "info".to_string(),
+            job_logs: JobLogConfig::default(),
+        }
+    }
+}
+
+impl TelemetryConfig {
+    /// Defaults overridden by the standard `OTEL_SERVICE_NAME`,
+    /// `OTEL_EXPORTER_OTLP_ENDPOINT` and `RUST_LOG` variables; job log
+    /// settings as in `JobLogConfig::from_env`
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            service_name: std::env::var("OTEL_SERVICE_NAME").unwrap_or(defaults.service_name),
+            otlp_endpoint: std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok().filter(|endpoint| !endpoint.is_empty()),
+            log_filter: std::env::var("RUST_LOG").unwrap_or(defaults.log_filter),
+            job_logs: JobLogConfig::from_env(),
+        }
+    }
+}
+
+/// Keeps span export running; dropping it flushes the spans not yet sent
+#[must_use = "spans stop being exported when the guard is dropped"]
+pub struct TelemetryGuard {
+    #[cfg(feature = "telemetry")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "telemetry")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to flush exported spans: {}", e);
+            }
+        }
+    }
+}
+
+/// Install the global tracing subscriber: logs always, OTLP export when
+/// configured and built with the `telemetry` feature
+pub fn init(config: &TelemetryConfig) -> Result<TelemetryGuard, TelemetryError> {
+    let filter = EnvFilter::try_new(&config.log_filter).unwrap_or_else(|_| EnvFilter::new("info"));
+    let registry = tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer())
+        .with(JobLogs::new(config.job_logs.clone()).layer());
+
+    #[cfg(feature = "telemetry")]
+    {
+        let (layer, provider) = match &config.otlp_endpoint {
+            Some(endpoint) => {
+                let (layer, provider) = otlp::layer(&config.service_name, endpoint)?;
+                (Some(layer), Some(provider))
+            }
+            None => (None, None),
+        };
+        registry.with(layer).try_init().map_err(|e| TelemetryError::Subscriber(e.to_string()))?;
+        if let Some(endpoint) = &config.otlp_endpoint {
+            tracing::info!("Exporting spans to {}", endpoint);
+        }
+        Ok(TelemetryGuard { provider })
+    }
+
+    #[cfg(not(feature = "telemetry"))]
+    {
+        registry.try_init().map_err(|e| TelemetryError::Subscriber(e.to_string()))?;
+        if config.otlp_endpoint.is_some() {
+            tracing::warn!("OTEL_EXPORTER_OTLP_ENDPOINT is set but this build lacks the telemetry feature; spans are only logged");
+        }
+        Ok(TelemetryGuard {})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traceparent_round_trip() {
+        let header = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let context = TraceContext::parse(header).unwrap();
+        assert_eq!(context.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert!(context.sampled);
+        assert_eq!(context.to_traceparent(), header);
+
+        let child = context.child();
+        assert_eq!(child.trace_id, context.trace_id);
+        assert_ne!(child.parent_span_id, context.parent_span_id);
+
+        for malformed in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        ] {
+            assert!(TraceContext::parse(malformed).is_none(), "{}", malformed);
+        }
+        assert!(TraceContext::parse(&TraceContext::new_root().to_traceparent()).is_some());
+    }
+}
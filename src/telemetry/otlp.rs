@@ -0,0 +1,58 @@
+//! OTLP span export
+
+use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState, TracerProvider};
+use opentelemetry::Context;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::trace::{SdkTracerProvider, Tracer};
+use opentelemetry_sdk::Resource;
+use tracing::Subscriber;
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::registry::LookupSpan;
+
+use super::{TelemetryError, TraceContext};
+
+/// Layer exporting spans to the collector at `endpoint`, and the provider
+/// that must be shut down to flush them
+pub(super) fn layer<S>(
+    service_name: &str,
+    endpoint: &str,
+) -> Result<(OpenTelemetryLayer<S, Tracer>, SdkTracerProvider), TelemetryError>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let exporter = SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()
+        .map_err(|e| TelemetryError::Exporter(e.to_string()))?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name.to_string()).build())
+        .build();
+    let tracer = provider.tracer("strategy_lab");
+    Ok((tracing_opentelemetry::layer().with_tracer(tracer), provider))
+}
+
+pub(super) fn current_context() -> Option<TraceContext> {
+    let context = tracing::Span::current().context();
+    let span = context.span();
+    let span_context = span.span_context();
+    if !span_context.is_valid() {
+        return None;
+    }
+    Some(TraceContext {
+        trace_id: span_context.trace_id().to_string(),
+        parent_span_id: span_context.span_id().to_string(),
+        sampled: span_context.is_sampled(),
+    })
+}
+
+pub(super) fn set_parent(span: &tracing::Span, context: &TraceContext) {
+    let (Ok(trace_id), Ok(span_id)) = (TraceId::from_hex(&context.trace_id), SpanId::from_hex(&context.parent_span_id)) else {
+        return;
+    };
+    let flags = if context.sampled { TraceFlags::SAMPLED } else { TraceFlags::default() };
+    let remote = SpanContext::new(trace_id, span_id, flags, true, TraceState::default());
+    // Only fails once the span has been entered, when its trace is fixed
+    let _ = span.set_parent(Context::new().with_remote_span_context(remote));
+}
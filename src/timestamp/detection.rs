@@ -0,0 +1,210 @@
+//! Session boundaries inferred from tick density
+//!
+//! Vendors differ in which hours they deliver, and users often do not know
+//! them exactly. Each minute of the exchange day is scored by the share of
+//! observed calendar days with at least one tick in it. Minutes below a
+//! coverage threshold are closed; the longest closed stretch of the day is
+//! the gap between close and the next open, and shorter closed stretches are
+//! maintenance halts. Weekdays inside the observed range with no session at
+//! all are proposed as holidays.
+
+use chrono::{Datelike, NaiveDate, NaiveTime, Timelike, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::session::{MaintenanceHalt, SessionCalendar};
+use super::Timestamp;
+
+const MINUTES_PER_DAY: usize = 24 * 60;
+
+/// Errors raised detecting sessions
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum DetectionError {
+    #[error("No ticks to detect sessions from")]
+    NoData,
+    #[error("Ticks cover every minute of the day; there is no daily close to detect")]
+    NoDailyBreak,
+    #[error("No minute reaches {0:.0}% coverage; the data is too sparse to detect sessions")]
+    TooSparse(f64),
+}
+
+/// Thresholds used to tell trading minutes from closed ones
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionDetectionConfig {
+    /// Share of observed days a minute needs ticks on to count as trading
+    #[serde(default = "default_min_coverage")]
+    pub min_coverage: f64,
+    /// Shortest closed stretch inside the session reported as a halt;
+    /// shorter gaps are treated as quiet trading
+    #[serde(default = "default_min_halt_minutes")]
+    pub min_halt_minutes: usize,
+}
+
+fn default_min_coverage() -> f64 {
+    0.2
+}
+
+fn default_min_halt_minutes() -> usize {
+    5
+}
+
+impl Default for SessionDetectionConfig {
+    fn default() -> Self {
+        Self {
+            min_coverage: default_min_coverage(),
+            min_halt_minutes: default_min_halt_minutes(),
+        }
+    }
+}
+
+/// A calendar inferred from data, for the user to confirm
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionProposal {
+    pub calendar: SessionCalendar,
+    /// Exchange calendar days with at least one tick
+    pub observed_days: usize,
+    /// Trade dates the proposed calendar assigns ticks to
+    pub sessions_observed: usize,
+    /// Mean coverage of trading minutes minus that of closed minutes; near
+    /// one when the boundaries are sharp
+    pub contrast: f64,
+    /// Coverage of each minute of the exchange day, from midnight
+    pub minute_coverage: Vec<f64>,
+}
+
+/// Propose a session calendar from the timestamps of a dataset's ticks
+pub fn detect_sessions(
+    timestamps: impl IntoIterator<Item = Timestamp>,
+    config: &SessionDetectionConfig,
+) -> Result<SessionProposal, DetectionError> {
+    let timestamps: Vec<Timestamp> = timestamps.into_iter().collect();
+    let mut days: BTreeMap<NaiveDate, Vec<bool>> = BTreeMap::new();
+    for timestamp in &timestamps {
+        let local = timestamp.to_exchange();
+        let minute = (local.hour() * 60 + local.minute()) as usize;
+        days.entry(local.date_naive()).or_insert_with(|| vec![false; MINUTES_PER_DAY])[minute] = true;
+    }
+    if days.is_empty() {
+        return Err(DetectionError::NoData);
+    }
+
+    let mut minute_coverage = vec![0.0; MINUTES_PER_DAY];
+    for minutes in days.values() {
+        for (coverage, seen) in minute_coverage.iter_mut().zip(minutes) {
+            if *seen {
+                *coverage += 1.0;
+            }
+        }
+    }
+    for coverage in &mut minute_coverage {
+        *coverage /= days.len() as f64;
+    }
+
+    let active: Vec<bool> = minute_coverage.iter().map(|c| *c >= config.min_coverage).collect();
+    let Some(first_active) = active.iter().position(|a| *a) else {
+        return Err(DetectionError::TooSparse(config.min_coverage * 100.0));
+    };
+    let mut gaps = closed_stretches(&active, first_active);
+    if gaps.is_empty() {
+        return Err(DetectionError::NoDailyBreak);
+    }
+
+    // The longest closed stretch runs from the close to the next open
+    gaps.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let (close, length) = gaps[0];
+    let mut calendar = SessionCalendar {
+        open: minute_time((close + length) % MINUTES_PER_DAY),
+        close: minute_time(close),
+        holidays: BTreeSet::new(),
+        halts: Vec::new(),
+    };
+    let mut halts: Vec<MaintenanceHalt> = gaps[1..].iter()
+        .filter(|(_, length)| *length >= config.min_halt_minutes)
+        .map(|(start, length)| MaintenanceHalt {
+            start: minute_time(*start),
+            end: minute_time((start + length) % MINUTES_PER_DAY),
+        })
+        .collect();
+    halts.sort_by_key(|halt| halt.start);
+    calendar.halts = halts;
+
+    // Weekdays between the first and last session with no ticks at all
+    let sessions: BTreeSet<NaiveDate> = timestamps.iter().filter_map(|t| calendar.trading_date(*t)).collect();
+    if let (Some(first), Some(last)) = (sessions.first(), sessions.last()) {
+        calendar.holidays = first.iter_days()
+            .take_while(|date| date <= last)
+            .filter(|date| !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !sessions.contains(date))
+            .collect();
+    }
+
+    let mean = |values: Vec<f64>| if values.is_empty() { 0.0 } else { values.iter().sum::<f64>() / values.len() as f64 };
+    let (open_coverage, closed_coverage): (Vec<(f64, bool)>, Vec<(f64, bool)>) = minute_coverage.iter()
+        .copied()
+        .zip(active.iter().copied())
+        .partition(|(_, active)| *active);
+    let contrast = mean(open_coverage.into_iter().map(|(c, _)| c).collect())
+        - mean(closed_coverage.into_iter().map(|(c, _)| c).collect());
+
+    Ok(SessionProposal {
+        calendar,
+        observed_days: days.len(),
+        sessions_observed: sessions.len(),
+        contrast,
+        minute_coverage,
+    })
+}
+
+/// Closed stretches of the circular day as `(first minute, length)`,
+/// scanning from an active minute so no stretch is split at midnight
+fn closed_stretches(active: &[bool], first_active: usize) -> Vec<(usize, usize)> {
+    let mut stretches = Vec::new();
+    let mut current: Option<(usize, usize)> = None;
+    for offset in 0..active.len() {
+        let minute = (first_active + offset) % active.len();
+        match (active[minute], current.as_mut()) {
+            (false, Some((_, length))) => *length += 1,
+            (false, None) => current = Some((minute, 1)),
+            (true, Some(_)) => stretches.extend(current.take()),
+            (true, None) => {}
+        }
+    }
+    stretches.extend(current);
+    stretches
+}
+
+fn minute_time(minute: usize) -> NaiveTime {
+    NaiveTime::from_hms_opt((minute / 60) as u32, (minute % 60) as u32, 0).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    fn test_detects_globex_hours_and_halt() {
+        // Sunday 2024-01-14 17:00 CT (23:00 UTC) to Friday 16:00 CT, one tick
+        // a minute, closed 16:00-17:00 CT and paused 03:00-03:15 CT; Wednesday
+        // the 17th has no session
+        let start = Utc.with_ymd_and_hms(2024, 1, 14, 23, 0, 0).unwrap();
+        let calendar = SessionCalendar::default();
+        let ticks: Vec<Timestamp> = (0..6 * 24 * 60)
+            .map(|m| Timestamp::from(start + Duration::minutes(m)))
+            .filter(|t| {
+                let time = t.to_exchange().time();
+                calendar.trading_date(*t).is_some_and(|d| d != NaiveDate::from_ymd_opt(2024, 1, 17).unwrap())
+                    && !(time >= minute_time(180) && time < minute_time(195))
+            })
+            .collect();
+
+        let proposal = detect_sessions(ticks, &SessionDetectionConfig::default()).unwrap();
+        assert_eq!(proposal.calendar.open, minute_time(17 * 60));
+        assert_eq!(proposal.calendar.close, minute_time(16 * 60));
+        assert_eq!(proposal.calendar.halts, vec![MaintenanceHalt { start: minute_time(180), end: minute_time(195) }]);
+        assert_eq!(proposal.calendar.holidays.iter().copied().collect::<Vec<_>>(), vec![NaiveDate::from_ymd_opt(2024, 1, 17).unwrap()]);
+        assert_eq!(proposal.sessions_observed, 4);
+        assert!(proposal.contrast > 0.5);
+
+        assert_eq!(detect_sessions(Vec::new(), &SessionDetectionConfig::default()).unwrap_err(), DetectionError::NoData);
+    }
+}
@@ -0,0 +1,216 @@
+//! Typed timestamps and time zone conversions
+//!
+//! Ticks carry nanoseconds since the Unix epoch in UTC. Every conversion to
+//! wall-clock time goes through `Timestamp` so the zone is always explicit:
+//! exchange time is US Central (CME Globex, with daylight saving), display
+//! time is whatever zone the user asks for. Mixing a bare `i64` with local
+//! wall-clock arithmetic is how DST boundaries turn into off-by-one-hour bugs.
+
+pub mod detection;
+pub mod session;
+
+pub use detection::{detect_sessions, DetectionError, SessionDetectionConfig, SessionProposal};
+pub use session::{MaintenanceHalt, SessionCalendar};
+
+use chrono::{DateTime, Duration, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::{Add, Sub};
+
+/// Zone CME equity index futures trade in
+pub const EXCHANGE_TZ: Tz = chrono_tz::America::Chicago;
+
+/// Errors converting to or from wall-clock time
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum TimestampError {
+    #[error("Unknown time zone: {0}")]
+    UnknownZone(String),
+    #[error("{time} does not exist in {zone} (skipped by a DST change)")]
+    NonexistentLocalTime { time: NaiveDateTime, zone: Tz },
+    #[error("{time} is ambiguous in {zone} (repeated by a DST change)")]
+    AmbiguousLocalTime { time: NaiveDateTime, zone: Tz },
+    #[error("Timestamp out of range")]
+    OutOfRange,
+}
+
+/// A point in time as UTC nanoseconds since the Unix epoch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    pub const MIN: Timestamp = Timestamp(i64::MIN);
+    pub const MAX: Timestamp = Timestamp(i64::MAX);
+
+    pub const fn from_nanos(nanos: i64) -> Self {
+        Self(nanos)
+    }
+
+    pub const fn nanos(self) -> i64 {
+        self.0
+    }
+
+    pub fn now() -> Self {
+        Self::from_datetime(Utc::now())
+    }
+
+    /// Convert from a zoned time; saturates outside the ~1677-2262 range of i64 nanoseconds
+    pub fn from_datetime<Z: TimeZone>(time: DateTime<Z>) -> Self {
+        let utc = time.with_timezone(&Utc);
+        match utc.timestamp_nanos_opt() {
+            Some(nanos) => Self(nanos),
+            None if utc.timestamp() < 0 => Self::MIN,
+            None => Self::MAX,
+        }
+    }
+
+    /// Interpret a wall-clock time in `zone`
+    ///
+    /// Times skipped or repeated by a daylight saving change are errors rather
+    /// than silently shifted by an hour.
+    pub fn from_local(time: NaiveDateTime, zone: Tz) -> Result<Self, TimestampError> {
+        match zone.from_local_datetime(&time) {
+            LocalResult::Single(local) => Ok(Self::from_datetime(local)),
+            LocalResult::None => Err(TimestampError::NonexistentLocalTime { time, zone }),
+            LocalResult::Ambiguous(_, _) => Err(TimestampError::AmbiguousLocalTime { time, zone }),
+        }
+    }
+
+    /// Interpret a wall-clock time on the exchange clock
+    pub fn from_exchange_local(time: NaiveDateTime) -> Result<Self, TimestampError> {
+        Self::from_local(time, EXCHANGE_TZ)
+    }
+
+    pub fn to_utc(self) -> DateTime<Utc> {
+        DateTime::from_timestamp_nanos(self.0)
+    }
+
+    /// Wall-clock time at the exchange (US Central, DST aware)
+    pub fn to_exchange(self) -> DateTime<Tz> {
+        self.to_zone(EXCHANGE_TZ)
+    }
+
+    /// Wall-clock time in a display zone
+    pub fn to_zone(self, zone: Tz) -> DateTime<Tz> {
+        self.to_utc().with_timezone(&zone)
+    }
+
+    /// Calendar date on the exchange clock
+    pub fn exchange_date(self) -> NaiveDate {
+        self.to_exchange().date_naive()
+    }
+
+    /// Format in a display zone with a chrono format string
+    pub fn format_in(self, zone: Tz, format: &str) -> String {
+        self.to_zone(zone).format(format).to_string()
+    }
+
+    pub fn checked_add(self, duration: Duration) -> Option<Self> {
+        duration.num_nanoseconds()
+            .and_then(|nanos| self.0.checked_add(nanos))
+            .map(Self)
+    }
+
+    /// Time elapsed since `earlier` (negative if `earlier` is later)
+    pub fn duration_since(self, earlier: Timestamp) -> Duration {
+        Duration::nanoseconds(self.0.saturating_sub(earlier.0))
+    }
+}
+
+/// Parse an IANA zone name such as "America/New_York" for display
+pub fn parse_zone(name: &str) -> Result<Tz, TimestampError> {
+    name.parse().map_err(|_| TimestampError::UnknownZone(name.to_string()))
+}
+
+impl From<i64> for Timestamp {
+    fn from(nanos: i64) -> Self {
+        Self(nanos)
+    }
+}
+
+impl From<Timestamp> for i64 {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.0
+    }
+}
+
+impl From<DateTime<Utc>> for Timestamp {
+    fn from(time: DateTime<Utc>) -> Self {
+        Self::from_datetime(time)
+    }
+}
+
+impl From<Timestamp> for DateTime<Utc> {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.to_utc()
+    }
+}
+
+impl Add<Duration> for Timestamp {
+    type Output = Timestamp;
+
+    fn add(self, duration: Duration) -> Timestamp {
+        self.checked_add(duration).unwrap_or(if duration < Duration::zero() { Self::MIN } else { Self::MAX })
+    }
+}
+
+impl Sub for Timestamp {
+    type Output = Duration;
+
+    fn sub(self, other: Timestamp) -> Duration {
+        self.duration_since(other)
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_utc().to_rfc3339_opts(chrono::SecondsFormat::Nanos, true))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, Timelike};
+
+    fn local(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_exchange_offset_follows_dst() {
+        // 14:30 UTC is 08:30 CT in winter (CST) and 09:30 CT in summer (CDT)
+        let winter = Timestamp::from(Utc.with_ymd_and_hms(2024, 1, 15, 14, 30, 0).unwrap());
+        let summer = Timestamp::from(Utc.with_ymd_and_hms(2024, 7, 15, 14, 30, 0).unwrap());
+
+        assert_eq!(winter.to_exchange().hour(), 8);
+        assert_eq!(summer.to_exchange().hour(), 9);
+    }
+
+    #[test]
+    fn test_local_round_trip_and_dst_gaps() {
+        let open = Timestamp::from_exchange_local(local(2024, 3, 4, 8, 30)).unwrap();
+        assert_eq!(open.to_exchange().naive_local(), local(2024, 3, 4, 8, 30));
+        assert_eq!(open.to_utc().hour(), 14);
+
+        // 2024-03-10 02:30 CT was skipped, 2024-11-03 01:30 CT happened twice
+        assert!(matches!(
+            Timestamp::from_exchange_local(local(2024, 3, 10, 2, 30)),
+            Err(TimestampError::NonexistentLocalTime { .. })
+        ));
+        assert!(matches!(
+            Timestamp::from_exchange_local(local(2024, 11, 3, 1, 30)),
+            Err(TimestampError::AmbiguousLocalTime { .. })
+        ));
+    }
+
+    #[test]
+    fn test_display_zone() {
+        let ts = Timestamp::from(Utc.with_ymd_and_hms(2024, 7, 15, 14, 30, 0).unwrap());
+        let zone = parse_zone("America/New_York").unwrap();
+
+        assert_eq!(ts.format_in(zone, "%H:%M"), "10:30");
+        assert!(parse_zone("Mars/Olympus").is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//! Exchange session calendar
+//!
+//! CME Globex equity index futures trade from 17:00 to 16:00 Central time,
+//! Sunday evening through Friday afternoon, with a one hour halt each day.
+//! A session opening Sunday 17:00 CT belongs to Monday's trade date. All
+//! boundaries are computed on the exchange clock, so they stay at 17:00 CT
+//! across daylight saving changes (22:00 or 23:00 UTC depending on the date).
+//! Calendars whose open is earlier in the day than their close describe
+//! sessions that open and close on the trade date itself, and maintenance
+//! halts can close the market for part of a session.
+
+use chrono::{Datelike, Days, NaiveDate, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+use super::{Timestamp, TimestampError};
+
+/// Trading hours and holidays on the exchange clock
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionCalendar {
+    /// Session open on the previous calendar day
+    pub open: NaiveTime,
+    /// Session close on the trade date
+    pub close: NaiveTime,
+    /// Trade dates with no session
+    #[serde(default)]
+    pub holidays: BTreeSet<NaiveDate>,
+    /// Daily pauses inside the session
+    #[serde(default)]
+    pub halts: Vec<MaintenanceHalt>,
+}
+
+/// A daily pause in trading, `start` inclusive to `end` exclusive on the exchange clock
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceHalt {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl MaintenanceHalt {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl Default for SessionCalendar {
+    fn default() -> Self {
+        Self::cme_equity_futures()
+    }
+}
+
+impl SessionCalendar {
+    /// Globex hours for equity index futures such as MNQ
+    pub fn cme_equity_futures() -> Self {
+        Self {
+            open: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            close: NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+            holidays: BTreeSet::new(),
+            halts: Vec::new(),
+        }
+    }
+
+    pub fn with_halts(mut self, halts: impl IntoIterator<Item = MaintenanceHalt>) -> Self {
+        self.halts.extend(halts);
+        self
+    }
+
+    pub fn with_holidays(mut self, holidays: impl IntoIterator<Item = NaiveDate>) -> Self {
+        self.holidays.extend(holidays);
+        self
+    }
+
+    fn is_trading_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.holidays.contains(&date)
+    }
+
+    /// Trade date a timestamp belongs to, or `None` outside trading hours
+    pub fn trading_date(&self, timestamp: Timestamp) -> Option<NaiveDate> {
+        let local = timestamp.to_exchange();
+        let (date, time) = (local.date_naive(), local.time());
+
+        let trade_date = if self.open < self.close {
+            // Session within a single calendar day
+            if time < self.open || time >= self.close {
+                return None;
+            }
+            date
+        } else if time >= self.open {
+            date.checked_add_days(Days::new(1))?
+        } else if time < self.close {
+            date
+        } else {
+            // Daily halt between close and the next open
+            return None;
+        };
+
+        self.is_trading_day(trade_date).then_some(trade_date)
+    }
+
+    /// Whether the market is open at `timestamp`, outside any maintenance halt
+    pub fn is_open(&self, timestamp: Timestamp) -> bool {
+        let time = timestamp.to_exchange().time();
+        self.trading_date(timestamp).is_some() && !self.halts.iter().any(|halt| halt.contains(time))
+    }
+
+    /// Open and close of the session for a trade date
+    pub fn session_bounds(&self, trade_date: NaiveDate) -> Result<(Timestamp, Timestamp), TimestampError> {
+        let open_date = if self.open < self.close {
+            trade_date
+        } else {
+            trade_date.checked_sub_days(Days::new(1)).ok_or(TimestampError::OutOfRange)?
+        };
+        let open = Timestamp::from_exchange_local(open_date.and_time(self.open))?;
+        let close = Timestamp::from_exchange_local(trade_date.and_time(self.close))?;
+        Ok((open, close))
+    }
+
+    /// Whether two consecutive events fall in different sessions
+    pub fn crosses_session(&self, previous: Timestamp, next: Timestamp) -> bool {
+        match (self.trading_date(previous), self.trading_date(next)) {
+            (Some(a), Some(b)) => a != b,
+            (Some(_), None) => true,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> Timestamp {
+        Timestamp::from(Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap())
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_evening_belongs_to_next_trade_date() {
+        let calendar = SessionCalendar::default();
+
+        // Sunday 2024-01-14 18:00 CT (00:00 UTC Monday) is Monday's session
+        assert_eq!(calendar.trading_date(utc(2024, 1, 15, 0, 0)), Some(date(2024, 1, 15)));
+        // Monday 15:59 CT
+        assert_eq!(calendar.trading_date(utc(2024, 1, 15, 21, 59)), Some(date(2024, 1, 15)));
+        // Monday 16:30 CT is the daily halt
+        assert_eq!(calendar.trading_date(utc(2024, 1, 15, 22, 30)), None);
+        // Friday 17:30 CT opens nothing: Saturday is not a trade date
+        assert_eq!(calendar.trading_date(utc(2024, 1, 19, 23, 30)), None);
+    }
+
+    #[test]
+    fn test_session_open_tracks_dst() {
+        let calendar = SessionCalendar::default();
+
+        // 17:00 CT is 23:00 UTC in winter and 22:00 UTC in summer
+        let (winter_open, _) = calendar.session_bounds(date(2024, 1, 16)).unwrap();
+        let (summer_open, summer_close) = calendar.session_bounds(date(2024, 7, 16)).unwrap();
+
+        assert_eq!(winter_open, utc(2024, 1, 15, 23, 0));
+        assert_eq!(summer_open, utc(2024, 7, 15, 22, 0));
+        assert_eq!(summer_close, utc(2024, 7, 16, 21, 0));
+        // 22:30 UTC is the halt in winter but already the next session in summer
+        assert!(!calendar.is_open(utc(2024, 1, 16, 22, 30)));
+        assert!(calendar.is_open(utc(2024, 7, 16, 22, 30)));
+    }
+
+    #[test]
+    fn test_holidays_and_session_crossing() {
+        let calendar = SessionCalendar::default().with_holidays([date(2024, 7, 4)]);
+
+        assert!(!calendar.is_open(utc(2024, 7, 4, 15, 0)));
+        assert!(calendar.crosses_session(utc(2024, 7, 15, 20, 59), utc(2024, 7, 15, 22, 1)));
+        assert!(!calendar.crosses_session(utc(2024, 7, 15, 14, 0), utc(2024, 7, 15, 15, 0)));
+
+        // A day session with a 15-minute pause at 11:00 CT (16:00 UTC in summer)
+        let day = SessionCalendar {
+            open: NaiveTime::from_hms_opt(8, 30, 0).unwrap(),
+            close: NaiveTime::from_hms_opt(15, 15, 0).unwrap(),
+            ..SessionCalendar::default()
+        }
+        .with_halts([MaintenanceHalt {
+            start: NaiveTime::from_hms_opt(11, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(11, 15, 0).unwrap(),
+        }]);
+        assert_eq!(day.trading_date(utc(2024, 7, 15, 14, 0)), Some(date(2024, 7, 15)));
+        assert!(day.is_open(utc(2024, 7, 15, 15, 59)));
+        assert!(!day.is_open(utc(2024, 7, 15, 16, 5)));
+        assert!(!day.is_open(utc(2024, 7, 15, 21, 0)));
+        assert_eq!(day.session_bounds(date(2024, 7, 15)).unwrap().0, utc(2024, 7, 15, 13, 30));
+    }
+}
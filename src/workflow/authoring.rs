@@ -0,0 +1,280 @@
+//! User-defined workflow templates
+//!
+//! Teams describe their own research process as a template: an ordered list
+//! of steps with dependencies, inputs and help content. Templates are checked
+//! here before they are stored or registered with the workflow engine.
+
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::{
+    is_builtin_workflow, DifficultyLevel, InputType, ValidationRule, Workflow, WorkflowCategory, WorkflowMetadata, WorkflowStep,
+};
+
+/// A workflow template as submitted by a user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowTemplateDraft {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub category: WorkflowCategory,
+    pub steps: Vec<WorkflowStep>,
+    pub difficulty_level: DifficultyLevel,
+    #[serde(default)]
+    pub prerequisites: Vec<String>,
+    #[serde(default)]
+    pub learning_objectives: Vec<String>,
+}
+
+impl WorkflowTemplateDraft {
+    /// Build the workflow definition, stamped with its author and version
+    pub fn into_workflow(self, author: &str, version: u32) -> Workflow {
+        let estimated_total_duration = self.steps.iter()
+            .fold(Duration::zero(), |total, step| total + step.estimated_duration);
+
+        Workflow {
+            id: self.id,
+            name: self.name,
+            description: self.description,
+            category: self.category,
+            steps: self.steps,
+            metadata: WorkflowMetadata {
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                version: format!("{}.0.0", version),
+                author: author.to_string(),
+                difficulty_level: self.difficulty_level,
+                estimated_total_duration,
+                prerequisites: self.prerequisites,
+                learning_objectives: self.learning_objectives,
+            },
+            templates: vec![],
+        }
+    }
+}
+
+/// A problem found in a workflow template
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateIssue {
+    /// Step the problem is in; `None` for the workflow itself
+    pub step_id: Option<String>,
+    pub field: String,
+    pub message: String,
+}
+
+impl TemplateIssue {
+    fn workflow(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            step_id: None,
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+
+    fn step(step: &WorkflowStep, field: &str, message: impl Into<String>) -> Self {
+        Self {
+            step_id: Some(step.id.clone()),
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+fn is_slug(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Check a workflow template, returning every problem found
+///
+/// Steps run in the order given and the engine only looks forward for the
+/// next available step, so a step may only depend on steps listed before it.
+/// That rule also rules out dependency cycles.
+pub fn validate_template(workflow: &Workflow) -> Vec<TemplateIssue> {
+    let mut issues = Vec::new();
+
+    if !is_slug(&workflow.id) {
+        issues.push(TemplateIssue::workflow("id", "must be non-empty and use only letters, digits, '-' and '_'"));
+    }
+    if workflow.name.trim().is_empty() {
+        issues.push(TemplateIssue::workflow("name", "must not be empty"));
+    }
+    if workflow.steps.is_empty() {
+        issues.push(TemplateIssue::workflow("steps", "a workflow needs at least one step"));
+    }
+
+    let all_steps: HashSet<&str> = workflow.steps.iter().map(|s| s.id.as_str()).collect();
+    let mut earlier: HashSet<&str> = HashSet::new();
+
+    for step in &workflow.steps {
+        if !is_slug(&step.id) {
+            issues.push(TemplateIssue::step(step, "id", "must be non-empty and use only letters, digits, '-' and '_'"));
+        } else if earlier.contains(step.id.as_str()) {
+            issues.push(TemplateIssue::step(step, "id", "duplicate step id"));
+        }
+        if step.name.trim().is_empty() {
+            issues.push(TemplateIssue::step(step, "name", "must not be empty"));
+        }
+        if step.estimated_duration < Duration::zero() {
+            issues.push(TemplateIssue::step(step, "estimated_duration", "must not be negative"));
+        }
+
+        for dependency in &step.dependencies {
+            if dependency == &step.id {
+                issues.push(TemplateIssue::step(step, "dependencies", "a step cannot depend on itself"));
+            } else if !all_steps.contains(dependency.as_str()) {
+                issues.push(TemplateIssue::step(step, "dependencies", format!("unknown step '{}'", dependency)));
+            } else if !earlier.contains(dependency.as_str()) {
+                issues.push(TemplateIssue::step(
+                    step,
+                    "dependencies",
+                    format!("depends on '{}', which must be listed before it", dependency),
+                ));
+            }
+        }
+
+        let mut input_names = HashSet::new();
+        for input in &step.required_inputs {
+            let field = format!("required_inputs.{}", input.name);
+            if input.name.trim().is_empty() {
+                issues.push(TemplateIssue::step(step, "required_inputs", "input names must not be empty"));
+            } else if !input_names.insert(input.name.as_str()) {
+                issues.push(TemplateIssue::step(step, &field, "duplicate input name"));
+            }
+            if let Some(default) = &input.default_value {
+                if !default_matches(&input.input_type, default) {
+                    issues.push(TemplateIssue::step(
+                        step,
+                        &field,
+                        format!("default value does not match input type {:?}", input.input_type),
+                    ));
+                }
+            }
+            issues.extend(rule_issues(step, &field, &input.validation_rules, input.default_value.as_ref()));
+        }
+
+        earlier.insert(step.id.as_str());
+    }
+
+    issues
+}
+
+/// Validate a user-defined workflow, which may not take a built-in's id
+pub fn check_user_workflow(workflow: &Workflow) -> Result<(), Vec<TemplateIssue>> {
+    let mut issues = validate_template(workflow);
+    if is_builtin_workflow(&workflow.id) {
+        issues.push(TemplateIssue::workflow("id", format!("'{}' is a built-in workflow", workflow.id)));
+    }
+    if issues.is_empty() { Ok(()) } else { Err(issues) }
+}
+
+fn default_matches(input_type: &InputType, value: &serde_json::Value) -> bool {
+    match input_type {
+        InputType::String | InputType::File => value.is_string(),
+        InputType::Number => value.is_number(),
+        InputType::Boolean => value.is_boolean(),
+        InputType::Array => value.is_array(),
+        InputType::Object | InputType::ParameterSet => value.is_object(),
+        // Accepted as a string or a {start, end} object
+        InputType::DateRange => value.is_string() || value.is_object(),
+    }
+}
+
+fn rule_issues(
+    step: &WorkflowStep,
+    field: &str,
+    rules: &[ValidationRule],
+    default: Option<&serde_json::Value>,
+) -> Vec<TemplateIssue> {
+    let mut issues = Vec::new();
+    let mut min_value = None;
+    let mut max_value = None;
+    let mut min_length = None;
+    let mut max_length = None;
+
+    for rule in rules {
+        match rule {
+            ValidationRule::MinValue(v) => min_value = Some(*v),
+            ValidationRule::MaxValue(v) => max_value = Some(*v),
+            ValidationRule::MinLength(v) => min_length = Some(*v),
+            ValidationRule::MaxLength(v) => max_length = Some(*v),
+            ValidationRule::StringInSet(allowed) => {
+                if allowed.is_empty() {
+                    issues.push(TemplateIssue::step(step, field, "StringInSet needs at least one value"));
+                } else if let Some(value) = default.and_then(|d| d.as_str()) {
+                    if !allowed.iter().any(|a| a == value) {
+                        issues.push(TemplateIssue::step(
+                            step,
+                            field,
+                            format!("default '{}' is not one of the allowed values", value),
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let (Some(min), Some(max)) = (min_value, max_value) {
+        if min > max {
+            issues.push(TemplateIssue::step(step, field, format!("MinValue {} exceeds MaxValue {}", min, max)));
+        }
+    }
+    if let (Some(min), Some(max)) = (min_length, max_length) {
+        if min > max {
+            issues.push(TemplateIssue::step(step, field, format!("MinLength {} exceeds MaxLength {}", min, max)));
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflow::{builtin_workflows, WorkflowStepType};
+
+    fn step(id: &str, dependencies: &[&str]) -> WorkflowStep {
+        WorkflowStep {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: String::new(),
+            step_type: WorkflowStepType::Custom("research".to_string()),
+            required_inputs: vec![],
+            validation_requirements: vec![],
+            estimated_duration: Duration::minutes(5),
+            dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+            optional: false,
+            help_content: None,
+        }
+    }
+
+    #[test]
+    fn test_builtin_workflows_are_valid() {
+        for workflow in builtin_workflows() {
+            assert!(validate_template(&workflow).is_empty(), "{} has issues", workflow.id);
+        }
+    }
+
+    #[test]
+    fn test_dependencies_must_precede_step() {
+        let draft = WorkflowTemplateDraft {
+            id: "team-process".to_string(),
+            name: "Team Process".to_string(),
+            description: String::new(),
+            category: WorkflowCategory::Custom("team".to_string()),
+            steps: vec![step("hypothesis", &["review"]), step("review", &["hypothesis", "missing"])],
+            difficulty_level: DifficultyLevel::Intermediate,
+            prerequisites: vec![],
+            learning_objectives: vec![],
+        };
+        let workflow = draft.into_workflow("alice", 1);
+        assert_eq!(workflow.metadata.estimated_total_duration, Duration::minutes(10));
+
+        let issues = validate_template(&workflow);
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].step_id.as_deref(), Some("hypothesis"));
+        assert!(issues[1].message.contains("missing"));
+    }
+}
@@ -25,6 +25,66 @@ pub struct HelpContent {
     pub interactive_elements: Vec<InteractiveElement>,
 }
 
+/// Help content and the workflow steps it is shown for
+///
+/// Articles are stored outside the binary so they can be edited without a
+/// release; loading one into a `HelpSystem` makes it the contextual help of
+/// its steps, replacing any built-in content.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HelpArticle {
+    #[serde(default)]
+    pub step_types: Vec<WorkflowStepType>,
+    /// Users at this level see the article ahead of the step's general
+    /// help; `None` makes it the general help
+    #[serde(default)]
+    pub difficulty_level: Option<HelpDifficultyLevel>,
+    pub content: HelpContent,
+}
+
+/// Problems that keep a help article from being saved
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HelpArticleIssue {
+    MissingId,
+    /// Ids are used in URLs and limited to letters, digits, `-` and `_`
+    InvalidId(String),
+    MissingTitle,
+    MissingContent,
+}
+
+impl HelpArticle {
+    pub fn id(&self) -> &str {
+        &self.content.id
+    }
+
+    /// Everything wrong with the article; empty when it can be saved
+    pub fn check(&self) -> Vec<HelpArticleIssue> {
+        let mut issues = Vec::new();
+        let id = self.content.id.as_str();
+        if id.is_empty() {
+            issues.push(HelpArticleIssue::MissingId);
+        } else if id.len() > 255 || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            issues.push(HelpArticleIssue::InvalidId(id.to_string()));
+        }
+        if self.content.title.trim().is_empty() {
+            issues.push(HelpArticleIssue::MissingTitle);
+        }
+        if self.content.content.trim().is_empty() {
+            issues.push(HelpArticleIssue::MissingContent);
+        }
+        issues
+    }
+
+    /// Keys the article is looked up by in a `HelpSystem`
+    fn keys(&self) -> Vec<String> {
+        self.step_types.iter()
+            .map(|step| match self.difficulty_level {
+                Some(level) => format!("{}_{}", step.as_str(), level.as_str()),
+                None => step.as_str().to_string(),
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum HelpContentType {
     QuickTip,
@@ -519,6 +579,21 @@ impl HelpSystem {
         });
     }
     
+    /// Show an article as the contextual help of its steps
+    pub fn add_article(&mut self, article: &HelpArticle) {
+        self.remove_article(article.id());
+        for key in article.keys() {
+            self.content_database.insert(key, article.content.clone());
+        }
+    }
+    
+    /// Stop showing an article, returning whether it was shown
+    pub fn remove_article(&mut self, id: &str) -> bool {
+        let before = self.content_database.len();
+        self.content_database.retain(|_, content| content.id != id);
+        self.content_database.len() != before
+    }
+    
     /// Update user preferences
     pub fn update_user_preferences(&mut self, preferences: UserHelpPreferences) {
         self.user_preferences = preferences;
@@ -552,7 +627,7 @@ impl Default for UserHelpPreferences {
 }
 
 impl HelpDifficultyLevel {
-    fn as_str(&self) -> &'static str {
+    pub fn as_str(&self) -> &'static str {
         match self {
             HelpDifficultyLevel::Beginner => "beginner",
             HelpDifficultyLevel::Intermediate => "intermediate",
@@ -565,7 +640,7 @@ impl HelpDifficultyLevel {
 use crate::workflow::WorkflowStepType;
 
 impl WorkflowStepType {
-    fn as_str(&self) -> &str {
+    pub fn as_str(&self) -> &str {
         match self {
             WorkflowStepType::DataIngestion => "DataIngestion",
             WorkflowStepType::StrategyConfiguration => "StrategyConfiguration",
@@ -577,6 +652,20 @@ impl WorkflowStepType {
             WorkflowStepType::Custom(name) => name,
         }
     }
+    
+    /// The step type `as_str` names; unknown names are custom steps
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "DataIngestion" => WorkflowStepType::DataIngestion,
+            "StrategyConfiguration" => WorkflowStepType::StrategyConfiguration,
+            "ParameterOptimization" => WorkflowStepType::ParameterOptimization,
+            "Backtesting" => WorkflowStepType::Backtesting,
+            "StatisticalValidation" => WorkflowStepType::StatisticalValidation,
+            "ResultsAnalysis" => WorkflowStepType::ResultsAnalysis,
+            "DeploymentPrep" => WorkflowStepType::DeploymentPrep,
+            name => WorkflowStepType::Custom(name.to_string()),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -590,6 +679,42 @@ mod tests {
         assert!(!help_system.explanations.is_empty());
     }
     
+    #[test]
+    fn test_articles_replace_built_in_help() {
+        let mut help_system = HelpSystem::new();
+        let article = HelpArticle {
+            step_types: vec![WorkflowStepType::DataIngestion, WorkflowStepType::Custom("Import".to_string())],
+            difficulty_level: None,
+            content: HelpContent {
+                id: "loading-vendor-files".to_string(),
+                title: "Loading vendor files".to_string(),
+                content: "Drop daily Parquet files in the watched directory".to_string(),
+                content_type: HelpContentType::StepByStepGuide,
+                difficulty_level: HelpDifficultyLevel::Beginner,
+                tags: vec!["data".to_string()],
+                related_topics: vec![],
+                examples: vec![],
+                interactive_elements: vec![],
+            },
+        };
+        assert!(article.check().is_empty());
+
+        help_system.add_article(&article);
+        assert_eq!(help_system.content_database["DataIngestion"].id, "loading-vendor-files");
+        assert_eq!(help_system.content_database["Import"].id, "loading-vendor-files");
+
+        assert!(help_system.remove_article("loading-vendor-files"));
+        assert!(!help_system.content_database.contains_key("Import"));
+
+        let mut invalid = article;
+        invalid.content.id = "../etc".to_string();
+        invalid.content.title.clear();
+        assert_eq!(invalid.check(), vec![
+            HelpArticleIssue::InvalidId("../etc".to_string()),
+            HelpArticleIssue::MissingTitle,
+        ]);
+    }
+    
     #[test]
     fn test_value_range_checking() {
         let help_system = HelpSystem::new();
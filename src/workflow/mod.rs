@@ -11,6 +11,7 @@ pub mod help_system;
 pub mod progress;
 pub mod error_recovery;
 pub mod templates;
+pub mod authoring;
 
 pub use onboarding::*;
 pub use validation::*;
@@ -18,6 +19,7 @@ pub use help_system::*;
 pub use progress::*;
 pub use error_recovery::*;
 pub use templates::*;
+pub use authoring::*;
 
 /// Workflow step definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -338,6 +340,18 @@ impl GuidedWorkflowEngine {
         self.help_system.get_contextual_help(current_step, context)
     }
     
+    /// Serve edited help articles as the contextual help of their steps
+    pub fn load_help_articles(&mut self, articles: &[HelpArticle]) {
+        for article in articles {
+            self.help_system.add_article(article);
+        }
+    }
+    
+    /// Stop serving an article, e.g. after it was deleted
+    pub fn remove_help_article(&mut self, id: &str) -> bool {
+        self.help_system.remove_article(id)
+    }
+    
     /// Get best practice recommendations
     pub fn get_recommendations(&self, instance_id: &str, decision_point: &DecisionPoint) -> Vec<Recommendation> {
         if let Some(instance) = self.get_instance(instance_id) {
@@ -402,17 +416,26 @@ impl GuidedWorkflowEngine {
     
     /// Load default workflow templates
     fn load_default_workflows(&mut self) {
-        // Basic Strategy Development Workflow
-        let basic_workflow = create_basic_strategy_workflow();
-        self.workflows.insert(basic_workflow.id.clone(), basic_workflow);
-        
-        // Parameter Optimization Workflow
-        let optimization_workflow = create_optimization_workflow();
-        self.workflows.insert(optimization_workflow.id.clone(), optimization_workflow);
-        
-        // Strategy Validation Workflow
-        let validation_workflow = create_validation_workflow();
-        self.workflows.insert(validation_workflow.id.clone(), validation_workflow);
+        for workflow in builtin_workflows() {
+            self.workflows.insert(workflow.id.clone(), workflow);
+        }
+    }
+    
+    /// Register a user-defined workflow, replacing one with the same id
+    ///
+    /// Built-in workflows cannot be replaced.
+    pub fn register_workflow(&mut self, workflow: Workflow) -> Result<(), Vec<TemplateIssue>> {
+        check_user_workflow(&workflow)?;
+        self.workflows.insert(workflow.id.clone(), workflow);
+        Ok(())
+    }
+    
+    /// Remove a user-defined workflow; running instances keep their state
+    pub fn remove_workflow(&mut self, workflow_id: &str) -> Option<Workflow> {
+        if is_builtin_workflow(workflow_id) {
+            return None;
+        }
+        self.workflows.remove(workflow_id)
     }
     
     /// Get available workflows
@@ -458,6 +481,20 @@ impl Default for GuidedWorkflowEngine {
     }
 }
 
+/// Workflows shipped with Strategy Lab
+pub fn builtin_workflows() -> Vec<Workflow> {
+    vec![
+        create_basic_strategy_workflow(),
+        create_optimization_workflow(),
+        create_validation_workflow(),
+    ]
+}
+
+/// Whether `workflow_id` names a built-in workflow
+pub fn is_builtin_workflow(workflow_id: &str) -> bool {
+    builtin_workflows().iter().any(|w| w.id == workflow_id)
+}
+
 /// Create basic strategy development workflow
 fn create_basic_strategy_workflow() -> Workflow {
     Workflow {
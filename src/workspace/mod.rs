@@ -0,0 +1,308 @@
+//! Workspaces and resource quotas
+//!
+//! A workspace owns strategies, datasets and results for a user or team.
+//! Each workspace carries quotas on concurrent jobs, stored bytes and
+//! CPU-hours; the job scheduler asks the registry for a slot before a job
+//! starts and reports the time it used when it finishes.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use tracing::info;
+
+/// Workspace that records created without an explicit workspace belong to
+pub const DEFAULT_WORKSPACE: &str = "default";
+
+/// Limits for a workspace; `None` means unlimited
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResourceQuota {
+    #[serde(default)]
+    pub max_concurrent_jobs: Option<usize>,
+    #[serde(default)]
+    pub max_storage_bytes: Option<u64>,
+    #[serde(default)]
+    pub max_cpu_hours: Option<f64>,
+}
+
+/// Resources a workspace is currently using
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    pub running_jobs: usize,
+    pub storage_bytes: u64,
+    pub cpu_hours: f64,
+}
+
+/// A user or team workspace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workspace {
+    pub id: String,
+    pub name: String,
+    pub owner: String,
+    /// Users allowed to act in the workspace, including the owner
+    pub members: HashSet<String>,
+    pub quota: ResourceQuota,
+    pub usage: ResourceUsage,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Workspace {
+    pub fn is_member(&self, user: &str) -> bool {
+        self.members.contains(user)
+    }
+}
+
+/// Errors raised by workspace operations
+#[derive(Debug, thiserror::Error)]
+pub enum WorkspaceError {
+    #[error("Unknown workspace: {0}")]
+    UnknownWorkspace(String),
+    #[error("User {user} is not a member of workspace {workspace}")]
+    NotAMember { user: String, workspace: String },
+    #[error("Workspace {workspace} is running {running} of {limit} allowed jobs")]
+    ConcurrentJobLimit { workspace: String, running: usize, limit: usize },
+    #[error("Workspace {workspace} would use {requested} of {limit} allowed storage bytes")]
+    StorageLimit { workspace: String, requested: u64, limit: u64 },
+    #[error("Workspace {workspace} has used {used:.2} of {limit:.2} CPU-hours")]
+    CpuHoursLimit { workspace: String, used: f64, limit: f64 },
+}
+
+impl WorkspaceError {
+    /// Whether the error is a quota being exhausted rather than an access problem
+    pub fn is_quota(&self) -> bool {
+        matches!(
+            self,
+            WorkspaceError::ConcurrentJobLimit { .. }
+                | WorkspaceError::StorageLimit { .. }
+                | WorkspaceError::CpuHoursLimit { .. }
+        )
+    }
+}
+
+/// Shared registry of workspaces and their resource usage
+///
+/// The default workspace always exists, has no quota and admits every user,
+/// so callers that predate workspaces keep working unchanged.
+#[derive(Debug, Clone)]
+pub struct WorkspaceRegistry {
+    inner: Arc<RwLock<HashMap<String, Workspace>>>,
+}
+
+impl Default for WorkspaceRegistry {
+    fn default() -> Self {
+        let default = Workspace {
+            id: DEFAULT_WORKSPACE.to_string(),
+            name: "Default".to_string(),
+            owner: String::new(),
+            members: HashSet::new(),
+            quota: ResourceQuota::default(),
+            usage: ResourceUsage::default(),
+            created_at: Utc::now(),
+        };
+
+        Self {
+            inner: Arc::new(RwLock::new(HashMap::from([(DEFAULT_WORKSPACE.to_string(), default)]))),
+        }
+    }
+}
+
+impl WorkspaceRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a workspace owned by `owner`
+    pub fn create(&self, name: &str, owner: &str, quota: ResourceQuota) -> Workspace {
+        let workspace = Workspace {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            owner: owner.to_string(),
+            members: HashSet::from([owner.to_string()]),
+            quota,
+            usage: ResourceUsage::default(),
+            created_at: Utc::now(),
+        };
+
+        info!("Created workspace {} ({}) for {}", workspace.name, workspace.id, owner);
+        self.inner.write().unwrap().insert(workspace.id.clone(), workspace.clone());
+        workspace
+    }
+
+    pub fn get(&self, id: &str) -> Option<Workspace> {
+        self.inner.read().unwrap().get(id).cloned()
+    }
+
+    /// Workspaces a user belongs to
+    pub fn for_user(&self, user: &str) -> Vec<Workspace> {
+        let mut workspaces: Vec<_> = self.inner.read().unwrap().values()
+            .filter(|w| w.is_member(user))
+            .cloned()
+            .collect();
+        workspaces.sort_by_key(|w| w.created_at);
+        workspaces
+    }
+
+    pub fn add_member(&self, id: &str, user: &str) -> Result<(), WorkspaceError> {
+        self.update(id, |w| {
+            w.members.insert(user.to_string());
+            Ok(())
+        })
+    }
+
+    pub fn set_quota(&self, id: &str, quota: ResourceQuota) -> Result<(), WorkspaceError> {
+        self.update(id, |w| {
+            w.quota = quota;
+            Ok(())
+        })
+    }
+
+    /// Check that `user` may act in workspace `id`
+    pub fn authorize(&self, id: &str, user: Option<&str>) -> Result<(), WorkspaceError> {
+        if id == DEFAULT_WORKSPACE {
+            return Ok(());
+        }
+
+        let inner = self.inner.read().unwrap();
+        let workspace = inner.get(id)
+            .ok_or_else(|| WorkspaceError::UnknownWorkspace(id.to_string()))?;
+
+        match user {
+            Some(user) if workspace.is_member(user) => Ok(()),
+            _ => Err(WorkspaceError::NotAMember {
+                user: user.unwrap_or("anonymous").to_string(),
+                workspace: id.to_string(),
+            }),
+        }
+    }
+
+    /// Fail if the workspace may not accept more work
+    pub fn check_admission(&self, id: &str) -> Result<(), WorkspaceError> {
+        let inner = self.inner.read().unwrap();
+        let workspace = inner.get(id)
+            .ok_or_else(|| WorkspaceError::UnknownWorkspace(id.to_string()))?;
+        check_cpu(workspace)
+    }
+
+    /// Reserve a job slot, failing if a quota is exhausted
+    pub fn start_job(&self, id: &str) -> Result<(), WorkspaceError> {
+        self.update(id, |w| {
+            check_cpu(w)?;
+            if let Some(limit) = w.quota.max_concurrent_jobs {
+                if w.usage.running_jobs >= limit {
+                    return Err(WorkspaceError::ConcurrentJobLimit {
+                        workspace: w.id.clone(),
+                        running: w.usage.running_jobs,
+                        limit,
+                    });
+                }
+            }
+            w.usage.running_jobs += 1;
+            Ok(())
+        })
+    }
+
+    /// Release a job slot and charge the CPU time the job used
+    pub fn finish_job(&self, id: &str, cpu_seconds: f64) -> Result<(), WorkspaceError> {
+        self.update(id, |w| {
+            w.usage.running_jobs = w.usage.running_jobs.saturating_sub(1);
+            w.usage.cpu_hours += cpu_seconds.max(0.0) / 3600.0;
+            Ok(())
+        })
+    }
+
+    /// Account for stored bytes (negative to release), failing if the quota would be exceeded
+    pub fn charge_storage(&self, id: &str, bytes: i64) -> Result<(), WorkspaceError> {
+        self.update(id, |w| {
+            let requested = (w.usage.storage_bytes as i64 + bytes).max(0) as u64;
+            if let Some(limit) = w.quota.max_storage_bytes {
+                if bytes > 0 && requested > limit {
+                    return Err(WorkspaceError::StorageLimit {
+                        workspace: w.id.clone(),
+                        requested,
+                        limit,
+                    });
+                }
+            }
+            w.usage.storage_bytes = requested;
+            Ok(())
+        })
+    }
+
+    fn update<T>(
+        &self,
+        id: &str,
+        f: impl FnOnce(&mut Workspace) -> Result<T, WorkspaceError>,
+    ) -> Result<T, WorkspaceError> {
+        let mut inner = self.inner.write().unwrap();
+        let workspace = inner.get_mut(id)
+            .ok_or_else(|| WorkspaceError::UnknownWorkspace(id.to_string()))?;
+        f(workspace)
+    }
+}
+
+fn check_cpu(workspace: &Workspace) -> Result<(), WorkspaceError> {
+    match workspace.quota.max_cpu_hours {
+        Some(limit) if workspace.usage.cpu_hours >= limit => Err(WorkspaceError::CpuHoursLimit {
+            workspace: workspace.id.clone(),
+            used: workspace.usage.cpu_hours,
+            limit,
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_membership() {
+        let registry = WorkspaceRegistry::new();
+        let ws = registry.create("research", "alice", ResourceQuota::default());
+
+        assert!(registry.authorize(&ws.id, Some("alice")).is_ok());
+        assert!(registry.authorize(&ws.id, Some("bob")).is_err());
+        assert!(registry.authorize(&ws.id, None).is_err());
+        assert!(registry.authorize(DEFAULT_WORKSPACE, None).is_ok());
+
+        registry.add_member(&ws.id, "bob").unwrap();
+        assert!(registry.authorize(&ws.id, Some("bob")).is_ok());
+        assert_eq!(registry.for_user("bob").len(), 1);
+    }
+
+    #[test]
+    fn test_job_and_cpu_quotas() {
+        let registry = WorkspaceRegistry::new();
+        let quota = ResourceQuota {
+            max_concurrent_jobs: Some(1),
+            max_cpu_hours: Some(1.0),
+            ..Default::default()
+        };
+        let ws = registry.create("team", "alice", quota);
+
+        registry.start_job(&ws.id).unwrap();
+        let err = registry.start_job(&ws.id).unwrap_err();
+        assert!(matches!(err, WorkspaceError::ConcurrentJobLimit { .. }));
+
+        registry.finish_job(&ws.id, 3600.0).unwrap();
+        let err = registry.start_job(&ws.id).unwrap_err();
+        assert!(matches!(err, WorkspaceError::CpuHoursLimit { .. }));
+        assert!(err.is_quota());
+    }
+
+    #[test]
+    fn test_storage_quota() {
+        let registry = WorkspaceRegistry::new();
+        let quota = ResourceQuota {
+            max_storage_bytes: Some(1_000),
+            ..Default::default()
+        };
+        let ws = registry.create("data", "alice", quota);
+
+        registry.charge_storage(&ws.id, 800).unwrap();
+        assert!(registry.charge_storage(&ws.id, 300).is_err());
+        registry.charge_storage(&ws.id, -500).unwrap();
+        registry.charge_storage(&ws.id, 300).unwrap();
+        assert_eq!(registry.get(&ws.id).unwrap().usage.storage_bytes, 600);
+    }
+}
@@ -0,0 +1,46 @@
+//! The library under each feature set
+//!
+//! Only what the enabled features provide is referenced, so running this in
+//! each configuration catches code that uses a feature it does not declare:
+//!
+//! ```bash
+//! cargo test --no-default-features --test feature_flags
+//! cargo test --no-default-features --features jobs --test feature_flags
+//! cargo test --no-default-features --features websocket --test feature_flags
+//! cargo test --test feature_flags
+//! ```
+
+use strategy_lab::backtesting::{BacktestConfig, BacktestEngine};
+use strategy_lab::monitoring::{MonitorConfig, PerformanceMonitor};
+use strategy_lab::strategy::examples::from_template;
+
+#[test]
+fn test_engine_and_monitor_need_no_features() {
+    let engine = BacktestEngine::new(BacktestConfig::default());
+    assert!(engine.trades().is_empty());
+    assert!(from_template("VWAP Reversion", &serde_json::Value::Null).is_some());
+
+    // Without a port the monitor binds nothing, whether or not `websocket` is on
+    let _monitor = PerformanceMonitor::new(MonitorConfig { websocket_port: None, ..Default::default() });
+}
+
+#[cfg(feature = "jobs")]
+#[test]
+fn test_jobs_feature_provides_the_queue() {
+    use strategy_lab::jobs::{Job, JobType};
+
+    let job = Job { job_type: JobType::DataIngestion, ..Default::default() };
+    assert!(job.dataset().is_none());
+}
+
+#[cfg(feature = "websocket")]
+#[test]
+fn test_websocket_feature_provides_the_server() {
+    let _server = strategy_lab::monitoring::WebSocketServer::builder().build();
+}
+
+#[cfg(feature = "api")]
+#[test]
+fn test_api_feature_implies_its_dependencies() {
+    assert!(cfg!(feature = "database") && cfg!(feature = "jobs") && cfg!(feature = "websocket"));
+}
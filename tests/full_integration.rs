@@ -118,6 +118,9 @@ async fn test_full_pipeline() {
         num_workers: 4,
         objective: strategy_lab::optimization::ObjectiveFunction::SharpeRatio,
         min_trades: 10,
+        parameter_constraints: Vec::new(),
+        constraint_handling: Default::default(),
+        top_k: 10,
     };
     
     let mut optimizer = GridSearchOptimizer::new(opt_config);
@@ -212,6 +212,9 @@ async fn test_optimization_load() {
         num_workers: 8,
         objective: strategy_lab::optimization::ObjectiveFunction::SharpeRatio,
         min_trades: 10,
+        parameter_constraints: Vec::new(),
+        constraint_handling: Default::default(),
+        top_k: 10,
     };
     
     let mut optimizer = GridSearchOptimizer::new(config);